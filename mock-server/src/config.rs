@@ -0,0 +1,100 @@
+//! What a mock server serves and how it behaves
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::Path;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MockConfig {
+    /// Server name reported in the initialize response
+    pub name: String,
+    /// Protocol version reported in the initialize response; the version the
+    /// client asked for when unset
+    pub protocol_version: Option<String>,
+    pub tools: Vec<MockTool>,
+    pub resources: Vec<MockResource>,
+}
+
+impl Default for MockConfig {
+    /// A single `echo` tool returning its arguments
+    fn default() -> Self {
+        Self {
+            name: "mcp-proxy-mock".to_string(),
+            protocol_version: None,
+            tools: vec![MockTool {
+                name: "echo".to_string(),
+                description: "Returns its arguments as JSON".to_string(),
+                input_schema: default_input_schema(),
+                outcome: Outcome::default(),
+                script: Vec::new(),
+            }],
+            resources: Vec::new(),
+        }
+    }
+}
+
+impl MockConfig {
+    /// Read a configuration from a YAML or JSON file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        serde_yaml::from_str(&text).map_err(|e| format!("invalid {}: {e}", path.display()))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockTool {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "default_input_schema")]
+    pub input_schema: Value,
+    /// What calls do when the tool has no script
+    #[serde(flatten)]
+    pub outcome: Outcome,
+    /// What successive calls do, starting over once every step was used
+    #[serde(default)]
+    pub script: Vec<Outcome>,
+}
+
+fn default_input_schema() -> Value {
+    json!({ "type": "object" })
+}
+
+/// How the server answers one call
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Outcome {
+    /// Delay before answering
+    pub latency_ms: u64,
+    /// Text of the result; `{arguments}` is replaced by the call's arguments
+    /// as JSON. Defaults to the arguments alone.
+    pub text: Option<String>,
+    /// Answer with a JSON-RPC error with this message instead
+    pub error: Option<String>,
+    /// Answer with a tool result flagged `isError` with this text instead
+    pub tool_error: Option<String>,
+    /// Exit without answering, as a crashing server would
+    pub exit: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockResource {
+    pub uri: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default = "default_mime_type")]
+    pub mime_type: String,
+    #[serde(default)]
+    pub text: String,
+    /// Delay before answering reads
+    #[serde(default)]
+    pub latency_ms: u64,
+}
+
+fn default_mime_type() -> String {
+    "text/plain".to_string()
+}
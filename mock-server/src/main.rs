@@ -0,0 +1,77 @@
+//! A scriptable MCP server speaking JSON-RPC over stdio, for trying proxy
+//! configurations, plugins and policies without real backends
+
+use clap::Parser;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::Mutex;
+
+mod config;
+mod server;
+
+use config::MockConfig;
+use server::{MockServer, Reply};
+
+#[derive(Parser, Debug)]
+#[command(name = "mcp-proxy-mock")]
+#[command(about = "A scriptable MCP server for testing mcp-rust-proxy", long_about = None)]
+struct Args {
+    /// Tools and resources to serve (YAML or JSON); a single `echo` tool
+    /// when omitted
+    #[arg(short, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let config = match &args.config {
+        Some(path) => MockConfig::load(path).unwrap_or_else(|e| {
+            eprintln!("mcp-proxy-mock: {e}");
+            std::process::exit(2);
+        }),
+        None => MockConfig::default(),
+    };
+    let server = Arc::new(MockServer::new(config));
+    let stdout = Arc::new(Mutex::new(tokio::io::stdout()));
+
+    let mut calls = tokio::task::JoinSet::new();
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        // Forget calls that have been answered
+        while calls.try_join_next().is_some() {}
+
+        let (server, stdout) = (server.clone(), stdout.clone());
+        // Calls are answered concurrently, so a slow one does not hold up the rest
+        calls.spawn(async move {
+            let reply = match serde_json::from_str(&line) {
+                Ok(message) => server.handle(message).await,
+                Err(e) => Reply::Send(json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": { "code": -32700, "message": format!("Parse error: {e}") }
+                })),
+            };
+            match reply {
+                Reply::Send(message) => {
+                    let mut stdout = stdout.lock().await;
+                    let _ = stdout.write_all(format!("{message}\n").as_bytes()).await;
+                    let _ = stdout.flush().await;
+                }
+                Reply::Nothing => {}
+                Reply::Exit => {
+                    eprintln!("mcp-proxy-mock: exiting as scripted");
+                    std::process::exit(1);
+                }
+            }
+        });
+    }
+
+    // Answer the calls still running when the client closes stdin
+    while calls.join_next().await.is_some() {}
+}
@@ -0,0 +1,249 @@
+//! Answers to the JSON-RPC messages a mock server receives
+
+use crate::config::{MockConfig, Outcome};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const DEFAULT_PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// What to do after handling a message
+#[derive(Debug, PartialEq)]
+pub enum Reply {
+    Send(Value),
+    /// Notifications get no reply
+    Nothing,
+    Exit,
+}
+
+pub struct MockServer {
+    config: MockConfig,
+    /// Calls made to each tool, to step through its script
+    calls: Mutex<HashMap<String, usize>>,
+}
+
+impl MockServer {
+    pub fn new(config: MockConfig) -> Self {
+        Self {
+            config,
+            calls: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn handle(&self, message: Value) -> Reply {
+        let Some(id) = message.get("id").cloned() else {
+            return Reply::Nothing;
+        };
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+
+        let result = match method {
+            "initialize" => Ok(self.initialize(params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({
+                "tools": self.config.tools.iter().map(|tool| json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "inputSchema": tool.input_schema,
+                })).collect::<Vec<_>>()
+            })),
+            "tools/call" => match self.call_tool(params).await {
+                Some(result) => result,
+                None => return Reply::Exit,
+            },
+            "resources/list" => Ok(json!({
+                "resources": self.config.resources.iter().map(|resource| json!({
+                    "uri": resource.uri,
+                    "name": resource.name.as_deref().unwrap_or(&resource.uri),
+                    "mimeType": resource.mime_type,
+                })).collect::<Vec<_>>()
+            })),
+            "resources/read" => self.read_resource(params).await,
+            "prompts/list" => Ok(json!({ "prompts": [] })),
+            _ => Err((-32601, format!("Method not found: {method}"))),
+        };
+
+        Reply::Send(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message }
+            }),
+        })
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let version = self
+            .config
+            .protocol_version
+            .as_deref()
+            .or(params["protocolVersion"].as_str())
+            .unwrap_or(DEFAULT_PROTOCOL_VERSION);
+        json!({
+            "protocolVersion": version,
+            "capabilities": { "tools": {}, "resources": {}, "prompts": {} },
+            "serverInfo": { "name": self.config.name, "version": env!("CARGO_PKG_VERSION") }
+        })
+    }
+
+    /// The result of a tool call, or `None` when the server should exit
+    async fn call_tool(&self, params: &Value) -> Option<Result<Value, (i64, String)>> {
+        let name = params["name"].as_str().unwrap_or_default();
+        let Some(tool) = self.config.tools.iter().find(|tool| tool.name == name) else {
+            return Some(Err((-32602, format!("Unknown tool: {name}"))));
+        };
+        let outcome: Outcome = if tool.script.is_empty() {
+            tool.outcome.clone()
+        } else {
+            let mut calls = self.calls.lock().unwrap();
+            let count = calls.entry(tool.name.clone()).or_default();
+            *count += 1;
+            tool.script[(*count - 1) % tool.script.len()].clone()
+        };
+
+        tokio::time::sleep(Duration::from_millis(outcome.latency_ms)).await;
+        if outcome.exit {
+            return None;
+        }
+        if let Some(message) = outcome.error {
+            return Some(Err((-32603, message)));
+        }
+
+        let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+        let (text, is_error) = match outcome.tool_error {
+            Some(text) => (text, true),
+            None => (
+                outcome.text.unwrap_or_else(|| "{arguments}".to_string()),
+                false,
+            ),
+        };
+        Some(Ok(json!({
+            "content": [{
+                "type": "text",
+                "text": text.replace("{arguments}", &arguments.to_string()),
+            }],
+            "isError": is_error
+        })))
+    }
+
+    async fn read_resource(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let uri = params["uri"].as_str().unwrap_or_default();
+        let Some(resource) = self.config.resources.iter().find(|r| r.uri == uri) else {
+            return Err((-32002, format!("Resource not found: {uri}")));
+        };
+        tokio::time::sleep(Duration::from_millis(resource.latency_ms)).await;
+        Ok(json!({
+            "contents": [{
+                "uri": resource.uri,
+                "mimeType": resource.mime_type,
+                "text": resource.text,
+            }]
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": name, "arguments": { "q": "rust" } }
+        })
+    }
+
+    fn sent(reply: Reply) -> Value {
+        match reply {
+            Reply::Send(message) => message,
+            other => panic!("expected a reply, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scripted_tool_steps_through_outcomes() {
+        let config: MockConfig = serde_yaml::from_str(
+            r#"
+            name: flaky-search
+            tools:
+              - name: search
+                script:
+                  - text: "found {arguments}"
+                  - error: upstream timeout
+                  - toolError: rate limited
+                  - exit: true
+            resources:
+              - uri: mock://readme
+                text: hello
+            "#,
+        )
+        .unwrap();
+        let server = MockServer::new(config);
+
+        let first = sent(server.handle(call("search")).await);
+        assert_eq!(
+            first["result"]["content"][0]["text"],
+            r#"found {"q":"rust"}"#
+        );
+        let second = sent(server.handle(call("search")).await);
+        assert_eq!(second["error"]["message"], "upstream timeout");
+        let third = sent(server.handle(call("search")).await);
+        assert_eq!(third["result"]["isError"], true);
+        assert_eq!(server.handle(call("search")).await, Reply::Exit);
+        // The script starts over
+        let fifth = sent(server.handle(call("search")).await);
+        assert_eq!(fifth["result"]["isError"], false);
+
+        let unknown = sent(server.handle(call("missing")).await);
+        assert_eq!(unknown["error"]["code"], -32602);
+
+        let read = sent(
+            server
+                .handle(json!({
+                    "jsonrpc": "2.0",
+                    "id": "r",
+                    "method": "resources/read",
+                    "params": { "uri": "mock://readme" }
+                }))
+                .await,
+        );
+        assert_eq!(read["id"], "r");
+        assert_eq!(read["result"]["contents"][0]["text"], "hello");
+    }
+
+    #[tokio::test]
+    async fn test_default_server_echoes() {
+        let server = MockServer::new(MockConfig::default());
+
+        let init = sent(
+            server
+                .handle(json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "method": "initialize",
+                    "params": { "protocolVersion": "2024-11-05" }
+                }))
+                .await,
+        );
+        assert_eq!(init["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(
+            server
+                .handle(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+                .await,
+            Reply::Nothing
+        );
+
+        let tools = sent(
+            server
+                .handle(json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }))
+                .await,
+        );
+        assert_eq!(tools["result"]["tools"][0]["name"], "echo");
+        let echo = sent(server.handle(call("echo")).await);
+        assert_eq!(echo["result"]["content"][0]["text"], r#"{"q":"rust"}"#);
+    }
+}
@@ -0,0 +1,159 @@
+//! Load testing the proxy with synthetic tool calls
+//!
+//! `mcp-rust-proxy bench` calls one tool from many concurrent workers for a
+//! fixed time, through the same request handling the HTTP and stdio modes
+//! use, and reports throughput and latency percentiles for each stage of
+//! handling: routing, plugins and the backend round trip.
+
+use crate::proxy::stages::{Stage, StageTimings};
+use crate::transport::pool::LatencySummary;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// The tool to call, as clients see it
+    pub tool: String,
+    pub arguments: serde_json::Value,
+    /// Calls in flight at once
+    pub concurrency: usize,
+    pub duration: Duration,
+}
+
+/// Parse a duration like `60s`, `2m`, `500ms` or `1h`; bare numbers are
+/// seconds
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{text}'"))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(number)),
+        "" | "s" => Ok(Duration::from_secs(number)),
+        "m" => Ok(Duration::from_secs(number * 60)),
+        "h" => Ok(Duration::from_secs(number * 3600)),
+        _ => Err(format!("invalid duration unit '{unit}' in '{text}'")),
+    }
+}
+
+/// One finished call
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub total: Duration,
+    /// Time in each stage, indexed like [`Stage::ALL`]
+    pub stages: [Duration; 3],
+    pub ok: bool,
+}
+
+impl Sample {
+    pub fn new(total: Duration, timings: &StageTimings, ok: bool) -> Self {
+        Self {
+            total,
+            stages: Stage::ALL.map(|stage| timings.get(stage)),
+            ok,
+        }
+    }
+
+    /// Time not spent in any stage: parsing, serializing and bookkeeping
+    fn overhead(&self) -> Duration {
+        self.total
+            .saturating_sub(self.stages.iter().sum::<Duration>())
+    }
+}
+
+/// What a run measured
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchReport {
+    pub requests: usize,
+    pub errors: usize,
+    pub requests_per_second: f64,
+    /// Latency percentiles of whole calls, then of each stage, then of the
+    /// time outside the stages
+    pub latencies: Vec<(&'static str, LatencySummary)>,
+}
+
+impl BenchReport {
+    pub fn of(samples: &[Sample], elapsed: Duration) -> Self {
+        let summary = |duration: &dyn Fn(&Sample) -> Duration| {
+            LatencySummary::of(&samples.iter().map(duration).collect::<VecDeque<_>>())
+        };
+        let mut latencies = Vec::new();
+        latencies.extend(summary(&|sample| sample.total).map(|s| ("total", s)));
+        for (index, stage) in Stage::ALL.iter().enumerate() {
+            latencies.extend(summary(&|sample| sample.stages[index]).map(|s| (stage.as_str(), s)));
+        }
+        latencies.extend(summary(&Sample::overhead).map(|s| ("overhead", s)));
+
+        Self {
+            requests: samples.len(),
+            errors: samples.iter().filter(|sample| !sample.ok).count(),
+            requests_per_second: samples.len() as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+            latencies,
+        }
+    }
+
+    /// The report as a table
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "{} requests, {} errors, {:.1} req/s\n\n{:<10} {:>10} {:>10} {:>10} {:>10}\n",
+            self.requests,
+            self.errors,
+            self.requests_per_second,
+            "stage",
+            "p50 ms",
+            "p95 ms",
+            "p99 ms",
+            "max ms"
+        );
+        for (name, latency) in &self.latencies {
+            out.push_str(&format!(
+                "{:<10} {:>10.2} {:>10.2} {:>10.2} {:>10.2}\n",
+                name, latency.p50_ms, latency.p95_ms, latency.p99_ms, latency.max_ms
+            ));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("60s"), Ok(Duration::from_secs(60)));
+        assert_eq!(parse_duration("45"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("10 days").is_err());
+    }
+
+    #[test]
+    fn test_report_splits_latency_by_stage() {
+        let timings = StageTimings::default();
+        let start = std::time::Instant::now() - Duration::from_millis(2);
+        timings.record(Stage::Backend, start);
+        let samples = vec![
+            Sample::new(Duration::from_millis(10), &timings, true),
+            Sample::new(Duration::from_millis(20), &StageTimings::default(), false),
+        ];
+
+        let report = BenchReport::of(&samples, Duration::from_secs(2));
+        assert_eq!(report.requests, 2);
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.requests_per_second, 1.0);
+        let names: Vec<_> = report.latencies.iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names,
+            ["total", "routing", "plugins", "backend", "overhead"]
+        );
+        assert_eq!(report.latencies[0].1.max_ms, 20.0);
+        assert!(report.latencies[3].1.max_ms >= 2.0);
+        assert!(report.render().contains("2 requests, 1 errors, 1.0 req/s"));
+    }
+}
@@ -0,0 +1,362 @@
+//! Human-readable documentation of a catalog
+//!
+//! Tools, resources and prompts are grouped by the server providing them,
+//! with each tool's input schema laid out as a table of parameters. The
+//! proxy's own entries, which name no server, come under "proxy".
+
+use super::Catalog;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Group for entries that no backend server provides
+const PROXY_GROUP: &str = "proxy";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DocFormat {
+    #[default]
+    Markdown,
+    /// A standalone HTML page
+    Html,
+}
+
+impl DocFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "markdown" | "md" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+
+/// Render `catalog` as a document in `format`
+pub fn render(catalog: &Catalog, format: DocFormat) -> String {
+    let blocks = document(catalog);
+    match format {
+        DocFormat::Markdown => markdown(&blocks),
+        DocFormat::Html => html(&blocks),
+    }
+}
+
+/// What a document is made of, whatever it is rendered as
+enum Block {
+    Heading(usize, String),
+    /// Text shown as code, such as a full tool name
+    Code(String),
+    Paragraph(String),
+    Table {
+        headers: &'static [&'static str],
+        rows: Vec<Vec<String>>,
+    },
+}
+
+fn document(catalog: &Catalog) -> Vec<Block> {
+    let mut blocks = vec![
+        Block::Heading(1, "Tools".to_string()),
+        Block::Paragraph(format!(
+            "{} tools, {} resources and {} prompts, exported {}.",
+            catalog.tools.len(),
+            catalog.resources.len(),
+            catalog.prompts.len(),
+            catalog.exported_at.format("%Y-%m-%d %H:%M UTC")
+        )),
+    ];
+
+    let mut servers: BTreeMap<&str, ServerEntries> = BTreeMap::new();
+    for (entries, list) in [
+        (&catalog.tools, List::Tools),
+        (&catalog.resources, List::Resources),
+        (&catalog.prompts, List::Prompts),
+    ] {
+        for entry in entries {
+            let group = servers.entry(server_of(entry)).or_default();
+            match list {
+                List::Tools => group.tools.push(entry),
+                List::Resources => group.resources.push(entry),
+                List::Prompts => group.prompts.push(entry),
+            }
+        }
+    }
+
+    for (server, entries) in servers {
+        blocks.push(Block::Heading(2, server.to_string()));
+        for tool in entries.tools {
+            blocks.extend(tool_blocks(tool));
+        }
+        if !entries.resources.is_empty() {
+            blocks.push(Block::Heading(3, "Resources".to_string()));
+            blocks.push(Block::Table {
+                headers: &["URI", "Name", "Description", "MIME type"],
+                rows: entries
+                    .resources
+                    .iter()
+                    .map(|resource| {
+                        vec![
+                            text(&resource["uri"]),
+                            text(&resource["name"]),
+                            text(&resource["description"]),
+                            text(&resource["mimeType"]),
+                        ]
+                    })
+                    .collect(),
+            });
+        }
+        if !entries.prompts.is_empty() {
+            blocks.push(Block::Heading(3, "Prompts".to_string()));
+            blocks.push(Block::Table {
+                headers: &["Name", "Description", "Arguments"],
+                rows: entries
+                    .prompts
+                    .iter()
+                    .map(|prompt| {
+                        let arguments: Vec<String> = prompt["arguments"]
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|argument| {
+                                let name = argument["name"].as_str()?;
+                                let required = argument["required"].as_bool() == Some(true);
+                                Some(if required {
+                                    format!("{name} (required)")
+                                } else {
+                                    name.to_string()
+                                })
+                            })
+                            .collect();
+                        vec![
+                            text(&prompt["name"]),
+                            text(&prompt["description"]),
+                            arguments.join(", "),
+                        ]
+                    })
+                    .collect(),
+            });
+        }
+    }
+    blocks
+}
+
+#[derive(Clone, Copy)]
+enum List {
+    Tools,
+    Resources,
+    Prompts,
+}
+
+#[derive(Default)]
+struct ServerEntries<'a> {
+    tools: Vec<&'a Value>,
+    resources: Vec<&'a Value>,
+    prompts: Vec<&'a Value>,
+}
+
+fn server_of(entry: &Value) -> &str {
+    entry["server"].as_str().unwrap_or(PROXY_GROUP)
+}
+
+/// A tool's name, description and parameters
+fn tool_blocks(tool: &Value) -> Vec<Block> {
+    let name = text(&tool["name"]);
+    let title = tool["title"]
+        .as_str()
+        .or_else(|| tool["originalName"].as_str())
+        .map_or_else(|| name.clone(), str::to_string);
+    let mut blocks = vec![Block::Heading(3, title), Block::Code(name)];
+    if let Some(description) = tool["description"].as_str() {
+        blocks.push(Block::Paragraph(description.to_string()));
+    }
+
+    let schema = &tool["inputSchema"];
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+    let rows: Vec<Vec<String>> = schema["properties"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(parameter, property)| {
+            vec![
+                parameter.clone(),
+                type_of(property),
+                if required.contains(&parameter.as_str()) {
+                    "yes".to_string()
+                } else {
+                    "no".to_string()
+                },
+                text(&property["description"]),
+            ]
+        })
+        .collect();
+    if rows.is_empty() {
+        blocks.push(Block::Paragraph("No parameters.".to_string()));
+    } else {
+        blocks.push(Block::Table {
+            headers: &["Parameter", "Type", "Required", "Description"],
+            rows,
+        });
+    }
+    blocks
+}
+
+/// A short description of a property's type: `string`, `integer | null`,
+/// `array of string`, or the allowed values of an enum
+fn type_of(property: &Value) -> String {
+    if let Some(values) = property["enum"].as_array() {
+        let values: Vec<String> = values.iter().map(Value::to_string).collect();
+        return format!("one of {}", values.join(", "));
+    }
+    let base = match &property["type"] {
+        Value::String(name) => name.clone(),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        _ if property["anyOf"].is_array() || property["oneOf"].is_array() => {
+            let options = property["anyOf"]
+                .as_array()
+                .or_else(|| property["oneOf"].as_array())
+                .into_iter()
+                .flatten()
+                .map(type_of)
+                .collect::<Vec<_>>();
+            options.join(" | ")
+        }
+        _ => "any".to_string(),
+    };
+    if base == "array" && property["items"].is_object() {
+        format!("array of {}", type_of(&property["items"]))
+    } else {
+        base
+    }
+}
+
+/// A string value as is, anything else empty
+fn text(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+fn markdown(blocks: &[Block]) -> String {
+    let cell = |text: &str| text.replace('|', "\\|").replace('\n', " ");
+    let mut out = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, title) => {
+                out.push_str(&format!("{} {}\n\n", "#".repeat(*level), title));
+            }
+            Block::Code(code) => out.push_str(&format!("`{code}`\n\n")),
+            Block::Paragraph(text) => out.push_str(&format!("{text}\n\n")),
+            Block::Table { headers, rows } => {
+                out.push_str(&format!("| {} |\n", headers.join(" | ")));
+                out.push_str(&format!("|{}\n", "---|".repeat(headers.len())));
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|text| cell(text)).collect();
+                    out.push_str(&format!("| {} |\n", cells.join(" | ")));
+                }
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+fn html(blocks: &[Block]) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Tools</title>\n\
+         <style>body{font-family:sans-serif;max-width:60em;margin:auto}\
+         table{border-collapse:collapse}th,td{border:1px solid #ccc;padding:4px 8px;\
+         text-align:left;vertical-align:top}</style>\n</head>\n<body>\n",
+    );
+    for block in blocks {
+        match block {
+            Block::Heading(level, title) => {
+                out.push_str(&format!("<h{level}>{}</h{level}>\n", escape(title)));
+            }
+            Block::Code(code) => out.push_str(&format!("<p><code>{}</code></p>\n", escape(code))),
+            Block::Paragraph(text) => out.push_str(&format!("<p>{}</p>\n", escape(text))),
+            Block::Table { headers, rows } => {
+                out.push_str("<table>\n<tr>");
+                for header in headers.iter() {
+                    out.push_str(&format!("<th>{}</th>", escape(header)));
+                }
+                out.push_str("</tr>\n");
+                for row in rows {
+                    out.push_str("<tr>");
+                    for cell in row {
+                        out.push_str(&format!("<td>{}</td>", escape(cell)));
+                    }
+                    out.push_str("</tr>\n");
+                }
+                out.push_str("</table>\n");
+            }
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_groups_by_server() {
+        let catalog = Catalog::from_lists(
+            &json!({ "tools": [
+                {
+                    "name": "mcp__proxy__db__query",
+                    "originalName": "query",
+                    "server": "db",
+                    "description": "Run a <read-only> query",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "sql": { "type": "string", "description": "SQL | text" },
+                            "params": { "type": "array", "items": { "type": "integer" } },
+                            "mode": { "enum": ["fast", "exact"] }
+                        },
+                        "required": ["sql"]
+                    }
+                },
+                { "name": "mcp__proxy__status__overview", "inputSchema": { "type": "object" } }
+            ] }),
+            &json!({ "resources": [{ "uri": "db://tables", "name": "tables", "server": "db" }] }),
+            &Value::Null,
+            &Value::Null,
+        );
+
+        let markdown = render(&catalog, DocFormat::Markdown);
+        let db = markdown.find("## db").unwrap();
+        let proxy = markdown.find("## proxy").unwrap();
+        assert!(db < proxy);
+        assert!(markdown.contains("### query\n\n`mcp__proxy__db__query`"));
+        assert!(markdown.contains("| sql | string | yes | SQL \\| text |"));
+        assert!(markdown.contains("| params | array of integer | no |  |"));
+        assert!(markdown.contains("| mode | one of \"fast\", \"exact\" | no |  |"));
+        assert!(markdown.contains("| db://tables | tables |  |  |"));
+        assert!(markdown[proxy..].contains("No parameters."));
+
+        let html = render(&catalog, DocFormat::Html);
+        assert!(html.contains("<h2>db</h2>"));
+        assert!(html.contains("<p>Run a &lt;read-only&gt; query</p>"));
+        assert!(html.contains("<td>sql</td><td>string</td><td>yes</td>"));
+    }
+}
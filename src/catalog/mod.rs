@@ -0,0 +1,132 @@
+//! Catalog bundles for `catalog export` and `catalog serve`
+//!
+//! A catalog holds everything clients see through the proxy: the aggregated
+//! tools, resources, resource templates and prompts, with their schemas,
+//! named as the proxy lists them. Exported from a running configuration, it
+//! can document that configuration or be served again without any backend,
+//! for demos and for clients built against it offline. [`docs`] renders it
+//! as documentation.
+
+pub mod docs;
+
+use crate::error::Result;
+use crate::proxy::{RequestContext, RequestHandler, RequestRouter};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Catalog {
+    pub exported_at: DateTime<Utc>,
+    /// Version of the proxy that exported it
+    pub proxy_version: String,
+    #[serde(default)]
+    pub tools: Vec<Value>,
+    #[serde(default)]
+    pub resources: Vec<Value>,
+    #[serde(default)]
+    pub resource_templates: Vec<Value>,
+    #[serde(default)]
+    pub prompts: Vec<Value>,
+}
+
+impl Catalog {
+    /// Build from the proxy's results of `tools/list`, `resources/list`,
+    /// `resources/templates/list` and `prompts/list`; a missing result
+    /// counts as an empty list
+    pub fn from_lists(
+        tools: &Value,
+        resources: &Value,
+        resource_templates: &Value,
+        prompts: &Value,
+    ) -> Self {
+        let list = |result: &Value, key: &str| result[key].as_array().cloned().unwrap_or_default();
+        Self {
+            exported_at: Utc::now(),
+            proxy_version: env!("CARGO_PKG_VERSION").to_string(),
+            tools: list(tools, "tools"),
+            resources: list(resources, "resources"),
+            resource_templates: list(resource_templates, "resourceTemplates"),
+            prompts: list(prompts, "prompts"),
+        }
+    }
+
+    /// Collect what `handler` lists to clients. A list that fails is left
+    /// empty, with a warning.
+    pub async fn collect(handler: &RequestHandler, router: &Arc<RequestRouter>) -> Result<Self> {
+        let mut lists = Vec::new();
+        for method in [
+            "tools/list",
+            "resources/list",
+            "resources/templates/list",
+            "prompts/list",
+        ] {
+            let request = json!({ "jsonrpc": "2.0", "id": 0, "method": method });
+            let response = handler
+                .handle_request(request, router.clone(), &RequestContext::default())
+                .await?;
+            if let Some(e) = &response.error {
+                tracing::warn!("{} failed: {}", method, e.message);
+            }
+            lists.push(response.result.unwrap_or(Value::Null));
+        }
+        Ok(Self::from_lists(&lists[0], &lists[1], &lists[2], &lists[3]))
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// The result of a list request, or `None` for a method the catalog
+    /// doesn't answer. The lists are served whole, without pages.
+    pub fn list(&self, method: &str) -> Option<Value> {
+        let result = match method {
+            "tools/list" => json!({ "tools": self.tools }),
+            "resources/list" => json!({ "resources": self.resources }),
+            "resources/templates/list" => {
+                json!({ "resourceTemplates": self.resource_templates })
+            }
+            "prompts/list" => json!({ "prompts": self.prompts }),
+            _ => return None,
+        };
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_round_trip() {
+        let catalog = Catalog::from_lists(
+            &json!({ "tools": [{ "name": "mcp__proxy__db__query", "inputSchema": { "type": "object" } }] }),
+            &json!({ "resources": [{ "uri": "file:///notes.md", "name": "notes" }] }),
+            &Value::Null,
+            &json!({ "prompts": [], "nextCursor": "2" }),
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("catalog.json");
+        catalog.write(&path).unwrap();
+        let read = Catalog::read(&path).unwrap();
+        assert_eq!(read, catalog);
+
+        assert_eq!(
+            read.list("tools/list").unwrap()["tools"][0]["inputSchema"]["type"],
+            "object"
+        );
+        assert_eq!(
+            read.list("resources/templates/list").unwrap(),
+            json!({ "resourceTemplates": [] })
+        );
+        assert_eq!(read.list("tools/call"), None);
+    }
+}
@@ -0,0 +1,234 @@
+//! The report `mcp-rust-proxy check` produces
+//!
+//! Servers are checked concurrently and their results gathered into one
+//! report, which is logged as text or printed as JSON for CI pipelines and
+//! scripts.
+
+use crate::proxy::router::ToolConflict;
+use crate::snapshot::Drift;
+use crate::transport::timeline::StartupTimeline;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone)]
+pub struct CheckOptions {
+    /// Start stdio servers and talk to them
+    pub ping: bool,
+    /// Only check servers carrying this tag
+    pub tag: Option<String>,
+    /// Capability snapshot to compare with
+    pub snapshot: Option<PathBuf>,
+    /// Save the servers' current capabilities to `snapshot`
+    pub update_snapshot: bool,
+    /// Longest a single server's checks may take
+    pub timeout: Duration,
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Log lines meant for people
+    #[default]
+    Text,
+    /// One JSON document on stdout
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckReport {
+    pub ok: bool,
+    pub servers: Vec<ServerReport>,
+    /// Tools several pinged servers expose
+    pub conflicts: Vec<ToolConflict>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<SnapshotReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerReport {
+    pub name: String,
+    pub transport: &'static str,
+    pub command: String,
+    pub ok: bool,
+    pub errors: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ping: Option<PingReport>,
+    /// Why the server wasn't pinged, when pinging was asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ping_skipped: Option<String>,
+}
+
+/// What a pinged server said about itself
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PingReport {
+    pub protocol_version: Option<String>,
+    pub server_info: Option<Value>,
+    /// The capabilities it declared, e.g. `["prompts", "tools"]`
+    pub capabilities: Vec<String>,
+    pub tool_count: usize,
+    pub resource_count: usize,
+    pub prompt_count: usize,
+    pub duration_ms: u64,
+    pub timeline: StartupTimeline,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotReport {
+    pub path: PathBuf,
+    /// When the snapshot compared with was taken; `None` when there was
+    /// none and it was written for the first time
+    pub taken_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub drift: Vec<Drift>,
+    /// Removed or changed items, which fail the check unless accepted
+    pub breaking: usize,
+    /// Whether the file was written
+    pub saved: bool,
+}
+
+impl PingReport {
+    /// Fill in what an initialize result says
+    pub fn with_initialize_result(mut self, result: &Value) -> Self {
+        self.protocol_version = result["protocolVersion"].as_str().map(str::to_string);
+        self.server_info = result.get("serverInfo").cloned();
+        self.capabilities = result["capabilities"]
+            .as_object()
+            .map(|capabilities| capabilities.keys().cloned().collect())
+            .unwrap_or_default();
+        self
+    }
+}
+
+impl CheckReport {
+    /// Log the report for people to read
+    pub fn log(&self) {
+        for server in &self.servers {
+            info!("\nServer: {}", server.name);
+            info!("  Command: {}", server.command);
+            info!("  Transport: {}", server.transport);
+            if let Some(ping) = &server.ping {
+                info!("  Startup: {}", ping.timeline.summary());
+                info!(
+                    "  Protocol: {}; capabilities: {}",
+                    ping.protocol_version.as_deref().unwrap_or("unknown"),
+                    if ping.capabilities.is_empty() {
+                        "none".to_string()
+                    } else {
+                        ping.capabilities.join(", ")
+                    }
+                );
+                info!(
+                    "  ✓ Ping test passed ({} tools, {} resources, {} prompts in {}ms)",
+                    ping.tool_count, ping.resource_count, ping.prompt_count, ping.duration_ms
+                );
+            }
+            if let Some(reason) = &server.ping_skipped {
+                warn!("  ! Ping test skipped ({})", reason);
+            }
+            for e in &server.errors {
+                error!("  ✗ {}", e);
+            }
+        }
+
+        for conflict in &self.conflicts {
+            let resolution = match &conflict.resolved_to {
+                Some(server) => format!("unprefixed calls go to {server}"),
+                None => "unprefixed calls are refused".to_string(),
+            };
+            warn!(
+                "\n! Tool '{}' is exposed by {}; {}",
+                conflict.tool,
+                conflict.servers.join(", "),
+                resolution
+            );
+        }
+
+        if let Some(snapshot) = &self.snapshot {
+            snapshot.log();
+        }
+
+        if self.ok {
+            info!("\n✓ All checks passed");
+        }
+    }
+}
+
+impl SnapshotReport {
+    fn log(&self) {
+        let Some(taken_at) = self.taken_at else {
+            info!("\n✓ Wrote capability snapshot to {}", self.path.display());
+            return;
+        };
+        info!(
+            "\nComparing with capability snapshot {} (taken {})",
+            self.path.display(),
+            taken_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+        for change in &self.drift {
+            if change.is_breaking() {
+                error!("  ✗ {}", change);
+            } else {
+                info!("  + {}", change);
+            }
+        }
+        if self.drift.is_empty() {
+            info!("  ✓ No changes");
+        }
+        if self.saved {
+            info!("  Updated the snapshot");
+        } else if self.breaking > 0 {
+            error!(
+                "  {} removed or changed item(s); rerun with --update-snapshot to accept them",
+                self.breaking
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_report_serializes_for_scripts() {
+        let ping = PingReport {
+            tool_count: 2,
+            ..Default::default()
+        }
+        .with_initialize_result(&json!({
+            "protocolVersion": "2025-03-26",
+            "capabilities": { "tools": {}, "logging": {} },
+            "serverInfo": { "name": "db", "version": "1.2.0" }
+        }));
+        let report = CheckReport {
+            ok: true,
+            servers: vec![ServerReport {
+                name: "db".to_string(),
+                transport: "stdio",
+                command: "db-server --readonly".to_string(),
+                ok: true,
+                errors: Vec::new(),
+                ping: Some(ping),
+                ping_skipped: None,
+            }],
+            conflicts: Vec::new(),
+            snapshot: None,
+        };
+
+        let json = serde_json::to_value(&report).unwrap();
+        let server = &json["servers"][0];
+        assert_eq!(server["ping"]["protocolVersion"], "2025-03-26");
+        assert_eq!(server["ping"]["capabilities"], json!(["logging", "tools"]));
+        assert_eq!(server["ping"]["toolCount"], 2);
+        assert_eq!(server["ping"]["serverInfo"]["version"], "1.2.0");
+        assert!(server.get("pingSkipped").is_none());
+        assert!(json.get("snapshot").is_none());
+    }
+}
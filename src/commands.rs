@@ -1,61 +1,643 @@
+use crate::bench::{BenchOptions, BenchReport, Sample};
+use crate::catalog::docs::DocFormat;
+use crate::catalog::Catalog;
+use crate::check::{
+    CheckOptions, CheckReport, OutputFormat, PingReport, ServerReport, SnapshotReport,
+};
 use crate::config::{Config, TransportConfig};
 use crate::error::Result;
 use crate::protocol::{mcp, JsonRpcId, JsonRpcMessage, JsonRpcV2Message};
+use crate::proxy::stages::StageTimings;
+use crate::proxy::{RequestContext, RequestHandler, RequestRouter};
+use crate::recording::{self, BackendExchange};
+use crate::registry::{self, RegistryClient};
+use crate::server::ServerManager;
+use crate::snapshot::{CapabilitySnapshot, ServerCapabilities};
 use crate::transport::create_transport;
+use crate::transport::pool::ConnectionPool;
+use crate::transport::timeline::StartupTimeline;
 use bytes::Bytes;
-use serde_json::json;
-use tokio::time::{timeout, Duration};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::time::{timeout, Duration, Instant};
 use tracing::{error, info, warn};
 
-pub async fn run_config_check(config: Config, ping: bool) -> Result<()> {
+/// Validate the configuration and, with `options.ping`, start each stdio
+/// server and talk to it; servers are checked concurrently, each within
+/// `options.timeout`. With a snapshot, what the pinged servers offer is
+/// compared with the snapshot saved in that file, which is written when
+/// missing or when `update_snapshot` is set.
+pub async fn run_config_check(config: Config, options: CheckOptions) -> Result<()> {
     info!("Checking configuration...");
 
     // Validate configuration
     crate::config::validate(&config)?;
     info!("✓ Configuration is valid");
 
-    // Check servers
-    info!("Checking {} server(s)...", config.servers.len());
+    // Check servers, optionally restricted to a tag
+    let tag = options.tag.as_deref();
+    let servers: Vec<_> = config
+        .servers
+        .iter()
+        .filter(|(_, server)| tag.is_none_or(|tag| server.tags.iter().any(|t| t == tag)))
+        .collect();
+    match tag {
+        Some(tag) => info!("Checking {} server(s) tagged '{}'...", servers.len(), tag),
+        None => info!("Checking {} server(s)...", servers.len()),
+    }
 
-    let mut all_ok = true;
+    let checks = servers
+        .into_iter()
+        .map(|(name, server_config)| check_server(name, server_config, &options));
+    let results = futures::future::join_all(checks).await;
 
-    for (name, server_config) in &config.servers {
-        info!("\nChecking server: {}", name);
-        info!(
-            "  Command: {} {}",
-            server_config.command,
-            server_config.args.join(" ")
-        );
-        info!("  Transport: {:?}", server_config.transport);
+    let tools = RequestRouter::new();
+    tools.set_routing(config.routing.clone());
+    let mut capabilities = std::collections::BTreeMap::new();
+    let mut servers = Vec::new();
+    for (report, offered) in results {
+        if let Some(offered) = offered {
+            tools.register_server_tools(&report.name, offered.tools.keys().cloned());
+            capabilities.insert(report.name.clone(), offered);
+        }
+        servers.push(report);
+    }
+
+    let snapshot = match &options.snapshot {
+        Some(path) => Some(check_snapshot(
+            path,
+            &CapabilitySnapshot::new(capabilities),
+            options.update_snapshot,
+        )?),
+        None => None,
+    };
+
+    let report = CheckReport {
+        ok: servers.iter().all(|server| server.ok)
+            && snapshot
+                .as_ref()
+                .is_none_or(|snapshot| snapshot.saved || snapshot.breaking == 0),
+        servers,
+        // Only pinged servers have listed their tools
+        conflicts: tools.tool_conflicts(),
+        snapshot,
+    };
+    match options.format {
+        OutputFormat::Text => report.log(),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    if report.ok {
+        Ok(())
+    } else {
+        Err(crate::error::ProxyError::Config(
+            crate::error::ConfigError::Validation("One or more servers failed checks".to_string()),
+        ))
+    }
+}
 
-        if ping && matches!(server_config.transport, TransportConfig::Stdio) {
-            match test_server_ping(name, server_config).await {
-                Ok(()) => {
-                    info!("  ✓ Ping test passed");
+/// Check one server, pinging it when asked; the capabilities are returned
+/// when the ping succeeded
+async fn check_server(
+    name: &str,
+    server_config: &crate::config::ServerConfig,
+    options: &CheckOptions,
+) -> (ServerReport, Option<ServerCapabilities>) {
+    let (command, args) = crate::transport::runtime::launch_command(server_config);
+    let mut report = ServerReport {
+        name: name.to_string(),
+        transport: server_config.transport.name(),
+        command: format!("{} {}", command, args.join(" ")),
+        ok: true,
+        errors: Vec::new(),
+        ping: None,
+        ping_skipped: None,
+    };
+    if let Err(e) = crate::transport::runtime::verify(server_config) {
+        report.errors.push(e.to_string());
+        report.ok = false;
+    }
+
+    if !options.ping {
+        return (report, None);
+    }
+    if !matches!(server_config.transport, TransportConfig::Stdio) {
+        report.ping_skipped = Some("only supported for stdio transport".to_string());
+        return (report, None);
+    }
+
+    let started = Instant::now();
+    let mut timeline = StartupTimeline::starting();
+    let result = timeout(
+        options.timeout,
+        test_server_ping(name, server_config, &mut timeline),
+    )
+    .await
+    .unwrap_or(Err(crate::error::ProxyError::Timeout));
+    let ping = PingReport {
+        duration_ms: started.elapsed().as_millis() as u64,
+        ..Default::default()
+    };
+    match result {
+        Ok((initialized, offered)) => {
+            report.ping = Some(
+                PingReport {
+                    tool_count: offered.tools.len(),
+                    resource_count: offered.resources.len(),
+                    prompt_count: offered.prompts.len(),
+                    timeline,
+                    ..ping
                 }
-                Err(e) => {
-                    error!("  ✗ Ping test failed: {}", e);
-                    all_ok = false;
+                .with_initialize_result(&initialized),
+            );
+            (report, Some(offered))
+        }
+        Err(e) => {
+            timeline.error.get_or_insert_with(|| e.to_string());
+            report.errors.push(format!("Ping test failed: {e}"));
+            report.ok = false;
+            report.ping = Some(PingReport { timeline, ..ping });
+            (report, None)
+        }
+    }
+}
+
+/// Compare `current` with the snapshot saved at `path`. The file is written
+/// when there is none yet or `update` is set.
+fn check_snapshot(
+    path: &Path,
+    current: &CapabilitySnapshot,
+    update: bool,
+) -> Result<SnapshotReport> {
+    if !path.exists() {
+        current.write(path)?;
+        return Ok(SnapshotReport {
+            path: path.to_path_buf(),
+            taken_at: None,
+            drift: Vec::new(),
+            breaking: 0,
+            saved: true,
+        });
+    }
+
+    let saved = CapabilitySnapshot::read(path)?;
+    let drift = saved.drift(current);
+    if update {
+        saved.updated_with(current).write(path)?;
+    }
+    Ok(SnapshotReport {
+        path: path.to_path_buf(),
+        taken_at: Some(saved.taken_at),
+        breaking: drift.iter().filter(|d| d.is_breaking()).count(),
+        drift,
+        saved: update,
+    })
+}
+
+/// Send the backend requests recorded in `file` to the configured servers,
+/// in the order they were recorded, and report every response that differs
+/// from the recorded one
+pub async fn run_replay(config: Config, file: &Path) -> Result<()> {
+    let exchanges = recording::backend_exchanges(&recording::read_session(file)?);
+    info!(
+        "Replaying {} backend request(s) from {}",
+        exchanges.len(),
+        file.display()
+    );
+
+    let pool = ConnectionPool::new(Default::default(), Default::default());
+    let request_timeout = config.request_timeout();
+    // Whether each server could be started
+    let mut started: HashMap<String, bool> = HashMap::new();
+    let (mut matched, mut changed, mut failed) = (0, 0, 0);
+
+    for exchange in &exchanges {
+        let server = &exchange.server;
+        let available = match started.get(server) {
+            Some(available) => *available,
+            None => {
+                let result = start_replay_server(&pool, &config, server).await;
+                if let Err(e) = &result {
+                    error!("✗ Could not start server {}: {}", server, e);
+                }
+                started.insert(server.clone(), result.is_ok());
+                result.is_ok()
+            }
+        };
+        if !available {
+            failed += 1;
+            continue;
+        }
+
+        let label = exchange.label();
+        let replayed = timeout(request_timeout, replay_request(&pool, exchange))
+            .await
+            .unwrap_or(Err(crate::error::ProxyError::Timeout));
+        match (replayed, &exchange.response) {
+            (Err(e), _) => {
+                error!("✗ {} {}: {}", server, label, e);
+                failed += 1;
+            }
+            (Ok(_), None) => {
+                warn!("? {} {}: no recorded response to compare", server, label);
+            }
+            (Ok(actual), Some(expected)) => {
+                let differences = recording::diff(expected, &actual);
+                if differences.is_empty() {
+                    info!("✓ {} {}", server, label);
+                    matched += 1;
+                } else {
+                    error!("✗ {} {}: response changed", server, label);
+                    for difference in differences {
+                        error!("    {}", difference);
+                    }
+                    changed += 1;
                 }
             }
-        } else if ping && !matches!(server_config.transport, TransportConfig::Stdio) {
-            warn!("  ! Ping test skipped (only supported for stdio transport)");
         }
     }
+    let _ = pool.close_all().await;
 
-    if all_ok {
-        info!("\n✓ All checks passed");
+    info!(
+        "\n{} unchanged, {} changed, {} failed",
+        matched, changed, failed
+    );
+    if changed + failed == 0 {
         Ok(())
     } else {
         Err(crate::error::ProxyError::Config(
-            crate::error::ConfigError::Validation("One or more servers failed checks".to_string()),
+            crate::error::ConfigError::Validation(format!(
+                "{} replayed response(s) changed and {} failed",
+                changed, failed
+            )),
         ))
     }
 }
 
-async fn test_server_ping(name: &str, config: &crate::config::ServerConfig) -> Result<()> {
+async fn start_replay_server(pool: &ConnectionPool, config: &Config, name: &str) -> Result<()> {
+    let server = config.servers.get(name).ok_or_else(|| {
+        crate::error::ConfigError::Validation(format!("server '{name}' is not configured"))
+    })?;
+    let pinned_version = server
+        .protocol_version
+        .as_deref()
+        .and_then(crate::protocol::ProtocolVersion::parse);
+    pool.pin_protocol_version(name, pinned_version);
+    pool.set_initialization_timeout(name, server.initialization_timeout());
+    pool.set_max_connection_age(name, server.dns.ttl());
+
+    let transport = create_transport(
+        &server.transport,
+        server,
+        None,
+        config.server_proxy_url(server),
+    )?;
+    pool.add_server(name.to_string(), transport).await
+}
+
+async fn replay_request(pool: &ConnectionPool, exchange: &BackendExchange) -> Result<Value> {
+    let connection = pool.get(&exchange.server).await?;
+    connection
+        .send(Bytes::from(format!("{}\n", exchange.request)))
+        .await?;
+    let response = connection.recv().await?;
+    Ok(serde_json::from_slice(&response)?)
+}
+
+/// Drive the proxy with concurrent calls to one tool and report throughput
+/// and latency per stage
+pub async fn run_bench(config: Config, options: BenchOptions) -> Result<()> {
+    let (state, _shutdown_rx) = crate::state::AppState::new(config);
+    if let Some(plugin_manager) = &state.plugin_manager {
+        if let Err(e) = plugin_manager.discover_plugins().await {
+            error!("Failed to discover plugins: {}", e);
+        }
+    }
+    let server_manager = ServerManager::new(state.clone(), state.shutdown_tx.subscribe());
+    tokio::spawn(async move {
+        if let Err(e) = server_manager.run().await {
+            error!("Server manager error: {}", e);
+        }
+    });
+
+    let handler = Arc::new(RequestHandler::new(state.clone()));
+    let router = state.request_router.clone();
+    let result = async {
+        wait_for_tool(&handler, &router, &options.tool).await?;
+        info!(
+            "Calling {} from {} worker(s) for {:?}",
+            options.tool, options.concurrency, options.duration
+        );
+
+        let body = Bytes::from(serde_json::to_vec(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": options.tool, "arguments": options.arguments }
+        }))?);
+        let started = Instant::now();
+        let deadline = started + options.duration;
+        let workers: Vec<_> = (0..options.concurrency.max(1))
+            .map(|_| {
+                let (handler, router, body) = (handler.clone(), router.clone(), body.clone());
+                tokio::spawn(async move {
+                    let mut samples = Vec::new();
+                    while Instant::now() < deadline {
+                        samples.push(bench_call(&handler, &router, &body).await);
+                    }
+                    samples
+                })
+            })
+            .collect();
+        let mut samples = Vec::new();
+        for worker in workers {
+            samples.extend(worker.await.unwrap_or_default());
+        }
+
+        println!("{}", BenchReport::of(&samples, started.elapsed()).render());
+        Ok(())
+    }
+    .await;
+
+    state.shutdown().await;
+    let _ = state.connection_pool.close_all().await;
+    result
+}
+
+/// Start the configured servers and save what the proxy lists once they
+/// are up, to `output` or else on stdout. Servers still starting after
+/// `wait` are left out.
+pub async fn run_catalog_export(
+    config: Config,
+    output: Option<&Path>,
+    wait: Duration,
+) -> Result<()> {
+    let catalog = collect_catalog(config, wait).await?;
+    match output {
+        Some(path) => {
+            catalog.write(path)?;
+            info!(
+                "Exported {} tools, {} resources and {} prompts to {}",
+                catalog.tools.len(),
+                catalog.resources.len(),
+                catalog.prompts.len(),
+                path.display()
+            );
+        }
+        None => println!("{}", serde_json::to_string_pretty(&catalog)?),
+    }
+    Ok(())
+}
+
+/// Render `catalog` as documentation, to `output` or else on stdout
+pub fn write_catalog_docs(
+    catalog: &Catalog,
+    format: DocFormat,
+    output: Option<&Path>,
+) -> Result<()> {
+    let document = crate::catalog::docs::render(catalog, format);
+    match output {
+        Some(path) => {
+            std::fs::write(path, document)?;
+            info!("Wrote documentation to {}", path.display());
+        }
+        None => print!("{document}"),
+    }
+    Ok(())
+}
+
+/// Start the configured servers and collect what the proxy lists once they
+/// are up; servers still starting after `wait` are left out
+pub async fn collect_catalog(config: Config, wait: Duration) -> Result<Catalog> {
+    let servers: Vec<String> = config
+        .servers
+        .iter()
+        .filter(|(_, server)| server.enabled)
+        .map(|(name, _)| name.clone())
+        .collect();
+    let (state, _shutdown_rx) = crate::state::AppState::new(config);
+    if let Some(plugin_manager) = &state.plugin_manager {
+        if let Err(e) = plugin_manager.discover_plugins().await {
+            error!("Failed to discover plugins: {}", e);
+        }
+    }
+    let server_manager = ServerManager::new(state.clone(), state.shutdown_tx.subscribe());
+    tokio::spawn(async move {
+        if let Err(e) = server_manager.run().await {
+            error!("Server manager error: {}", e);
+        }
+    });
+
+    let handler = RequestHandler::new(state.clone());
+    let router = state.request_router.clone();
+    let result = async {
+        let deadline = Instant::now() + wait;
+        for server in &servers {
+            loop {
+                match state.get_server_state(server).await {
+                    Some(crate::state::ServerState::Running) => break,
+                    Some(
+                        crate::state::ServerState::Failed | crate::state::ServerState::Stopped,
+                    ) => {
+                        warn!("Server '{}' is not running; leaving it out", server);
+                        break;
+                    }
+                    _ if Instant::now() >= deadline => {
+                        warn!(
+                            "Server '{}' did not start within {:?}; leaving it out",
+                            server, wait
+                        );
+                        break;
+                    }
+                    _ => tokio::time::sleep(Duration::from_millis(200)).await,
+                }
+            }
+        }
+
+        Catalog::collect(&handler, &router).await
+    }
+    .await;
+
+    state.shutdown().await;
+    let _ = state.connection_pool.close_all().await;
+    result
+}
+
+/// Wait until the servers have started and `tool` is listed
+async fn wait_for_tool(
+    handler: &RequestHandler,
+    router: &Arc<RequestRouter>,
+    tool: &str,
+) -> Result<()> {
+    let request = json!({ "jsonrpc": "2.0", "id": 0, "method": "tools/list" });
+    let deadline = Instant::now() + Duration::from_secs(30);
+    loop {
+        // An empty list cached while servers were still starting would hide
+        // the tool
+        handler.clear_cache().await;
+        let response = handler
+            .handle_request(request.clone(), router.clone(), &RequestContext::default())
+            .await?;
+        let listed = response
+            .result
+            .as_ref()
+            .and_then(|result| result["tools"].as_array());
+        if listed.is_some_and(|tools| tools.iter().any(|t| t["name"] == tool)) {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(crate::error::ConfigError::Validation(format!(
+                "tool '{tool}' was not listed within 30s; tools are named \
+                 mcp__proxy__{{server}}__{{tool}}"
+            ))
+            .into());
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// Make one call the way the HTTP endpoint would, timing its stages
+async fn bench_call(handler: &RequestHandler, router: &Arc<RequestRouter>, body: &Bytes) -> Sample {
+    let timings = Arc::new(StageTimings::default());
+    let ctx = RequestContext {
+        stages: Some(timings.clone()),
+        ..Default::default()
+    };
+    let started = Instant::now();
+    let ok = match crate::proxy::dispatch(handler, router, body, &ctx).await {
+        Ok(response) => serde_json::from_slice::<Value>(&response)
+            .is_ok_and(|response| call_succeeded(&response)),
+        Err(_) => false,
+    };
+    Sample::new(started.elapsed(), &timings, ok)
+}
+
+/// Whether a tools/call response is neither a JSON-RPC nor a tool error
+fn call_succeeded(response: &Value) -> bool {
+    response.get("error").is_none() && response["result"]["isError"] != true
+}
+
+pub async fn run_search(registry_url: &str, query: &str) -> Result<()> {
+    let servers = RegistryClient::new(registry_url).search(query, 30).await?;
+    if servers.is_empty() {
+        println!("No servers match '{query}'");
+        return Ok(());
+    }
+    for server in servers {
+        let kinds: Vec<&str> = server
+            .packages
+            .iter()
+            .map(|package| package.registry_type.as_str())
+            .collect();
+        println!(
+            "{} {} [{}]\n    {}",
+            server.name,
+            server.version.as_deref().unwrap_or(""),
+            kinds.join(", "),
+            server.description
+        );
+    }
+    println!("\nAdd one with: mcp-rust-proxy add <name>");
+    Ok(())
+}
+
+/// Add a registry server to the configuration file at `path`, after
+/// showing the entry and asking unless `yes`
+pub async fn run_add(
+    registry_url: &str,
+    registry_name: &str,
+    path: &Path,
+    name: Option<String>,
+    yes: bool,
+) -> Result<()> {
+    let server = RegistryClient::new(registry_url)
+        .find(registry_name)
+        .await?;
+    let mut install = registry::install(&server)?;
+    if let Some(name) = name {
+        install.name = name;
+    }
+
+    let entry = serde_yaml::to_string(&std::collections::BTreeMap::from([(
+        &install.name,
+        &install.server,
+    )]))
+    .map_err(|e| crate::error::ConfigError::Parse(e.to_string()))?;
+    println!("{entry}");
+    if !yes && !confirm(&format!("Add '{}' to {}?", install.name, path.display()))? {
+        println!("Nothing added");
+        return Ok(());
+    }
+    registry::append_server(path, &install.name, &install.server)?;
+    println!("Added '{}' to {}", install.name, path.display());
+    for note in &install.notes {
+        println!("  - {note}");
+    }
+    Ok(())
+}
+
+/// Apply the pending schema migrations to the context tracing database,
+/// or with `dry_run` only list them
+pub async fn run_trace_migrate(config: Config, dry_run: bool) -> Result<()> {
+    use crate::context::{migrations, storage};
+
+    let path = &config.context_tracing.sqlite_path;
+    if !path.exists() {
+        println!(
+            "No context tracing database at {}; it is created at schema version {} on first use",
+            path.display(),
+            migrations::latest_version()
+        );
+        return Ok(());
+    }
+    let conn = rusqlite::Connection::open(path).map_err(storage::StorageError::from)?;
+    let pending = migrations::pending(&conn)?;
+    if pending.is_empty() {
+        println!(
+            "{} is up to date at schema version {}",
+            path.display(),
+            migrations::latest_version()
+        );
+        return Ok(());
+    }
+
+    let verb = if dry_run { "Would apply" } else { "Applying" };
+    for migration in &pending {
+        println!("{verb} V{} {}", migration.version, migration.name);
+    }
+    if dry_run {
+        return Ok(());
+    }
+    storage::initialize_schema(&conn)?;
+    println!(
+        "Migrated {} to schema version {}",
+        path.display(),
+        migrations::latest_version()
+    );
+    Ok(())
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    use std::io::Write;
+    print!("{question} [y/N] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Start a server, initialize and ping it, and list what it offers, noting
+/// in `timeline` when each startup step happened. Returns the initialize
+/// result along with the lists.
+async fn test_server_ping(
+    name: &str,
+    config: &crate::config::ServerConfig,
+    timeline: &mut StartupTimeline,
+) -> Result<(Value, ServerCapabilities)> {
     // Create transport
-    let transport = create_transport(&config.transport, config, None)?;
+    let transport = create_transport(&config.transport, config, None, None)?;
 
     // Connect
     let connection = timeout(Duration::from_secs(10), transport.connect())
@@ -85,15 +667,18 @@ async fn test_server_ping(name: &str, config: &crate::config::ServerConfig) -> R
     connection
         .send(Bytes::from(format!("{request_json}\n")))
         .await?;
+    timeline.initialize_sent_at = Some(chrono::Utc::now());
 
     // Wait for initialize response
-    let response_bytes = timeout(Duration::from_secs(5), connection.recv())
-        .await
-        .map_err(|_| crate::error::ProxyError::Timeout)??;
+    let response_bytes = timeout(Duration::from_secs(5), connection.recv()).await;
+    timeline.first_byte_at = connection.first_output_at();
+    let response_bytes = response_bytes.map_err(|_| crate::error::ProxyError::Timeout)??;
+    timeline.initialize_received_at = Some(chrono::Utc::now());
 
     let response_str = std::str::from_utf8(&response_bytes)
         .map_err(|_| crate::error::ProxyError::InvalidResponse)?;
-    let _response: JsonRpcMessage = serde_json::from_str(response_str.trim())?;
+    let mut response: Value = serde_json::from_str(response_str.trim())?;
+    timeline.ready_at = Some(chrono::Utc::now());
 
     // Send ping request
     let ping_request = mcp::create_ping_request(JsonRpcId::Number(2));
@@ -117,8 +702,31 @@ async fn test_server_ping(name: &str, config: &crate::config::ServerConfig) -> R
             if resp.error.is_some() {
                 return Err(crate::error::ProxyError::InvalidResponse);
             }
-            Ok(())
         }
-        _ => Err(crate::error::ProxyError::InvalidResponse),
+        _ => return Err(crate::error::ProxyError::InvalidResponse),
     }
+
+    // List what the server offers, for conflict detection and snapshots
+    let tools = list(connection.as_ref(), 3, "tools/list").await?;
+    let resources = list(connection.as_ref(), 4, "resources/list").await?;
+    let prompts = list(connection.as_ref(), 5, "prompts/list").await?;
+    Ok((
+        response["result"].take(),
+        ServerCapabilities::from_lists(&tools, &resources, &prompts),
+    ))
+}
+
+/// The result of a list request, or null when the server doesn't support it
+async fn list(
+    connection: &dyn crate::transport::Connection,
+    id: u64,
+    method: &str,
+) -> Result<Value> {
+    let request = json!({ "jsonrpc": "2.0", "id": id, "method": method });
+    connection.send(Bytes::from(format!("{request}\n"))).await?;
+    let response = timeout(Duration::from_secs(5), connection.recv())
+        .await
+        .map_err(|_| crate::error::ProxyError::Timeout)??;
+    let mut response: Value = serde_json::from_slice(&response)?;
+    Ok(response["result"].take())
 }
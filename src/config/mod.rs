@@ -3,3 +3,26 @@ pub mod schema;
 
 pub use loader::*;
 pub use schema::*;
+
+/// A configuration with the given `servers`, the web UI off and defaults
+/// elsewhere
+#[cfg(test)]
+pub(crate) fn test_config(servers: serde_json::Value) -> Config {
+    test_config_with(servers, serde_json::json!({}))
+}
+
+/// [`test_config`] with the top-level `settings` added, replacing any of
+/// the same name
+#[cfg(test)]
+pub(crate) fn test_config_with(servers: serde_json::Value, settings: serde_json::Value) -> Config {
+    let mut config = serde_json::json!({
+        "servers": servers,
+        "proxy": {},
+        "webUi": { "enabled": false }
+    });
+    if let (Some(config), serde_json::Value::Object(settings)) = (config.as_object_mut(), settings)
+    {
+        config.extend(settings);
+    }
+    serde_json::from_value(config).unwrap()
+}
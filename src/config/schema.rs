@@ -1,5 +1,7 @@
+use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -15,15 +17,403 @@ pub struct Config {
     pub context_tracing: ContextTracingConfig,
     #[serde(default)]
     pub plugins: Option<crate::plugin::PluginConfig>,
+    #[serde(default)]
+    pub cache_warmer: CacheWarmerConfig,
+    #[serde(default)]
+    pub quarantine: QuarantineConfig,
+    /// Where alerts such as quarantined servers are sent
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    /// Redis-backed state shared between proxy instances
+    #[serde(default)]
+    pub shared_state: Option<SharedStateConfig>,
+    /// Clients allowed to use the proxy endpoint, by client id. When empty
+    /// the endpoint is open.
+    #[serde(default)]
+    pub clients: HashMap<String, ClientConfig>,
+    /// Outbound HTTP or SOCKS5 proxy for network transports, unless a server
+    /// sets its own
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    /// When `/readyz` reports the proxy ready to take traffic
+    #[serde(default)]
+    pub readiness: ReadinessConfig,
+    /// Rolling per-server request, error and latency history for the
+    /// dashboard's charts
+    #[serde(default)]
+    pub metrics_history: MetricsHistoryConfig,
+    /// Prometheus counters and histograms kept in SQLite so they carry on
+    /// across restarts
+    #[serde(default)]
+    pub metrics_persistence: MetricsPersistenceConfig,
+    /// Explicit routes to servers, consulted before the servers' advertised
+    /// catalogs
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    /// Failed tool calls kept for investigation and replay
+    #[serde(default)]
+    pub dead_letter: DeadLetterConfig,
+    /// Prompts served by the proxy itself, rendered from templates
+    #[serde(default)]
+    pub prompts: Vec<PromptConfig>,
+    /// Limits on binary resource contents, and links for downloading them
+    #[serde(default)]
+    pub blobs: BlobConfig,
+    /// Write nothing to disk: no server log files, context tracing in
+    /// memory, and nothing read from or saved to `~/.mcp-proxy`
+    #[serde(default)]
+    pub ephemeral: bool,
+}
+
+/// A prompt the proxy serves, whose text is a template over its arguments
+/// and the proxy's servers and tools
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptConfig {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<PromptArgumentConfig>,
+    /// Handlebars-style text; see `proxy::template`
+    pub template: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptArgumentConfig {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+    /// Used when the argument is left out
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessConfig {
+    /// Servers that must be running, capped at the number configured
+    #[serde(default = "default_min_ready_servers")]
+    pub min_ready_servers: usize,
+    /// Servers that must each be running
+    #[serde(default)]
+    pub required_servers: Vec<String>,
+    /// Also wait for the cache warmer to have fetched the tool list
+    #[serde(default)]
+    pub require_warm_cache: bool,
+    /// How long to keep taking requests after SIGTERM while reporting not
+    /// ready, so load balancers stop routing here before draining begins
+    #[serde(default)]
+    pub shutdown_delay_ms: u64,
+}
+
+impl Default for ReadinessConfig {
+    fn default() -> Self {
+        Self {
+            min_ready_servers: default_min_ready_servers(),
+            required_servers: Vec::new(),
+            require_warm_cache: false,
+            shutdown_delay_ms: 0,
+        }
+    }
+}
+
+impl ReadinessConfig {
+    pub fn shutdown_delay(&self) -> Duration {
+        Duration::from_millis(self.shutdown_delay_ms)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsHistoryConfig {
+    #[serde(default = "default_metrics_history_enabled")]
+    pub enabled: bool,
+    /// Length of each sample
+    #[serde(default = "default_metrics_history_interval")]
+    pub interval_seconds: u64,
+    /// How far back samples are kept
+    #[serde(default = "default_metrics_history_retention")]
+    pub retention_hours: u64,
+    /// Keep samples in SQLite so charts survive restarts
+    #[serde(default = "default_metrics_history_persist")]
+    pub persist: bool,
+    #[serde(default = "default_metrics_history_path")]
+    pub sqlite_path: PathBuf,
+}
+
+impl Default for MetricsHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_metrics_history_enabled(),
+            interval_seconds: default_metrics_history_interval(),
+            retention_hours: default_metrics_history_retention(),
+            persist: default_metrics_history_persist(),
+            sqlite_path: default_metrics_history_path(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsPersistenceConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// How often the metrics are saved, besides at shutdown
+    #[serde(default = "default_metrics_persistence_interval")]
+    pub interval_seconds: u64,
+    #[serde(default = "default_metrics_persistence_path")]
+    pub sqlite_path: PathBuf,
+}
+
+impl Default for MetricsPersistenceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_seconds: default_metrics_persistence_interval(),
+            sqlite_path: default_metrics_persistence_path(),
+        }
+    }
+}
+
+impl MetricsPersistenceConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_seconds)
+    }
+}
+
+impl MetricsHistoryConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_seconds)
+    }
+
+    pub fn retention(&self) -> Duration {
+        Duration::from_secs(self.retention_hours * 60 * 60)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetterConfig {
+    /// Off by default, as entries keep the calls' arguments
+    #[serde(default)]
+    pub enabled: bool,
+    /// Entries kept; the oldest are dropped beyond this
+    #[serde(default = "default_dead_letter_max_entries")]
+    pub max_entries: usize,
+    /// Keep entries in SQLite so they survive restarts
+    #[serde(default)]
+    pub persist: bool,
+    #[serde(default = "default_dead_letter_path")]
+    pub sqlite_path: PathBuf,
+}
+
+impl Default for DeadLetterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: default_dead_letter_max_entries(),
+            persist: false,
+            sqlite_path: default_dead_letter_path(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobConfig {
+    /// Largest blob, decoded, that a result may carry or link to; larger
+    /// ones fail the request
+    #[serde(default)]
+    pub max_bytes: Option<usize>,
+    #[serde(default)]
+    pub downloads: BlobDownloadConfig,
+}
+
+/// Serving large blobs from the proxy endpoint instead of inline, through
+/// signed links that expire
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobDownloadConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Blobs up to this size, decoded, stay inline
+    #[serde(default = "default_blob_inline_max_bytes")]
+    pub inline_max_bytes: usize,
+    /// How long a link works
+    #[serde(default = "default_blob_link_ttl")]
+    pub ttl_seconds: u64,
+    /// Memory held for blobs waiting to be downloaded; the oldest are
+    /// dropped beyond this
+    #[serde(default = "default_blob_max_stored_bytes")]
+    pub max_stored_bytes: usize,
+    /// The proxy endpoint's address as clients reach it, for building links;
+    /// defaults to the listening host and port
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+impl Default for BlobDownloadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            inline_max_bytes: default_blob_inline_max_bytes(),
+            ttl_seconds: default_blob_link_ttl(),
+            max_stored_bytes: default_blob_max_stored_bytes(),
+            base_url: None,
+        }
+    }
+}
+
+impl BlobDownloadConfig {
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_seconds)
+    }
+}
+
+/// Routes for names and URIs that discovery gets wrong or that several
+/// servers advertise. Rules are tried in order and the first match wins.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoutingConfig {
+    #[serde(default)]
+    pub tools: Vec<RouteRule>,
+    #[serde(default)]
+    pub resources: Vec<RouteRule>,
+    #[serde(default)]
+    pub prompts: Vec<RouteRule>,
+    /// Where unprefixed calls to a tool name several servers expose go
+    #[serde(default)]
+    pub conflicts: ConflictResolution,
+    /// Server order for `priority` resolution and for picking among
+    /// duplicate prompts, highest first
+    #[serde(default)]
+    pub priority: Vec<String>,
+    /// How prompts that several servers expose under one name are listed
+    #[serde(default)]
+    pub prompt_duplicates: PromptDuplicates,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PromptDuplicates {
+    /// Every server's prompts, each under `mcp__proxy__{server}__{name}`
+    #[default]
+    Prefixed,
+    /// Each name once, unprefixed, from the server a `prompts` route names,
+    /// else the one listed earliest in `priority`, else the first to list it
+    Precedence,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictResolution {
+    /// The server that registered the tool first
+    #[default]
+    FirstWins,
+    /// The server listed earliest in `priority`
+    Priority,
+    /// Refuse the call; clients must use the prefixed name
+    Error,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteRule {
+    /// Tool or prompt name, or resource URI, where `*` matches any run of
+    /// characters (e.g. `db_*` or `jira://*`)
+    pub pattern: String,
+    pub server: String,
+}
+
+impl RoutingConfig {
+    pub fn rules(&self) -> impl Iterator<Item = (&'static str, &RouteRule)> {
+        self.tools
+            .iter()
+            .map(|rule| ("tools", rule))
+            .chain(self.resources.iter().map(|rule| ("resources", rule)))
+            .chain(self.prompts.iter().map(|rule| ("prompts", rule)))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientConfig {
+    /// Sent by the client as `Authorization: Bearer <apiKey>`
+    pub api_key: String,
+    #[serde(default)]
+    pub quota: Option<ClientQuotaConfig>,
+    #[serde(default)]
+    pub role: ClientRole,
+    /// How the client's tool calls are scheduled when servers are busy
+    #[serde(default)]
+    pub priority: RequestPriority,
+}
+
+/// Which waiting tool calls get a server's free call slots first
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum RequestPriority {
+    /// Scheduled after interactive calls, and shed first when queues fill
+    Batch,
+    /// Calls someone is waiting on, such as from a chat
+    #[default]
+    Interactive,
+}
+
+/// What a client may change through the proxy's own tools
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ClientRole {
+    /// May also change proxy configuration, such as which plugins run, and
+    /// start, stop, restart, disable or put servers in maintenance
+    Admin,
+    #[default]
+    User,
+}
+
+/// Limits on a client's backend tool calls, counted per UTC day and month.
+/// Unset limits are unlimited.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientQuotaConfig {
+    #[serde(default)]
+    pub daily_calls: Option<u64>,
+    #[serde(default)]
+    pub monthly_calls: Option<u64>,
+    /// Token-equivalent cost, estimated from request and response size
+    #[serde(default)]
+    pub daily_tokens: Option<u64>,
+    #[serde(default)]
+    pub monthly_tokens: Option<u64>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ServerConfig {
+    /// The executable to run, or the package to run with `runtime`
     pub command: String,
     pub args: Vec<String>,
+    /// Launcher that runs `command` as a package, so the configuration
+    /// doesn't depend on how each machine installs it
+    #[serde(default)]
+    pub runtime: Option<Runtime>,
+    /// Version of the package a `runtime` server runs; the latest when unset
+    #[serde(default)]
+    pub package_version: Option<String>,
+    /// Fetch the package of a `runtime` server when the proxy starts, so its
+    /// first start doesn't wait on a download
+    #[serde(default)]
+    pub preinstall: bool,
     #[serde(default)]
     pub env: HashMap<String, String>,
+    /// Proxy environment variables the server inherits, by name or `PREFIX*`;
+    /// `["*"]` inherits everything
+    #[serde(default = "default_env_passthrough")]
+    pub env_passthrough: Vec<String>,
     pub transport: TransportConfig,
     #[serde(default = "default_enabled")]
     pub enabled: bool,
@@ -39,6 +429,381 @@ pub struct ServerConfig {
     pub initialization_delay_ms: Option<u64>,
     #[serde(default)]
     pub health_check: Option<ServerHealthCheckConfig>,
+    /// Pin the MCP protocol version used with this server (e.g. "2024-11-05"),
+    /// skipping detection for backends that mis-report their version
+    #[serde(default)]
+    pub protocol_version: Option<String>,
+    /// How long to wait for the initialize handshake before giving up
+    #[serde(default = "default_initialization_timeout")]
+    pub initialization_timeout_ms: u64,
+    /// What to do when the initialize handshake times out
+    #[serde(default)]
+    pub on_initialization_timeout: InitializationTimeoutAction,
+    /// Whether this server coming up triggers cache warming and its lists
+    /// are checked for changes. Its lists are still part of the aggregated
+    /// lists the warmer caches, which clients are served.
+    #[serde(default = "default_cache_warm")]
+    pub cache_warm: bool,
+    /// Tags grouping this server for bulk operations (e.g. `[search, internal]`)
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Number of recent log lines kept in memory for late-joining clients
+    #[serde(default = "default_log_buffer_lines")]
+    pub log_buffer_lines: usize,
+    /// Parsers tried in order to extract level, timestamp and message from
+    /// captured output lines
+    #[serde(default = "default_log_parsers")]
+    pub log_parsers: Vec<LogParserConfig>,
+    /// Servers this server relies on; it is stopped before them at shutdown
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Fail reads on stdout lines that are not JSON-RPC messages instead of
+    /// logging and skipping them
+    #[serde(default)]
+    pub strict_stdout: bool,
+    /// Longest line read from the server's stdout or stderr, in bytes.
+    /// Longer ones are discarded as they arrive, and a response among them
+    /// fails its request.
+    #[serde(default = "default_max_message_bytes")]
+    pub max_message_bytes: usize,
+    /// Initialized spare connections kept ready, each its own process for
+    /// stdio servers, so a failed connection is replaced without waiting
+    /// for a full startup
+    #[serde(default)]
+    pub warm_spares: usize,
+    /// Signal sent to the server's process group once its stdin is closed at
+    /// stop (Unix only)
+    #[serde(default = "default_stop_signal")]
+    pub stop_signal: String,
+    /// How long a stopping server may take to exit before it and its
+    /// children are killed
+    #[serde(default = "default_stop_timeout")]
+    pub stop_timeout_ms: u64,
+    /// Unix account the stdio server runs as (the proxy must be root)
+    #[serde(default)]
+    pub run_as: Option<RunAsConfig>,
+    /// Linux confinement applied to the stdio server before it starts
+    #[serde(default)]
+    pub sandbox: Option<SandboxConfig>,
+    /// Outbound HTTP or SOCKS5 proxy for this server's network transport
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    /// Tool calls the server handles at once; unlimited when unset. With
+    /// adaptive concurrency, the most it may allow
+    #[serde(default)]
+    pub max_concurrent_calls: Option<usize>,
+    /// Adjust the concurrent call limit to the server's latency and errors
+    #[serde(default)]
+    pub adaptive_concurrency: Option<AdaptiveConcurrencyConfig>,
+    /// What happens to calls beyond `max_concurrent_calls`
+    #[serde(default)]
+    pub excess_calls: ExcessCallAction,
+    /// Calls that may wait for a slot when excess calls queue; when full,
+    /// batch calls are shed first. Unbounded when unset
+    #[serde(default)]
+    pub max_queued_calls: Option<usize>,
+    /// Priority of calls to these tools, lowering that of the client
+    #[serde(default)]
+    pub tool_priorities: HashMap<String, RequestPriority>,
+    /// Cron expression (local time) at which the server is restarted, e.g.
+    /// `0 4 * * *`
+    #[serde(default)]
+    pub restart_cron: Option<String>,
+    /// Cron expression (local time) matching the minutes the server should
+    /// run, e.g. `* 9-17 * * 1-5`; it is stopped outside them
+    #[serde(default)]
+    pub available_cron: Option<String>,
+    /// Pass the W3C trace context of requests on to the server in the
+    /// `_meta` of its tool calls
+    #[serde(default)]
+    pub propagate_trace_context: bool,
+    /// A tool call that must succeed after the handshake before the server
+    /// is marked Running and included in aggregation
+    #[serde(default)]
+    pub startup_probe: Option<StartupProbeConfig>,
+    /// Commands run as the server starts, becomes ready, fails or stops
+    #[serde(default)]
+    pub hooks: LifecycleHooks,
+    /// Declarative changes to tool call arguments, applied in order before
+    /// request plugins
+    #[serde(default)]
+    pub transforms: Vec<RequestTransform>,
+    /// How an HTTP or WebSocket server's address is re-resolved
+    #[serde(default)]
+    pub dns: DnsConfig,
+    /// Largest tool call result or resource read passed on to clients, in
+    /// bytes; unlimited when unset
+    #[serde(default)]
+    pub max_response_bytes: Option<usize>,
+    /// `maxResponseBytes` for particular tools, by their name on the server
+    #[serde(default)]
+    pub tool_max_response_bytes: HashMap<String, usize>,
+    /// What happens to responses over the limit
+    #[serde(default)]
+    pub oversized_responses: OversizedResponseAction,
+    /// Fail successful calls to tools declaring an `outputSchema` whose
+    /// result has no `structuredContent` or one that doesn't match it
+    #[serde(default)]
+    pub strict_output_schema: bool,
+}
+
+/// Re-resolution of a network server's address, for backends whose DNS
+/// records change under them (e.g. blue/green deploys)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DnsConfig {
+    /// Reconnect, resolving the address afresh, once the connection is this
+    /// old; kept for the process lifetime when unset
+    #[serde(default)]
+    pub ttl_seconds: Option<u64>,
+    /// Reconnect, resolving the address afresh, when a health check fails,
+    /// before counting the failure
+    #[serde(default = "default_reresolve_on_health_failure")]
+    pub reresolve_on_health_failure: bool,
+    /// Which address family is dialed first when the name has both
+    #[serde(default)]
+    pub ip_preference: IpPreference,
+}
+
+/// Address family tried first on dual-stack hosts. The other family is
+/// still tried if the first is slow to connect.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum IpPreference {
+    /// The order the system resolver returns
+    #[default]
+    Auto,
+    PreferIpv4,
+    PreferIpv6,
+}
+
+impl Default for DnsConfig {
+    fn default() -> Self {
+        Self {
+            ttl_seconds: None,
+            reresolve_on_health_failure: default_reresolve_on_health_failure(),
+            ip_preference: IpPreference::default(),
+        }
+    }
+}
+
+impl DnsConfig {
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl_seconds.map(Duration::from_secs)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestTransform {
+    /// Tools the rule applies to, where `*` matches any run of characters;
+    /// every tool when unset
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// Arguments removed from the call
+    #[serde(default)]
+    pub drop: Vec<String>,
+    /// Arguments added when the call leaves them out
+    #[serde(default)]
+    pub defaults: serde_json::Map<String, serde_json::Value>,
+    /// Arguments set whatever the call passes
+    #[serde(default)]
+    pub set: serde_json::Map<String, serde_json::Value>,
+    /// Entries added to the request's `_meta`
+    #[serde(default)]
+    pub meta: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupProbeConfig {
+    /// Tool to call, as the server names it
+    pub tool: String,
+    #[serde(default)]
+    pub arguments: serde_json::Map<String, serde_json::Value>,
+    /// Text the call's result must contain
+    #[serde(default)]
+    pub expect_text: Option<String>,
+    #[serde(default = "default_startup_probe_timeout")]
+    pub timeout_ms: u64,
+    /// Calls made before the server is marked Failed
+    #[serde(default = "default_startup_probe_attempts")]
+    pub attempts: u32,
+    #[serde(default = "default_startup_probe_retry_interval")]
+    pub retry_interval_ms: u64,
+}
+
+/// Commands run on a server's lifecycle events, with `MCP_PROXY_*`
+/// environment variables describing the event
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LifecycleHooks {
+    /// Before the server is started; it isn't started when this fails
+    #[serde(default)]
+    pub on_start: Option<HookCommand>,
+    /// Once the server is Running
+    #[serde(default)]
+    pub on_ready: Option<HookCommand>,
+    /// When the server is marked Failed
+    #[serde(default)]
+    pub on_crash: Option<HookCommand>,
+    /// Once the server has stopped
+    #[serde(default)]
+    pub on_stop: Option<HookCommand>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookCommand {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// The command is killed after this long
+    #[serde(default = "default_hook_timeout")]
+    pub timeout_ms: u64,
+}
+
+/// Package runner for a server's `command`
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Runtime {
+    /// npm packages, run with `npx -y`
+    Node,
+    /// PyPI packages, run with `uvx`
+    Python,
+    /// npm packages, run with `bunx`
+    Bun,
+}
+
+/// Unix account a server process runs as
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RunAsConfig {
+    pub user: String,
+    /// Defaults to the user's primary group
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+/// Linux-only restrictions on what a server process can reach
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxConfig {
+    /// Run in a private mount namespace, so mounts made by or for the server
+    /// stay invisible to the host
+    #[serde(default)]
+    pub mount_namespace: bool,
+    /// Directories hidden behind an empty tmpfs inside the server's mount
+    /// namespace (implies `mountNamespace`)
+    #[serde(default)]
+    pub tmpfs_paths: Vec<PathBuf>,
+    /// Stop the server and its children from gaining privileges through
+    /// setuid binaries
+    #[serde(default = "default_no_new_privileges")]
+    pub no_new_privileges: bool,
+    /// Landlock rules: when either list is set, the server may only read
+    /// and execute beneath `readOnlyPaths` and fully use `readWritePaths`
+    #[serde(default)]
+    pub read_only_paths: Vec<PathBuf>,
+    #[serde(default)]
+    pub read_write_paths: Vec<PathBuf>,
+}
+
+/// How to recognize structured log lines in a server's output
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum LogParserConfig {
+    /// Lines that are JSON objects; unset fields fall back to common names
+    /// (`level`/`severity`, `message`/`msg`, `timestamp`/`time`)
+    #[serde(rename_all = "camelCase")]
+    Json {
+        #[serde(default)]
+        level_field: Option<String>,
+        #[serde(default)]
+        message_field: Option<String>,
+        #[serde(default)]
+        timestamp_field: Option<String>,
+    },
+    /// A regex with a `level` named group and optional `message` and
+    /// `timestamp` groups
+    Regex { pattern: String },
+}
+
+/// Matches lines starting with a level, e.g. `ERROR: ...`, `[warn] ...` or
+/// `2025-01-01T00:00:00Z INFO ...`
+pub const DEFAULT_LOG_LEVEL_PATTERN: &str = r"(?i)^\s*(?:(?P<timestamp>\d{4}-\d{2}-\d{2}[T ][\d:.]+(?:Z|[+-]\d{2}:?\d{2})?)\s+)?\[?(?P<level>trace|debug|info|warn(?:ing)?|error|fatal|critical)\]?[:\s]\s*(?P<message>.*)$";
+
+/// Behavior for tool calls beyond a server's `maxConcurrentCalls`
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ExcessCallAction {
+    /// Wait for a running call to finish, up to the request timeout
+    #[default]
+    Queue,
+    /// Fail at once with a retryable error
+    Reject,
+}
+
+/// Most concurrent calls an adaptive limit grows to without
+/// `maxConcurrentCalls`
+pub const DEFAULT_ADAPTIVE_MAX_CALLS: usize = 32;
+
+/// Limit on a server's concurrent tool calls that follows its latency and
+/// errors: it grows by one per round of calls that go well and shrinks by
+/// `backoff` when recent calls slow down or fail
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AdaptiveConcurrencyConfig {
+    /// Limit to start from (default: 4)
+    #[serde(default = "default_adaptive_initial_limit")]
+    pub initial_limit: usize,
+    /// The limit never drops below this (default: 1)
+    #[serde(default = "default_adaptive_min_limit")]
+    pub min_limit: usize,
+    /// Recent latency above this multiple of the long-run latency counts as
+    /// overload (default: 2.0)
+    #[serde(default = "default_adaptive_latency_tolerance")]
+    pub latency_tolerance: f64,
+    /// Factor the limit is multiplied by on overload (default: 0.75)
+    #[serde(default = "default_adaptive_backoff")]
+    pub backoff: f64,
+}
+
+impl Default for AdaptiveConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            initial_limit: default_adaptive_initial_limit(),
+            min_limit: default_adaptive_min_limit(),
+            latency_tolerance: default_adaptive_latency_tolerance(),
+            backoff: default_adaptive_backoff(),
+        }
+    }
+}
+
+/// Behavior for results over a server's `maxResponseBytes`
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum OversizedResponseAction {
+    /// Keep as much content as fits and say that the rest was cut
+    #[default]
+    Truncate,
+    /// Fail the request
+    Reject,
+}
+
+/// Behavior when a server's initialize handshake exceeds its timeout
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum InitializationTimeoutAction {
+    /// Mark the server as failed immediately
+    #[default]
+    MarkFailed,
+    /// Retry the handshake up to `attempts` more times before failing
+    Retry { attempts: u32 },
+    /// Keep retrying while requests stay queued, failing once the queue
+    /// has waited longer than `max_queue_age_ms`
+    #[serde(rename_all = "camelCase")]
+    KeepQueuing { max_queue_age_ms: u64 },
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -50,6 +815,9 @@ pub enum TransportConfig {
         url: String,
         #[serde(default)]
         headers: HashMap<String, String>,
+        /// Headers whose values are printed by a command, by header name
+        #[serde(default)]
+        headers_from_command: HashMap<String, HeaderCommand>,
         #[serde(default = "default_http_timeout")]
         timeout_ms: u64,
     },
@@ -63,51 +831,314 @@ pub enum TransportConfig {
     },
 }
 
+impl TransportConfig {
+    /// The `type` naming this transport in configuration files
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransportConfig::Stdio => "stdio",
+            TransportConfig::HttpSse { .. } => "httpSse",
+            TransportConfig::WebSocket { .. } => "webSocket",
+        }
+    }
+}
+
+/// A command printing a header value, such as a short-lived access token
+/// from `gcloud auth print-access-token`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderCommand {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Put before the command's output, e.g. `"Bearer "`
+    #[serde(default)]
+    pub prefix: String,
+    /// How long a value is used before the command runs again. Keep it
+    /// below the token's lifetime.
+    #[serde(default = "default_header_command_ttl")]
+    pub ttl_seconds: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyConfig {
+    #[serde(default = "default_proxy_port")]
+    pub port: u16,
+    #[serde(default = "default_proxy_host")]
+    pub host: String,
+    #[serde(default = "default_connection_pool_size")]
+    pub connection_pool_size: usize,
+    #[serde(default = "default_request_timeout")]
+    pub request_timeout_ms: u64,
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// How long in-flight requests may keep running once shutdown begins
+    #[serde(default = "default_drain_timeout")]
+    pub drain_timeout_ms: u64,
+    /// Bind listeners with `SO_REUSEPORT` so a replacement proxy can take
+    /// over the ports before this one exits
+    #[serde(default)]
+    pub reuse_port: bool,
+    /// Compress responses on the proxy and web UI listeners for clients
+    /// that accept gzip or brotli
+    #[serde(default = "default_compression")]
+    pub compression: bool,
+    /// Largest request body accepted on the proxy endpoint
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// Deepest nesting of arrays and objects accepted in a request
+    #[serde(default = "default_max_json_depth")]
+    pub max_json_depth: usize,
+    /// Most array items and object members accepted in a request, in total
+    #[serde(default = "default_max_json_elements")]
+    pub max_json_elements: usize,
+    /// Client addresses or CIDR ranges allowed to connect; empty allows all
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+    /// How often idle backend connections are checked and replaced if their
+    /// process has exited or their transport failed; 0 disables the check
+    #[serde(default = "default_connection_reap_interval")]
+    pub connection_reap_interval_ms: u64,
+    /// Add the estimated token count of each backend tool result to its
+    /// `_meta` as `tokenEstimate`
+    #[serde(default)]
+    pub token_estimates: bool,
+    /// Accept the draft of the next MCP version from servers and in
+    /// `protocolVersion`; needs a build with the `draft-protocol` feature
+    #[serde(default)]
+    pub draft_protocol: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebUIConfig {
+    #[serde(default = "default_web_ui_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_web_ui_port")]
+    pub port: u16,
+    #[serde(default = "default_web_ui_host")]
+    pub host: String,
+    #[serde(default)]
+    pub static_dir: Option<PathBuf>,
+    #[serde(default = "default_api_key")]
+    pub api_key: Option<String>,
+    /// Client addresses or CIDR ranges allowed to connect; empty allows all
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+    #[serde(default)]
+    pub cors: CorsConfig,
+}
+
+/// Cross-origin access to the web UI and its API. With no allowed origins
+/// only pages served by the web UI itself can use the API.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorsConfig {
+    /// Origins such as `https://ops.example.com`, or `*` for any origin
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    #[serde(default = "default_cors_methods")]
+    pub allowed_methods: Vec<String>,
+    #[serde(default = "default_cors_headers")]
+    pub allowed_headers: Vec<String>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_methods: default_cors_methods(),
+            allowed_headers: default_cors_headers(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheckConfig {
+    #[serde(default = "default_health_check_interval")]
+    pub interval_seconds: u64,
+    #[serde(default = "default_health_check_timeout")]
+    pub timeout_seconds: u64,
+    #[serde(default = "default_health_check_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_retry_interval")]
+    pub retry_interval_seconds: u64,
+}
+
+/// Background cache warmer configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheWarmerConfig {
+    #[serde(default = "default_cache_warmer_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_cache_warmer_interval")]
+    pub interval_seconds: u64,
+    /// List methods to pre-fetch (tools/list, resources/list, prompts/list)
+    #[serde(default = "default_cache_warmer_methods")]
+    pub methods: Vec<String>,
+}
+
+impl Default for CacheWarmerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_cache_warmer_enabled(),
+            interval_seconds: default_cache_warmer_interval(),
+            methods: default_cache_warmer_methods(),
+        }
+    }
+}
+
+/// Automatic quarantine of servers that keep restarting
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantineConfig {
+    #[serde(default = "default_quarantine_enabled")]
+    pub enabled: bool,
+    /// Quarantine a server that restarts more than this many times...
+    #[serde(default = "default_quarantine_max_restarts")]
+    pub max_restarts: u32,
+    /// ...within this many minutes
+    #[serde(default = "default_quarantine_window_minutes")]
+    pub window_minutes: u64,
+}
+
+impl Default for QuarantineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_quarantine_enabled(),
+            max_restarts: default_quarantine_max_restarts(),
+            window_minutes: default_quarantine_window_minutes(),
+        }
+    }
+}
+
+impl QuarantineConfig {
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.window_minutes * 60)
+    }
+}
+
+/// Webhooks notified of alerts, and rules that raise alerts from metrics
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertsConfig {
+    #[serde(default)]
+    pub notifiers: Vec<NotifierConfig>,
+    #[serde(default)]
+    pub rules: Vec<AlertRuleConfig>,
+    /// How often rules are evaluated
+    #[serde(default = "default_alert_evaluation_interval")]
+    pub evaluation_interval_seconds: u64,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            notifiers: Vec::new(),
+            rules: Vec::new(),
+            evaluation_interval_seconds: default_alert_evaluation_interval(),
+        }
+    }
+}
+
+impl AlertsConfig {
+    pub fn evaluation_interval(&self) -> Duration {
+        Duration::from_secs(self.evaluation_interval_seconds)
+    }
+}
+
+/// Fires when `metric` of a server stays above `above` for `forSeconds`
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ProxyConfig {
-    #[serde(default = "default_proxy_port")]
-    pub port: u16,
-    #[serde(default = "default_proxy_host")]
-    pub host: String,
-    #[serde(default = "default_connection_pool_size")]
-    pub connection_pool_size: usize,
-    #[serde(default = "default_request_timeout")]
-    pub request_timeout_ms: u64,
-    #[serde(default = "default_max_concurrent_requests")]
-    pub max_concurrent_requests: usize,
+pub struct AlertRuleConfig {
+    pub name: String,
+    pub metric: AlertMetric,
+    /// Only watch this server; every server is watched separately when unset
+    #[serde(default)]
+    pub server: Option<String>,
+    pub above: f64,
+    #[serde(default)]
+    pub for_seconds: u64,
+    /// Measure the error rate over this much of the metrics history rather
+    /// than since the last evaluation
+    #[serde(default)]
+    pub window_seconds: Option<u64>,
+    /// Only watch calls to this tool, as clients call it; needs
+    /// `windowSeconds`
+    #[serde(default)]
+    pub tool: Option<String>,
+}
+
+/// Per-server values alert rules can watch
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertMetric {
+    /// Percentage of requests since the last evaluation that failed in
+    /// transport or got a JSON-RPC error reply
+    ErrorRate,
+    P50LatencyMs,
+    P95LatencyMs,
+    P99LatencyMs,
+    /// Requests waiting on the server
+    InFlight,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct WebUIConfig {
-    #[serde(default = "default_web_ui_enabled")]
-    pub enabled: bool,
-    #[serde(default = "default_web_ui_port")]
-    pub port: u16,
-    #[serde(default = "default_web_ui_host")]
-    pub host: String,
+pub struct NotifierConfig {
+    #[serde(rename = "type")]
+    pub kind: NotifierKind,
+    /// Incoming webhook URL of the channel
+    pub webhook_url: String,
+    /// Message text; `{server}`, `{kind}`, `{message}` and `{timestamp}` are
+    /// replaced with the alert's fields
+    #[serde(default = "default_notifier_template")]
+    pub template: String,
+    /// Alert kinds to send; all when empty
     #[serde(default)]
-    pub static_dir: Option<PathBuf>,
-    #[serde(default = "default_api_key")]
-    pub api_key: Option<String>,
+    pub events: Vec<crate::state::AlertKind>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
-pub struct HealthCheckConfig {
-    #[serde(default = "default_health_check_interval")]
-    pub interval_seconds: u64,
-    #[serde(default = "default_health_check_timeout")]
-    pub timeout_seconds: u64,
-    #[serde(default = "default_health_check_enabled")]
-    pub enabled: bool,
-    #[serde(default = "default_max_attempts")]
-    pub max_attempts: u32,
-    #[serde(default = "default_retry_interval")]
-    pub retry_interval_seconds: u64,
+pub enum NotifierKind {
+    Slack,
+    Discord,
+}
+
+/// State shared by proxy instances running behind a load balancer
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedStateConfig {
+    /// e.g. `redis://redis.internal:6379/0`
+    pub redis_url: String,
+    /// Prefix for every key, so several deployments can share one Redis
+    #[serde(default = "default_shared_state_key_prefix")]
+    pub key_prefix: String,
+    /// How long an aggregated tools/resources catalog is shared
+    #[serde(default = "default_catalog_ttl_seconds")]
+    pub catalog_ttl_seconds: u64,
+    /// How often servers disabled or enabled by other instances are picked up
+    #[serde(default = "default_disabled_refresh_seconds")]
+    pub disabled_refresh_seconds: u64,
 }
 
+impl SharedStateConfig {
+    pub fn catalog_ttl(&self) -> Duration {
+        Duration::from_secs(self.catalog_ttl_seconds)
+    }
+
+    pub fn disabled_refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.disabled_refresh_seconds)
+    }
+}
+
+/// List methods the cache warmer knows how to pre-fetch
+pub const CACHE_WARMABLE_METHODS: &[&str] = &["tools/list", "resources/list", "prompts/list"];
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ServerHealthCheckConfig {
@@ -140,6 +1171,54 @@ fn default_restart_delay() -> u64 {
     5000
 }
 
+fn default_initialization_timeout() -> u64 {
+    60000
+}
+
+fn default_env_passthrough() -> Vec<String> {
+    crate::transport::process::DEFAULT_ENV_PASSTHROUGH
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+fn default_no_new_privileges() -> bool {
+    true
+}
+
+fn default_stop_signal() -> String {
+    "SIGTERM".to_string()
+}
+
+fn default_stop_timeout() -> u64 {
+    5000
+}
+
+fn default_cache_warm() -> bool {
+    true
+}
+
+fn default_max_message_bytes() -> usize {
+    crate::transport::stdio::DEFAULT_MAX_MESSAGE_BYTES
+}
+
+fn default_log_buffer_lines() -> usize {
+    crate::state::DEFAULT_LOG_BUFFER_LINES
+}
+
+fn default_log_parsers() -> Vec<LogParserConfig> {
+    vec![
+        LogParserConfig::Json {
+            level_field: None,
+            message_field: None,
+            timestamp_field: None,
+        },
+        LogParserConfig::Regex {
+            pattern: DEFAULT_LOG_LEVEL_PATTERN.to_string(),
+        },
+    ]
+}
+
 fn default_proxy_port() -> u16 {
     3000
 }
@@ -160,6 +1239,30 @@ fn default_max_concurrent_requests() -> usize {
     100
 }
 
+fn default_drain_timeout() -> u64 {
+    30000 // 30 seconds
+}
+
+fn default_compression() -> bool {
+    true
+}
+
+fn default_max_body_bytes() -> usize {
+    4 * 1024 * 1024 // 4 MiB
+}
+
+fn default_max_json_depth() -> usize {
+    64
+}
+
+fn default_max_json_elements() -> usize {
+    100_000
+}
+
+fn default_connection_reap_interval() -> u64 {
+    30000 // 30 seconds
+}
+
 fn default_web_ui_enabled() -> bool {
     true
 }
@@ -172,6 +1275,17 @@ fn default_web_ui_host() -> String {
     "0.0.0.0".to_string()
 }
 
+fn default_cors_methods() -> Vec<String> {
+    ["GET", "POST", "PUT", "DELETE"]
+        .iter()
+        .map(|m| m.to_string())
+        .collect()
+}
+
+fn default_cors_headers() -> Vec<String> {
+    vec!["Content-Type".to_string(), "Authorization".to_string()]
+}
+
 fn default_api_key() -> Option<String> {
     None
 }
@@ -196,6 +1310,69 @@ fn default_retry_interval() -> u64 {
     10
 }
 
+fn default_cache_warmer_enabled() -> bool {
+    true
+}
+
+fn default_cache_warmer_interval() -> u64 {
+    60
+}
+
+fn default_cache_warmer_methods() -> Vec<String> {
+    CACHE_WARMABLE_METHODS
+        .iter()
+        .map(|m| m.to_string())
+        .collect()
+}
+
+fn default_quarantine_enabled() -> bool {
+    true
+}
+
+fn default_quarantine_max_restarts() -> u32 {
+    5
+}
+
+fn default_adaptive_initial_limit() -> usize {
+    4
+}
+
+fn default_adaptive_min_limit() -> usize {
+    1
+}
+
+fn default_adaptive_latency_tolerance() -> f64 {
+    2.0
+}
+
+fn default_adaptive_backoff() -> f64 {
+    0.75
+}
+
+fn default_quarantine_window_minutes() -> u64 {
+    10
+}
+
+fn default_alert_evaluation_interval() -> u64 {
+    30
+}
+
+fn default_notifier_template() -> String {
+    ":rotating_light: {message}".to_string()
+}
+
+fn default_shared_state_key_prefix() -> String {
+    "mcp-proxy".to_string()
+}
+
+fn default_catalog_ttl_seconds() -> u64 {
+    120 // Matches the local tools/list cache
+}
+
+fn default_disabled_refresh_seconds() -> u64 {
+    5
+}
+
 fn default_server_health_check_enabled() -> Option<bool> {
     None
 }
@@ -208,6 +1385,91 @@ fn default_ws_reconnect() -> bool {
     true
 }
 
+fn default_min_ready_servers() -> usize {
+    1
+}
+
+fn default_metrics_history_enabled() -> bool {
+    true
+}
+
+fn default_metrics_history_interval() -> u64 {
+    60
+}
+
+fn default_metrics_history_retention() -> u64 {
+    24
+}
+
+fn default_metrics_history_persist() -> bool {
+    true
+}
+
+fn default_metrics_persistence_interval() -> u64 {
+    60
+}
+
+fn default_dead_letter_max_entries() -> usize {
+    1000
+}
+
+fn default_blob_inline_max_bytes() -> usize {
+    64 * 1024
+}
+
+fn default_blob_link_ttl() -> u64 {
+    300
+}
+
+fn default_blob_max_stored_bytes() -> usize {
+    256 * 1024 * 1024
+}
+
+fn default_dead_letter_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".mcp-proxy")
+        .join("dead-letter.db")
+}
+
+fn default_metrics_history_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".mcp-proxy")
+        .join("metrics-history.db")
+}
+
+fn default_metrics_persistence_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".mcp-proxy")
+        .join("metrics.db")
+}
+
+fn default_startup_probe_timeout() -> u64 {
+    10_000
+}
+
+fn default_startup_probe_attempts() -> u32 {
+    3
+}
+
+fn default_startup_probe_retry_interval() -> u64 {
+    2_000
+}
+
+fn default_reresolve_on_health_failure() -> bool {
+    true
+}
+
+fn default_hook_timeout() -> u64 {
+    30_000
+}
+
+fn default_header_command_ttl() -> u64 {
+    300
+}
+
 // Context tracing defaults
 fn default_context_tracing_enabled() -> bool {
     true
@@ -232,6 +1494,26 @@ fn default_retention_days() -> u32 {
     90
 }
 
+fn default_compaction_cron() -> Option<String> {
+    Some("0 3 * * *".to_string())
+}
+
+fn default_checkpoint_interval_seconds() -> u64 {
+    300
+}
+
+fn default_read_connections() -> u32 {
+    4
+}
+
+fn default_hash_dimensions() -> usize {
+    256
+}
+
+fn default_embedding_timeout_ms() -> u64 {
+    10_000
+}
+
 /// Context tracing configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -256,9 +1538,62 @@ pub struct ContextTracingConfig {
     #[serde(default = "default_cache_ttl_seconds")]
     pub cache_ttl_seconds: i64,
 
+    /// Which entries each cache (responses, contexts, lineage) drops when
+    /// full (default: timeBasedLru)
+    #[serde(default)]
+    pub eviction_strategy: crate::context::cache::EvictionStrategies,
+
+    /// Read-only SQLite connections serving lookups alongside the single
+    /// writer (default: 4)
+    #[serde(default = "default_read_connections")]
+    pub read_connections: u32,
+
+    /// Embed context units for similarity search (default: off)
+    #[serde(default)]
+    pub embeddings: Option<EmbeddingConfig>,
+
+    /// Hash user identifiers and drop free text (context summaries,
+    /// feedback comments) before storing, keeping only the lineage
+    /// structure (default: false)
+    #[serde(default)]
+    pub anonymize: bool,
+
+    /// Secret user identifiers are hashed with when anonymizing; hashes
+    /// stay comparable as long as it does not change
+    #[serde(default)]
+    pub anonymization_key: Option<String>,
+
     /// Retention period in days (default: 90)
     #[serde(default = "default_retention_days")]
     pub retention_days: u32,
+
+    /// Compaction and checkpointing of the SQLite database
+    #[serde(default)]
+    pub maintenance: TraceMaintenanceConfig,
+}
+
+/// Keeps the tracing database from growing without bound between deletes
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceMaintenanceConfig {
+    /// When to release free pages and refresh query statistics, as a cron
+    /// expression in local time; null never compacts (default: "0 3 * * *")
+    #[serde(default = "default_compaction_cron")]
+    pub compaction_cron: Option<String>,
+
+    /// Seconds between checkpoints of the write-ahead log; 0 leaves it to
+    /// SQLite (default: 300)
+    #[serde(default = "default_checkpoint_interval_seconds")]
+    pub checkpoint_interval_seconds: u64,
+}
+
+impl Default for TraceMaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            compaction_cron: default_compaction_cron(),
+            checkpoint_interval_seconds: default_checkpoint_interval_seconds(),
+        }
+    }
 }
 
 impl Default for ContextTracingConfig {
@@ -269,11 +1604,40 @@ impl Default for ContextTracingConfig {
             sqlite_path: default_sqlite_path(),
             cache_size: default_cache_size(),
             cache_ttl_seconds: default_cache_ttl_seconds(),
+            eviction_strategy: Default::default(),
+            read_connections: default_read_connections(),
+            embeddings: None,
+            anonymize: false,
+            anonymization_key: None,
             retention_days: default_retention_days(),
+            maintenance: TraceMaintenanceConfig::default(),
         }
     }
 }
 
+/// Where embeddings of context units come from
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "provider", rename_all = "camelCase")]
+pub enum EmbeddingConfig {
+    /// Built in: hashes the words of the text, so similar means sharing
+    /// words rather than meaning
+    Hash {
+        #[serde(default = "default_hash_dimensions")]
+        dimensions: usize,
+    },
+    /// An OpenAI-compatible API; `url` is its base, e.g.
+    /// `https://api.openai.com/v1` or `http://localhost:11434/v1` for Ollama
+    #[serde(rename_all = "camelCase")]
+    Api {
+        url: String,
+        model: String,
+        #[serde(default)]
+        api_key: Option<String>,
+        #[serde(default = "default_embedding_timeout_ms")]
+        timeout_ms: u64,
+    },
+}
+
 /// Storage backend type
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
@@ -290,7 +1654,44 @@ impl Default for StorageType {
     }
 }
 
+/// Check an outbound proxy URL. HTTP proxies (tunnelling HTTPS with
+/// CONNECT) and SOCKS5 proxies are supported; `socks5h` has the proxy
+/// resolve host names.
+pub fn parse_proxy_url(proxy_url: &str) -> std::result::Result<url::Url, String> {
+    let url = url::Url::parse(proxy_url).map_err(|e| format!("invalid URL '{proxy_url}': {e}"))?;
+    match url.scheme() {
+        "http" | "https" | "socks5" | "socks5h" if url.host_str().is_some() => Ok(url),
+        "http" | "https" | "socks5" | "socks5h" => Err(format!("'{proxy_url}' has no host")),
+        "socks4" | "socks4a" => Err(format!(
+            "SOCKS4 proxies are not supported ('{proxy_url}'); use SOCKS5 or an HTTP proxy"
+        )),
+        scheme => Err(format!("unsupported proxy scheme '{scheme}'")),
+    }
+}
+
+/// Parse CIDR ranges, taking a bare address as a single-host range
+fn parse_ip_ranges(ranges: &[String]) -> std::result::Result<Vec<IpNet>, String> {
+    ranges
+        .iter()
+        .map(|range| {
+            range
+                .parse::<IpNet>()
+                .or_else(|_| range.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| format!("invalid IP address or CIDR range '{range}'"))
+        })
+        .collect()
+}
+
 impl Config {
+    /// Turn on `ephemeral`, along with everything it implies for the
+    /// settings that would otherwise persist data
+    pub fn make_ephemeral(&mut self) {
+        self.ephemeral = true;
+        self.dead_letter.persist = false;
+        self.metrics_history.persist = false;
+        self.metrics_persistence.enabled = false;
+    }
+
     pub fn health_check_interval(&self) -> Duration {
         Duration::from_secs(self.health_check.interval_seconds)
     }
@@ -303,6 +1704,77 @@ impl Config {
         Duration::from_millis(self.proxy.request_timeout_ms)
     }
 
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_millis(self.proxy.drain_timeout_ms)
+    }
+
+    /// Interval of the connection reaper, if enabled
+    pub fn connection_reap_interval(&self) -> Option<Duration> {
+        (self.proxy.connection_reap_interval_ms > 0)
+            .then(|| Duration::from_millis(self.proxy.connection_reap_interval_ms))
+    }
+
+    /// Outbound proxy for a server: its own `proxyUrl`, else the global one
+    pub fn server_proxy_url<'a>(&'a self, server: &'a ServerConfig) -> Option<&'a str> {
+        server.proxy_url.as_deref().or(self.proxy_url.as_deref())
+    }
+
+    /// Parsed `proxy.allowedIps`
+    pub fn proxy_allowlist(&self) -> std::result::Result<Vec<IpNet>, String> {
+        parse_ip_ranges(&self.proxy.allowed_ips)
+    }
+
+    /// Parsed `webUi.allowedIps`
+    pub fn web_ui_allowlist(&self) -> std::result::Result<Vec<IpNet>, String> {
+        parse_ip_ranges(&self.web_ui.allowed_ips)
+    }
+
+    /// Group servers into waves that can be stopped in parallel, dependents
+    /// before the servers they depend on. Fails if `dependsOn` has a cycle.
+    pub fn shutdown_order(&self) -> std::result::Result<Vec<Vec<String>>, String> {
+        let mut remaining: Vec<&String> = self.servers.keys().collect();
+        let mut waves = Vec::new();
+
+        while !remaining.is_empty() {
+            let (mut wave, rest): (Vec<&String>, Vec<&String>) =
+                remaining.iter().partition(|name| {
+                    !remaining.iter().any(|other| {
+                        self.servers[*other]
+                            .depends_on
+                            .iter()
+                            .any(|dep| dep == **name)
+                    })
+                });
+
+            if wave.is_empty() {
+                let mut cycle: Vec<&str> = rest.iter().map(|name| name.as_str()).collect();
+                cycle.sort();
+                return Err(format!(
+                    "dependsOn has a cycle between {}",
+                    cycle.join(", ")
+                ));
+            }
+
+            wave.sort();
+            waves.push(wave.into_iter().cloned().collect());
+            remaining = rest;
+        }
+
+        Ok(waves)
+    }
+
+    /// Names of the servers carrying a tag, sorted for stable bulk operations
+    pub fn servers_with_tag(&self, tag: &str) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .servers
+            .iter()
+            .filter(|(_, server)| server.tags.iter().any(|t| t == tag))
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
     /// Get the effective health check configuration for a specific server
     pub fn get_server_health_check(&self, server_name: &str) -> Option<EffectiveHealthCheckConfig> {
         let server = self.servers.get(server_name)?;
@@ -339,6 +1811,32 @@ impl Config {
     }
 }
 
+impl ServerConfig {
+    pub fn initialization_timeout(&self) -> Duration {
+        Duration::from_millis(self.initialization_timeout_ms)
+    }
+
+    /// Most tool calls the server may handle at once, if limited
+    pub fn call_limit(&self) -> Option<usize> {
+        match self.adaptive_concurrency {
+            Some(_) => Some(
+                self.max_concurrent_calls
+                    .unwrap_or(DEFAULT_ADAPTIVE_MAX_CALLS),
+            ),
+            None => self.max_concurrent_calls,
+        }
+    }
+
+    /// Whether the server is reached over the network, by a name that may
+    /// resolve differently over time
+    pub fn is_network(&self) -> bool {
+        matches!(
+            self.transport,
+            TransportConfig::HttpSse { .. } | TransportConfig::WebSocket { .. }
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EffectiveHealthCheckConfig {
     pub interval_seconds: u64,
@@ -0,0 +1,298 @@
+//! Anonymized storage of context tracing data
+//!
+//! [`AnonymizingStorage`] wraps a storage backend and scrubs everything on
+//! its way in: user identifiers are replaced by a keyed hash, and free text
+//! (context summaries, feedback comments) is dropped. What remains is the
+//! structure of the lineage: which sources, in which versions, fed which
+//! responses with what weight, and how those responses were scored. The
+//! same user always hashes the same way under one key, so attribution per
+//! user still works without knowing who the user is.
+//!
+//! Embeddings are derived from content, so none are stored. Each source
+//! keeps the hash of its latest content, which only tells whether the
+//! content changed.
+
+use crate::context::storage::{StorageBackend, StorageError};
+use crate::context::types::{
+    ContextUnit, FeedbackRecord, LineageManifest, Response, ResponseSummary, SourceHead,
+    StorageStats,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use std::sync::Arc;
+
+/// Bytes of the HMAC kept in a hashed identifier
+const HASH_BYTES: usize = 16;
+
+pub struct AnonymizingStorage {
+    inner: Arc<dyn StorageBackend>,
+    key: hmac::Key,
+}
+
+impl AnonymizingStorage {
+    /// Anonymize what is stored in `inner`, hashing identifiers with `key`
+    pub fn new(inner: Arc<dyn StorageBackend>, key: &str) -> Self {
+        Self {
+            inner,
+            key: hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes()),
+        }
+    }
+
+    /// HMAC-SHA256 of `value`, hex encoded and truncated. Without the key
+    /// it can't be reversed by hashing candidate values.
+    pub fn hash(&self, value: &str) -> String {
+        let tag = hmac::sign(&self.key, value.as_bytes());
+        let hex: String = tag.as_ref()[..HASH_BYTES]
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        format!("anon_{hex}")
+    }
+}
+
+#[async_trait]
+impl StorageBackend for AnonymizingStorage {
+    async fn store_context_unit(&self, unit: &ContextUnit) -> Result<(), StorageError> {
+        let unit = ContextUnit {
+            summary: None,
+            ..unit.clone()
+        };
+        self.inner.store_context_unit(&unit).await
+    }
+
+    async fn get_context_unit(&self, id: &str) -> Result<Option<ContextUnit>, StorageError> {
+        self.inner.get_context_unit(id).await
+    }
+
+    async fn update_context_unit(
+        &self,
+        id: &str,
+        aggregate_score: f32,
+        feedback_count: i32,
+    ) -> Result<(), StorageError> {
+        self.inner
+            .update_context_unit(id, aggregate_score, feedback_count)
+            .await
+    }
+
+    async fn store_response(&self, response: &Response) -> Result<(), StorageError> {
+        self.inner.store_response(response).await
+    }
+
+    async fn get_response(&self, id: &str) -> Result<Option<Response>, StorageError> {
+        self.inner.get_response(id).await
+    }
+
+    async fn store_lineage(&self, manifest: &LineageManifest) -> Result<(), StorageError> {
+        let mut manifest = manifest.clone();
+        for node in &mut manifest.context_tree {
+            node.summary = None;
+        }
+        self.inner.store_lineage(&manifest).await
+    }
+
+    async fn query_lineage(
+        &self,
+        response_id: &str,
+    ) -> Result<Option<LineageManifest>, StorageError> {
+        self.inner.query_lineage(response_id).await
+    }
+
+    async fn store_feedback(&self, feedback: &FeedbackRecord) -> Result<(), StorageError> {
+        let feedback = FeedbackRecord {
+            feedback_text: None,
+            user_id: feedback.user_id.as_deref().map(|user| self.hash(user)),
+            ..feedback.clone()
+        };
+        self.inner.store_feedback(&feedback).await
+    }
+
+    async fn get_feedback(&self, response_id: &str) -> Result<Vec<FeedbackRecord>, StorageError> {
+        self.inner.get_feedback(response_id).await
+    }
+
+    async fn get_feedback_range(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<FeedbackRecord>, StorageError> {
+        self.inner.get_feedback_range(start, end).await
+    }
+
+    async fn get_context_version_chain(
+        &self,
+        context_unit_id: &str,
+    ) -> Result<Vec<ContextUnit>, StorageError> {
+        self.inner.get_context_version_chain(context_unit_id).await
+    }
+
+    async fn get_responses_for_context(
+        &self,
+        context_unit_id: &str,
+        min_weight: Option<f32>,
+        start_date: Option<DateTime<Utc>>,
+        end_date: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<Vec<ResponseSummary>, StorageError> {
+        self.inner
+            .get_responses_for_context(context_unit_id, min_weight, start_date, end_date, limit)
+            .await
+    }
+
+    async fn cleanup_old_data(&self, retention_days: u32) -> Result<usize, StorageError> {
+        self.inner.cleanup_old_data(retention_days).await
+    }
+
+    async fn flush(&self) -> Result<(), StorageError> {
+        self.inner.flush().await
+    }
+
+    async fn compact(&self) -> Result<(), StorageError> {
+        self.inner.compact().await
+    }
+
+    async fn checkpoint(&self) -> Result<(), StorageError> {
+        self.inner.checkpoint().await
+    }
+
+    async fn storage_stats(&self) -> Result<Option<StorageStats>, StorageError> {
+        self.inner.storage_stats().await
+    }
+
+    async fn source_head(&self, source: &str) -> Result<Option<SourceHead>, StorageError> {
+        self.inner.source_head(source).await
+    }
+
+    async fn set_source_head(&self, head: &SourceHead) -> Result<(), StorageError> {
+        self.inner.set_source_head(head).await
+    }
+
+    // Embeddings would carry the content into storage. Configurations that
+    // ask for both are rejected by validation; these keep anything else
+    // from reaching the inner backend's embeddings.
+    async fn store_embedding(
+        &self,
+        _context_unit_id: &str,
+        _model: &str,
+        _vector: &[f32],
+    ) -> Result<(), StorageError> {
+        Err(StorageError::InternalError(
+            "anonymized traces keep no embeddings".into(),
+        ))
+    }
+
+    async fn get_embedding(
+        &self,
+        _context_unit_id: &str,
+        _model: &str,
+    ) -> Result<Option<Vec<f32>>, StorageError> {
+        Ok(None)
+    }
+
+    async fn similar_context_units(
+        &self,
+        _model: &str,
+        _vector: &[f32],
+        _limit: usize,
+    ) -> Result<Vec<(ContextUnit, f32)>, StorageError> {
+        Err(StorageError::InternalError(
+            "anonymized traces keep no embeddings".into(),
+        ))
+    }
+
+    async fn cache_stats(&self) -> Option<crate::state::cache_stats::CacheStats> {
+        self.inner.cache_stats().await
+    }
+
+    async fn clear_cache(&self) -> usize {
+        self.inner.clear_cache().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::storage::HybridStorage;
+    use crate::context::tracker::ContextTracker;
+    use crate::context::types::ContextType;
+
+    #[tokio::test]
+    async fn test_stores_structure_without_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let inner: Arc<dyn StorageBackend> = Arc::new(
+            HybridStorage::new(dir.path().join("context.db"), None)
+                .await
+                .unwrap(),
+        );
+        let storage = Arc::new(AnonymizingStorage::new(inner.clone(), "secret"));
+        let tracker = ContextTracker::new(storage.clone());
+
+        let response_id = tracker
+            .start_response("agent".to_string(), "model".to_string())
+            .await
+            .unwrap();
+        let (unit, _) = tracker
+            .observe_context(
+                response_id.clone(),
+                "docs::search",
+                ContextType::External,
+                "private notes",
+                None,
+            )
+            .await
+            .unwrap();
+        tracker
+            .finalize_response(response_id.clone(), None)
+            .await
+            .unwrap();
+        tracker
+            .record_feedback(
+                &response_id,
+                1.0,
+                Some("about me".to_string()),
+                Some("alice@example.com".to_string()),
+            )
+            .await
+            .unwrap();
+
+        let stored = inner.get_context_unit(&unit.id).await.unwrap().unwrap();
+        assert_eq!(stored.source, "docs::search");
+        assert_eq!(stored.summary, None);
+        let lineage = inner.query_lineage(&response_id).await.unwrap().unwrap();
+        assert_eq!(lineage.context_tree[0].id, unit.id);
+        assert_eq!(lineage.context_tree[0].summary, None);
+
+        let feedback = inner.get_feedback(&response_id).await.unwrap();
+        assert_eq!(feedback[0].feedback_text, None);
+        let user = feedback[0].user_id.clone().unwrap();
+        assert_eq!(user, storage.hash("alice@example.com"));
+        assert!(storage
+            .store_embedding(&unit.id, "hash", &[0.5, 0.5])
+            .await
+            .is_err());
+        assert_eq!(inner.get_embedding(&unit.id, "hash").await.unwrap(), None);
+        assert_ne!(
+            user,
+            AnonymizingStorage::new(inner, "other").hash("alice@example.com")
+        );
+
+        // Unchanged content is still recognized without its summary
+        let response_id = tracker
+            .start_response("agent".to_string(), "model".to_string())
+            .await
+            .unwrap();
+        let (again, change) = tracker
+            .observe_context(
+                response_id,
+                "docs::search",
+                ContextType::External,
+                "private notes",
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(again.id, unit.id);
+        assert_eq!(change, crate::context::evolution::ContentChange::Unchanged);
+    }
+}
@@ -0,0 +1,187 @@
+//! Bounded in-memory caches in front of the SQLite tables of
+//! [`HybridStorage`](super::storage::HybridStorage)
+//!
+//! Entries are kept in a linked hash map whose front is the next to go, so
+//! eviction never scans or sorts: inserting moves an entry to the back, and
+//! under [`EvictionStrategy::StrictLRU`] so does reading it.
+
+use chrono::{DateTime, Utc};
+use hashlink::LinkedHashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Which entry a full cache drops
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum EvictionStrategy {
+    /// The least recently stored, after any that outlived the TTL
+    #[default]
+    #[serde(rename = "timeBasedLru")]
+    TimeBasedLRU,
+    /// The least recently stored or read
+    #[serde(rename = "strictLru")]
+    StrictLRU,
+}
+
+/// The eviction strategy of each of the storage's caches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvictionStrategies {
+    #[serde(default)]
+    pub responses: EvictionStrategy,
+    #[serde(default)]
+    pub contexts: EvictionStrategy,
+    #[serde(default)]
+    pub lineage: EvictionStrategy,
+}
+
+struct Entry<T> {
+    data: T,
+    inserted_at: DateTime<Utc>,
+}
+
+pub struct MemoryCache<T> {
+    entries: Mutex<LinkedHashMap<String, Entry<T>>>,
+    strategy: EvictionStrategy,
+    max_entries: usize,
+    ttl: chrono::Duration,
+}
+
+impl<T: Clone> MemoryCache<T> {
+    pub fn new(strategy: EvictionStrategy, max_entries: usize, ttl_seconds: i64) -> Self {
+        Self {
+            entries: Mutex::new(LinkedHashMap::new()),
+            strategy,
+            max_entries,
+            ttl: chrono::Duration::seconds(ttl_seconds),
+        }
+    }
+
+    fn expired(&self, entry: &Entry<T>, now: DateTime<Utc>) -> bool {
+        now.signed_duration_since(entry.inserted_at) >= self.ttl
+    }
+
+    /// The cached value, unless there is none or it outlived the TTL, in
+    /// which case it is dropped
+    pub fn get(&self, key: &str) -> Option<T> {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Utc::now();
+        let entry = match self.strategy {
+            EvictionStrategy::StrictLRU => entries.to_back(key)?,
+            EvictionStrategy::TimeBasedLRU => entries.get(key)?,
+        };
+        if self.expired(entry, now) {
+            entries.remove(key);
+            return None;
+        }
+        Some(entry.data.clone())
+    }
+
+    /// Cache a value, evicting entries while over the size limit. Returns
+    /// how many were evicted.
+    pub fn insert(&self, key: String, data: T) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Utc::now();
+        entries.insert(
+            key,
+            Entry {
+                data,
+                inserted_at: now,
+            },
+        );
+        if entries.len() <= self.max_entries {
+            return 0;
+        }
+
+        let mut evicted = 0;
+        if self.strategy == EvictionStrategy::TimeBasedLRU {
+            // In insertion order, expired entries are all at the front;
+            // the one just stored stays whatever the TTL
+            while entries.len() > 1
+                && entries
+                    .front()
+                    .is_some_and(|(_, entry)| self.expired(entry, now))
+            {
+                entries.pop_front();
+                evicted += 1;
+            }
+        }
+        while entries.len() > self.max_entries {
+            entries.pop_front();
+            evicted += 1;
+        }
+        evicted
+    }
+
+    /// Change a cached value in place, if there is one, without counting as
+    /// a use
+    pub fn update(&self, key: &str, f: impl FnOnce(&mut T)) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(key) {
+            f(&mut entry.data);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every entry, returning how many there were
+    pub fn clear(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let count = entries.len();
+        entries.clear();
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(strategy: EvictionStrategy) -> MemoryCache<u32> {
+        let cache = MemoryCache::new(strategy, 3, 3600);
+        for (i, key) in ["a", "b", "c"].into_iter().enumerate() {
+            assert_eq!(cache.insert(key.to_string(), i as u32), 0);
+        }
+        cache
+    }
+
+    #[test]
+    fn test_strategies_pick_different_victims() {
+        // Reading "a" keeps it under strict LRU only
+        let strict = filled(EvictionStrategy::StrictLRU);
+        assert_eq!(strict.get("a"), Some(0));
+        assert_eq!(strict.insert("d".to_string(), 3), 1);
+        assert_eq!(strict.get("a"), Some(0));
+        assert_eq!(strict.get("b"), None);
+
+        let time_based = filled(EvictionStrategy::TimeBasedLRU);
+        assert_eq!(time_based.get("a"), Some(0));
+        assert_eq!(time_based.insert("d".to_string(), 3), 1);
+        assert_eq!(time_based.get("a"), None);
+        assert_eq!(time_based.len(), 3);
+
+        // Storing again counts as new
+        time_based.insert("b".to_string(), 10);
+        time_based.insert("e".to_string(), 4);
+        assert_eq!(time_based.get("b"), Some(10));
+        assert_eq!(time_based.get("c"), None);
+
+        time_based.update("b", |value| *value += 1);
+        assert_eq!(time_based.get("b"), Some(11));
+        assert_eq!(time_based.clear(), 3);
+    }
+
+    #[test]
+    fn test_expired_entries_go_first() {
+        let cache = MemoryCache::new(EvictionStrategy::TimeBasedLRU, 2, 0);
+        cache.insert("a".to_string(), 1);
+        cache.insert("b".to_string(), 2);
+        // With no TTL everything older than now has expired
+        assert_eq!(cache.insert("c".to_string(), 3), 2);
+        assert_eq!(cache.get("a"), None);
+    }
+}
@@ -0,0 +1,198 @@
+//! Embeddings of context units, for finding contexts similar to a text or
+//! to another context
+//!
+//! A provider turns the text of a context unit (its summary, or else its
+//! source) into a vector. The tracker embeds units in the background as
+//! they are stored, and the storage backend keeps one vector per unit and
+//! model, so switching models never compares vectors of different spaces.
+
+use crate::config::EmbeddingConfig;
+use crate::context::types::ContextUnit;
+use async_trait::async_trait;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Name stored with each vector; vectors of different models are never
+    /// compared
+    fn model(&self) -> &str;
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, String>;
+}
+
+/// A context unit found by similarity search
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarContext {
+    pub context_unit: ContextUnit,
+    /// Cosine similarity to the query, from -1.0 to 1.0
+    pub similarity: f32,
+}
+
+/// The provider `config` describes
+pub fn provider(config: &EmbeddingConfig) -> Result<Arc<dyn EmbeddingProvider>, String> {
+    match config {
+        EmbeddingConfig::Hash { dimensions } => Ok(Arc::new(HashEmbedder::new(*dimensions))),
+        EmbeddingConfig::Api {
+            url,
+            model,
+            api_key,
+            timeout_ms,
+        } => {
+            let client = reqwest::Client::builder()
+                .timeout(Duration::from_millis(*timeout_ms))
+                .build()
+                .map_err(|e| e.to_string())?;
+            Ok(Arc::new(ApiEmbedder {
+                client,
+                url: format!("{}/embeddings", url.trim_end_matches('/')),
+                model: model.clone(),
+                api_key: api_key.clone(),
+            }))
+        }
+    }
+}
+
+/// The text a context unit is embedded from, if it has any
+pub fn embedding_text(unit: &ContextUnit) -> Option<&str> {
+    unit.summary
+        .as_deref()
+        .filter(|summary| !summary.trim().is_empty())
+        .or(Some(unit.source.as_str()))
+        .filter(|text| !text.trim().is_empty())
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// Hashes the words of a text into a fixed number of buckets. Needs no
+/// model or network; texts come out similar when they share words, not
+/// when they mean the same.
+pub struct HashEmbedder {
+    dimensions: usize,
+    model: String,
+}
+
+impl HashEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions,
+            model: format!("hash-{dimensions}"),
+        }
+    }
+
+    fn vector(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0; self.dimensions];
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            let hash = super::stable_hash(word.to_lowercase().as_bytes());
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[(hash % self.dimensions as u64) as usize] += sign;
+        }
+        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|v| *v /= norm);
+        }
+        vector
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for HashEmbedder {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        Ok(self.vector(text))
+    }
+}
+
+/// An OpenAI-compatible `/embeddings` endpoint, such as OpenAI's own or a
+/// local model served by Ollama or llama.cpp
+pub struct ApiEmbedder {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+#[async_trait]
+impl EmbeddingProvider for ApiEmbedder {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        let mut request = self.client.post(&self.url).json(&serde_json::json!({
+            "model": self.model,
+            "input": text,
+        }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("embedding request to {} failed: {e}", self.url))?;
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("invalid embedding response from {}: {e}", self.url))?;
+        body["data"][0]["embedding"]
+            .as_array()
+            .and_then(|values| {
+                values
+                    .iter()
+                    .map(|v| v.as_f64().map(|v| v as f32))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .filter(|vector| !vector.is_empty())
+            .ok_or_else(|| format!("no embedding in the response from {}", self.url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_hash_embeddings_rank_shared_words() {
+        let embedder = HashEmbedder::new(256);
+        let query = embedder.embed("rust async runtime").await.unwrap();
+        let close = embedder
+            .embed("The Rust async runtime, tokio")
+            .await
+            .unwrap();
+        let far = embedder.embed("baking sourdough bread").await.unwrap();
+        assert_eq!(embedder.model(), "hash-256");
+        assert!(cosine_similarity(&query, &close) > 0.5);
+        assert!(cosine_similarity(&query, &close) > cosine_similarity(&query, &far));
+        assert_eq!(cosine_similarity(&query, &[1.0]), 0.0);
+
+        // No words, no direction
+        assert!(embedder
+            .embed("  --  ")
+            .await
+            .unwrap()
+            .iter()
+            .all(|v| *v == 0.0));
+    }
+}
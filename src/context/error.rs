@@ -74,12 +74,9 @@ impl From<serde_json::Error> for ContextError {
     }
 }
 
-// Conversion to warp::Rejection for web layer integration
-impl warp::reject::Reject for ContextError {}
-
 /// Helper to convert ContextError to appropriate HTTP status
-pub fn context_error_to_status(err: &ContextError) -> warp::http::StatusCode {
-    use warp::http::StatusCode;
+pub fn context_error_to_status(err: &ContextError) -> axum::http::StatusCode {
+    use axum::http::StatusCode;
 
     match err {
         ContextError::NotFound(_) | ContextError::SessionNotFound(_) => StatusCode::NOT_FOUND,
@@ -115,7 +112,7 @@ mod tests {
 
     #[test]
     fn test_status_codes() {
-        use warp::http::StatusCode;
+        use axum::http::StatusCode;
 
         assert_eq!(
             context_error_to_status(&ContextError::NotFound("test".to_string())),
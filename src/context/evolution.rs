@@ -3,13 +3,103 @@
 //! This module provides functionality for tracking how context units evolve over time
 //! through versioning. Each context unit can have multiple versions linked by
 //! `previous_version_id`, forming a version chain.
+//!
+//! Versions are made automatically by [`EvolutionService::observe`]: the
+//! context from a source (e.g. a server's tool) gets a new version when its
+//! content changes materially, which here means other than in whitespace.
 
 use crate::context::storage::StorageBackend;
-use crate::context::types::{ContextUnit, ContextVersion, EvolutionHistory};
+use crate::context::types::{
+    ContextType, ContextUnit, ContextVersion, DiffLine, DiffOp, EvolutionHistory, SourceHead,
+    VersionDiff,
+};
 use chrono::Utc;
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// Longest summary a context unit may have, in bytes
+const MAX_SUMMARY_BYTES: usize = 500;
+
+/// How observed content relates to what was seen before from its source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentChange {
+    /// The source had not been seen
+    New,
+    /// Same content as the current version
+    Unchanged,
+    /// Different content; a new version was made
+    Changed,
+}
+
+/// Hash of `content` with runs of whitespace made single spaces, so only
+/// material changes give a different hash
+pub fn content_hash(content: &str) -> String {
+    let normalized = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("{:016x}", super::stable_hash(normalized.as_bytes()))
+}
+
+/// The start of `content`, cut at a character boundary to fit a summary
+fn excerpt(content: &str) -> String {
+    let content = content.trim();
+    if content.len() <= MAX_SUMMARY_BYTES {
+        return content.to_string();
+    }
+    let end = (0..=MAX_SUMMARY_BYTES - 3)
+        .rev()
+        .find(|&i| content.is_char_boundary(i))
+        .unwrap_or(0);
+    format!("{}...", &content[..end])
+}
+
+/// Line diff of two texts, by longest common subsequence
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // common[i][j]: length of the LCS of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let line = |op, text: &str| DiffLine {
+        op,
+        text: text.to_string(),
+    };
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(line(DiffOp::Unchanged, old[i]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            lines.push(line(DiffOp::Removed, old[i]));
+            i += 1;
+        } else {
+            lines.push(line(DiffOp::Added, new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|text| line(DiffOp::Removed, text)));
+    lines.extend(new[j..].iter().map(|text| line(DiffOp::Added, text)));
+    lines
+}
+
+fn context_version(unit: &ContextUnit) -> ContextVersion {
+    ContextVersion {
+        id: unit.id.clone(),
+        version: unit.version,
+        timestamp: unit.timestamp,
+        summary: unit.summary.clone(),
+    }
+}
+
 /// Service for managing context evolution and versioning
 pub struct EvolutionService {
     storage: Arc<dyn StorageBackend>,
@@ -78,6 +168,86 @@ impl EvolutionService {
         Ok(new_version)
     }
 
+    /// Find the context unit for content seen from `source`, making a new
+    /// version when the content changed since the current one
+    ///
+    /// Callers observing the same source concurrently must serialize, or
+    /// both may make a version from the same predecessor.
+    ///
+    /// # Arguments
+    /// * `source` - Where the content came from, e.g. `server::tool`
+    /// * `context_type` - Type of a context unit made for a new source
+    /// * `content` - The content; its start becomes the unit's summary
+    ///
+    /// # Returns
+    /// * `Ok((ContextUnit, ContentChange))` with the current version
+    /// * `Err(String)` if storage fails
+    pub async fn observe(
+        &self,
+        source: &str,
+        context_type: ContextType,
+        content: &str,
+    ) -> Result<(ContextUnit, ContentChange), String> {
+        let hash = content_hash(content);
+        let head = self
+            .storage
+            .source_head(source)
+            .await
+            .map_err(|e| format!("Failed to get current version: {e}"))?;
+        let current = match &head {
+            Some(head) => self
+                .storage
+                .get_context_unit(&head.context_unit_id)
+                .await
+                .map_err(|e| format!("Failed to get current version: {e}"))?,
+            None => None,
+        };
+
+        let (unit, change) = match (current, head) {
+            (Some(unit), Some(head)) if head.content_hash == hash => {
+                return Ok((unit, ContentChange::Unchanged));
+            }
+            (Some(unit), Some(_)) => {
+                let unit = self
+                    .create_context_version(&unit, Some(excerpt(content)), None)
+                    .await?;
+                (unit, ContentChange::Changed)
+            }
+            // A source whose current version was cleaned up starts over
+            _ => {
+                let unit = ContextUnit {
+                    id: format!("ctx_{}", Uuid::new_v4()),
+                    r#type: context_type,
+                    source: source.to_string(),
+                    timestamp: Utc::now(),
+                    embedding_id: None,
+                    summary: Some(excerpt(content)),
+                    version: 1,
+                    previous_version_id: None,
+                    aggregate_score: 0.0,
+                    feedback_count: 0,
+                };
+                unit.validate()
+                    .map_err(|e| format!("Invalid context unit: {e}"))?;
+                self.storage
+                    .store_context_unit(&unit)
+                    .await
+                    .map_err(|e| format!("Failed to store context unit: {e}"))?;
+                (unit, ContentChange::New)
+            }
+        };
+
+        self.storage
+            .set_source_head(&SourceHead {
+                source: source.to_string(),
+                context_unit_id: unit.id.clone(),
+                content_hash: hash,
+            })
+            .await
+            .map_err(|e| format!("Failed to update current version: {e}"))?;
+        Ok((unit, change))
+    }
+
     /// Get complete version history for a context unit
     ///
     /// Traverses the version chain from the given context unit back to the first version.
@@ -163,6 +333,48 @@ impl EvolutionService {
         Ok((version_a, version_b))
     }
 
+    /// Diff a context unit's summary against an earlier version's
+    ///
+    /// # Arguments
+    /// * `context_unit_id` - The later version
+    /// * `from_version` - Version number to compare with (default: the one
+    ///   before)
+    ///
+    /// # Returns
+    /// * `Ok(VersionDiff)` with the line diff of the two summaries
+    /// * `Err(String)` if the unit or the version is not in the chain
+    pub async fn diff_versions(
+        &self,
+        context_unit_id: &str,
+        from_version: Option<i32>,
+    ) -> Result<VersionDiff, String> {
+        let chain = self
+            .storage
+            .get_context_version_chain(context_unit_id)
+            .await
+            .map_err(|e| format!("Failed to get version chain: {e}"))?;
+        let to = chain
+            .iter()
+            .find(|unit| unit.id == context_unit_id)
+            .ok_or_else(|| format!("Context unit {context_unit_id} not found"))?;
+        let from_version = from_version.unwrap_or(to.version - 1);
+        let from = chain
+            .iter()
+            .find(|unit| unit.version == from_version && from_version < to.version)
+            .ok_or_else(|| {
+                format!("Context unit {context_unit_id} has no earlier version {from_version}")
+            })?;
+
+        Ok(VersionDiff {
+            from: context_version(from),
+            to: context_version(to),
+            lines: diff_lines(
+                from.summary.as_deref().unwrap_or_default(),
+                to.summary.as_deref().unwrap_or_default(),
+            ),
+        })
+    }
+
     /// Check if a context unit is below deprecation threshold
     ///
     /// # Arguments
@@ -219,6 +431,55 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_observe_versions_changed_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageBackend> = Arc::new(
+            crate::context::storage::HybridStorage::new(dir.path().join("context.db"), None)
+                .await
+                .unwrap(),
+        );
+        let evolution = EvolutionService::new(storage);
+        let source = "github::get_issue";
+
+        let (v1, change) = evolution
+            .observe(source, ContextType::External, "title: Crash\nstate: open")
+            .await
+            .unwrap();
+        assert_eq!(change, ContentChange::New);
+
+        // Whitespace alone is no change
+        let (same, change) = evolution
+            .observe(
+                source,
+                ContextType::External,
+                "title:  Crash\n\nstate: open ",
+            )
+            .await
+            .unwrap();
+        assert_eq!(change, ContentChange::Unchanged);
+        assert_eq!(same.id, v1.id);
+
+        let (v2, change) = evolution
+            .observe(source, ContextType::External, "title: Crash\nstate: closed")
+            .await
+            .unwrap();
+        assert_eq!(change, ContentChange::Changed);
+        assert_eq!(v2.version, 2);
+        assert_eq!(v2.previous_version_id.as_deref(), Some(v1.id.as_str()));
+
+        let diff = evolution.diff_versions(&v2.id, None).await.unwrap();
+        assert_eq!(diff.from.id, v1.id);
+        assert_eq!(
+            diff.lines.iter().map(|l| l.op).collect::<Vec<_>>(),
+            [DiffOp::Unchanged, DiffOp::Removed, DiffOp::Added]
+        );
+        assert_eq!(diff.lines[2].text, "state: closed");
+        assert!(evolution.diff_versions(&v1.id, None).await.is_err());
+
+        assert!(excerpt(&"é".repeat(400)).len() <= MAX_SUMMARY_BYTES);
+    }
+
     #[test]
     fn test_version_validation() {
         let v1 = create_test_context("ctx_v1", 1, None);
@@ -0,0 +1,102 @@
+//! Scheduled upkeep of the tracing database
+//!
+//! Retention deletes free pages without shrinking the file, and a busy
+//! write-ahead log only shrinks when checkpointed. This runs compaction at
+//! the times `compactionCron` matches and a checkpoint every
+//! `checkpointIntervalSeconds`, both configured under
+//! `contextTracing.maintenance`.
+
+use crate::config::TraceMaintenanceConfig;
+use crate::context::storage::StorageBackend;
+use crate::server::schedule::CronSchedule;
+use chrono::{Local, NaiveDateTime, Timelike};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Run maintenance on `storage` until shutdown
+pub async fn run(
+    storage: Arc<dyn StorageBackend>,
+    config: TraceMaintenanceConfig,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    let compaction = config.compaction_cron.as_deref().and_then(|expression| {
+        CronSchedule::parse(expression)
+            .map_err(|e| tracing::error!("Not compacting the tracing database: {}", e))
+            .ok()
+    });
+    let mut checkpoints = (config.checkpoint_interval_seconds > 0).then(|| {
+        let period = Duration::from_secs(config.checkpoint_interval_seconds);
+        tokio::time::interval_at(tokio::time::Instant::now() + period, period)
+    });
+    // A wake-up just before the minute turns must not compact twice
+    let mut last_compacted: Option<NaiveDateTime> = None;
+
+    loop {
+        let checkpoint_due = async {
+            match &mut checkpoints {
+                Some(interval) => {
+                    interval.tick().await;
+                }
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = next_minute(), if compaction.is_some() => {
+                let minute = Local::now()
+                    .naive_local()
+                    .with_second(0)
+                    .and_then(|at| at.with_nanosecond(0));
+                let due = compaction
+                    .as_ref()
+                    .zip(minute)
+                    .is_some_and(|(schedule, minute)| schedule.matches(&minute));
+                if due && last_compacted != minute {
+                    last_compacted = minute;
+                    tracing::info!("Compacting the tracing database");
+                    match storage.compact().await {
+                        Ok(()) => tracing::info!("Tracing database compacted"),
+                        Err(e) => tracing::error!("Failed to compact the tracing database: {}", e),
+                    }
+                }
+            }
+            _ = checkpoint_due => {
+                if let Err(e) = storage.checkpoint().await {
+                    tracing::warn!("Failed to checkpoint the tracing database: {}", e);
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+/// Delete tracing data older than `retention_days` once a day until
+/// shutdown, starting right away
+pub async fn run_retention(
+    storage: Arc<dyn StorageBackend>,
+    retention_days: u32,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(86400));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown_rx.recv() => break,
+        }
+        tracing::info!(
+            "Running context tracing retention policy ({}d)",
+            retention_days
+        );
+        match storage.cleanup_old_data(retention_days).await {
+            Ok(deleted) => tracing::info!("Retention policy deleted {} old records", deleted),
+            Err(e) => tracing::error!("Retention policy failed: {}", e),
+        }
+    }
+}
+
+/// Sleep until the next minute starts
+async fn next_minute() {
+    let now = Local::now();
+    let into_minute = Duration::from_secs(now.second().into())
+        + Duration::from_nanos(now.nanosecond().min(999_999_999).into());
+    tokio::time::sleep(Duration::from_secs(60).saturating_sub(into_minute)).await;
+}
@@ -0,0 +1,232 @@
+//! Versioned migrations of the context tracing schema
+//!
+//! Each migration is an SQL file under `migrations/`, named
+//! `V<version>__<name>.sql` and embedded in the binary. Applied versions are
+//! recorded in `schema_migrations`, and each pending one runs in its own
+//! transaction, so an upgrade that fails leaves the database at the last
+//! version that succeeded.
+//!
+//! Databases created before migrations existed have the tables but no
+//! history; they are baselined at the version their schema matches.
+
+use super::storage::StorageError;
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// Every migration, in version order. Released migrations must not change:
+/// add a new one instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: include_str!("migrations/V1__initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "response_sessions",
+        sql: include_str!("migrations/V2__response_sessions.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "context_embeddings",
+        sql: include_str!("migrations/V3__context_embeddings.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "context_sources",
+        sql: include_str!("migrations/V4__context_sources.sql"),
+    },
+];
+
+/// The version this build migrates databases to
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+fn has_table(conn: &Connection, table: &str) -> Result<bool, StorageError> {
+    Ok(conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [table],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool, StorageError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(columns.iter().any(|name| name == column))
+}
+
+/// The version an unversioned database is at: the last migration whose
+/// changes it already has. Version 1 only creates missing tables, so it is
+/// safe to run again on a partial schema.
+fn baseline(conn: &Connection) -> Result<u32, StorageError> {
+    if has_table(conn, "responses")? && has_column(conn, "responses", "session_id")? {
+        Ok(2)
+    } else {
+        Ok(0)
+    }
+}
+
+/// Versions applied to the database, with the name each was applied as.
+/// For a database without history these are its baseline, not yet written.
+fn applied(conn: &Connection) -> Result<BTreeMap<u32, String>, StorageError> {
+    if !has_table(conn, "schema_migrations")? {
+        let baseline = baseline(conn)?;
+        return Ok(MIGRATIONS
+            .iter()
+            .take_while(|m| m.version <= baseline)
+            .map(|m| (m.version, m.name.to_string()))
+            .collect());
+    }
+    let mut stmt = conn.prepare("SELECT version, name FROM schema_migrations")?;
+    let applied = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    Ok(applied)
+}
+
+/// Migrations the database still needs, in order. Fails when it was
+/// migrated by a newer release, or a version was applied under another
+/// name, since the schema is then not what this build expects.
+pub fn pending(conn: &Connection) -> Result<Vec<&'static Migration>, StorageError> {
+    let applied = applied(conn)?;
+    let latest = latest_version();
+    if let Some(&version) = applied.keys().next_back().filter(|&&v| v > latest) {
+        return Err(StorageError::MigrationError(format!(
+            "database is at schema version {version}, newer than the {latest} this release supports"
+        )));
+    }
+    for migration in MIGRATIONS {
+        match applied.get(&migration.version) {
+            Some(name) if name != migration.name => {
+                return Err(StorageError::MigrationError(format!(
+                    "schema version {} was applied as '{}', but this release knows it as '{}'",
+                    migration.version, name, migration.name
+                )));
+            }
+            _ => {}
+        }
+    }
+    Ok(MIGRATIONS
+        .iter()
+        .filter(|m| !applied.contains_key(&m.version))
+        .collect())
+}
+
+/// Bring the database up to [`latest_version`], returning the migrations
+/// that ran
+pub fn migrate(conn: &Connection) -> Result<Vec<&'static Migration>, StorageError> {
+    let pending = pending(conn)?;
+    if !has_table(conn, "schema_migrations")? {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "CREATE TABLE schema_migrations (
+                version INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                applied_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        for migration in MIGRATIONS.iter().filter(|m| !pending.contains(m)) {
+            record(&tx, migration)?;
+        }
+        tx.commit()?;
+    }
+
+    for migration in &pending {
+        tracing::info!(
+            "Migrating context tracing schema to version {} ({})",
+            migration.version,
+            migration.name
+        );
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(migration.sql).map_err(|e| {
+            StorageError::MigrationError(format!(
+                "version {} ({}) failed: {e}",
+                migration.version, migration.name
+            ))
+        })?;
+        record(&tx, migration)?;
+        tx.commit()?;
+    }
+    Ok(pending)
+}
+
+fn record(conn: &Connection, migration: &Migration) -> Result<(), StorageError> {
+    conn.execute(
+        "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?1, ?2, ?3)",
+        rusqlite::params![
+            migration.version,
+            migration.name,
+            chrono::Utc::now().to_rfc3339()
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_fresh_and_legacy_databases() {
+        let fresh = Connection::open_in_memory().unwrap();
+        assert_eq!(pending(&fresh).unwrap().len(), MIGRATIONS.len());
+        assert_eq!(migrate(&fresh).unwrap().len(), MIGRATIONS.len());
+        assert!(pending(&fresh).unwrap().is_empty());
+        assert!(migrate(&fresh).unwrap().is_empty());
+        assert!(has_column(&fresh, "responses", "session_id").unwrap());
+
+        // Made by the old ad-hoc schema code: all tables, no history
+        let legacy = Connection::open_in_memory().unwrap();
+        legacy.execute_batch(MIGRATIONS[0].sql).unwrap();
+        legacy.execute_batch(MIGRATIONS[1].sql).unwrap();
+        assert_eq!(
+            pending(&legacy).unwrap(),
+            MIGRATIONS[2..].iter().collect::<Vec<_>>()
+        );
+        migrate(&legacy).unwrap();
+        let recorded: u32 = legacy
+            .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(recorded as usize, MIGRATIONS.len());
+
+        // Older still, without sessions on responses
+        let older = Connection::open_in_memory().unwrap();
+        older.execute_batch(MIGRATIONS[0].sql).unwrap();
+        assert_eq!(
+            pending(&older).unwrap(),
+            MIGRATIONS.iter().collect::<Vec<_>>()
+        );
+        migrate(&older).unwrap();
+        assert!(has_column(&older, "responses", "session_id").unwrap());
+    }
+
+    #[test]
+    fn test_refuses_a_newer_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO schema_migrations (version, name, applied_at) VALUES (99, 'future', '')",
+            [],
+        )
+        .unwrap();
+        let error = migrate(&conn).unwrap_err().to_string();
+        assert!(error.contains("version 99"), "{error}");
+    }
+}
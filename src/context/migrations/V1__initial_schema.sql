@@ -0,0 +1,111 @@
+-- context_units table
+CREATE TABLE IF NOT EXISTS context_units (
+    id TEXT PRIMARY KEY,
+    type TEXT NOT NULL,
+    source TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    embedding_id TEXT,
+    summary TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    previous_version_id TEXT,
+    aggregate_score REAL NOT NULL DEFAULT 0.0,
+    feedback_count INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (previous_version_id) REFERENCES context_units(id)
+);
+
+-- Indexes for context_units
+CREATE INDEX IF NOT EXISTS idx_context_units_type ON context_units(type);
+CREATE INDEX IF NOT EXISTS idx_context_units_source ON context_units(source);
+CREATE INDEX IF NOT EXISTS idx_context_units_timestamp ON context_units(timestamp);
+CREATE INDEX IF NOT EXISTS idx_context_units_embedding ON context_units(embedding_id);
+
+-- Responses table
+CREATE TABLE IF NOT EXISTS responses (
+    id TEXT PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    agent TEXT NOT NULL,
+    model TEXT NOT NULL,
+    token_count INTEGER
+);
+
+-- Indexes for responses
+CREATE INDEX IF NOT EXISTS idx_responses_timestamp ON responses(timestamp);
+CREATE INDEX IF NOT EXISTS idx_responses_agent ON responses(agent);
+
+-- Lineage junction table (response -> context_unit mapping)
+CREATE TABLE IF NOT EXISTS lineage (
+    response_id TEXT NOT NULL,
+    context_unit_id TEXT NOT NULL,
+    weight REAL NOT NULL,
+    PRIMARY KEY (response_id, context_unit_id),
+    FOREIGN KEY (response_id) REFERENCES responses(id) ON DELETE CASCADE,
+    FOREIGN KEY (context_unit_id) REFERENCES context_units(id) ON DELETE CASCADE
+);
+
+-- Indexes for lineage
+CREATE INDEX IF NOT EXISTS idx_lineage_context ON lineage(context_unit_id);
+CREATE INDEX IF NOT EXISTS idx_lineage_response ON lineage(response_id);
+
+-- lineage_manifests table for complete provenance trees
+CREATE TABLE IF NOT EXISTS lineage_manifests (
+    response_id TEXT PRIMARY KEY,
+    manifest_json TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    FOREIGN KEY (response_id) REFERENCES responses(id) ON DELETE CASCADE
+);
+
+-- Feedback table
+CREATE TABLE IF NOT EXISTS feedback (
+    id TEXT PRIMARY KEY,
+    response_id TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    score REAL NOT NULL,
+    feedback_text TEXT,
+    user_id TEXT,
+    FOREIGN KEY (response_id) REFERENCES responses(id) ON DELETE CASCADE
+);
+
+-- Indexes for feedback
+CREATE INDEX IF NOT EXISTS idx_feedback_response ON feedback(response_id);
+CREATE INDEX IF NOT EXISTS idx_feedback_timestamp ON feedback(timestamp);
+CREATE INDEX IF NOT EXISTS idx_feedback_user ON feedback(user_id);
+
+-- Sessions table
+CREATE TABLE IF NOT EXISTS sessions (
+    id TEXT PRIMARY KEY,
+    started_at TEXT NOT NULL,
+    ended_at TEXT,
+    user_query TEXT,
+    agent TEXT NOT NULL,
+    metadata TEXT,
+    session_score REAL
+);
+
+-- Indexes for sessions
+CREATE INDEX IF NOT EXISTS idx_sessions_started ON sessions(started_at);
+CREATE INDEX IF NOT EXISTS idx_sessions_agent ON sessions(agent);
+
+-- Tasks table
+CREATE TABLE IF NOT EXISTS tasks (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    description TEXT NOT NULL,
+    status TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    completed_at TEXT,
+    task_score REAL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- Indexes for tasks
+CREATE INDEX IF NOT EXISTS idx_tasks_session ON tasks(session_id);
+CREATE INDEX IF NOT EXISTS idx_tasks_status ON tasks(status);
+
+-- task_responses junction table
+CREATE TABLE IF NOT EXISTS task_responses (
+    task_id TEXT NOT NULL,
+    response_id TEXT NOT NULL,
+    PRIMARY KEY (task_id, response_id),
+    FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE,
+    FOREIGN KEY (response_id) REFERENCES responses(id) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- Responses belong to the session that produced them
+ALTER TABLE responses ADD COLUMN session_id TEXT;
+CREATE INDEX IF NOT EXISTS idx_responses_session ON responses(session_id);
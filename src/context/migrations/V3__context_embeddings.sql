@@ -0,0 +1,12 @@
+-- Embedding vectors of context units, one per unit and model, as
+-- little-endian f32s
+CREATE TABLE IF NOT EXISTS context_embeddings (
+    context_unit_id TEXT NOT NULL,
+    model TEXT NOT NULL,
+    dimensions INTEGER NOT NULL,
+    vector BLOB NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (context_unit_id, model)
+);
+
+CREATE INDEX IF NOT EXISTS idx_context_embeddings_model ON context_embeddings(model);
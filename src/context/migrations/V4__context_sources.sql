@@ -0,0 +1,8 @@
+-- The current version of the context from each source, with a hash of
+-- the content it was made from
+CREATE TABLE IF NOT EXISTS context_sources (
+    source TEXT PRIMARY KEY,
+    context_unit_id TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
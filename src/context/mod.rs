@@ -17,6 +17,10 @@
 //! - [`tracker`]: Runtime hooks for capturing context during response generation
 //! - [`query`]: Query interface for lineage data retrieval
 //! - [`evolution`]: Context versioning and evolution tracking
+//! - [`embedding`]: Embeddings of context units for similarity search
+//! - [`migrations`]: Versioned migrations of the SQLite schema
+//! - [`anonymize`]: Storage that keeps lineage structure but no content
+//! - [`maintenance`]: Scheduled compaction and checkpointing of the database
 //!
 //! # Example Usage
 //!
@@ -60,9 +64,22 @@
 //! # }
 //! ```
 
+pub mod anonymize;
+pub mod cache;
+pub mod embedding;
 pub mod error;
 pub mod evolution;
+pub mod maintenance;
+pub mod migrations;
 pub mod query;
 pub mod storage;
 pub mod tracker;
 pub mod types;
+
+/// FNV-1a hash of `bytes`. Unlike `std`'s hashers it is the same across
+/// builds and runs, so it can be stored.
+pub(crate) fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
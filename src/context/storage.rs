@@ -1,26 +1,33 @@
-//! Hybrid storage backend with DashMap + SQLite
+//! Hybrid storage backend with in-memory LRU caches + SQLite
 //!
 //! This module provides a persistent storage layer for context tracing data using:
-//! - **DashMap**: In-memory cache for hot data (recent responses, high-traffic lookups)
+//! - **LRU caches**: In-memory cache for hot data (recent responses, high-traffic lookups)
 //! - **SQLite**: Persistent storage for complete lineage history
 //!
 //! # Architecture
 //!
 //! ```text
-//! Write Path:  Request → DashMap (sync) → SQLite (async)
-//! Read Path:   Request → DashMap cache → SQLite fallback
+//! Write Path:  Request → LRU cache (sync) → SQLite (async)
+//! Read Path:   Request → LRU cache → SQLite fallback
 //! ```
 //!
 //! # Concurrency
 //!
-//! - DashMap provides lock-free concurrent access for cache operations
-//! - SQLite uses WAL mode for concurrent reads with single-writer model
-//! - Arc wrapper enables safe sharing across async tasks
+//! - Each cache is a linked hash map behind a short-held mutex, with O(1)
+//!   lookups and evictions
+//! - SQLite uses WAL mode: one pooled writer connection and a pool of
+//!   read-only connections, so reads run alongside writes and each other
+//! - Queries run on the blocking thread pool with statements prepared once
+//!   per connection and cached
+//! - [`HybridStorage::in_memory`] keeps the database in memory instead,
+//!   behind a single connection that serves reads and writes alike
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 
-use crate::context::types::{ContextUnit, FeedbackRecord, LineageManifest, Response};
+use crate::context::types::{
+    ContextUnit, FeedbackRecord, LineageManifest, Response, SourceHead, StorageStats,
+};
 
 /// Storage backend abstraction for context tracing data
 ///
@@ -215,6 +222,133 @@ pub trait StorageBackend: Send + Sync {
     /// * `Ok(count)` with number of records deleted
     /// * `Err(_)` if cleanup operation fails
     async fn cleanup_old_data(&self, retention_days: u32) -> Result<usize, StorageError>;
+
+    /// Make all stored data durable
+    ///
+    /// Called during shutdown. Backends that write through need not override this.
+    ///
+    /// # Returns
+    /// * `Ok(())` if all data is on disk
+    /// * `Err(_)` if flushing fails
+    async fn flush(&self) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    /// Give space freed by deletes back to the file system and refresh the
+    /// statistics the query planner works from
+    ///
+    /// # Returns
+    /// * `Ok(())` once compacted, or if the backend has nothing to compact
+    /// * `Err(_)` if compaction fails
+    async fn compact(&self) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    /// Fold recent writes back into the database file without waiting on
+    /// readers, so the write-ahead log stays small
+    async fn checkpoint(&self) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    /// Size and fragmentation of the stored data
+    ///
+    /// # Returns
+    /// * `Ok(Some(stats))` for backends that keep a database file
+    /// * `Ok(None)` otherwise
+    async fn storage_stats(&self) -> Result<Option<StorageStats>, StorageError> {
+        Ok(None)
+    }
+
+    // ========== Versioning Operations ==========
+
+    /// The current version of the context from `source`
+    ///
+    /// # Returns
+    /// * `Ok(Some(head))` if the source has been seen
+    /// * `Ok(None)` otherwise, or if the backend does not track sources
+    async fn source_head(&self, source: &str) -> Result<Option<SourceHead>, StorageError> {
+        let _ = source;
+        Ok(None)
+    }
+
+    /// Make `head` the current version of its source
+    ///
+    /// # Returns
+    /// * `Ok(())` if stored successfully, or the backend does not track
+    ///   sources
+    /// * `Err(_)` if storage fails
+    async fn set_source_head(&self, head: &SourceHead) -> Result<(), StorageError> {
+        let _ = head;
+        Ok(())
+    }
+
+    // ========== Embedding Operations ==========
+
+    /// Store the embedding of a context unit under `model`, replacing any
+    /// earlier one, and point the unit's `embedding_id` at it
+    ///
+    /// # Returns
+    /// * `Ok(())` if stored successfully
+    /// * `Err(_)` if storage fails or the backend keeps no embeddings
+    async fn store_embedding(
+        &self,
+        context_unit_id: &str,
+        model: &str,
+        vector: &[f32],
+    ) -> Result<(), StorageError> {
+        let _ = (context_unit_id, model, vector);
+        Err(StorageError::InternalError(
+            "this storage backend keeps no embeddings".into(),
+        ))
+    }
+
+    /// The embedding of a context unit under `model`
+    ///
+    /// # Returns
+    /// * `Ok(Some(vector))` if the unit was embedded with that model
+    /// * `Ok(None)` otherwise
+    async fn get_embedding(
+        &self,
+        context_unit_id: &str,
+        model: &str,
+    ) -> Result<Option<Vec<f32>>, StorageError> {
+        let _ = (context_unit_id, model);
+        Ok(None)
+    }
+
+    /// The context units whose `model` embeddings are most similar to
+    /// `vector`, most similar first
+    ///
+    /// # Returns
+    /// * `Ok(units)` with up to `limit` units and their cosine similarity
+    /// * `Err(_)` if the search fails or the backend keeps no embeddings
+    async fn similar_context_units(
+        &self,
+        model: &str,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<(ContextUnit, f32)>, StorageError> {
+        let _ = (model, vector, limit);
+        Err(StorageError::InternalError(
+            "this storage backend keeps no embeddings".into(),
+        ))
+    }
+
+    /// Hits, misses and evictions of the backend's in-memory cache
+    ///
+    /// # Returns
+    /// * `Some(stats)` for backends with a cache, `None` otherwise
+    async fn cache_stats(&self) -> Option<crate::state::cache_stats::CacheStats> {
+        None
+    }
+
+    /// Empty the in-memory cache; stored data is kept
+    ///
+    /// # Returns
+    /// * The number of cached entries removed
+    async fn clear_cache(&self) -> usize {
+        0
+    }
 }
 
 /// Storage operation errors
@@ -236,6 +370,10 @@ pub enum StorageError {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    /// Schema migration error
+    #[error("Migration error: {0}")]
+    MigrationError(String),
+
     /// Cache operation error
     #[error("Cache error: {0}")]
     CacheError(String),
@@ -263,8 +401,8 @@ impl From<serde_json::Error> for StorageError {
 
 /// Initialize SQLite database schema for context tracing
 ///
-/// Creates all required tables with indexes and foreign keys.
-/// Configures WAL mode for concurrent access.
+/// Configures WAL mode for concurrent access, then applies any pending
+/// [migrations](super::migrations) to create or upgrade the tables.
 ///
 /// # Arguments
 /// * `conn` - SQLite connection to initialize
@@ -273,200 +411,15 @@ impl From<serde_json::Error> for StorageError {
 /// * `Ok(())` if schema initialized successfully
 /// * `Err(StorageError)` if initialization fails
 pub fn initialize_schema(conn: &rusqlite::Connection) -> Result<(), StorageError> {
+    // Only takes effect before the first table is created; older databases
+    // switch on their first compaction
+    conn.pragma_update(None, "auto_vacuum", "INCREMENTAL")?;
     // Enable WAL mode for concurrent reads
     conn.pragma_update(None, "journal_mode", "WAL")?;
     conn.pragma_update(None, "synchronous", "NORMAL")?;
     conn.pragma_update(None, "foreign_keys", "ON")?;
 
-    // Create context_units table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS context_units (
-            id TEXT PRIMARY KEY,
-            type TEXT NOT NULL,
-            source TEXT NOT NULL,
-            timestamp TEXT NOT NULL,
-            embedding_id TEXT,
-            summary TEXT,
-            version INTEGER NOT NULL DEFAULT 1,
-            previous_version_id TEXT,
-            aggregate_score REAL NOT NULL DEFAULT 0.0,
-            feedback_count INTEGER NOT NULL DEFAULT 0,
-            FOREIGN KEY (previous_version_id) REFERENCES context_units(id)
-        )",
-        [],
-    )?;
-
-    // Create indexes for context_units
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_context_units_type ON context_units(type)",
-        [],
-    )?;
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_context_units_source ON context_units(source)",
-        [],
-    )?;
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_context_units_timestamp ON context_units(timestamp)",
-        [],
-    )?;
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_context_units_embedding ON context_units(embedding_id)",
-        [],
-    )?;
-
-    // Create responses table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS responses (
-            id TEXT PRIMARY KEY,
-            timestamp TEXT NOT NULL,
-            agent TEXT NOT NULL,
-            model TEXT NOT NULL,
-            token_count INTEGER
-        )",
-        [],
-    )?;
-
-    // Create indexes for responses
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_responses_timestamp ON responses(timestamp)",
-        [],
-    )?;
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_responses_agent ON responses(agent)",
-        [],
-    )?;
-
-    // Create lineage junction table (response -> context_unit mapping)
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS lineage (
-            response_id TEXT NOT NULL,
-            context_unit_id TEXT NOT NULL,
-            weight REAL NOT NULL,
-            PRIMARY KEY (response_id, context_unit_id),
-            FOREIGN KEY (response_id) REFERENCES responses(id) ON DELETE CASCADE,
-            FOREIGN KEY (context_unit_id) REFERENCES context_units(id) ON DELETE CASCADE
-        )",
-        [],
-    )?;
-
-    // Create indexes for lineage
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_lineage_context ON lineage(context_unit_id)",
-        [],
-    )?;
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_lineage_response ON lineage(response_id)",
-        [],
-    )?;
-
-    // Create lineage_manifests table for complete provenance trees
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS lineage_manifests (
-            response_id TEXT PRIMARY KEY,
-            manifest_json TEXT NOT NULL,
-            timestamp TEXT NOT NULL,
-            FOREIGN KEY (response_id) REFERENCES responses(id) ON DELETE CASCADE
-        )",
-        [],
-    )?;
-
-    // Create feedback table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS feedback (
-            id TEXT PRIMARY KEY,
-            response_id TEXT NOT NULL,
-            timestamp TEXT NOT NULL,
-            score REAL NOT NULL,
-            feedback_text TEXT,
-            user_id TEXT,
-            FOREIGN KEY (response_id) REFERENCES responses(id) ON DELETE CASCADE
-        )",
-        [],
-    )?;
-
-    // Create indexes for feedback
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_feedback_response ON feedback(response_id)",
-        [],
-    )?;
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_feedback_timestamp ON feedback(timestamp)",
-        [],
-    )?;
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_feedback_user ON feedback(user_id)",
-        [],
-    )?;
-
-    // Create sessions table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS sessions (
-            id TEXT PRIMARY KEY,
-            started_at TEXT NOT NULL,
-            ended_at TEXT,
-            user_query TEXT,
-            agent TEXT NOT NULL,
-            metadata TEXT,
-            session_score REAL
-        )",
-        [],
-    )?;
-
-    // Create indexes for sessions
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_sessions_started ON sessions(started_at)",
-        [],
-    )?;
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_sessions_agent ON sessions(agent)",
-        [],
-    )?;
-
-    // Create tasks table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS tasks (
-            id TEXT PRIMARY KEY,
-            session_id TEXT NOT NULL,
-            description TEXT NOT NULL,
-            status TEXT NOT NULL,
-            created_at TEXT NOT NULL,
-            completed_at TEXT,
-            task_score REAL,
-            FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
-        )",
-        [],
-    )?;
-
-    // Create indexes for tasks
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_tasks_session ON tasks(session_id)",
-        [],
-    )?;
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_tasks_status ON tasks(status)",
-        [],
-    )?;
-
-    // Create task_responses junction table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS task_responses (
-            task_id TEXT NOT NULL,
-            response_id TEXT NOT NULL,
-            PRIMARY KEY (task_id, response_id),
-            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE,
-            FOREIGN KEY (response_id) REFERENCES responses(id) ON DELETE CASCADE
-        )",
-        [],
-    )?;
-
-    // Add session_id to responses table (migration)
-    conn.execute("ALTER TABLE responses ADD COLUMN session_id TEXT", [])
-        .ok(); // Ignore error if column already exists
-
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_responses_session ON responses(session_id)",
-        [],
-    )?;
+    super::migrations::migrate(conn)?;
 
     Ok(())
 }
@@ -500,44 +453,128 @@ pub fn cleanup_old_data(
          AND timestamp < ?1",
         [&cutoff_str],
     )?;
+    conn.execute(
+        "DELETE FROM context_embeddings
+         WHERE context_unit_id NOT IN (SELECT id FROM context_units)",
+        [],
+    )?;
+    conn.execute(
+        "DELETE FROM context_sources
+         WHERE context_unit_id NOT IN (SELECT id FROM context_units)",
+        [],
+    )?;
 
     Ok(deleted)
 }
 
+/// Reclaim free pages and run `ANALYZE`. A database created without
+/// incremental auto-vacuum is rebuilt once with a full `VACUUM` to turn it
+/// on; after that only the free pages are released.
+pub fn compact(conn: &rusqlite::Connection) -> Result<(), StorageError> {
+    let auto_vacuum: i64 = conn.pragma_query_value(None, "auto_vacuum", |row| row.get(0))?;
+    if auto_vacuum == AUTO_VACUUM_INCREMENTAL {
+        conn.execute_batch("PRAGMA incremental_vacuum")?;
+    } else {
+        conn.pragma_update(None, "auto_vacuum", "INCREMENTAL")?;
+        conn.execute_batch("VACUUM")?;
+    }
+    conn.execute_batch("ANALYZE")?;
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    Ok(())
+}
+
+/// `PRAGMA auto_vacuum` value of incremental mode
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
+/// Page counts of the database `conn` is open on, and the size of its
+/// write-ahead log next to `db_path`
+fn storage_stats(
+    conn: &rusqlite::Connection,
+    db_path: &std::path::Path,
+) -> Result<StorageStats, StorageError> {
+    let pragma = |name: &str| -> Result<u64, StorageError> {
+        Ok(conn.pragma_query_value(None, name, |row| row.get::<_, i64>(0))? as u64)
+    };
+    let (page_size, page_count, free_pages) = (
+        pragma("page_size")?,
+        pragma("page_count")?,
+        pragma("freelist_count")?,
+    );
+    let mut wal_path = db_path.as_os_str().to_owned();
+    wal_path.push("-wal");
+    Ok(StorageStats {
+        database_bytes: page_size * page_count,
+        wal_bytes: std::fs::metadata(wal_path).map_or(0, |m| m.len()),
+        page_size,
+        page_count,
+        free_pages,
+        fragmentation: if page_count == 0 {
+            0.0
+        } else {
+            free_pages as f64 / page_count as f64
+        },
+        incremental_vacuum: pragma("auto_vacuum")? == AUTO_VACUUM_INCREMENTAL as u64,
+        last_compaction: None,
+        last_checkpoint: None,
+    })
+}
+
 // ========== Hybrid Storage Implementation ==========
 
-use dashmap::DashMap;
+use super::cache::MemoryCache;
+pub use super::cache::{EvictionStrategies, EvictionStrategy};
+use crate::state::CacheCounters;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::OptionalExtension;
 use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
 
-/// Cache entry with timestamp for TTL tracking
-#[derive(Debug, Clone)]
-struct CacheEntry<T> {
-    data: T,
-    inserted_at: DateTime<Utc>,
+type ConnectionPool = r2d2::Pool<SqliteConnectionManager>;
+
+/// Prepared statements kept per connection; enough for every query here,
+/// including the variants of the filtered lineage query
+const STATEMENT_CACHE_CAPACITY: usize = 32;
+
+/// How long a connection waits on a database locked by another process
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl From<r2d2::Error> for StorageError {
+    fn from(err: r2d2::Error) -> Self {
+        StorageError::DatabaseError(err.to_string())
+    }
 }
 
-/// Hybrid storage backend combining DashMap cache with SQLite persistence
+/// Hybrid storage backend combining in-memory caches with SQLite persistence
 ///
 /// # Architecture
-/// - Hot cache: DashMap for recent responses (configurable TTL and size)
+/// - Hot cache: bounded LRU caches for recent data (configurable TTL, size
+///   and eviction strategy)
 /// - Cold storage: SQLite for complete history
-/// - Write path: Update cache + async write to SQLite
-/// - Read path: Check cache → fallback to SQLite
+/// - Write path: Update cache + write to SQLite through the single writer
+/// - Read path: Check cache → fallback to SQLite through a pooled reader
 pub struct HybridStorage {
     /// In-memory cache for responses
-    response_cache: Arc<DashMap<String, CacheEntry<Response>>>,
+    response_cache: MemoryCache<Response>,
     /// In-memory cache for context units
-    context_cache: Arc<DashMap<String, CacheEntry<ContextUnit>>>,
+    context_cache: MemoryCache<ContextUnit>,
     /// In-memory cache for lineage manifests
-    lineage_cache: Arc<DashMap<String, CacheEntry<LineageManifest>>>,
-    /// SQLite connection (wrapped in Mutex for async access)
-    db: Arc<Mutex<rusqlite::Connection>>,
-    /// Cache configuration
-    config: CacheConfig,
+    lineage_cache: MemoryCache<LineageManifest>,
+    /// The one connection that writes; SQLite allows a single writer anyway
+    writer: ConnectionPool,
+    /// Read-only connections, so dashboard queries and tracker lookups
+    /// neither wait for writes nor for each other
+    readers: ConnectionPool,
     /// Cache statistics
-    stats: Arc<CacheStats>,
+    stats: CacheCounters,
+    db_path: PathBuf,
+    /// When `compact` and `checkpoint` last succeeded
+    maintenance: std::sync::Mutex<Maintenance>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Maintenance {
+    last_compaction: Option<DateTime<Utc>>,
+    last_checkpoint: Option<DateTime<Utc>>,
 }
 
 /// Cache configuration
@@ -547,8 +584,10 @@ pub struct CacheConfig {
     pub max_entries: usize,
     /// Time-to-live for cache entries (in seconds)
     pub ttl_seconds: i64,
-    /// Cache eviction strategy
-    pub eviction_strategy: EvictionStrategy,
+    /// Eviction strategy of each cache
+    pub eviction: EvictionStrategies,
+    /// SQLite connections serving reads
+    pub read_connections: u32,
 }
 
 impl Default for CacheConfig {
@@ -556,50 +595,103 @@ impl Default for CacheConfig {
         Self {
             max_entries: 10_000,
             ttl_seconds: 7 * 24 * 60 * 60, // 7 days
-            eviction_strategy: EvictionStrategy::TimeBasedLRU,
+            eviction: EvictionStrategies::default(),
+            read_connections: 4,
         }
     }
 }
 
-/// Cache eviction strategy
-#[derive(Debug, Clone, Copy)]
-pub enum EvictionStrategy {
-    /// Time-based least recently used
-    TimeBasedLRU,
-    /// Strict LRU (not yet implemented)
-    StrictLRU,
+/// The one connection to a database held in memory. It is never closed or
+/// replaced, which would lose the database.
+fn memory_pool() -> Result<ConnectionPool, StorageError> {
+    let manager = SqliteConnectionManager::memory().with_init(|conn| {
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+        conn.pragma_update(None, "foreign_keys", "ON")
+    });
+    Ok(r2d2::Pool::builder()
+        .max_size(1)
+        .max_lifetime(None)
+        .idle_timeout(None)
+        .build(manager)?)
 }
 
-/// Cache statistics for monitoring
-#[derive(Debug, Default)]
-pub struct CacheStats {
-    hits: Arc<Mutex<u64>>,
-    misses: Arc<Mutex<u64>>,
-    evictions: Arc<Mutex<u64>>,
+/// A pool of `size` connections to the database, each with a statement
+/// cache. Reader connections refuse to write.
+fn connection_pool(
+    db_path: &std::path::Path,
+    size: u32,
+    read_only: bool,
+) -> Result<ConnectionPool, StorageError> {
+    let manager = SqliteConnectionManager::file(db_path).with_init(move |conn| {
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.pragma_update(None, "foreign_keys", "ON")?;
+        if read_only {
+            conn.pragma_update(None, "query_only", "ON")?;
+        }
+        Ok(())
+    });
+    Ok(r2d2::Pool::builder().max_size(size).build(manager)?)
 }
 
-impl CacheStats {
-    async fn record_hit(&self) {
-        let mut hits = self.hits.lock().await;
-        *hits += 1;
-    }
+/// Run `f` on a connection from `pool` on the blocking thread pool, so a
+/// slow query or a wait for a free connection holds up no async task
+async fn with_connection<T, F>(pool: &ConnectionPool, f: F) -> Result<T, StorageError>
+where
+    T: Send + 'static,
+    F: FnOnce(&rusqlite::Connection) -> Result<T, StorageError> + Send + 'static,
+{
+    let pool = pool.clone();
+    tokio::task::spawn_blocking(move || {
+        let conn = pool.get()?;
+        f(&conn)
+    })
+    .await
+    .map_err(|e| StorageError::InternalError(e.to_string()))?
+}
 
-    async fn record_miss(&self) {
-        let mut misses = self.misses.lock().await;
-        *misses += 1;
-    }
+fn context_unit_from_row(row: &rusqlite::Row) -> rusqlite::Result<ContextUnit> {
+    Ok(ContextUnit {
+        id: row.get(0)?,
+        r#type: match row.get::<_, String>(1)?.as_str() {
+            "System" => crate::context::types::ContextType::System,
+            "User" => crate::context::types::ContextType::User,
+            "External" => crate::context::types::ContextType::External,
+            "ModelState" => crate::context::types::ContextType::ModelState,
+            _ => crate::context::types::ContextType::User,
+        },
+        source: row.get(2)?,
+        timestamp: DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+            .unwrap()
+            .with_timezone(&chrono::Utc),
+        embedding_id: row.get(4)?,
+        summary: row.get(5)?,
+        version: row.get(6)?,
+        previous_version_id: row.get(7)?,
+        aggregate_score: row.get(8)?,
+        feedback_count: row.get(9)?,
+    })
+}
 
-    async fn record_eviction(&self) {
-        let mut evictions = self.evictions.lock().await;
-        *evictions += 1;
-    }
+fn vector_from_blob(blob: &[u8]) -> Vec<f32> {
+    let (chunks, _) = blob.as_chunks::<4>();
+    chunks
+        .iter()
+        .map(|bytes| f32::from_le_bytes(*bytes))
+        .collect()
+}
 
-    pub async fn get_stats(&self) -> (u64, u64, u64) {
-        let hits = *self.hits.lock().await;
-        let misses = *self.misses.lock().await;
-        let evictions = *self.evictions.lock().await;
-        (hits, misses, evictions)
-    }
+fn feedback_from_row(row: &rusqlite::Row) -> rusqlite::Result<FeedbackRecord> {
+    Ok(FeedbackRecord {
+        id: row.get(0)?,
+        response_id: row.get(1)?,
+        timestamp: DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
+            .unwrap()
+            .with_timezone(&chrono::Utc),
+        score: row.get(3)?,
+        feedback_text: row.get(4)?,
+        user_id: row.get(5)?,
+    })
 }
 
 impl HybridStorage {
@@ -614,67 +706,65 @@ impl HybridStorage {
     /// * `Err(StorageError)` if initialization fails
     pub async fn new(db_path: PathBuf, config: Option<CacheConfig>) -> Result<Self, StorageError> {
         let config = config.unwrap_or_default();
+        if config.read_connections == 0 {
+            return Err(StorageError::ValidationError(
+                "at least one read connection is needed".into(),
+            ));
+        }
 
-        // Open SQLite connection
-        let conn = rusqlite::Connection::open(&db_path)
-            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-
-        // Initialize schema
-        initialize_schema(&conn)?;
-
-        Ok(Self {
-            response_cache: Arc::new(DashMap::new()),
-            context_cache: Arc::new(DashMap::new()),
-            lineage_cache: Arc::new(DashMap::new()),
-            db: Arc::new(Mutex::new(conn)),
-            config,
-            stats: Arc::new(CacheStats::default()),
-        })
+        // The writer creates the schema before any reader opens the file
+        let writer = connection_pool(&db_path, 1, false)?;
+        with_connection(&writer, initialize_schema).await?;
+        let readers = connection_pool(&db_path, config.read_connections, true)?;
+        Ok(Self::with_pools(writer, readers, db_path, config))
     }
 
-    /// Check if cache entry is still valid (not expired)
-    fn is_cache_valid<T>(&self, entry: &CacheEntry<T>) -> bool {
-        let age = chrono::Utc::now().signed_duration_since(entry.inserted_at);
-        age.num_seconds() < self.config.ttl_seconds
+    /// Storage whose database lives in memory and is gone on exit, for
+    /// proxies that may not write to disk
+    pub async fn in_memory(config: Option<CacheConfig>) -> Result<Self, StorageError> {
+        let pool = memory_pool()?;
+        with_connection(&pool, initialize_schema).await?;
+        Ok(Self::with_pools(
+            pool.clone(),
+            pool,
+            PathBuf::from(":memory:"),
+            config.unwrap_or_default(),
+        ))
     }
 
-    /// Evict expired entries from cache if over max size
-    async fn evict_if_needed<T: Clone>(&self, cache: &Arc<DashMap<String, CacheEntry<T>>>) {
-        if cache.len() > self.config.max_entries {
-            let now = chrono::Utc::now();
-
-            // Collect expired keys
-            let expired_keys: Vec<String> = cache
-                .iter()
-                .filter(|entry| {
-                    let age = now.signed_duration_since(entry.value().inserted_at);
-                    age.num_seconds() >= self.config.ttl_seconds
-                })
-                .map(|entry| entry.key().clone())
-                .collect();
-
-            // Remove expired entries
-            for key in expired_keys {
-                cache.remove(&key);
-                self.stats.record_eviction().await;
-            }
-
-            // If still over limit, remove oldest entries
-            if cache.len() > self.config.max_entries {
-                let mut entries: Vec<_> = cache
-                    .iter()
-                    .map(|e| (e.key().clone(), e.value().inserted_at))
-                    .collect();
+    fn with_pools(
+        writer: ConnectionPool,
+        readers: ConnectionPool,
+        db_path: PathBuf,
+        config: CacheConfig,
+    ) -> Self {
+        let (max_entries, ttl) = (config.max_entries, config.ttl_seconds);
+        Self {
+            response_cache: MemoryCache::new(config.eviction.responses, max_entries, ttl),
+            context_cache: MemoryCache::new(config.eviction.contexts, max_entries, ttl),
+            lineage_cache: MemoryCache::new(config.eviction.lineage, max_entries, ttl),
+            writer,
+            readers,
+            stats: CacheCounters::default(),
+            db_path,
+            maintenance: Default::default(),
+        }
+    }
 
-                entries.sort_by_key(|(_, ts)| *ts);
+    async fn read<T, F>(&self, f: F) -> Result<T, StorageError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&rusqlite::Connection) -> Result<T, StorageError> + Send + 'static,
+    {
+        with_connection(&self.readers, f).await
+    }
 
-                let to_remove = cache.len() - self.config.max_entries;
-                for (key, _) in entries.iter().take(to_remove) {
-                    cache.remove(key);
-                    self.stats.record_eviction().await;
-                }
-            }
-        }
+    async fn write<T, F>(&self, f: F) -> Result<T, StorageError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&rusqlite::Connection) -> Result<T, StorageError> + Send + 'static,
+    {
+        with_connection(&self.writer, f).await
     }
 }
 
@@ -684,25 +774,36 @@ impl StorageBackend for HybridStorage {
         // Validate before storing
         unit.validate().map_err(StorageError::ValidationError)?;
 
-        // Update cache
-        self.context_cache.insert(
-            unit.id.clone(),
-            CacheEntry {
-                data: unit.clone(),
-                inserted_at: chrono::Utc::now(),
-            },
-        );
+        // A unit stored again keeps the embedding it already has
+        let mut unit = unit.clone();
+        if unit.embedding_id.is_none() {
+            self.context_cache.update(&unit.id, |cached| {
+                unit.embedding_id = cached.embedding_id.clone();
+            });
+        }
 
-        // Evict if needed
-        self.evict_if_needed(&self.context_cache).await;
+        // Update cache
+        let evicted = self.context_cache.insert(unit.id.clone(), unit.clone());
+        self.stats.record_evictions(evicted);
 
         // Persist to SQLite
-        let db = self.db.lock().await;
-        db.execute(
-            "INSERT OR REPLACE INTO context_units
-             (id, type, source, timestamp, embedding_id, summary, version, previous_version_id, aggregate_score, feedback_count)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            rusqlite::params![
+        self.write(move |db| {
+            db.prepare_cached(
+                "INSERT INTO context_units
+                 (id, type, source, timestamp, embedding_id, summary, version, previous_version_id, aggregate_score, feedback_count)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                 ON CONFLICT(id) DO UPDATE SET
+                    type = excluded.type,
+                    source = excluded.source,
+                    timestamp = excluded.timestamp,
+                    embedding_id = COALESCE(excluded.embedding_id, context_units.embedding_id),
+                    summary = excluded.summary,
+                    version = excluded.version,
+                    previous_version_id = excluded.previous_version_id,
+                    aggregate_score = excluded.aggregate_score,
+                    feedback_count = excluded.feedback_count",
+            )?
+            .execute(rusqlite::params![
                 &unit.id,
                 format!("{:?}", unit.r#type),
                 &unit.source,
@@ -713,67 +814,38 @@ impl StorageBackend for HybridStorage {
                 &unit.previous_version_id,
                 unit.aggregate_score,
                 unit.feedback_count,
-            ],
-        )?;
-
-        Ok(())
+            ])?;
+            Ok(())
+        })
+        .await
     }
 
     async fn get_context_unit(&self, id: &str) -> Result<Option<ContextUnit>, StorageError> {
         // Check cache first
-        if let Some(entry) = self.context_cache.get(id) {
-            if self.is_cache_valid(&entry) {
-                self.stats.record_hit().await;
-                return Ok(Some(entry.data.clone()));
-            } else {
-                // Remove expired entry
-                drop(entry);
-                self.context_cache.remove(id);
-            }
+        if let Some(unit) = self.context_cache.get(id) {
+            self.stats.record_hit();
+            return Ok(Some(unit));
         }
 
-        self.stats.record_miss().await;
+        self.stats.record_miss();
 
         // Fallback to SQLite
-        let db = self.db.lock().await;
-        let mut stmt = db.prepare(
-            "SELECT id, type, source, timestamp, embedding_id, summary, version, previous_version_id, aggregate_score, feedback_count
-             FROM context_units WHERE id = ?1",
-        )?;
-
-        let result = stmt.query_row([id], |row| {
-            Ok(ContextUnit {
-                id: row.get(0)?,
-                r#type: match row.get::<_, String>(1)?.as_str() {
-                    "System" => crate::context::types::ContextType::System,
-                    "User" => crate::context::types::ContextType::User,
-                    "External" => crate::context::types::ContextType::External,
-                    "ModelState" => crate::context::types::ContextType::ModelState,
-                    _ => crate::context::types::ContextType::User,
-                },
-                source: row.get(2)?,
-                timestamp: DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                embedding_id: row.get(4)?,
-                summary: row.get(5)?,
-                version: row.get(6)?,
-                previous_version_id: row.get(7)?,
-                aggregate_score: row.get(8)?,
-                feedback_count: row.get(9)?,
+        let key = id.to_string();
+        let result = self
+            .read(move |db| {
+                let mut stmt = db.prepare_cached(
+                    "SELECT id, type, source, timestamp, embedding_id, summary, version, previous_version_id, aggregate_score, feedback_count
+                     FROM context_units WHERE id = ?1",
+                )?;
+                Ok(stmt.query_row([&key], context_unit_from_row))
             })
-        });
+            .await?;
 
         match result {
             Ok(unit) => {
                 // Cache for future reads
-                self.context_cache.insert(
-                    id.to_string(),
-                    CacheEntry {
-                        data: unit.clone(),
-                        inserted_at: chrono::Utc::now(),
-                    },
-                );
+                let evicted = self.context_cache.insert(id.to_string(), unit.clone());
+                self.stats.record_evictions(evicted);
                 Ok(Some(unit))
             }
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
@@ -788,19 +860,21 @@ impl StorageBackend for HybridStorage {
         feedback_count: i32,
     ) -> Result<(), StorageError> {
         // Update cache if present
-        if let Some(mut entry) = self.context_cache.get_mut(id) {
-            entry.data.aggregate_score = aggregate_score;
-            entry.data.feedback_count = feedback_count;
-        }
+        self.context_cache.update(id, |unit| {
+            unit.aggregate_score = aggregate_score;
+            unit.feedback_count = feedback_count;
+        });
 
         // Update SQLite
-        let db = self.db.lock().await;
-        db.execute(
-            "UPDATE context_units SET aggregate_score = ?1, feedback_count = ?2 WHERE id = ?3",
-            rusqlite::params![aggregate_score, feedback_count, id],
-        )?;
-
-        Ok(())
+        let id = id.to_string();
+        self.write(move |db| {
+            db.prepare_cached(
+                "UPDATE context_units SET aggregate_score = ?1, feedback_count = ?2 WHERE id = ?3",
+            )?
+            .execute(rusqlite::params![aggregate_score, feedback_count, id])?;
+            Ok(())
+        })
+        .await
     }
 
     async fn store_response(&self, response: &Response) -> Result<(), StorageError> {
@@ -808,79 +882,82 @@ impl StorageBackend for HybridStorage {
         response.validate().map_err(StorageError::ValidationError)?;
 
         // Update cache
-        self.response_cache.insert(
-            response.id.clone(),
-            CacheEntry {
-                data: response.clone(),
-                inserted_at: chrono::Utc::now(),
-            },
-        );
-
-        // Evict if needed
-        self.evict_if_needed(&self.response_cache).await;
-
-        // Persist to SQLite
-        let db = self.db.lock().await;
-        db.execute(
-            "INSERT OR REPLACE INTO responses (id, timestamp, agent, model, token_count)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            rusqlite::params![
+        let evicted = self
+            .response_cache
+            .insert(response.id.clone(), response.clone());
+        self.stats.record_evictions(evicted);
+
+        // Persist to SQLite, with its lineage, in one transaction
+        let response = response.clone();
+        self.write(move |db| {
+            let tx = db.unchecked_transaction()?;
+            tx.prepare_cached(
+                "INSERT OR REPLACE INTO responses (id, timestamp, agent, model, token_count)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?
+            .execute(rusqlite::params![
                 &response.id,
                 response.timestamp.to_rfc3339(),
                 &response.agent,
                 &response.model,
                 response.token_count,
-            ],
-        )?;
+            ])?;
 
-        // Store lineage relationships
-        for ctx_ref in &response.context_units {
-            db.execute(
+            // Store lineage relationships
+            let mut lineage_stmt = tx.prepare_cached(
                 "INSERT OR REPLACE INTO lineage (response_id, context_unit_id, weight)
                  VALUES (?1, ?2, ?3)",
-                rusqlite::params![&response.id, &ctx_ref.context_unit_id, ctx_ref.weight],
             )?;
-        }
-
-        Ok(())
+            for ctx_ref in &response.context_units {
+                lineage_stmt.execute(rusqlite::params![
+                    &response.id,
+                    &ctx_ref.context_unit_id,
+                    ctx_ref.weight
+                ])?;
+            }
+            drop(lineage_stmt);
+            tx.commit()?;
+            Ok(())
+        })
+        .await
     }
 
     async fn get_response(&self, id: &str) -> Result<Option<Response>, StorageError> {
         // Check cache first
-        if let Some(entry) = self.response_cache.get(id) {
-            if self.is_cache_valid(&entry) {
-                self.stats.record_hit().await;
-                return Ok(Some(entry.data.clone()));
-            } else {
-                drop(entry);
-                self.response_cache.remove(id);
-            }
+        if let Some(response) = self.response_cache.get(id) {
+            self.stats.record_hit();
+            return Ok(Some(response));
         }
 
-        self.stats.record_miss().await;
+        self.stats.record_miss();
 
         // Fallback to SQLite
-        let db = self.db.lock().await;
-
-        // Get response metadata
-        let mut stmt = db.prepare(
-            "SELECT id, timestamp, agent, model, token_count FROM responses WHERE id = ?1",
-        )?;
-
-        let response_result = stmt.query_row([id], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, Option<i32>>(4)?,
-            ))
-        });
+        let key = id.to_string();
+        let response = self
+            .read(move |db| {
+                // Get response metadata
+                let mut stmt = db.prepare_cached(
+                    "SELECT id, timestamp, agent, model, token_count FROM responses WHERE id = ?1",
+                )?;
+
+                let response_result = stmt.query_row([&key], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, Option<i32>>(4)?,
+                    ))
+                });
+
+                let (id, timestamp_str, agent, model, token_count) = match response_result {
+                    Ok(row) => row,
+                    Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+                    Err(e) => return Err(StorageError::DatabaseError(e.to_string())),
+                };
 
-        match response_result {
-            Ok((id, timestamp_str, agent, model, token_count)) => {
                 // Get lineage data
-                let mut lineage_stmt = db.prepare(
+                let mut lineage_stmt = db.prepare_cached(
                     "SELECT context_unit_id, weight FROM lineage WHERE response_id = ?1",
                 )?;
 
@@ -893,7 +970,7 @@ impl StorageBackend for HybridStorage {
                     })?
                     .collect::<Result<Vec<_>, _>>()?;
 
-                let response = Response {
+                Ok(Some(Response {
                     id,
                     timestamp: DateTime::parse_from_rfc3339(&timestamp_str)
                         .unwrap()
@@ -902,53 +979,42 @@ impl StorageBackend for HybridStorage {
                     model,
                     token_count,
                     context_units,
-                };
-
-                // Cache for future reads
-                self.response_cache.insert(
-                    response.id.clone(),
-                    CacheEntry {
-                        data: response.clone(),
-                        inserted_at: chrono::Utc::now(),
-                    },
-                );
-
-                Ok(Some(response))
-            }
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(StorageError::DatabaseError(e.to_string())),
+                }))
+            })
+            .await?;
+
+        if let Some(response) = &response {
+            // Cache for future reads
+            let evicted = self
+                .response_cache
+                .insert(response.id.clone(), response.clone());
+            self.stats.record_evictions(evicted);
         }
+        Ok(response)
     }
 
     async fn store_lineage(&self, manifest: &LineageManifest) -> Result<(), StorageError> {
         // Update cache
-        self.lineage_cache.insert(
-            manifest.response_id.clone(),
-            CacheEntry {
-                data: manifest.clone(),
-                inserted_at: chrono::Utc::now(),
-            },
-        );
-
-        // Evict if needed
-        self.evict_if_needed(&self.lineage_cache).await;
+        let evicted = self
+            .lineage_cache
+            .insert(manifest.response_id.clone(), manifest.clone());
+        self.stats.record_evictions(evicted);
 
         // Serialize manifest to JSON
         let manifest_json = serde_json::to_string(manifest)?;
 
         // Persist to SQLite
-        let db = self.db.lock().await;
-        db.execute(
-            "INSERT OR REPLACE INTO lineage_manifests (response_id, manifest_json, timestamp)
-             VALUES (?1, ?2, ?3)",
-            rusqlite::params![
-                &manifest.response_id,
-                &manifest_json,
-                manifest.timestamp.to_rfc3339()
-            ],
-        )?;
-
-        Ok(())
+        let response_id = manifest.response_id.clone();
+        let timestamp = manifest.timestamp.to_rfc3339();
+        self.write(move |db| {
+            db.prepare_cached(
+                "INSERT OR REPLACE INTO lineage_manifests (response_id, manifest_json, timestamp)
+                 VALUES (?1, ?2, ?3)",
+            )?
+            .execute(rusqlite::params![&response_id, &manifest_json, &timestamp])?;
+            Ok(())
+        })
+        .await
     }
 
     async fn query_lineage(
@@ -956,40 +1022,33 @@ impl StorageBackend for HybridStorage {
         response_id: &str,
     ) -> Result<Option<LineageManifest>, StorageError> {
         // Check cache first
-        if let Some(entry) = self.lineage_cache.get(response_id) {
-            if self.is_cache_valid(&entry) {
-                self.stats.record_hit().await;
-                return Ok(Some(entry.data.clone()));
-            } else {
-                drop(entry);
-                self.lineage_cache.remove(response_id);
-            }
+        if let Some(manifest) = self.lineage_cache.get(response_id) {
+            self.stats.record_hit();
+            return Ok(Some(manifest));
         }
 
-        self.stats.record_miss().await;
+        self.stats.record_miss();
 
         // Fallback to SQLite
-        let db = self.db.lock().await;
-        let mut stmt =
-            db.prepare("SELECT manifest_json FROM lineage_manifests WHERE response_id = ?1")?;
-
-        let result = stmt.query_row([response_id], |row| {
-            let json_str: String = row.get(0)?;
-            Ok(json_str)
-        });
+        let key = response_id.to_string();
+        let result = self
+            .read(move |db| {
+                let mut stmt = db.prepare_cached(
+                    "SELECT manifest_json FROM lineage_manifests WHERE response_id = ?1",
+                )?;
+                Ok(stmt.query_row([&key], |row| row.get::<_, String>(0)))
+            })
+            .await?;
 
         match result {
             Ok(json_str) => {
                 let manifest: LineageManifest = serde_json::from_str(&json_str)?;
 
                 // Cache for future reads
-                self.lineage_cache.insert(
-                    response_id.to_string(),
-                    CacheEntry {
-                        data: manifest.clone(),
-                        inserted_at: chrono::Utc::now(),
-                    },
-                );
+                let evicted = self
+                    .lineage_cache
+                    .insert(response_id.to_string(), manifest.clone());
+                self.stats.record_evictions(evicted);
 
                 Ok(Some(manifest))
             }
@@ -1003,46 +1062,38 @@ impl StorageBackend for HybridStorage {
         feedback.validate().map_err(StorageError::ValidationError)?;
 
         // Persist to SQLite (feedback not cached)
-        let db = self.db.lock().await;
-        db.execute(
-            "INSERT INTO feedback (id, response_id, timestamp, score, feedback_text, user_id)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            rusqlite::params![
+        let feedback = feedback.clone();
+        self.write(move |db| {
+            db.prepare_cached(
+                "INSERT INTO feedback (id, response_id, timestamp, score, feedback_text, user_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?
+            .execute(rusqlite::params![
                 &feedback.id,
                 &feedback.response_id,
                 feedback.timestamp.to_rfc3339(),
                 feedback.score,
                 &feedback.feedback_text,
                 &feedback.user_id,
-            ],
-        )?;
-
-        Ok(())
+            ])?;
+            Ok(())
+        })
+        .await
     }
 
     async fn get_feedback(&self, response_id: &str) -> Result<Vec<FeedbackRecord>, StorageError> {
-        let db = self.db.lock().await;
-        let mut stmt = db.prepare(
-            "SELECT id, response_id, timestamp, score, feedback_text, user_id
-             FROM feedback WHERE response_id = ?1 ORDER BY timestamp DESC",
-        )?;
-
-        let feedback: Vec<FeedbackRecord> = stmt
-            .query_map([response_id], |row| {
-                Ok(FeedbackRecord {
-                    id: row.get(0)?,
-                    response_id: row.get(1)?,
-                    timestamp: DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
-                        .unwrap()
-                        .with_timezone(&chrono::Utc),
-                    score: row.get(3)?,
-                    feedback_text: row.get(4)?,
-                    user_id: row.get(5)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(feedback)
+        let response_id = response_id.to_string();
+        self.read(move |db| {
+            let mut stmt = db.prepare_cached(
+                "SELECT id, response_id, timestamp, score, feedback_text, user_id
+                 FROM feedback WHERE response_id = ?1 ORDER BY timestamp DESC",
+            )?;
+            let feedback = stmt
+                .query_map([&response_id], feedback_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(feedback)
+        })
+        .await
     }
 
     async fn get_feedback_range(
@@ -1050,36 +1101,23 @@ impl StorageBackend for HybridStorage {
         start: DateTime<Utc>,
         end: DateTime<Utc>,
     ) -> Result<Vec<FeedbackRecord>, StorageError> {
-        let db = self.db.lock().await;
-        let mut stmt = db.prepare(
-            "SELECT id, response_id, timestamp, score, feedback_text, user_id
-             FROM feedback WHERE timestamp >= ?1 AND timestamp <= ?2 ORDER BY timestamp DESC",
-        )?;
-
-        let feedback: Vec<FeedbackRecord> = stmt
-            .query_map([start.to_rfc3339(), end.to_rfc3339()], |row| {
-                Ok(FeedbackRecord {
-                    id: row.get(0)?,
-                    response_id: row.get(1)?,
-                    timestamp: DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
-                        .unwrap()
-                        .with_timezone(&chrono::Utc),
-                    score: row.get(3)?,
-                    feedback_text: row.get(4)?,
-                    user_id: row.get(5)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(feedback)
+        self.read(move |db| {
+            let mut stmt = db.prepare_cached(
+                "SELECT id, response_id, timestamp, score, feedback_text, user_id
+                 FROM feedback WHERE timestamp >= ?1 AND timestamp <= ?2 ORDER BY timestamp DESC",
+            )?;
+            let feedback = stmt
+                .query_map([start.to_rfc3339(), end.to_rfc3339()], feedback_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(feedback)
+        })
+        .await
     }
 
     async fn get_context_version_chain(
         &self,
         context_unit_id: &str,
     ) -> Result<Vec<ContextUnit>, StorageError> {
-        let db = self.db.lock().await;
-
         // Use recursive CTE to traverse version chain
         let sql = "
             WITH RECURSIVE version_chain AS (
@@ -1095,34 +1133,15 @@ impl StorageBackend for HybridStorage {
             FROM version_chain
             ORDER BY version ASC";
 
-        let mut stmt = db.prepare(sql)?;
-
-        let results: Vec<ContextUnit> = stmt
-            .query_map([context_unit_id], |row| {
-                Ok(ContextUnit {
-                    id: row.get(0)?,
-                    r#type: match row.get::<_, String>(1)?.as_str() {
-                        "System" => crate::context::types::ContextType::System,
-                        "User" => crate::context::types::ContextType::User,
-                        "External" => crate::context::types::ContextType::External,
-                        "ModelState" => crate::context::types::ContextType::ModelState,
-                        _ => crate::context::types::ContextType::User,
-                    },
-                    source: row.get(2)?,
-                    timestamp: DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                        .unwrap()
-                        .with_timezone(&chrono::Utc),
-                    embedding_id: row.get(4)?,
-                    summary: row.get(5)?,
-                    version: row.get(6)?,
-                    previous_version_id: row.get(7)?,
-                    aggregate_score: row.get(8)?,
-                    feedback_count: row.get(9)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(results)
+        let context_unit_id = context_unit_id.to_string();
+        self.read(move |db| {
+            let mut stmt = db.prepare_cached(sql)?;
+            let results = stmt
+                .query_map([&context_unit_id], context_unit_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(results)
+        })
+        .await
     }
 
     async fn get_responses_for_context(
@@ -1133,8 +1152,6 @@ impl StorageBackend for HybridStorage {
         end_date: Option<DateTime<Utc>>,
         limit: Option<usize>,
     ) -> Result<Vec<crate::context::types::ResponseSummary>, StorageError> {
-        let db = self.db.lock().await;
-
         // Build the query dynamically based on filters
         let mut sql = String::from(
             "SELECT r.id, r.timestamp, r.agent, l.weight
@@ -1143,7 +1160,8 @@ impl StorageBackend for HybridStorage {
              WHERE l.context_unit_id = ?1",
         );
 
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(context_unit_id.to_string())];
+        let mut params: Vec<Box<dyn rusqlite::ToSql + Send>> =
+            vec![Box::new(context_unit_id.to_string())];
 
         if let Some(min_w) = min_weight {
             sql.push_str(" AND l.weight >= ?");
@@ -1167,32 +1185,222 @@ impl StorageBackend for HybridStorage {
             params.push(Box::new(lim as i64));
         }
 
-        let mut stmt = db.prepare(&sql)?;
+        self.read(move |db| {
+            let mut stmt = db.prepare_cached(&sql)?;
 
-        let param_refs: Vec<&dyn rusqlite::ToSql> = params
-            .iter()
-            .map(|p| p.as_ref() as &dyn rusqlite::ToSql)
-            .collect();
+            let param_refs: Vec<&dyn rusqlite::ToSql> = params
+                .iter()
+                .map(|p| p.as_ref() as &dyn rusqlite::ToSql)
+                .collect();
 
-        let results: Vec<crate::context::types::ResponseSummary> = stmt
-            .query_map(&param_refs[..], |row| {
-                Ok(crate::context::types::ResponseSummary {
-                    response_id: row.get(0)?,
-                    timestamp: DateTime::parse_from_rfc3339(&row.get::<_, String>(1)?)
-                        .unwrap()
-                        .with_timezone(&chrono::Utc),
-                    agent: row.get(2)?,
-                    weight: row.get(3)?,
+            let results = stmt
+                .query_map(&param_refs[..], |row| {
+                    Ok(crate::context::types::ResponseSummary {
+                        response_id: row.get(0)?,
+                        timestamp: DateTime::parse_from_rfc3339(&row.get::<_, String>(1)?)
+                            .unwrap()
+                            .with_timezone(&chrono::Utc),
+                        agent: row.get(2)?,
+                        weight: row.get(3)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(results)
+        })
+        .await
+    }
+
+    async fn source_head(&self, source: &str) -> Result<Option<SourceHead>, StorageError> {
+        let source = source.to_string();
+        self.read(move |db| {
+            let head = db
+                .prepare_cached(
+                    "SELECT context_unit_id, content_hash FROM context_sources WHERE source = ?1",
+                )?
+                .query_row([&source], |row| {
+                    Ok(SourceHead {
+                        source: source.clone(),
+                        context_unit_id: row.get(0)?,
+                        content_hash: row.get(1)?,
+                    })
+                })
+                .optional()?;
+            Ok(head)
+        })
+        .await
+    }
+
+    async fn set_source_head(&self, head: &SourceHead) -> Result<(), StorageError> {
+        let head = head.clone();
+        self.write(move |db| {
+            db.prepare_cached(
+                "INSERT OR REPLACE INTO context_sources (source, context_unit_id, content_hash, updated_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?
+            .execute(rusqlite::params![
+                &head.source,
+                &head.context_unit_id,
+                &head.content_hash,
+                Utc::now().to_rfc3339()
+            ])?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn store_embedding(
+        &self,
+        context_unit_id: &str,
+        model: &str,
+        vector: &[f32],
+    ) -> Result<(), StorageError> {
+        let embedding_id = format!("{model}:{context_unit_id}");
+        self.context_cache.update(context_unit_id, |unit| {
+            unit.embedding_id = Some(embedding_id.clone());
+        });
+
+        let (context_unit_id, model) = (context_unit_id.to_string(), model.to_string());
+        let blob: Vec<u8> = vector.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let dimensions = vector.len() as i64;
+        self.write(move |db| {
+            let tx = db.unchecked_transaction()?;
+            tx.prepare_cached(
+                "INSERT OR REPLACE INTO context_embeddings
+                 (context_unit_id, model, dimensions, vector, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?
+            .execute(rusqlite::params![
+                &context_unit_id,
+                &model,
+                dimensions,
+                blob,
+                Utc::now().to_rfc3339()
+            ])?;
+            tx.prepare_cached("UPDATE context_units SET embedding_id = ?1 WHERE id = ?2")?
+                .execute(rusqlite::params![embedding_id, &context_unit_id])?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_embedding(
+        &self,
+        context_unit_id: &str,
+        model: &str,
+    ) -> Result<Option<Vec<f32>>, StorageError> {
+        let (context_unit_id, model) = (context_unit_id.to_string(), model.to_string());
+        self.read(move |db| {
+            let blob = db
+                .prepare_cached(
+                    "SELECT vector FROM context_embeddings WHERE context_unit_id = ?1 AND model = ?2",
+                )?
+                .query_row([&context_unit_id, &model], |row| row.get::<_, Vec<u8>>(0))
+                .optional()?;
+            Ok(blob.map(|blob| vector_from_blob(&blob)))
+        })
+        .await
+    }
+
+    async fn similar_context_units(
+        &self,
+        model: &str,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<(ContextUnit, f32)>, StorageError> {
+        let model = model.to_string();
+        let query = vector.to_vec();
+        self.read(move |db| {
+            // Score every vector of the model, keeping only ids until the
+            // best are known
+            let mut stmt = db.prepare_cached(
+                "SELECT context_unit_id, vector FROM context_embeddings WHERE model = ?1",
+            )?;
+            let mut scored = stmt
+                .query_map([&model], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+                })?
+                .map(|row| {
+                    row.map(|(id, blob)| {
+                        let similarity =
+                            super::embedding::cosine_similarity(&query, &vector_from_blob(&blob));
+                        (id, similarity)
+                    })
                 })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
+                .collect::<Result<Vec<_>, _>>()?;
+            scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+            scored.truncate(limit);
 
-        Ok(results)
+            let mut unit_stmt = db.prepare_cached(
+                "SELECT id, type, source, timestamp, embedding_id, summary, version, previous_version_id, aggregate_score, feedback_count
+                 FROM context_units WHERE id = ?1",
+            )?;
+            let mut results = Vec::with_capacity(scored.len());
+            for (id, similarity) in scored {
+                if let Some(unit) = unit_stmt
+                    .query_row([&id], context_unit_from_row)
+                    .optional()?
+                {
+                    results.push((unit, similarity));
+                }
+            }
+            Ok(results)
+        })
+        .await
     }
 
     async fn cleanup_old_data(&self, retention_days: u32) -> Result<usize, StorageError> {
-        let db = self.db.lock().await;
-        cleanup_old_data(&db, retention_days)
+        self.write(move |db| cleanup_old_data(db, retention_days))
+            .await
+    }
+
+    async fn flush(&self) -> Result<(), StorageError> {
+        // Writes already go to SQLite; fold the WAL back into the database file
+        self.write(|db| {
+            db.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn compact(&self) -> Result<(), StorageError> {
+        self.write(compact).await?;
+        let now = Some(Utc::now());
+        let mut maintenance = self.maintenance.lock().unwrap();
+        maintenance.last_compaction = now;
+        maintenance.last_checkpoint = now;
+        Ok(())
+    }
+
+    async fn checkpoint(&self) -> Result<(), StorageError> {
+        self.write(|db| {
+            db.query_row("PRAGMA wal_checkpoint(PASSIVE)", [], |_| Ok(()))?;
+            Ok(())
+        })
+        .await?;
+        self.maintenance.lock().unwrap().last_checkpoint = Some(Utc::now());
+        Ok(())
+    }
+
+    async fn storage_stats(&self) -> Result<Option<StorageStats>, StorageError> {
+        let db_path = self.db_path.clone();
+        let stats = self.read(move |db| storage_stats(db, &db_path)).await?;
+        let maintenance = *self.maintenance.lock().unwrap();
+        Ok(Some(StorageStats {
+            last_compaction: maintenance.last_compaction,
+            last_checkpoint: maintenance.last_checkpoint,
+            ..stats
+        }))
+    }
+
+    async fn cache_stats(&self) -> Option<crate::state::cache_stats::CacheStats> {
+        let entries =
+            self.response_cache.len() + self.context_cache.len() + self.lineage_cache.len();
+        Some(self.stats.stats(Some(entries)))
+    }
+
+    async fn clear_cache(&self) -> usize {
+        self.response_cache.clear() + self.context_cache.clear() + self.lineage_cache.clear()
     }
 }
 
@@ -1245,4 +1453,126 @@ mod tests {
         drop(conn);
         let _ = std::fs::remove_file(&db_path);
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reads_share_a_read_only_pool() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = HybridStorage::new(
+            dir.path().join("context.db"),
+            Some(CacheConfig {
+                read_connections: 2,
+                ..CacheConfig::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+        let unit = ContextUnit {
+            id: "ctx_1".to_string(),
+            r#type: crate::context::types::ContextType::External,
+            source: "docs".to_string(),
+            timestamp: Utc::now(),
+            embedding_id: None,
+            summary: None,
+            version: 1,
+            previous_version_id: None,
+            aggregate_score: 0.0,
+            feedback_count: 0,
+        };
+        storage.store_context_unit(&unit).await.unwrap();
+        storage
+            .store_response(&Response {
+                id: "resp_1".to_string(),
+                timestamp: Utc::now(),
+                agent: "agent".to_string(),
+                model: "model".to_string(),
+                token_count: None,
+                context_units: vec![crate::context::types::ContextReference {
+                    context_unit_id: unit.id.clone(),
+                    weight: 1.0,
+                }],
+            })
+            .await
+            .unwrap();
+        storage.clear_cache().await;
+
+        // More lookups than readers wait their turn rather than fail
+        let lookups = (0..8).map(|_| storage.get_response("resp_1"));
+        for response in futures::future::join_all(lookups).await {
+            assert_eq!(response.unwrap().unwrap().context_units.len(), 1);
+        }
+
+        let error = storage
+            .read(|db| {
+                db.execute("DELETE FROM responses", [])?;
+                Ok(())
+            })
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("readonly"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn test_compaction_releases_free_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("context.db");
+        {
+            // A database from before incremental auto-vacuum, with room freed
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE padding (data BLOB);
+                 INSERT INTO padding VALUES (zeroblob(1000000));
+                 DELETE FROM padding;",
+            )
+            .unwrap();
+        }
+        let storage = HybridStorage::new(path, None).await.unwrap();
+
+        let before = storage.storage_stats().await.unwrap().unwrap();
+        assert!(!before.incremental_vacuum);
+        assert!(before.free_pages > 0);
+        assert!(before.fragmentation > 0.5);
+        assert!(before.last_compaction.is_none());
+
+        storage.compact().await.unwrap();
+        let after = storage.storage_stats().await.unwrap().unwrap();
+        assert!(after.incremental_vacuum);
+        assert_eq!(after.free_pages, 0);
+        assert!(after.database_bytes < before.database_bytes);
+        assert!(after.last_compaction.is_some());
+
+        // From now on only the freed pages are released
+        storage.compact().await.unwrap();
+        storage.checkpoint().await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_in_memory_storage_keeps_data_without_a_file() {
+        let storage = HybridStorage::in_memory(None).await.unwrap();
+        let unit = ContextUnit {
+            id: "ctx_1".to_string(),
+            r#type: crate::context::types::ContextType::External,
+            source: "docs".to_string(),
+            timestamp: Utc::now(),
+            embedding_id: None,
+            summary: None,
+            version: 1,
+            previous_version_id: None,
+            aggregate_score: 0.0,
+            feedback_count: 0,
+        };
+        storage.store_context_unit(&unit).await.unwrap();
+        storage.clear_cache().await;
+
+        // Reads and writes take turns on the one connection
+        let lookups = (0..4).map(|_| storage.get_context_unit("ctx_1"));
+        for found in futures::future::join_all(lookups).await {
+            assert_eq!(found.unwrap().unwrap().source, "docs");
+        }
+        storage.compact().await.unwrap();
+        storage.checkpoint().await.unwrap();
+        let stats = storage.storage_stats().await.unwrap().unwrap();
+        assert_eq!(stats.wal_bytes, 0);
+        assert!(storage.get_context_unit("ctx_1").await.unwrap().is_some());
+    }
 }
@@ -238,6 +238,8 @@ impl ResponseTracking {
 
 // ========== Context Tracker (Main Interface) ==========
 
+use crate::context::embedding::{EmbeddingProvider, SimilarContext};
+use crate::context::evolution::{ContentChange, EvolutionService};
 use crate::context::storage::StorageBackend;
 use crate::context::types::{
     ContextTreeNode, LineageManifest, ProvenanceEdge, ProvenanceTree, Response,
@@ -259,6 +261,11 @@ pub struct ContextTracker {
     storage: Arc<dyn StorageBackend>,
     /// Active tracking sessions (response_id -> ResponseTracking)
     active_sessions: Arc<RwLock<HashMap<String, ResponseTracking>>>,
+    /// Embeds stored context units for similarity search, when configured
+    embedder: Option<Arc<dyn EmbeddingProvider>>,
+    /// One lock per source, so concurrent observations of a source make
+    /// one version rather than two from the same predecessor
+    source_locks: dashmap::DashMap<String, Arc<tokio::sync::Mutex<()>>>,
 }
 
 impl ContextTracker {
@@ -273,9 +280,17 @@ impl ContextTracker {
         Self {
             storage,
             active_sessions: Arc::new(RwLock::new(HashMap::new())),
+            embedder: None,
+            source_locks: dashmap::DashMap::new(),
         }
     }
 
+    /// Embed context units with `provider` as they are stored
+    pub fn with_embeddings(mut self, provider: Arc<dyn EmbeddingProvider>) -> Self {
+        self.embedder = Some(provider);
+        self
+    }
+
     /// Start tracking a new response
     ///
     /// Generates a unique response ID and initializes a tracking session.
@@ -332,11 +347,159 @@ impl ContextTracker {
             .await
             .map_err(|e| format!("Failed to store context unit: {e}"))?;
 
+        self.embed_in_background(&context);
         session.add_context(context, score);
 
         Ok(())
     }
 
+    /// Add the context seen from `source` to an active response, as the
+    /// source's current context unit, or a new version of it when the
+    /// content changed
+    ///
+    /// # Arguments
+    /// * `response_id` - The response ID from start_response()
+    /// * `source` - Where the content came from, e.g. `server::tool`
+    /// * `context_type` - Type of the context
+    /// * `content` - What the source returned
+    /// * `retrieval_score` - Relevance score (0.0 to 1.0, default 0.5 if unknown)
+    ///
+    /// # Returns
+    /// * `Ok((ContextUnit, ContentChange))` with the unit added
+    /// * `Err(String)` if versioning fails or response_id not found
+    pub async fn observe_context(
+        &self,
+        response_id: String,
+        source: &str,
+        context_type: ContextType,
+        content: &str,
+        retrieval_score: Option<f32>,
+    ) -> Result<(ContextUnit, ContentChange), String> {
+        let lock = self
+            .source_locks
+            .entry(source.to_string())
+            .or_default()
+            .clone();
+        let observed = {
+            let _guard = lock.lock().await;
+            EvolutionService::new(self.storage.clone())
+                .observe(source, context_type, content)
+                .await?
+        };
+        self.add_context(response_id, observed.0.clone(), retrieval_score)
+            .await?;
+        Ok(observed)
+    }
+
+    /// Embed a stored context unit without holding up the caller, unless it
+    /// already has an embedding from the current model
+    fn embed_in_background(&self, context: &ContextUnit) {
+        let Some(embedder) = self.embedder.clone() else {
+            return;
+        };
+        let Some(text) = crate::context::embedding::embedding_text(context).map(str::to_string)
+        else {
+            return;
+        };
+        let storage = self.storage.clone();
+        let id = context.id.clone();
+        tokio::spawn(async move {
+            let model = embedder.model().to_string();
+            if let Ok(Some(_)) = storage.get_embedding(&id, &model).await {
+                return;
+            }
+            let result = match embedder.embed(&text).await {
+                Ok(vector) => storage
+                    .store_embedding(&id, &model, &vector)
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                tracing::warn!("Failed to embed context unit {}: {}", id, e);
+            }
+        });
+    }
+
+    /// Context units most similar to `text`, most similar first
+    ///
+    /// # Returns
+    /// * `Ok(contexts)` with up to `limit` units
+    /// * `Err(String)` if embeddings are not configured or the search fails
+    pub async fn similar_to_text(
+        &self,
+        text: &str,
+        limit: usize,
+    ) -> Result<Vec<SimilarContext>, String> {
+        let embedder = self.embedder()?;
+        let vector = embedder.embed(text).await?;
+        self.similar(embedder.model(), &vector, limit, None).await
+    }
+
+    /// Context units most similar to the given one, which is left out
+    ///
+    /// # Returns
+    /// * `Ok(contexts)` with up to `limit` units
+    /// * `Err(String)` if the unit is unknown, has no text, or the search fails
+    pub async fn similar_to_context(
+        &self,
+        context_unit_id: &str,
+        limit: usize,
+    ) -> Result<Vec<SimilarContext>, String> {
+        let embedder = self.embedder()?;
+        let model = embedder.model();
+        let stored = self
+            .storage
+            .get_embedding(context_unit_id, model)
+            .await
+            .map_err(|e| e.to_string())?;
+        let vector = match stored {
+            Some(vector) => vector,
+            None => {
+                let unit = self
+                    .storage
+                    .get_context_unit(context_unit_id)
+                    .await
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| format!("Context unit {context_unit_id} not found"))?;
+                let text = crate::context::embedding::embedding_text(&unit)
+                    .ok_or_else(|| format!("Context unit {context_unit_id} has no text"))?;
+                embedder.embed(text).await?
+            }
+        };
+        self.similar(model, &vector, limit, Some(context_unit_id))
+            .await
+    }
+
+    fn embedder(&self) -> Result<&Arc<dyn EmbeddingProvider>, String> {
+        self.embedder
+            .as_ref()
+            .ok_or_else(|| "Embeddings are not configured".to_string())
+    }
+
+    async fn similar(
+        &self,
+        model: &str,
+        vector: &[f32],
+        limit: usize,
+        exclude: Option<&str>,
+    ) -> Result<Vec<SimilarContext>, String> {
+        let found = self
+            .storage
+            .similar_context_units(model, vector, limit + exclude.is_some() as usize)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(found
+            .into_iter()
+            .filter(|(unit, _)| Some(unit.id.as_str()) != exclude)
+            .take(limit)
+            .map(|(context_unit, similarity)| SimilarContext {
+                context_unit,
+                similarity,
+            })
+            .collect())
+    }
+
     /// Finalize response tracking and generate lineage manifest
     ///
     /// Calculates final weights, generates provenance tree, persists to storage,
@@ -465,6 +628,26 @@ impl ContextTracker {
         })
     }
 
+    /// Flush stored lineage data to disk
+    ///
+    /// Responses that were started but never finalized are not persisted;
+    /// they are logged so a shutdown does not drop them silently.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the storage backend flushed successfully
+    /// * `Err(String)` if flushing fails
+    pub async fn flush(&self) -> Result<(), String> {
+        let unfinished = self.active_sessions.read().await.len();
+        if unfinished > 0 {
+            tracing::warn!(
+                "{} context tracking sessions were not finalized and will not be stored",
+                unfinished
+            );
+        }
+
+        self.storage.flush().await.map_err(|e| e.to_string())
+    }
+
     /// Get storage backend (for testing/introspection)
     pub fn storage(&self) -> Arc<dyn StorageBackend> {
         self.storage.clone()
@@ -165,6 +165,59 @@ pub struct EvolutionHistory {
     pub history: Vec<ContextVersion>,
 }
 
+/// The current version of the context from a source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceHead {
+    pub source: String,
+    pub context_unit_id: String,
+    /// Hash of the normalized content the version was made from
+    pub content_hash: String,
+}
+
+/// One line of a diff between two versions' summaries
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+/// How a context unit changed between two of its versions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionDiff {
+    pub from: ContextVersion,
+    pub to: ContextVersion,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Size and fragmentation of the tracing database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageStats {
+    /// Size of the database file
+    pub database_bytes: u64,
+    /// Size of the write-ahead log not yet folded into the file
+    pub wal_bytes: u64,
+    pub page_size: u64,
+    pub page_count: u64,
+    /// Pages freed by deletes and not yet given back
+    pub free_pages: u64,
+    /// Share of the pages that are free, from 0.0 to 1.0
+    pub fragmentation: f64,
+    /// Whether compaction can release free pages without a full rebuild
+    pub incremental_vacuum: bool,
+    /// When the database was last compacted by this process
+    pub last_compaction: Option<DateTime<Utc>>,
+    /// When the write-ahead log was last checkpointed by this process
+    pub last_checkpoint: Option<DateTime<Utc>>,
+}
+
 /// Context impact report
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContextImpactReport {
@@ -0,0 +1,238 @@
+//! Running the proxy inside another Rust application
+//!
+//! ```no_run
+//! # async fn example(config: mcp_rust_proxy::config::Config) -> mcp_rust_proxy::error::Result<()> {
+//! use mcp_rust_proxy::Proxy;
+//!
+//! let proxy = Proxy::builder().config(config).build()?;
+//! proxy.start().await?;
+//! let tools = proxy
+//!     .request(serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }))
+//!     .await?;
+//! proxy.stop().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::config::{self, Config};
+use crate::context::storage::StorageBackend;
+use crate::error::{ConfigError, ProxyError, Result};
+use crate::protocol::{AdapterRegistry, ProtocolAdapter, ProtocolVersion};
+use crate::proxy::{self, RequestContext, RequestHandler};
+use crate::recording::{Direction, SessionRecorder};
+use crate::startup::{self, StartOptions};
+use crate::state::AppState;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+
+/// Configures a [`Proxy`]
+#[derive(Default)]
+pub struct ProxyBuilder {
+    config: Option<Config>,
+    storage: Option<Arc<dyn StorageBackend>>,
+    serve_http: bool,
+    record: Option<PathBuf>,
+}
+
+impl ProxyBuilder {
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Store context tracing data here instead of the SQLite database
+    /// `contextTracing` configures. Enables context tracing.
+    pub fn with_storage(mut self, storage: Arc<dyn StorageBackend>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Also serve clients over HTTP, and the web UI when configured. Off by
+    /// default: embedding applications send requests with [`Proxy::request`].
+    pub fn serve_http(mut self, serve: bool) -> Self {
+        self.serve_http = serve;
+        self
+    }
+
+    /// Record the session's messages to `path`, as `--record` does
+    pub fn record_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record = Some(path.into());
+        self
+    }
+
+    /// Translate messages from `source` to `target` with adapters made by
+    /// `factory`, replacing the built-in adapter for the pair. Adapters are
+    /// registered for the whole process, in [`AdapterRegistry::global`].
+    pub fn protocol_adapter<F>(
+        self,
+        source: ProtocolVersion,
+        target: ProtocolVersion,
+        factory: F,
+    ) -> Self
+    where
+        F: Fn() -> Arc<dyn ProtocolAdapter> + Send + Sync + 'static,
+    {
+        AdapterRegistry::global().register(source, target, factory);
+        self
+    }
+
+    /// Check the configuration and set up the proxy, without starting
+    /// anything yet
+    pub fn build(self) -> Result<Proxy> {
+        let config = self
+            .config
+            .ok_or_else(|| ConfigError::Validation("no configuration given".to_string()))?;
+        config::validate(&config)?;
+
+        let (state, _shutdown_rx) = AppState::new(config);
+        if let Some(path) = &self.record {
+            state.record_session(Arc::new(SessionRecorder::create(path)?));
+        }
+        let handler = Arc::new(RequestHandler::new(state.clone()));
+
+        Ok(Proxy {
+            state,
+            handler,
+            storage: self.storage,
+            serve_http: self.serve_http,
+            lifecycle: Mutex::new(Lifecycle::Built),
+        })
+    }
+}
+
+enum Lifecycle {
+    Built,
+    /// Started, with its background tasks
+    Running(Vec<JoinHandle<()>>),
+    Stopped,
+}
+
+/// An embedded proxy: its servers, background tasks and, optionally, HTTP
+/// endpoints
+pub struct Proxy {
+    state: Arc<AppState>,
+    handler: Arc<RequestHandler>,
+    storage: Option<Arc<dyn StorageBackend>>,
+    serve_http: bool,
+    lifecycle: Mutex<Lifecycle>,
+}
+
+impl Proxy {
+    pub fn builder() -> ProxyBuilder {
+        ProxyBuilder::default()
+    }
+
+    /// The proxy's state, for inspecting servers and metrics
+    pub fn state(&self) -> &Arc<AppState> {
+        &self.state
+    }
+
+    /// Start the configured servers and background tasks. Returns once they
+    /// are started; servers finish connecting in the background. Starting a
+    /// running proxy does nothing.
+    pub async fn start(&self) -> Result<()> {
+        let mut lifecycle = self.lifecycle.lock().await;
+        match *lifecycle {
+            Lifecycle::Built => {}
+            Lifecycle::Running(_) => return Ok(()),
+            Lifecycle::Stopped => return Err(ProxyError::ShuttingDown),
+        }
+        let handles = startup::start(
+            &self.state,
+            self.handler.clone(),
+            StartOptions {
+                serve_http: self.serve_http,
+                storage: self.storage.clone(),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        *lifecycle = Lifecycle::Running(handles);
+        Ok(())
+    }
+
+    /// Notifications for clients, such as `notifications/tools/list_changed`
+    /// when a server comes up or goes away
+    pub fn notifications(&self) -> tokio::sync::broadcast::Receiver<Value> {
+        self.state.client_notification_tx.subscribe()
+    }
+
+    /// Handle a JSON-RPC request as if a client had sent it, returning the
+    /// response
+    pub async fn request(&self, request: Value) -> Result<Value> {
+        let body = serde_json::to_vec(&request)?;
+        let ctx = RequestContext::default();
+        let _in_flight = self.state.drain.track().ok_or(ProxyError::ShuttingDown)?;
+        if let Some(recorder) = self.state.recorder() {
+            recorder.client(Direction::ClientRequest, &ctx.correlation_id, &body);
+        }
+
+        let reply = proxy::dispatch(&self.handler, &self.state.request_router, &body, &ctx)
+            .instrument(ctx.span())
+            .await?;
+        if let Some(recorder) = self.state.recorder() {
+            recorder.client(Direction::ClientResponse, &ctx.correlation_id, &reply);
+        }
+        Ok(serde_json::from_slice(&reply)?)
+    }
+
+    /// Let in-flight requests finish, stop the servers and background tasks,
+    /// and wait for them to exit. The proxy cannot be started again.
+    pub async fn stop(&self) {
+        let mut lifecycle = self.lifecycle.lock().await;
+        let Lifecycle::Running(handles) = std::mem::replace(&mut *lifecycle, Lifecycle::Stopped)
+        else {
+            return;
+        };
+        self.state.drain_requests().await;
+        self.state.shutdown().await;
+        let finished = tokio::time::timeout(std::time::Duration::from_secs(30), async {
+            for handle in handles {
+                let _ = handle.await;
+            }
+        })
+        .await;
+        if finished.is_err() {
+            tracing::warn!("Proxy tasks did not stop within 30s");
+        }
+        let _ = self.state.connection_pool.close_all().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config_with;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_embedded_proxy_lifecycle() {
+        assert!(Proxy::builder().build().is_err());
+
+        let config = test_config_with(json!({}), json!({ "ephemeral": true }));
+        let proxy = Proxy::builder().config(config).build().unwrap();
+        proxy.start().await.unwrap();
+        proxy.start().await.unwrap();
+
+        let pong = proxy
+            .request(json!({ "jsonrpc": "2.0", "id": 7, "method": "ping" }))
+            .await
+            .unwrap();
+        assert_eq!(pong["id"], 7);
+        assert_eq!(pong["result"], json!({}));
+
+        proxy.stop().await;
+        assert!(matches!(
+            proxy
+                .request(json!({ "jsonrpc": "2.0", "id": 8, "method": "ping" }))
+                .await,
+            Err(ProxyError::ShuttingDown)
+        ));
+        assert!(matches!(proxy.start().await, Err(ProxyError::ShuttingDown)));
+    }
+}
@@ -27,14 +27,48 @@ pub enum ProxyError {
     #[error("Server not ready: {0}")]
     ServerNotReady(String),
 
+    #[error("Server '{server}' is temporarily unavailable: {reason}")]
+    ServerUnavailable { server: String, reason: String },
+
+    #[error("Server '{server}' is disabled: {reason}")]
+    ServerDisabled { server: String, reason: String },
+
+    #[error("Proxy is shutting down")]
+    ShuttingDown,
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Client '{client}' is over quota: {reason}")]
+    QuotaExceeded { client: String, reason: String },
+
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
 
+    #[error("Parse error: {0}")]
+    Parse(String),
+
+    #[error("Request exceeds limits: {0}")]
+    RequestLimit(String),
+
     #[error("Invalid response")]
     InvalidResponse,
 
+    #[error("Response from '{server}' is {size} bytes, over the {limit}-byte limit")]
+    ResponseTooLarge {
+        server: String,
+        size: usize,
+        limit: usize,
+    },
+
     #[error("Pool error: {0}")]
     Pool(#[from] PoolError),
+
+    #[error("Shared state error: {0}")]
+    SharedState(#[from] redis::RedisError),
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] crate::context::storage::StorageError),
 }
 
 #[derive(Error, Debug)]
@@ -83,6 +117,9 @@ pub enum ServerError {
 
     #[error("Health check failed")]
     HealthCheckFailed,
+
+    #[error("Server is disabled: {0}")]
+    Disabled(String),
 }
 
 #[derive(Error, Debug)]
@@ -110,5 +147,3 @@ pub enum HealthError {
 }
 
 pub type Result<T> = std::result::Result<T, ProxyError>;
-
-impl warp::reject::Reject for ProxyError {}
@@ -1,16 +1,27 @@
+pub mod bench;
+pub mod catalog;
+pub mod check;
 pub mod commands;
 pub mod config;
 pub mod context;
+pub mod embed;
 pub mod error;
 pub mod logging;
 pub mod plugin;
 pub mod protocol;
 pub mod proxy;
+pub mod recording;
+pub mod registry;
 pub mod server;
+pub mod snapshot;
+pub mod startup;
 pub mod state;
+pub mod systemd;
 pub mod transport;
 pub mod types;
 pub mod web;
 
+pub use embed::{Proxy, ProxyBuilder};
+
 // #[cfg(test)]
 // pub mod test_utils; // TODO: Add test_utils module
@@ -1,20 +1,44 @@
 use crate::error::Result;
-use chrono::{DateTime, Local, Utc};
+use crate::state::{log_buffer::level_matches, LogEntry};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::fs::{File, OpenOptions};
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tokio::sync::Mutex;
 
+pub mod parser;
+
+pub use parser::LogClassifier;
+
 const MAX_LOG_SIZE: u64 = 10 * 1024 * 1024; // 10MB
 const LOG_ROTATION_DAYS: i64 = 2;
+/// Bytes read at a time when reading log history from the end of a file
+const HISTORY_CHUNK_SIZE: u64 = 64 * 1024;
 
 pub struct ServerLogger {
     log_dir: PathBuf,
     server_name: String,
     log_writer: Arc<Mutex<File>>,
     current_log_size: Arc<Mutex<u64>>,
+    /// Correlation IDs of the calls the server is handling
+    active_calls: std::sync::Mutex<Vec<String>>,
+}
+
+/// A call in progress on a server; see [`ServerLogger::call_window`]
+pub struct CallWindow {
+    logger: Arc<ServerLogger>,
+    correlation_id: String,
+}
+
+impl Drop for CallWindow {
+    fn drop(&mut self) {
+        let mut calls = self.logger.active_calls.lock().unwrap();
+        if let Some(index) = calls.iter().position(|id| *id == self.correlation_id) {
+            calls.remove(index);
+        }
+    }
 }
 
 impl ServerLogger {
@@ -59,9 +83,24 @@ impl ServerLogger {
             server_name,
             log_writer: Arc::new(Mutex::new(log_file)),
             current_log_size: Arc::new(Mutex::new(log_size)),
+            active_calls: std::sync::Mutex::new(Vec::new()),
         })
     }
 
+    /// Tag the lines the server writes with `correlation_id` until the
+    /// returned window is dropped, tying its output to the call it came from.
+    /// Lines written while several calls are in progress carry all their IDs.
+    pub fn call_window(self: &Arc<Self>, correlation_id: &str) -> CallWindow {
+        self.active_calls
+            .lock()
+            .unwrap()
+            .push(correlation_id.to_string());
+        CallWindow {
+            logger: self.clone(),
+            correlation_id: correlation_id.to_string(),
+        }
+    }
+
     pub async fn write_stdout(&self, data: &str) -> Result<()> {
         self.write_log(data, "STDOUT").await
     }
@@ -72,7 +111,12 @@ impl ServerLogger {
 
     async fn write_log(&self, data: &str, stream_type: &str) -> Result<()> {
         let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
-        let log_line = format!("[{timestamp}] [{stream_type}] {data}\n");
+        let calls = self.active_calls.lock().unwrap().join(",");
+        let log_line = if calls.is_empty() {
+            format!("[{timestamp}] [{stream_type}] {data}\n")
+        } else {
+            format!("[{timestamp}] [{stream_type}] [correlation_id={calls}] {data}\n")
+        };
         let log_bytes = log_line.as_bytes();
 
         let mut size = self.current_log_size.lock().await;
@@ -156,3 +200,252 @@ impl ServerLogger {
         Ok(())
     }
 }
+
+/// Read the most recent `num_lines` entries from a server's log directory,
+/// walking `server.log` and then the rotated files from newest to oldest.
+/// Only entries at or above `min_level` are returned, oldest first. When a
+/// classifier is given, levels come from the line contents rather than the
+/// stream it was written to.
+pub async fn read_log_history(
+    server_log_dir: &Path,
+    num_lines: usize,
+    min_level: Option<&str>,
+    classifier: Option<&LogClassifier>,
+) -> Result<Vec<LogEntry>> {
+    let mut rotated = Vec::new();
+    let mut entries = tokio::fs::read_dir(server_log_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let is_rotated = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n != "server.log" && n.ends_with(".log"));
+        if is_rotated && path.is_file() {
+            rotated.push(path);
+        }
+    }
+    // Rotated files are named `{server}.{%Y%m%d_%H%M%S}.log`, so names sort by age
+    rotated.sort();
+
+    let files = std::iter::once(ServerLogger::get_log_path(server_log_dir))
+        .chain(rotated.into_iter().rev());
+
+    let mut history = Vec::new();
+    for file in files {
+        if history.len() >= num_lines {
+            break;
+        }
+
+        let mut lines = match ReverseLines::open(&file).await {
+            Ok(lines) => lines,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        while history.len() < num_lines {
+            let Some(batch) = lines.next_batch().await? else {
+                break;
+            };
+            history.extend(
+                batch
+                    .iter()
+                    .filter_map(|line| parse_log_line(line, classifier))
+                    .filter(|entry| {
+                        min_level.is_none_or(|level| level_matches(&entry.level, level))
+                    })
+                    .take(num_lines - history.len()),
+            );
+        }
+    }
+
+    history.reverse();
+    Ok(history)
+}
+
+/// Reads a file's lines from last to first, [`HISTORY_CHUNK_SIZE`] bytes at
+/// a time, so the tail of a large log can be read without loading all of it
+struct ReverseLines {
+    file: File,
+    /// Everything before this offset is still unread
+    pos: u64,
+    /// The end of a line whose start is in the unread part of the file
+    partial: Vec<u8>,
+}
+
+impl ReverseLines {
+    async fn open(path: &Path) -> std::io::Result<Self> {
+        let file = File::open(path).await?;
+        let pos = file.metadata().await?.len();
+        Ok(Self {
+            file,
+            pos,
+            partial: Vec::new(),
+        })
+    }
+
+    /// The complete lines in the next chunk, newest first, or `None` once
+    /// the whole file has been read
+    async fn next_batch(&mut self) -> std::io::Result<Option<Vec<String>>> {
+        if self.pos == 0 {
+            return Ok(None);
+        }
+
+        let len = self.pos.min(HISTORY_CHUNK_SIZE);
+        self.pos -= len;
+        self.file.seek(std::io::SeekFrom::Start(self.pos)).await?;
+        let mut chunk = vec![0; len as usize];
+        self.file.read_exact(&mut chunk).await?;
+        chunk.append(&mut self.partial);
+
+        if self.pos > 0 {
+            // The first line may have started in the part not read yet
+            let Some(newline) = chunk.iter().position(|b| *b == b'\n') else {
+                self.partial = chunk;
+                return Ok(Some(Vec::new()));
+            };
+            let complete = chunk.split_off(newline + 1);
+            chunk.pop();
+            self.partial = std::mem::replace(&mut chunk, complete);
+        }
+
+        Ok(Some(
+            chunk
+                .rsplit(|b| *b == b'\n')
+                .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+                .filter(|line| !line.is_empty())
+                .map(|line| String::from_utf8_lossy(line).into_owned())
+                .collect(),
+        ))
+    }
+}
+
+/// Parse a line written by `ServerLogger` (`[timestamp] [STREAM] message`)
+fn parse_log_line(line: &str, classifier: Option<&LogClassifier>) -> Option<LogEntry> {
+    let rest = line.strip_prefix('[')?;
+    let (timestamp, rest) = rest.split_once("] [")?;
+    let (stream, message) = rest.split_once("] ")?;
+
+    let naive = NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S%.3f").ok()?;
+    let timestamp = Local
+        .from_local_datetime(&naive)
+        .earliest()?
+        .with_timezone(&Utc);
+
+    let level = match stream {
+        "STDERR" => "error",
+        "STDOUT" => "info",
+        _ => return None,
+    };
+
+    Some(match classifier {
+        Some(classifier) => classifier.entry(message, level, timestamp),
+        None => LogEntry {
+            timestamp,
+            level: level.to_string(),
+            message: message.to_string(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_call_window_tags_server_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let logger = Arc::new(
+            ServerLogger::new("demo".to_string(), Some(dir.path().to_path_buf()))
+                .await
+                .unwrap(),
+        );
+
+        logger.write_stderr("starting").await.unwrap();
+        let first = logger.call_window("call-1");
+        let second = logger.call_window("call-2");
+        logger.write_stderr("both running").await.unwrap();
+        drop(first);
+        logger.write_stdout("second running").await.unwrap();
+        drop(second);
+        logger.write_stdout("idle").await.unwrap();
+
+        let history = read_log_history(&dir.path().join("demo"), 10, None, None)
+            .await
+            .unwrap();
+        let messages: Vec<_> = history.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "starting",
+                "[correlation_id=call-1,call-2] both running",
+                "[correlation_id=call-2] second running",
+                "idle"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_log_history_spans_rotated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("demo.20250101_000000.log"),
+            "[2025-01-01 00:00:00.000] [STDERR] oldest failure\n\
+             [2025-01-01 00:00:01.000] [STDOUT] oldest output\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("demo.20250102_000000.log"),
+            "[2025-01-02 00:00:00.000] [STDERR] middle failure\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("server.log"),
+            "[2025-01-03 00:00:00.000] [STDOUT] current output\n\
+             not a log line\n\
+             [2025-01-03 00:00:01.000] [STDERR] current failure\n",
+        )
+        .unwrap();
+
+        let all = read_log_history(dir.path(), 10, None, None).await.unwrap();
+        let messages: Vec<_> = all.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "oldest failure",
+                "oldest output",
+                "middle failure",
+                "current output",
+                "current failure"
+            ]
+        );
+
+        let errors = read_log_history(dir.path(), 2, Some("error"), None)
+            .await
+            .unwrap();
+        let messages: Vec<_> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, vec!["middle failure", "current failure"]);
+    }
+
+    #[tokio::test]
+    async fn test_read_log_history_across_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let lines: Vec<String> = (0..5000)
+            .map(|i| format!("[2025-01-03 00:00:00.000] [STDOUT] line {i} é"))
+            .collect();
+        let contents = lines.join("\n") + "\n";
+        assert!(contents.len() as u64 > 2 * HISTORY_CHUNK_SIZE);
+        std::fs::write(dir.path().join("server.log"), contents).unwrap();
+
+        let tail = read_log_history(dir.path(), 3, None, None).await.unwrap();
+        let messages: Vec<_> = tail.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, vec!["line 4997 é", "line 4998 é", "line 4999 é"]);
+
+        let all = read_log_history(dir.path(), 10_000, None, None)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 5000);
+        for (i, entry) in all.iter().enumerate() {
+            assert_eq!(entry.message, format!("line {i} é"));
+        }
+    }
+}
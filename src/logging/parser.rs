@@ -0,0 +1,288 @@
+//! Classification of captured server output into structured log entries
+
+use crate::config::LogParserConfig;
+use crate::state::LogEntry;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
+use serde_json::Value;
+
+const LEVEL_FIELDS: &[&str] = &["level", "severity", "lvl"];
+const MESSAGE_FIELDS: &[&str] = &["message", "msg"];
+const TIMESTAMP_FIELDS: &[&str] = &["timestamp", "time", "ts"];
+
+/// Fields extracted from a recognized log line
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedLine {
+    pub level: String,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub message: Option<String>,
+}
+
+enum Parser {
+    Json {
+        level_field: Option<String>,
+        message_field: Option<String>,
+        timestamp_field: Option<String>,
+    },
+    Regex(Regex),
+}
+
+/// Applies a server's configured log parsers to raw output lines
+pub struct LogClassifier {
+    parsers: Vec<Parser>,
+}
+
+impl LogClassifier {
+    pub fn new(configs: &[LogParserConfig]) -> std::result::Result<Self, String> {
+        let parsers = configs
+            .iter()
+            .map(|config| match config {
+                LogParserConfig::Json {
+                    level_field,
+                    message_field,
+                    timestamp_field,
+                } => Ok(Parser::Json {
+                    level_field: level_field.clone(),
+                    message_field: message_field.clone(),
+                    timestamp_field: timestamp_field.clone(),
+                }),
+                LogParserConfig::Regex { pattern } => {
+                    let regex = Regex::new(pattern)
+                        .map_err(|e| format!("invalid log parser pattern '{pattern}': {e}"))?;
+                    if !regex.capture_names().any(|name| name == Some("level")) {
+                        return Err(format!(
+                            "log parser pattern '{pattern}' has no 'level' named group"
+                        ));
+                    }
+                    Ok(Parser::Regex(regex))
+                }
+            })
+            .collect::<std::result::Result<_, String>>()?;
+
+        Ok(Self { parsers })
+    }
+
+    /// Run the parsers in order, returning the first match
+    pub fn classify(&self, line: &str) -> Option<ParsedLine> {
+        self.parsers.iter().find_map(|parser| match parser {
+            Parser::Json {
+                level_field,
+                message_field,
+                timestamp_field,
+            } => parse_json(
+                line,
+                level_field.as_deref(),
+                message_field.as_deref(),
+                timestamp_field.as_deref(),
+            ),
+            Parser::Regex(regex) => parse_regex(regex, line),
+        })
+    }
+
+    /// Build a log entry for a line, using `default_level` and `timestamp`
+    /// for anything the parsers could not extract
+    pub fn entry(&self, line: &str, default_level: &str, timestamp: DateTime<Utc>) -> LogEntry {
+        match self.classify(line) {
+            Some(parsed) => LogEntry {
+                timestamp: parsed.timestamp.unwrap_or(timestamp),
+                level: parsed.level,
+                message: parsed.message.unwrap_or_else(|| line.to_string()),
+            },
+            None => LogEntry {
+                timestamp,
+                level: default_level.to_string(),
+                message: line.to_string(),
+            },
+        }
+    }
+}
+
+fn parse_json(
+    line: &str,
+    level_field: Option<&str>,
+    message_field: Option<&str>,
+    timestamp_field: Option<&str>,
+) -> Option<ParsedLine> {
+    let trimmed = line.trim();
+    if !trimmed.starts_with('{') {
+        return None;
+    }
+    let value: Value = serde_json::from_str(trimmed).ok()?;
+    // JSON-RPC traffic is protocol, not logging
+    if value.get("jsonrpc").is_some() {
+        return None;
+    }
+
+    let level = lookup(&value, level_field, LEVEL_FIELDS).and_then(json_level)?;
+    let message = lookup(&value, message_field, MESSAGE_FIELDS).map(|v| match v {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    });
+    let timestamp = lookup(&value, timestamp_field, TIMESTAMP_FIELDS).and_then(|v| match v {
+        Value::String(s) => parse_timestamp(s),
+        Value::Number(n) => n.as_i64().and_then(DateTime::from_timestamp_millis),
+        _ => None,
+    });
+
+    Some(ParsedLine {
+        level,
+        timestamp,
+        message,
+    })
+}
+
+/// Look up a configured field (dot-separated for nested objects), or the
+/// first present of the common field names
+fn lookup<'a>(value: &'a Value, field: Option<&str>, defaults: &[&str]) -> Option<&'a Value> {
+    match field {
+        Some(path) => path
+            .split('.')
+            .try_fold(value, |current, key| current.get(key)),
+        None => defaults.iter().find_map(|key| value.get(*key)),
+    }
+}
+
+fn json_level(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => normalize_level(s),
+        // pino/bunyan numeric levels
+        Value::Number(n) => match n.as_u64()? {
+            0..=10 => Some("trace"),
+            11..=20 => Some("debug"),
+            21..=30 => Some("info"),
+            31..=40 => Some("warn"),
+            _ => Some("error"),
+        }
+        .map(str::to_string),
+        _ => None,
+    }
+}
+
+fn parse_regex(regex: &Regex, line: &str) -> Option<ParsedLine> {
+    let captures = regex.captures(line)?;
+    let level = normalize_level(captures.name("level")?.as_str())?;
+
+    Some(ParsedLine {
+        level,
+        timestamp: captures
+            .name("timestamp")
+            .and_then(|m| parse_timestamp(m.as_str())),
+        message: captures.name("message").map(|m| m.as_str().to_string()),
+    })
+}
+
+/// Map level names from common logging libraries onto the proxy's levels
+fn normalize_level(level: &str) -> Option<String> {
+    let level = match level.trim().to_ascii_lowercase().as_str() {
+        "trace" | "verbose" | "t" => "trace",
+        "debug" | "d" => "debug",
+        "info" | "notice" | "i" => "info",
+        "warn" | "warning" | "w" => "warn",
+        "error" | "err" | "fatal" | "critical" | "crit" | "panic" | "e" | "f" => "error",
+        _ => return None,
+    };
+    Some(level.to_string())
+}
+
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(s) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+
+    // Timestamps without an offset are assumed to be in local time
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_LOG_LEVEL_PATTERN;
+
+    fn default_classifier() -> LogClassifier {
+        LogClassifier::new(&[
+            LogParserConfig::Json {
+                level_field: None,
+                message_field: None,
+                timestamp_field: None,
+            },
+            LogParserConfig::Regex {
+                pattern: DEFAULT_LOG_LEVEL_PATTERN.to_string(),
+            },
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_classifies_plain_and_json_lines() {
+        let classifier = default_classifier();
+
+        let parsed = classifier.classify("INFO: server listening").unwrap();
+        assert_eq!(parsed.level, "info");
+        assert_eq!(parsed.message.as_deref(), Some("server listening"));
+
+        let parsed = classifier.classify("[WARNING] cache miss").unwrap();
+        assert_eq!(parsed.level, "warn");
+
+        let parsed = classifier
+            .classify("2025-01-01T12:00:00Z DEBUG loading tools")
+            .unwrap();
+        assert_eq!(parsed.level, "debug");
+        assert_eq!(
+            parsed.timestamp,
+            Some(Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap())
+        );
+
+        let parsed = classifier
+            .classify(r#"{"level":50,"time":1735732800000,"msg":"boom"}"#)
+            .unwrap();
+        assert_eq!(parsed.level, "error");
+        assert_eq!(parsed.message.as_deref(), Some("boom"));
+        assert!(parsed.timestamp.is_some());
+
+        assert!(classifier.classify("Starting server...").is_none());
+        assert!(classifier
+            .classify(r#"{"jsonrpc":"2.0","method":"notifications/message"}"#)
+            .is_none());
+
+        let entry = classifier.entry("Starting server...", "error", Utc::now());
+        assert_eq!(entry.level, "error");
+        assert_eq!(entry.message, "Starting server...");
+    }
+
+    #[test]
+    fn test_custom_parsers() {
+        let classifier = LogClassifier::new(&[
+            LogParserConfig::Json {
+                level_field: Some("log.level".to_string()),
+                message_field: Some("text".to_string()),
+                timestamp_field: None,
+            },
+            LogParserConfig::Regex {
+                pattern: r"^(?P<level>[EWI]) (?P<message>.*)$".to_string(),
+            },
+        ])
+        .unwrap();
+
+        let parsed = classifier
+            .classify(r#"{"log":{"level":"warn"},"text":"disk low"}"#)
+            .unwrap();
+        assert_eq!(parsed.level, "warn");
+        assert_eq!(parsed.message.as_deref(), Some("disk low"));
+
+        let parsed = classifier.classify("E failed to bind").unwrap();
+        assert_eq!(parsed.level, "error");
+        assert_eq!(parsed.message.as_deref(), Some("failed to bind"));
+
+        // Levels outside the known names are not classified
+        assert!(classifier.classify("X unknown").is_none());
+
+        assert!(LogClassifier::new(&[LogParserConfig::Regex {
+            pattern: "(?P<message>.*)".to_string()
+        }])
+        .is_err());
+    }
+}
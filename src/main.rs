@@ -2,15 +2,18 @@ use clap::Parser;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::signal;
-use tracing::{error, info};
+use tracing::{error, info, Instrument};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 
+use mcp_rust_proxy::catalog::docs::DocFormat;
+use mcp_rust_proxy::check::{CheckOptions, OutputFormat};
 use mcp_rust_proxy::commands;
 use mcp_rust_proxy::config;
 use mcp_rust_proxy::error::Result;
-use mcp_rust_proxy::proxy::ProxyServer;
-use mcp_rust_proxy::server::ServerManager;
-use mcp_rust_proxy::state::AppState;
-use mcp_rust_proxy::web;
+use mcp_rust_proxy::proxy::{RequestContext, RequestHandler, TraceContext};
+use mcp_rust_proxy::recording::Direction;
+use mcp_rust_proxy::startup::{self, StartOptions};
+use mcp_rust_proxy::state::{AppState, ServerState};
 
 #[derive(Parser, Debug)]
 #[command(name = "mcp-rust-proxy")]
@@ -30,6 +33,15 @@ struct Args {
     /// Run in stdio mode (for use as MCP server with Claude CLI)
     #[arg(long, global = true)]
     stdio: bool,
+
+    /// Write nothing to disk: no server log files, context tracing in
+    /// memory, nothing under ~/.mcp-proxy
+    #[arg(long, global = true)]
+    ephemeral: bool,
+
+    /// Record every JSON-RPC message of the session to FILE, for `replay`
+    #[arg(long, value_name = "FILE", global = true)]
+    record: Option<PathBuf>,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -41,7 +53,146 @@ enum Command {
         /// Test ping support for all servers
         #[arg(long)]
         ping: bool,
+
+        /// Only check servers carrying this tag
+        #[arg(long, value_name = "TAG")]
+        tag: Option<String>,
+
+        /// Compare the pinged servers' tools, resources and prompts with
+        /// the snapshot in this file, failing when any were removed or
+        /// changed; the file is written if missing. Implies --ping.
+        #[arg(long, value_name = "FILE")]
+        snapshot: Option<PathBuf>,
+
+        /// Accept the differences and save them to the --snapshot file
+        #[arg(long, requires = "snapshot")]
+        update_snapshot: bool,
+
+        /// How to report the results; `json` prints one document on stdout
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+
+        /// Longest each server's ping may take, e.g. 30s or 500ms
+        #[arg(long, default_value = "30s", value_parser = mcp_rust_proxy::bench::parse_duration)]
+        timeout: std::time::Duration,
+    },
+    /// Replay the backend requests of a recorded session against the
+    /// configured servers and report responses that changed
+    Replay {
+        /// Recording made with `--record`
+        file: PathBuf,
     },
+    /// Call a tool from many concurrent workers and report throughput and
+    /// latency percentiles per stage (routing, plugins, backend)
+    Bench {
+        /// Tool to call, as listed to clients
+        #[arg(long)]
+        tool: String,
+
+        /// Arguments of each call, as JSON
+        #[arg(long, default_value = "{}", value_parser = parse_json)]
+        arguments: serde_json::Value,
+
+        /// Calls in flight at once
+        #[arg(long, default_value_t = 10)]
+        concurrency: usize,
+
+        /// How long to run, e.g. 60s, 2m or 500ms
+        #[arg(long, default_value = "30s", value_parser = mcp_rust_proxy::bench::parse_duration)]
+        duration: std::time::Duration,
+    },
+    /// Search the MCP registry for servers
+    Search {
+        query: String,
+
+        /// Registry to search
+        #[arg(long, default_value = mcp_rust_proxy::registry::DEFAULT_REGISTRY_URL)]
+        registry: String,
+    },
+    /// Add a server from the MCP registry to the configuration file
+    Add {
+        /// Registry name of the server, as `search` lists it
+        server: String,
+
+        /// Name of the entry under `servers` (default: from the registry name)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Add without asking
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Registry to fetch the server from
+        #[arg(long, default_value = mcp_rust_proxy::registry::DEFAULT_REGISTRY_URL)]
+        registry: String,
+    },
+    /// Manage the context tracing database
+    Trace {
+        #[command(subcommand)]
+        command: TraceCommand,
+    },
+    /// Export the aggregated tools, resources and prompts, or serve an
+    /// exported catalog without any backend
+    Catalog {
+        #[command(subcommand)]
+        command: CatalogCommand,
+    },
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum TraceCommand {
+    /// Upgrade the database schema to this release's version
+    Migrate {
+        /// List the migrations that would run without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum CatalogCommand {
+    /// Start the configured servers and write everything the proxy lists,
+    /// schemas included, as a JSON bundle
+    Export {
+        /// File to write (default: stdout)
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Longest to wait for the servers to start, e.g. 30s or 2m
+        #[arg(long, default_value = "30s", value_parser = mcp_rust_proxy::bench::parse_duration)]
+        timeout: std::time::Duration,
+    },
+    /// Render the tools, resources and prompts as documentation, grouped
+    /// by server, with each tool's parameters in a table
+    Docs {
+        /// Catalog written by `catalog export` (default: start the
+        /// configured servers and document what they offer)
+        #[arg(long, value_name = "FILE")]
+        from: Option<PathBuf>,
+
+        /// Document format
+        #[arg(long, value_enum, default_value_t = DocFormat::Markdown)]
+        format: DocFormat,
+
+        /// File to write (default: stdout)
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Longest to wait for the servers to start, without --from
+        #[arg(long, default_value = "30s", value_parser = mcp_rust_proxy::bench::parse_duration)]
+        timeout: std::time::Duration,
+    },
+    /// Run the proxy with the lists of an exported catalog instead of the
+    /// configured servers; tools, resources and prompts can be listed but
+    /// not used
+    Serve {
+        /// Catalog written by `catalog export`
+        file: PathBuf,
+    },
+}
+
+fn parse_json(text: &str) -> std::result::Result<serde_json::Value, String> {
+    serde_json::from_str(text).map_err(|e| e.to_string())
 }
 
 #[tokio::main]
@@ -51,15 +202,58 @@ async fn main() -> Result<()> {
 
     // Initialize tracing
     let log_level = if args.debug { "debug" } else { "info" };
+    // A JSON check report, exported catalog or documentation owns stdout, so logs go to stderr instead
+    let writer = match &args.command {
+        Some(Command::Check {
+            output: OutputFormat::Json,
+            ..
+        })
+        | Some(Command::Catalog {
+            command:
+                CatalogCommand::Export { output: None, .. } | CatalogCommand::Docs { output: None, .. },
+        }) => BoxMakeWriter::new(std::io::stderr),
+        _ => BoxMakeWriter::new(std::io::stdout),
+    };
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::from_default_env()
                 .add_directive(format!("mcp_rust_proxy={log_level}").parse().unwrap()),
         )
+        .with_writer(writer)
         .init();
 
+    // Registry commands edit the configuration rather than run it, and
+    // documenting a saved catalog needs no configuration
+    match args.command {
+        Some(Command::Search { query, registry }) => {
+            return commands::run_search(&registry, &query).await;
+        }
+        Some(Command::Add {
+            server,
+            name,
+            yes,
+            registry,
+        }) => {
+            let path = args.config.unwrap_or_else(config::default_config_file);
+            return commands::run_add(&registry, &server, &path, name, yes).await;
+        }
+        Some(Command::Catalog {
+            command:
+                CatalogCommand::Docs {
+                    from: Some(file),
+                    format,
+                    output,
+                    ..
+                },
+        }) => {
+            let catalog = mcp_rust_proxy::catalog::Catalog::read(&file)?;
+            return commands::write_catalog_docs(&catalog, format, output.as_deref());
+        }
+        _ => {}
+    }
+
     // Load configuration first
-    let config = match args.config {
+    let mut config = match args.config {
         Some(path) => {
             info!("Loading configuration from: {}", path.display());
             match config::load_from_path(&path).await {
@@ -92,19 +286,98 @@ async fn main() -> Result<()> {
         }
     };
 
+    if args.ephemeral {
+        config.make_ephemeral();
+    }
+
+    // `catalog serve` runs the proxy on a saved catalog, without servers
+    let static_catalog = match &args.command {
+        Some(Command::Catalog {
+            command: CatalogCommand::Serve { file },
+        }) => {
+            let catalog = mcp_rust_proxy::catalog::Catalog::read(file)?;
+            info!(
+                "Serving the catalog exported at {} from {}",
+                catalog.exported_at,
+                file.display()
+            );
+            config.servers.clear();
+            Some(Arc::new(catalog))
+        }
+        _ => None,
+    };
+
     // Handle commands
     match args.command.unwrap_or(Command::Run) {
-        Command::Check { ping } => {
+        Command::Check {
+            ping,
+            tag,
+            snapshot,
+            update_snapshot,
+            output,
+            timeout,
+        } => {
             // Run config check
-            return commands::run_config_check(config, ping).await;
+            let options = CheckOptions {
+                ping: ping || snapshot.is_some(),
+                tag,
+                snapshot,
+                update_snapshot,
+                timeout,
+                format: output,
+            };
+            return commands::run_config_check(config, options).await;
+        }
+        Command::Replay { file } => {
+            return commands::run_replay(config, &file).await;
+        }
+        Command::Bench {
+            tool,
+            arguments,
+            concurrency,
+            duration,
+        } => {
+            let options = mcp_rust_proxy::bench::BenchOptions {
+                tool,
+                arguments,
+                concurrency,
+                duration,
+            };
+            return commands::run_bench(config, options).await;
         }
-        Command::Run => {
+        Command::Trace {
+            command: TraceCommand::Migrate { dry_run },
+        } => {
+            return commands::run_trace_migrate(config, dry_run).await;
+        }
+        Command::Catalog {
+            command: CatalogCommand::Export { output, timeout },
+        } => {
+            return commands::run_catalog_export(config, output.as_deref(), timeout).await;
+        }
+        Command::Catalog {
+            command:
+                CatalogCommand::Docs {
+                    format,
+                    output,
+                    timeout,
+                    ..
+                },
+        } => {
+            let catalog = commands::collect_catalog(config, timeout).await?;
+            return commands::write_catalog_docs(&catalog, format, output.as_deref());
+        }
+        Command::Search { .. } | Command::Add { .. } => unreachable!("handled above"),
+        Command::Run
+        | Command::Catalog {
+            command: CatalogCommand::Serve { .. },
+        } => {
             // Check if stdio mode is enabled
             if args.stdio {
                 info!("Starting MCP Rust Proxy in stdio mode");
                 info!("Loaded {} server configurations", config.servers.len());
                 // In stdio mode, run the stdio server instead of HTTP
-                return run_stdio_mode(config).await;
+                return run_stdio_mode(config, args.record, static_catalog).await;
             } else {
                 // Continue with normal HTTP server startup
                 info!("Starting MCP Rust Proxy Server");
@@ -124,132 +397,97 @@ async fn main() -> Result<()> {
     }
 
     // Initialize application state
-    let (state, shutdown_rx) = AppState::new(config.clone());
-
-    // Discover and load plugins if configured
-    if let Some(plugin_manager) = &state.plugin_manager {
-        info!(
-            "Discovering plugins from: {}",
-            config.plugins.as_ref().unwrap().plugin_dir.display()
-        );
-        match plugin_manager.discover_plugins().await {
-            Ok(count) => {
-                info!("Discovered {} plugins successfully", count);
-            }
-            Err(e) => {
-                error!("Failed to discover plugins: {}", e);
-            }
-        }
+    let (state, _shutdown_rx) = AppState::new(config);
+    if let Some(path) = &args.record {
+        start_recording(&state, path)?;
+    }
+    if let Some(catalog) = static_catalog {
+        state.serve_catalog(catalog);
     }
 
-    // Initialize context tracker if enabled
-    if config.context_tracing.enabled {
-        info!("Initializing context tracing framework");
-        match mcp_rust_proxy::context::storage::HybridStorage::new(
-            config.context_tracing.sqlite_path.clone(),
-            Some(mcp_rust_proxy::context::storage::CacheConfig {
-                max_entries: config.context_tracing.cache_size,
-                ttl_seconds: config.context_tracing.cache_ttl_seconds,
-                eviction_strategy: mcp_rust_proxy::context::storage::EvictionStrategy::TimeBasedLRU,
-            }),
-        )
-        .await
-        {
-            Ok(storage) => {
-                let storage: Arc<dyn mcp_rust_proxy::context::storage::StorageBackend> =
-                    Arc::new(storage);
-                if let Err(e) = state.initialize_context_tracker(storage.clone()).await {
-                    error!("Failed to initialize context tracker: {}", e);
-                } else {
-                    info!("Context tracing initialized successfully");
-
-                    // Start background retention job
-                    let retention_days = config.context_tracing.retention_days;
-                    let storage_for_cleanup = storage.clone();
-                    tokio::spawn(async move {
-                        let mut interval =
-                            tokio::time::interval(tokio::time::Duration::from_secs(86400)); // Daily
-                        loop {
-                            interval.tick().await;
-                            info!(
-                                "Running context tracing retention policy ({}d)",
-                                retention_days
-                            );
-                            match storage_for_cleanup.cleanup_old_data(retention_days).await {
-                                Ok(deleted) => {
-                                    info!("Retention policy deleted {} old records", deleted);
-                                }
-                                Err(e) => {
-                                    error!("Retention policy failed: {}", e);
-                                }
-                            }
-                        }
-                    });
-                    info!("Background retention job started (runs daily)");
+    let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+    let handler = Arc::new(RequestHandler::new(state.clone()));
+    let tasks = startup::start(
+        &state,
+        handler,
+        StartOptions {
+            serve_http: true,
+            started_tx: Some(started_tx),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    // Tell systemd the proxy is up once its servers have started, and keep
+    // its watchdog fed
+    let notify_state = state.clone();
+    tokio::spawn(async move {
+        if started_rx.await.is_ok() {
+            let names: Vec<String> = notify_state
+                .servers
+                .iter()
+                .map(|entry| entry.key().clone())
+                .collect();
+            let mut running = 0;
+            for name in &names {
+                if notify_state.get_server_state(name).await == Some(ServerState::Running) {
+                    running += 1;
                 }
             }
-            Err(e) => {
-                error!("Failed to create context storage: {}", e);
-            }
+            mcp_rust_proxy::systemd::notify(&format!(
+                "READY=1\nSTATUS={} of {} servers running",
+                running,
+                names.len()
+            ));
         }
+    });
+    if let Some(interval) = mcp_rust_proxy::systemd::watchdog_interval() {
+        tokio::spawn(mcp_rust_proxy::systemd::run_watchdog(
+            interval,
+            state.shutdown_tx.subscribe(),
+        ));
     }
 
-    // Start server manager
-    let server_manager = ServerManager::new(state.clone(), shutdown_rx.resubscribe());
-    let manager_handle = tokio::spawn(async move {
-        if let Err(e) = server_manager.run().await {
-            error!("Server manager error: {}", e);
-        }
-    });
+    // Wait for shutdown signal
+    shutdown_signal().await;
 
-    // Start proxy server
-    let proxy_server = ProxyServer::new(state.clone());
-    let proxy_handle = tokio::spawn(async move {
-        if let Err(e) = proxy_server.run().await {
-            error!("Proxy server error: {}", e);
-        }
-    });
+    info!("Shutting down MCP Rust Proxy Server");
+    mcp_rust_proxy::systemd::notify("STOPPING=1");
 
-    // Start cache warmer for instant tool/resource availability
-    let cache_warmer_state = state.clone();
-    let _cache_warmer_handler = Arc::new(mcp_rust_proxy::proxy::RequestHandler::new(state.clone()));
-    let _cache_warmer_handle = tokio::spawn(async move {
-        let warmer = mcp_rust_proxy::proxy::cache_warmer::CacheWarmer::new(
-            cache_warmer_state,
-            _cache_warmer_handler,
-            60, // Refresh every 60 seconds
+    // Fail readiness first and keep serving while load balancers catch up
+    state.drain.mark_terminating();
+    let shutdown_delay = state.config.read().await.readiness.shutdown_delay();
+    if !shutdown_delay.is_zero() {
+        info!(
+            "Taking requests for {:?} more while reporting not ready",
+            shutdown_delay
         );
-        warmer.run().await;
-    });
-
-    // Start web UI if enabled
-    let web_handle = if state.config.read().await.web_ui.enabled {
-        let web_state = state.clone();
-        Some(tokio::spawn(async move {
-            if let Err(e) = web::start_server(web_state).await {
-                error!("Web UI server error: {}", e);
-            }
-        }))
-    } else {
-        None
-    };
+        tokio::time::sleep(shutdown_delay).await;
+    }
 
-    // Wait for shutdown signal
-    shutdown_signal().await;
+    shutdown_gracefully(&state, tasks).await;
+    Ok(())
+}
 
-    info!("Shutting down MCP Rust Proxy Server");
+/// Stop the proxy the same way whatever the mode: finish in-flight
+/// requests, flush logs and context storage, then stop the servers in
+/// reverse dependency order and wait for `tasks` to finish. Exits the
+/// process if that takes longer than 30 seconds.
+async fn shutdown_gracefully(state: &AppState, tasks: Vec<tokio::task::JoinHandle<()>>) {
+    // Stop taking requests and let in-flight ones finish before anything
+    // they depend on goes away
+    state.drain_requests().await;
 
-    // Graceful shutdown with timeout
     let shutdown_timeout = tokio::time::timeout(tokio::time::Duration::from_secs(30), async {
-        // Signal shutdown to all components
+        // Signal shutdown to all components; servers stop in reverse
+        // dependency order
         state.shutdown().await;
 
         // Wait for tasks to complete
-        if let Some(web_handle) = web_handle {
-            let _ = tokio::join!(manager_handle, proxy_handle, web_handle);
-        } else {
-            let _ = tokio::join!(manager_handle, proxy_handle);
-        }
+        futures::future::join_all(tasks).await;
+
+        // Close anything the server manager did not know about
+        let _ = state.connection_pool.close_all().await;
     })
     .await;
 
@@ -263,111 +501,106 @@ async fn main() -> Result<()> {
             std::process::exit(1);
         }
     }
+}
 
+/// Record the session to `path`
+fn start_recording(state: &AppState, path: &std::path::Path) -> Result<()> {
+    let recorder = mcp_rust_proxy::recording::SessionRecorder::create(path)?;
+    state.record_session(Arc::new(recorder));
+    info!("Recording session to {}", path.display());
     Ok(())
 }
 
-async fn run_stdio_mode(config: mcp_rust_proxy::config::Config) -> Result<()> {
-    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+/// Write one message to stdout. Responses and notifications share stdout,
+/// so each is written whole under the lock.
+async fn write_line(
+    writer: &tokio::sync::Mutex<tokio::io::Stdout>,
+    line: &[u8],
+) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut writer = writer.lock().await;
+    writer.write_all(line).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await
+}
 
-    // Initialize application state
-    let (state, _shutdown_rx) = AppState::new(config.clone());
+/// Resolves once stdin reaches end of file. Input still to be read, such as
+/// the next request, is left in the buffer.
+async fn stdin_closed(reader: &mut tokio::io::BufReader<tokio::io::Stdin>) {
+    use tokio::io::AsyncBufReadExt;
 
-    // Discover and load plugins if configured
-    if let Some(plugin_manager) = &state.plugin_manager {
-        info!(
-            "Discovering plugins from: {}",
-            config.plugins.as_ref().unwrap().plugin_dir.display()
-        );
-        match plugin_manager.discover_plugins().await {
-            Ok(count) => {
-                info!("Discovered {} plugins successfully", count);
-            }
-            Err(e) => {
-                error!("Failed to discover plugins: {}", e);
-            }
-        }
+    match reader.fill_buf().await {
+        Ok([]) | Err(_) => {}
+        Ok(_) => std::future::pending().await,
     }
+}
 
-    // Initialize context tracker if enabled
-    if config.context_tracing.enabled {
-        info!("Initializing context tracing framework");
-        match mcp_rust_proxy::context::storage::HybridStorage::new(
-            config.context_tracing.sqlite_path.clone(),
-            Some(mcp_rust_proxy::context::storage::CacheConfig {
-                max_entries: config.context_tracing.cache_size,
-                ttl_seconds: config.context_tracing.cache_ttl_seconds,
-                eviction_strategy: mcp_rust_proxy::context::storage::EvictionStrategy::TimeBasedLRU,
-            }),
-        )
-        .await
-        {
-            Ok(storage) => {
-                let storage: Arc<dyn mcp_rust_proxy::context::storage::StorageBackend> =
-                    Arc::new(storage);
-                if let Err(e) = state.initialize_context_tracker(storage.clone()).await {
-                    error!("Failed to initialize context tracker: {}", e);
-                } else {
-                    info!("Context tracing initialized successfully");
-
-                    // Start background retention job
-                    let retention_days = config.context_tracing.retention_days;
-                    let storage_for_cleanup = storage.clone();
-                    tokio::spawn(async move {
-                        let mut interval =
-                            tokio::time::interval(tokio::time::Duration::from_secs(86400)); // Daily
-                        loop {
-                            interval.tick().await;
-                            info!(
-                                "Running context tracing retention policy ({}d)",
-                                retention_days
-                            );
-                            match storage_for_cleanup.cleanup_old_data(retention_days).await {
-                                Ok(deleted) => {
-                                    info!("Retention policy deleted {} old records", deleted);
-                                }
-                                Err(e) => {
-                                    error!("Retention policy failed: {}", e);
-                                }
-                            }
-                        }
-                    });
-                    info!("Background retention job started (runs daily)");
-                }
-            }
-            Err(e) => {
-                error!("Failed to create context storage: {}", e);
-            }
-        }
+async fn run_stdio_mode(
+    config: mcp_rust_proxy::config::Config,
+    record: Option<PathBuf>,
+    static_catalog: Option<Arc<mcp_rust_proxy::catalog::Catalog>>,
+) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    // Initialize application state
+    let (state, _shutdown_rx) = AppState::new(config);
+    if let Some(path) = &record {
+        start_recording(&state, path)?;
+    }
+    if let Some(catalog) = static_catalog {
+        state.serve_catalog(catalog);
     }
 
-    // Start server manager in background
-    let server_manager = ServerManager::new(state.clone(), state.shutdown_tx.subscribe());
-    tokio::spawn(async move {
-        if let Err(e) = server_manager.run().await {
-            error!("Server manager error: {}", e);
-        }
-    });
+    let handler = Arc::new(RequestHandler::new(state.clone()));
+    let tasks = startup::start(&state, handler.clone(), StartOptions::default()).await;
 
     // Give servers time to start
     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
 
-    // Create proxy components
-    let router = std::sync::Arc::new(mcp_rust_proxy::proxy::RequestRouter::new());
-    let handler = std::sync::Arc::new(mcp_rust_proxy::proxy::RequestHandler::new(state.clone()));
+    let router = state.request_router.clone();
 
     info!("Stdio mode ready - reading from stdin, writing to stdout");
 
     // Read from stdin, write to stdout
     let stdin = tokio::io::stdin();
-    let stdout = tokio::io::stdout();
     let mut reader = BufReader::new(stdin);
-    let mut writer = stdout;
+    let writer = Arc::new(tokio::sync::Mutex::new(tokio::io::stdout()));
     let mut line = String::new();
 
+    // Tell the client when servers come up or go away
+    let mut notifications = state.client_notification_tx.subscribe();
+    let notification_writer = writer.clone();
+    tokio::spawn(async move {
+        loop {
+            match notifications.recv().await {
+                Ok(notification) => {
+                    let line = notification.to_string();
+                    if write_line(&notification_writer, line.as_bytes())
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    });
+
+    // A signal stops the proxy as closing stdin does, rather than leaving
+    // the servers' processes behind
+    let signal = shutdown_signal();
+    tokio::pin!(signal);
+
     loop {
         line.clear();
-        match reader.read_line(&mut line).await {
+        let read = tokio::select! {
+            read = reader.read_line(&mut line) => read,
+            () = &mut signal => break,
+        };
+        match read {
             Ok(0) => {
                 // EOF - client disconnected
                 info!("Stdin closed, shutting down");
@@ -382,41 +615,70 @@ async fn run_stdio_mode(config: mcp_rust_proxy::config::Config) -> Result<()> {
                 // Parse JSON-RPC request
                 match serde_json::from_str::<serde_json::Value>(trimmed) {
                     Ok(request) => {
-                        // Handle the request
-                        match handler.handle_request(request, router.clone()).await {
+                        let ctx = RequestContext {
+                            trace: TraceContext::from_request(trimmed.as_bytes()),
+                            ..Default::default()
+                        };
+                        let span = ctx.span();
+                        let recorder = state.recorder();
+                        if let Some(recorder) = recorder {
+                            recorder.client(
+                                Direction::ClientRequest,
+                                &ctx.correlation_id,
+                                trimmed.as_bytes(),
+                            );
+                        }
+
+                        // Handle the request, giving it up if the client goes away
+                        let handled = handler
+                            .handle_request(request, router.clone(), &ctx)
+                            .instrument(span);
+                        let result = tokio::select! {
+                            result = handled => result,
+                            () = stdin_closed(&mut reader) => {
+                                info!(
+                                    correlation_id = %ctx.correlation_id,
+                                    "Stdin closed before the reply, cancelling the request"
+                                );
+                                break;
+                            }
+                        };
+                        match result {
                             Ok(response) => {
                                 // Write response to stdout
+                                let response = response.with_context(&ctx);
                                 let response_json = serde_json::to_string(&response).unwrap();
-                                if let Err(e) = writer.write_all(response_json.as_bytes()).await {
-                                    error!("Failed to write response: {}", e);
-                                    break;
+                                if let Some(recorder) = recorder {
+                                    recorder.client(
+                                        Direction::ClientResponse,
+                                        &ctx.correlation_id,
+                                        response_json.as_bytes(),
+                                    );
                                 }
-                                if let Err(e) = writer.write_all(b"\n").await {
-                                    error!("Failed to write newline: {}", e);
-                                    break;
-                                }
-                                if let Err(e) = writer.flush().await {
-                                    error!("Failed to flush: {}", e);
+                                if let Err(e) = write_line(&writer, response_json.as_bytes()).await
+                                {
+                                    error!("Failed to write response: {}", e);
                                     break;
                                 }
                             }
                             Err(e) => {
-                                error!("Error handling request: {}", e);
+                                error!(
+                                    correlation_id = %ctx.correlation_id,
+                                    "Error handling request: {}", e
+                                );
                                 // Send error response
-                                let error_response = mcp_rust_proxy::proxy::MCPResponse {
-                                    jsonrpc: "2.0".to_string(),
-                                    id: None,
-                                    result: None,
-                                    error: Some(mcp_rust_proxy::proxy::MCPError {
-                                        code: -32603,
-                                        message: e.to_string(),
-                                        data: None,
-                                    }),
-                                };
+                                let error_response =
+                                    mcp_rust_proxy::proxy::MCPResponse::error(None, &e)
+                                        .with_context(&ctx);
                                 let response_json = serde_json::to_string(&error_response).unwrap();
-                                let _ = writer.write_all(response_json.as_bytes()).await;
-                                let _ = writer.write_all(b"\n").await;
-                                let _ = writer.flush().await;
+                                if let Some(recorder) = recorder {
+                                    recorder.client(
+                                        Direction::ClientResponse,
+                                        &ctx.correlation_id,
+                                        response_json.as_bytes(),
+                                    );
+                                }
+                                let _ = write_line(&writer, response_json.as_bytes()).await;
                             }
                         }
                     }
@@ -434,9 +696,7 @@ async fn run_stdio_mode(config: mcp_rust_proxy::config::Config) -> Result<()> {
                             }),
                         };
                         let response_json = serde_json::to_string(&error_response).unwrap();
-                        let _ = writer.write_all(response_json.as_bytes()).await;
-                        let _ = writer.write_all(b"\n").await;
-                        let _ = writer.flush().await;
+                        let _ = write_line(&writer, response_json.as_bytes()).await;
                     }
                 }
             }
@@ -447,6 +707,7 @@ async fn run_stdio_mode(config: mcp_rust_proxy::config::Config) -> Result<()> {
         }
     }
 
+    shutdown_gracefully(&state, tasks).await;
     info!("Stdio mode exiting");
     Ok(())
 }
@@ -230,6 +230,7 @@ mod tests {
                 user_query: None,
                 tool_arguments: None,
                 mcp_servers: None,
+                correlation_id: None,
             },
         };
 
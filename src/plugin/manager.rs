@@ -5,14 +5,19 @@
 use crate::plugin::config::{PluginAssignment, PluginConfig};
 use crate::plugin::process::ProcessPool;
 use crate::plugin::schema::{PluginError, PluginInput, PluginOutput, PluginPhase};
-use crate::state::Metrics;
+use crate::state::{AlertEvent, AlertKind, Metrics};
+use chrono::{DateTime, Utc};
 use dashmap::DashMap;
+use serde::Serialize;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::Semaphore;
+use tokio::sync::{broadcast, Semaphore};
 use tokio::time::{timeout, Duration};
 
+/// Consecutive failed executions after which a plugin is reported as failing
+const FAILURE_ALERT_STREAK: u32 = 10;
+
 /// Plugin metadata
 #[derive(Debug, Clone)]
 pub struct Plugin {
@@ -26,6 +31,19 @@ pub struct Plugin {
     pub timeout_ms: u64,
 }
 
+/// How a plugin's executions for one server have gone since startup
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginExecutionStats {
+    pub executions: u64,
+    /// Failed executions, including timeouts
+    pub failures: u64,
+    pub timeouts: u64,
+    pub avg_duration_ms: f64,
+    pub last_execution: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
 /// Manages plugin lifecycle and concurrency
 pub struct PluginManager {
     /// Global plugin configuration
@@ -38,6 +56,12 @@ pub struct PluginManager {
     semaphore: Arc<Semaphore>,
     /// Metrics for observability
     metrics: Option<Arc<Metrics>>,
+    /// Consecutive failures per (plugin, server)
+    failure_streaks: DashMap<(String, String), u32>,
+    /// Execution outcomes per (plugin, server)
+    execution_stats: DashMap<(String, String), PluginExecutionStats>,
+    /// Where failing plugins are reported
+    alert_tx: Option<broadcast::Sender<AlertEvent>>,
 }
 
 impl PluginManager {
@@ -51,6 +75,9 @@ impl PluginManager {
             pools: Arc::new(DashMap::new()),
             semaphore: Arc::new(Semaphore::new(max_concurrent)),
             metrics: None,
+            failure_streaks: DashMap::new(),
+            execution_stats: DashMap::new(),
+            alert_tx: None,
         }
     }
 
@@ -59,6 +86,11 @@ impl PluginManager {
         self.metrics = Some(metrics);
     }
 
+    /// Report plugins that fail every execution for a server as alerts
+    pub fn set_alerts(&mut self, alert_tx: broadcast::Sender<AlertEvent>) {
+        self.alert_tx = Some(alert_tx);
+    }
+
     /// Discover and load plugins from the plugin directory
     pub async fn discover_plugins(&self) -> Result<usize, PluginError> {
         let plugin_dir = &self.config.plugin_dir;
@@ -260,9 +292,81 @@ impl PluginManager {
             }
         };
 
+        self.record_execution(
+            plugin_name,
+            server_name,
+            start.elapsed(),
+            output.as_ref().err(),
+        );
+        self.record_outcome(plugin_name, server_name, output.is_ok());
         output
     }
 
+    fn record_execution(
+        &self,
+        plugin_name: &str,
+        server_name: &str,
+        duration: std::time::Duration,
+        error: Option<&PluginError>,
+    ) {
+        let mut stats = self
+            .execution_stats
+            .entry((plugin_name.to_string(), server_name.to_string()))
+            .or_default();
+        let total_ms = stats.avg_duration_ms * stats.executions as f64;
+        stats.executions += 1;
+        stats.avg_duration_ms =
+            (total_ms + duration.as_secs_f64() * 1000.0) / stats.executions as f64;
+        stats.last_execution = Some(Utc::now());
+        if let Some(error) = error {
+            stats.failures += 1;
+            if matches!(error, PluginError::Timeout { .. }) {
+                stats.timeouts += 1;
+            }
+            stats.last_error = Some(error.to_string());
+        }
+    }
+
+    /// Execution outcomes of a plugin for a server, if it has run for it
+    pub fn execution_stats(
+        &self,
+        plugin_name: &str,
+        server_name: &str,
+    ) -> Option<PluginExecutionStats> {
+        self.execution_stats
+            .get(&(plugin_name.to_string(), server_name.to_string()))
+            .map(|stats| stats.clone())
+    }
+
+    /// Track consecutive failures, alerting once when a plugin has failed
+    /// `FAILURE_ALERT_STREAK` times in a row for a server
+    fn record_outcome(&self, plugin_name: &str, server_name: &str, succeeded: bool) {
+        let key = (plugin_name.to_string(), server_name.to_string());
+        if succeeded {
+            self.failure_streaks.remove(&key);
+            return;
+        }
+
+        let streak = {
+            let mut streak = self.failure_streaks.entry(key).or_insert(0);
+            *streak += 1;
+            *streak
+        };
+        if streak != FAILURE_ALERT_STREAK {
+            return;
+        }
+        if let Some(alert_tx) = &self.alert_tx {
+            let _ = alert_tx.send(AlertEvent {
+                server: server_name.to_string(),
+                kind: AlertKind::PluginFailing,
+                message: format!(
+                    "Plugin {plugin_name} failed its last {streak} executions for server {server_name}"
+                ),
+                timestamp: Utc::now(),
+            });
+        }
+    }
+
     /// Execute a plugin assignment (with config-specific timeout)
     pub async fn execute_assignment(
         &self,
@@ -280,6 +384,26 @@ impl PluginManager {
         }
     }
 
+    /// Names of the discovered plugins, sorted
+    pub fn plugin_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.plugins.iter().map(|p| p.key().clone()).collect();
+        names.sort();
+        names
+    }
+
+    /// Rediscover plugins and retire warm processes, so edited plugin files
+    /// take effect on the next execution
+    pub async fn reload(&self) -> Result<usize, PluginError> {
+        let pools: Vec<Arc<ProcessPool>> = self.pools.iter().map(|p| p.value().clone()).collect();
+        self.pools.clear();
+        for pool in pools {
+            pool.shutdown().await;
+        }
+
+        self.plugins.clear();
+        self.discover_plugins().await
+    }
+
     /// Get plugin count
     pub fn plugin_count(&self) -> usize {
         self.plugins.len()
@@ -374,6 +498,7 @@ rl.on('line', async (line) => {
                 user_query: None,
                 tool_arguments: None,
                 mcp_servers: None,
+                correlation_id: None,
             },
         };
 
@@ -421,6 +546,7 @@ rl.on('line', () => {
                 user_query: None,
                 tool_arguments: None,
                 mcp_servers: None,
+                correlation_id: None,
             },
         };
 
@@ -433,4 +559,57 @@ rl.on('line', () => {
             panic!("Expected timeout error");
         }
     }
+
+    #[test]
+    fn test_alerts_once_per_failure_streak() {
+        let mut manager = PluginManager::new(PluginConfig {
+            plugin_dir: PathBuf::from("/nonexistent"),
+            node_executable: PathBuf::from("node"),
+            max_concurrent_executions: 1,
+            pool_size_per_plugin: 1,
+            default_timeout_ms: 500,
+            servers: std::collections::HashMap::new(),
+        });
+        let (alert_tx, mut alerts) = broadcast::channel(8);
+        manager.set_alerts(alert_tx);
+
+        for _ in 1..FAILURE_ALERT_STREAK {
+            manager.record_outcome("redact", "github", false);
+        }
+        // A success starts the streak over
+        manager.record_outcome("redact", "github", true);
+        for _ in 0..FAILURE_ALERT_STREAK * 2 {
+            manager.record_outcome("redact", "github", false);
+        }
+
+        let alert = alerts.try_recv().unwrap();
+        assert_eq!(alert.kind, AlertKind::PluginFailing);
+        assert_eq!(alert.server, "github");
+        assert!(alerts.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_execution_stats() {
+        let manager = PluginManager::new(PluginConfig::default());
+        assert!(manager.execution_stats("redact", "github").is_none());
+
+        manager.record_execution("redact", "github", Duration::from_millis(10), None);
+        manager.record_execution(
+            "redact",
+            "github",
+            Duration::from_millis(30),
+            Some(&PluginError::Timeout { timeout_ms: 30 }),
+        );
+
+        let stats = manager.execution_stats("redact", "github").unwrap();
+        assert_eq!(stats.executions, 2);
+        assert_eq!(stats.failures, 1);
+        assert_eq!(stats.timeouts, 1);
+        assert!((stats.avg_duration_ms - 20.0).abs() < 0.001);
+        assert_eq!(
+            stats.last_error.as_deref(),
+            Some("Plugin timed out after 30ms")
+        );
+        assert!(manager.execution_stats("redact", "jira").is_none());
+    }
 }
@@ -250,6 +250,7 @@ mod tests {
                 user_query: None,
                 tool_arguments: None,
                 mcp_servers: None,
+                correlation_id: None,
             },
         };
 
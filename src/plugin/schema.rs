@@ -45,6 +45,10 @@ pub struct PluginMetadata {
     /// MCP server configurations (for aggregator plugin)
     #[serde(skip_serializing_if = "Option::is_none", rename = "mcpServers")]
     pub mcp_servers: Option<Vec<serde_json::Value>>,
+
+    /// Correlation ID of the client request the plugin runs for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
 }
 
 /// Input data passed to plugin processes via stdin
@@ -203,6 +207,7 @@ mod tests {
                 user_query: Some("test query".to_string()),
                 tool_arguments: None,
                 mcp_servers: None,
+                correlation_id: None,
             },
         };
 
@@ -265,6 +270,7 @@ mod tests {
                 user_query: None,
                 tool_arguments: None,
                 mcp_servers: None,
+                correlation_id: None,
             },
         };
 
@@ -0,0 +1,158 @@
+/// Draft to V20250618 Adapter
+///
+/// Translates messages from the draft format to 2025-06-18 format
+/// Key differences: Must strip icons, tool execution hints and task support,
+/// which 2025-06-18 does not have
+use crate::protocol::{ProtocolAdapter, ProtocolError, ProtocolVersion};
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// Lists in results whose items may carry icons
+const LISTS_WITH_ICONS: [&str; 4] = ["tools", "resources", "resourceTemplates", "prompts"];
+
+pub struct DraftToV20250618Adapter;
+
+impl Default for DraftToV20250618Adapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DraftToV20250618Adapter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Translate list responses (strip icons, and execution from tools)
+    fn translate_list_response(&self, mut response: Value) -> Result<Value, ProtocolError> {
+        if let Some(result) = response.get_mut("result") {
+            for list in LISTS_WITH_ICONS {
+                if let Some(items) = result.get_mut(list).and_then(|l| l.as_array_mut()) {
+                    for item in items.iter_mut().filter_map(|i| i.as_object_mut()) {
+                        item.remove("icons");
+                        if list == "tools" {
+                            item.remove("execution");
+                        }
+                    }
+                }
+            }
+        }
+        Ok(response)
+    }
+
+    /// Translate initialize response (strip serverInfo icons and the tasks
+    /// capability)
+    fn translate_initialize_response(&self, mut response: Value) -> Result<Value, ProtocolError> {
+        if let Some(result) = response.get_mut("result") {
+            if let Some(server_info) = result.get_mut("serverInfo").and_then(|s| s.as_object_mut())
+            {
+                server_info.remove("icons");
+                server_info.remove("websiteUrl");
+            }
+            if let Some(capabilities) = result
+                .get_mut("capabilities")
+                .and_then(|c| c.as_object_mut())
+            {
+                capabilities.remove("tasks");
+            }
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl ProtocolAdapter for DraftToV20250618Adapter {
+    fn source_version(&self) -> ProtocolVersion {
+        ProtocolVersion::Draft
+    }
+
+    fn target_version(&self) -> ProtocolVersion {
+        ProtocolVersion::V20250618
+    }
+
+    async fn translate_request(&self, mut request: Value) -> Result<Value, ProtocolError> {
+        // Task-augmented requests run as ordinary ones
+        if let Some(params) = request.get_mut("params").and_then(|p| p.as_object_mut()) {
+            params.remove("task");
+        }
+        Ok(request)
+    }
+
+    async fn translate_response(&self, response: Value) -> Result<Value, ProtocolError> {
+        // Infer method from response structure
+        if let Some(result) = response.get("result") {
+            if result.get("serverInfo").is_some() {
+                return self.translate_initialize_response(response);
+            }
+            if LISTS_WITH_ICONS
+                .iter()
+                .any(|list| result.get(list).is_some())
+            {
+                return self.translate_list_response(response);
+            }
+        }
+
+        // For other responses, pass through
+        Ok(response)
+    }
+
+    async fn translate_notification(&self, notification: Value) -> Result<Value, ProtocolError> {
+        // Notifications are compatible
+        Ok(notification)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_strips_draft_fields() {
+        let adapter = DraftToV20250618Adapter::new();
+        assert_eq!(adapter.source_version(), ProtocolVersion::Draft);
+        assert_eq!(adapter.target_version(), ProtocolVersion::V20250618);
+
+        let response = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "tools": [{
+                    "name": "my-tool",
+                    "title": "My Tool",
+                    "icons": [{ "src": "https://example.com/tool.png" }],
+                    "execution": { "taskSupport": "optional" },
+                    "inputSchema": { "type": "object" }
+                }]
+            }
+        });
+        let translated = adapter.translate_response(response).await.unwrap();
+        let tool = &translated["result"]["tools"][0];
+        assert_eq!(tool["title"], "My Tool");
+        assert!(tool.get("icons").is_none());
+        assert!(tool.get("execution").is_none());
+
+        let response = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "result": {
+                "protocolVersion": "DRAFT-2026-v1",
+                "capabilities": { "tools": {}, "tasks": { "list": {} } },
+                "serverInfo": { "name": "s", "version": "1", "icons": [] }
+            }
+        });
+        let translated = adapter.translate_response(response).await.unwrap();
+        assert!(translated["result"]["capabilities"].get("tasks").is_none());
+        assert!(translated["result"]["capabilities"].get("tools").is_some());
+        assert!(translated["result"]["serverInfo"].get("icons").is_none());
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": { "name": "my-tool", "arguments": {}, "task": { "ttl": 60000 } }
+        });
+        let translated = adapter.translate_request(request).await.unwrap();
+        assert!(translated["params"].get("task").is_none());
+    }
+}
@@ -1,51 +1,47 @@
+#[cfg(feature = "draft-protocol")]
+mod draft_to_v20250618;
 mod pass_through;
+mod registry;
 mod v20241105_to_v20250326;
 mod v20241105_to_v20250618;
 mod v20250326_to_v20241105;
 mod v20250326_to_v20250618;
+#[cfg(feature = "draft-protocol")]
+mod v20250618_to_draft;
 mod v20250618_to_v20241105;
 mod v20250618_to_v20250326;
 
+#[cfg(feature = "draft-protocol")]
+pub use draft_to_v20250618::DraftToV20250618Adapter;
 pub use pass_through::PassThroughAdapter;
+pub use registry::{AdapterFactory, AdapterRegistry};
 pub use v20241105_to_v20250326::V20241105ToV20250326Adapter;
 pub use v20241105_to_v20250618::V20241105ToV20250618Adapter;
 pub use v20250326_to_v20241105::V20250326ToV20241105Adapter;
 pub use v20250326_to_v20250618::V20250326ToV20250618Adapter;
+#[cfg(feature = "draft-protocol")]
+pub use v20250618_to_draft::V20250618ToDraftAdapter;
 pub use v20250618_to_v20241105::V20250618ToV20241105Adapter;
 pub use v20250618_to_v20250326::V20250618ToV20250326Adapter;
 
 use crate::protocol::{ProtocolAdapter, ProtocolVersion};
 use std::sync::Arc;
 
-/// Factory function to create the appropriate adapter for a version pair
+/// The adapter for a version pair from the global [`AdapterRegistry`]
 pub fn create_adapter(
     source_version: ProtocolVersion,
     target_version: ProtocolVersion,
 ) -> Arc<dyn ProtocolAdapter> {
-    use ProtocolVersion::*;
-
-    // If versions match, use pass-through adapter (zero overhead)
-    if source_version == target_version {
-        return Arc::new(PassThroughAdapter::new(source_version));
-    }
-
-    // Select appropriate translation adapter based on version pair
-    match (source_version, target_version) {
-        // V20241105 → other versions
-        (V20241105, V20250326) => Arc::new(V20241105ToV20250326Adapter::new()),
-        (V20241105, V20250618) => Arc::new(V20241105ToV20250618Adapter::new()),
-
-        // V20250326 → other versions
-        (V20250326, V20241105) => Arc::new(V20250326ToV20241105Adapter::new()),
-        (V20250326, V20250618) => Arc::new(V20250326ToV20250618Adapter::new()),
-
-        // V20250618 → other versions
-        (V20250618, V20241105) => Arc::new(V20250618ToV20241105Adapter::new()),
-        (V20250618, V20250326) => Arc::new(V20250618ToV20250326Adapter::new()),
-
-        // Same version cases are handled by the if statement above
-        _ => unreachable!("Same-version pairs should have been handled by pass-through check"),
-    }
+    AdapterRegistry::global()
+        .create(source_version, target_version)
+        .unwrap_or_else(|| {
+            tracing::warn!(
+                source = source_version.as_str(),
+                target = target_version.as_str(),
+                "No protocol adapter between versions, passing messages through unchanged"
+            );
+            Arc::new(PassThroughAdapter::new(target_version))
+        })
 }
 
 #[cfg(test)]
@@ -0,0 +1,285 @@
+//! Adapters by the version pair they translate between
+//!
+//! The built-in adapters are registered up front, and applications embedding
+//! the proxy can register their own for new pairs or in place of built-in
+//! ones. A pair with no adapter of its own is translated through a chain of
+//! registered adapters, so a new version only needs adapters to and from a
+//! neighbouring one.
+
+use super::*;
+use crate::protocol::{ProtocolAdapter, ProtocolError, ProtocolVersion};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Makes an adapter for the pair it is registered under
+pub type AdapterFactory = Arc<dyn Fn() -> Arc<dyn ProtocolAdapter> + Send + Sync>;
+
+pub struct AdapterRegistry {
+    factories: RwLock<HashMap<(ProtocolVersion, ProtocolVersion), AdapterFactory>>,
+}
+
+impl Default for AdapterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdapterRegistry {
+    /// A registry holding only the built-in adapters
+    pub fn new() -> Self {
+        use ProtocolVersion::*;
+
+        let registry = Self {
+            factories: RwLock::new(HashMap::new()),
+        };
+        registry.register(V20241105, V20250326, || {
+            Arc::new(V20241105ToV20250326Adapter::new())
+        });
+        registry.register(V20241105, V20250618, || {
+            Arc::new(V20241105ToV20250618Adapter::new())
+        });
+        registry.register(V20250326, V20241105, || {
+            Arc::new(V20250326ToV20241105Adapter::new())
+        });
+        registry.register(V20250326, V20250618, || {
+            Arc::new(V20250326ToV20250618Adapter::new())
+        });
+        registry.register(V20250618, V20241105, || {
+            Arc::new(V20250618ToV20241105Adapter::new())
+        });
+        registry.register(V20250618, V20250326, || {
+            Arc::new(V20250618ToV20250326Adapter::new())
+        });
+        // Other versions reach the draft through 2025-06-18
+        #[cfg(feature = "draft-protocol")]
+        {
+            registry.register(
+                V20250618,
+                Draft,
+                || Arc::new(V20250618ToDraftAdapter::new()),
+            );
+            registry.register(
+                Draft,
+                V20250618,
+                || Arc::new(DraftToV20250618Adapter::new()),
+            );
+        }
+        registry
+    }
+
+    /// The registry [`super::create_adapter`] uses
+    pub fn global() -> &'static Self {
+        static REGISTRY: OnceLock<AdapterRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(Self::new)
+    }
+
+    /// Use `factory` for translating from `source` to `target`, replacing
+    /// any adapter registered for that pair. Connections already made keep
+    /// their adapters.
+    pub fn register<F>(&self, source: ProtocolVersion, target: ProtocolVersion, factory: F)
+    where
+        F: Fn() -> Arc<dyn ProtocolAdapter> + Send + Sync + 'static,
+    {
+        self.factories
+            .write()
+            .unwrap()
+            .insert((source, target), Arc::new(factory));
+    }
+
+    /// The pairs with an adapter of their own
+    pub fn pairs(&self) -> Vec<(ProtocolVersion, ProtocolVersion)> {
+        self.factories.read().unwrap().keys().copied().collect()
+    }
+
+    /// An adapter from `source` to `target`: pass-through for the same
+    /// version, the registered one, or else the shortest chain of
+    /// registered ones. `None` when there is no way between them.
+    pub fn create(
+        &self,
+        source: ProtocolVersion,
+        target: ProtocolVersion,
+    ) -> Option<Arc<dyn ProtocolAdapter>> {
+        if source == target {
+            return Some(Arc::new(PassThroughAdapter::new(source)));
+        }
+        let factories = self.factories.read().unwrap();
+        if let Some(factory) = factories.get(&(source, target)) {
+            return Some(factory());
+        }
+
+        // Breadth-first, so chains are as short as they can be
+        let mut previous = HashMap::new();
+        let mut seen = HashSet::from([source]);
+        let mut queue = VecDeque::from([source]);
+        while let Some(version) = queue.pop_front() {
+            if version == target {
+                break;
+            }
+            for &(from, to) in factories.keys() {
+                if from == version && seen.insert(to) {
+                    previous.insert(to, from);
+                    queue.push_back(to);
+                }
+            }
+        }
+        let mut hops = vec![target];
+        while let Some(&from) = previous.get(hops.last()?) {
+            hops.push(from);
+        }
+        if hops.last() != Some(&source) {
+            return None;
+        }
+        hops.reverse();
+
+        let adapters = hops
+            .windows(2)
+            .map(|pair| factories[&(pair[0], pair[1])]())
+            .collect();
+        Some(Arc::new(ChainedAdapter { adapters }))
+    }
+}
+
+/// Adapters applied one after another: requests in order, and responses
+/// and notifications, which travel the other way, in reverse
+struct ChainedAdapter {
+    adapters: Vec<Arc<dyn ProtocolAdapter>>,
+}
+
+#[async_trait]
+impl ProtocolAdapter for ChainedAdapter {
+    fn source_version(&self) -> ProtocolVersion {
+        self.adapters[0].source_version()
+    }
+
+    fn target_version(&self) -> ProtocolVersion {
+        self.adapters[self.adapters.len() - 1].target_version()
+    }
+
+    async fn translate_request(&self, mut request: Value) -> Result<Value, ProtocolError> {
+        for adapter in &self.adapters {
+            request = adapter.translate_request(request).await?;
+        }
+        Ok(request)
+    }
+
+    async fn translate_response(&self, mut response: Value) -> Result<Value, ProtocolError> {
+        for adapter in self.adapters.iter().rev() {
+            response = adapter.translate_response(response).await?;
+        }
+        Ok(response)
+    }
+
+    async fn translate_notification(
+        &self,
+        mut notification: Value,
+    ) -> Result<Value, ProtocolError> {
+        for adapter in self.adapters.iter().rev() {
+            notification = adapter.translate_notification(notification).await?;
+        }
+        Ok(notification)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use ProtocolVersion::*;
+
+    /// Marks what passes through it, to show the order adapters ran in
+    struct Tagging(ProtocolVersion, ProtocolVersion);
+
+    #[async_trait]
+    impl ProtocolAdapter for Tagging {
+        fn source_version(&self) -> ProtocolVersion {
+            self.0
+        }
+
+        fn target_version(&self) -> ProtocolVersion {
+            self.1
+        }
+
+        async fn translate_request(&self, mut request: Value) -> Result<Value, ProtocolError> {
+            request["hops"]
+                .as_array_mut()
+                .unwrap()
+                .push(self.1.as_str().into());
+            Ok(request)
+        }
+
+        async fn translate_response(&self, mut response: Value) -> Result<Value, ProtocolError> {
+            response["hops"]
+                .as_array_mut()
+                .unwrap()
+                .push(self.0.as_str().into());
+            Ok(response)
+        }
+
+        async fn translate_notification(
+            &self,
+            notification: Value,
+        ) -> Result<Value, ProtocolError> {
+            self.translate_response(notification).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_adapters_and_chains() {
+        let registry = AdapterRegistry {
+            factories: RwLock::new(HashMap::new()),
+        };
+        registry.register(V20241105, V20250326, || {
+            Arc::new(Tagging(V20241105, V20250326))
+        });
+        registry.register(V20250326, V20250618, || {
+            Arc::new(Tagging(V20250326, V20250618))
+        });
+
+        // No direct adapter, so the two registered ones are chained
+        let adapter = registry.create(V20241105, V20250618).unwrap();
+        assert_eq!(adapter.source_version(), V20241105);
+        assert_eq!(adapter.target_version(), V20250618);
+        let request = adapter
+            .translate_request(json!({ "hops": [] }))
+            .await
+            .unwrap();
+        assert_eq!(request["hops"], json!(["2025-03-26", "2025-06-18"]));
+        let response = adapter
+            .translate_response(json!({ "hops": [] }))
+            .await
+            .unwrap();
+        assert_eq!(response["hops"], json!(["2025-03-26", "2024-11-05"]));
+
+        assert!(registry.create(V20250618, V20241105).is_none());
+        assert_eq!(
+            registry
+                .create(V20250618, V20250618)
+                .unwrap()
+                .target_version(),
+            V20250618
+        );
+
+        // Registering a pair replaces the built-in adapter
+        let registry = AdapterRegistry::new();
+        assert!(registry.pairs().contains(&(V20250326, V20250618)));
+        registry.register(V20250326, V20250618, || {
+            Arc::new(Tagging(V20250326, V20250618))
+        });
+        let adapter = registry.create(V20250326, V20250618).unwrap();
+        let request = adapter
+            .translate_request(json!({ "hops": [] }))
+            .await
+            .unwrap();
+        assert_eq!(request["hops"], json!(["2025-06-18"]));
+    }
+
+    #[cfg(feature = "draft-protocol")]
+    #[test]
+    fn test_draft_reached_through_20250618() {
+        let adapter = AdapterRegistry::new().create(V20250326, Draft).unwrap();
+        assert_eq!(adapter.source_version(), V20250326);
+        assert_eq!(adapter.target_version(), Draft);
+    }
+}
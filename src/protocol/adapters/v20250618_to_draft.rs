@@ -0,0 +1,66 @@
+/// V20250618 to Draft Adapter
+///
+/// Translates messages from 2025-06-18 format to the draft format
+/// Key differences: None that need translating, as the draft only adds
+/// optional fields and capabilities
+use crate::protocol::{ProtocolAdapter, ProtocolError, ProtocolVersion};
+use async_trait::async_trait;
+use serde_json::Value;
+
+pub struct V20250618ToDraftAdapter;
+
+impl Default for V20250618ToDraftAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl V20250618ToDraftAdapter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl ProtocolAdapter for V20250618ToDraftAdapter {
+    fn source_version(&self) -> ProtocolVersion {
+        ProtocolVersion::V20250618
+    }
+
+    fn target_version(&self) -> ProtocolVersion {
+        ProtocolVersion::Draft
+    }
+
+    async fn translate_request(&self, request: Value) -> Result<Value, ProtocolError> {
+        Ok(request)
+    }
+
+    async fn translate_response(&self, response: Value) -> Result<Value, ProtocolError> {
+        Ok(response)
+    }
+
+    async fn translate_notification(&self, notification: Value) -> Result<Value, ProtocolError> {
+        Ok(notification)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_messages_pass_through() {
+        let adapter = V20250618ToDraftAdapter::new();
+        assert_eq!(adapter.source_version(), ProtocolVersion::V20250618);
+        assert_eq!(adapter.target_version(), ProtocolVersion::Draft);
+
+        let response = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": { "content": [], "structuredContent": { "ok": true } }
+        });
+        let translated = adapter.translate_response(response.clone()).await.unwrap();
+        assert_eq!(translated, response);
+    }
+}
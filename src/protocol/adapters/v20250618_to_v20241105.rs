@@ -1,6 +1,8 @@
 /// T041: V20250618 to V20241105 Adapter
 ///
 /// Translates messages from 2025-06-18 format to 2024-11-05 format (backward compatibility)
+use crate::protocol::translation::content::resource_link_to_resource;
+use crate::protocol::translation::tools::structured_content_to_text;
 use crate::protocol::{ProtocolAdapter, ProtocolError, ProtocolVersion};
 use async_trait::async_trait;
 use serde_json::Value;
@@ -76,21 +78,23 @@ impl V20250618ToV20241105Adapter {
         Ok(response)
     }
 
-    /// Translate tools/call response (strip structuredContent, convert audio to text)
+    /// Translate tools/call response (strip structuredContent, convert audio
+    /// to text and resource links to embedded resources)
     fn translate_tools_call_response(&self, mut response: Value) -> Result<Value, ProtocolError> {
         if let Some(result) = response.get_mut("result") {
-            // Strip structuredContent field
-            if result
-                .as_object_mut()
-                .and_then(|r| r.remove("structuredContent"))
-                .is_some()
-            {
+            // Strip structuredContent field, keeping its value as text
+            if structured_content_to_text(result) {
                 tracing::debug!("Stripping structuredContent field during v2→v1 translation");
             }
 
-            // Convert audio content to text descriptions
+            // Convert audio content to text descriptions, and resource
+            // links to embedded resources
             if let Some(content_array) = result.get_mut("content").and_then(|c| c.as_array_mut()) {
                 for content in content_array {
+                    if resource_link_to_resource(content) {
+                        tracing::debug!("Converted resource link to embedded resource");
+                        continue;
+                    }
                     if let Some(content_obj) = content.as_object_mut() {
                         if content_obj.get("type").and_then(|t| t.as_str()) == Some("audio") {
                             // Convert to text content
@@ -141,7 +145,7 @@ impl ProtocolAdapter for V20250618ToV20241105Adapter {
             if result.get("contents").is_some() {
                 return self.translate_resources_read_response(response);
             }
-            if result.get("content").is_some() {
+            if result.get("content").is_some() || result.get("structuredContent").is_some() {
                 return self.translate_tools_call_response(response);
             }
         }
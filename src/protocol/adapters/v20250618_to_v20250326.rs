@@ -2,6 +2,8 @@
 ///
 /// Translates messages from 2025-06-18 format to 2025-03-26 format
 /// Key differences: Must strip title fields and outputSchema from tools
+use crate::protocol::translation::content::resource_link_to_resource;
+use crate::protocol::translation::tools::structured_content_to_text;
 use crate::protocol::{ProtocolAdapter, ProtocolError, ProtocolVersion};
 use async_trait::async_trait;
 use serde_json::Value;
@@ -84,12 +86,16 @@ impl V20250618ToV20250326Adapter {
         Ok(response)
     }
 
-    /// Translate tools/call response (strip structuredContent)
+    /// Translate tools/call response (structuredContent becomes text and
+    /// resource links become embedded resources)
     fn translate_tools_call_response(&self, mut response: Value) -> Result<Value, ProtocolError> {
         if let Some(result) = response.get_mut("result") {
-            // Remove structuredContent field (not in V20250326)
-            if let Some(result_obj) = result.as_object_mut() {
-                result_obj.remove("structuredContent");
+            // Neither structuredContent nor resource_link is in V20250326
+            structured_content_to_text(result);
+            if let Some(content) = result.get_mut("content").and_then(|c| c.as_array_mut()) {
+                content.iter_mut().for_each(|item| {
+                    resource_link_to_resource(item);
+                });
             }
         }
         Ok(response)
@@ -123,7 +129,7 @@ impl ProtocolAdapter for V20250618ToV20250326Adapter {
             if result.get("contents").is_some() {
                 return self.translate_resources_read_response(response);
             }
-            if result.get("content").is_some() {
+            if result.get("content").is_some() || result.get("structuredContent").is_some() {
                 return self.translate_tools_call_response(response);
             }
         }
@@ -224,6 +230,51 @@ mod tests {
         assert!(translated["result"].get("structuredContent").is_none());
     }
 
+    #[tokio::test]
+    async fn test_tools_call_structured_content_becomes_text() {
+        let adapter = V20250618ToV20250326Adapter::new();
+
+        let response = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "content": [],
+                "structuredContent": {"temperature": 21.5}
+            }
+        });
+
+        let translated = adapter.translate_response(response).await.unwrap();
+
+        assert_eq!(
+            translated["result"]["content"],
+            json!([{"type": "text", "text": "{\"temperature\":21.5}"}])
+        );
+        assert!(translated["result"].get("structuredContent").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tools_call_resource_links_become_resources() {
+        let adapter = V20250618ToV20250326Adapter::new();
+
+        let response = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "content": [
+                    {"type": "text", "text": "Found 1 file"},
+                    {"type": "resource_link", "uri": "file:///notes.md", "name": "notes.md"}
+                ]
+            }
+        });
+
+        let translated = adapter.translate_response(response).await.unwrap();
+
+        let link = &translated["result"]["content"][1];
+        assert_eq!(link["type"], "resource");
+        assert_eq!(link["resource"]["uri"], "file:///notes.md");
+        assert_eq!(translated["result"]["content"][0]["text"], "Found 1 file");
+    }
+
     #[tokio::test]
     async fn test_request_pass_through() {
         let adapter = V20250618ToV20250326Adapter::new();
@@ -87,7 +87,7 @@ pub mod version;
 
 // Re-exports for convenience
 pub use adapter::ProtocolAdapter;
-pub use adapters::create_adapter;
+pub use adapters::{create_adapter, AdapterRegistry};
 pub use error::ProtocolError;
 pub use state::{ConnectionState, ServerConnectionState};
 pub use version::ProtocolVersion;
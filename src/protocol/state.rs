@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::sync::{Mutex, RwLock};
 
 use super::adapter::ProtocolAdapter;
@@ -138,6 +138,21 @@ impl ServerConnectionState {
         }
     }
 
+    /// Reset to Connecting so a fresh connection can run the handshake again
+    pub async fn reset(&self) {
+        *self.state.lock().await = ConnectionState::Connecting;
+        *self.adapter.write().await = None;
+        *self.last_activity.lock().await = Instant::now();
+    }
+
+    /// Take over the state and adapter of a connection initialized on its
+    /// own, such as a warm spare swapped in for a failed connection
+    pub async fn adopt(&self, other: &ServerConnectionState) {
+        *self.state.lock().await = other.get_state().await;
+        *self.adapter.write().await = other.adapter.read().await.clone();
+        *self.last_activity.lock().await = Instant::now();
+    }
+
     /// Mark as failed
     pub async fn mark_failed(&self, error: String) {
         let mut state = self.state.lock().await;
@@ -148,6 +163,24 @@ impl ServerConnectionState {
         *self.last_activity.lock().await = Instant::now();
     }
 
+    /// Mark as failed because the initialize handshake did not finish in time
+    pub async fn mark_initialization_timed_out(&self, timeout: Duration) {
+        let error = ProtocolError::InitializationTimeout {
+            server_name: self.server_name.clone(),
+            duration: timeout,
+        };
+        tracing::warn!(server_name = %self.server_name, "{}", error);
+        self.mark_failed(error.to_string()).await;
+    }
+
+    /// Time spent in the Initializing state, if a handshake is in progress
+    pub async fn initialization_elapsed(&self) -> Option<Duration> {
+        match *self.state.lock().await {
+            ConnectionState::Initializing { started_at, .. } => Some(started_at.elapsed()),
+            _ => None,
+        }
+    }
+
     /// Check if server is ready to handle requests
     pub async fn is_ready(&self) -> bool {
         matches!(*self.state.lock().await, ConnectionState::Ready { .. })
@@ -360,6 +393,54 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_initialization_timeout_marks_failed() {
+        let state = ServerConnectionState::new("test-server".to_string());
+        assert!(state.initialization_elapsed().await.is_none());
+
+        state
+            .start_initialization("req-1".to_string())
+            .await
+            .unwrap();
+        assert!(state.initialization_elapsed().await.is_some());
+
+        state
+            .mark_initialization_timed_out(Duration::from_secs(60))
+            .await;
+        match state.get_state().await {
+            ConnectionState::Failed { error, .. } => {
+                assert!(error.contains("test-server"));
+                assert!(state.initialization_elapsed().await.is_none());
+            }
+            _ => panic!("Expected Failed state"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reset_allows_new_handshake() {
+        let state = ServerConnectionState::new("test-server".to_string());
+        state
+            .start_initialization("req-1".to_string())
+            .await
+            .unwrap();
+        state
+            .received_initialize_response(ProtocolVersion::V20241105)
+            .await
+            .unwrap();
+        state.complete_initialization().await.unwrap();
+
+        state.reset().await;
+        assert!(matches!(
+            state.get_state().await,
+            ConnectionState::Connecting
+        ));
+        assert!(state.get_adapter().await.is_none());
+        assert!(state
+            .start_initialization("req-2".to_string())
+            .await
+            .is_ok());
+    }
+
     #[tokio::test]
     async fn test_state_machine_concurrent_access() {
         let state = Arc::new(ServerConnectionState::new("test-server".to_string()));
@@ -2,6 +2,7 @@
 ///
 /// Functions for converting Content types between protocol versions
 use crate::types::mcp::{v20241105::ContentV1, v20250326::ContentV2};
+use serde_json::{json, Value};
 
 /// Convert ContentV1 to ContentV2
 ///
@@ -34,6 +35,30 @@ pub fn content_v2_to_v1(v2: ContentV2) -> ContentV1 {
     }
 }
 
+/// Downgrade a `resource_link` content item (2025-06-18) to an embedded
+/// resource, which versions before it understand. The link's description,
+/// or else its name, stands in for the resource's text; clients can still
+/// read the full resource by its URI.
+///
+/// Returns whether the item was a resource link.
+pub fn resource_link_to_resource(item: &mut Value) -> bool {
+    if item.get("type").and_then(Value::as_str) != Some("resource_link") {
+        return false;
+    }
+    let field = |name: &str| item.get(name).and_then(Value::as_str).map(str::to_string);
+    let uri = field("uri").unwrap_or_default();
+    let text = field("description")
+        .or_else(|| field("name").map(|name| format!("[Resource: {name}]")))
+        .unwrap_or_else(|| format!("[Resource: {uri}]"));
+
+    let mut resource = json!({ "uri": uri, "text": text });
+    if let Some(mime_type) = field("mimeType") {
+        resource["mimeType"] = Value::from(mime_type);
+    }
+    *item = json!({ "type": "resource", "resource": resource });
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -144,4 +169,39 @@ mod tests {
 
         assert_eq!(v2_array.len(), 2);
     }
+
+    #[test]
+    fn test_resource_link_downgrades_to_resource() {
+        let mut link = json!({
+            "type": "resource_link",
+            "uri": "file:///report.csv",
+            "name": "report.csv",
+            "mimeType": "text/csv"
+        });
+        assert!(resource_link_to_resource(&mut link));
+        assert_eq!(
+            link,
+            json!({
+                "type": "resource",
+                "resource": {
+                    "uri": "file:///report.csv",
+                    "mimeType": "text/csv",
+                    "text": "[Resource: report.csv]"
+                }
+            })
+        );
+
+        let mut described = json!({
+            "type": "resource_link",
+            "uri": "file:///a",
+            "name": "a",
+            "description": "Quarterly numbers"
+        });
+        resource_link_to_resource(&mut described);
+        assert_eq!(described["resource"]["text"], "Quarterly numbers");
+
+        let mut text = json!({ "type": "text", "text": "hi" });
+        assert!(!resource_link_to_resource(&mut text));
+        assert_eq!(text["text"], "hi");
+    }
 }
@@ -2,6 +2,7 @@
 ///
 /// Functions for converting Tool definitions between protocol versions
 use crate::types::mcp::{v20241105::ToolV1, v20250618::ToolV2};
+use serde_json::{json, Value};
 
 /// Convert ToolV1 to ToolV2
 ///
@@ -27,6 +28,40 @@ pub fn tool_v2_to_v1(v2: ToolV2) -> ToolV1 {
     }
 }
 
+/// Remove structuredContent from a tools/call result for a version without
+/// it. When the result has no text content of its own, the structured
+/// value is added as a JSON text item so older clients still receive it.
+///
+/// Returns whether there was structured content to remove.
+pub fn structured_content_to_text(result: &mut Value) -> bool {
+    let Some(structured) = result
+        .as_object_mut()
+        .and_then(|r| r.remove("structuredContent"))
+    else {
+        return false;
+    };
+    if structured.is_null() {
+        return true;
+    }
+
+    let has_text = result
+        .get("content")
+        .and_then(Value::as_array)
+        .is_some_and(|content| {
+            content
+                .iter()
+                .any(|item| item.get("type").and_then(Value::as_str) == Some("text"))
+        });
+    if !has_text {
+        let text = json!({ "type": "text", "text": structured.to_string() });
+        match result.get_mut("content").and_then(Value::as_array_mut) {
+            Some(content) => content.push(text),
+            None => result["content"] = json!([text]),
+        }
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -103,4 +138,39 @@ mod tests {
         assert_eq!(v1.name, "minimal");
         assert_eq!(v1.description, "Minimal tool");
     }
+
+    #[test]
+    fn test_structured_content_to_text() {
+        // Existing text is assumed to already describe the structured value
+        let mut result = json!({
+            "content": [{ "type": "text", "text": "3 rows" }],
+            "structuredContent": { "rows": 3 }
+        });
+        assert!(structured_content_to_text(&mut result));
+        assert_eq!(
+            result,
+            json!({ "content": [{ "type": "text", "text": "3 rows" }] })
+        );
+
+        let mut result = json!({
+            "content": [{ "type": "image", "data": "AAAA", "mimeType": "image/png" }],
+            "structuredContent": { "rows": 3 }
+        });
+        assert!(structured_content_to_text(&mut result));
+        assert_eq!(
+            result["content"][1],
+            json!({ "type": "text", "text": "{\"rows\":3}" })
+        );
+
+        let mut result = json!({ "structuredContent": [1, 2] });
+        structured_content_to_text(&mut result);
+        assert_eq!(
+            result,
+            json!({ "content": [{ "type": "text", "text": "[1,2]" }] })
+        );
+
+        let mut plain = json!({ "content": [] });
+        assert!(!structured_content_to_text(&mut plain));
+        assert_eq!(plain, json!({ "content": [] }));
+    }
 }
@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Whether the draft version is accepted, from `proxy.draftProtocol`
+#[cfg(feature = "draft-protocol")]
+static DRAFT_ENABLED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Versions are declared oldest first, so they compare by release
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ProtocolVersion {
     /// MCP Protocol version 2024-11-05 (initial stable release)
     V20241105,
@@ -8,6 +13,15 @@ pub enum ProtocolVersion {
     V20250326,
     /// MCP Protocol version 2025-06-18 (adds structured output, titles)
     V20250618,
+    /// The next MCP version while it is being drafted (adds icons, tasks).
+    /// Servers report it as `DRAFT` or `DRAFT-<revision>`.
+    #[cfg(feature = "draft-protocol")]
+    Draft,
+}
+
+/// Whether a version string names a draft of the specification
+pub fn is_draft_version(s: &str) -> bool {
+    s.eq_ignore_ascii_case("draft") || s.starts_with("DRAFT-")
 }
 
 impl ProtocolVersion {
@@ -17,13 +31,11 @@ impl ProtocolVersion {
     /// default version (V20250326) to enable pass-through operation.
     /// Returns (version, is_supported) tuple.
     pub fn from_string(s: &str) -> (Self, bool) {
-        match s {
-            "2024-11-05" => (Self::V20241105, true),
-            "2025-03-26" => (Self::V20250326, true),
-            "2025-06-18" => (Self::V20250618, true),
-            unsupported => {
+        match Self::parse(s) {
+            Some(version) => (version, true),
+            None => {
                 tracing::warn!(
-                    reported_version = unsupported,
+                    reported_version = s,
                     supported_versions = ?["2024-11-05", "2025-03-26", "2025-06-18"],
                     "Backend server reports unsupported protocol version, using pass-through mode"
                 );
@@ -32,48 +44,78 @@ impl ProtocolVersion {
         }
     }
 
+    /// Parse a version string strictly, returning `None` if it is unsupported
+    ///
+    /// Unlike `from_string`, this does not fall back to a default version and
+    /// is intended for validating user-supplied configuration.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "2024-11-05" => Some(Self::V20241105),
+            "2025-03-26" => Some(Self::V20250326),
+            "2025-06-18" => Some(Self::V20250618),
+            #[cfg(feature = "draft-protocol")]
+            s if is_draft_version(s) && Self::draft_enabled() => Some(Self::Draft),
+            _ => None,
+        }
+    }
+
+    /// Accept the draft version from servers and in configuration. Builds
+    /// without the `draft-protocol` feature have no draft version, and
+    /// configuration asking for it is rejected when loaded.
+    #[cfg(feature = "draft-protocol")]
+    pub fn enable_draft(enabled: bool) {
+        DRAFT_ENABLED.store(enabled, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "draft-protocol")]
+    pub fn draft_enabled() -> bool {
+        DRAFT_ENABLED.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
     /// Get version string for initialize messages
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::V20241105 => "2024-11-05",
             Self::V20250326 => "2025-03-26",
             Self::V20250618 => "2025-06-18",
+            #[cfg(feature = "draft-protocol")]
+            Self::Draft => "DRAFT",
         }
     }
 
     /// Check if this version supports audio content
     pub fn supports_audio_content(&self) -> bool {
-        matches!(self, Self::V20250326 | Self::V20250618)
+        *self >= Self::V20250326
     }
 
     /// Check if this version supports completions capability
     pub fn supports_completions(&self) -> bool {
-        matches!(self, Self::V20250326 | Self::V20250618)
+        *self >= Self::V20250326
     }
 
     /// Check if this version requires ResourceContents.name field
     pub fn requires_resource_name(&self) -> bool {
-        matches!(self, Self::V20250618)
+        *self >= Self::V20250618
     }
 
     /// Check if this version supports structured content output
     pub fn supports_structured_content(&self) -> bool {
-        matches!(self, Self::V20250618)
+        *self >= Self::V20250618
     }
 
     /// Check if this version supports elicitation capability
     pub fn supports_elicitation(&self) -> bool {
-        matches!(self, Self::V20250618)
+        *self >= Self::V20250618
     }
 
     /// Check if this version supports title fields
     pub fn supports_title_fields(&self) -> bool {
-        matches!(self, Self::V20250618)
+        *self >= Self::V20250618
     }
 
     /// Check if this version supports output schema in tools
     pub fn supports_output_schema(&self) -> bool {
-        matches!(self, Self::V20250618)
+        *self >= Self::V20250618
     }
 
     /// Check if this version is deprecated
@@ -53,6 +53,7 @@ pub async fn handle_aggregator_tool(
     _tool_name: &str,
     arguments: Value,
     state: Arc<AppState>,
+    correlation_id: &str,
 ) -> std::result::Result<Value, String> {
     // Extract query from arguments
     let query = arguments
@@ -122,6 +123,7 @@ pub async fn handle_aggregator_tool(
             user_query: Some(query.to_string()),
             tool_arguments: Some(arguments.clone()),
             mcp_servers: Some(mcp_server_configs),
+            correlation_id: Some(correlation_id.to_string()),
         },
     };
 
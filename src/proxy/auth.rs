@@ -0,0 +1,118 @@
+//! Identification of clients calling the proxy endpoint
+
+use super::{MCPResponse, RequestContext, TraceContext};
+use crate::config::{ClientConfig, ClientRole};
+use crate::error::{ProxyError, Result};
+use crate::state::AppState;
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The client a request was made by
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientIdentity {
+    pub id: String,
+    pub role: ClientRole,
+}
+
+/// Resolve the client sending an `Authorization` header.
+///
+/// With no clients configured the endpoint is open and requests are
+/// anonymous. Otherwise the header must carry a configured client's key as
+/// `Bearer <apiKey>`.
+pub fn authenticate(
+    clients: &HashMap<String, ClientConfig>,
+    authorization: Option<&str>,
+) -> Result<Option<ClientIdentity>> {
+    if clients.is_empty() {
+        return Ok(None);
+    }
+
+    let key = authorization
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| ProxyError::Unauthorized("missing API key".to_string()))?;
+
+    clients
+        .iter()
+        .find(|(_, client)| constant_time_eq(client.api_key.as_bytes(), key.as_bytes()))
+        .map(|(id, client)| {
+            Some(ClientIdentity {
+                id: id.clone(),
+                role: client.role,
+            })
+        })
+        .ok_or_else(|| ProxyError::Unauthorized("invalid API key".to_string()))
+}
+
+/// Layer attaching the authenticated client to each request as its
+/// [`RequestContext`]; requests that fail authentication get HTTP 401
+pub async fn require_client(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    let client = authenticate(&state.config.read().await.clients, authorization);
+
+    let ctx = RequestContext {
+        trace: TraceContext::from_headers(request.headers()),
+        ..Default::default()
+    };
+
+    match client {
+        Ok(client) => {
+            request
+                .extensions_mut()
+                .insert(RequestContext { client, ..ctx });
+            next.run(request).await
+        }
+        Err(e) => {
+            tracing::warn!(correlation_id = %ctx.correlation_id, "Rejected proxy request: {}", e);
+            let reply = MCPResponse::error(None, &e).with_correlation_id(&ctx.correlation_id);
+            (StatusCode::UNAUTHORIZED, Json(reply)).into_response()
+        }
+    }
+}
+
+/// Compare keys without leaking through timing how much of a guess matched
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authenticate() {
+        let mut clients = HashMap::new();
+        assert_eq!(authenticate(&clients, None).unwrap(), None);
+
+        clients.insert(
+            "ci-agent".to_string(),
+            ClientConfig {
+                api_key: "secret-1".to_string(),
+                quota: None,
+                role: ClientRole::Admin,
+                priority: Default::default(),
+            },
+        );
+        assert_eq!(
+            authenticate(&clients, Some("Bearer secret-1")).unwrap(),
+            Some(ClientIdentity {
+                id: "ci-agent".to_string(),
+                role: ClientRole::Admin,
+            })
+        );
+        assert!(authenticate(&clients, Some("Bearer secret-2")).is_err());
+        assert!(authenticate(&clients, Some("secret-1")).is_err());
+        assert!(authenticate(&clients, None).is_err());
+    }
+}
@@ -0,0 +1,435 @@
+//! Binary resource contents: `blob`s in resource reads and in resources
+//! embedded in tool results
+//!
+//! Blobs are limited by their decoded size, kept out of the way of
+//! transformations that treat results as text, and, when downloads are
+//! enabled, served from the proxy endpoint through signed links instead of
+//! being sent inline as base64.
+
+use crate::config::{BlobDownloadConfig, ProxyConfig};
+use crate::error::{ProxyError, Result};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use serde_json::{json, Map, Value};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// What a blob is replaced with while it is detached
+const PLACEHOLDER: &str = "mcp-proxy-blob:";
+
+/// The resource contents in a result that carry a blob
+fn blob_items(result: &mut Value) -> Vec<&mut Map<String, Value>> {
+    let mut items = Vec::new();
+    let Some(object) = result.as_object_mut() else {
+        return items;
+    };
+    for (key, value) in object.iter_mut() {
+        let Some(entries) = value.as_array_mut() else {
+            continue;
+        };
+        for entry in entries.iter_mut().filter_map(Value::as_object_mut) {
+            let item = match key.as_str() {
+                "contents" => Some(entry),
+                "content" if entry.get("type").and_then(Value::as_str) == Some("resource") => {
+                    entry.get_mut("resource").and_then(Value::as_object_mut)
+                }
+                _ => None,
+            };
+            if let Some(item) = item.filter(|item| item.get("blob").is_some_and(Value::is_string)) {
+                items.push(item);
+            }
+        }
+    }
+    items
+}
+
+/// Size of base64 data once decoded, without decoding it
+pub fn decoded_len(blob: &str) -> usize {
+    let data = blob.bytes().filter(|b| !b.is_ascii_whitespace()).count();
+    let padding = blob
+        .trim_end()
+        .bytes()
+        .rev()
+        .take_while(|&b| b == b'=')
+        .count();
+    (data * 3 / 4).saturating_sub(padding)
+}
+
+/// Blobs taken out of a result, leaving placeholders, while it goes through
+/// plugins that would otherwise see, and could mangle, the base64
+pub struct Detached(Vec<Option<Value>>);
+
+pub fn detach(result: &mut Value) -> Detached {
+    let mut blobs = Vec::new();
+    for item in blob_items(result) {
+        let placeholder = Value::from(format!("{PLACEHOLDER}{}", blobs.len()));
+        blobs.push(item.insert("blob".to_string(), placeholder));
+    }
+    Detached(blobs)
+}
+
+impl Detached {
+    /// Put each blob back where its placeholder still is
+    pub fn reattach(mut self, result: &mut Value) {
+        if self.0.is_empty() {
+            return;
+        }
+        for item in blob_items(result) {
+            let index = item["blob"]
+                .as_str()
+                .and_then(|blob| blob.strip_prefix(PLACEHOLDER))
+                .and_then(|index| index.parse::<usize>().ok());
+            if let Some(blob) = index.and_then(|index| self.0.get_mut(index)?.take()) {
+                item.insert("blob".to_string(), blob);
+            }
+        }
+    }
+}
+
+/// Check every blob in a result against `max_bytes`, and swap those too
+/// large to send inline for download links
+pub fn process(
+    result: &mut Value,
+    server: &str,
+    max_bytes: Option<usize>,
+    downloads: Option<&BlobDownloads>,
+) -> Result<()> {
+    for item in blob_items(result) {
+        let size = item["blob"].as_str().map_or(0, decoded_len);
+        if let Some(limit) = max_bytes.filter(|&limit| size > limit) {
+            return Err(ProxyError::ResponseTooLarge {
+                server: server.to_string(),
+                size,
+                limit,
+            });
+        }
+        let Some(downloads) = downloads.filter(|d| size > d.inline_max_bytes) else {
+            continue;
+        };
+        let data = match STANDARD.decode(item["blob"].as_str().unwrap_or_default()) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!(server = server, "Sending undecodable blob inline: {}", e);
+                continue;
+            }
+        };
+        let mime_type = item
+            .get("mimeType")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let Some(link) = downloads.store(Bytes::from(data), mime_type.clone()) else {
+            tracing::warn!(
+                server = server,
+                size = size,
+                "Blob too large to store for download"
+            );
+            continue;
+        };
+        replace_with_link(item, &link, size, mime_type);
+    }
+    Ok(())
+}
+
+/// Turn a blob item into a text item pointing at its download, with the
+/// details in `_meta.download`
+fn replace_with_link(
+    item: &mut Map<String, Value>,
+    link: &Link,
+    size: usize,
+    mime_type: Option<String>,
+) {
+    let uri = item
+        .get("uri")
+        .and_then(Value::as_str)
+        .unwrap_or("resource");
+    let text = format!(
+        "[{uri} is {size} bytes of {}; download it from {} before {}]",
+        mime_type.as_deref().unwrap_or("binary data"),
+        link.url,
+        link.expires_at.to_rfc3339()
+    );
+    item.remove("blob");
+    item.insert("mimeType".to_string(), Value::from("text/plain"));
+    item.insert("text".to_string(), Value::from(text));
+    let meta = item
+        .entry("_meta")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Some(meta) = meta.as_object_mut() {
+        meta.insert(
+            "download".to_string(),
+            json!({
+                "url": link.url,
+                "size": size,
+                "mimeType": mime_type,
+                "expiresAt": link.expires_at,
+            }),
+        );
+    }
+}
+
+pub struct Link {
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Why a download link was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadError {
+    BadSignature,
+    Expired,
+    /// Never stored, or dropped to make room
+    NotFound,
+}
+
+struct StoredBlob {
+    id: String,
+    data: Bytes,
+    mime_type: Option<String>,
+    expires_at: DateTime<Utc>,
+}
+
+/// Blobs waiting to be downloaded, oldest first, and the key their links
+/// are signed with. The key is made at startup, so links do not outlive
+/// the process.
+pub struct BlobDownloads {
+    key: hmac::Key,
+    base_url: String,
+    inline_max_bytes: usize,
+    ttl: Duration,
+    max_stored_bytes: usize,
+    blobs: Mutex<VecDeque<StoredBlob>>,
+}
+
+impl BlobDownloads {
+    /// Downloads for the configuration, or `None` when they are disabled
+    pub fn new(config: &BlobDownloadConfig, proxy: &ProxyConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        let key = match hmac::Key::generate(hmac::HMAC_SHA256, &ring::rand::SystemRandom::new()) {
+            Ok(key) => key,
+            Err(_) => {
+                tracing::error!("Blob downloads disabled: cannot generate a signing key");
+                return None;
+            }
+        };
+        let base_url = match &config.base_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => default_base_url(proxy),
+        };
+        Some(Self {
+            key,
+            base_url,
+            inline_max_bytes: config.inline_max_bytes,
+            ttl: config.ttl(),
+            max_stored_bytes: config.max_stored_bytes,
+            blobs: Mutex::new(VecDeque::new()),
+        })
+    }
+
+    /// Keep a blob for download, dropping expired ones and then the oldest
+    /// to make room. `None` when it is larger than the whole store.
+    pub fn store(&self, data: Bytes, mime_type: Option<String>) -> Option<Link> {
+        if data.len() > self.max_stored_bytes {
+            return None;
+        }
+        let now = Utc::now();
+        let expires_at = now + self.ttl;
+        let id = uuid::Uuid::new_v4().simple().to_string();
+
+        let mut blobs = self.blobs.lock().unwrap();
+        blobs.retain(|blob| blob.expires_at > now);
+        let mut stored: usize = blobs.iter().map(|blob| blob.data.len()).sum();
+        while stored + data.len() > self.max_stored_bytes {
+            let Some(oldest) = blobs.pop_front() else {
+                break;
+            };
+            stored -= oldest.data.len();
+        }
+        blobs.push_back(StoredBlob {
+            id: id.clone(),
+            data,
+            mime_type,
+            expires_at,
+        });
+        drop(blobs);
+
+        let expires = expires_at.timestamp();
+        Some(Link {
+            url: format!(
+                "{}/blobs/{id}?expires={expires}&signature={}",
+                self.base_url,
+                self.sign(&id, expires)
+            ),
+            expires_at,
+        })
+    }
+
+    /// The blob a link points at, with its MIME type
+    pub fn fetch(
+        &self,
+        id: &str,
+        expires: i64,
+        signature: &str,
+    ) -> std::result::Result<(Bytes, Option<String>), DownloadError> {
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| DownloadError::BadSignature)?;
+        hmac::verify(&self.key, message(id, expires).as_bytes(), &signature)
+            .map_err(|_| DownloadError::BadSignature)?;
+        if expires <= Utc::now().timestamp() {
+            return Err(DownloadError::Expired);
+        }
+        self.blobs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|blob| blob.id == id)
+            .map(|blob| (blob.data.clone(), blob.mime_type.clone()))
+            .ok_or(DownloadError::NotFound)
+    }
+
+    fn sign(&self, id: &str, expires: i64) -> String {
+        URL_SAFE_NO_PAD.encode(hmac::sign(&self.key, message(id, expires).as_bytes()))
+    }
+}
+
+fn message(id: &str, expires: i64) -> String {
+    format!("{id}.{expires}")
+}
+
+/// The proxy's own address, with wildcard hosts replaced by `localhost`
+fn default_base_url(proxy: &ProxyConfig) -> String {
+    let host = match proxy.host.parse::<std::net::IpAddr>() {
+        Ok(ip) if ip.is_unspecified() => "localhost".to_string(),
+        Ok(std::net::IpAddr::V6(ip)) => format!("[{ip}]"),
+        _ => proxy.host.clone(),
+    };
+    format!("http://{host}:{}", proxy.port)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn downloads(inline_max_bytes: usize, max_stored_bytes: usize) -> BlobDownloads {
+        let proxy: ProxyConfig = serde_json::from_value(json!({ "host": "0.0.0.0" })).unwrap();
+        let config = BlobDownloadConfig {
+            enabled: true,
+            inline_max_bytes,
+            max_stored_bytes,
+            ..Default::default()
+        };
+        BlobDownloads::new(&config, &proxy).unwrap()
+    }
+
+    fn query(url: &str) -> (String, i64, String) {
+        let url = url::Url::parse(url).unwrap();
+        let id = url.path().trim_start_matches("/blobs/").to_string();
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .unwrap()
+                .1
+                .into_owned()
+        };
+        (id, param("expires").parse().unwrap(), param("signature"))
+    }
+
+    #[test]
+    fn test_decoded_len() {
+        for data in ["", "a", "ab", "abc", "abcd", "hello world"] {
+            let encoded = STANDARD.encode(data);
+            assert_eq!(decoded_len(&encoded), data.len());
+            assert_eq!(decoded_len(&URL_SAFE_NO_PAD.encode(data)), data.len());
+        }
+        assert_eq!(decoded_len("aGVs\nbG8="), 5);
+    }
+
+    #[test]
+    fn test_detach_and_reattach() {
+        let blob = STANDARD.encode([0u8, 159, 146, 150]);
+        let mut result = json!({
+            "content": [
+                { "type": "text", "text": "see attached" },
+                { "type": "resource", "resource": { "uri": "file:///a.bin", "blob": blob } }
+            ]
+        });
+        let detached = detach(&mut result);
+        assert_eq!(result["content"][1]["resource"]["blob"], "mcp-proxy-blob:0");
+
+        // A plugin rewrites the text and keeps the resource
+        result["content"][0]["text"] = json!("SEE ATTACHED");
+        detached.reattach(&mut result);
+        assert_eq!(result["content"][0]["text"], "SEE ATTACHED");
+        assert_eq!(result["content"][1]["resource"]["blob"], blob);
+    }
+
+    #[test]
+    fn test_limits_and_links() {
+        let image = vec![7u8; 2048];
+        let mut result = json!({
+            "contents": [
+                { "uri": "file:///small.png", "mimeType": "image/png", "blob": STANDARD.encode([1u8; 16]) },
+                { "uri": "file:///large.png", "mimeType": "image/png", "blob": STANDARD.encode(&image) }
+            ]
+        });
+
+        let err = process(&mut result.clone(), "files", Some(1024), None).unwrap_err();
+        assert!(matches!(
+            err,
+            ProxyError::ResponseTooLarge {
+                size: 2048,
+                limit: 1024,
+                ..
+            }
+        ));
+
+        let downloads = downloads(1024, 1 << 20);
+        process(&mut result, "files", Some(4096), Some(&downloads)).unwrap();
+        assert!(result["contents"][0]["blob"].is_string());
+        let linked = &result["contents"][1];
+        assert!(linked.get("blob").is_none());
+        assert_eq!(linked["_meta"]["download"]["size"], 2048);
+        assert_eq!(linked["_meta"]["download"]["mimeType"], "image/png");
+
+        let url = linked["_meta"]["download"]["url"].as_str().unwrap();
+        assert!(url.starts_with("http://localhost:3000/blobs/"));
+        assert!(linked["text"].as_str().unwrap().contains(url));
+        let (id, expires, signature) = query(url);
+        let (data, mime_type) = downloads.fetch(&id, expires, &signature).unwrap();
+        assert_eq!(data, Bytes::from(image));
+        assert_eq!(mime_type.as_deref(), Some("image/png"));
+
+        // Links cannot be altered or used after they expire
+        assert_eq!(
+            downloads.fetch(&id, expires + 60, &signature).err(),
+            Some(DownloadError::BadSignature)
+        );
+        let past = Utc::now().timestamp() - 1;
+        assert_eq!(
+            downloads.fetch(&id, past, &downloads.sign(&id, past)).err(),
+            Some(DownloadError::Expired)
+        );
+    }
+
+    #[test]
+    fn test_store_drops_oldest() {
+        let downloads = downloads(0, 100);
+        let first = downloads.store(Bytes::from(vec![1u8; 60]), None).unwrap();
+        let second = downloads.store(Bytes::from(vec![2u8; 60]), None).unwrap();
+        assert!(downloads.store(Bytes::from(vec![3u8; 101]), None).is_none());
+
+        let (id, expires, signature) = query(&first.url);
+        assert_eq!(
+            downloads.fetch(&id, expires, &signature).err(),
+            Some(DownloadError::NotFound)
+        );
+        let (id, expires, signature) = query(&second.url);
+        assert!(downloads.fetch(&id, expires, &signature).is_ok());
+    }
+}
@@ -0,0 +1,190 @@
+//! Reading JSON-RPC request bodies within configured limits.
+//!
+//! The body is read incrementally and abandoned as soon as it passes the
+//! size limit, and its nesting depth and element count are checked with a
+//! byte scan before its syntax is validated, so a pathological payload is
+//! rejected before it can cost much memory or stack. The body is handed on
+//! as bytes; see [`super::passthrough`] for requests that are never parsed
+//! into a value.
+
+use super::MCPResponse;
+use crate::config::ProxyConfig;
+use crate::error::{ProxyError, Result};
+use crate::state::AppState;
+use axum::extract::{FromRef, FromRequest, Request};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use bytes::Bytes;
+use futures::stream::{BoxStream, StreamExt};
+use serde::de::IgnoredAny;
+use std::sync::Arc;
+
+pub type BodyStream = BoxStream<'static, std::result::Result<Bytes, axum::Error>>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RequestLimits {
+    pub max_body_bytes: usize,
+    pub max_json_depth: usize,
+    pub max_json_elements: usize,
+}
+
+impl From<&ProxyConfig> for RequestLimits {
+    fn from(config: &ProxyConfig) -> Self {
+        Self {
+            max_body_bytes: config.max_body_bytes,
+            max_json_depth: config.max_json_depth,
+            max_json_elements: config.max_json_elements,
+        }
+    }
+}
+
+/// A well-formed JSON-RPC request body, read within the configured
+/// [`RequestLimits`]. Bodies over a limit are answered with HTTP 413 and
+/// malformed ones with HTTP 400, both carrying a JSON-RPC error.
+pub struct JsonRpcBody(pub Bytes);
+
+#[axum::async_trait]
+impl<S> FromRequest<S> for JsonRpcBody
+where
+    S: Send + Sync,
+    Arc<AppState>: FromRef<S>,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> std::result::Result<Self, Response> {
+        let state = Arc::<AppState>::from_ref(state);
+        let limits = RequestLimits::from(&state.config.read().await.proxy);
+        let body = request.into_body().into_data_stream().boxed();
+
+        read_body(body, limits).await.map(Self).map_err(|e| {
+            tracing::warn!("Rejected malformed proxy request: {}", e);
+            let status = match e {
+                ProxyError::RequestLimit(_) => StatusCode::PAYLOAD_TOO_LARGE,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            (status, Json(MCPResponse::error(None, &e))).into_response()
+        })
+    }
+}
+
+/// Read a request body and check that it is JSON, enforcing `limits`
+pub async fn read_body(mut body: BodyStream, limits: RequestLimits) -> Result<Bytes> {
+    let mut buf = Vec::new();
+    while let Some(chunk) = body.next().await {
+        let chunk =
+            chunk.map_err(|e| ProxyError::InvalidRequest(format!("failed to read body: {e}")))?;
+        if buf.len() + chunk.len() > limits.max_body_bytes {
+            return Err(ProxyError::RequestLimit(format!(
+                "body is larger than {} bytes",
+                limits.max_body_bytes
+            )));
+        }
+        buf.extend_from_slice(&chunk);
+    }
+
+    check_shape(&buf, limits)?;
+    // Validate the syntax without building a value
+    serde_json::from_slice::<IgnoredAny>(&buf).map_err(|e| ProxyError::Parse(e.to_string()))?;
+    Ok(Bytes::from(buf))
+}
+
+/// Check nesting depth and the number of array items and object members
+/// without building a value
+fn check_shape(json: &[u8], limits: RequestLimits) -> Result<()> {
+    let mut depth = 0usize;
+    let mut elements = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for &byte in json {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > limits.max_json_depth {
+                    return Err(ProxyError::RequestLimit(format!(
+                        "JSON is nested deeper than {} levels",
+                        limits.max_json_depth
+                    )));
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            b',' => {
+                elements += 1;
+                if elements > limits.max_json_elements {
+                    return Err(ProxyError::RequestLimit(format!(
+                        "JSON has more than {} elements",
+                        limits.max_json_elements
+                    )));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(json: &str) -> BodyStream {
+        // Split the body to exercise the incremental read
+        let (a, b) = json.split_at(json.len() / 2);
+        futures::stream::iter([
+            Ok(Bytes::from(a.to_string())),
+            Ok(Bytes::from(b.to_string())),
+        ])
+        .boxed()
+    }
+
+    #[tokio::test]
+    async fn test_read_body_limits() {
+        let limits = RequestLimits {
+            max_body_bytes: 256,
+            max_json_depth: 4,
+            max_json_elements: 8,
+        };
+
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"a,[{"}}"#;
+        assert_eq!(
+            read_body(body(request), limits).await.unwrap(),
+            request.as_bytes()
+        );
+
+        let too_large = format!(r#"{{"text":"{}"}}"#, "x".repeat(300));
+        assert!(matches!(
+            read_body(body(&too_large), limits).await,
+            Err(ProxyError::RequestLimit(_))
+        ));
+
+        let too_deep = format!("{}{}", "[".repeat(5), "]".repeat(5));
+        assert!(matches!(
+            read_body(body(&too_deep), limits).await,
+            Err(ProxyError::RequestLimit(_))
+        ));
+
+        let too_many = format!("[{}0]", "0,".repeat(9));
+        assert!(matches!(
+            read_body(body(&too_many), limits).await,
+            Err(ProxyError::RequestLimit(_))
+        ));
+
+        assert!(matches!(
+            read_body(body(r#"{"id":1,"#), limits).await,
+            Err(ProxyError::Parse(_))
+        ));
+    }
+}
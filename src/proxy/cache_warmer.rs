@@ -1,7 +1,11 @@
+use crate::config::CacheWarmerConfig;
 use crate::error::Result;
-use crate::state::AppState;
-use std::sync::Arc;
+use crate::state::{AppState, ServerState};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::time::interval;
 use tracing::{debug, info, warn};
 
@@ -10,50 +14,98 @@ use tracing::{debug, info, warn};
 pub struct CacheWarmer {
     state: Arc<AppState>,
     handler: Arc<super::RequestHandler>,
-    refresh_interval: Duration,
+    config: CacheWarmerConfig,
+    /// Hash of each server's list, by list method, as of the last cycle
+    hashes: Arc<Mutex<HashMap<String, HashMap<String, String>>>>,
+}
+
+/// How the servers' lists compare with the last cycle
+#[derive(Debug, PartialEq)]
+enum ListChange {
+    Unchanged,
+    /// Servers came or went; their state changes already told clients
+    Servers,
+    /// These servers list something different than before
+    Lists(Vec<String>),
 }
 
 impl CacheWarmer {
     pub fn new(
         state: Arc<AppState>,
         handler: Arc<super::RequestHandler>,
-        refresh_interval_secs: u64,
+        config: CacheWarmerConfig,
     ) -> Self {
         Self {
             state,
             handler,
-            refresh_interval: Duration::from_secs(refresh_interval_secs),
+            config,
+            hashes: Arc::default(),
         }
     }
 
     /// Start the cache warmer background task
     pub async fn run(self) {
+        if !self.config.enabled {
+            info!("Cache warmer disabled");
+            return;
+        }
+
+        let refresh_interval = Duration::from_secs(self.config.interval_seconds);
         info!(
-            "Starting cache warmer (refresh every {}s)",
-            self.refresh_interval.as_secs()
+            "Starting cache warmer (refresh every {}s, methods: {:?})",
+            refresh_interval.as_secs(),
+            self.config.methods
         );
 
-        // Wait for servers to initialize before first warm
-        // This ensures we capture all servers in the initial cache
-        info!("Waiting 5s for servers to initialize before first cache warm");
-        tokio::time::sleep(Duration::from_secs(5)).await;
-
-        // Warm cache after servers have had time to start
-        self.warm_cache_once().await;
-
-        // Then run periodic refresh
-        let mut ticker = interval(self.refresh_interval);
+        let mut state_rx = self.state.server_state_tx.subscribe();
+        let mut shutdown_rx = self.state.shutdown_tx.subscribe();
+        let mut ticker = interval(refresh_interval);
         loop {
-            ticker.tick().await;
-            debug!("Cache warmer tick - refreshing caches");
-            self.warm_cache_once().await;
+            tokio::select! {
+                _ = shutdown_rx.recv() => return,
+                _ = ticker.tick() => {
+                    debug!("Cache warmer tick - refreshing caches");
+                    self.warm_cache_once().await;
+                }
+                event = state_rx.recv() => match event {
+                    Ok((name, ServerState::Running)) => {
+                        if !self.server_opted_in(&name).await {
+                            continue;
+                        }
+                        // Coalesce servers that came up together into one warm
+                        while let Ok((other, ServerState::Running)) = state_rx.try_recv() {
+                            debug!("Server {} also became ready", other);
+                        }
+                        info!("Server {} is running, warming caches now", name);
+                        self.handler.clear_cache().await;
+                        self.warm_cache_once().await;
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!("Cache warmer missed {} state changes, warming now", skipped);
+                        self.warm_cache_once().await;
+                    }
+                    Err(RecvError::Closed) => return,
+                },
+            }
         }
     }
 
+    /// Check whether a server has not opted out of triggering warms and of
+    /// change detection
+    async fn server_opted_in(&self, server_name: &str) -> bool {
+        let config = self.state.config.read().await;
+        config
+            .servers
+            .get(server_name)
+            .map(|s| s.cache_warm)
+            .unwrap_or(true)
+    }
+
     /// Perform a single cache warming cycle
     async fn warm_cache_once(&self) {
-        // Only warm cache if we have running servers
-        let running_count = self
+        // Only warm cache if we have running servers that participate in warming
+        let running: Vec<String> = self
             .state
             .servers
             .iter()
@@ -61,31 +113,48 @@ impl CacheWarmer {
                 let state = entry.value().state.try_read();
                 matches!(
                     state,
-                    Ok(guard) if matches!(*guard, crate::state::ServerState::Running)
+                    Ok(guard) if matches!(*guard, ServerState::Running)
                 )
             })
-            .count();
+            .map(|entry| entry.key().clone())
+            .collect();
 
-        if running_count == 0 {
-            debug!("No running servers, skipping cache warm");
-            return;
+        let mut opted_in = Vec::new();
+        for name in running {
+            if self.server_opted_in(&name).await {
+                opted_in.push(name);
+            }
         }
 
-        info!("Warming cache for {} running servers", running_count);
-
-        // Warm tools cache
-        if let Err(e) = self.warm_tools_cache().await {
-            warn!("Failed to warm tools cache: {}", e);
+        if opted_in.is_empty() {
+            debug!("No running servers, skipping cache warm");
+            return;
         }
 
-        // Warm resources cache
-        if let Err(e) = self.warm_resources_cache().await {
-            warn!("Failed to warm resources cache: {}", e);
-        }
+        info!("Warming cache for {} running servers", opted_in.len());
 
-        // Warm prompts cache
-        if let Err(e) = self.warm_prompts_cache().await {
-            warn!("Failed to warm prompts cache: {}", e);
+        for method in &self.config.methods {
+            match self.compare_lists(method, &opted_in).await {
+                ListChange::Unchanged => {
+                    // Lists only expire from the handler's tools/list cache
+                    if method != "tools/list" || self.handler.renew_cache().await {
+                        debug!("{} unchanged, keeping the cached list", method);
+                        continue;
+                    }
+                }
+                ListChange::Servers => {}
+                ListChange::Lists(changed) => {
+                    info!("{} changed on {}", method, changed.join(", "));
+                    self.state.invalidate_catalog().await;
+                    let _ = self.state.client_notification_tx.send(serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "method": format!("notifications/{method}_changed")
+                    }));
+                }
+            }
+            if let Err(e) = self.warm_list_cache(method).await {
+                warn!("Failed to warm {} cache: {}", method, e);
+            }
         }
 
         // Warm capabilities cache
@@ -96,86 +165,60 @@ impl CacheWarmer {
         info!("Cache warming complete");
     }
 
-    /// Warm tools/list cache
-    async fn warm_tools_cache(&self) -> Result<()> {
-        debug!("Warming tools cache...");
-
-        // Create a dummy request for tools/list
-        let request = serde_json::json!({
-            "jsonrpc": "2.0",
-            "id": "cache_warmer",
-            "method": "tools/list"
-        });
-
-        let router = Arc::new(super::RequestRouter::new());
-        match self.handler.handle_request(request, router).await {
-            Ok(response) => {
-                if let Some(result) = response.result {
-                    if let Some(tools) = result.get("tools").and_then(|t| t.as_array()) {
-                        debug!("Tools cache warmed with {} tools", tools.len());
-                    }
+    /// Fetch each server's `method` list and compare its hash with the one
+    /// from the last cycle. A server that doesn't answer keeps its old hash.
+    async fn compare_lists(&self, method: &str, servers: &[String]) -> ListChange {
+        let key = method.trim_end_matches("/list");
+        let mut current = HashMap::new();
+        for server in servers {
+            match self.handler.forward_to_server(server, method, None).await {
+                Ok(result) => {
+                    let items = result[key]
+                        .as_array()
+                        .map(Vec::as_slice)
+                        .unwrap_or_default();
+                    current.insert(server.clone(), catalog_hash(items));
                 }
-                Ok(())
-            }
-            Err(e) => {
-                warn!("Failed to warm tools cache: {}", e);
-                Err(e)
+                Err(e) => debug!("Could not hash {} of {}: {}", method, server, e),
             }
         }
-    }
 
-    /// Warm resources/list cache
-    async fn warm_resources_cache(&self) -> Result<()> {
-        debug!("Warming resources cache...");
-
-        let request = serde_json::json!({
-            "jsonrpc": "2.0",
-            "id": "cache_warmer",
-            "method": "resources/list"
-        });
-
-        let router = Arc::new(super::RequestRouter::new());
-        match self.handler.handle_request(request, router).await {
-            Ok(response) => {
-                if let Some(result) = response.result {
-                    if let Some(resources) = result.get("resources").and_then(|r| r.as_array()) {
-                        debug!("Resources cache warmed with {} resources", resources.len());
-                    }
-                }
-                Ok(())
-            }
-            Err(e) => {
-                warn!("Failed to warm resources cache: {}", e);
-                Err(e)
-            }
-        }
+        let mut hashes = self.hashes.lock().unwrap();
+        compare_hashes(
+            hashes.entry(method.to_string()).or_default(),
+            current,
+            servers,
+        )
     }
 
-    /// Warm prompts/list cache
-    async fn warm_prompts_cache(&self) -> Result<()> {
-        debug!("Warming prompts cache...");
+    /// Warm the cache for a list method (tools/list, resources/list, prompts/list)
+    async fn warm_list_cache(&self, method: &str) -> Result<()> {
+        debug!("Warming {} cache...", method);
 
+        // Create a dummy request for the list method
         let request = serde_json::json!({
             "jsonrpc": "2.0",
             "id": "cache_warmer",
-            "method": "prompts/list"
+            "method": method
         });
 
-        let router = Arc::new(super::RequestRouter::new());
-        match self.handler.handle_request(request, router).await {
-            Ok(response) => {
-                if let Some(result) = response.result {
-                    if let Some(prompts) = result.get("prompts").and_then(|p| p.as_array()) {
-                        debug!("Prompts cache warmed with {} prompts", prompts.len());
-                    }
-                }
-                Ok(())
-            }
-            Err(e) => {
-                warn!("Failed to warm prompts cache: {}", e);
-                Err(e)
-            }
+        let router = self.state.request_router.clone();
+        let response = self
+            .handler
+            .handle_request(request, router, &Default::default())
+            .await?;
+
+        // "tools/list" -> "tools"
+        let key = method.trim_end_matches("/list");
+        if let Some(items) = response
+            .result
+            .as_ref()
+            .and_then(|result| result.get(key))
+            .and_then(|items| items.as_array())
+        {
+            debug!("{} cache warmed with {} {}", method, items.len(), key);
         }
+        Ok(())
     }
 
     /// Warm server capabilities cache
@@ -195,7 +238,7 @@ impl CacheWarmer {
             // Check if server is running
             if let Some(info) = self.state.servers.get(&server_name) {
                 let state = info.state.read().await;
-                if matches!(*state, crate::state::ServerState::Running) {
+                if matches!(*state, ServerState::Running) {
                     debug!("Caching capabilities for server: {}", server_name);
                     // TODO: Store capabilities from server's initialize response
                     // For now, capabilities are stored during connection initialization
@@ -215,7 +258,8 @@ impl CacheWarmer {
             let warmer = Self {
                 state: self.state.clone(),
                 handler: self.handler.clone(),
-                refresh_interval: self.refresh_interval,
+                config: self.config.clone(),
+                hashes: self.hashes.clone(),
             };
             async move {
                 warmer.warm_cache_once().await;
@@ -237,7 +281,8 @@ impl CacheWarmerHandle {
 
     /// Trigger immediate cache refresh
     pub async fn refresh_now(&self) {
-        let warmer = CacheWarmer::new(self.state.clone(), self.handler.clone(), 60);
+        let config = self.state.config.read().await.cache_warmer.clone();
+        let warmer = CacheWarmer::new(self.state.clone(), self.handler.clone(), config);
         warmer.warm_cache_once().await;
     }
 
@@ -248,3 +293,159 @@ impl CacheWarmerHandle {
         self.refresh_now().await;
     }
 }
+
+/// Compare the hashes of `servers` with the previous ones, which they
+/// replace. Servers missing from `current` keep their previous hash.
+fn compare_hashes(
+    previous: &mut HashMap<String, String>,
+    mut current: HashMap<String, String>,
+    servers: &[String],
+) -> ListChange {
+    for server in servers {
+        if !current.contains_key(server) {
+            if let Some(hash) = previous.get(server) {
+                current.insert(server.clone(), hash.clone());
+            }
+        }
+    }
+    let mut changed: Vec<String> = current
+        .iter()
+        .filter(|(server, hash)| previous.get(*server).is_some_and(|before| before != *hash))
+        .map(|(server, _)| server.clone())
+        .collect();
+    changed.sort();
+    let same_servers = previous.len() == current.len()
+        && current.keys().all(|server| previous.contains_key(server));
+    *previous = current;
+
+    if !changed.is_empty() {
+        ListChange::Lists(changed)
+    } else if same_servers {
+        ListChange::Unchanged
+    } else {
+        ListChange::Servers
+    }
+}
+
+/// SHA-256 of a server's list, hex encoded. Neither the order of the items
+/// nor the order of keys within them affects it, so it changes only when
+/// what the server offers does.
+fn catalog_hash(items: &[Value]) -> String {
+    let mut canonical: Vec<String> = items
+        .iter()
+        .map(|item| {
+            let mut out = String::new();
+            write_canonical(item, &mut out);
+            out
+        })
+        .collect();
+    canonical.sort();
+
+    let mut digest = ring::digest::Context::new(&ring::digest::SHA256);
+    for item in &canonical {
+        digest.update(item.as_bytes());
+        digest.update(b"\n");
+    }
+    digest
+        .finish()
+        .as_ref()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// JSON with object keys sorted
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_catalog_hash_ignores_order() {
+        let query =
+            json!({ "name": "query", "inputSchema": { "type": "object", "required": ["sql"] } });
+        let vacuum = json!({ "name": "vacuum", "description": "Reclaim space" });
+        let reordered =
+            json!({ "inputSchema": { "required": ["sql"], "type": "object" }, "name": "query" });
+
+        assert_eq!(
+            catalog_hash(&[query.clone(), vacuum.clone()]),
+            catalog_hash(&[vacuum.clone(), reordered])
+        );
+        let changed = json!({ "name": "query", "inputSchema": { "type": "object", "required": ["sql", "db"] } });
+        assert_ne!(
+            catalog_hash(&[query, vacuum.clone()]),
+            catalog_hash(&[changed, vacuum])
+        );
+    }
+
+    #[test]
+    fn test_compare_hashes() {
+        let hashes = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(server, hash)| (server.to_string(), hash.to_string()))
+                .collect()
+        };
+        let servers =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+        let mut previous = HashMap::new();
+
+        // Servers seen for the first time were announced when they started
+        assert_eq!(
+            compare_hashes(&mut previous, hashes(&[("db", "a")]), &servers(&["db"])),
+            ListChange::Servers
+        );
+        assert_eq!(
+            compare_hashes(&mut previous, hashes(&[("db", "a")]), &servers(&["db"])),
+            ListChange::Unchanged
+        );
+        // A server that doesn't answer keeps its hash
+        assert_eq!(
+            compare_hashes(&mut previous, hashes(&[]), &servers(&["db"])),
+            ListChange::Unchanged
+        );
+        assert_eq!(
+            compare_hashes(
+                &mut previous,
+                hashes(&[("db", "b"), ("git", "c")]),
+                &servers(&["db", "git"])
+            ),
+            ListChange::Lists(vec!["db".to_string()])
+        );
+        assert_eq!(
+            compare_hashes(&mut previous, hashes(&[("git", "c")]), &servers(&["git"])),
+            ListChange::Servers
+        );
+    }
+}
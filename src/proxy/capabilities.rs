@@ -0,0 +1,111 @@
+//! The capabilities advertised to clients in the initialize response
+
+use crate::state::{AppState, ServerState};
+use serde_json::{json, Map, Value};
+
+/// Capabilities the proxy serves itself. Its own tools, resources and prompts
+/// are always there, and it tells clients when the catalog changes.
+/// Resource subscriptions are not relayed, so `subscribe` stays off whatever
+/// the servers support.
+fn native() -> Value {
+    json!({
+        "tools": { "listChanged": true },
+        "resources": { "subscribe": false, "listChanged": true },
+        "prompts": { "listChanged": true }
+    })
+}
+
+/// The proxy's own capabilities plus everything a running server supports,
+/// such as `logging` or `completions`, whose requests are forwarded to the
+/// servers
+pub async fn advertised(state: &AppState) -> Value {
+    let mut capabilities = native();
+    let reported: Vec<(String, Value)> = state
+        .server_versions
+        .iter()
+        .map(|entry| (entry.key().clone(), entry.value().capabilities.clone()))
+        .collect();
+    for (server, server_capabilities) in reported {
+        if state.get_server_state(&server).await == Some(ServerState::Running) {
+            merge_backend(&mut capabilities, &server_capabilities);
+        }
+    }
+    capabilities
+}
+
+/// Add a server's capabilities to the advertised set. The catalogs are the
+/// proxy's own, so only capabilities it doesn't serve itself are taken.
+fn merge_backend(capabilities: &mut Value, backend: &Value) {
+    let (Some(capabilities), Some(backend)) = (capabilities.as_object_mut(), backend.as_object())
+    else {
+        return;
+    };
+    for (name, value) in backend {
+        if matches!(name.as_str(), "tools" | "resources" | "prompts") {
+            continue;
+        }
+        union(
+            capabilities
+                .entry(name.clone())
+                .or_insert_with(|| Value::Object(Map::new())),
+            value,
+        );
+    }
+}
+
+/// Union of two capability values: objects merge key by key and a flag is
+/// on when either side turns it on
+fn union(into: &mut Value, from: &Value) {
+    match (into, from) {
+        (Value::Object(into), Value::Object(from)) => {
+            for (key, value) in from {
+                match into.get_mut(key) {
+                    Some(existing) => union(existing, value),
+                    None => {
+                        into.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (Value::Bool(into), Value::Bool(from)) => *into |= *from,
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_capabilities_are_unioned() {
+        let mut capabilities = native();
+        merge_backend(
+            &mut capabilities,
+            &json!({
+                "tools": { "listChanged": false },
+                "resources": { "subscribe": true },
+                "logging": {},
+                "experimental": { "streaming": { "chunks": false } }
+            }),
+        );
+        merge_backend(
+            &mut capabilities,
+            &json!({
+                "completions": {},
+                "experimental": { "streaming": { "chunks": true }, "batch": {} }
+            }),
+        );
+
+        assert_eq!(
+            capabilities,
+            json!({
+                "tools": { "listChanged": true },
+                "resources": { "subscribe": false, "listChanged": true },
+                "prompts": { "listChanged": true },
+                "logging": {},
+                "completions": {},
+                "experimental": { "streaming": { "chunks": true }, "batch": {} }
+            })
+        );
+    }
+}
@@ -1,20 +1,36 @@
-use super::{CallParams, MCPError, MCPResponse, ReadParams, RequestRouter};
+use super::passthrough::{self, CallHead, RequestHead};
+use super::response_limit::{ResponseLimit, Subject};
+use super::router::QueuedRequest;
+use super::stages::Stage;
+use super::{CallParams, MCPError, MCPResponse, ReadParams, RequestContext, RequestRouter};
+use crate::config::{ExcessCallAction, PromptDuplicates};
 use crate::error::{ProxyError, Result};
-use crate::state::AppState;
+use crate::logging::CallWindow;
+use crate::plugin::schema::PluginPhase;
+use crate::state::call_limits::{CallSlot, SlotError};
+use crate::state::translation::Direction as TranslationDirection;
+use crate::state::{AppState, ServerState};
+use bytes::Bytes;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
 struct CachedResponse {
     value: Value,
     expires_at: Instant,
+    /// Catalog generation the value was aggregated under
+    catalog_generation: u64,
 }
 
 #[derive(Clone)]
 pub struct RequestHandler {
     state: Arc<AppState>,
     tools_list_cache: Arc<RwLock<Option<CachedResponse>>>,
+    /// Whether a tool list has been cached since startup
+    warmed: Arc<AtomicBool>,
 }
 
 impl RequestHandler {
@@ -22,13 +38,21 @@ impl RequestHandler {
         Self {
             state,
             tools_list_cache: Arc::new(RwLock::new(None)),
+            warmed: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Whether the tool list has been aggregated and cached at least once,
+    /// by the cache warmer or a client
+    pub fn catalog_warmed(&self) -> bool {
+        self.warmed.load(Ordering::Relaxed)
+    }
+
     pub async fn handle_request(
         &self,
         request: Value,
         router: Arc<RequestRouter>,
+        ctx: &RequestContext,
     ) -> Result<MCPResponse> {
         // Extract request ID
         let id = request.get("id").cloned();
@@ -39,26 +63,37 @@ impl RequestHandler {
             .and_then(|m| m.as_str())
             .ok_or_else(|| ProxyError::InvalidRequest("Missing method".to_string()))?;
 
+        // A static catalog lists what it holds but has nothing behind it
+        if let Some(catalog) = self.state.static_catalog() {
+            if let Some(result) = catalog.list(method) {
+                return Ok(MCPResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id,
+                    result: Some(result),
+                    error: None,
+                });
+            }
+            if matches!(
+                method,
+                "call" | "tools/call" | "read" | "resources/read" | "prompts/get"
+            ) {
+                return Ok(MCPResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id,
+                    result: None,
+                    error: Some(MCPError {
+                        code: -32601,
+                        message: format!("{method} is not available from a static catalog"),
+                        data: None,
+                    }),
+                });
+            }
+        }
+
         // Start context tracking if enabled (for tool calls and reads)
         let tracking_response_id =
             if matches!(method, "call" | "tools/call" | "read" | "resources/read") {
-                if let Some(tracker) = &*self.state.context_tracker.read().await {
-                    match tracker
-                        .start_response("mcp-proxy".to_string(), "client".to_string())
-                        .await
-                    {
-                        Ok(resp_id) => {
-                            tracing::debug!("Started tracking response: {}", resp_id);
-                            Some(resp_id)
-                        }
-                        Err(e) => {
-                            tracing::warn!("Failed to start response tracking: {}", e);
-                            None
-                        }
-                    }
-                } else {
-                    None
-                }
+                self.start_tracking(ctx).await
             } else {
                 None
             };
@@ -67,14 +102,9 @@ impl RequestHandler {
         let result = match method {
             "initialize" => {
                 // Return MCP server capabilities
-                let _config = self.state.config.read().await;
                 serde_json::json!({
                     "protocolVersion": "2025-03-26",
-                    "capabilities": {
-                        "tools": { "listChanged": false },
-                        "resources": { "subscribe": false, "listChanged": false },
-                        "prompts": { "listChanged": false }
-                    },
+                    "capabilities": super::capabilities::advertised(&self.state).await,
                     "serverInfo": {
                         "name": "mcp-rust-proxy",
                         "version": env!("CARGO_PKG_VERSION")
@@ -133,6 +163,15 @@ impl RequestHandler {
                     }
                 } else if tool_name.starts_with("mcp__proxy__server__") {
                     let server_tool = tool_name.strip_prefix("mcp__proxy__server__").unwrap();
+                    if let Err(e) = super::server_tools::authorize(server_tool, ctx.client.as_ref())
+                    {
+                        return Ok(MCPResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id,
+                            result: None,
+                            error: Some(MCPError::from(&e)),
+                        });
+                    }
                     match super::server_tools::handle_server_tool(
                         server_tool,
                         arguments,
@@ -154,6 +193,61 @@ impl RequestHandler {
                             });
                         }
                     }
+                } else if tool_name.starts_with("mcp__proxy__plugins__") {
+                    let plugin_tool = tool_name.strip_prefix("mcp__proxy__plugins__").unwrap();
+                    if let Err(e) = super::plugin_tools::authorize(plugin_tool, ctx.client.as_ref())
+                    {
+                        return Ok(MCPResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id,
+                            result: None,
+                            error: Some(MCPError::from(&e)),
+                        });
+                    }
+                    match super::plugin_tools::handle_plugin_tool(
+                        plugin_tool,
+                        arguments,
+                        self.state.clone(),
+                    )
+                    .await
+                    {
+                        Ok(result) => result,
+                        Err(e) => {
+                            return Ok(MCPResponse {
+                                jsonrpc: "2.0".to_string(),
+                                id,
+                                result: None,
+                                error: Some(MCPError {
+                                    code: -32603,
+                                    message: e,
+                                    data: None,
+                                }),
+                            });
+                        }
+                    }
+                } else if tool_name.starts_with("mcp__proxy__status__") {
+                    let status_tool = tool_name.strip_prefix("mcp__proxy__status__").unwrap();
+                    match super::status_tools::handle_status_tool(
+                        status_tool,
+                        arguments,
+                        self.state.clone(),
+                    )
+                    .await
+                    {
+                        Ok(result) => result,
+                        Err(e) => {
+                            return Ok(MCPResponse {
+                                jsonrpc: "2.0".to_string(),
+                                id,
+                                result: None,
+                                error: Some(MCPError {
+                                    code: -32603,
+                                    message: e,
+                                    data: None,
+                                }),
+                            });
+                        }
+                    }
                 } else if tool_name.starts_with("mcp__proxy__aggregator__") {
                     let aggregator_tool =
                         tool_name.strip_prefix("mcp__proxy__aggregator__").unwrap();
@@ -161,6 +255,7 @@ impl RequestHandler {
                         aggregator_tool,
                         arguments,
                         self.state.clone(),
+                        &ctx.correlation_id,
                     )
                     .await
                     {
@@ -179,12 +274,58 @@ impl RequestHandler {
                         }
                     }
                 } else {
+                    if let Err(e) = self.reserve_quota(ctx).await {
+                        return Ok(MCPResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id,
+                            result: None,
+                            error: Some(MCPError::from(&e)),
+                        });
+                    }
+
+                    let request_tokens = crate::state::usage::estimate_tokens(&arguments);
                     let call_params = CallParams {
                         tool: tool_name,
                         arguments,
                     };
-                    self.handle_call_with_tracking(call_params, router, &tracking_response_id)
-                        .await?
+                    let started = Instant::now();
+                    let result = self
+                        .handle_call_with_tracking(
+                            call_params.clone(),
+                            router,
+                            &tracking_response_id,
+                            ctx,
+                        )
+                        .await;
+                    self.record_tool_call(&call_params.tool, result.is_ok(), started, ctx);
+                    if let Err(e) = &result {
+                        self.dead_letter(
+                            &call_params.tool,
+                            &call_params.arguments,
+                            e,
+                            started,
+                            ctx,
+                        )
+                        .await;
+                    }
+                    let response_tokens = result
+                        .as_ref()
+                        .map_or(0, crate::state::usage::estimate_tokens);
+                    self.record_usage(ctx, request_tokens + response_tokens)
+                        .await;
+
+                    match result {
+                        Ok(result) => result,
+                        Err(e @ ProxyError::ServerUnavailable { .. }) => {
+                            return Ok(MCPResponse {
+                                jsonrpc: "2.0".to_string(),
+                                id,
+                                result: None,
+                                error: Some(MCPError::from(&e)),
+                            });
+                        }
+                        Err(e) => return Err(e),
+                    }
                 }
             }
             "read" | "resources/read" => {
@@ -238,7 +379,18 @@ impl RequestHandler {
                         }
                     }
                 } else {
-                    self.handle_read(params, router).await?
+                    match self.handle_read(params, router, ctx).await {
+                        Ok(result) => result,
+                        Err(e @ ProxyError::ServerUnavailable { .. }) => {
+                            return Ok(MCPResponse {
+                                jsonrpc: "2.0".to_string(),
+                                id,
+                                result: None,
+                                error: Some(MCPError::from(&e)),
+                            });
+                        }
+                        Err(e) => return Err(e),
+                    }
                 }
             }
             "ping" => {
@@ -249,8 +401,11 @@ impl RequestHandler {
                 // Check cache first
                 let cache = self.tools_list_cache.read().await;
                 if let Some(cached) = cache.as_ref() {
-                    if cached.expires_at > Instant::now() {
+                    if cached.expires_at > Instant::now()
+                        && cached.catalog_generation == self.state.catalog_generation()
+                    {
                         tracing::debug!("Returning cached tools/list response");
+                        self.state.tools_cache.record_hit();
                         return Ok(MCPResponse {
                             jsonrpc: "2.0".to_string(),
                             id,
@@ -258,10 +413,30 @@ impl RequestHandler {
                             error: None,
                         });
                     }
+                    self.state.tools_cache.record_eviction();
                 }
                 drop(cache);
+                self.state.tools_cache.record_miss();
+
+                // Cache miss or expired; another instance may have aggregated it
+                let catalog_generation = self.state.catalog_generation();
+                if let Some(result) = self.shared_catalog(method).await {
+                    tracing::debug!("Returning shared tools/list response");
+                    *self.tools_list_cache.write().await = Some(CachedResponse {
+                        value: result.clone(),
+                        expires_at: Instant::now() + Duration::from_secs(120), // 2 minutes
+                        catalog_generation,
+                    });
+                    self.warmed.store(true, Ordering::Relaxed);
+                    return Ok(MCPResponse {
+                        jsonrpc: "2.0".to_string(),
+                        id,
+                        result: Some(result),
+                        error: None,
+                    });
+                }
 
-                // Cache miss or expired, fetch fresh data
+                // Fetch fresh data
                 tracing::debug!("Cache miss for tools/list, fetching from servers");
                 match self
                     .forward_to_all_servers(method, request.get("params"))
@@ -274,9 +449,13 @@ impl RequestHandler {
                         {
                             let tracing_tools = super::tracing_tools::get_tracing_tools();
                             let server_tools = super::server_tools::get_server_tools();
+                            let plugin_tools = super::plugin_tools::get_plugin_tools();
+                            let status_tools = super::status_tools::get_status_tools();
                             let aggregator_tools = super::aggregator_tools::get_aggregator_tools();
                             tools_array.extend(tracing_tools);
                             tools_array.extend(server_tools);
+                            tools_array.extend(plugin_tools);
+                            tools_array.extend(status_tools);
                             tools_array.extend(aggregator_tools);
                         }
 
@@ -285,7 +464,11 @@ impl RequestHandler {
                         *cache = Some(CachedResponse {
                             value: result.clone(),
                             expires_at: Instant::now() + Duration::from_secs(120), // 2 minutes
+                            catalog_generation,
                         });
+                        drop(cache);
+                        self.warmed.store(true, Ordering::Relaxed);
+                        self.share_catalog(method, &result).await;
                         result
                     }
                     Err(e) => {
@@ -303,10 +486,15 @@ impl RequestHandler {
                     }
                 }
             }
-            "resources/list" => {
-                // Use list_resources() to ensure proxy resources are included
-                self.list_resources(router).await?
-            }
+            "resources/list" => match self.shared_catalog(method).await {
+                Some(result) => result,
+                None => {
+                    // Use list_resources() to ensure proxy resources are included
+                    let result = self.list_resources(router).await?;
+                    self.share_catalog(method, &result).await;
+                    result
+                }
+            },
             "prompts/list" => {
                 // Use list_prompts() to ensure proxy prompts are included
                 self.list_prompts(router).await?
@@ -317,8 +505,20 @@ impl RequestHandler {
                     .forward_to_all_servers(method, request.get("params"))
                     .await
                 {
-                    Ok(result) => result,
-                    Err(_) => serde_json::json!({ "resourceTemplates": [] }),
+                    Ok(mut result) => {
+                        if let Some(templates) = result.as_object_mut().and_then(|result| {
+                            result
+                                .entry("resourceTemplates")
+                                .or_insert_with(|| serde_json::json!([]))
+                                .as_array_mut()
+                        }) {
+                            templates.extend(super::resources::get_proxy_resource_templates());
+                        }
+                        result
+                    }
+                    Err(_) => serde_json::json!({
+                        "resourceTemplates": super::resources::get_proxy_resource_templates()
+                    }),
                 }
             }
             "prompts/get" => {
@@ -329,12 +529,34 @@ impl RequestHandler {
                     .and_then(|n| n.as_str())
                     .ok_or_else(|| ProxyError::InvalidRequest("Missing prompt name".to_string()))?;
 
+                let configured = self
+                    .state
+                    .config
+                    .read()
+                    .await
+                    .prompts
+                    .iter()
+                    .find(|prompt| prompt.name == prompt_name)
+                    .cloned();
+
                 // Try to get from proxy prompts
-                if let Some(prompt) = super::prompts::get_prompt(
+                if let Some(prompt) = configured {
+                    let proxy = super::prompts::proxy_data(&self.state).await;
+                    super::prompts::render_configured_prompt(
+                        &prompt,
+                        params.and_then(|p| p.get("arguments")),
+                        proxy,
+                    )?
+                } else if let Some(prompt) = super::prompts::get_prompt(
                     prompt_name,
                     params.and_then(|p| p.get("arguments")).cloned(),
                 ) {
                     prompt
+                } else if let Some((server, name)) = self.resolve_prompt(prompt_name, &router) {
+                    let mut params = params.cloned().unwrap_or_else(|| serde_json::json!({}));
+                    params["name"] = Value::String(name);
+                    self.forward_to_server(&server, method, Some(&params))
+                        .await?
                 } else {
                     // Forward to backend servers
                     match self
@@ -382,20 +604,7 @@ impl RequestHandler {
 
         // Finalize context tracking if we were tracking this request
         if let Some(resp_id) = tracking_response_id {
-            if let Some(tracker) = &*self.state.context_tracker.read().await {
-                match tracker.finalize_response(resp_id.clone(), None).await {
-                    Ok(manifest) => {
-                        tracing::info!(
-                            "Response {} tracked: {} contexts, manifest generated",
-                            resp_id,
-                            manifest.context_tree.len()
-                        );
-                    }
-                    Err(e) => {
-                        tracing::warn!("Failed to finalize response tracking: {}", e);
-                    }
-                }
-            }
+            self.finish_tracking(resp_id).await;
         }
 
         Ok(MCPResponse {
@@ -406,6 +615,356 @@ impl RequestHandler {
         })
     }
 
+    /// Forward a tool call as raw bytes when nothing needs to see or change
+    /// its payload: no plugins for the server and no protocol translation.
+    /// Returns `None` for requests that must go through
+    /// [`Self::handle_request`] instead.
+    pub async fn pass_through(
+        &self,
+        body: &[u8],
+        router: &RequestRouter,
+        ctx: &RequestContext,
+    ) -> Option<Result<Bytes>> {
+        let routing = Instant::now();
+        let head = RequestHead::parse(body)?;
+        if !matches!(&*head.method, "call" | "tools/call") {
+            return None;
+        }
+        let call = CallHead::parse(head.params?)?;
+        if ["tracing", "server", "plugins", "status", "aggregator"]
+            .iter()
+            .any(|kind| call.name.starts_with(&format!("mcp__proxy__{kind}__")))
+        {
+            return None;
+        }
+        // Errors resolving the tool are reported by the full path
+        let (server_name, tool) = self.resolve_tool(&call.name, router).ok()?;
+        if !self.can_pass_through(&server_name).await {
+            return None;
+        }
+        ctx.set_server(&server_name);
+
+        ctx.record_stage(Stage::Routing, routing);
+        tracing::debug!(server = %server_name, tool = %tool, "Passing tool call through");
+        let started = Instant::now();
+        let result = self
+            .forward_raw(&head, &call, &server_name, &tool, ctx)
+            .await;
+        self.record_tool_call(&call.name, result.is_ok(), started, ctx);
+        if let Err(e) = &result {
+            let arguments = call
+                .arguments
+                .and_then(|arguments| serde_json::from_str(arguments.get()).ok())
+                .unwrap_or(Value::Null);
+            self.dead_letter(&call.name, &arguments, e, started, ctx)
+                .await;
+        }
+        Some(result)
+    }
+
+    /// Count a finished call towards its tool's series in the metrics
+    /// history, once it was routed to a server
+    fn record_tool_call(&self, tool: &str, ok: bool, started: Instant, ctx: &RequestContext) {
+        if let Some(server) = ctx.server.get() {
+            self.state
+                .metrics_history
+                .record_tool_call(server, tool, ok, started.elapsed());
+        }
+    }
+
+    /// Keep a failed tool call in the dead-letter queue, if there is one,
+    /// with a snapshot of the server it was routed to
+    async fn dead_letter(
+        &self,
+        tool: &str,
+        arguments: &Value,
+        error: &ProxyError,
+        started: Instant,
+        ctx: &RequestContext,
+    ) {
+        let Some(dead_letters) = &self.state.dead_letters else {
+            return;
+        };
+        let server = ctx.server.get().cloned();
+        let mut server_state = None;
+        if let Some(name) = &server {
+            if let Some(info) = self.state.servers.get(name).map(|info| info.clone()) {
+                let healthy = info
+                    .last_health_check
+                    .read()
+                    .await
+                    .as_ref()
+                    .map(|check| check.success);
+                server_state = Some(crate::state::dead_letter::ServerSnapshot {
+                    state: format!("{:?}", *info.state.read().await),
+                    restart_count: *info.restart_count.read().await,
+                    in_maintenance: self.state.maintenance.get(name).is_some(),
+                    healthy,
+                    in_flight: self
+                        .state
+                        .connection_pool
+                        .stats()
+                        .iter()
+                        .find(|stats| &stats.server == name)
+                        .map_or(0, |stats| stats.in_flight),
+                });
+            }
+        }
+
+        let id = dead_letters.record(crate::state::DeadLetter {
+            id: 0,
+            timestamp: chrono::Utc::now(),
+            tool: tool.to_string(),
+            arguments: arguments.clone(),
+            server,
+            error: serde_json::to_value(MCPError::from(error)).unwrap_or(Value::Null),
+            duration_ms: started.elapsed().as_millis() as u64,
+            server_state,
+            correlation_id: ctx.correlation_id.clone(),
+            client: ctx.client.as_ref().map(|client| client.id.clone()),
+        });
+        tracing::debug!(dead_letter = id, tool = tool, "Recorded failed tool call");
+    }
+
+    /// Whether calls to a server can skip transforms, plugins and
+    /// translation. Servers that are disabled, in maintenance or starting
+    /// take the full path, which reports or queues the call.
+    async fn can_pass_through(&self, server_name: &str) -> bool {
+        let config = self.state.config.read().await;
+        let enabled = config
+            .servers
+            .get(server_name)
+            .map(|s| s.enabled)
+            .unwrap_or(true);
+        let has_transforms = config
+            .servers
+            .get(server_name)
+            .is_some_and(|s| !s.transforms.is_empty());
+        // Results must be parsed to be checked against output schemas
+        let validates_output = config
+            .servers
+            .get(server_name)
+            .is_some_and(|s| s.strict_output_schema);
+        let has_plugins = self.state.plugin_manager.is_some()
+            && config.plugins.as_ref().is_some_and(|plugins| {
+                [PluginPhase::Request, PluginPhase::Response]
+                    .into_iter()
+                    .any(|phase| !plugins.get_plugins_for_phase(server_name, phase).is_empty())
+            });
+        drop(config);
+
+        if !enabled
+            || has_plugins
+            || has_transforms
+            || validates_output
+            || self.state.maintenance.get(server_name).is_some()
+        {
+            return false;
+        }
+        if self.server_initializing(server_name).await {
+            return false;
+        }
+
+        let Some(connection_state) = self
+            .state
+            .servers
+            .get(server_name)
+            .and_then(|info| info.connection_state.clone())
+        else {
+            return true;
+        };
+        if !connection_state.can_send_request("tools/call").await {
+            return false;
+        }
+        match connection_state.get_adapter().await {
+            Some(adapter) => adapter.source_version() == adapter.target_version(),
+            None => true,
+        }
+    }
+
+    async fn forward_raw(
+        &self,
+        head: &RequestHead<'_>,
+        call: &CallHead<'_>,
+        server_name: &str,
+        tool: &str,
+        ctx: &RequestContext,
+    ) -> Result<Bytes> {
+        if let Err(e) = self.reserve_quota(ctx).await {
+            let reply = MCPResponse::error(head.id_value(), &e).with_context(ctx);
+            return Ok(Bytes::from(serde_json::to_vec(&reply)?));
+        }
+
+        if let Some(server_info) = self.state.servers.get(server_name) {
+            *server_info.last_access_time.write().await = Some(chrono::Utc::now());
+        }
+
+        let tracking_response_id = self.start_tracking(ctx).await;
+        let backend = Instant::now();
+        let permit = self.acquire_call_slot(server_name, tool, ctx).await?;
+        let conn = self.state.connection_pool.get(server_name).await?;
+        let meta = self.trace_meta(server_name, ctx).await;
+        let window = self.log_window(server_name, ctx);
+        conn.send(passthrough::call_request(
+            tool,
+            call.arguments,
+            meta.as_ref(),
+        )?)
+        .await?;
+        let response = conn.recv().await?;
+        drop(window);
+        if let Some(slot) = permit {
+            slot.succeeded();
+        }
+        ctx.record_stage(Stage::Backend, backend);
+        let response = self.limit_raw_response(server_name, tool, response).await?;
+
+        let result_tokens = crate::state::usage::tokens_for_len(response.len());
+        self.record_usage(
+            ctx,
+            crate::state::usage::tokens_for_len(call.arguments_len()) + result_tokens,
+        )
+        .await;
+        self.state
+            .metrics
+            .record_tool_result(server_name, tool, result_tokens);
+
+        let meta = self
+            .state
+            .config
+            .read()
+            .await
+            .proxy
+            .token_estimates
+            .then(|| {
+                let mut meta = serde_json::Map::new();
+                meta.insert("tokenEstimate".to_string(), result_tokens.into());
+                meta
+            });
+        let reply = passthrough::client_reply(head.id, &response, meta.as_ref())?;
+        if let Some(resp_id) = tracking_response_id {
+            let result = serde_json::from_slice::<Value>(&response)
+                .map(|mut reply| reply["result"].take())
+                .unwrap_or_default();
+            self.record_context_from_server(
+                &Some(resp_id.clone()),
+                server_name,
+                &call.name,
+                &result,
+            )
+            .await;
+            self.finish_tracking(resp_id).await;
+        }
+        Ok(reply)
+    }
+
+    /// Apply blob handling, resource link prefixes and the server's
+    /// response size limit to a raw reply, decoding it only when it may hold
+    /// a blob or a resource link or be over the limit
+    async fn limit_raw_response(
+        &self,
+        server_name: &str,
+        tool: &str,
+        response: Bytes,
+    ) -> Result<Bytes> {
+        // The envelope only adds to the size, so a reply within the limit
+        // holds a result within it
+        let limit = self
+            .response_limit(server_name, Some(tool))
+            .await
+            .filter(|limit| response.len() > limit.max_bytes);
+        let has_blobs = self.handles_blobs().await && contains(&response, b"\"blob\"");
+        let has_links = contains(&response, b"\"resource_link\"");
+        if limit.is_none() && !has_blobs && !has_links {
+            return Ok(response);
+        }
+        let mut reply: Value = serde_json::from_slice(&response)?;
+        let Some(result) = reply.get_mut("result") else {
+            return Ok(response);
+        };
+        if has_links {
+            super::resource_links::prefix_links(result, server_name);
+        }
+        if has_blobs {
+            self.process_blobs(result, server_name).await?;
+        }
+        if let Some(limit) = limit {
+            limit.enforce(result, server_name, Subject::Tool(tool))?;
+        }
+        Ok(Bytes::from(serde_json::to_vec(&reply)?))
+    }
+
+    /// Whether blobs are limited or served as downloads
+    async fn handles_blobs(&self) -> bool {
+        self.state.blob_downloads.is_some()
+            || self.state.config.read().await.blobs.max_bytes.is_some()
+    }
+
+    /// With `strictOutputSchema`, turn a successful result that doesn't
+    /// carry structured content matching the tool's declared output schema
+    /// into a tool error, so clients never act on malformed output
+    async fn check_output_schema(
+        &self,
+        result: &mut Value,
+        server_name: &str,
+        tool: &str,
+        router: &RequestRouter,
+    ) {
+        let strict = self
+            .state
+            .config
+            .read()
+            .await
+            .servers
+            .get(server_name)
+            .is_some_and(|server| server.strict_output_schema);
+        if !strict || result.get("isError") == Some(&Value::Bool(true)) {
+            return;
+        }
+        let Some(schema) = router.output_schema(server_name, tool) else {
+            return;
+        };
+
+        let problem = match result.get("structuredContent") {
+            None | Some(Value::Null) => "has no structuredContent".to_string(),
+            Some(structured) => {
+                let errors = super::json_schema::validate(&schema, structured);
+                if errors.is_empty() {
+                    return;
+                }
+                format!("does not match its outputSchema: {}", errors.join("; "))
+            }
+        };
+        tracing::warn!(
+            server = server_name,
+            tool = tool,
+            "Tool result failed output schema validation: {}",
+            problem
+        );
+        *result = serde_json::json!({
+            "content": [{
+                "type": "text",
+                "text": format!("The result of {tool} {problem}")
+            }],
+            "isError": true
+        });
+    }
+
+    async fn process_blobs(&self, result: &mut Value, server_name: &str) -> Result<()> {
+        let max_bytes = self.state.config.read().await.blobs.max_bytes;
+        super::blobs::process(
+            result,
+            server_name,
+            max_bytes,
+            self.state.blob_downloads.as_deref(),
+        )
+    }
+
+    async fn response_limit(&self, server_name: &str, tool: Option<&str>) -> Option<ResponseLimit> {
+        let config = self.state.config.read().await;
+        ResponseLimit::for_server(config.servers.get(server_name)?, tool)
+    }
+
     async fn handle_list(&self, params: &Value, router: Arc<RequestRouter>) -> Result<Value> {
         let list_type = params
             .get("type")
@@ -427,8 +986,9 @@ impl RequestHandler {
         params: CallParams,
         router: Arc<RequestRouter>,
         tracking_response_id: &Option<String>,
+        ctx: &RequestContext,
     ) -> Result<Value> {
-        let result = self.handle_call(params.clone(), router).await?;
+        let result = self.handle_call(params.clone(), router, ctx).await?;
 
         // Record context from this backend call
         if let Some(resp_id) = tracking_response_id {
@@ -447,8 +1007,8 @@ impl RequestHandler {
             self.record_context_from_server(
                 &Some(resp_id.clone()),
                 &server_name,
-                "tools/call",
                 &params.tool,
+                &result,
             )
             .await;
         }
@@ -456,29 +1016,78 @@ impl RequestHandler {
         Ok(result)
     }
 
-    async fn handle_call(&self, params: CallParams, router: Arc<RequestRouter>) -> Result<Value> {
+    /// Find the server for a tool and the tool's name on that server
+    pub(crate) fn resolve_tool(
+        &self,
+        tool: &str,
+        router: &RequestRouter,
+    ) -> Result<(String, String)> {
         // Check if tool name has proxy prefix: mcp__proxy__{server}__{tool}
-        let (server_name, original_tool_name) = if params.tool.starts_with("mcp__proxy__") {
+        if tool.starts_with("mcp__proxy__") {
             // Parse the prefixed name to extract server and original tool name
-            let parts: Vec<&str> = params.tool.splitn(4, "__").collect();
+            let parts: Vec<&str> = tool.splitn(4, "__").collect();
             if parts.len() == 4 && parts[0] == "mcp" && parts[1] == "proxy" {
                 let server = parts[2].replace("_", "-");
-                let tool = parts[3].to_string();
-                (server, tool)
+                return Ok((server, parts[3].to_string()));
+            }
+            // Malformed prefix, try original routing
+        }
+
+        let server = router.get_server_for_tool(tool).ok_or_else(|| {
+            let servers = router.servers_for_tool(tool);
+            if servers.len() > 1 {
+                ProxyError::InvalidRequest(format!(
+                    "Tool '{tool}' is provided by several servers ({}); call it by its prefixed name",
+                    servers.join(", ")
+                ))
             } else {
-                // Malformed prefix, try original routing
-                let server = router.get_server_for_tool(&params.tool).ok_or_else(|| {
-                    ProxyError::ServerNotFound(format!("No server handles tool: {}", params.tool))
-                })?;
-                (server, params.tool.clone())
+                ProxyError::ServerNotFound(format!("No server handles tool: {tool}"))
             }
-        } else {
-            // No prefix, use router to find server
-            let server = router.get_server_for_tool(&params.tool).ok_or_else(|| {
-                ProxyError::ServerNotFound(format!("No server handles tool: {}", params.tool))
-            })?;
-            (server, params.tool.clone())
-        };
+        })?;
+        Ok((server, tool.to_string()))
+    }
+
+    /// Find the server for a prompt and the prompt's name on that server,
+    /// when a prefix or the registered prompts say which it is
+    fn resolve_prompt(&self, prompt: &str, router: &RequestRouter) -> Option<(String, String)> {
+        if let Some(rest) = prompt.strip_prefix("mcp__proxy__") {
+            if let Some((server, name)) = rest.split_once("__") {
+                return Some((server.replace('_', "-"), name.to_string()));
+            }
+        }
+        let server = router.get_server_for_prompt(prompt)?;
+        Some((server, prompt.to_string()))
+    }
+
+    /// The `_meta` handing the request's trace on to a server, when the
+    /// request has one and the server takes it
+    async fn trace_meta(
+        &self,
+        server_name: &str,
+        ctx: &RequestContext,
+    ) -> Option<serde_json::Map<String, Value>> {
+        let trace = ctx.trace.as_ref()?;
+        let config = self.state.config.read().await;
+        let server = config.servers.get(server_name)?;
+        server.propagate_trace_context.then(|| trace.meta())
+    }
+
+    /// Tag the server's log output with the request's correlation ID until
+    /// the returned window is dropped
+    fn log_window(&self, server_name: &str, ctx: &RequestContext) -> Option<CallWindow> {
+        let logger = self.state.servers.get(server_name)?.logger.clone()?;
+        Some(logger.call_window(&ctx.correlation_id))
+    }
+
+    async fn handle_call(
+        &self,
+        params: CallParams,
+        router: Arc<RequestRouter>,
+        ctx: &RequestContext,
+    ) -> Result<Value> {
+        let routing = Instant::now();
+        let (server_name, original_tool_name) = self.resolve_tool(&params.tool, &router)?;
+        ctx.set_server(&server_name);
 
         // Check if server is enabled
         let config = self.state.config.read().await;
@@ -487,14 +1096,27 @@ impl RequestHandler {
             .get(&server_name)
             .map(|s| s.enabled)
             .unwrap_or(true);
+        let transforms = config
+            .servers
+            .get(&server_name)
+            .map(|s| s.transforms.clone())
+            .unwrap_or_default();
         drop(config);
 
         if !server_enabled {
-            return Err(ProxyError::ServerNotFound(format!(
-                "Server '{server_name}' is disabled. Enable it with mcp__proxy__server__enable"
-            )));
+            let reason = match self.state.disabled_servers.get(&server_name).await {
+                Some(disabled) => disabled.reason,
+                None => "enable it with mcp__proxy__server__enable".to_string(),
+            };
+            return Err(ProxyError::ServerDisabled {
+                server: server_name,
+                reason,
+            });
         }
 
+        self.check_not_in_maintenance(&server_name)?;
+        ctx.record_stage(Stage::Routing, routing);
+
         // Extract tokens parameter from arguments (for Context7 and similar servers)
         let max_tokens = params
             .arguments
@@ -502,40 +1124,77 @@ impl RequestHandler {
             .and_then(|t| t.as_u64())
             .map(|t| t as u32);
 
-        // Apply request-phase plugins before forwarding to server
+        // Apply configured transforms, then request-phase plugins, before
+        // forwarding to server
+        let mut arguments = params.arguments.clone();
+        let mut meta = super::transforms::apply(&transforms, &original_tool_name, &mut arguments);
+        let plugins = Instant::now();
         let processed_arguments = self
-            .apply_request_plugins(&server_name, &original_tool_name, params.arguments.clone())
+            .apply_request_plugins(
+                &server_name,
+                &original_tool_name,
+                arguments,
+                &ctx.correlation_id,
+            )
             .await?;
-
-        // Get connection from pool
-        let conn = self.state.connection_pool.get(&server_name).await?;
+        ctx.record_stage(Stage::Plugins, plugins);
 
         // Forward request to server with ORIGINAL tool name (no prefix)
         // Use MCP spec format: tools/call with "name" field
-        let request = serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": "tools/call",
-            "params": {
-                "name": original_tool_name,
-                "arguments": processed_arguments,
-            },
-            "id": 1
+        let mut call_params = serde_json::json!({
+            "name": original_tool_name,
+            "arguments": processed_arguments,
         });
+        if let Some(trace_meta) = self.trace_meta(&server_name, ctx).await {
+            meta.extend(trace_meta);
+        }
+        if !meta.is_empty() {
+            call_params["_meta"] = Value::Object(meta);
+        }
 
-        conn.send(bytes::Bytes::from(format!("{request}\n")))
+        let backend = Instant::now();
+        let permit = self
+            .acquire_call_slot(&server_name, &original_tool_name, ctx)
             .await?;
+        let window = self.log_window(&server_name, ctx);
+        let mut result = if self.server_initializing(&server_name).await {
+            // Hold the call until the server finishes its handshake
+            self.queue_until_ready(&server_name, "tools/call", Some(call_params))
+                .await?
+        } else {
+            // Get connection from pool
+            let conn = self.state.connection_pool.get(&server_name).await?;
+
+            let request = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": call_params,
+                "id": 1
+            });
+
+            conn.send(bytes::Bytes::from(format!("{request}\n")))
+                .await?;
+
+            // Get response
+            let response = conn.recv().await?;
+            let response: Value = serde_json::from_slice(&response)?;
+
+            // Extract result
+            response
+                .get("result")
+                .cloned()
+                .ok_or_else(|| ProxyError::InvalidRequest("No result in response".to_string()))?
+        };
+        drop(window);
+        if let Some(slot) = permit {
+            slot.succeeded();
+        }
+        ctx.record_stage(Stage::Backend, backend);
 
-        // Get response
-        let response = conn.recv().await?;
-        let response: Value = serde_json::from_slice(&response)?;
-
-        // Extract result
-        let mut result = response
-            .get("result")
-            .cloned()
-            .ok_or_else(|| ProxyError::InvalidRequest("No result in response".to_string()))?;
-
-        // Apply response-phase plugins if configured
+        // Apply response-phase plugins if configured, with any blobs set
+        // aside so they are not rewritten as text
+        let plugins = Instant::now();
+        let detached = super::blobs::detach(&mut result);
         result = self
             .apply_response_plugins(
                 &server_name,
@@ -543,31 +1202,77 @@ impl RequestHandler {
                 result,
                 max_tokens,
                 Some(params.arguments.clone()),
+                &ctx.correlation_id,
             )
             .await?;
+        detached.reattach(&mut result);
+        ctx.record_stage(Stage::Plugins, plugins);
+
+        super::resource_links::prefix_links(&mut result, &server_name);
+        self.check_output_schema(&mut result, &server_name, &original_tool_name, &router)
+            .await;
+        self.process_blobs(&mut result, &server_name).await?;
+        if let Some(limit) = self
+            .response_limit(&server_name, Some(&original_tool_name))
+            .await
+        {
+            limit.enforce(
+                &mut result,
+                &server_name,
+                Subject::Tool(&original_tool_name),
+            )?;
+        }
+
+        let tokens = crate::state::usage::estimate_tokens(&result);
+        self.state
+            .metrics
+            .record_tool_result(&server_name, &original_tool_name, tokens);
+        if self.state.config.read().await.proxy.token_estimates {
+            crate::state::usage::annotate_tokens(&mut result, tokens);
+        }
 
         Ok(result)
     }
 
-    async fn handle_read(&self, params: ReadParams, router: Arc<RequestRouter>) -> Result<Value> {
-        // Find server that handles this resource
-        let server_name = router.get_server_for_resource(&params.uri).ok_or_else(|| {
-            ProxyError::ServerNotFound(format!("No server handles resource: {}", params.uri))
-        })?;
+    async fn handle_read(
+        &self,
+        params: ReadParams,
+        router: Arc<RequestRouter>,
+        ctx: &RequestContext,
+    ) -> Result<Value> {
+        // Find server that handles this resource, by the prefix resources
+        // are listed under or else the registered resources
+        let (server_name, uri) = match super::resource_links::unprefixed_uri(&params.uri) {
+            Some((server, uri)) => (server, uri.to_string()),
+            None => {
+                let server = router.get_server_for_resource(&params.uri).ok_or_else(|| {
+                    ProxyError::ServerNotFound(format!(
+                        "No server handles resource: {}",
+                        params.uri
+                    ))
+                })?;
+                (server, params.uri.clone())
+            }
+        };
+        ctx.set_server(&server_name);
+        self.check_not_in_maintenance(&server_name)?;
 
         // Get connection from pool
         let conn = self.state.connection_pool.get(&server_name).await?;
 
         // Forward request to server
+        let mut read_params = serde_json::json!({ "uri": uri });
+        if let Some(meta) = self.trace_meta(&server_name, ctx).await {
+            read_params["_meta"] = Value::Object(meta);
+        }
         let request = serde_json::json!({
             "jsonrpc": "2.0",
             "method": "read",
-            "params": {
-                "uri": params.uri,
-            },
+            "params": read_params,
             "id": 1
         });
 
+        let _window = self.log_window(&server_name, ctx);
         conn.send(bytes::Bytes::from(format!("{request}\n")))
             .await?;
 
@@ -576,10 +1281,15 @@ impl RequestHandler {
         let response: Value = serde_json::from_slice(&response)?;
 
         // Extract result
-        response
+        let mut result = response
             .get("result")
             .cloned()
-            .ok_or_else(|| ProxyError::InvalidRequest("No result in response".to_string()))
+            .ok_or_else(|| ProxyError::InvalidRequest("No result in response".to_string()))?;
+        self.process_blobs(&mut result, &server_name).await?;
+        if let Some(limit) = self.response_limit(&server_name, None).await {
+            limit.enforce(&mut result, &server_name, Subject::Resource(&uri))?;
+        }
+        Ok(result)
     }
 
     async fn list_tools(&self, _router: Arc<RequestRouter>) -> Result<Value> {
@@ -592,6 +1302,8 @@ impl RequestHandler {
                     let server_tools = super::server_tools::get_server_tools();
                     tools_array.extend(tracing_tools);
                     tools_array.extend(server_tools);
+                    tools_array.extend(super::plugin_tools::get_plugin_tools());
+                    tools_array.extend(super::status_tools::get_status_tools());
                 }
                 Ok(result)
             }
@@ -599,6 +1311,8 @@ impl RequestHandler {
                 // If no backends available, return only proxy tools
                 let mut proxy_tools = super::tracing_tools::get_tracing_tools();
                 proxy_tools.extend(super::server_tools::get_server_tools());
+                proxy_tools.extend(super::plugin_tools::get_plugin_tools());
+                proxy_tools.extend(super::status_tools::get_status_tools());
                 Ok(serde_json::json!({ "tools": proxy_tools }))
             }
         }
@@ -627,6 +1341,11 @@ impl RequestHandler {
     }
 
     async fn list_prompts(&self, _router: Arc<RequestRouter>) -> Result<Value> {
+        let mut proxy_prompts = super::prompts::get_proxy_prompts();
+        proxy_prompts.extend(super::prompts::get_configured_prompts(
+            &self.state.config.read().await.prompts,
+        ));
+
         // Aggregate prompts from all backend servers
         match self.forward_to_all_servers("prompts/list", None).await {
             Ok(mut result) => {
@@ -634,14 +1353,12 @@ impl RequestHandler {
                 if let Some(prompts_array) =
                     result.get_mut("prompts").and_then(|p| p.as_array_mut())
                 {
-                    let proxy_prompts = super::prompts::get_proxy_prompts();
                     prompts_array.extend(proxy_prompts);
                 }
                 Ok(result)
             }
             Err(_) => {
                 // If no backends available, return only proxy prompts
-                let proxy_prompts = super::prompts::get_proxy_prompts();
                 Ok(serde_json::json!({ "prompts": proxy_prompts }))
             }
         }
@@ -652,12 +1369,13 @@ impl RequestHandler {
         use futures::future::join_all;
         use tokio::time::{timeout, Duration};
 
-        // Collect server names
+        // Collect server names, leaving out servers in maintenance
         let server_names: Vec<String> = self
             .state
             .servers
             .iter()
             .map(|entry| entry.key().clone())
+            .filter(|name| !self.state.maintenance.contains(name))
             .collect();
 
         // Create concurrent requests with timeout
@@ -708,6 +1426,9 @@ impl RequestHandler {
                 "No servers could handle the request".to_string(),
             ));
         }
+        self.state
+            .request_router
+            .sort_by_priority(&mut aggregated_results);
 
         // Aggregate results based on method type
         match method {
@@ -715,6 +1436,22 @@ impl RequestHandler {
                 let mut all_tools = Vec::new();
                 for (server_name, result) in aggregated_results {
                     if let Some(tools) = result.get("tools").and_then(|t| t.as_array()) {
+                        // Route unprefixed calls by the servers' own tool names
+                        self.state.request_router.register_server_tools(
+                            &server_name,
+                            tools
+                                .iter()
+                                .filter_map(|tool| tool.get("name").and_then(Value::as_str))
+                                .map(str::to_string),
+                        );
+                        self.state.request_router.register_output_schemas(
+                            &server_name,
+                            tools.iter().filter_map(|tool| {
+                                let name = tool.get("name")?.as_str()?;
+                                let schema = tool.get("outputSchema")?;
+                                Some((name.to_string(), schema.clone()))
+                            }),
+                        );
                         // Prefix each tool name with mcp__proxy__{server_name}__
                         for tool in tools {
                             let mut prefixed_tool = tool.clone();
@@ -758,11 +1495,8 @@ impl RequestHandler {
                             if let Some(res_obj) = prefixed_resource.as_object_mut() {
                                 if let Some(uri) = res_obj.get("uri").and_then(|u| u.as_str()) {
                                     let uri_str = uri.to_string();
-                                    let prefixed_uri = format!(
-                                        "mcp__proxy__{}://{}",
-                                        server_name.replace("-", "_"),
-                                        uri_str
-                                    );
+                                    let prefixed_uri =
+                                        super::resource_links::prefixed_uri(&server_name, uri);
                                     res_obj
                                         .insert("uri".to_string(), serde_json::json!(prefixed_uri));
                                     res_obj.insert(
@@ -787,87 +1521,279 @@ impl RequestHandler {
                 Ok(serde_json::json!({ "resources": all_resources }))
             }
             "prompts/list" => {
-                let mut all_prompts = Vec::new();
-                for (server_name, result) in aggregated_results {
+                let router = &self.state.request_router;
+                for (server_name, result) in &aggregated_results {
                     if let Some(prompts) = result.get("prompts").and_then(|p| p.as_array()) {
-                        // Prefix each prompt name with server name
-                        for prompt in prompts {
-                            let mut prefixed_prompt = prompt.clone();
-                            if let Some(prompt_obj) = prefixed_prompt.as_object_mut() {
-                                if let Some(name) = prompt_obj.get("name").and_then(|n| n.as_str())
-                                {
-                                    let name_str = name.to_string();
-                                    let prefixed_name = format!(
-                                        "mcp__proxy__{}__{}",
-                                        server_name.replace("-", "_"),
-                                        name_str
-                                    );
-                                    prompt_obj.insert(
-                                        "name".to_string(),
-                                        serde_json::json!(prefixed_name),
-                                    );
-                                    prompt_obj.insert(
-                                        "originalName".to_string(),
-                                        serde_json::json!(name_str),
-                                    );
-                                    prompt_obj.insert(
-                                        "server".to_string(),
-                                        serde_json::json!(server_name.clone()),
-                                    );
-                                }
-                            }
-                            all_prompts.push(prefixed_prompt);
-                        }
+                        router.register_server_prompts(
+                            server_name,
+                            prompts
+                                .iter()
+                                .filter_map(|prompt| prompt.get("name").and_then(Value::as_str))
+                                .map(str::to_string),
+                        );
                     }
                 }
+                let all_prompts = match router.prompt_duplicates() {
+                    PromptDuplicates::Prefixed => prefixed_prompts(aggregated_results),
+                    PromptDuplicates::Precedence => {
+                        deduplicated_prompts(aggregated_results, router)
+                    }
+                };
                 Ok(serde_json::json!({ "prompts": all_prompts }))
             }
+            _ if method.ends_with("/list") => Ok(merged_lists(aggregated_results)),
             _ => {
-                // For other methods, just return the first successful result
+                // Other methods get the result of the highest priority server
                 Ok(aggregated_results.into_iter().next().unwrap().1)
             }
         }
     }
 
     /// Record a context unit from a backend server call
+    /// Start tracking the context of a response, when tracing is enabled
+    async fn start_tracking(&self, ctx: &RequestContext) -> Option<String> {
+        let tracker = self.state.context_tracker.read().await;
+        let tracker = tracker.as_ref()?;
+        let agent = ctx
+            .client
+            .as_ref()
+            .map_or("mcp-proxy", |client| client.id.as_str());
+        match tracker
+            .start_response(agent.to_string(), "client".to_string())
+            .await
+        {
+            Ok(resp_id) => {
+                tracing::debug!("Started tracking response: {}", resp_id);
+                Some(resp_id)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to start response tracking: {}", e);
+                None
+            }
+        }
+    }
+
+    async fn finish_tracking(&self, resp_id: String) {
+        if let Some(tracker) = &*self.state.context_tracker.read().await {
+            match tracker.finalize_response(resp_id.clone(), None).await {
+                Ok(manifest) => {
+                    tracing::info!(
+                        "Response {} tracked: {} contexts, manifest generated",
+                        resp_id,
+                        manifest.context_tree.len()
+                    );
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to finalize response tracking: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Add what a server returned to the tracked response, versioning the
+    /// context of `server::tool` when its content changed
     async fn record_context_from_server(
         &self,
         tracking_response_id: &Option<String>,
         server_name: &str,
-        method: &str,
         tool_or_resource: &str,
+        result: &Value,
     ) {
         if let Some(resp_id) = tracking_response_id {
             if let Some(tracker) = &*self.state.context_tracker.read().await {
-                use crate::context::types::{ContextType, ContextUnit};
-                use chrono::Utc;
-                use uuid::Uuid;
-
-                let context = ContextUnit {
-                    id: format!("ctx_{}", Uuid::new_v4()),
-                    r#type: ContextType::External,
-                    source: format!("{server_name}::{tool_or_resource}"),
-                    timestamp: Utc::now(),
-                    embedding_id: None,
-                    summary: Some(format!("{method} from {server_name}")),
-                    version: 1,
-                    previous_version_id: None,
-                    aggregate_score: 0.0,
-                    feedback_count: 0,
-                };
+                use crate::context::evolution::ContentChange;
+                use crate::context::types::ContextType;
 
+                let source = format!("{server_name}::{tool_or_resource}");
                 // Add context with default retrieval score of 0.8
-                if let Err(e) = tracker
-                    .add_context(resp_id.clone(), context, Some(0.8))
+                match tracker
+                    .observe_context(
+                        resp_id.clone(),
+                        &source,
+                        ContextType::External,
+                        &result_text(result),
+                        Some(0.8),
+                    )
                     .await
                 {
-                    tracing::warn!("Failed to record context: {}", e);
+                    Ok((unit, ContentChange::Changed)) => tracing::debug!(
+                        "Content from {} changed; recorded as version {}",
+                        source,
+                        unit.version
+                    ),
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Failed to record context: {}", e),
                 }
             }
         }
     }
 
-    async fn forward_to_server(
+    pub(super) async fn forward_to_server(
+        &self,
+        server_name: &str,
+        method: &str,
+        params: Option<&Value>,
+    ) -> Result<Value> {
+        // Requests for a server that is still starting wait in its queue
+        if self.server_initializing(server_name).await {
+            return self
+                .queue_until_ready(server_name, method, params.cloned())
+                .await;
+        }
+
+        self.send_to_server(server_name, method, params).await
+    }
+
+    /// Take one of a server's call slots for a tool call, waiting or
+    /// failing as configured when all are in use. Waiting calls get slots by
+    /// priority, the lower of the client's and the tool's. `None` for
+    /// servers without a limit.
+    async fn acquire_call_slot(
+        &self,
+        server_name: &str,
+        tool: &str,
+        ctx: &RequestContext,
+    ) -> Result<Option<CallSlot>> {
+        let config = self.state.config.read().await;
+        let Some(server) = config.servers.get(server_name) else {
+            return Ok(None);
+        };
+        let Some(limit) = server.call_limit() else {
+            return Ok(None);
+        };
+        let adaptive = server.adaptive_concurrency.clone();
+        let (excess_calls, max_queued) = (server.excess_calls, server.max_queued_calls);
+        let client_priority = ctx
+            .client
+            .as_ref()
+            .and_then(|client| config.clients.get(&client.id))
+            .map(|client| client.priority)
+            .unwrap_or_default();
+        let priority = server
+            .tool_priorities
+            .get(tool)
+            .map_or(client_priority, |&tool_priority| {
+                tool_priority.min(client_priority)
+            });
+        let timeout = config.request_timeout();
+        drop(config);
+
+        let slots = self
+            .state
+            .call_limits
+            .slots(server_name, limit, adaptive.as_ref());
+        let unavailable = |error| ProxyError::ServerUnavailable {
+            server: server_name.to_string(),
+            reason: match error {
+                SlotError::Busy => {
+                    format!("already handling its limit of {limit} concurrent calls")
+                }
+                SlotError::Shed => "too many calls waiting; batch calls are shed first".to_string(),
+            },
+        };
+        match excess_calls {
+            ExcessCallAction::Reject => slots.try_acquire().map(Some).map_err(unavailable),
+            ExcessCallAction::Queue => {
+                match tokio::time::timeout(timeout, slots.acquire(priority, max_queued)).await {
+                    Ok(slot) => slot.map(Some).map_err(unavailable),
+                    Err(_) => Err(ProxyError::Timeout),
+                }
+            }
+        }
+    }
+
+    /// Reject new work for a server that is out of rotation
+    fn check_not_in_maintenance(&self, server_name: &str) -> Result<()> {
+        match self.state.maintenance.get(server_name) {
+            Some(info) => Err(ProxyError::ServerUnavailable {
+                server: server_name.to_string(),
+                reason: info.reason,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Check whether a server is still starting (handshake in progress or being retried)
+    async fn server_initializing(&self, server_name: &str) -> bool {
+        let server_state = match self.state.servers.get(server_name) {
+            Some(info) => info.state.clone(),
+            None => return false,
+        };
+        let starting = *server_state.read().await == ServerState::Starting;
+        starting
+    }
+
+    /// Queue a request until the server becomes Ready and wait for its result
+    async fn queue_until_ready(
+        &self,
+        server_name: &str,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Value> {
+        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+        let request = QueuedRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            method: method.to_string(),
+            params,
+            response_tx: Arc::new(Mutex::new(Some(response_tx))),
+        };
+
+        tracing::debug!(
+            server = server_name,
+            method = method,
+            "Queueing request until server is ready"
+        );
+        self.state
+            .request_router
+            .queue_request(server_name, request)
+            .await;
+
+        // The server may have finished starting (and drained) while we queued
+        if !self.server_initializing(server_name).await {
+            self.drain_queued_requests(server_name).await;
+        }
+
+        let timeout = self.state.config.read().await.request_timeout();
+        match tokio::time::timeout(timeout, response_rx).await {
+            Ok(Ok(result)) => result.map_err(ProxyError::InvalidRequest),
+            Ok(Err(_)) => Err(ProxyError::ServerNotReady(format!(
+                "Server '{server_name}' dropped the queued '{method}' request"
+            ))),
+            Err(_) => Err(ProxyError::Timeout),
+        }
+    }
+
+    /// Forward requests queued while a server was initializing and resolve
+    /// their waiting callers; returns the number of requests drained
+    pub async fn drain_queued_requests(&self, server_name: &str) -> usize {
+        let queued = self
+            .state
+            .request_router
+            .process_queued_requests(server_name)
+            .await;
+        let count = queued.len();
+
+        for request in queued {
+            let Some(response_tx) = request.response_tx.lock().await.take() else {
+                continue;
+            };
+            // Skip requests whose callers already gave up waiting
+            if response_tx.is_closed() {
+                continue;
+            }
+
+            let result = self
+                .send_to_server(server_name, &request.method, request.params.as_ref())
+                .await
+                .map_err(|e| e.to_string());
+            let _ = response_tx.send(result);
+        }
+
+        count
+    }
+
+    /// Send a request to a server over its pooled connection
+    async fn send_to_server(
         &self,
         server_name: &str,
         method: &str,
@@ -904,14 +1830,27 @@ impl RequestHandler {
         });
 
         // T047: Translate request using protocol adapter if available
-        if let Some(server_info) = self.state.servers.get(server_name) {
-            if let Some(connection_state) = &server_info.connection_state {
-                if let Some(adapter) = connection_state.get_adapter().await {
-                    request = adapter.translate_request(request).await.map_err(|e| {
-                        ProxyError::InvalidRequest(format!("Translation error: {e}"))
-                    })?;
-                }
-            }
+        let adapter = match self.state.servers.get(server_name) {
+            Some(server_info) => match &server_info.connection_state {
+                Some(connection_state) => connection_state.get_adapter().await,
+                None => None,
+            },
+            None => None,
+        }
+        .filter(|adapter| adapter.source_version() != adapter.target_version());
+        if let Some(adapter) = &adapter {
+            let original = request.clone();
+            request = adapter
+                .translate_request(request)
+                .await
+                .map_err(|e| ProxyError::InvalidRequest(format!("Translation error: {e}")))?;
+            self.state.translation_reports.record(
+                server_name,
+                method,
+                TranslationDirection::Request,
+                &original,
+                &request,
+            );
         }
 
         let request_bytes = bytes::Bytes::from(format!("{request}\n"));
@@ -921,14 +1860,19 @@ impl RequestHandler {
         let mut response: Value = serde_json::from_slice(&response_bytes)?;
 
         // T047: Translate response using protocol adapter if available
-        if let Some(server_info) = self.state.servers.get(server_name) {
-            if let Some(connection_state) = &server_info.connection_state {
-                if let Some(adapter) = connection_state.get_adapter().await {
-                    response = adapter.translate_response(response).await.map_err(|e| {
-                        ProxyError::InvalidRequest(format!("Translation error: {e}"))
-                    })?;
-                }
-            }
+        if let Some(adapter) = &adapter {
+            let original = response.clone();
+            response = adapter
+                .translate_response(response)
+                .await
+                .map_err(|e| ProxyError::InvalidRequest(format!("Translation error: {e}")))?;
+            self.state.translation_reports.record(
+                server_name,
+                method,
+                TranslationDirection::Response,
+                &original,
+                &response,
+            );
         }
 
         // Check for error
@@ -954,6 +1898,7 @@ impl RequestHandler {
         server_name: &str,
         tool_name: &str,
         arguments: Value,
+        correlation_id: &str,
     ) -> Result<Value> {
         // Check if plugins are configured
         let plugin_manager = match &self.state.plugin_manager {
@@ -971,7 +1916,7 @@ impl RequestHandler {
 
         // Build plugin chain for this server (request phase)
         use crate::plugin::chain::PluginChain;
-        use crate::plugin::schema::{PluginError, PluginInput, PluginMetadata, PluginPhase};
+        use crate::plugin::schema::{PluginError, PluginInput, PluginMetadata};
         use uuid::Uuid;
 
         let chain = PluginChain::new(
@@ -999,6 +1944,7 @@ impl RequestHandler {
                 user_query: None,
                 tool_arguments: Some(arguments.clone()),
                 mcp_servers: None,
+                correlation_id: Some(correlation_id.to_string()),
             },
         };
 
@@ -1044,6 +1990,7 @@ impl RequestHandler {
         result: Value,
         max_tokens: Option<u32>,
         tool_arguments: Option<Value>,
+        correlation_id: &str,
     ) -> Result<Value> {
         // Check if plugins are configured
         let plugin_manager = match &self.state.plugin_manager {
@@ -1061,7 +2008,7 @@ impl RequestHandler {
 
         // Build plugin chain for this server
         use crate::plugin::chain::PluginChain;
-        use crate::plugin::schema::{PluginInput, PluginMetadata, PluginPhase};
+        use crate::plugin::schema::{PluginInput, PluginMetadata};
         use uuid::Uuid;
 
         let chain = PluginChain::new(
@@ -1089,6 +2036,7 @@ impl RequestHandler {
                 user_query: None, // TODO: Extract from request context
                 tool_arguments,
                 mcp_servers: None,
+                correlation_id: Some(correlation_id.to_string()),
             },
         };
 
@@ -1108,10 +2056,269 @@ impl RequestHandler {
         Ok(modified_result)
     }
 
-    /// Clear the tools/list cache
+    /// Count the call against the client's quota, rejecting it if the quota
+    /// is used up. Usage that cannot be counted does not block calls.
+    async fn reserve_quota(&self, ctx: &RequestContext) -> Result<()> {
+        let Some(client) = &ctx.client else {
+            return Ok(());
+        };
+        let quota = self
+            .state
+            .config
+            .read()
+            .await
+            .clients
+            .get(&client.id)
+            .and_then(|c| c.quota.clone());
+        let shared = self.state.shared_state.read().await.clone();
+
+        match self
+            .state
+            .usage
+            .reserve(
+                shared.as_deref(),
+                &client.id,
+                quota.as_ref(),
+                chrono::Utc::now(),
+            )
+            .await
+        {
+            Err(e @ ProxyError::QuotaExceeded { .. }) => {
+                tracing::warn!("{}", e);
+                Err(e)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to count usage for client {}: {}", client.id, e);
+                Ok(())
+            }
+            Ok(()) => Ok(()),
+        }
+    }
+
+    /// Add the token cost of a call counted by [`Self::reserve_quota`]
+    async fn record_usage(&self, ctx: &RequestContext, tokens: u64) {
+        let Some(client) = &ctx.client else {
+            return;
+        };
+        let shared = self.state.shared_state.read().await.clone();
+
+        if let Err(e) = self
+            .state
+            .usage
+            .record_tokens(shared.as_deref(), &client.id, tokens, chrono::Utc::now())
+            .await
+        {
+            tracing::warn!("Failed to record usage for client {}: {}", client.id, e);
+        }
+    }
+
+    /// Catalog aggregated by another proxy instance. Not used while servers
+    /// here are in maintenance, as this instance then lists less.
+    async fn shared_catalog(&self, method: &str) -> Option<Value> {
+        if !self.state.maintenance.is_empty() {
+            return None;
+        }
+        let shared = self.state.shared_state.read().await.clone()?;
+
+        match shared.catalog(method).await {
+            Ok(catalog) => catalog,
+            Err(e) => {
+                tracing::warn!("Failed to read shared {} catalog: {}", method, e);
+                None
+            }
+        }
+    }
+
+    async fn share_catalog(&self, method: &str, catalog: &Value) {
+        if !self.state.maintenance.is_empty() {
+            return;
+        }
+        let Some(shared) = self.state.shared_state.read().await.clone() else {
+            return;
+        };
+
+        if let Err(e) = shared.set_catalog(method, catalog).await {
+            tracing::warn!("Failed to share {} catalog: {}", method, e);
+        }
+    }
+
+    /// Keep the cached tools/list for another two minutes, if it was
+    /// aggregated under the current catalog generation. Returns whether
+    /// there was such a list to keep.
+    pub async fn renew_cache(&self) -> bool {
+        let mut cache = self.tools_list_cache.write().await;
+        match cache.as_mut() {
+            Some(cached) if cached.catalog_generation == self.state.catalog_generation() => {
+                cached.expires_at = Instant::now() + Duration::from_secs(120);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Clear the tools/list cache, here and in shared state
     pub async fn clear_cache(&self) {
         let mut cache = self.tools_list_cache.write().await;
         *cache = None;
+        drop(cache);
+
+        if let Some(shared) = self.state.shared_state.read().await.clone() {
+            if let Err(e) = shared
+                .clear_catalogs(&["tools/list", "resources/list"])
+                .await
+            {
+                tracing::warn!("Failed to clear shared catalogs: {}", e);
+            }
+        }
         tracing::debug!("Cleared tools/list cache");
     }
 }
+
+/// Whether `needle` occurs in a raw reply
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// The content of a tool result as text: its text items, one per line,
+/// and any other items as JSON
+fn result_text(result: &Value) -> String {
+    match result.get("content").and_then(Value::as_array) {
+        Some(items) => items
+            .iter()
+            .map(|item| match item["text"].as_str() {
+                Some(text) if item["type"] == "text" => text.to_string(),
+                _ => item.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        None => result.to_string(),
+    }
+}
+
+/// Every server's prompts, each under a name prefixed with its server
+fn prefixed_prompts(results: Vec<(String, Value)>) -> Vec<Value> {
+    let mut all_prompts = Vec::new();
+    for (server_name, result) in results {
+        let Some(prompts) = result.get("prompts").and_then(|p| p.as_array()) else {
+            continue;
+        };
+        for prompt in prompts {
+            let mut prefixed_prompt = prompt.clone();
+            if let Some(prompt_obj) = prefixed_prompt.as_object_mut() {
+                if let Some(name) = prompt_obj.get("name").and_then(|n| n.as_str()) {
+                    let name_str = name.to_string();
+                    let prefixed_name = format!(
+                        "mcp__proxy__{}__{}",
+                        server_name.replace("-", "_"),
+                        name_str
+                    );
+                    prompt_obj.insert("name".to_string(), serde_json::json!(prefixed_name));
+                    prompt_obj.insert("originalName".to_string(), serde_json::json!(name_str));
+                    prompt_obj.insert("server".to_string(), serde_json::json!(server_name));
+                }
+            }
+            all_prompts.push(prefixed_prompt);
+        }
+    }
+    all_prompts
+}
+
+/// One listing made of every server's, for list methods the proxy does not
+/// know: the arrays in each result are joined in server order, each object
+/// in them marked with its `server`, and other fields come from the first
+/// server to return them. Cursors are left out, since one would page
+/// through a single server's list. Results that aren't objects can't be
+/// merged, so then the first is returned.
+pub(super) fn merged_lists(results: Vec<(String, Value)>) -> Value {
+    if !results.iter().all(|(_, result)| result.is_object()) {
+        return results
+            .into_iter()
+            .next()
+            .map(|(_, result)| result)
+            .unwrap_or_default();
+    }
+    let mut merged = serde_json::Map::new();
+    for (server_name, result) in results {
+        let Value::Object(fields) = result else {
+            continue;
+        };
+        for (key, value) in fields {
+            if key == "nextCursor" {
+                continue;
+            }
+            let value = match value {
+                Value::Array(items) => Value::Array(
+                    items
+                        .into_iter()
+                        .map(|mut item| {
+                            if let Some(item_obj) = item.as_object_mut() {
+                                item_obj
+                                    .insert("server".to_string(), serde_json::json!(server_name));
+                            }
+                            item
+                        })
+                        .collect(),
+                ),
+                value => value,
+            };
+            match (merged.get_mut(&key), value) {
+                (Some(Value::Array(items)), Value::Array(more)) => items.extend(more),
+                (Some(_), _) => {}
+                (None, value) => {
+                    merged.insert(key, value);
+                }
+            }
+        }
+    }
+    Value::Object(merged)
+}
+
+/// Each prompt name once, as the server the router picks for it lists it,
+/// noting the other servers that list it too
+pub(super) fn deduplicated_prompts(
+    results: Vec<(String, Value)>,
+    router: &RequestRouter,
+) -> Vec<Value> {
+    let mut names: Vec<String> = Vec::new();
+    let mut listings: HashMap<(String, String), Value> = HashMap::new();
+    for (server_name, result) in results {
+        let Some(prompts) = result.get("prompts").and_then(|p| p.as_array()) else {
+            continue;
+        };
+        for prompt in prompts {
+            let Some(name) = prompt.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+            listings.insert((name.to_string(), server_name.clone()), prompt.clone());
+        }
+    }
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let servers = router.servers_for_prompt(&name);
+            // A route may name a server that doesn't list the prompt
+            let chosen = router
+                .get_server_for_prompt(&name)
+                .filter(|server| listings.contains_key(&(name.clone(), server.clone())))
+                .or_else(|| {
+                    servers
+                        .iter()
+                        .find(|server| listings.contains_key(&(name.clone(), (*server).clone())))
+                        .cloned()
+                })?;
+            let mut prompt = listings.remove(&(name.clone(), chosen.clone()))?;
+            if let Some(prompt_obj) = prompt.as_object_mut() {
+                prompt_obj.insert("server".to_string(), serde_json::json!(chosen));
+                let others: Vec<&String> = servers.iter().filter(|s| **s != chosen).collect();
+                if !others.is_empty() {
+                    prompt_obj.insert("alsoProvidedBy".to_string(), serde_json::json!(others));
+                }
+            }
+            Some(prompt)
+        })
+        .collect()
+}
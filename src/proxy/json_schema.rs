@@ -0,0 +1,433 @@
+//! A JSON Schema validator for the keywords tool schemas use in practice
+//!
+//! Covers `type`, `enum`, `const`, object keywords (`properties`,
+//! `required`, `additionalProperties`, `patternProperties`,
+//! `min/maxProperties`), array keywords (`items`, `prefixItems`,
+//! `min/maxItems`, `uniqueItems`), string length and `pattern`, numeric
+//! bounds and `multipleOf`, the `allOf`/`anyOf`/`oneOf`/`not` combinators
+//! and local `$ref`s. Other keywords, such as `format`, are annotations here
+//! and always pass.
+
+use serde_json::{Map, Value};
+
+/// Deepest nesting of schemas followed, so `$ref` cycles end
+const MAX_DEPTH: usize = 64;
+
+/// Everything about `value` that doesn't match `schema`, each prefixed
+/// with the JSON pointer to the offending part; empty when it is valid
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    Validator { root: schema }.check(schema, value, "", 0, &mut errors);
+    errors
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+}
+
+impl Validator<'_> {
+    fn check(
+        &self,
+        schema: &Value,
+        value: &Value,
+        at: &str,
+        depth: usize,
+        errors: &mut Vec<String>,
+    ) {
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => {
+                errors.push(format!("{}: no value is allowed here", pointer(at)));
+                return;
+            }
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+        if depth > MAX_DEPTH {
+            errors.push(format!("{}: schema nests too deeply", pointer(at)));
+            return;
+        }
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            match self.resolve(reference) {
+                Some(target) => self.check(target, value, at, depth + 1, errors),
+                None => errors.push(format!("{}: cannot resolve $ref {reference}", pointer(at))),
+            }
+        }
+
+        if let Some(expected) = schema.get("type") {
+            let types: Vec<&str> = match expected {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+                errors.push(format!(
+                    "{}: expected {}, got {}",
+                    pointer(at),
+                    types.join(" or "),
+                    type_name(value)
+                ));
+                // The other keywords would only repeat the mismatch
+                return;
+            }
+        }
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.iter().any(|a| equal(a, value)) {
+                errors.push(format!(
+                    "{}: {value} is not one of the allowed values",
+                    pointer(at)
+                ));
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if !equal(expected, value) {
+                errors.push(format!("{}: expected {expected}, got {value}", pointer(at)));
+            }
+        }
+
+        match value {
+            Value::Object(object) => self.check_object(schema, object, at, depth, errors),
+            Value::Array(items) => self.check_array(schema, items, at, depth, errors),
+            Value::String(s) => check_string(schema, s, at, errors),
+            Value::Number(_) => check_number(schema, value.as_f64().unwrap_or(0.0), at, errors),
+            _ => {}
+        }
+
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            for sub in all {
+                self.check(sub, value, at, depth + 1, errors);
+            }
+        }
+        if let Some(any) = schema.get("anyOf").and_then(Value::as_array) {
+            if !any.iter().any(|sub| self.is_valid(sub, value, depth)) {
+                errors.push(format!("{}: matches none of anyOf", pointer(at)));
+            }
+        }
+        if let Some(one) = schema.get("oneOf").and_then(Value::as_array) {
+            let matching = one
+                .iter()
+                .filter(|sub| self.is_valid(sub, value, depth))
+                .count();
+            if matching != 1 {
+                errors.push(format!(
+                    "{}: matches {matching} of oneOf instead of exactly one",
+                    pointer(at)
+                ));
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.is_valid(not, value, depth) {
+                errors.push(format!("{}: matches a schema it must not", pointer(at)));
+            }
+        }
+    }
+
+    fn is_valid(&self, schema: &Value, value: &Value, depth: usize) -> bool {
+        let mut errors = Vec::new();
+        self.check(schema, value, "", depth + 1, &mut errors);
+        errors.is_empty()
+    }
+
+    fn check_object(
+        &self,
+        schema: &Map<String, Value>,
+        object: &Map<String, Value>,
+        at: &str,
+        depth: usize,
+        errors: &mut Vec<String>,
+    ) {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    errors.push(format!(
+                        "{}: missing required property '{name}'",
+                        pointer(at)
+                    ));
+                }
+            }
+        }
+        check_count(
+            schema,
+            ("Properties", "properties"),
+            object.len(),
+            at,
+            errors,
+        );
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let patterns: Vec<(regex::Regex, &Value)> = schema
+            .get("patternProperties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter_map(|(pattern, sub)| Some((regex::Regex::new(pattern).ok()?, sub)))
+            .collect();
+        for (name, item) in object {
+            let item_at = format!("{at}/{}", escape(name));
+            let mut matched = false;
+            if let Some(sub) = properties.and_then(|properties| properties.get(name)) {
+                matched = true;
+                self.check(sub, item, &item_at, depth + 1, errors);
+            }
+            for (pattern, sub) in &patterns {
+                if pattern.is_match(name) {
+                    matched = true;
+                    self.check(sub, item, &item_at, depth + 1, errors);
+                }
+            }
+            if matched {
+                continue;
+            }
+            match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    errors.push(format!("{}: unexpected property '{name}'", pointer(at)));
+                }
+                Some(sub) => self.check(sub, item, &item_at, depth + 1, errors),
+                None => {}
+            }
+        }
+    }
+
+    fn check_array(
+        &self,
+        schema: &Map<String, Value>,
+        items: &[Value],
+        at: &str,
+        depth: usize,
+        errors: &mut Vec<String>,
+    ) {
+        check_count(schema, ("Items", "items"), items.len(), at, errors);
+
+        let prefix = schema
+            .get("prefixItems")
+            .and_then(Value::as_array)
+            .map_or(&[][..], Vec::as_slice);
+        for (index, item) in items.iter().enumerate() {
+            let item_at = format!("{at}/{index}");
+            match prefix.get(index) {
+                Some(sub) => self.check(sub, item, &item_at, depth + 1, errors),
+                None => {
+                    if let Some(sub) = schema.get("items") {
+                        self.check(sub, item, &item_at, depth + 1, errors);
+                    }
+                }
+            }
+        }
+
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            let duplicate = items
+                .iter()
+                .enumerate()
+                .any(|(i, a)| items[..i].iter().any(|b| equal(a, b)));
+            if duplicate {
+                errors.push(format!("{}: items are not unique", pointer(at)));
+            }
+        }
+    }
+
+    /// The schema a `#`-relative JSON pointer refers to
+    fn resolve(&self, reference: &str) -> Option<&Value> {
+        let path = reference.strip_prefix('#')?;
+        if path.is_empty() {
+            return Some(self.root);
+        }
+        self.root.pointer(path)
+    }
+}
+
+fn check_string(schema: &Map<String, Value>, s: &str, at: &str, errors: &mut Vec<String>) {
+    check_count(
+        schema,
+        ("Length", "characters"),
+        s.chars().count(),
+        at,
+        errors,
+    );
+    if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+        if let Ok(regex) = regex::Regex::new(pattern) {
+            if !regex.is_match(s) {
+                errors.push(format!("{}: does not match pattern {pattern}", pointer(at)));
+            }
+        }
+    }
+}
+
+fn check_number(schema: &Map<String, Value>, n: f64, at: &str, errors: &mut Vec<String>) {
+    let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+    let mut fail = |message: String| errors.push(format!("{}: {n} {message}", pointer(at)));
+    if let Some(min) = bound("minimum").filter(|&min| n < min) {
+        fail(format!("is less than {min}"));
+    }
+    if let Some(max) = bound("maximum").filter(|&max| n > max) {
+        fail(format!("is more than {max}"));
+    }
+    if let Some(min) = bound("exclusiveMinimum").filter(|&min| n <= min) {
+        fail(format!("is not more than {min}"));
+    }
+    if let Some(max) = bound("exclusiveMaximum").filter(|&max| n >= max) {
+        fail(format!("is not less than {max}"));
+    }
+    if let Some(step) = bound("multipleOf").filter(|&step| step > 0.0) {
+        let quotient = n / step;
+        if (quotient - quotient.round()).abs() > 1e-9 {
+            fail(format!("is not a multiple of {step}"));
+        }
+    }
+}
+
+/// `min<Noun>` and `max<Noun>` against a count of `unit`s
+fn check_count(
+    schema: &Map<String, Value>,
+    (noun, unit): (&str, &str),
+    count: usize,
+    at: &str,
+    errors: &mut Vec<String>,
+) {
+    let limit = |keyword: String| schema.get(&keyword).and_then(Value::as_u64);
+    if let Some(min) = limit(format!("min{noun}")).filter(|&min| (count as u64) < min) {
+        errors.push(format!(
+            "{}: has {count} {unit}, fewer than {min}",
+            pointer(at)
+        ));
+    }
+    if let Some(max) = limit(format!("max{noun}")).filter(|&max| count as u64 > max) {
+        errors.push(format!(
+            "{}: has {count} {unit}, more than {max}",
+            pointer(at)
+        ));
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Equality where numbers compare by value, so `1` equals `1.0`
+fn equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equal(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| equal(a, b)))
+        }
+        _ => a == b,
+    }
+}
+
+fn pointer(at: &str) -> &str {
+    if at.is_empty() {
+        "/"
+    } else {
+        at
+    }
+}
+
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_object_schema() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "rows": {
+                    "type": "array",
+                    "items": { "$ref": "#/$defs/row" },
+                    "maxItems": 2
+                },
+                "status": { "enum": ["ok", "partial"] }
+            },
+            "required": ["rows", "status"],
+            "additionalProperties": false,
+            "$defs": {
+                "row": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "integer", "minimum": 1 },
+                        "name": { "type": ["string", "null"], "maxLength": 5 }
+                    },
+                    "required": ["id"]
+                }
+            }
+        });
+
+        let valid = json!({ "rows": [{ "id": 1, "name": "a" }, { "id": 2.0, "name": null }], "status": "ok" });
+        assert_eq!(validate(&schema, &valid), Vec::<String>::new());
+
+        let invalid = json!({
+            "rows": [{ "id": "1" }, { "id": 0, "name": "too long" }, { "id": 3 }],
+            "status": "failed",
+            "extra": true
+        });
+        let errors = validate(&schema, &invalid);
+        assert_eq!(
+            errors,
+            vec![
+                "/: unexpected property 'extra'",
+                "/rows: has 3 items, more than 2",
+                "/rows/0/id: expected integer, got string",
+                "/rows/1/id: 0 is less than 1",
+                "/rows/1/name: has 8 characters, more than 5",
+                "/status: \"failed\" is not one of the allowed values",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_combinators_and_strings() {
+        let schema = json!({
+            "oneOf": [
+                { "type": "string", "pattern": "^[a-z]+$" },
+                { "type": "number", "multipleOf": 0.5 }
+            ]
+        });
+        assert!(validate(&schema, &json!("abc")).is_empty());
+        assert!(validate(&schema, &json!(1.5)).is_empty());
+        assert_eq!(validate(&schema, &json!("ABC")).len(), 1);
+        assert_eq!(validate(&schema, &json!(1.2)).len(), 1);
+
+        assert!(validate(&json!({ "not": { "const": 1 } }), &json!(1.0)).len() == 1);
+        assert!(validate(&json!(true), &json!({ "anything": [] })).is_empty());
+        assert_eq!(
+            validate(&json!({ "$ref": "#/missing" }), &json!(1)).len(),
+            1
+        );
+
+        // Self-referencing schemas stop at the depth limit
+        let looping = json!({ "$ref": "#" });
+        assert_eq!(validate(&looping, &json!(1)).len(), 1);
+    }
+}
@@ -0,0 +1,153 @@
+//! Listening sockets for the proxy and web UI.
+//!
+//! A socket passed in by systemd socket activation is used when present, so
+//! the service can be restarted while systemd keeps accepting connections.
+//! Otherwise the address is bound directly, optionally with `SO_REUSEPORT`
+//! so a new proxy binary can bind the same port before the old one drains
+//! and exits.
+
+use crate::error::Result;
+use std::net::SocketAddr;
+use tokio::net::{TcpListener, TcpSocket};
+
+/// First file descriptor passed by systemd (`SD_LISTEN_FDS_START`)
+#[cfg(unix)]
+const LISTEN_FDS_START: i32 = 3;
+
+/// Get the listener for `name`: the socket-activated one if systemd passed
+/// it, otherwise a newly bound one.
+///
+/// Activated sockets are matched by name against `LISTEN_FDNAMES` (set with
+/// `FileDescriptorName=` in the socket unit), falling back to their position
+/// when the sockets are unnamed.
+pub fn bind(name: &str, index: usize, addr: SocketAddr, reuse_port: bool) -> Result<TcpListener> {
+    #[cfg(unix)]
+    if let Some(listener) = activated_listener(name, index)? {
+        tracing::info!(
+            "Using socket-activated {} listener on {}",
+            name,
+            listener.local_addr()?
+        );
+        return Ok(listener);
+    }
+    #[cfg(not(unix))]
+    let _ = (name, index);
+
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    socket.set_reuseaddr(true)?;
+    #[cfg(all(unix, not(target_os = "solaris"), not(target_os = "illumos")))]
+    if reuse_port {
+        socket.set_reuseport(true)?;
+    }
+    #[cfg(not(all(unix, not(target_os = "solaris"), not(target_os = "illumos"))))]
+    if reuse_port {
+        tracing::warn!("reusePort is not supported on this platform");
+    }
+
+    socket.bind(addr)?;
+    Ok(socket.listen(1024)?)
+}
+
+#[cfg(unix)]
+fn activated_listener(name: &str, index: usize) -> Result<Option<TcpListener>> {
+    use std::os::unix::io::FromRawFd;
+
+    let Some(fd) = activated_fd(
+        std::env::var("LISTEN_PID").ok().as_deref(),
+        std::env::var("LISTEN_FDS").ok().as_deref(),
+        std::env::var("LISTEN_FDNAMES").ok().as_deref(),
+        std::process::id(),
+        name,
+        index,
+    ) else {
+        return Ok(None);
+    };
+
+    // SAFETY: systemd hands the descriptor to this process, and each name or
+    // index selects a distinct descriptor, so it is owned exactly once
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+    listener.set_nonblocking(true)?;
+    Ok(Some(TcpListener::from_std(listener)?))
+}
+
+/// Pick the descriptor for `name` from the socket activation environment
+#[cfg(unix)]
+fn activated_fd(
+    listen_pid: Option<&str>,
+    listen_fds: Option<&str>,
+    fd_names: Option<&str>,
+    pid: u32,
+    name: &str,
+    index: usize,
+) -> Option<i32> {
+    // The variables are inherited by children; only the intended process may use them
+    if listen_pid?.parse::<u32>().ok()? != pid {
+        return None;
+    }
+    let count: usize = listen_fds?.parse().ok()?;
+
+    // Unnamed sockets are named after their socket unit, so fall back to
+    // the position when no socket carries our name
+    let position = fd_names
+        .and_then(|names| names.split(':').position(|n| n == name))
+        .unwrap_or(index);
+
+    (position < count).then(|| LISTEN_FDS_START + position as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_activated_fd_selection() {
+        // Named sockets are matched by name
+        assert_eq!(
+            activated_fd(Some("42"), Some("2"), Some("webui:proxy"), 42, "proxy", 0),
+            Some(4)
+        );
+        // Unnamed sockets are matched by position
+        assert_eq!(
+            activated_fd(
+                Some("42"),
+                Some("2"),
+                Some("mcp-proxy.socket:mcp-proxy.socket"),
+                42,
+                "webui",
+                1
+            ),
+            Some(4)
+        );
+        assert_eq!(
+            activated_fd(Some("42"), Some("1"), None, 42, "proxy", 0),
+            Some(3)
+        );
+        assert_eq!(
+            activated_fd(Some("42"), Some("1"), None, 42, "webui", 1),
+            None
+        );
+        // Variables meant for another process are ignored
+        assert_eq!(
+            activated_fd(Some("7"), Some("1"), None, 42, "proxy", 0),
+            None
+        );
+        assert_eq!(activated_fd(None, None, None, 42, "proxy", 0), None);
+    }
+
+    #[cfg(all(unix, not(target_os = "solaris"), not(target_os = "illumos")))]
+    #[tokio::test]
+    async fn test_reuse_port_allows_a_second_listener() {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let first = bind("proxy", 0, addr, true).unwrap();
+        let addr = first.local_addr().unwrap();
+
+        // The replacement process binds the same port while the old one is still up
+        let second = bind("proxy", 0, addr, true).unwrap();
+        assert_eq!(second.local_addr().unwrap(), addr);
+    }
+}
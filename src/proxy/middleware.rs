@@ -0,0 +1,148 @@
+//! Layers shared by the proxy endpoint and the web UI.
+//!
+//! Both servers are axum routers wrapped in the same stack: request tracing,
+//! the client IP allowlist and response compression. Endpoint-specific
+//! layers such as authentication live next to their routes.
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use ipnet::IpNet;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+use tower_http::trace::TraceLayer;
+
+/// Bodies smaller than this are sent uncompressed
+const MIN_COMPRESSED_SIZE: u16 = 1024;
+
+/// Wrap `router` in the shared layers. The router must be served with
+/// `into_make_service_with_connect_info::<SocketAddr>()` for the allowlist
+/// to see client addresses.
+pub fn apply(router: Router, allowlist: Vec<IpNet>, compression: bool) -> Router {
+    // Event streams are flushed event by event, so they are never compressed
+    let compress_when = SizeAbove::new(MIN_COMPRESSED_SIZE)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE);
+
+    router
+        .layer(
+            CompressionLayer::new()
+                .br(compression)
+                .gzip(compression)
+                .compress_when(compress_when),
+        )
+        .layer(middleware::from_fn_with_state(
+            Arc::<[IpNet]>::from(allowlist),
+            allow_ips,
+        ))
+        .layer(TraceLayer::new_for_http())
+}
+
+/// Refuse clients outside the allowlist. An empty allowlist allows every
+/// client.
+async fn allow_ips(
+    State(allowlist): State<Arc<[IpNet]>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if is_allowed(&allowlist, peer.ip()) {
+        next.run(request).await
+    } else {
+        tracing::warn!("Rejected request from {} (not in allowedIps)", peer);
+        StatusCode::FORBIDDEN.into_response()
+    }
+}
+
+fn is_allowed(allowlist: &[IpNet], ip: IpAddr) -> bool {
+    // IPv4 clients of a dual-stack listener appear as ::ffff:a.b.c.d
+    let ip = ip.to_canonical();
+    allowlist.is_empty() || allowlist.iter().any(|net| net.contains(&ip))
+}
+
+/// Warn when a listener without authentication or an allowlist is reachable
+/// from every interface
+pub fn warn_if_exposed(name: &str, addr: SocketAddr, authenticated: bool, allowlist: &[IpNet]) {
+    if addr.ip().is_unspecified() && !authenticated && allowlist.is_empty() {
+        tracing::warn!(
+            "The {} listener on {} accepts unauthenticated connections from any host; \
+             configure authentication or allowedIps, or bind to 127.0.0.1",
+            name,
+            addr
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+
+    #[test]
+    fn test_allowlist() {
+        let allowlist: Vec<IpNet> = vec!["10.0.0.0/8".parse().unwrap(), "::1/128".parse().unwrap()];
+
+        assert!(is_allowed(&allowlist, "10.1.2.3".parse().unwrap()));
+        assert!(is_allowed(&allowlist, "::ffff:10.1.2.3".parse().unwrap()));
+        assert!(is_allowed(&allowlist, "::1".parse().unwrap()));
+        assert!(!is_allowed(&allowlist, "192.168.1.10".parse().unwrap()));
+        assert!(is_allowed(&[], "192.168.1.10".parse().unwrap()));
+    }
+
+    async fn serve(allowlist: Vec<IpNet>) -> SocketAddr {
+        let large = "tool ".repeat(1000);
+        let router = Router::new()
+            .route("/large", get(move || async move { large }))
+            .route("/small", get(|| async { "ok" }));
+
+        let listener =
+            crate::proxy::listener::bind("proxy", 0, "127.0.0.1:0".parse().unwrap(), false)
+                .unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = apply(router, allowlist, true);
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_compresses_large_replies_over_http2() {
+        let addr = serve(Vec::new()).await;
+        let client = reqwest::Client::builder()
+            .http2_prior_knowledge()
+            .build()
+            .unwrap();
+        let get = |path: &str| {
+            client
+                .get(format!("http://{addr}/{path}"))
+                .header("accept-encoding", "gzip")
+                .send()
+        };
+
+        let response = get("large").await.unwrap();
+        assert_eq!(response.version(), reqwest::Version::HTTP_2);
+        assert_eq!(response.headers()["content-encoding"], "gzip");
+        assert!(response.bytes().await.unwrap().len() < 1000);
+
+        let response = get("small").await.unwrap();
+        assert!(!response.headers().contains_key("content-encoding"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_clients_outside_allowlist() {
+        let addr = serve(vec!["10.0.0.0/8".parse().unwrap()]).await;
+
+        let response = reqwest::get(format!("http://{addr}/small")).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    }
+}
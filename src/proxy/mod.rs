@@ -1,20 +1,59 @@
 use crate::error::{ProxyError, Result};
+use crate::recording::Direction;
 use crate::state::AppState;
+use axum::extract::{FromRef, State};
+use axum::http::{header, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use body::JsonRpcBody;
+use bytes::Bytes;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use warp::Filter;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::Instrument;
 
 pub mod aggregator_tools;
+pub mod auth;
+pub mod blobs;
+pub mod body;
 pub mod cache_warmer;
+pub mod capabilities;
 pub mod handler;
+pub mod json_schema;
+pub mod listener;
+pub mod middleware;
+pub mod passthrough;
+pub mod plugin_tools;
 pub mod prompts;
+pub mod readiness;
+pub mod resource_links;
 pub mod resources;
+pub mod response_limit;
 pub mod router;
 pub mod server_tools;
+pub mod stages;
+pub mod status_tools;
+pub mod template;
+pub mod trace_context;
 pub mod tracing_tools;
+pub mod traffic;
+pub mod transforms;
 
+pub use auth::ClientIdentity;
 pub use handler::RequestHandler;
 pub use router::RequestRouter;
+pub use trace_context::TraceContext;
+
+/// Sent to clients when a server comes up or goes away, changing what the
+/// proxy lists
+pub const LIST_CHANGED_NOTIFICATIONS: [&str; 3] = [
+    "notifications/tools/list_changed",
+    "notifications/resources/list_changed",
+    "notifications/prompts/list_changed",
+];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "method")]
@@ -53,6 +92,66 @@ pub struct ReadParams {
     pub uri: String,
 }
 
+/// What the front-end knows about a request besides its JSON-RPC body
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    /// Identifies the request in logs, plugin metadata and error responses
+    pub correlation_id: String,
+    /// The authenticated client, when clients are configured
+    pub client: Option<ClientIdentity>,
+    /// The trace the request is part of, from its `_meta` or headers
+    pub trace: Option<TraceContext>,
+    /// Where to record how long each stage of handling took, if anywhere
+    pub stages: Option<Arc<stages::StageTimings>>,
+    /// The backend server the request was routed to, once known
+    pub server: std::sync::OnceLock<String>,
+}
+
+impl Default for RequestContext {
+    /// Context of a new request from an anonymous client, outside any trace
+    fn default() -> Self {
+        Self {
+            correlation_id: uuid::Uuid::new_v4().to_string(),
+            client: None,
+            trace: None,
+            stages: None,
+            server: std::sync::OnceLock::new(),
+        }
+    }
+}
+
+impl RequestContext {
+    /// Note the server the request was routed to, for error reports
+    pub fn set_server(&self, server: &str) {
+        let _ = self.server.set(server.to_string());
+    }
+
+    /// Count the time since `start` towards `stage`, when timing stages
+    pub fn record_stage(&self, stage: stages::Stage, start: std::time::Instant) {
+        if let Some(stages) = &self.stages {
+            stages.record(stage, start);
+        }
+    }
+
+    /// The span the request is handled in, so that every event logged for
+    /// it carries its correlation ID and trace
+    pub fn span(&self) -> tracing::Span {
+        let span = tracing::info_span!(
+            "mcp_request",
+            correlation_id = %self.correlation_id,
+            trace_id = tracing::field::Empty,
+            span_id = tracing::field::Empty,
+            parent_span_id = tracing::field::Empty,
+        );
+        if let Some(trace) = &self.trace {
+            span.record("trace_id", trace.trace_id.as_str());
+            span.record("span_id", trace.span_id.as_str());
+            span.record("parent_span_id", trace.parent_id.as_str());
+        }
+        span
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MCPResponse {
     pub jsonrpc: String,
@@ -63,6 +162,45 @@ pub struct MCPResponse {
     pub error: Option<MCPError>,
 }
 
+impl MCPResponse {
+    /// An error response to the request with `id`
+    pub fn error(id: Option<serde_json::Value>, error: &ProxyError) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(MCPError::from(error)),
+        }
+    }
+
+    /// Add `correlation_id` to the error's `data`, if this is an error
+    pub fn with_correlation_id(mut self, correlation_id: &str) -> Self {
+        if let Some(error) = &mut self.error {
+            let data = error.data.get_or_insert_with(|| serde_json::json!({}));
+            if let Some(data) = data.as_object_mut() {
+                data.insert("correlation_id".to_string(), correlation_id.into());
+            }
+        }
+        self
+    }
+
+    /// Add what the request's context knows to the error's `data`, if this
+    /// is an error: its correlation ID and the server it was routed to
+    pub fn with_context(mut self, ctx: &RequestContext) -> Self {
+        if let (Some(error), Some(server)) = (&mut self.error, ctx.server.get()) {
+            if let Some(data) = error
+                .data
+                .get_or_insert_with(|| serde_json::json!({}))
+                .as_object_mut()
+            {
+                data.entry("server")
+                    .or_insert_with(|| server.as_str().into());
+            }
+        }
+        self.with_correlation_id(&ctx.correlation_id)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MCPError {
     pub code: i32,
@@ -71,24 +209,190 @@ pub struct MCPError {
     pub data: Option<serde_json::Value>,
 }
 
+/// JSON-RPC error code for a server that is temporarily out of rotation
+pub const SERVER_UNAVAILABLE_CODE: i32 = -32001;
+
+/// JSON-RPC error code for a client that has used up its quota
+pub const QUOTA_EXCEEDED_CODE: i32 = -32002;
+
+/// JSON-RPC error code for a request that ran out of time
+pub const TIMEOUT_CODE: i32 = -32003;
+
+/// JSON-RPC error code for a lost connection to a server
+pub const TRANSPORT_CLOSED_CODE: i32 = -32004;
+
+/// JSON-RPC error code for a server that is starting or failed to start
+pub const SERVER_NOT_READY_CODE: i32 = -32005;
+
+/// JSON-RPC error code for a server disabled by an operator or quarantine
+pub const CIRCUIT_OPEN_CODE: i32 = -32006;
+
+/// JSON-RPC error code for a client not allowed to make the request
+pub const POLICY_DENIED_CODE: i32 = -32007;
+
+/// JSON-RPC error code for a result over the server's response size limit
+pub const RESPONSE_TOO_LARGE_CODE: i32 = -32008;
+
+/// The kind of failure an error response reports, sent as `error.data.category`
+/// so clients can decide whether to retry without parsing messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    Timeout,
+    TransportClosed,
+    ServerNotReady,
+    ServerUnavailable,
+    CircuitOpen,
+    PolicyDenied,
+    QuotaExceeded,
+    ResponseTooLarge,
+    NotFound,
+    InvalidRequest,
+    Parse,
+    Internal,
+}
+
+impl ErrorCategory {
+    pub fn of(error: &ProxyError) -> Self {
+        use crate::error::{PoolError, ServerError};
+        match error {
+            ProxyError::Timeout => Self::Timeout,
+            ProxyError::Transport(_)
+            | ProxyError::Io(_)
+            | ProxyError::Pool(PoolError::Connection(_)) => Self::TransportClosed,
+            ProxyError::ServerNotReady(_)
+            | ProxyError::Pool(PoolError::ServerNotFound(_))
+            | ProxyError::Server(
+                ServerError::StartFailed(_)
+                | ServerError::Crashed(_)
+                | ServerError::HealthCheckFailed
+                | ServerError::InvalidStateTransition(_),
+            ) => Self::ServerNotReady,
+            ProxyError::ServerUnavailable { .. }
+            | ProxyError::ShuttingDown
+            | ProxyError::Pool(PoolError::Exhausted) => Self::ServerUnavailable,
+            ProxyError::ServerDisabled { .. } | ProxyError::Server(ServerError::Disabled(_)) => {
+                Self::CircuitOpen
+            }
+            ProxyError::Unauthorized(_) => Self::PolicyDenied,
+            ProxyError::QuotaExceeded { .. } => Self::QuotaExceeded,
+            ProxyError::ResponseTooLarge { .. } => Self::ResponseTooLarge,
+            ProxyError::ServerNotFound(_) => Self::NotFound,
+            ProxyError::RequestLimit(_) => Self::InvalidRequest,
+            ProxyError::Parse(_) => Self::Parse,
+            ProxyError::Config(_)
+            | ProxyError::Serialization(_)
+            | ProxyError::InvalidRequest(_)
+            | ProxyError::InvalidResponse
+            | ProxyError::SharedState(_)
+            | ProxyError::Storage(_) => Self::Internal,
+        }
+    }
+
+    pub fn code(self) -> i32 {
+        match self {
+            Self::Timeout => TIMEOUT_CODE,
+            Self::TransportClosed => TRANSPORT_CLOSED_CODE,
+            Self::ServerNotReady => SERVER_NOT_READY_CODE,
+            Self::ServerUnavailable => SERVER_UNAVAILABLE_CODE,
+            Self::CircuitOpen => CIRCUIT_OPEN_CODE,
+            Self::PolicyDenied => POLICY_DENIED_CODE,
+            Self::QuotaExceeded => QUOTA_EXCEEDED_CODE,
+            Self::ResponseTooLarge => RESPONSE_TOO_LARGE_CODE,
+            Self::NotFound => -32602,
+            Self::InvalidRequest => -32600,
+            Self::Parse => -32700,
+            Self::Internal => -32603,
+        }
+    }
+
+    /// Whether the same request may succeed if sent again later. Disabled
+    /// servers wait on an operator, so they are not.
+    pub fn retryable(self) -> bool {
+        matches!(
+            self,
+            Self::Timeout | Self::TransportClosed | Self::ServerNotReady | Self::ServerUnavailable
+        )
+    }
+}
+
+impl From<&ProxyError> for MCPError {
+    fn from(error: &ProxyError) -> Self {
+        let category = ErrorCategory::of(error);
+        let mut data = serde_json::json!({
+            "category": category,
+            "retryable": category.retryable()
+        });
+        match error {
+            ProxyError::ServerUnavailable { server, reason }
+            | ProxyError::ServerDisabled { server, reason } => {
+                data["server"] = server.as_str().into();
+                data["reason"] = reason.as_str().into();
+            }
+            ProxyError::QuotaExceeded { client, reason } => {
+                data["client"] = client.as_str().into();
+                data["reason"] = reason.as_str().into();
+            }
+            ProxyError::ResponseTooLarge {
+                server,
+                size,
+                limit,
+            } => {
+                data["server"] = server.as_str().into();
+                data["size"] = (*size).into();
+                data["limit"] = (*limit).into();
+            }
+            _ => {}
+        }
+        Self {
+            code: category.code(),
+            message: error.to_string(),
+            data: Some(data),
+        }
+    }
+}
+
 pub struct ProxyServer {
     state: Arc<AppState>,
     router: Arc<RequestRouter>,
     handler: Arc<RequestHandler>,
 }
 
+/// State of the proxy endpoint's handlers
+#[derive(Clone)]
+struct Endpoint {
+    state: Arc<AppState>,
+    router: Arc<RequestRouter>,
+    handler: Arc<RequestHandler>,
+}
+
+impl FromRef<Endpoint> for Arc<AppState> {
+    fn from_ref(endpoint: &Endpoint) -> Self {
+        endpoint.state.clone()
+    }
+}
+
 impl ProxyServer {
     pub fn new(state: Arc<AppState>) -> Self {
-        let router = Arc::new(RequestRouter::new());
         let handler = Arc::new(RequestHandler::new(state.clone()));
+        Self::with_handler(state, handler)
+    }
 
+    /// Serve requests with `handler`, sharing its list caches with whoever
+    /// else uses it
+    pub fn with_handler(state: Arc<AppState>, handler: Arc<RequestHandler>) -> Self {
         Self {
+            router: state.request_router.clone(),
             state,
-            router,
             handler,
         }
     }
 
+    /// The request handler serving proxy clients (shares its list caches)
+    pub fn handler(&self) -> Arc<RequestHandler> {
+        self.handler.clone()
+    }
+
     pub async fn run(self) -> Result<()> {
         tracing::info!("Starting proxy server");
 
@@ -98,23 +402,34 @@ impl ProxyServer {
         // Start the server based on configuration
         let config = self.state.config.read().await;
         let addr = format!("{}:{}", config.proxy.host, config.proxy.port);
+        let reuse_port = config.proxy.reuse_port;
+        let compression = config.proxy.compression;
+        let authenticated = !config.clients.is_empty();
+        let allowlist = config
+            .proxy_allowlist()
+            .map_err(|e| ProxyError::Config(crate::error::ConfigError::Validation(e)))?;
         drop(config);
 
-        tracing::info!("Proxy server listening on {}", addr);
+        let addr = addr
+            .parse::<std::net::SocketAddr>()
+            .map_err(|e| ProxyError::Config(crate::error::ConfigError::Parse(e.to_string())))?;
+        let listener = listener::bind("proxy", 0, addr, reuse_port)?;
+        middleware::warn_if_exposed("proxy", addr, authenticated, &allowlist);
 
-        // Create warp routes
-        let routes = self.create_routes();
+        tracing::info!("Proxy server listening on {}", listener.local_addr()?);
 
-        // Start server
-        let (_addr, server) = warp::serve(routes).bind_with_graceful_shutdown(
-            addr.parse::<std::net::SocketAddr>()
-                .map_err(|e| ProxyError::Config(crate::error::ConfigError::Parse(e.to_string())))?,
-            async move {
-                let _ = self.state.shutdown_tx.subscribe().recv().await;
-            },
-        );
+        let app = middleware::apply(self.create_routes(), allowlist, compression);
+        let drain = self.state.drain.clone();
 
-        server.await;
+        // Close the listener as soon as draining begins so that, with
+        // SO_REUSEPORT, new connections go to the instance taking over;
+        // requests already accepted keep running until the drain ends
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+        )
+        .with_graceful_shutdown(async move { drain.started().await })
+        .await?;
 
         tracing::info!("Proxy server stopped");
         Ok(())
@@ -127,64 +442,236 @@ impl ProxyServer {
         Ok(())
     }
 
-    fn create_routes(
-        &self,
-    ) -> impl warp::Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
-        let state = self.state.clone();
-        let router = self.router.clone();
-        let handler = self.handler.clone();
-
-        // JSON-RPC endpoint
-        let rpc = warp::path::end()
-            .and(warp::post())
-            .and(warp::body::json())
-            .and(warp::any().map(move || state.clone()))
-            .and(warp::any().map(move || router.clone()))
-            .and(warp::any().map(move || handler.clone()))
-            .and_then(handle_rpc_request);
-
-        // Health check endpoint
-        let health = warp::path("health").and(warp::get()).map(|| {
-            warp::reply::json(&serde_json::json!({
-                "status": "healthy",
-                "service": "mcp-proxy"
-            }))
-        });
+    fn create_routes(&self) -> Router {
+        let endpoint = Endpoint {
+            state: self.state.clone(),
+            router: self.router.clone(),
+            handler: self.handler.clone(),
+        };
+
+        // JSON-RPC endpoint, for authenticated clients
+        let rpc = Router::new()
+            .route("/", post(handle_rpc_request).get(stream_notifications))
+            .route_layer(axum::middleware::from_fn_with_state(
+                self.state.clone(),
+                auth::require_client,
+            ));
+
+        // Health check endpoints, open to load balancers and probes
+        let health = Router::new()
+            .route(
+                "/health",
+                get(|| async {
+                    Json(serde_json::json!({
+                        "status": "healthy",
+                        "service": "mcp-proxy"
+                    }))
+                }),
+            )
+            .route(
+                "/livez",
+                get(|| async { Json(serde_json::json!({ "status": "alive" })) }),
+            )
+            .route("/readyz", get(readyz));
+
+        // Blob downloads, authorized by their links' signatures
+        let downloads = Router::new().route("/blobs/:id", get(download_blob));
 
-        rpc.or(health)
+        rpc.merge(health).merge(downloads).with_state(endpoint)
+    }
+}
+
+#[derive(Deserialize)]
+struct BlobLink {
+    expires: i64,
+    signature: String,
+}
+
+/// A blob from a result, behind the signed link that replaced it
+async fn download_blob(
+    State(endpoint): State<Endpoint>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    axum::extract::Query(link): axum::extract::Query<BlobLink>,
+) -> Response {
+    let Some(downloads) = &endpoint.state.blob_downloads else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match downloads.fetch(&id, link.expires, &link.signature) {
+        Ok((data, mime_type)) => (
+            [(
+                header::CONTENT_TYPE,
+                mime_type.unwrap_or_else(|| "application/octet-stream".to_string()),
+            )],
+            data,
+        )
+            .into_response(),
+        Err(blobs::DownloadError::BadSignature) => StatusCode::FORBIDDEN.into_response(),
+        Err(blobs::DownloadError::Expired) => StatusCode::GONE.into_response(),
+        Err(blobs::DownloadError::NotFound) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// 200 when the `readiness` criteria are met, 503 with the reasons otherwise
+async fn readyz(State(endpoint): State<Endpoint>) -> Response {
+    let reasons =
+        readiness::unready_reasons(&endpoint.state, endpoint.handler.catalog_warmed()).await;
+    if reasons.is_empty() {
+        Json(serde_json::json!({ "status": "ready" })).into_response()
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "status": "not ready", "reasons": reasons })),
+        )
+            .into_response()
     }
 }
 
 async fn handle_rpc_request(
-    request: serde_json::Value,
-    state: Arc<AppState>,
-    router: Arc<RequestRouter>,
-    handler: Arc<RequestHandler>,
-) -> std::result::Result<impl warp::Reply, warp::Rejection> {
-    tracing::debug!("Received RPC request: {:?}", request);
+    State(endpoint): State<Endpoint>,
+    Extension(mut ctx): Extension<RequestContext>,
+    JsonRpcBody(body): JsonRpcBody,
+) -> Response {
+    let state = endpoint.state.clone();
+
+    // A trace in the request's `_meta` takes precedence over the headers
+    if let Some(trace) = TraceContext::from_request(&body) {
+        ctx.trace = Some(trace);
+    }
+    let span = ctx.span();
+
+    let Some(in_flight) = state.drain.track() else {
+        let id = passthrough::RequestHead::parse(&body).and_then(|head| head.id_value());
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(
+                MCPResponse::error(id, &ProxyError::ShuttingDown)
+                    .with_correlation_id(&ctx.correlation_id),
+            ),
+        )
+            .into_response();
+    };
+    let drain_timeout = state.config.read().await.drain_timeout();
     let timer = crate::state::metrics::RequestTimer::new(state.metrics.clone());
+    let started = std::time::Instant::now();
+    if let Some(recorder) = state.recorder() {
+        recorder.client(Direction::ClientRequest, &ctx.correlation_id, &body);
+    }
+
+    // Once shutdown starts, a request gets the drain timeout to finish
+    let mut disconnect = DisconnectGuard::new(&ctx.correlation_id);
+    let result = tokio::select! {
+        result = dispatch(&endpoint.handler, &endpoint.router, &body, &ctx).instrument(span) => result,
+        _ = in_flight.deadline(drain_timeout) => Err(ProxyError::Timeout),
+    };
+    disconnect.disarm();
 
-    let response = match handler.handle_request(request, router).await {
-        Ok(resp) => {
+    let reply = match result {
+        Ok(reply) => {
             timer.finish();
-            resp
+            reply
         }
         Err(e) => {
             timer.fail();
-            MCPResponse {
-                jsonrpc: "2.0".to_string(),
-                id: None,
-                result: None,
-                error: Some(MCPError {
-                    code: -32603,
-                    message: e.to_string(),
-                    data: None,
-                }),
+            let reply = MCPResponse::error(None, &e).with_context(&ctx);
+            match serde_json::to_vec(&reply) {
+                Ok(reply) => Bytes::from(reply),
+                Err(e) => {
+                    return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+                }
             }
         }
     };
+    if let Some(recorder) = state.recorder() {
+        recorder.client(Direction::ClientResponse, &ctx.correlation_id, &reply);
+    }
+    traffic::publish(
+        &state,
+        &endpoint.handler,
+        &endpoint.router,
+        &ctx,
+        &body,
+        &reply,
+        started.elapsed(),
+    );
+    ([(header::CONTENT_TYPE, "application/json")], reply).into_response()
+}
 
-    Ok(warp::reply::json(&response))
+/// Server-to-client notifications as server-sent events, for clients that
+/// open a GET stream on the endpoint
+async fn stream_notifications(State(endpoint): State<Endpoint>) -> Response {
+    let receiver = endpoint.state.client_notification_tx.subscribe();
+    let drain = endpoint.state.drain.clone();
+    let notifications = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(notification) => {
+                    let event = Event::default().data(notification.to_string());
+                    return Some((Ok::<_, std::convert::Infallible>(event), receiver));
+                }
+                // Notifications a slow client missed cannot be sent anymore
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+    // End the stream when draining begins so it does not hold the server open
+    .take_until(async move { drain.started().await });
+    Sse::new(notifications)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Notes a request whose client disconnected before the reply was ready.
+/// The server then drops the request's future, which releases its call slot
+/// and connection; the pool tells the backend to cancel the call.
+struct DisconnectGuard<'a> {
+    correlation_id: &'a str,
+    armed: bool,
+}
+
+impl<'a> DisconnectGuard<'a> {
+    fn new(correlation_id: &'a str) -> Self {
+        Self {
+            correlation_id,
+            armed: true,
+        }
+    }
+
+    /// The request finished; dropping the guard no longer means the client
+    /// went away
+    fn disarm(&mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for DisconnectGuard<'_> {
+    fn drop(&mut self) {
+        if self.armed {
+            tracing::info!(
+                correlation_id = %self.correlation_id,
+                "Client disconnected before the reply, cancelling the request"
+            );
+        }
+    }
+}
+
+/// Answer a request on the raw-byte fast path when possible, and through
+/// the request handler otherwise
+pub(crate) async fn dispatch(
+    handler: &RequestHandler,
+    router: &Arc<RequestRouter>,
+    body: &[u8],
+    ctx: &RequestContext,
+) -> Result<Bytes> {
+    if let Some(reply) = handler.pass_through(body, router, ctx).await {
+        return reply;
+    }
+
+    let request: serde_json::Value = serde_json::from_slice(body)?;
+    tracing::debug!("Received RPC request: {:?}", request);
+    let response = handler.handle_request(request, router.clone(), ctx).await?;
+    let response = response.with_context(ctx);
+    Ok(Bytes::from(serde_json::to_vec(&response)?))
 }
 
 #[cfg(test)]
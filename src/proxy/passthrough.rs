@@ -0,0 +1,255 @@
+//! Raw-byte forwarding of tool calls.
+//!
+//! A `tools/call` that needs no translation and no plugins is forwarded
+//! without building a `serde_json::Value` for it or its reply. Only the
+//! envelope is inspected: method, id, tool name and result are borrowed from
+//! the raw bytes, and the arguments and result are copied through unparsed.
+
+use crate::error::{ProxyError, Result};
+use bytes::Bytes;
+use serde::{Deserialize, Deserializer};
+use serde_json::value::RawValue;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// The JSON-RPC envelope of a client request
+#[derive(Debug, Deserialize)]
+pub struct RequestHead<'a> {
+    #[serde(borrow, default)]
+    pub id: Option<&'a RawValue>,
+    #[serde(borrow)]
+    pub method: Cow<'a, str>,
+    #[serde(borrow, default)]
+    pub params: Option<&'a RawValue>,
+}
+
+impl<'a> RequestHead<'a> {
+    /// Read the envelope of a single request; `None` for anything else,
+    /// such as a batch
+    pub fn parse(body: &'a [u8]) -> Option<Self> {
+        serde_json::from_slice(body).ok()
+    }
+
+    /// The request id as a value, for building error replies
+    pub fn id_value(&self) -> Option<serde_json::Value> {
+        self.id.and_then(|id| serde_json::from_str(id.get()).ok())
+    }
+}
+
+/// Parameters of a `tools/call`
+#[derive(Debug, Deserialize)]
+pub struct CallHead<'a> {
+    #[serde(borrow, alias = "tool")]
+    pub name: Cow<'a, str>,
+    #[serde(borrow, default)]
+    pub arguments: Option<&'a RawValue>,
+}
+
+impl<'a> CallHead<'a> {
+    pub fn parse(params: &'a RawValue) -> Option<Self> {
+        serde_json::from_str(params.get()).ok()
+    }
+
+    /// Length of the arguments as sent, for usage accounting
+    pub fn arguments_len(&self) -> usize {
+        self.arguments.map_or(2, |arguments| arguments.get().len())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseHead<'a> {
+    // A null result is still a result
+    #[serde(borrow, default, deserialize_with = "present")]
+    result: Option<&'a RawValue>,
+    #[serde(borrow, default)]
+    error: Option<&'a RawValue>,
+}
+
+fn present<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<&'de RawValue>, D::Error> {
+    <&RawValue>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorHead<'a> {
+    #[serde(borrow, default)]
+    message: Option<Cow<'a, str>>,
+}
+
+/// The line sent to a backend to call `tool` with the client's arguments,
+/// and with `meta` as the call's `_meta` when given
+pub fn call_request(
+    tool: &str,
+    arguments: Option<&RawValue>,
+    meta: Option<&Map<String, Value>>,
+) -> Result<Bytes> {
+    let arguments = arguments.map_or("{}", RawValue::get);
+    let mut line = Vec::with_capacity(arguments.len() + tool.len() + 96);
+    line.extend_from_slice(br#"{"jsonrpc":"2.0","method":"tools/call","params":{"name":"#);
+    serde_json::to_writer(&mut line, tool)?;
+    line.extend_from_slice(br#","arguments":"#);
+    line.extend_from_slice(arguments.as_bytes());
+    if let Some(meta) = meta {
+        line.extend_from_slice(br#","_meta":"#);
+        serde_json::to_writer(&mut line, meta)?;
+    }
+    line.extend_from_slice(b"},\"id\":1}\n");
+    Ok(Bytes::from(line))
+}
+
+/// The reply to the client for a backend response, keeping the client's
+/// request id and the backend's result as they were sent, with any `meta`
+/// entries added to the result's `_meta`
+pub fn client_reply(
+    id: Option<&RawValue>,
+    backend_response: &[u8],
+    meta: Option<&Map<String, Value>>,
+) -> Result<Bytes> {
+    let head: ResponseHead = serde_json::from_slice(backend_response)?;
+    let Some(result) = head.result else {
+        let message = head
+            .error
+            .and_then(|error| serde_json::from_str::<ErrorHead>(error.get()).ok())
+            .and_then(|error| error.message);
+        return Err(ProxyError::InvalidRequest(match message {
+            Some(message) => format!("Server error: {message}"),
+            None => "No result in response".to_string(),
+        }));
+    };
+    let result = match meta {
+        Some(meta) if !meta.is_empty() => Cow::Owned(add_meta(result, meta)?),
+        _ => Cow::Borrowed(result.get()),
+    };
+
+    let id = id.map_or("null", RawValue::get);
+    let mut reply = Vec::with_capacity(result.len() + id.len() + 32);
+    reply.extend_from_slice(br#"{"jsonrpc":"2.0","id":"#);
+    reply.extend_from_slice(id.as_bytes());
+    reply.extend_from_slice(br#","result":"#);
+    reply.extend_from_slice(result.as_bytes());
+    reply.push(b'}');
+    Ok(Bytes::from(reply))
+}
+
+/// A result with entries added to its `_meta`. Only its top level is
+/// parsed, so the content is still copied through as it was sent. Results
+/// that are not objects are left as they are.
+fn add_meta(result: &RawValue, meta: &Map<String, Value>) -> Result<String> {
+    let Ok(mut fields) = serde_json::from_str::<BTreeMap<String, &RawValue>>(result.get()) else {
+        return Ok(result.get().to_string());
+    };
+    let mut merged: Map<String, Value> = fields
+        .remove("_meta")
+        .and_then(|existing| serde_json::from_str(existing.get()).ok())
+        .unwrap_or_default();
+    merged.extend(meta.clone());
+    let merged = serde_json::value::to_raw_value(&merged)?;
+    fields.insert("_meta".to_string(), &merged);
+    Ok(serde_json::to_string(&fields)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_forwards_call_without_reparsing() {
+        let body = br#"{"jsonrpc":"2.0","id":"req-7","method":"tools/call",
+            "params":{"name":"mcp__proxy__files__read","arguments":{"path":"a\"b","n":[1, 2]}}}"#;
+
+        let head = RequestHead::parse(body).unwrap();
+        assert_eq!(head.method, "tools/call");
+        assert_eq!(head.id_value(), Some(json!("req-7")));
+
+        let call = CallHead::parse(head.params.unwrap()).unwrap();
+        assert_eq!(call.name, "mcp__proxy__files__read");
+
+        let request = call_request("read", call.arguments, None).unwrap();
+        assert!(request.ends_with(b"\n"));
+        // Arguments are forwarded byte for byte, whitespace included
+        assert!(std::str::from_utf8(&request)
+            .unwrap()
+            .contains(r#""arguments":{"path":"a\"b","n":[1, 2]}"#));
+        let request: Value = serde_json::from_slice(&request).unwrap();
+        assert_eq!(
+            request,
+            json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": {"name": "read", "arguments": {"path": "a\"b", "n": [1, 2]}},
+                "id": 1
+            })
+        );
+
+        let trace = crate::proxy::TraceContext::continue_from(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            None,
+        )
+        .unwrap();
+        let request = call_request("read", call.arguments, Some(&trace.meta())).unwrap();
+        let request: Value = serde_json::from_slice(&request).unwrap();
+        assert_eq!(
+            request["params"]["_meta"],
+            json!({"traceparent": trace.traceparent()})
+        );
+
+        let reply = client_reply(
+            head.id,
+            br#"{"jsonrpc":"2.0","id":1,"result":{"content":[{"type":"text","text":"hi"}]}}"#,
+            None,
+        )
+        .unwrap();
+        let reply: Value = serde_json::from_slice(&reply).unwrap();
+        assert_eq!(
+            reply,
+            json!({
+                "jsonrpc": "2.0",
+                "id": "req-7",
+                "result": {"content": [{"type": "text", "text": "hi"}]}
+            })
+        );
+    }
+
+    #[test]
+    fn test_backend_errors_and_other_shapes() {
+        let error = client_reply(
+            None,
+            br#"{"id":1,"error":{"code":-1,"message":"boom"}}"#,
+            None,
+        );
+        assert!(matches!(error, Err(ProxyError::InvalidRequest(m)) if m == "Server error: boom"));
+
+        // Batches are not single requests
+        assert!(RequestHead::parse(br#"[{"method":"ping"}]"#).is_none());
+
+        let reply = client_reply(None, br#"{"id":1,"result":null}"#, None).unwrap();
+        assert_eq!(&reply[..], br#"{"jsonrpc":"2.0","id":null,"result":null}"#);
+
+        let reply = client_reply(None, br#"{"id":1}"#, None);
+        assert!(matches!(reply, Err(ProxyError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn test_reply_meta() {
+        let mut meta = Map::new();
+        meta.insert("tokenEstimate".to_string(), json!(12));
+        let reply = client_reply(
+            None,
+            br#"{"id":1,"result":{"content":[{"type":"text","text":"a \"b\""}],"_meta":{"k":1}}}"#,
+            Some(&meta),
+        )
+        .unwrap();
+        let reply: Value = serde_json::from_slice(&reply).unwrap();
+        assert_eq!(
+            reply["result"]["_meta"],
+            json!({"k": 1, "tokenEstimate": 12})
+        );
+        assert_eq!(reply["result"]["content"][0]["text"], "a \"b\"");
+
+        let reply = client_reply(None, br#"{"id":1,"result":null}"#, Some(&meta)).unwrap();
+        assert_eq!(&reply[..], br#"{"jsonrpc":"2.0","id":null,"result":null}"#);
+    }
+}
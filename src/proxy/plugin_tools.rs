@@ -0,0 +1,260 @@
+use super::ClientIdentity;
+use crate::config::ClientRole;
+use crate::error::{ProxyError, Result};
+use crate::plugin::config::PluginAssignment;
+use crate::plugin::schema::PluginPhase;
+use crate::state::AppState;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Get proxy plugin management tools
+pub fn get_plugin_tools() -> Vec<Value> {
+    let toggle_schema = json!({
+        "type": "object",
+        "properties": {
+            "server_name": {
+                "type": "string",
+                "description": "Server the plugin is assigned to"
+            },
+            "plugin_name": {
+                "type": "string",
+                "description": "Name of the plugin"
+            },
+            "phase": {
+                "type": "string",
+                "enum": ["request", "response"],
+                "description": "Only change the assignment in this phase (default: both)"
+            }
+        },
+        "required": ["server_name", "plugin_name"]
+    });
+
+    vec![
+        create_tool(
+            "mcp__proxy__plugins__list",
+            "List the plugins assigned to each backend MCP server, in execution order, and whether they are enabled",
+            json!({
+                "type": "object",
+                "properties": {
+                    "server_name": {
+                        "type": "string",
+                        "description": "Only list plugins assigned to this server"
+                    }
+                },
+                "additionalProperties": false
+            }),
+        ),
+        create_tool(
+            "mcp__proxy__plugins__enable",
+            "Enable a plugin for a backend MCP server (requires the admin role)",
+            toggle_schema.clone(),
+        ),
+        create_tool(
+            "mcp__proxy__plugins__disable",
+            "Disable a plugin for a backend MCP server without removing it (requires the admin role)",
+            toggle_schema,
+        ),
+        create_tool(
+            "mcp__proxy__plugins__reload",
+            "Rediscover plugins in the plugin directory and restart their processes (requires the admin role)",
+            json!({
+                "type": "object",
+                "properties": {},
+                "additionalProperties": false
+            }),
+        ),
+    ]
+}
+
+fn create_tool(name: &str, description: &str, input_schema: Value) -> Value {
+    json!({
+        "name": name,
+        "description": description,
+        "inputSchema": input_schema
+    })
+}
+
+/// Check that the client may call a plugin tool. Listing is open to all;
+/// changes need the admin role once clients are configured. Anonymous
+/// requests come from an open endpoint or stdio and are trusted.
+pub fn authorize(tool_name: &str, client: Option<&ClientIdentity>) -> Result<()> {
+    match client {
+        Some(client) if tool_name != "list" && client.role != ClientRole::Admin => {
+            Err(ProxyError::Unauthorized(format!(
+                "client '{}' needs the admin role to {tool_name} plugins",
+                client.id
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Handle plugin management tool calls
+pub async fn handle_plugin_tool(
+    tool_name: &str,
+    arguments: Value,
+    state: Arc<AppState>,
+) -> std::result::Result<Value, String> {
+    match tool_name {
+        "list" => handle_list_plugins(arguments, state).await,
+        "enable" => handle_set_enabled(arguments, state, true).await,
+        "disable" => handle_set_enabled(arguments, state, false).await,
+        "reload" => handle_reload_plugins(state).await,
+        _ => Err(format!("Unknown plugin tool: {tool_name}")),
+    }
+}
+
+async fn handle_list_plugins(
+    arguments: Value,
+    state: Arc<AppState>,
+) -> std::result::Result<Value, String> {
+    let server_filter = arguments.get("server_name").and_then(|n| n.as_str());
+    let available = state
+        .plugin_manager
+        .as_ref()
+        .map(|manager| manager.plugin_names())
+        .unwrap_or_default();
+
+    let config = state.config.read().await;
+    let plugin_config = config
+        .plugins
+        .as_ref()
+        .ok_or("Plugins are not configured")?;
+    let describe = |assignments: &[PluginAssignment]| {
+        let mut assignments = assignments.to_vec();
+        assignments.sort_by_key(|a| a.order);
+        assignments
+            .iter()
+            .map(|a| {
+                json!({
+                    "name": a.name,
+                    "order": a.order,
+                    "enabled": a.enabled,
+                    "timeout_ms": plugin_config.get_plugin_timeout(a),
+                    "loaded": available.contains(&a.name),
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut names: Vec<&String> = plugin_config
+        .servers
+        .keys()
+        .filter(|name| server_filter.is_none_or(|filter| filter == name.as_str()))
+        .collect();
+    names.sort();
+    if let Some(server_name) = server_filter.filter(|_| names.is_empty()) {
+        return Err(format!("No plugins are assigned to server {server_name}"));
+    }
+
+    let servers: Vec<Value> = names
+        .into_iter()
+        .map(|name| {
+            let assignments = &plugin_config.servers[name];
+            json!({
+                "server": name,
+                "request": describe(&assignments.request),
+                "response": describe(&assignments.response),
+            })
+        })
+        .collect();
+    let listing = json!({"available": available, "servers": servers});
+
+    Ok(json!({
+        "content": [{"type": "text", "text": serde_json::to_string_pretty(&listing).unwrap()}],
+        "available": listing["available"],
+        "servers": listing["servers"]
+    }))
+}
+
+async fn handle_set_enabled(
+    arguments: Value,
+    state: Arc<AppState>,
+    enabled: bool,
+) -> std::result::Result<Value, String> {
+    let server_name = arguments
+        .get("server_name")
+        .and_then(|n| n.as_str())
+        .ok_or("Missing server_name")?;
+    let plugin_name = arguments
+        .get("plugin_name")
+        .and_then(|n| n.as_str())
+        .ok_or("Missing plugin_name")?;
+    let phase = match arguments.get("phase").and_then(|p| p.as_str()) {
+        None => None,
+        Some("request") => Some(PluginPhase::Request),
+        Some("response") => Some(PluginPhase::Response),
+        Some(other) => return Err(format!("Unknown plugin phase: {other}")),
+    };
+
+    // Update config
+    let mut config = state.config.write().await;
+    let assignments = config
+        .plugins
+        .as_mut()
+        .ok_or("Plugins are not configured")?
+        .servers
+        .get_mut(server_name)
+        .ok_or_else(|| format!("No plugins are assigned to server {server_name}"))?;
+
+    let mut matched = false;
+    for (assigned_phase, assignments) in [
+        (PluginPhase::Request, &mut assignments.request),
+        (PluginPhase::Response, &mut assignments.response),
+    ] {
+        if phase.is_some_and(|phase| phase != assigned_phase) {
+            continue;
+        }
+        for assignment in assignments.iter_mut().filter(|a| a.name == plugin_name) {
+            assignment.enabled = enabled;
+            matched = true;
+        }
+    }
+    if !matched {
+        return Err(format!(
+            "Plugin {plugin_name} is not assigned to server {server_name}"
+        ));
+    }
+
+    let action = if enabled { "enabled" } else { "disabled" };
+    Ok(json!({
+        "content": [{"type": "text", "text": format!("Plugin {plugin_name} {action} for server {server_name}")}]
+    }))
+}
+
+async fn handle_reload_plugins(state: Arc<AppState>) -> std::result::Result<Value, String> {
+    let manager = state
+        .plugin_manager
+        .as_ref()
+        .ok_or("Plugins are not configured")?;
+    let count = manager.reload().await.map_err(|e| e.to_string())?;
+
+    Ok(json!({
+        "content": [{"type": "text", "text": format!("Reloaded {count} plugins")}]
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorize_requires_admin_for_changes() {
+        let user = ClientIdentity {
+            id: "ci-agent".to_string(),
+            role: ClientRole::User,
+        };
+        let admin = ClientIdentity {
+            id: "ops".to_string(),
+            role: ClientRole::Admin,
+        };
+
+        assert!(authorize("list", Some(&user)).is_ok());
+        assert!(matches!(
+            authorize("disable", Some(&user)),
+            Err(ProxyError::Unauthorized(_))
+        ));
+        assert!(authorize("disable", Some(&admin)).is_ok());
+        assert!(authorize("reload", None).is_ok());
+    }
+}
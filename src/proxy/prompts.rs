@@ -1,3 +1,7 @@
+use super::template::Template;
+use crate::config::PromptConfig;
+use crate::error::{ProxyError, Result};
+use crate::state::AppState;
 use serde_json::{json, Value};
 
 /// Get all proxy-native prompts
@@ -124,3 +128,151 @@ pub fn get_prompt(name: &str, _arguments: Option<Value>) -> Option<Value> {
             .unwrap_or(false)
     })
 }
+
+/// Definitions of the prompts configured under `prompts`
+pub fn get_configured_prompts(prompts: &[PromptConfig]) -> Vec<Value> {
+    prompts
+        .iter()
+        .map(|prompt| {
+            let arguments: Vec<Value> = prompt
+                .arguments
+                .iter()
+                .map(|argument| {
+                    json!({
+                        "name": argument.name,
+                        "description": argument.description,
+                        "required": argument.required
+                    })
+                })
+                .collect();
+            json!({
+                "name": prompt.name,
+                "description": prompt.description,
+                "arguments": arguments
+            })
+        })
+        .collect()
+}
+
+/// Render a configured prompt as a `prompts/get` result. The template sees
+/// the arguments by name and the live servers and tools under `proxy`.
+pub fn render_configured_prompt(
+    prompt: &PromptConfig,
+    arguments: Option<&Value>,
+    proxy: Value,
+) -> Result<Value> {
+    let mut data = serde_json::Map::new();
+    for argument in &prompt.arguments {
+        let value = arguments
+            .and_then(|arguments| arguments.get(&argument.name))
+            .cloned()
+            .or_else(|| argument.default.clone().map(Value::String));
+        match value {
+            Some(value) => {
+                data.insert(argument.name.clone(), value);
+            }
+            None if argument.required => {
+                return Err(ProxyError::InvalidRequest(format!(
+                    "Prompt '{}' needs argument '{}'",
+                    prompt.name, argument.name
+                )));
+            }
+            None => {}
+        }
+    }
+    data.insert("proxy".to_string(), proxy);
+
+    let template = Template::parse(&prompt.template).map_err(|e| {
+        ProxyError::InvalidRequest(format!("Prompt '{}' template: {e}", prompt.name))
+    })?;
+    Ok(json!({
+        "description": prompt.description,
+        "messages": [{
+            "role": "user",
+            "content": { "type": "text", "text": template.render(&Value::Object(data)) }
+        }]
+    }))
+}
+
+/// The servers, by name, and the tools from the last aggregated
+/// `tools/list`, for prompt templates
+pub async fn proxy_data(state: &AppState) -> Value {
+    let config = state.config.read().await;
+    let mut names: Vec<String> = state.servers.iter().map(|e| e.key().clone()).collect();
+    names.sort();
+    let mut servers = Vec::new();
+    for name in names {
+        let Some(info) = state.servers.get(&name).map(|info| info.clone()) else {
+            continue;
+        };
+        servers.push(json!({
+            "name": name,
+            "state": format!("{:?}", *info.state.read().await),
+            "tags": config.servers.get(&name).map(|s| s.tags.clone()).unwrap_or_default()
+        }));
+    }
+
+    let mut tools: Vec<(String, String)> = state
+        .request_router
+        .tool_to_server
+        .iter()
+        .flat_map(|entry| {
+            let tool = entry.key().clone();
+            entry
+                .value()
+                .iter()
+                .map(move |server| (tool.clone(), server.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    tools.sort();
+    let tools: Vec<Value> = tools
+        .into_iter()
+        .map(|(tool, server)| {
+            json!({
+                "prefixedName": format!("mcp__proxy__{}__{}", server.replace('-', "_"), tool),
+                "name": tool,
+                "server": server
+            })
+        })
+        .collect();
+
+    json!({ "servers": servers, "tools": tools })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_configured_prompt() {
+        let prompt: PromptConfig = serde_json::from_value(json!({
+            "name": "triage",
+            "description": "Triage a ticket",
+            "arguments": [
+                { "name": "ticket", "required": true },
+                { "name": "team", "default": "core" }
+            ],
+            "template": "Triage {{ticket}} for {{team}} with {{#each proxy.tools}}{{prefixedName}} {{/each}}"
+        }))
+        .unwrap();
+        let proxy = json!({
+            "servers": [],
+            "tools": [{ "name": "search", "server": "jira", "prefixedName": "mcp__proxy__jira__search" }]
+        });
+
+        let result =
+            render_configured_prompt(&prompt, Some(&json!({ "ticket": "OPS-1" })), proxy.clone())
+                .unwrap();
+        assert_eq!(
+            result["messages"][0]["content"]["text"],
+            "Triage OPS-1 for core with mcp__proxy__jira__search "
+        );
+
+        assert!(render_configured_prompt(&prompt, None, proxy).is_err());
+        assert_eq!(
+            get_configured_prompts(&[prompt])[0]["arguments"][0]["required"],
+            true
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! Whether the proxy should be sent traffic, for `/readyz`
+
+use crate::state::{AppState, ServerState};
+
+/// Why the proxy is not ready, by the configured `readiness` criteria;
+/// empty when it is
+pub async fn unready_reasons(state: &AppState, catalog_warmed: bool) -> Vec<String> {
+    let mut reasons = Vec::new();
+    if state.drain.is_terminating() {
+        reasons.push("shutting down".to_string());
+    }
+
+    let config = state.config.read().await;
+    let readiness = &config.readiness;
+    let names: Vec<String> = state.servers.iter().map(|e| e.key().clone()).collect();
+    let mut running = Vec::new();
+    for name in names {
+        if state.get_server_state(&name).await == Some(ServerState::Running) {
+            running.push(name);
+        }
+    }
+
+    let wanted = readiness.min_ready_servers.min(config.servers.len());
+    if running.len() < wanted {
+        reasons.push(format!(
+            "{} of {} servers running, {} needed",
+            running.len(),
+            config.servers.len(),
+            wanted
+        ));
+    }
+    for name in &readiness.required_servers {
+        if !running.contains(name) {
+            reasons.push(format!("server {name} is not running"));
+        }
+    }
+    if readiness.require_warm_cache && !catalog_warmed {
+        reasons.push("tool list not cached yet".to_string());
+    }
+    reasons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config_with;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_readiness_criteria() {
+        let config = test_config_with(
+            json!({
+                "github": { "command": "gh", "args": [], "transport": { "type": "stdio" } },
+                "search": { "command": "search", "args": [], "transport": { "type": "stdio" } }
+            }),
+            json!({ "readiness": { "requiredServers": ["search"], "requireWarmCache": true } }),
+        );
+        let (state, _) = AppState::new(config);
+        for name in ["github", "search"] {
+            state
+                .register_server(
+                    name.to_string(),
+                    crate::state::ServerInfo::new(name.to_string()),
+                )
+                .await;
+        }
+
+        assert_eq!(
+            unready_reasons(&state, false).await,
+            [
+                "0 of 2 servers running, 1 needed",
+                "server search is not running",
+                "tool list not cached yet"
+            ]
+        );
+
+        state
+            .set_server_state("github", ServerState::Running)
+            .await
+            .unwrap();
+        assert_eq!(
+            unready_reasons(&state, false).await,
+            ["server search is not running", "tool list not cached yet"]
+        );
+
+        state
+            .set_server_state("search", ServerState::Running)
+            .await
+            .unwrap();
+        assert_eq!(
+            unready_reasons(&state, false).await,
+            ["tool list not cached yet"]
+        );
+        assert!(unready_reasons(&state, true).await.is_empty());
+
+        state.drain.mark_terminating();
+        assert_eq!(unready_reasons(&state, true).await, ["shutting down"]);
+    }
+}
@@ -0,0 +1,82 @@
+//! Resource URIs as clients of the proxy see them
+//!
+//! Aggregated resources are listed under `mcp__proxy__{server}://{uri}` so
+//! reads can be routed back to the server they came from. `resource_link`
+//! content in tool results gets the same prefix, so a link a tool hands out
+//! can be read through the proxy like any listed resource.
+
+use serde_json::Value;
+
+/// The URI clients see for a server's resource
+pub fn prefixed_uri(server: &str, uri: &str) -> String {
+    format!("mcp__proxy__{}://{uri}", server.replace('-', "_"))
+}
+
+/// The server and its own URI for a prefixed URI; `None` for URIs without
+/// the prefix
+pub fn unprefixed_uri(uri: &str) -> Option<(String, &str)> {
+    let (server, uri) = uri.strip_prefix("mcp__proxy__")?.split_once("://")?;
+    if server.is_empty() {
+        return None;
+    }
+    Some((server.replace('_', "-"), uri))
+}
+
+/// Prefix the URIs of the resource links in a tool result, keeping the
+/// server's own URI alongside as `originalUri` as resource listings do
+pub fn prefix_links(result: &mut Value, server: &str) {
+    let Some(content) = result.get_mut("content").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for item in content {
+        if item.get("type").and_then(Value::as_str) != Some("resource_link") {
+            continue;
+        }
+        let Some(object) = item.as_object_mut() else {
+            continue;
+        };
+        let Some(uri) = object
+            .get("uri")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        object.insert("uri".to_string(), Value::from(prefixed_uri(server, &uri)));
+        object.insert("originalUri".to_string(), Value::from(uri));
+        object.insert("server".to_string(), Value::from(server));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_resource_links_are_prefixed() {
+        let mut result = json!({
+            "content": [
+                { "type": "text", "text": "Wrote the report" },
+                { "type": "resource_link", "uri": "file:///out/report.md", "name": "report.md" }
+            ]
+        });
+        prefix_links(&mut result, "file-store");
+
+        let link = &result["content"][1];
+        assert_eq!(
+            link["uri"],
+            "mcp__proxy__file_store://file:///out/report.md"
+        );
+        assert_eq!(link["originalUri"], "file:///out/report.md");
+        assert_eq!(link["name"], "report.md");
+        assert!(result["content"][0].get("originalUri").is_none());
+
+        assert_eq!(
+            unprefixed_uri(link["uri"].as_str().unwrap()),
+            Some(("file-store".to_string(), "file:///out/report.md"))
+        );
+        assert_eq!(unprefixed_uri("file:///out/report.md"), None);
+        assert_eq!(unprefixed_uri("mcp__proxy__://x"), None);
+    }
+}
@@ -0,0 +1,213 @@
+//! Size limits on what servers send back, so one huge tool result or
+//! resource cannot flood a client's context window
+
+use crate::config::{OversizedResponseAction, ServerConfig};
+use crate::error::{ProxyError, Result};
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseLimit {
+    pub max_bytes: usize,
+    pub action: OversizedResponseAction,
+}
+
+/// What a result answers
+#[derive(Debug, Clone, Copy)]
+pub enum Subject<'a> {
+    Tool(&'a str),
+    Resource(&'a str),
+}
+
+impl ResponseLimit {
+    /// The limit for a server's responses, or for one of its tools when a
+    /// tool name is given; `None` when they are unlimited
+    pub fn for_server(server: &ServerConfig, tool: Option<&str>) -> Option<Self> {
+        let max_bytes = tool
+            .and_then(|tool| server.tool_max_response_bytes.get(tool).copied())
+            .or(server.max_response_bytes)?;
+        Some(Self {
+            max_bytes,
+            action: server.oversized_responses,
+        })
+    }
+
+    /// Check a result against the limit, cutting it down or failing as
+    /// configured
+    pub fn enforce(&self, result: &mut Value, server: &str, subject: Subject) -> Result<()> {
+        let size = json_len(result);
+        if size <= self.max_bytes {
+            return Ok(());
+        }
+        match self.action {
+            OversizedResponseAction::Reject => Err(ProxyError::ResponseTooLarge {
+                server: server.to_string(),
+                size,
+                limit: self.max_bytes,
+            }),
+            OversizedResponseAction::Truncate => {
+                tracing::warn!(
+                    server = server,
+                    size = size,
+                    limit = self.max_bytes,
+                    "Truncating oversized response"
+                );
+                truncate(result, self.max_bytes, size, subject);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Keep the leading content items that fit, shortening the text of the
+/// first one that doesn't, and end with a note saying what was cut
+fn truncate(result: &mut Value, max_bytes: usize, size: usize, subject: Subject) {
+    let (key, note) = match subject {
+        Subject::Tool(tool) => (
+            "content",
+            json!({
+                "type": "text",
+                "text": format!(
+                    "[Truncated by mcp-proxy: {tool} returned {size} bytes, over the {max_bytes}-byte limit]"
+                )
+            }),
+        ),
+        Subject::Resource(uri) => (
+            "contents",
+            json!({
+                "uri": uri,
+                "mimeType": "text/plain",
+                "text": format!(
+                    "[Truncated by mcp-proxy: {uri} is {size} bytes, over the {max_bytes}-byte limit]"
+                )
+            }),
+        ),
+    };
+
+    let items = match result.get_mut(key).and_then(Value::as_array_mut) {
+        Some(items) => std::mem::take(items),
+        None => Vec::new(),
+    };
+    if !result.is_object() {
+        *result = json!({});
+    }
+    if let Some(object) = result.as_object_mut() {
+        // Structured output cannot be cut without breaking its schema
+        object.remove("structuredContent");
+        object.insert(key.to_string(), json!([]));
+    }
+
+    // Each item after the first takes a comma too
+    let mut budget = max_bytes.saturating_sub(json_len(result) + json_len(&note) + 1);
+    let mut kept = Vec::new();
+    for mut item in items {
+        let item_len = json_len(&item) + 1;
+        if item_len <= budget {
+            budget -= item_len;
+            kept.push(item);
+            continue;
+        }
+        if let Some(text) = item.get("text").and_then(Value::as_str) {
+            let overhead = item_len - json_len(&Value::from(text)) + 2;
+            if budget > overhead {
+                item["text"] = Value::from(shorten(text, budget - overhead));
+                kept.push(item);
+            }
+        }
+        break;
+    }
+    kept.push(note);
+    result[key] = Value::Array(kept);
+}
+
+/// The longest prefix of `text` whose JSON-escaped form fits in `max_len`
+/// bytes
+fn shorten(text: &str, max_len: usize) -> &str {
+    let mut len = 0;
+    for (at, c) in text.char_indices() {
+        len += match c {
+            '"' | '\\' | '\n' | '\r' | '\t' => 2,
+            c if c < ' ' => 6,
+            c => c.len_utf8(),
+        };
+        if len > max_len {
+            return &text[..at];
+        }
+    }
+    text
+}
+
+fn json_len(value: &Value) -> usize {
+    serde_json::to_vec(value).map_or(0, |bytes| bytes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(max_bytes: usize, action: OversizedResponseAction) -> ResponseLimit {
+        ResponseLimit { max_bytes, action }
+    }
+
+    #[test]
+    fn test_truncate_tool_result() {
+        let mut result = json!({
+            "content": [
+                { "type": "text", "text": "first\n".repeat(20) },
+                { "type": "text", "text": "second \"quoted\" ".repeat(100) },
+                { "type": "text", "text": "third" }
+            ],
+            "structuredContent": { "rows": [1, 2, 3] }
+        });
+        let limit = limit(600, OversizedResponseAction::Truncate);
+        limit
+            .enforce(&mut result, "db", Subject::Tool("query"))
+            .unwrap();
+
+        assert!(json_len(&result) <= 600);
+        let content = result["content"].as_array().unwrap();
+        assert_eq!(content.len(), 3);
+        assert_eq!(content[0]["text"], "first\n".repeat(20));
+        assert!(content[1]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("second \"quoted\""));
+        assert!(content[2]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("[Truncated by mcp-proxy: query returned"));
+        assert!(result.get("structuredContent").is_none());
+
+        // Results within the limit are left alone
+        let mut small = json!({ "content": [{ "type": "text", "text": "ok" }] });
+        limit
+            .enforce(&mut small, "db", Subject::Tool("query"))
+            .unwrap();
+        assert_eq!(small["content"][0]["text"], "ok");
+    }
+
+    #[test]
+    fn test_reject_and_resources() {
+        let mut result = json!({
+            "contents": [{ "uri": "file:///big.log", "blob": "A".repeat(4096) }]
+        });
+        let err = limit(1024, OversizedResponseAction::Reject)
+            .enforce(&mut result, "files", Subject::Resource("file:///big.log"))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ProxyError::ResponseTooLarge { limit: 1024, .. }
+        ));
+
+        // A blob cannot be cut, so only the note is left
+        limit(1024, OversizedResponseAction::Truncate)
+            .enforce(&mut result, "files", Subject::Resource("file:///big.log"))
+            .unwrap();
+        let contents = result["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 1);
+        assert_eq!(contents[0]["uri"], "file:///big.log");
+        assert_eq!(contents[0]["mimeType"], "text/plain");
+
+        assert_eq!(shorten("héllo", 2), "h");
+        assert_eq!(shorten("a\"b", 3), "a\"");
+    }
+}
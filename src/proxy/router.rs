@@ -1,5 +1,9 @@
+use crate::config::{ConflictResolution, PromptDuplicates, RouteRule, RoutingConfig};
+use arc_swap::ArcSwap;
 use dashmap::DashMap;
+use serde::Serialize;
 use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -15,11 +19,31 @@ pub struct QueuedRequest {
     pub response_tx: ResponseChannel,
 }
 
+/// A tool name exposed by more than one server
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolConflict {
+    pub tool: String,
+    /// Servers exposing the tool, in the order they registered it
+    pub servers: Vec<String>,
+    /// Where unprefixed calls go, or `None` when they are refused
+    pub resolved_to: Option<String>,
+}
+
 pub struct RequestRouter {
-    // Maps resource/tool/prompt names to server names
-    pub tool_to_server: DashMap<String, String>,
+    // Maps resource/tool/prompt names to server names. Several servers may
+    // expose the same tool or prompt, kept in the order they registered it.
+    pub tool_to_server: DashMap<String, Vec<String>>,
     pub resource_to_server: DashMap<String, String>,
-    pub prompt_to_server: DashMap<String, String>,
+    pub prompt_to_server: DashMap<String, Vec<String>>,
+
+    // Output schemas declared in each server's tools/list, by server and
+    // then tool name
+    output_schemas: DashMap<String, HashMap<String, Value>>,
+
+    // Configured routes that take precedence over the maps above, and how
+    // tool conflicts are resolved
+    routing: ArcSwap<RoutingConfig>,
 
     // Request queues per server (for requests during initialization)
     pub request_queues: DashMap<String, Arc<Mutex<Vec<QueuedRequest>>>>,
@@ -37,12 +61,50 @@ impl RequestRouter {
             tool_to_server: DashMap::new(),
             resource_to_server: DashMap::new(),
             prompt_to_server: DashMap::new(),
+            output_schemas: DashMap::new(),
+            routing: ArcSwap::from_pointee(RoutingConfig::default()),
             request_queues: DashMap::new(),
         }
     }
 
     pub fn register_tool(&self, tool_name: String, server_name: String) {
-        self.tool_to_server.insert(tool_name, server_name);
+        let mut servers = self.tool_to_server.entry(tool_name).or_default();
+        if !servers.contains(&server_name) {
+            servers.push(server_name);
+        }
+    }
+
+    /// Replace the tools registered for a server with the ones it now lists
+    pub fn register_server_tools<I>(&self, server_name: &str, tool_names: I)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        self.unregister_tools(server_name);
+        for tool_name in tool_names {
+            self.register_tool(tool_name, server_name.to_string());
+        }
+    }
+
+    /// Replace the output schemas recorded for a server's tools
+    pub fn register_output_schemas<I>(&self, server_name: &str, schemas: I)
+    where
+        I: IntoIterator<Item = (String, Value)>,
+    {
+        let schemas: HashMap<_, _> = schemas.into_iter().collect();
+        if schemas.is_empty() {
+            self.output_schemas.remove(server_name);
+        } else {
+            self.output_schemas.insert(server_name.to_string(), schemas);
+        }
+    }
+
+    /// The output schema a server declared for one of its tools, by the
+    /// tool's own (unprefixed) name
+    pub fn output_schema(&self, server_name: &str, tool_name: &str) -> Option<Value> {
+        self.output_schemas
+            .get(server_name)?
+            .get(tool_name)
+            .cloned()
     }
 
     pub fn register_resource(&self, resource_uri: String, server_name: String) {
@@ -50,32 +112,122 @@ impl RequestRouter {
     }
 
     pub fn register_prompt(&self, prompt_name: String, server_name: String) {
-        self.prompt_to_server.insert(prompt_name, server_name);
+        let mut servers = self.prompt_to_server.entry(prompt_name).or_default();
+        if !servers.contains(&server_name) {
+            servers.push(server_name);
+        }
+    }
+
+    /// Replace the prompts registered for a server with the ones it now lists
+    pub fn register_server_prompts<I>(&self, server_name: &str, prompt_names: I)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        unregister(&self.prompt_to_server, server_name);
+        for prompt_name in prompt_names {
+            self.register_prompt(prompt_name, server_name.to_string());
+        }
+    }
+
+    /// Replace the configured routes and conflict resolution
+    pub fn set_routing(&self, routing: RoutingConfig) {
+        self.routing.store(Arc::new(routing));
+    }
+
+    /// Put results from several servers in `routing.priority` order, the
+    /// unlisted ones after by name, so merged and picked results don't
+    /// depend on which server answered first
+    pub fn sort_by_priority<T>(&self, results: &mut [(String, T)]) {
+        let routing = self.routing.load();
+        results.sort_by(|(a, _), (b, _)| {
+            let rank = |server: &String| routing.priority.iter().position(|name| name == server);
+            let (a_rank, b_rank) = (rank(a), rank(b));
+            a_rank
+                .is_none()
+                .cmp(&b_rank.is_none())
+                .then(a_rank.cmp(&b_rank))
+                .then(a.cmp(b))
+        });
+    }
+
+    pub fn prompt_duplicates(&self) -> PromptDuplicates {
+        self.routing.load().prompt_duplicates
     }
 
     pub fn get_server_for_tool(&self, tool_name: &str) -> Option<String> {
-        self.tool_to_server.get(tool_name).map(|v| v.clone())
+        let routing = self.routing.load();
+        override_for(&routing.tools, tool_name).or_else(|| {
+            let servers = self.tool_to_server.get(tool_name)?;
+            resolve_conflict(&routing, &servers)
+        })
+    }
+
+    /// Servers exposing a tool, in the order they registered it
+    pub fn servers_for_tool(&self, tool_name: &str) -> Vec<String> {
+        self.tool_to_server
+            .get(tool_name)
+            .map(|servers| servers.clone())
+            .unwrap_or_default()
+    }
+
+    /// Tools exposed by more than one server, sorted by name
+    pub fn tool_conflicts(&self) -> Vec<ToolConflict> {
+        let routing = self.routing.load();
+        let mut conflicts: Vec<_> = self
+            .tool_to_server
+            .iter()
+            .filter(|entry| entry.value().len() > 1)
+            .map(|entry| ToolConflict {
+                tool: entry.key().clone(),
+                servers: entry.value().clone(),
+                resolved_to: override_for(&routing.tools, entry.key())
+                    .or_else(|| resolve_conflict(&routing, entry.value())),
+            })
+            .collect();
+        conflicts.sort_by(|a, b| a.tool.cmp(&b.tool));
+        conflicts
     }
 
     pub fn get_server_for_resource(&self, resource_uri: &str) -> Option<String> {
-        self.resource_to_server.get(resource_uri).map(|v| v.clone())
+        override_for(&self.routing.load().resources, resource_uri)
+            .or_else(|| self.resource_to_server.get(resource_uri).map(|v| v.clone()))
     }
 
+    /// The server a prompt is fetched from: a configured route, else the
+    /// server listed earliest in `priority`, else the first to register it
     pub fn get_server_for_prompt(&self, prompt_name: &str) -> Option<String> {
-        self.prompt_to_server.get(prompt_name).map(|v| v.clone())
+        let routing = self.routing.load();
+        override_for(&routing.prompts, prompt_name).or_else(|| {
+            let servers = self.prompt_to_server.get(prompt_name)?;
+            by_priority(&routing.priority, &servers)
+        })
+    }
+
+    /// Servers exposing a prompt, in the order they registered it
+    pub fn servers_for_prompt(&self, prompt_name: &str) -> Vec<String> {
+        self.prompt_to_server
+            .get(prompt_name)
+            .map(|servers| servers.clone())
+            .unwrap_or_default()
     }
 
     pub fn unregister_server(&self, server_name: &str) {
         // Remove all entries for this server
-        self.tool_to_server.retain(|_, v| v != server_name);
+        self.unregister_tools(server_name);
         self.resource_to_server.retain(|_, v| v != server_name);
-        self.prompt_to_server.retain(|_, v| v != server_name);
+        unregister(&self.prompt_to_server, server_name);
+    }
+
+    fn unregister_tools(&self, server_name: &str) {
+        unregister(&self.tool_to_server, server_name);
+        self.output_schemas.remove(server_name);
     }
 
     pub fn clear(&self) {
         self.tool_to_server.clear();
         self.resource_to_server.clear();
         self.prompt_to_server.clear();
+        self.output_schemas.clear();
     }
 
     // ========================================================================
@@ -119,3 +271,67 @@ impl RequestRouter {
         self.process_queued_requests(server_name).await
     }
 }
+
+/// The server of the first rule whose pattern matches `name`
+fn override_for(rules: &[RouteRule], name: &str) -> Option<String> {
+    rules
+        .iter()
+        .find(|rule| glob_matches(&rule.pattern, name))
+        .map(|rule| rule.server.clone())
+}
+
+/// The server unprefixed calls to a tool go to, given the servers exposing
+/// it in registration order
+fn resolve_conflict(routing: &RoutingConfig, servers: &[String]) -> Option<String> {
+    if servers.len() < 2 {
+        return servers.first().cloned();
+    }
+    match routing.conflicts {
+        ConflictResolution::FirstWins => servers.first().cloned(),
+        ConflictResolution::Priority => by_priority(&routing.priority, servers),
+        ConflictResolution::Error => None,
+    }
+}
+
+/// The server listed earliest in `priority`. Unlisted servers rank after
+/// listed ones, in registration order.
+fn by_priority(priority: &[String], servers: &[String]) -> Option<String> {
+    servers
+        .iter()
+        .min_by_key(|server| {
+            priority
+                .iter()
+                .position(|name| name == *server)
+                .unwrap_or(usize::MAX)
+        })
+        .cloned()
+}
+
+/// Drop a server from every name in a name-to-servers map
+fn unregister(map: &DashMap<String, Vec<String>>, server_name: &str) {
+    map.retain(|_, servers| {
+        servers.retain(|server| server != server_name);
+        !servers.is_empty()
+    });
+}
+
+/// Match `text` against a pattern where `*` stands for any run of characters
+pub(crate) fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No `*` at all
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
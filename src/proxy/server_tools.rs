@@ -1,3 +1,7 @@
+use super::ClientIdentity;
+use crate::config::ClientRole;
+use crate::error::{ProxyError, Result};
+use crate::server::ServerManager;
 use crate::state::AppState;
 use serde_json::{json, Value};
 use std::sync::Arc;
@@ -10,7 +14,12 @@ pub fn get_server_tools() -> Vec<Value> {
             "List all backend MCP servers and their status",
             json!({
                 "type": "object",
-                "properties": {},
+                "properties": {
+                    "tag": {
+                        "type": "string",
+                        "description": "Only list servers carrying this tag"
+                    }
+                },
                 "additionalProperties": false
             }),
         ),
@@ -56,6 +65,21 @@ pub fn get_server_tools() -> Vec<Value> {
                 "required": ["server_name"]
             }),
         ),
+        create_tool(
+            "mcp__proxy__server__restart_all",
+            "Restart all running backend MCP servers one at a time, waiting for each to be ready and healthy",
+            json!({
+                "type": "object",
+                "properties": {
+                    "strategy": {
+                        "type": "string",
+                        "enum": ["rolling"],
+                        "description": "Restart strategy (default: rolling)"
+                    }
+                },
+                "additionalProperties": false
+            }),
+        ),
         create_tool(
             "mcp__proxy__server__start",
             "Start a stopped backend MCP server",
@@ -70,6 +94,28 @@ pub fn get_server_tools() -> Vec<Value> {
                 "required": ["server_name"]
             }),
         ),
+        create_tool(
+            "mcp__proxy__server__maintenance",
+            "Take a backend MCP server out of rotation (or return it) without stopping it; in-flight calls finish, new calls get a temporarily unavailable error",
+            json!({
+                "type": "object",
+                "properties": {
+                    "server_name": {
+                        "type": "string",
+                        "description": "Name of the server"
+                    },
+                    "enabled": {
+                        "type": "boolean",
+                        "description": "true to enter maintenance mode, false to leave it"
+                    },
+                    "reason": {
+                        "type": "string",
+                        "description": "Reason reported to callers while in maintenance"
+                    }
+                },
+                "required": ["server_name", "enabled"]
+            }),
+        ),
         create_tool(
             "mcp__proxy__server__stop",
             "Stop a running backend MCP server",
@@ -95,6 +141,22 @@ fn create_tool(name: &str, description: &str, input_schema: Value) -> Value {
     })
 }
 
+/// Check that the client may call a server tool. Listing is open to all;
+/// starting, stopping, restarting, disabling and maintenance need the admin
+/// role once clients are configured. Anonymous requests come from an open
+/// endpoint or stdio and are trusted.
+pub fn authorize(tool_name: &str, client: Option<&ClientIdentity>) -> Result<()> {
+    match client {
+        Some(client) if tool_name != "list" && client.role != ClientRole::Admin => {
+            Err(ProxyError::Unauthorized(format!(
+                "client '{}' needs the admin role to {tool_name} servers",
+                client.id
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Handle server management tool calls
 pub async fn handle_server_tool(
     tool_name: &str,
@@ -102,22 +164,43 @@ pub async fn handle_server_tool(
     state: Arc<AppState>,
 ) -> std::result::Result<Value, String> {
     match tool_name {
-        "list" => handle_list_servers(state).await,
+        "list" => handle_list_servers(arguments, state).await,
         "enable" => handle_enable_server(arguments, state).await,
         "disable" => handle_disable_server(arguments, state).await,
         "restart" => handle_restart_server(arguments, state).await,
+        "restart_all" => handle_restart_all_servers(arguments, state).await,
         "start" => handle_start_server(arguments, state).await,
         "stop" => handle_stop_server(arguments, state).await,
+        "maintenance" => handle_maintenance(arguments, state).await,
         _ => Err(format!("Unknown server tool: {tool_name}")),
     }
 }
 
-async fn handle_list_servers(state: Arc<AppState>) -> std::result::Result<Value, String> {
+async fn handle_list_servers(
+    arguments: Value,
+    state: Arc<AppState>,
+) -> std::result::Result<Value, String> {
+    let tag_filter = arguments.get("tag").and_then(|t| t.as_str());
     let mut servers = Vec::new();
 
     for entry in state.servers.iter() {
         let name = entry.key();
         let info = entry.value();
+
+        let tags = state
+            .config
+            .read()
+            .await
+            .servers
+            .get(name)
+            .map(|s| s.tags.clone())
+            .unwrap_or_default();
+        if let Some(tag) = tag_filter {
+            if !tags.iter().any(|t| t == tag) {
+                continue;
+            }
+        }
+
         let server_state = info.state.read().await;
         let restart_count = info.restart_count.read().await;
 
@@ -126,6 +209,8 @@ async fn handle_list_servers(state: Arc<AppState>) -> std::result::Result<Value,
             "state": format!("{:?}", *server_state),
             "restart_count": *restart_count,
             "last_access_time": info.last_access_time.read().await.as_ref().map(|t| t.to_rfc3339()),
+            "tags": tags,
+            "maintenance": state.maintenance.get(name),
         }));
     }
 
@@ -177,6 +262,44 @@ async fn handle_disable_server(
     }
 }
 
+async fn handle_maintenance(
+    arguments: Value,
+    state: Arc<AppState>,
+) -> std::result::Result<Value, String> {
+    let server_name = arguments
+        .get("server_name")
+        .and_then(|n| n.as_str())
+        .ok_or("Missing server_name")?;
+    let enabled = arguments
+        .get("enabled")
+        .and_then(|e| e.as_bool())
+        .ok_or("Missing enabled")?;
+
+    if !state.servers.contains_key(server_name) {
+        return Err(format!("Server {server_name} not found"));
+    }
+
+    let text = if enabled {
+        let reason = arguments
+            .get("reason")
+            .and_then(|r| r.as_str())
+            .map(str::to_string);
+        if state.maintenance.enter(server_name, reason) {
+            format!("Server {server_name} is now in maintenance mode")
+        } else {
+            format!("Server {server_name} is already in maintenance mode")
+        }
+    } else if state.maintenance.exit(server_name).is_some() {
+        format!("Server {server_name} is back in rotation")
+    } else {
+        format!("Server {server_name} was not in maintenance mode")
+    };
+
+    Ok(json!({
+        "content": [{"type": "text", "text": text}]
+    }))
+}
+
 async fn handle_restart_server(
     arguments: Value,
     _state: Arc<AppState>,
@@ -192,6 +315,29 @@ async fn handle_restart_server(
     }))
 }
 
+async fn handle_restart_all_servers(
+    arguments: Value,
+    state: Arc<AppState>,
+) -> std::result::Result<Value, String> {
+    let strategy = arguments
+        .get("strategy")
+        .and_then(|s| s.as_str())
+        .unwrap_or("rolling");
+    if strategy != "rolling" {
+        return Err(format!("Unknown restart strategy: {strategy}"));
+    }
+
+    let manager = ServerManager::new(state.clone(), state.shutdown_tx.subscribe());
+    let report = manager.rolling_restart().await;
+    let report = serde_json::to_value(&report).map_err(|e| e.to_string())?;
+
+    Ok(json!({
+        "content": [{"type": "text", "text": serde_json::to_string_pretty(&report).unwrap()}],
+        "isError": report["failed"].is_string(),
+        "report": report
+    }))
+}
+
 async fn handle_start_server(
     arguments: Value,
     _state: Arc<AppState>,
@@ -221,3 +367,30 @@ async fn handle_stop_server(
         "content": [{"type": "text", "text": format!("Server {} stop requested (not yet implemented)", server_name)}]
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorize_requires_admin_for_changes() {
+        let user = ClientIdentity {
+            id: "ci-agent".to_string(),
+            role: ClientRole::User,
+        };
+        let admin = ClientIdentity {
+            id: "ops".to_string(),
+            role: ClientRole::Admin,
+        };
+
+        assert!(authorize("list", Some(&user)).is_ok());
+        for tool in ["restart_all", "stop", "start", "maintenance", "disable"] {
+            assert!(matches!(
+                authorize(tool, Some(&user)),
+                Err(ProxyError::Unauthorized(_))
+            ));
+            assert!(authorize(tool, Some(&admin)).is_ok());
+        }
+        assert!(authorize("restart", None).is_ok());
+    }
+}
@@ -0,0 +1,43 @@
+//! Time a request spends in each stage of being handled, for `bench`
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Finding the server for the request and checking it may take it
+    Routing,
+    /// Request- and response-phase plugins
+    Plugins,
+    /// Waiting for a call slot and for the server's reply
+    Backend,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 3] = [Stage::Routing, Stage::Plugins, Stage::Backend];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Routing => "routing",
+            Stage::Plugins => "plugins",
+            Stage::Backend => "backend",
+        }
+    }
+}
+
+/// How long one request has spent in each stage so far
+#[derive(Debug, Default)]
+pub struct StageTimings {
+    durations: Mutex<[Duration; 3]>,
+}
+
+impl StageTimings {
+    /// Count the time since `start` towards `stage`
+    pub fn record(&self, stage: Stage, start: Instant) {
+        self.durations.lock().unwrap()[stage as usize] += start.elapsed();
+    }
+
+    pub fn get(&self, stage: Stage) -> Duration {
+        self.durations.lock().unwrap()[stage as usize]
+    }
+}
@@ -0,0 +1,157 @@
+use crate::state::AppState;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Error lines reported per server unless the caller asks for more
+const DEFAULT_RECENT_ERRORS: usize = 5;
+
+/// Get proxy status tools
+pub fn get_status_tools() -> Vec<Value> {
+    vec![
+        json!({
+            "name": "mcp__proxy__status__overview",
+            "description": "Diagnose slow or failing tools: state, restarts, health, recent errors and request latency percentiles of each backend MCP server",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "server_name": {
+                        "type": "string",
+                        "description": "Only report on this server"
+                    },
+                    "errors": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Recent error log lines to include per server (default: 5)"
+                    }
+                },
+                "additionalProperties": false
+            }
+        }),
+        json!({
+            "name": "mcp__proxy__status__cache_stats",
+            "description": "Hits, misses, evictions and hit rate of the proxy's tool list cache and context tracing cache",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "additionalProperties": false
+            }
+        }),
+        json!({
+            "name": "mcp__proxy__status__cache_clear",
+            "description": "Clear the proxy's cached tool lists, context tracing cache, or both, so they are fetched again",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "scope": {
+                        "type": "string",
+                        "enum": ["tools", "context", "all"],
+                        "description": "Which caches to clear (default: all)"
+                    }
+                },
+                "additionalProperties": false
+            }
+        }),
+    ]
+}
+
+/// Handle status tool calls
+pub async fn handle_status_tool(
+    tool_name: &str,
+    arguments: Value,
+    state: Arc<AppState>,
+) -> std::result::Result<Value, String> {
+    match tool_name {
+        "overview" => handle_overview(arguments, state).await,
+        "cache_stats" => {
+            let report = crate::state::cache_stats::report(&state).await;
+            Ok(structured(json!(report)))
+        }
+        "cache_clear" => {
+            let scope = match arguments.get("scope").and_then(|s| s.as_str()) {
+                Some(scope) => scope.parse()?,
+                None => crate::state::cache_stats::CacheScope::All,
+            };
+            let cleared = crate::state::cache_stats::clear(&state, scope).await;
+            Ok(structured(json!(cleared)))
+        }
+        _ => Err(format!("Unknown status tool: {tool_name}")),
+    }
+}
+
+async fn handle_overview(
+    arguments: Value,
+    state: Arc<AppState>,
+) -> std::result::Result<Value, String> {
+    let server_filter = arguments.get("server_name").and_then(|n| n.as_str());
+    let error_lines = arguments
+        .get("errors")
+        .and_then(|e| e.as_u64())
+        .map_or(DEFAULT_RECENT_ERRORS, |e| e as usize);
+    if let Some(server_name) = server_filter {
+        if !state.servers.contains_key(server_name) {
+            return Err(format!("Server {server_name} not found"));
+        }
+    }
+
+    let pool_stats = state.connection_pool.stats();
+    let mut names: Vec<String> = state
+        .servers
+        .iter()
+        .map(|entry| entry.key().clone())
+        .filter(|name| server_filter.is_none_or(|filter| filter == name))
+        .collect();
+    names.sort();
+
+    let mut servers = Vec::new();
+    for name in names {
+        let Some(info) = state.servers.get(&name).map(|info| info.clone()) else {
+            continue;
+        };
+        let health = info.last_health_check.read().await.clone();
+        let pool = pool_stats.iter().find(|stats| stats.server == name);
+        let recent_errors: Vec<Value> = info
+            .log_buffer
+            .query(error_lines, Some("error"))
+            .into_iter()
+            .map(|entry| json!({"timestamp": entry.timestamp, "message": entry.message}))
+            .collect();
+
+        servers.push(json!({
+            "name": name,
+            "state": format!("{:?}", *info.state.read().await),
+            "restart_count": *info.restart_count.read().await,
+            "maintenance": state.maintenance.get(&name),
+            "last_access_time": info.last_access_time.read().await.as_ref().map(|t| t.to_rfc3339()),
+            "health": health.map(|h| json!({
+                "success": h.success,
+                "checked_at": h.timestamp,
+                "response_time_ms": h.response_time_ms,
+                "error": h.error,
+            })),
+            "in_flight": pool.map_or(0, |p| p.in_flight),
+            "transport_failures": pool.map_or(0, |p| p.failures),
+            "latency": pool.and_then(|p| p.latency.as_ref()),
+            "recent_errors": recent_errors,
+        }));
+    }
+
+    let metrics = &state.metrics;
+    let overview = json!({
+        "servers": servers,
+        "requests": {
+            "total": metrics.total_requests.get(),
+            "failed": metrics.failed_requests.get(),
+        },
+        "draining": state.drain.is_draining(),
+    });
+
+    Ok(structured(overview))
+}
+
+/// A tool result carrying `value` both as text and as structured content
+fn structured(value: Value) -> Value {
+    json!({
+        "content": [{"type": "text", "text": serde_json::to_string_pretty(&value).unwrap()}],
+        "structuredContent": value
+    })
+}
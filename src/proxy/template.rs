@@ -0,0 +1,312 @@
+//! A small Handlebars-style template language for configured prompts
+//!
+//! Supports `{{path.to.value}}`, `{{#each list}}…{{else}}…{{/each}}` (with
+//! `{{this}}`, `{{@index}}` and, over objects, `{{@key}}`),
+//! `{{#if value}}…{{else}}…{{/if}}`, `{{#unless value}}…{{/unless}}` and
+//! `{{! comments }}`. Values are inserted as they are, without escaping.
+//! Names not found in the current item are looked up in the enclosing ones.
+
+use serde_json::Value;
+
+#[derive(Debug)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Value(Path),
+    Each {
+        path: Path,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    If {
+        path: Path,
+        negate: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+#[derive(Debug)]
+struct Path {
+    /// Starts with `this`, so only the current item is searched
+    this: bool,
+    segments: Vec<String>,
+}
+
+enum Token {
+    Text(String),
+    Tag(String),
+}
+
+/// An item being rendered, innermost last
+#[derive(Clone, Copy)]
+struct Scope<'a> {
+    value: &'a Value,
+    index: Option<usize>,
+    key: Option<&'a str>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut tokens = tokenize(source)?.into_iter();
+        match parse_block(&mut tokens)? {
+            (nodes, None) => Ok(Self { nodes }),
+            (_, Some(tag)) => Err(format!("unexpected {{{{{tag}}}}}")),
+        }
+    }
+
+    pub fn render(&self, data: &Value) -> String {
+        let mut out = String::new();
+        let scopes = [Scope {
+            value: data,
+            index: None,
+            key: None,
+        }];
+        render_nodes(&self.nodes, &scopes, &mut out);
+        out
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(at) = rest.find("{{") {
+        if at > 0 {
+            tokens.push(Token::Text(rest[..at].to_string()));
+        }
+        let after = &rest[at + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| "unclosed {{ tag".to_string())?;
+        tokens.push(Token::Tag(after[..end].trim().to_string()));
+        rest = &after[end + 2..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    Ok(tokens)
+}
+
+/// Nodes up to the end of the input or an `else` or closing tag, which is
+/// returned
+fn parse_block(
+    tokens: &mut impl Iterator<Item = Token>,
+) -> Result<(Vec<Node>, Option<String>), String> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        let tag = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+        if tag.starts_with('!') {
+            continue;
+        }
+        if tag == "else" || tag.starts_with('/') {
+            return Ok((nodes, Some(tag)));
+        }
+        let Some(block) = tag.strip_prefix('#') else {
+            nodes.push(Node::Value(parse_path(&tag)?));
+            continue;
+        };
+
+        let (helper, argument) = block
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("{{{{#{block}}}}} needs a value"))?;
+        if !matches!(helper, "each" | "if" | "unless") {
+            return Err(format!("unknown block helper '{helper}'"));
+        }
+        let path = parse_path(argument.trim())?;
+        let (body, mut end) = parse_block(tokens)?;
+        let mut otherwise = Vec::new();
+        if end.as_deref() == Some("else") {
+            (otherwise, end) = parse_block(tokens)?;
+        }
+        if end.as_deref() != Some(&format!("/{helper}")) {
+            return Err(format!(
+                "{{{{#{helper} {}}}}} is not closed",
+                argument.trim()
+            ));
+        }
+        nodes.push(match helper {
+            "each" => Node::Each {
+                path,
+                body,
+                otherwise,
+            },
+            _ => Node::If {
+                path,
+                negate: helper == "unless",
+                then: body,
+                otherwise,
+            },
+        });
+    }
+    Ok((nodes, None))
+}
+
+fn parse_path(expression: &str) -> Result<Path, String> {
+    if expression.is_empty() || expression.contains(char::is_whitespace) {
+        return Err(format!("invalid expression '{expression}'"));
+    }
+    let mut segments: Vec<String> = expression.split('.').map(str::to_string).collect();
+    if segments.iter().any(String::is_empty) {
+        return Err(format!("invalid expression '{expression}'"));
+    }
+    let this = segments[0] == "this";
+    if this {
+        segments.remove(0);
+    }
+    Ok(Path { this, segments })
+}
+
+fn render_nodes(nodes: &[Node], scopes: &[Scope], out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Value(path) => match lookup(path, scopes) {
+                None | Some(Value::Null) => {}
+                Some(Value::String(s)) => out.push_str(&s),
+                Some(value) => out.push_str(&value.to_string()),
+            },
+            Node::Each {
+                path,
+                body,
+                otherwise,
+            } => {
+                let Some(value) = lookup_ref(path, scopes) else {
+                    render_nodes(otherwise, scopes, out);
+                    continue;
+                };
+                let items: Vec<(Option<&str>, &Value)> = match value {
+                    Value::Array(items) => items.iter().map(|item| (None, item)).collect(),
+                    Value::Object(map) => map.iter().map(|(k, v)| (Some(k.as_str()), v)).collect(),
+                    _ => Vec::new(),
+                };
+                if items.is_empty() {
+                    render_nodes(otherwise, scopes, out);
+                }
+                for (index, (key, value)) in items.into_iter().enumerate() {
+                    let mut inner = scopes.to_vec();
+                    inner.push(Scope {
+                        value,
+                        index: Some(index),
+                        key,
+                    });
+                    render_nodes(body, &inner, out);
+                }
+            }
+            Node::If {
+                path,
+                negate,
+                then,
+                otherwise,
+            } => {
+                let truthy = lookup(path, scopes).is_some_and(|value| is_truthy(&value));
+                if truthy != *negate {
+                    render_nodes(then, scopes, out);
+                } else {
+                    render_nodes(otherwise, scopes, out);
+                }
+            }
+        }
+    }
+}
+
+fn lookup(path: &Path, scopes: &[Scope]) -> Option<Value> {
+    let current = scopes.last()?;
+    match path.segments.first().map(String::as_str) {
+        Some("@index") => current.index.map(Value::from),
+        Some("@key") => current.key.map(Value::from),
+        _ => lookup_ref(path, scopes).cloned(),
+    }
+}
+
+fn lookup_ref<'a>(path: &Path, scopes: &[Scope<'a>]) -> Option<&'a Value> {
+    let Some(first) = path.segments.first() else {
+        return scopes.last().map(|scope| scope.value);
+    };
+    let scope = if path.this {
+        scopes.last()?
+    } else {
+        scopes
+            .iter()
+            .rev()
+            .find(|scope| scope.value.get(first).is_some())?
+    };
+    path.segments
+        .iter()
+        .try_fold(scope.value, |value, segment| match value {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => value.get(segment),
+        })
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render() {
+        let template = Template::parse(
+            "Review {{repo}}{{! the target }} for {{#if focus}}{{focus}}{{else}}anything{{/if}}.\n\
+             {{#each proxy.servers}}{{@index}}. {{name}} ({{state}}) in {{repo}}\n{{else}}No servers\n{{/each}}\
+             {{#unless proxy.tools}}No tools{{/unless}}",
+        )
+        .unwrap();
+
+        let data = json!({
+            "repo": "api",
+            "focus": "",
+            "proxy": {
+                "servers": [
+                    { "name": "github", "state": "running" },
+                    { "name": "jira", "state": "failed" }
+                ],
+                "tools": []
+            }
+        });
+        assert_eq!(
+            template.render(&data),
+            "Review api for anything.\n0. github (running) in api\n1. jira (failed) in api\nNo tools"
+        );
+
+        let data =
+            json!({ "repo": "api", "focus": "auth", "proxy": { "tools": [{ "name": "search" }] } });
+        assert_eq!(template.render(&data), "Review api for auth.\nNo servers\n");
+
+        let each_key = Template::parse("{{#each tags}}{{@key}}={{this}};{{/each}}").unwrap();
+        assert_eq!(
+            each_key.render(&json!({ "tags": { "team": "core", "tier": 1 } })),
+            "team=core;tier=1;"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{{name").is_err());
+        assert!(Template::parse("{{#each servers}}{{name}}").is_err());
+        assert!(Template::parse("{{#each servers}}{{/if}}").is_err());
+        assert!(Template::parse("{{#with server}}{{/with}}").is_err());
+        assert!(Template::parse("{{/each}}").is_err());
+        assert!(Template::parse("{{a..b}}").is_err());
+    }
+}
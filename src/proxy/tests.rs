@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use super::super::*;
+    use crate::config::test_config;
     use crate::protocol::{JsonRpcId, JsonRpcMessage, JsonRpcV2Message};
     use serde_json::json;
 
@@ -27,6 +28,202 @@ mod tests {
         assert_eq!(router.get_server_for_resource("resources/other"), None);
     }
 
+    #[test]
+    fn test_routing_overrides() {
+        let router = RequestRouter::new();
+        router.register_tool("db_query".to_string(), "sqlite".to_string());
+        router.register_resource("jira://PROJ-1".to_string(), "tickets".to_string());
+
+        let routing: crate::config::RoutingConfig = serde_json::from_value(json!({
+            "tools": [
+                { "pattern": "db_*", "server": "postgres" },
+                { "pattern": "*", "server": "fallback" }
+            ],
+            "resources": [{ "pattern": "jira://*/comments", "server": "jira" }]
+        }))
+        .unwrap();
+        router.set_routing(routing);
+
+        // The first matching rule wins over the catalog
+        assert_eq!(
+            router.get_server_for_tool("db_query"),
+            Some("postgres".to_string())
+        );
+        assert_eq!(
+            router.get_server_for_tool("search"),
+            Some("fallback".to_string())
+        );
+        assert_eq!(
+            router.get_server_for_resource("jira://PROJ-1/comments"),
+            Some("jira".to_string())
+        );
+        // Names no rule matches still use the catalog
+        assert_eq!(
+            router.get_server_for_resource("jira://PROJ-1"),
+            Some("tickets".to_string())
+        );
+        assert_eq!(router.get_server_for_prompt("summarize"), None);
+    }
+
+    #[test]
+    fn test_tool_conflict_resolution() {
+        use crate::config::{ConflictResolution, RoutingConfig};
+
+        let router = RequestRouter::new();
+        router.register_server_tools("github", ["search".to_string(), "issues".to_string()]);
+        router.register_server_tools("gitlab", ["search".to_string()]);
+
+        // First registration wins by default
+        assert_eq!(
+            router.get_server_for_tool("search"),
+            Some("github".to_string())
+        );
+        assert_eq!(router.tool_conflicts().len(), 1);
+
+        router.set_routing(RoutingConfig {
+            conflicts: ConflictResolution::Priority,
+            priority: vec!["gitlab".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(
+            router.get_server_for_tool("search"),
+            Some("gitlab".to_string())
+        );
+
+        router.set_routing(RoutingConfig {
+            conflicts: ConflictResolution::Error,
+            ..Default::default()
+        });
+        assert_eq!(router.get_server_for_tool("search"), None);
+        assert_eq!(
+            router.get_server_for_tool("issues"),
+            Some("github".to_string())
+        );
+        assert_eq!(router.tool_conflicts()[0].resolved_to, None);
+
+        // A server that stops listing a tool no longer conflicts on it
+        router.register_server_tools("gitlab", ["merge_requests".to_string()]);
+        assert!(router.tool_conflicts().is_empty());
+        assert_eq!(
+            router.get_server_for_tool("search"),
+            Some("github".to_string())
+        );
+    }
+
+    #[test]
+    fn test_output_schemas_follow_tool_lists() {
+        let router = RequestRouter::new();
+        router.register_server_tools("db", ["query".to_string()]);
+        router.register_output_schemas("db", [("query".to_string(), json!({ "type": "object" }))]);
+        assert_eq!(
+            router.output_schema("db", "query"),
+            Some(json!({ "type": "object" }))
+        );
+        assert_eq!(router.output_schema("other", "query"), None);
+
+        // Relisting tools replaces the schemas along with them
+        router.register_server_tools("db", ["query".to_string()]);
+        assert_eq!(router.output_schema("db", "query"), None);
+
+        router.register_output_schemas("db", [("query".to_string(), json!({ "type": "object" }))]);
+        router.unregister_server("db");
+        assert_eq!(router.output_schema("db", "query"), None);
+    }
+
+    #[test]
+    fn test_broadcast_results_in_priority_order() {
+        use crate::config::RoutingConfig;
+
+        let router = RequestRouter::new();
+        router.set_routing(RoutingConfig {
+            priority: vec!["zeta".to_string()],
+            ..Default::default()
+        });
+        let mut results = vec![
+            (
+                "beta".to_string(),
+                json!({ "items": [{ "id": 2 }], "nextCursor": "b" }),
+            ),
+            (
+                "zeta".to_string(),
+                json!({ "items": [{ "id": 3 }, "plain"], "total": 2 }),
+            ),
+            (
+                "alpha".to_string(),
+                json!({ "items": [{ "id": 1 }], "total": 1 }),
+            ),
+        ];
+        router.sort_by_priority(&mut results);
+        let order: Vec<&str> = results.iter().map(|(server, _)| server.as_str()).collect();
+        assert_eq!(order, ["zeta", "alpha", "beta"]);
+
+        assert_eq!(
+            handler::merged_lists(results.clone()),
+            json!({
+                "items": [
+                    { "id": 3, "server": "zeta" },
+                    "plain",
+                    { "id": 1, "server": "alpha" },
+                    { "id": 2, "server": "beta" }
+                ],
+                "total": 2
+            })
+        );
+
+        results[1].1 = json!("not a listing");
+        assert_eq!(handler::merged_lists(results)["total"], 2);
+    }
+
+    #[test]
+    fn test_prompt_precedence() {
+        use crate::config::RoutingConfig;
+
+        let router = RequestRouter::new();
+        let listing = |names: &[&str]| {
+            let prompts: Vec<serde_json::Value> = names
+                .iter()
+                .map(|name| json!({ "name": name, "description": *name }))
+                .collect();
+            json!({ "prompts": prompts })
+        };
+        let results = vec![
+            ("hosted".to_string(), listing(&["review", "summarize"])),
+            ("local".to_string(), listing(&["review"])),
+        ];
+        for (server, result) in &results {
+            let names = result["prompts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["name"].as_str().unwrap().to_string());
+            router.register_server_prompts(server, names);
+        }
+
+        // The first server to list a prompt provides it by default
+        assert_eq!(
+            router.get_server_for_prompt("review"),
+            Some("hosted".to_string())
+        );
+
+        router.set_routing(RoutingConfig {
+            priority: vec!["local".to_string()],
+            ..Default::default()
+        });
+        let prompts = handler::deduplicated_prompts(results, &router);
+        assert_eq!(prompts.len(), 2);
+        assert_eq!(prompts[0]["name"], "review");
+        assert_eq!(prompts[0]["server"], "local");
+        assert_eq!(prompts[0]["alsoProvidedBy"], json!(["hosted"]));
+        assert_eq!(prompts[1]["server"], "hosted");
+        assert!(prompts[1].get("alsoProvidedBy").is_none());
+
+        router.unregister_server("local");
+        assert_eq!(
+            router.servers_for_prompt("review"),
+            vec!["hosted".to_string()]
+        );
+    }
+
     #[test]
     fn test_request_parsing() {
         let request_json = json!({
@@ -70,4 +267,466 @@ mod tests {
             panic!("Expected notification message");
         }
     }
+
+    #[tokio::test]
+    async fn test_drain_queued_requests_resolves_waiters() {
+        use super::super::router::QueuedRequest;
+        use std::sync::Arc;
+        use tokio::sync::Mutex;
+
+        let config = test_config(json!({}));
+        let (state, _) = crate::state::AppState::new(config);
+        let handler = RequestHandler::new(state.clone());
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        state
+            .request_router
+            .queue_request(
+                "missing",
+                QueuedRequest {
+                    request_id: "req-1".to_string(),
+                    method: "tools/list".to_string(),
+                    params: None,
+                    response_tx: Arc::new(Mutex::new(Some(tx))),
+                },
+            )
+            .await;
+
+        assert_eq!(handler.drain_queued_requests("missing").await, 1);
+        assert_eq!(
+            state.request_router.queued_request_count("missing").await,
+            0
+        );
+
+        // The server is not in the pool, so the waiter receives the forwarding error
+        let result = rx.await.unwrap();
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_listener_closes_when_draining_begins() {
+        let config = crate::config::test_config_with(
+            json!({}),
+            json!({ "proxy": { "host": "127.0.0.1", "port": 0 } }),
+        );
+        let (state, _) = crate::state::AppState::new(config);
+        let server = tokio::spawn(ProxyServer::new(state.clone()).run());
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!server.is_finished());
+
+        // Draining alone closes the listener; shutdown has not been signalled
+        state.drain.start();
+        let stopped = tokio::time::timeout(std::time::Duration::from_secs(5), server).await;
+        assert!(stopped.expect("listener still open").unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_calls_to_server_in_maintenance_are_rejected() {
+        use std::sync::Arc;
+
+        let config = test_config(json!({
+            "github": {
+                "command": "github",
+                "args": [],
+                "transport": { "type": "stdio" }
+            }
+        }));
+        let (state, _) = crate::state::AppState::new(config);
+        let handler = RequestHandler::new(state.clone());
+        let router = Arc::new(RequestRouter::new());
+
+        assert!(state
+            .maintenance
+            .enter("github", Some("upgrading".to_string())));
+        let generation = state.maintenance.generation();
+        assert!(!state.maintenance.enter("github", None));
+        assert_eq!(state.maintenance.generation(), generation);
+
+        let response = handler
+            .handle_request(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 7,
+                    "method": "tools/call",
+                    "params": { "name": "mcp__proxy__github__search", "arguments": {} }
+                }),
+                router,
+                &RequestContext::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.id, Some(json!(7)));
+        let error = response.error.unwrap();
+        assert_eq!(error.code, SERVER_UNAVAILABLE_CODE);
+        let data = error.data.unwrap();
+        assert_eq!(data["server"], "github");
+        assert_eq!(data["reason"], "upgrading");
+        assert_eq!(data["retryable"], true);
+
+        assert!(state.maintenance.exit("github").is_some());
+        assert!(state.maintenance.generation() > generation);
+    }
+
+    #[tokio::test]
+    async fn test_calls_beyond_client_quota_are_rejected() {
+        use std::sync::Arc;
+
+        let config = crate::config::test_config_with(
+            json!({}),
+            json!({ "clients": { "ci": { "apiKey": "secret", "quota": { "dailyCalls": 1 } } } }),
+        );
+        let (state, _) = crate::state::AppState::new(config);
+        let handler = RequestHandler::new(state.clone());
+        let ctx = RequestContext {
+            client: Some(ClientIdentity {
+                id: "ci".to_string(),
+                role: Default::default(),
+            }),
+            ..Default::default()
+        };
+        let call = || {
+            handler.handle_request(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "tools/call",
+                    "params": { "name": "mcp__proxy__github__search", "arguments": {} }
+                }),
+                Arc::new(RequestRouter::new()),
+                &ctx,
+            )
+        };
+
+        // Failed backend calls still count against the quota
+        let first = call().await.unwrap_err();
+        assert!(matches!(
+            first,
+            crate::error::ProxyError::Pool(crate::error::PoolError::ServerNotFound(_))
+        ));
+        assert_eq!(MCPError::from(&first).code, SERVER_NOT_READY_CODE);
+
+        let error = call().await.unwrap().error.unwrap();
+        assert_eq!(error.code, QUOTA_EXCEEDED_CODE);
+        let data = error.data.unwrap();
+        assert_eq!(data["client"], "ci");
+        assert_eq!(data["retryable"], false);
+
+        // Anonymous requests have no quota, so fail as the first call did
+        let anonymous = handler
+            .handle_request(
+                json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": { "name": "mcp__proxy__github__search" } }),
+                Arc::new(RequestRouter::new()),
+                &RequestContext::default(),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(MCPError::from(&anonymous).code, SERVER_NOT_READY_CODE);
+    }
+
+    #[tokio::test]
+    async fn test_server_availability_changes_notify_clients() {
+        let config = test_config(json!({}));
+        let (state, _) = crate::state::AppState::new(config);
+        state
+            .register_server(
+                "github".to_string(),
+                crate::state::ServerInfo::new("github".to_string()),
+            )
+            .await;
+        let mut notifications = state.client_notification_tx.subscribe();
+        let generation = state.catalog_generation();
+
+        // Starting up changes nothing clients can see
+        state
+            .set_server_state("github", crate::state::ServerState::Starting)
+            .await
+            .unwrap();
+        assert!(notifications.try_recv().is_err());
+        assert_eq!(state.catalog_generation(), generation);
+
+        state
+            .set_server_state("github", crate::state::ServerState::Running)
+            .await
+            .unwrap();
+        let methods: Vec<_> = std::iter::from_fn(|| notifications.try_recv().ok())
+            .map(|notification| notification["method"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(methods, LIST_CHANGED_NOTIFICATIONS);
+        assert!(state.catalog_generation() > generation);
+
+        state
+            .set_server_state("github", crate::state::ServerState::Failed)
+            .await
+            .unwrap();
+        assert_eq!(
+            notifications.try_recv().unwrap()["method"],
+            "notifications/tools/list_changed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_initialize_advertises_running_server_capabilities() {
+        let config = test_config(json!({}));
+        let (state, _) = crate::state::AppState::new(config);
+        let handler = RequestHandler::new(state.clone());
+        state
+            .register_server(
+                "github".to_string(),
+                crate::state::ServerInfo::new("github".to_string()),
+            )
+            .await;
+        state.server_versions.insert(
+            "github".to_string(),
+            crate::state::ServerVersion {
+                protocol_version: "2025-03-26".to_string(),
+                capabilities: json!({ "tools": {}, "logging": {} }),
+                detected_at: chrono::Utc::now(),
+            },
+        );
+        let initialize = || async {
+            handler
+                .handle_request(
+                    json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+                    Arc::new(RequestRouter::new()),
+                    &RequestContext::default(),
+                )
+                .await
+                .unwrap()
+                .result
+                .unwrap()["capabilities"]
+                .clone()
+        };
+
+        // Capabilities of a server that isn't up aren't offered
+        let capabilities = initialize().await;
+        assert_eq!(capabilities["tools"]["listChanged"], true);
+        assert!(capabilities.get("logging").is_none());
+
+        state
+            .set_server_state("github", crate::state::ServerState::Running)
+            .await
+            .unwrap();
+        assert_eq!(initialize().await["logging"], json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_status_overview_reports_recent_errors() {
+        use std::sync::Arc;
+
+        let config = test_config(json!({}));
+        let (state, _) = crate::state::AppState::new(config);
+        let info = crate::state::ServerInfo::new("github".to_string());
+        for (level, message) in [("error", "rate limited"), ("info", "ready")] {
+            info.log_buffer.push(crate::state::LogEntry {
+                timestamp: chrono::Utc::now(),
+                level: level.to_string(),
+                message: message.to_string(),
+            });
+        }
+        state.servers.insert("github".to_string(), info);
+        let handler = RequestHandler::new(state.clone());
+
+        let response = handler
+            .handle_request(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "tools/call",
+                    "params": { "name": "mcp__proxy__status__overview", "arguments": {} }
+                }),
+                Arc::new(RequestRouter::new()),
+                &RequestContext::default(),
+            )
+            .await
+            .unwrap();
+
+        let overview = &response.result.unwrap()["structuredContent"];
+        let server = &overview["servers"][0];
+        assert_eq!(server["name"], "github");
+        assert_eq!(server["recent_errors"].as_array().unwrap().len(), 1);
+        assert_eq!(server["recent_errors"][0]["message"], "rate limited");
+        assert!(server["latency"].is_null());
+        assert_eq!(overview["draining"], false);
+    }
+
+    #[tokio::test]
+    async fn test_calls_beyond_concurrency_limit_are_rejected() {
+        use std::sync::Arc;
+
+        let config = test_config(json!({
+            "browser": {
+                "command": "browser-mcp",
+                "args": [],
+                "transport": { "type": "stdio" },
+                "maxConcurrentCalls": 1,
+                "excessCalls": "reject"
+            }
+        }));
+        let (state, _) = crate::state::AppState::new(config);
+        let handler = RequestHandler::new(state.clone());
+        let ctx = RequestContext::default();
+        let call = || {
+            handler.handle_request(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "tools/call",
+                    "params": { "name": "mcp__proxy__browser__navigate", "arguments": {} }
+                }),
+                Arc::new(RequestRouter::new()),
+                &ctx,
+            )
+        };
+
+        // Another call holds the only slot
+        let running = state
+            .call_limits
+            .slots("browser", 1, None)
+            .try_acquire()
+            .unwrap();
+        let error = call().await.unwrap().error.unwrap();
+        assert_eq!(error.code, SERVER_UNAVAILABLE_CODE);
+        assert_eq!(error.data.unwrap()["retryable"], true);
+
+        // Once it finishes the call gets through to the (missing) backend
+        drop(running);
+        assert!(matches!(
+            call().await,
+            Err(crate::error::ProxyError::Pool(_))
+        ));
+    }
+
+    #[test]
+    fn test_error_taxonomy() {
+        use crate::error::TransportError;
+
+        let cases = [
+            (ProxyError::Timeout, TIMEOUT_CODE, "timeout", true),
+            (
+                ProxyError::Transport(TransportError::Closed),
+                TRANSPORT_CLOSED_CODE,
+                "transport_closed",
+                true,
+            ),
+            (
+                ProxyError::ServerNotReady("github".to_string()),
+                SERVER_NOT_READY_CODE,
+                "server_not_ready",
+                true,
+            ),
+            (
+                ProxyError::ServerDisabled {
+                    server: "github".to_string(),
+                    reason: "quarantined".to_string(),
+                },
+                CIRCUIT_OPEN_CODE,
+                "circuit_open",
+                false,
+            ),
+            (
+                ProxyError::Unauthorized("needs admin".to_string()),
+                POLICY_DENIED_CODE,
+                "policy_denied",
+                false,
+            ),
+            (
+                ProxyError::ResponseTooLarge {
+                    server: "files".to_string(),
+                    size: 2048,
+                    limit: 1024,
+                },
+                RESPONSE_TOO_LARGE_CODE,
+                "response_too_large",
+                false,
+            ),
+            (ProxyError::InvalidResponse, -32603, "internal", false),
+        ];
+        for (error, code, category, retryable) in cases {
+            let error = MCPError::from(&error);
+            assert_eq!(error.code, code);
+            let data = error.data.unwrap();
+            assert_eq!(data["category"], category);
+            assert_eq!(data["retryable"], retryable);
+        }
+
+        // The server a request was routed to is reported with any failure
+        let ctx = RequestContext::default();
+        ctx.set_server("github");
+        let data = MCPResponse::error(None, &ProxyError::Timeout)
+            .with_context(&ctx)
+            .error
+            .unwrap()
+            .data
+            .unwrap();
+        assert_eq!(data["server"], "github");
+        assert_eq!(data["correlation_id"], ctx.correlation_id.as_str());
+    }
+
+    #[test]
+    fn test_error_responses_carry_correlation_id() {
+        let ctx = RequestContext::default();
+        assert_ne!(ctx.correlation_id, RequestContext::default().correlation_id);
+
+        let error = ProxyError::ServerUnavailable {
+            server: "github".to_string(),
+            reason: "quarantined".to_string(),
+        };
+        let response =
+            MCPResponse::error(Some(json!(3)), &error).with_correlation_id(&ctx.correlation_id);
+        let data = response.error.unwrap().data.unwrap();
+        assert_eq!(data["correlation_id"], ctx.correlation_id.as_str());
+        assert_eq!(data["retryable"], true);
+
+        let response = MCPResponse::error(None, &ProxyError::Timeout).with_correlation_id("c-1");
+        assert_eq!(
+            response.error.unwrap().data,
+            Some(json!({ "correlation_id": "c-1", "category": "timeout", "retryable": true }))
+        );
+
+        let success = MCPResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(4)),
+            result: Some(json!({})),
+            error: None,
+        }
+        .with_correlation_id("c-2");
+        assert!(success.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_static_catalog_lists_but_cannot_call() {
+        use std::sync::Arc;
+
+        let config = test_config(json!({}));
+        let (state, _) = crate::state::AppState::new(config);
+        state.serve_catalog(Arc::new(crate::catalog::Catalog::from_lists(
+            &json!({ "tools": [{ "name": "mcp__proxy__db__query", "inputSchema": { "type": "object" } }] }),
+            &json!({}),
+            &json!({}),
+            &json!({}),
+        )));
+        let handler = RequestHandler::new(state);
+        let ctx = RequestContext::default();
+        let request = |method: &str| {
+            handler.handle_request(
+                json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": { "name": "mcp__proxy__db__query" } }),
+                Arc::new(RequestRouter::new()),
+                &ctx,
+            )
+        };
+
+        let listed = request("tools/list").await.unwrap().result.unwrap();
+        assert_eq!(listed["tools"][0]["name"], "mcp__proxy__db__query");
+        assert_eq!(listed["tools"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            request("prompts/list").await.unwrap().result,
+            Some(json!({ "prompts": [] }))
+        );
+
+        let called = request("tools/call").await.unwrap();
+        assert!(called.result.is_none());
+        assert_eq!(called.error.unwrap().code, -32601);
+        assert!(request("initialize").await.unwrap().result.is_some());
+    }
 }
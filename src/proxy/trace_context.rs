@@ -0,0 +1,163 @@
+//! W3C Trace Context across the proxy.
+//!
+//! A request's trace is taken from `params._meta.traceparent` or, failing
+//! that, the HTTP `traceparent` header. The proxy joins the trace with a span
+//! of its own, recorded on the request's tracing span, and hands it on in
+//! `_meta` of tool calls to servers with `propagateTraceContext` set.
+
+use axum::http::HeaderMap;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+/// The trace a request belongs to, from the proxy's point of view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: String,
+    /// The proxy's span for the request; the parent of backend spans
+    pub span_id: String,
+    /// The caller's span
+    pub parent_id: String,
+    pub flags: u8,
+    pub tracestate: Option<String>,
+}
+
+impl TraceContext {
+    /// Join the trace of a `traceparent` value with a new span. `None` when
+    /// the value is malformed, in which case the trace is not continued.
+    pub fn continue_from(traceparent: &str, tracestate: Option<&str>) -> Option<Self> {
+        let traceparent = traceparent.trim();
+        let fields: Vec<&str> = traceparent.splitn(5, '-').collect();
+        let [version, trace_id, parent_id, flags, ..] = fields[..] else {
+            return None;
+        };
+        let is_hex = |text: &str, len: usize| {
+            text.len() == len
+                && text
+                    .bytes()
+                    .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        };
+        let valid = is_hex(version, 2)
+            && version != "ff"
+            // Version 00 has exactly four fields; later ones may add more
+            && (version != "00" || fields.len() == 4)
+            && is_hex(trace_id, 32)
+            && trace_id.bytes().any(|b| b != b'0')
+            && is_hex(parent_id, 16)
+            && parent_id.bytes().any(|b| b != b'0')
+            && is_hex(flags, 2);
+        if !valid {
+            return None;
+        }
+
+        Some(Self {
+            trace_id: trace_id.to_string(),
+            span_id: new_span_id(),
+            parent_id: parent_id.to_string(),
+            flags: u8::from_str_radix(flags, 16).ok()?,
+            tracestate: tracestate
+                .map(str::trim)
+                .filter(|state| !state.is_empty())
+                .map(str::to_string),
+        })
+    }
+
+    /// The trace in the `traceparent` and `tracestate` headers
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        Self::continue_from(header("traceparent")?, header("tracestate"))
+    }
+
+    /// The trace in `params._meta` of a JSON-RPC request
+    pub fn from_request(body: &[u8]) -> Option<Self> {
+        #[derive(Deserialize)]
+        struct Request {
+            params: Option<Params>,
+        }
+        #[derive(Deserialize)]
+        struct Params {
+            #[serde(rename = "_meta")]
+            meta: Option<Meta>,
+        }
+        #[derive(Deserialize)]
+        struct Meta {
+            traceparent: Option<String>,
+            tracestate: Option<String>,
+        }
+
+        let meta = serde_json::from_slice::<Request>(body).ok()?.params?.meta?;
+        Self::continue_from(meta.traceparent.as_deref()?, meta.tracestate.as_deref())
+    }
+
+    /// `traceparent` naming the proxy's span as the parent
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-{:02x}", self.trace_id, self.span_id, self.flags)
+    }
+
+    /// `_meta` entries that hand the trace on to a server
+    pub fn meta(&self) -> Map<String, Value> {
+        let mut meta = Map::new();
+        meta.insert("traceparent".to_string(), json!(self.traceparent()));
+        if let Some(tracestate) = &self.tracestate {
+            meta.insert("tracestate".to_string(), json!(tracestate));
+        }
+        meta
+    }
+}
+
+fn new_span_id() -> String {
+    // Random and, with overwhelming probability, not the invalid all-zero id
+    let id = uuid::Uuid::new_v4().as_u128() as u64;
+    format!("{:016x}", id.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn test_continues_trace_with_new_span() {
+        let trace = TraceContext::continue_from(TRACEPARENT, Some("vendor=abc")).unwrap();
+        assert_eq!(trace.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(trace.parent_id, "00f067aa0ba902b7");
+        assert_ne!(trace.span_id, trace.parent_id);
+        assert_eq!(
+            trace.traceparent(),
+            format!("00-4bf92f3577b34da6a3ce929d0e0e4736-{}-01", trace.span_id)
+        );
+        assert_eq!(trace.meta()["tracestate"], "vendor=abc");
+
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{{"name":"x","_meta":{{"traceparent":"{TRACEPARENT}"}}}}}}"#
+        );
+        let trace = TraceContext::from_request(body.as_bytes()).unwrap();
+        assert_eq!(trace.parent_id, "00f067aa0ba902b7");
+        assert!(!trace.meta().contains_key("tracestate"));
+        assert!(TraceContext::from_request(br#"{"method":"tools/list"}"#).is_none());
+    }
+
+    #[test]
+    fn test_rejects_malformed_traceparent() {
+        for traceparent in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+        ] {
+            assert!(
+                TraceContext::continue_from(traceparent, None).is_none(),
+                "{traceparent}"
+            );
+        }
+        // Later versions may append fields
+        assert!(TraceContext::continue_from(
+            "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            None
+        )
+        .is_some());
+    }
+}
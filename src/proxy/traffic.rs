@@ -0,0 +1,174 @@
+//! Summaries of proxied requests for the dashboard's live traffic view
+
+use super::{RequestContext, RequestHandler, RequestRouter};
+use crate::state::AppState;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Payloads larger than this, serialized, are cut down to a preview
+const MAX_PAYLOAD_BYTES: usize = 16 * 1024;
+
+/// One request and how it went, with secrets redacted from its payloads
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficEvent {
+    pub correlation_id: String,
+    pub timestamp: DateTime<Utc>,
+    /// The authenticated client, when clients are configured
+    pub client: Option<String>,
+    pub method: String,
+    /// The tool called, as the server knows it
+    pub tool: Option<String>,
+    pub server: Option<String>,
+    pub duration_ms: u64,
+    pub ok: bool,
+    pub error: Option<String>,
+    /// The request's `params`
+    pub request: Value,
+    /// The response's `result` or `error`
+    pub response: Value,
+}
+
+/// Send a summary of the request to dashboard clients watching traffic.
+/// Nothing is parsed when no one is watching.
+pub(crate) fn publish(
+    state: &AppState,
+    handler: &RequestHandler,
+    router: &RequestRouter,
+    ctx: &RequestContext,
+    request: &[u8],
+    reply: &[u8],
+    duration: Duration,
+) {
+    if state.traffic_tx.receiver_count() == 0 {
+        return;
+    }
+    // Batches and unparseable bodies are left out
+    let Ok(Value::Object(mut request)) = serde_json::from_slice::<Value>(request) else {
+        return;
+    };
+    let reply: Value = serde_json::from_slice(reply).unwrap_or(Value::Null);
+
+    let method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let params = request.remove("params").unwrap_or(Value::Null);
+    let (server, tool) = match params.get("name").and_then(Value::as_str) {
+        Some(name) if method == "tools/call" => match handler.resolve_tool(name, router) {
+            Ok((server, tool)) => (Some(server), Some(tool)),
+            Err(_) => (None, Some(name.to_string())),
+        },
+        _ => (None, None),
+    };
+    let error = reply.get("error").filter(|error| !error.is_null());
+
+    let _ = state.traffic_tx.send(TrafficEvent {
+        correlation_id: ctx.correlation_id.clone(),
+        timestamp: Utc::now(),
+        client: ctx.client.as_ref().map(|client| client.id.clone()),
+        method,
+        tool,
+        server,
+        duration_ms: duration.as_millis() as u64,
+        ok: error.is_none(),
+        error: error
+            .and_then(|error| error.get("message"))
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        request: sanitized(params),
+        response: sanitized(
+            error
+                .or_else(|| reply.get("result"))
+                .cloned()
+                .unwrap_or(Value::Null),
+        ),
+    });
+}
+
+/// The payload with secrets redacted, and cut to a preview when it is large
+fn sanitized(mut payload: Value) -> Value {
+    super::resources::redact(&mut payload);
+    let text = payload.to_string();
+    if text.len() <= MAX_PAYLOAD_BYTES {
+        return payload;
+    }
+    let mut end = MAX_PAYLOAD_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    json!({ "truncated": text.len(), "preview": &text[..end] })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+
+    #[tokio::test]
+    async fn test_published_summary() {
+        let config = test_config(json!({}));
+        let (state, _) = AppState::new(config);
+        let handler = RequestHandler::new(state.clone());
+        let router = RequestRouter::new();
+        let ctx = RequestContext::default();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {
+                "name": "mcp__proxy__git_hub__create_issue",
+                "arguments": { "title": "Flaky test", "apiToken": "ghp_secret" }
+            }
+        });
+        let reply = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": { "code": -32603, "message": "rate limited" }
+        });
+
+        // Without subscribers nothing is sent
+        publish(
+            &state,
+            &handler,
+            &router,
+            &ctx,
+            request.to_string().as_bytes(),
+            reply.to_string().as_bytes(),
+            Duration::from_millis(40),
+        );
+
+        let mut traffic = state.traffic_tx.subscribe();
+        publish(
+            &state,
+            &handler,
+            &router,
+            &ctx,
+            request.to_string().as_bytes(),
+            reply.to_string().as_bytes(),
+            Duration::from_millis(40),
+        );
+        let event = traffic.try_recv().unwrap();
+        assert!(traffic.try_recv().is_err());
+        assert_eq!(event.correlation_id, ctx.correlation_id);
+        assert_eq!(event.method, "tools/call");
+        assert_eq!(event.server.as_deref(), Some("git-hub"));
+        assert_eq!(event.tool.as_deref(), Some("create_issue"));
+        assert_eq!(event.duration_ms, 40);
+        assert!(!event.ok);
+        assert_eq!(event.error.as_deref(), Some("rate limited"));
+        assert_eq!(event.request["arguments"]["apiToken"], "redacted");
+        assert_eq!(event.request["arguments"]["title"], "Flaky test");
+        assert_eq!(event.response["code"], -32603);
+    }
+
+    #[test]
+    fn test_large_payloads_are_truncated() {
+        let payload = sanitized(json!({ "text": "é".repeat(MAX_PAYLOAD_BYTES) }));
+        assert!(payload["truncated"].as_u64().unwrap() > MAX_PAYLOAD_BYTES as u64);
+        assert!(payload["preview"].as_str().unwrap().len() <= MAX_PAYLOAD_BYTES);
+    }
+}
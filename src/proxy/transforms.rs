@@ -0,0 +1,74 @@
+//! Declarative request transforms: argument defaults, overrides and removals,
+//! and static `_meta` entries configured per server and tool, applied without
+//! a plugin
+
+use super::router::glob_matches;
+use crate::config::RequestTransform;
+use serde_json::{Map, Value};
+
+/// Apply the rules matching `tool` to a call's arguments, returning the
+/// `_meta` entries they add
+pub fn apply(
+    transforms: &[RequestTransform],
+    tool: &str,
+    arguments: &mut Value,
+) -> Map<String, Value> {
+    let mut meta = Map::new();
+    for transform in transforms.iter().filter(|transform| {
+        transform
+            .tool
+            .as_deref()
+            .is_none_or(|pattern| glob_matches(pattern, tool))
+    }) {
+        if arguments.is_null() {
+            *arguments = Value::Object(Map::new());
+        }
+        let Some(args) = arguments.as_object_mut() else {
+            continue;
+        };
+        for name in &transform.drop {
+            args.remove(name);
+        }
+        for (name, value) in &transform.defaults {
+            args.entry(name.clone()).or_insert_with(|| value.clone());
+        }
+        for (name, value) in &transform.set {
+            args.insert(name.clone(), value.clone());
+        }
+        meta.extend(transform.meta.clone());
+    }
+    meta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_transforms() {
+        let transforms: Vec<RequestTransform> = serde_json::from_value(json!([
+            {
+                "tool": "search_*",
+                "drop": ["debug"],
+                "defaults": { "perPage": 20, "sort": "updated" },
+                "set": { "owner": "acme" }
+            },
+            { "meta": { "tenant": "acme" } }
+        ]))
+        .unwrap();
+
+        let mut arguments = json!({ "q": "bug", "sort": "stars", "owner": "evil", "debug": true });
+        let meta = apply(&transforms, "search_issues", &mut arguments);
+        assert_eq!(
+            arguments,
+            json!({ "q": "bug", "sort": "stars", "owner": "acme", "perPage": 20 })
+        );
+        assert_eq!(Value::Object(meta), json!({ "tenant": "acme" }));
+
+        // Only the rule without a tool pattern applies to other tools
+        let mut arguments = json!({ "debug": true });
+        apply(&transforms, "get_issue", &mut arguments);
+        assert_eq!(arguments, json!({ "debug": true }));
+    }
+}
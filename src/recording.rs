@@ -0,0 +1,316 @@
+//! Recording MCP sessions and replaying them against backends
+//!
+//! With `--record FILE` the proxy writes every JSON-RPC message it exchanges,
+//! with clients and with backend servers, to FILE as one JSON object per line
+//! in the order they happened. `mcp-rust-proxy replay FILE` sends the
+//! recorded backend requests to the servers as currently configured and
+//! reports where their responses differ from the recorded ones.
+
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    ClientRequest,
+    ClientResponse,
+    BackendRequest,
+    BackendResponse,
+}
+
+/// One line of a recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedMessage {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub direction: Direction,
+    /// The backend server, for backend messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Backend messages sent and received over the same connection lease
+    /// share an exchange number, which pairs requests with their responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exchange: Option<u64>,
+    /// The client request, for client messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// The message, or the text sent when it was not JSON
+    pub message: Value,
+}
+
+struct Writer {
+    seq: u64,
+    out: BufWriter<File>,
+}
+
+/// Writes the messages of a session to a recording file
+pub struct SessionRecorder {
+    writer: Mutex<Writer>,
+    exchanges: AtomicU64,
+}
+
+impl SessionRecorder {
+    /// Start a recording at `path`, replacing any file there
+    pub fn create(path: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: Mutex::new(Writer {
+                seq: 0,
+                out: BufWriter::new(File::create(path)?),
+            }),
+            exchanges: AtomicU64::new(0),
+        })
+    }
+
+    /// A new exchange number for a backend connection lease
+    pub fn next_exchange(&self) -> u64 {
+        self.exchanges.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn client(&self, direction: Direction, correlation_id: &str, message: &[u8]) {
+        self.record(direction, None, None, Some(correlation_id), message);
+    }
+
+    pub fn backend(&self, direction: Direction, server: &str, exchange: u64, message: &[u8]) {
+        self.record(direction, Some(server), Some(exchange), None, message);
+    }
+
+    fn record(
+        &self,
+        direction: Direction,
+        server: Option<&str>,
+        exchange: Option<u64>,
+        correlation_id: Option<&str>,
+        message: &[u8],
+    ) {
+        let message = serde_json::from_slice(message).unwrap_or_else(|_| {
+            Value::String(String::from_utf8_lossy(message).trim_end().to_string())
+        });
+        let mut writer = self.writer.lock().unwrap();
+        writer.seq += 1;
+        let entry = RecordedMessage {
+            seq: writer.seq,
+            timestamp: Utc::now(),
+            direction,
+            server: server.map(str::to_string),
+            exchange,
+            correlation_id: correlation_id.map(str::to_string),
+            message,
+        };
+
+        // A recording that cannot be written must not fail the request
+        let written = serde_json::to_writer(&mut writer.out, &entry)
+            .map_err(std::io::Error::from)
+            .and_then(|()| writer.out.write_all(b"\n"))
+            .and_then(|()| writer.out.flush());
+        if let Err(e) = written {
+            tracing::warn!("Failed to record session message: {}", e);
+        }
+    }
+}
+
+/// Read the messages of a recording, in the order they were recorded
+pub fn read_session(path: &Path) -> Result<Vec<RecordedMessage>> {
+    let mut messages = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            messages.push(serde_json::from_str(&line)?);
+        }
+    }
+    messages.sort_by_key(|message: &RecordedMessage| message.seq);
+    Ok(messages)
+}
+
+/// A recorded backend request and the response it got
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackendExchange {
+    pub server: String,
+    pub request: Value,
+    /// `None` when the session ended before the response arrived
+    pub response: Option<Value>,
+}
+
+impl BackendExchange {
+    /// The request's method, and the tool for tool calls
+    pub fn label(&self) -> String {
+        let method = self.request["method"].as_str().unwrap_or("?");
+        match self.request["params"]["name"].as_str() {
+            Some(name) if method == "tools/call" => format!("{method} {name}"),
+            _ => method.to_string(),
+        }
+    }
+}
+
+/// The backend requests of a session in order, each paired with its
+/// response. Notifications, which get no response, are left out.
+pub fn backend_exchanges(messages: &[RecordedMessage]) -> Vec<BackendExchange> {
+    let mut exchanges: Vec<BackendExchange> = Vec::new();
+    // Unanswered requests by exchange number, oldest first
+    let mut pending: HashMap<u64, Vec<usize>> = HashMap::new();
+
+    for message in messages {
+        let (Some(server), Some(exchange)) = (&message.server, message.exchange) else {
+            continue;
+        };
+        match message.direction {
+            Direction::BackendRequest if message.message.get("id").is_some() => {
+                pending.entry(exchange).or_default().push(exchanges.len());
+                exchanges.push(BackendExchange {
+                    server: server.clone(),
+                    request: message.message.clone(),
+                    response: None,
+                });
+            }
+            Direction::BackendResponse => {
+                let waiting = pending.entry(exchange).or_default();
+                if !waiting.is_empty() {
+                    let index = waiting.remove(0);
+                    exchanges[index].response = Some(message.message.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    exchanges
+}
+
+/// Where `actual` differs from `expected`, one `path: expected -> actual`
+/// line per difference. JSON-RPC envelope fields (`id`, `jsonrpc`) are
+/// ignored at the top level.
+pub fn diff(expected: &Value, actual: &Value) -> Vec<String> {
+    let strip = |value: &Value| {
+        let mut value = value.clone();
+        if let Some(object) = value.as_object_mut() {
+            object.remove("id");
+            object.remove("jsonrpc");
+        }
+        value
+    };
+    let mut differences = Vec::new();
+    diff_at("$", &strip(expected), &strip(actual), &mut differences);
+    differences
+}
+
+fn diff_at(path: &str, expected: &Value, actual: &Value, differences: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            let added = actual.keys().filter(|key| !expected.contains_key(*key));
+            for key in expected.keys().chain(added) {
+                let path = format!("{path}.{key}");
+                match (expected.get(key), actual.get(key)) {
+                    (Some(before), Some(after)) => diff_at(&path, before, after, differences),
+                    (Some(before), None) => {
+                        differences.push(format!("{path}: {before} -> missing"))
+                    }
+                    (None, Some(after)) => differences.push(format!("{path}: missing -> {after}")),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            for index in 0..expected.len().max(actual.len()) {
+                let path = format!("{path}[{index}]");
+                match (expected.get(index), actual.get(index)) {
+                    (Some(before), Some(after)) => diff_at(&path, before, after, differences),
+                    (Some(before), None) => {
+                        differences.push(format!("{path}: {before} -> missing"))
+                    }
+                    (None, Some(after)) => differences.push(format!("{path}: missing -> {after}")),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if expected != actual => differences.push(format!("{path}: {expected} -> {actual}")),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_recording_pairs_backend_exchanges() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let recorder = SessionRecorder::create(&path).unwrap();
+
+        let call = br#"{"jsonrpc":"2.0","method":"tools/call","params":{"name":"search","arguments":{}},"id":1}"#;
+        recorder.client(Direction::ClientRequest, "c-1", call);
+        let (first, second) = (recorder.next_exchange(), recorder.next_exchange());
+        recorder.backend(Direction::BackendRequest, "github", first, call);
+        recorder.backend(
+            Direction::BackendRequest,
+            "files",
+            second,
+            br#"{"jsonrpc":"2.0","method":"resources/read","params":{"uri":"file:///a"},"id":1}"#,
+        );
+        recorder.backend(
+            Direction::BackendRequest,
+            "files",
+            second,
+            br#"{"jsonrpc":"2.0","method":"notifications/cancelled"}"#,
+        );
+        recorder.backend(
+            Direction::BackendResponse,
+            "github",
+            first,
+            b"{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{\"hits\":3}}\n",
+        );
+        recorder.client(Direction::ClientResponse, "c-1", b"not json\n");
+        drop(recorder);
+
+        let session = read_session(&path).unwrap();
+        assert_eq!(session.len(), 6);
+        assert_eq!(session[0].correlation_id.as_deref(), Some("c-1"));
+        assert_eq!(session[5].message, json!("not json"));
+
+        let exchanges = backend_exchanges(&session);
+        assert_eq!(exchanges.len(), 2);
+        assert_eq!(exchanges[0].server, "github");
+        assert_eq!(exchanges[0].label(), "tools/call search");
+        assert_eq!(exchanges[0].response.as_ref().unwrap()["result"]["hits"], 3);
+        assert_eq!(exchanges[1].label(), "resources/read");
+        assert_eq!(exchanges[1].response, None);
+    }
+
+    #[test]
+    fn test_diff_reports_changed_paths() {
+        let recorded = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": { "content": [{ "type": "text", "text": "3 hits" }], "isError": false }
+        });
+        let mut replayed = recorded.clone();
+        replayed["id"] = json!(9);
+        assert!(diff(&recorded, &replayed).is_empty());
+
+        replayed["result"]["content"][0]["text"] = json!("4 hits");
+        replayed["result"]["content"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "type": "text", "text": "more" }));
+        replayed["result"]
+            .as_object_mut()
+            .unwrap()
+            .remove("isError");
+        assert_eq!(
+            diff(&recorded, &replayed),
+            vec![
+                r#"$.result.content[0].text: "3 hits" -> "4 hits""#.to_string(),
+                r#"$.result.content[1]: missing -> {"text":"more","type":"text"}"#.to_string(),
+                "$.result.isError: false -> missing".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,540 @@
+//! Finding servers in the MCP registry and adding them to the configuration
+//!
+//! `mcp-rust-proxy search <query>` lists matching servers and
+//! `mcp-rust-proxy add <name>` turns a server's package into a server entry
+//! (a `node` or `python` runtime for npm and PyPI packages, `docker run` for
+//! OCI images) and appends it to the configuration file.
+
+use crate::config::{Runtime, ServerConfig, TransportConfig};
+use crate::error::{ConfigError, ProxyError, Result, TransportError};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const DEFAULT_REGISTRY_URL: &str = "https://registry.modelcontextprotocol.io";
+
+/// A server as the registry describes it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryServer {
+    /// Registry name, e.g. `io.github.owner/server`
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub packages: Vec<Package>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Package {
+    /// `npm`, `pypi` or `oci`
+    #[serde(alias = "registry_name")]
+    pub registry_type: String,
+    #[serde(alias = "name")]
+    pub identifier: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default, alias = "package_arguments")]
+    pub package_arguments: Vec<PackageArgument>,
+    #[serde(default, alias = "environment_variables")]
+    pub environment_variables: Vec<EnvironmentVariable>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageArgument {
+    /// `positional` or `named`
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    /// Flag of a named argument, e.g. `--port`
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default, alias = "is_required")]
+    pub is_required: bool,
+    #[serde(default, alias = "value_hint")]
+    pub value_hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentVariable {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default, alias = "is_required")]
+    pub is_required: bool,
+}
+
+/// The registry wraps each server with its metadata; older registries list
+/// servers bare
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ListedServer {
+    Wrapped { server: RegistryServer },
+    Bare(RegistryServer),
+}
+
+#[derive(Deserialize)]
+struct ServerList {
+    servers: Vec<ListedServer>,
+}
+
+pub struct RegistryClient {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl RegistryClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Servers whose name matches `query`
+    pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<RegistryServer>> {
+        let response = self
+            .client
+            .get(format!("{}/v0/servers", self.url))
+            .query(&[("search", query), ("limit", &limit.to_string())])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| TransportError::ConnectionFailed(format!("registry: {e}")))?;
+        let list: ServerList = response
+            .json()
+            .await
+            .map_err(|e| ProxyError::Parse(format!("registry response: {e}")))?;
+        Ok(list
+            .servers
+            .into_iter()
+            .map(|entry| match entry {
+                ListedServer::Wrapped { server } | ListedServer::Bare(server) => server,
+            })
+            .collect())
+    }
+
+    /// The server with exactly this registry name
+    pub async fn find(&self, name: &str) -> Result<RegistryServer> {
+        self.search(name, 100)
+            .await?
+            .into_iter()
+            .find(|server| server.name == name)
+            .ok_or_else(|| ProxyError::ServerNotFound(format!("{name} in the registry")))
+    }
+}
+
+/// A `servers` entry holding only what differs from the defaults, with its
+/// fields in the order they are written
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerEntry {
+    pub command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<Runtime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package_version: Option<String>,
+    pub args: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    pub transport: TransportConfig,
+}
+
+/// A server entry generated from a registry server
+#[derive(Debug, Clone)]
+pub struct Install {
+    /// Name for the entry under `servers`
+    pub name: String,
+    pub server: ServerEntry,
+    /// What the user has to do before the server can run
+    pub notes: Vec<String>,
+}
+
+/// Generate a server entry running the first package the proxy knows how
+/// to launch
+pub fn install(server: &RegistryServer) -> Result<Install> {
+    let Some((package, mut entry)) = server
+        .packages
+        .iter()
+        .find_map(|package| Some((package, launch(package)?)))
+    else {
+        return Err(ConfigError::Validation(format!(
+            "{} has no npm, PyPI or OCI package to run",
+            server.name
+        ))
+        .into());
+    };
+    let mut notes = Vec::new();
+    for argument in &package.package_arguments {
+        let value = argument.value.as_ref().or(argument.default.as_ref());
+        match (argument.kind.as_str(), &argument.name, value) {
+            ("named", Some(flag), Some(value)) => entry.args.extend([flag.clone(), value.clone()]),
+            ("named", Some(_), None) if !argument.is_required => {}
+            (_, _, Some(value)) => entry.args.push(value.clone()),
+            (_, name, None) if argument.is_required => notes.push(format!(
+                "add the required argument {} to args",
+                name.as_deref()
+                    .or(argument.value_hint.as_deref())
+                    .unwrap_or("(unnamed)")
+            )),
+            _ => {}
+        }
+    }
+
+    for variable in &package.environment_variables {
+        if variable.is_required {
+            entry
+                .env
+                .insert(variable.name.clone(), format!("${{{}}}", variable.name));
+            notes.push(match &variable.description {
+                Some(description) => format!("set {}: {}", variable.name, description),
+                None => format!("set {}", variable.name),
+            });
+        }
+    }
+
+    // The entry must load like a hand-written one
+    serde_json::from_value::<ServerConfig>(serde_json::to_value(&entry)?)?;
+
+    Ok(Install {
+        name: entry_name(&server.name),
+        server: entry,
+        notes,
+    })
+}
+
+/// An entry running the package, without its arguments and environment, or
+/// `None` for packages the proxy can't run
+fn launch(package: &Package) -> Option<ServerEntry> {
+    let (command, runtime, package_version, args) = match package.registry_type.as_str() {
+        "npm" => (
+            package.identifier.clone(),
+            Some(Runtime::Node),
+            package.version.clone(),
+            Vec::new(),
+        ),
+        "pypi" => (
+            package.identifier.clone(),
+            Some(Runtime::Python),
+            package.version.clone(),
+            Vec::new(),
+        ),
+        "oci" | "docker" => ("docker".to_string(), None, None, docker_args(package)),
+        _ => return None,
+    };
+    Some(ServerEntry {
+        command,
+        runtime,
+        package_version,
+        args,
+        env: BTreeMap::new(),
+        transport: TransportConfig::Stdio,
+    })
+}
+
+fn docker_args(package: &Package) -> Vec<String> {
+    let tagged = package
+        .identifier
+        .rsplit('/')
+        .next()
+        .is_some_and(|image| image.contains(':'));
+    let image = match &package.version {
+        Some(version) if !tagged => format!("{}:{}", package.identifier, version),
+        _ => package.identifier.clone(),
+    };
+    let mut args: Vec<String> = ["run", "-i", "--rm"].map(String::from).to_vec();
+    // The container only sees variables named with -e
+    for variable in package
+        .environment_variables
+        .iter()
+        .filter(|v| v.is_required)
+    {
+        args.extend(["-e".to_string(), variable.name.clone()]);
+    }
+    args.push(image);
+    args
+}
+
+/// `io.github.owner/weather-server` becomes `weather-server`
+fn entry_name(registry_name: &str) -> String {
+    let last = registry_name.rsplit('/').next().unwrap_or(registry_name);
+    let name: String = last
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    name.trim_matches('-').to_string()
+}
+
+/// Add a server entry to the configuration file at `path`, creating the
+/// file if needed. TOML and YAML files are appended to as text so comments
+/// survive; JSON files are rewritten.
+pub fn append_server(path: &Path, name: &str, server: &ServerEntry) -> Result<()> {
+    let existing = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("yaml");
+    let updated = match extension {
+        "toml" => append_toml(&existing, name, server)?,
+        "json" => append_json(&existing, name, server)?,
+        _ => append_yaml(&existing, name, server)?,
+    };
+    std::fs::write(path, updated)?;
+    Ok(())
+}
+
+fn already_configured(name: &str) -> ProxyError {
+    ConfigError::Validation(format!("Server '{name}' is already configured")).into()
+}
+
+fn append_toml(existing: &str, name: &str, server: &ServerEntry) -> Result<String> {
+    let document: toml::Table = existing
+        .parse()
+        .map_err(|e| ConfigError::Parse(format!("{e}")))?;
+    if document
+        .get("servers")
+        .and_then(|servers| servers.get(name))
+        .is_some()
+    {
+        return Err(already_configured(name));
+    }
+    let table = toml::to_string(&BTreeMap::from([(
+        "servers",
+        BTreeMap::from([(name, server)]),
+    )]))
+    .map_err(|e| ConfigError::Parse(e.to_string()))?;
+    let mut updated = existing.to_string();
+    if !updated.is_empty() {
+        if !updated.ends_with('\n') {
+            updated.push('\n');
+        }
+        updated.push('\n');
+    }
+    updated.push_str(&table);
+    Ok(updated)
+}
+
+fn append_json(existing: &str, name: &str, server: &ServerEntry) -> Result<String> {
+    let mut document: Value = if existing.trim().is_empty() {
+        json!({})
+    } else {
+        serde_json::from_str(existing)?
+    };
+    let servers = document
+        .as_object_mut()
+        .ok_or_else(|| ConfigError::Parse("configuration is not an object".to_string()))?
+        .entry("servers")
+        .or_insert_with(|| json!({}));
+    let servers = servers
+        .as_object_mut()
+        .ok_or_else(|| ConfigError::Parse("servers is not an object".to_string()))?;
+    if servers.contains_key(name) {
+        return Err(already_configured(name));
+    }
+    servers.insert(name.to_string(), serde_json::to_value(server)?);
+    Ok(serde_json::to_string_pretty(&document)? + "\n")
+}
+
+fn append_yaml(existing: &str, name: &str, server: &ServerEntry) -> Result<String> {
+    let document: serde_yaml::Value = if existing.trim().is_empty() {
+        serde_yaml::Value::Null
+    } else {
+        serde_yaml::from_str(existing).map_err(|e| ConfigError::Parse(e.to_string()))?
+    };
+    let servers = document.get("servers");
+    if servers.and_then(|servers| servers.get(name)).is_some() {
+        return Err(already_configured(name));
+    }
+    let entry = serde_yaml::to_string(&BTreeMap::from([(name, server)]))
+        .map_err(|e| ConfigError::Parse(e.to_string()))?;
+
+    let lines: Vec<&str> = existing.lines().collect();
+    let servers_line = lines.iter().position(|line| {
+        line.strip_prefix("servers:")
+            .is_some_and(|rest| rest.trim().is_empty() || rest.trim_start().starts_with('#'))
+    });
+    let Some(servers_line) = servers_line else {
+        if servers.is_some() {
+            // e.g. `servers: {}`, which text can't be added under
+            return Err(ConfigError::Validation(
+                "servers must be a block mapping to add to it; add the entry by hand".to_string(),
+            )
+            .into());
+        }
+        let mut updated = existing.to_string();
+        if !updated.is_empty() && !updated.ends_with('\n') {
+            updated.push('\n');
+        }
+        updated.push_str("servers:\n");
+        updated.push_str(&indent(&entry, "  "));
+        return Ok(updated);
+    };
+
+    // Indent like the entries already there
+    let indentation = lines[servers_line + 1..]
+        .iter()
+        .find(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .filter(|indentation| !indentation.is_empty())
+        .unwrap_or("  ");
+    let mut updated: Vec<String> = lines[..=servers_line]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    updated.push(indent(&entry, indentation).trim_end().to_string());
+    updated.extend(
+        lines[servers_line + 1..]
+            .iter()
+            .map(|line| line.to_string()),
+    );
+    Ok(updated.join("\n") + "\n")
+}
+
+fn indent(text: &str, indentation: &str) -> String {
+    text.lines()
+        .map(|line| format!("{indentation}{line}\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weather() -> RegistryServer {
+        serde_json::from_value::<ServerList>(json!({
+            "servers": [{
+                "server": {
+                    "name": "io.github.example/Weather.Server",
+                    "description": "Forecasts",
+                    "version": "1.2.0",
+                    "packages": [
+                        { "registryType": "nuget", "identifier": "Weather" },
+                        {
+                            "registryType": "npm",
+                            "identifier": "@example/weather",
+                            "version": "1.2.0",
+                            "packageArguments": [
+                                { "type": "named", "name": "--units", "default": "metric" },
+                                { "type": "positional", "valueHint": "region", "isRequired": true }
+                            ],
+                            "environmentVariables": [
+                                { "name": "WEATHER_API_KEY", "description": "API key", "isRequired": true },
+                                { "name": "WEATHER_DEBUG" }
+                            ]
+                        }
+                    ]
+                },
+                "_meta": {}
+            }]
+        }))
+        .map(|list| match list.servers.into_iter().next().unwrap() {
+            ListedServer::Wrapped { server } | ListedServer::Bare(server) => server,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_install_generates_launch_command() {
+        let generated = install(&weather()).unwrap();
+        assert_eq!(generated.name, "weather-server");
+        assert_eq!(
+            serde_json::to_value(&generated.server).unwrap(),
+            json!({
+                "command": "@example/weather",
+                "runtime": "node",
+                "packageVersion": "1.2.0",
+                "args": ["--units", "metric"],
+                "transport": { "type": "stdio" },
+                "env": { "WEATHER_API_KEY": "${WEATHER_API_KEY}" }
+            })
+        );
+        assert_eq!(
+            generated.notes,
+            [
+                "add the required argument region to args",
+                "set WEATHER_API_KEY: API key"
+            ]
+        );
+
+        let mut server = weather();
+        server.packages = vec![Package {
+            registry_type: "oci".to_string(),
+            identifier: "docker.io/example/weather".to_string(),
+            version: Some("1.2.0".to_string()),
+            package_arguments: Vec::new(),
+            environment_variables: server.packages[1].environment_variables.clone(),
+        }];
+        assert_eq!(
+            install(&server).unwrap().server.args,
+            [
+                "run",
+                "-i",
+                "--rm",
+                "-e",
+                "WEATHER_API_KEY",
+                "docker.io/example/weather:1.2.0"
+            ]
+        );
+
+        server.packages.clear();
+        assert!(install(&server).is_err());
+    }
+
+    #[test]
+    fn test_append_server_keeps_existing_text() {
+        let server = ServerEntry {
+            command: "weather".to_string(),
+            runtime: Some(Runtime::Python),
+            package_version: None,
+            args: Vec::new(),
+            env: BTreeMap::new(),
+            transport: TransportConfig::Stdio,
+        };
+
+        let yaml = "# Team servers\nservers:\n    github:\n        command: gh-mcp # pinned\n        args: []\n        transport:\n            type: stdio\nproxy:\n    port: 3000\nwebUi:\n    enabled: false\n";
+        let updated = append_yaml(yaml, "weather", &server).unwrap();
+        assert!(updated.starts_with("# Team servers\nservers:\n    weather:\n"));
+        assert!(updated.contains("command: gh-mcp # pinned"));
+        let config: crate::config::Config = serde_yaml::from_str(&updated).unwrap();
+        assert_eq!(config.servers["weather"].runtime, Some(Runtime::Python));
+        assert_eq!(config.servers["github"].command, "gh-mcp");
+        assert!(append_yaml(&updated, "weather", &server).is_err());
+        assert!(append_yaml("servers: {}\n", "weather", &server).is_err());
+        let created = append_yaml("", "weather", &server).unwrap();
+        assert!(created.starts_with("servers:\n  weather:\n"));
+
+        let toml = "# Team servers\n[proxy]\nport = 3000\n";
+        let updated = append_toml(toml, "weather", &server).unwrap();
+        assert!(updated.starts_with(toml));
+        let document: toml::Table = updated.parse().unwrap();
+        assert_eq!(
+            document["servers"]["weather"]["runtime"].as_str(),
+            Some("python")
+        );
+        assert!(append_toml(&updated, "weather", &server).is_err());
+
+        let updated = append_json(r#"{ "proxy": { "port": 3000 } }"#, "weather", &server).unwrap();
+        let document: Value = serde_json::from_str(&updated).unwrap();
+        assert_eq!(
+            document["servers"]["weather"],
+            serde_json::to_value(&server).unwrap()
+        );
+        assert_eq!(document["proxy"]["port"], 3000);
+    }
+}
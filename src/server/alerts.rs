@@ -0,0 +1,151 @@
+//! Alert rule evaluation, and delivery of alerts to Slack and Discord
+//! webhooks
+
+use crate::config::{NotifierConfig, NotifierKind};
+use crate::state::{AlertEvent, AppState};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Send each alert to the configured notifiers until shutdown. Notifiers are
+/// read from the current config for every alert, so reloads take effect.
+pub async fn run_notifiers(
+    state: Arc<AppState>,
+    mut alert_rx: broadcast::Receiver<AlertEvent>,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) {
+    let client = match reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!(
+                "Failed to create webhook client, alerts will not be sent: {}",
+                e
+            );
+            return;
+        }
+    };
+
+    loop {
+        let alert = tokio::select! {
+            alert = alert_rx.recv() => match alert {
+                Ok(alert) => alert,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("Dropped {} alerts that arrived faster than they were sent", missed);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            _ = shutdown_rx.recv() => break,
+        };
+
+        let notifiers = state.config.read().await.alerts.notifiers.clone();
+        for notifier in notifiers.into_iter().filter(|n| wants(n, &alert)) {
+            let client = client.clone();
+            let body = payload(&notifier, &alert);
+            // A slow webhook must not hold up the others
+            tokio::spawn(async move {
+                let result = client
+                    .post(&notifier.webhook_url)
+                    .json(&body)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+                if let Err(e) = result {
+                    tracing::warn!(
+                        "Failed to send alert to {:?} webhook: {}",
+                        notifier.kind,
+                        e.without_url()
+                    );
+                }
+            });
+        }
+    }
+}
+
+/// Evaluate the configured alert rules every evaluation interval until
+/// shutdown, raising an alert whenever a rule starts or stops firing
+pub async fn run_alert_rules(state: Arc<AppState>, mut shutdown_rx: broadcast::Receiver<()>) {
+    let interval = state.config.read().await.alerts.evaluation_interval();
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown_rx.recv() => break,
+        }
+
+        let rules = state.config.read().await.alerts.rules.clone();
+        let stats = state.connection_pool.stats();
+        for transition in
+            state
+                .alert_rules
+                .evaluate(&rules, &stats, &state.metrics_history, chrono::Utc::now())
+        {
+            tracing::warn!("{}", transition.message);
+            state.raise_alert(&transition.server, transition.kind, transition.message);
+        }
+    }
+}
+
+fn wants(notifier: &NotifierConfig, alert: &AlertEvent) -> bool {
+    notifier.events.is_empty() || notifier.events.contains(&alert.kind)
+}
+
+/// The webhook body for an alert
+fn payload(notifier: &NotifierConfig, alert: &AlertEvent) -> Value {
+    let text = notifier
+        .template
+        .replace("{server}", &alert.server)
+        .replace("{kind}", alert.kind.as_str())
+        .replace("{timestamp}", &alert.timestamp.to_rfc3339())
+        .replace("{message}", &alert.message);
+
+    match notifier.kind {
+        NotifierKind::Slack => json!({ "text": text }),
+        NotifierKind::Discord => json!({ "content": text }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::AlertKind;
+    use chrono::Utc;
+
+    fn notifier(kind: NotifierKind, template: &str, events: Vec<AlertKind>) -> NotifierConfig {
+        NotifierConfig {
+            kind,
+            webhook_url: "https://hooks.example.com/T000/B000".to_string(),
+            template: template.to_string(),
+            events,
+        }
+    }
+
+    #[test]
+    fn test_payload_renders_template() {
+        let alert = AlertEvent {
+            server: "github".to_string(),
+            kind: AlertKind::RestartLimitExceeded,
+            message: "Server github exceeded its maximum of 3 restarts".to_string(),
+            timestamp: Utc::now(),
+        };
+
+        let slack = notifier(NotifierKind::Slack, "[{kind}] {server}: {message}", vec![]);
+        assert_eq!(
+            payload(&slack, &alert),
+            json!({"text": "[restart_limit_exceeded] github: Server github exceeded its maximum of 3 restarts"})
+        );
+
+        let discord = notifier(
+            NotifierKind::Discord,
+            "{message}",
+            vec![AlertKind::Quarantined],
+        );
+        assert_eq!(payload(&discord, &alert)["content"], json!(alert.message));
+        assert!(!wants(&discord, &alert));
+        assert!(wants(&slack, &alert));
+    }
+}
@@ -39,6 +39,11 @@ impl HealthChecker {
         let timeout_duration = Duration::from_secs(effective_config.timeout_seconds);
         let max_attempts = effective_config.max_attempts;
         let retry_interval = Duration::from_secs(effective_config.retry_interval_seconds);
+        // The backend may have moved to another address since we connected
+        let reresolve = config
+            .servers
+            .get(&self.server_name)
+            .is_some_and(|server| server.is_network() && server.dns.reresolve_on_health_failure);
         drop(config);
 
         let mut interval = interval(interval_duration);
@@ -100,6 +105,16 @@ impl HealthChecker {
                 attempt += 1;
             }
 
+            if !check_passed && reresolve {
+                match self.reresolve(timeout_duration).await {
+                    Ok(elapsed) => {
+                        check_passed = true;
+                        response_time_ms = Some(elapsed.as_millis() as u64);
+                    }
+                    Err(e) => last_error = Some(e),
+                }
+            }
+
             // Update health check status
             if let Some(server_info) = self.state.servers.get(&self.server_name) {
                 let mut health_status = server_info.last_health_check.write().await;
@@ -132,6 +147,33 @@ impl HealthChecker {
         }
     }
 
+    /// Run a single health check, returning the response time on success
+    pub async fn check_once(&self, timeout_duration: Duration) -> Result<Duration, HealthError> {
+        let start_time = Instant::now();
+        match timeout(timeout_duration, self.check_health()).await {
+            Ok(Ok(())) => Ok(start_time.elapsed()),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(HealthError::Timeout),
+        }
+    }
+
+    /// Reconnect, resolving the server's address again, and check it once
+    /// more
+    async fn reresolve(&self, timeout_duration: Duration) -> Result<Duration, String> {
+        tracing::info!(
+            "Reconnecting to {} to re-resolve its address after a failed health check",
+            self.server_name
+        );
+        self.state
+            .connection_pool
+            .reconnect(&self.server_name)
+            .await
+            .map_err(|e| format!("Reconnect failed: {e}"))?;
+        self.check_once(timeout_duration)
+            .await
+            .map_err(|e| format!("Health check failed after reconnecting: {e}"))
+    }
+
     async fn check_health(&self) -> Result<(), HealthError> {
         // Get connection from pool
         let conn = self
@@ -139,6 +139,16 @@ mod tests {
                 connection_pool_size: 10,
                 request_timeout_ms: 5000,
                 max_concurrent_requests: 100,
+                drain_timeout_ms: 30000,
+                reuse_port: false,
+                compression: true,
+                max_body_bytes: 4 * 1024 * 1024,
+                max_json_depth: 64,
+                max_json_elements: 100_000,
+                allowed_ips: vec![],
+                connection_reap_interval_ms: 30000,
+                token_estimates: false,
+                draft_protocol: false,
             },
             web_ui: WebUIConfig {
                 enabled: false,
@@ -146,6 +156,8 @@ mod tests {
                 port: 8081,
                 static_dir: None,
                 api_key: None,
+                allowed_ips: vec![],
+                cors: Default::default(),
             },
             health_check: HealthCheckConfig {
                 enabled: true,
@@ -157,6 +169,20 @@ mod tests {
             servers: std::collections::HashMap::new(),
             context_tracing: Default::default(),
             plugins: None,
+            cache_warmer: Default::default(),
+            quarantine: Default::default(),
+            alerts: Default::default(),
+            shared_state: None,
+            clients: std::collections::HashMap::new(),
+            readiness: Default::default(),
+            metrics_history: Default::default(),
+            metrics_persistence: Default::default(),
+            routing: Default::default(),
+            dead_letter: Default::default(),
+            prompts: Vec::new(),
+            blobs: Default::default(),
+            ephemeral: false,
+            proxy_url: None,
         }
     }
 
@@ -366,10 +392,12 @@ mod tests {
                     state: Arc::new(tokio::sync::RwLock::new(crate::state::ServerState::Running)),
                     process_handle: None,
                     restart_count: Arc::new(tokio::sync::RwLock::new(0)),
+                    restart_times: Arc::new(tokio::sync::RwLock::new(Default::default())),
                     last_health_check: Arc::new(tokio::sync::RwLock::new(None)),
                     last_access_time: Arc::new(tokio::sync::RwLock::new(None)),
                     log_subscribers: Arc::new(dashmap::DashMap::new()),
                     logger: None,
+                    log_buffer: Arc::new(crate::state::LogBuffer::default()),
                     connection_state: None, // T021: No connection state for this test
                 },
             )
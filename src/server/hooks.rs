@@ -0,0 +1,184 @@
+//! Commands run on server lifecycle events
+//!
+//! Hooks let deployments do things around a server without writing a
+//! plugin: mount credentials before it starts, warm an external cache once
+//! it is ready, or tell a deploy system it failed. Each hook gets the event
+//! in `MCP_PROXY_*` environment variables.
+
+use crate::config::{HookCommand, ServerConfig};
+use crate::state::{AppState, ServerState};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    Start,
+    Ready,
+    Crash,
+    Stop,
+}
+
+impl HookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookEvent::Start => "start",
+            HookEvent::Ready => "ready",
+            HookEvent::Crash => "crash",
+            HookEvent::Stop => "stop",
+        }
+    }
+
+    /// The event entering `state` is. Starting has none here: `onStart`
+    /// runs before the server starts, not after.
+    fn of(state: ServerState) -> Option<Self> {
+        match state {
+            ServerState::Running => Some(HookEvent::Ready),
+            ServerState::Failed => Some(HookEvent::Crash),
+            ServerState::Stopped => Some(HookEvent::Stop),
+            ServerState::Starting | ServerState::Stopping => None,
+        }
+    }
+
+    fn hook(self, config: &ServerConfig) -> Option<&HookCommand> {
+        let hooks = &config.hooks;
+        match self {
+            HookEvent::Start => hooks.on_start.as_ref(),
+            HookEvent::Ready => hooks.on_ready.as_ref(),
+            HookEvent::Crash => hooks.on_crash.as_ref(),
+            HookEvent::Stop => hooks.on_stop.as_ref(),
+        }
+    }
+}
+
+/// Run the server's hook for `event`, if it has one, and wait for it
+pub async fn run(server: &str, config: &ServerConfig, event: HookEvent) -> Result<(), String> {
+    let Some(hook) = event.hook(config) else {
+        return Ok(());
+    };
+    let mut command = Command::new(&hook.command);
+    command
+        .args(&hook.args)
+        .env("MCP_PROXY_SERVER", server)
+        .env("MCP_PROXY_EVENT", event.as_str())
+        .env("MCP_PROXY_TIMESTAMP", chrono::Utc::now().to_rfc3339())
+        .env("MCP_PROXY_TRANSPORT", config.transport.name())
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+    if matches!(config.transport, crate::config::TransportConfig::Stdio) {
+        let (program, args) = crate::transport::runtime::launch_command(config);
+        command.env(
+            "MCP_PROXY_COMMAND",
+            std::iter::once(program)
+                .chain(args)
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+    }
+    if let Some(dir) = &config.working_directory {
+        command.current_dir(dir);
+    }
+
+    tracing::info!("Running {} hook for {}", event.as_str(), server);
+    let output = tokio::time::timeout(Duration::from_millis(hook.timeout_ms), command.output())
+        .await
+        .map_err(|_| format!("'{}' timed out", hook.command))?
+        .map_err(|e| format!("cannot run '{}': {e}", hook.command))?;
+    if !output.status.success() {
+        return Err(format!(
+            "'{}' exited with {}: {}",
+            hook.command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    tracing::debug!(
+        "{} hook for {} printed: {}",
+        event.as_str(),
+        server,
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+    Ok(())
+}
+
+/// Run the ready, crash and stop hooks of servers as they change state,
+/// until shutdown. Hooks are read from the current config, so reloads take
+/// effect, and run in the background so a slow one holds up nothing.
+pub async fn run_state_hooks(state: Arc<AppState>, mut shutdown_rx: broadcast::Receiver<()>) {
+    let mut state_rx = state.server_state_tx.subscribe();
+    loop {
+        let (server, new_state) = tokio::select! {
+            event = state_rx.recv() => match event {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!("Missed {} server state changes; their hooks did not run", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = shutdown_rx.recv() => break,
+        };
+        let Some(event) = HookEvent::of(new_state) else {
+            continue;
+        };
+        let Some(config) = state.config.read().await.servers.get(&server).cloned() else {
+            continue;
+        };
+        if event.hook(&config).is_none() {
+            continue;
+        }
+        tokio::spawn(async move {
+            if let Err(e) = run(&server, &config, event).await {
+                tracing::warn!("{} hook for {} failed: {}", event.as_str(), server, e);
+            }
+        });
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn server(hooks: serde_json::Value) -> ServerConfig {
+        serde_json::from_value(serde_json::json!({
+            "command": "db-server",
+            "args": ["--readonly"],
+            "transport": { "type": "stdio" },
+            "hooks": hooks
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_hooks_see_the_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("event");
+        let config = server(serde_json::json!({
+            "onReady": {
+                "command": "sh",
+                "args": ["-c", format!(
+                    "echo \"$MCP_PROXY_SERVER $MCP_PROXY_EVENT $MCP_PROXY_TRANSPORT $MCP_PROXY_COMMAND\" > {}",
+                    out.display()
+                )]
+            },
+            "onStart": { "command": "sh", "args": ["-c", "echo no vault >&2; exit 3"] },
+            "onStop": { "command": "sleep", "args": ["5"], "timeoutMs": 50 }
+        }));
+
+        run("db", &config, HookEvent::Ready).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "db ready stdio db-server --readonly\n"
+        );
+
+        let error = run("db", &config, HookEvent::Start).await.unwrap_err();
+        assert!(error.ends_with("no vault"), "{error}");
+        assert!(run("db", &config, HookEvent::Stop)
+            .await
+            .unwrap_err()
+            .contains("timed out"));
+        // Events without a hook do nothing
+        run("db", &config, HookEvent::Crash).await.unwrap();
+    }
+}
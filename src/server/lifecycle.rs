@@ -2,23 +2,64 @@ use super::ManagedServer;
 use crate::error::Result;
 use crate::logging::ServerLogger;
 use crate::state::{AppState, ServerInfo, ServerState};
+use serde::Serialize;
 use std::sync::Arc;
+use tokio::time::Duration;
+
+/// Result of restarting servers one at a time
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollingRestartReport {
+    /// Servers that restarted and passed their readiness and health checks
+    pub restarted: Vec<String>,
+    /// Server whose restart failed, halting the rollout
+    pub failed: Option<String>,
+    pub error: Option<String>,
+    /// Servers left untouched because the rollout halted
+    pub skipped: Vec<String>,
+}
 
 pub struct ServerManager {
     state: Arc<AppState>,
     shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+    started_tx: Option<tokio::sync::oneshot::Sender<()>>,
 }
 
 impl ServerManager {
     pub fn new(state: Arc<AppState>, shutdown_rx: tokio::sync::broadcast::Receiver<()>) -> Self {
-        Self { state, shutdown_rx }
+        Self {
+            state,
+            shutdown_rx,
+            started_tx: None,
+        }
+    }
+
+    /// Signal `started_tx` once every configured server has finished
+    /// starting, whether it came up or failed
+    pub fn notify_started(mut self, started_tx: tokio::sync::oneshot::Sender<()>) -> Self {
+        self.started_tx = Some(started_tx);
+        self
     }
 
     pub async fn run(mut self) -> Result<()> {
         tracing::info!("Server manager started");
+        tokio::spawn(super::hooks::run_state_hooks(
+            self.state.clone(),
+            self.state.shutdown_tx.subscribe(),
+        ));
+        tokio::spawn(super::quarantine::watch_respawns(
+            self.state.clone(),
+            self.state.shutdown_tx.subscribe(),
+        ));
 
         // Start all configured servers
-        self.start_all_servers().await?;
+        let starting = self.start_all_servers().await?;
+        if let Some(started_tx) = self.started_tx.take() {
+            tokio::spawn(async move {
+                futures::future::join_all(starting).await;
+                let _ = started_tx.send(());
+            });
+        }
 
         // Wait for shutdown signal
         let _ = self.shutdown_rx.recv().await;
@@ -30,26 +71,48 @@ impl ServerManager {
         Ok(())
     }
 
-    async fn start_all_servers(&self) -> Result<()> {
+    /// Start the configured servers in the background, returning the tasks
+    /// starting them
+    async fn start_all_servers(&self) -> Result<Vec<tokio::task::JoinHandle<()>>> {
+        let servers = self.state.config.read().await.servers.clone();
+        crate::transport::runtime::preinstall_packages(&servers).await;
+
         let config = self.state.config.read().await;
 
+        let mut starting = Vec::new();
         for (name, server_config) in &config.servers {
             // Create and register server info BEFORE creating ManagedServer
             let mut info = ServerInfo::new(name.clone());
-
-            // Create logger for this server
-            match ServerLogger::new(name.clone(), None).await {
-                Ok(logger) => {
-                    tracing::info!("Created logger for server: {}", name);
-                    info.set_logger(Arc::new(logger)).await;
-                }
-                Err(e) => {
-                    tracing::error!("Failed to create logger for server {}: {}", name, e);
+            info.set_log_buffer_capacity(server_config.log_buffer_lines);
+
+            // Create logger for this server; ephemeral proxies keep only the
+            // in-memory log buffer
+            if !config.ephemeral {
+                match ServerLogger::new(name.clone(), None).await {
+                    Ok(logger) => {
+                        tracing::info!("Created logger for server: {}", name);
+                        info.set_logger(Arc::new(logger)).await;
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to create logger for server {}: {}", name, e);
+                    }
                 }
             }
 
             self.state.register_server(name.clone(), info).await;
 
+            if let Some(disabled) = self.state.disabled_servers.get(name).await {
+                tracing::warn!("Not starting server {}: {}", name, disabled.reason);
+                continue;
+            }
+            if !super::schedule::is_available(server_config, &chrono::Local::now().naive_local()) {
+                tracing::info!(
+                    "Not starting server {}: outside its availability window",
+                    name
+                );
+                continue;
+            }
+
             let server =
                 ManagedServer::new(name.clone(), server_config.clone(), self.state.clone()).await?;
 
@@ -58,7 +121,7 @@ impl ServerManager {
             let server = Arc::new(server);
             let name = name.clone();
 
-            tokio::spawn(async move {
+            starting.push(tokio::spawn(async move {
                 if let Err(e) = server.start().await {
                     tracing::error!("Failed to start server {}: {}", name, e);
                     let _ = state.set_server_state(&name, ServerState::Failed).await;
@@ -73,40 +136,61 @@ impl ServerManager {
                         });
                     }
                 }
-            });
+            }));
         }
 
-        Ok(())
+        Ok(starting)
     }
 
     async fn stop_all_servers(&self) -> Result<()> {
-        let servers: Vec<_> = self
+        let running: Vec<String> = self
             .state
             .servers
             .iter()
             .map(|entry| entry.key().clone())
             .collect();
 
-        // Stop all servers in parallel
-        let mut stop_tasks = Vec::new();
-        for name in servers {
-            let state = self.state.clone();
-            let name_clone = name.clone();
+        // Stop dependents before the servers they rely on; servers within a
+        // wave are independent of each other and stop in parallel
+        let mut waves = match self.state.config.read().await.shutdown_order() {
+            Ok(waves) => waves,
+            Err(e) => {
+                tracing::warn!("Stopping all servers at once: {}", e);
+                vec![running.clone()]
+            }
+        };
+        for wave in &mut waves {
+            wave.retain(|name| running.contains(name));
+        }
+        // Servers missing from the config have no known dependencies
+        let unknown: Vec<String> = running
+            .iter()
+            .filter(|name| !waves.iter().any(|wave| wave.contains(name)))
+            .cloned()
+            .collect();
+        waves.insert(0, unknown);
 
-            stop_tasks.push(tokio::spawn(async move {
-                // First remove from connection pool to prevent new connections
-                state.connection_pool.remove(&name_clone);
+        for wave in waves.into_iter().filter(|wave| !wave.is_empty()) {
+            let mut stop_tasks = Vec::new();
+            for name in wave {
+                let state = self.state.clone();
 
-                // Then close existing connections
-                if let Err(e) = Self::force_stop_server(&state, &name_clone).await {
-                    tracing::error!("Failed to stop server {}: {}", name_clone, e);
-                }
-            }));
-        }
+                stop_tasks.push(tokio::spawn(async move {
+                    // Remove from the connection pool and close the connection
+                    if let Err(e) = state.connection_pool.close(&name).await {
+                        tracing::warn!("Failed to close connection to {}: {}", name, e);
+                    }
 
-        // Wait for all servers to stop
-        for task in stop_tasks {
-            let _ = task.await;
+                    if let Err(e) = Self::force_stop_server(&state, &name).await {
+                        tracing::error!("Failed to stop server {}: {}", name, e);
+                    }
+                }));
+            }
+
+            // Wait for the wave to stop before moving on to its dependencies
+            for task in stop_tasks {
+                let _ = task.await;
+            }
         }
 
         Ok(())
@@ -171,4 +255,115 @@ impl ServerManager {
         tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
         self.start_server(name).await
     }
+
+    /// Restart running servers one at a time, waiting for each to become Ready
+    /// and pass a health check before moving on. Halts at the first failure so
+    /// the remaining servers keep serving.
+    pub async fn rolling_restart(&self) -> RollingRestartReport {
+        let servers: Vec<(String, ServerInfo)> = self
+            .state
+            .servers
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        let mut names = Vec::new();
+        for (name, info) in servers {
+            if *info.state.read().await == ServerState::Running {
+                names.push(name);
+            }
+        }
+        names.sort();
+
+        let mut report = RollingRestartReport::default();
+        let mut remaining = names.into_iter();
+        for name in remaining.by_ref() {
+            tracing::info!("Rolling restart: restarting server {}", name);
+            match self.restart_and_verify(&name).await {
+                Ok(()) => report.restarted.push(name),
+                Err(e) => {
+                    tracing::error!("Rolling restart halted at server {}: {}", name, e);
+                    report.failed = Some(name);
+                    report.error = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+        report.skipped = remaining.collect();
+
+        report
+    }
+
+    /// Restart a server and wait until it is Ready and healthy
+    async fn restart_and_verify(&self, name: &str) -> Result<()> {
+        self.restart_server(name).await?;
+
+        let ready = match self.state.connection_states.get(name) {
+            Some(connection_state) => connection_state.clone().is_ready().await,
+            None => false,
+        };
+        if !ready {
+            return Err(crate::error::ProxyError::ServerNotReady(name.to_string()));
+        }
+
+        let health_check = self.state.config.read().await.get_server_health_check(name);
+        let Some(health_check) = health_check else {
+            // Health checks are disabled for this server; readiness is enough
+            return Ok(());
+        };
+
+        let checker = super::HealthChecker::new(name.to_string(), self.state.clone());
+        let timeout = Duration::from_secs(health_check.timeout_seconds);
+        let mut last_error = None;
+        for attempt in 0..health_check.max_attempts.max(1) {
+            if attempt > 0 {
+                tokio::time::sleep(Duration::from_secs(health_check.retry_interval_seconds)).await;
+            }
+            match checker.check_once(timeout).await {
+                Ok(_) => return Ok(()),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        tracing::warn!(
+            "Server {} failed health check after restart: {:?}",
+            name,
+            last_error
+        );
+        Err(crate::error::ServerError::HealthCheckFailed.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+
+    #[tokio::test]
+    async fn test_rolling_restart_halts_at_first_failure() {
+        let config = test_config(serde_json::json!({}));
+        let (state, _) = AppState::new(config);
+
+        // Running servers without a config entry cannot be restarted
+        for name in ["alpha", "beta"] {
+            state
+                .register_server(name.to_string(), ServerInfo::new(name.to_string()))
+                .await;
+            state
+                .set_server_state(name, ServerState::Running)
+                .await
+                .unwrap();
+        }
+        state
+            .register_server("gamma".to_string(), ServerInfo::new("gamma".to_string()))
+            .await;
+
+        let manager = ServerManager::new(state.clone(), state.shutdown_tx.subscribe());
+        let report = manager.rolling_restart().await;
+
+        assert!(report.restarted.is_empty());
+        assert_eq!(report.failed.as_deref(), Some("alpha"));
+        assert!(report.error.is_some());
+        // Only running servers take part in the rollout
+        assert_eq!(report.skipped, vec!["beta".to_string()]);
+    }
 }
@@ -1,15 +1,20 @@
-use crate::config::ServerConfig;
-use crate::error::{Result, ServerError};
-use crate::state::{AppState, ServerState};
+use crate::config::{InitializationTimeoutAction, ServerConfig};
+use crate::error::{ProxyError, Result, ServerError};
+use crate::state::{AlertKind, AppState, ServerState};
 use crate::transport::{create_transport, Transport};
 use std::sync::Arc;
-use tokio::time::{sleep, Duration};
+use tokio::time::{sleep, Duration, Instant};
 
+pub mod alerts;
 pub mod health;
+pub mod hooks;
 pub mod lifecycle;
+pub mod quarantine;
+pub mod schedule;
+pub mod startup_probe;
 
 pub use health::HealthChecker;
-pub use lifecycle::ServerManager;
+pub use lifecycle::{RollingRestartReport, ServerManager};
 
 pub struct ManagedServer {
     pub name: String,
@@ -27,7 +32,16 @@ impl ManagedServer {
             Arc::new(crate::state::ServerInfo::new(name.clone()))
         };
 
-        let transport = create_transport(&config.transport, &config, Some(server_info.clone()))?;
+        let proxy_url = {
+            let global = state.config.read().await;
+            global.server_proxy_url(&config).map(str::to_string)
+        };
+        let transport = create_transport(
+            &config.transport,
+            &config,
+            Some(server_info.clone()),
+            proxy_url.as_deref(),
+        )?;
 
         Ok(Self {
             name,
@@ -38,6 +52,10 @@ impl ManagedServer {
     }
 
     pub async fn start(&self) -> Result<()> {
+        if let Some(disabled) = self.state.disabled_servers.get(&self.name).await {
+            return Err(ServerError::Disabled(disabled.reason).into());
+        }
+
         tracing::info!("Starting server: {}", self.name);
 
         // Update state
@@ -45,26 +63,153 @@ impl ManagedServer {
             .set_server_state(&self.name, ServerState::Starting)
             .await?;
 
-        // Create transport and add to connection pool
-        match self
-            .state
+        // Pin the protocol version if the config overrides detection
+        let pinned_version = self
+            .config
+            .protocol_version
+            .as_deref()
+            .and_then(crate::protocol::ProtocolVersion::parse);
+        self.state
             .connection_pool
-            .add_server(self.name.clone(), self.transport.clone())
-            .await
-        {
-            Ok(_) => {
-                tracing::info!("Server {} started successfully", self.name);
-                self.state
-                    .set_server_state(&self.name, ServerState::Running)
-                    .await?;
-                Ok(())
+            .pin_protocol_version(&self.name, pinned_version);
+        self.state
+            .connection_pool
+            .set_initialization_timeout(&self.name, self.config.initialization_timeout());
+        self.state
+            .connection_pool
+            .set_max_connection_age(&self.name, self.config.dns.ttl());
+
+        if let Err(e) = hooks::run(&self.name, &self.config, hooks::HookEvent::Start).await {
+            return self
+                .start_failed(ServerError::StartFailed(format!("start hook failed: {e}")).into())
+                .await;
+        }
+
+        // Create transport and add to connection pool, then probe it while
+        // requests for it stay queued
+        if let Err(e) = self.connect().await {
+            return self.start_failed(e).await;
+        }
+        self.attach_connection_state();
+        if let Some(probe) = &self.config.startup_probe {
+            if let Err(e) = startup_probe::run(&self.state, &self.name, probe).await {
+                if let Err(close_error) = self.state.connection_pool.close(&self.name).await {
+                    tracing::warn!(
+                        "Error closing connection for {}: {}",
+                        self.name,
+                        close_error
+                    );
+                }
+                return self.start_failed(e).await;
             }
-            Err(e) => {
-                tracing::error!("Failed to start server {}: {}", self.name, e);
+        }
+
+        tracing::info!("Server {} started successfully", self.name);
+        if self.config.warm_spares > 0 {
+            tokio::spawn(
                 self.state
-                    .set_server_state(&self.name, ServerState::Failed)
-                    .await?;
-                Err(e)
+                    .connection_pool
+                    .clone()
+                    .keep_warm_spares(self.name.clone(), self.config.warm_spares),
+            );
+        }
+        self.state
+            .set_server_state(&self.name, ServerState::Running)
+            .await?;
+        self.drain_queued_requests();
+        Ok(())
+    }
+
+    async fn start_failed(&self, e: ProxyError) -> Result<()> {
+        tracing::error!("Failed to start server {}: {}", self.name, e);
+        self.fail_queued_requests(&e).await;
+        self.state
+            .set_server_state(&self.name, ServerState::Failed)
+            .await?;
+        Err(e)
+    }
+
+    /// Add the server to the connection pool, applying the configured
+    /// initialization timeout behavior when the handshake times out
+    async fn connect(&self) -> Result<()> {
+        crate::transport::runtime::verify(&self.config).map_err(ServerError::StartFailed)?;
+        let started_at = Instant::now();
+        let mut retries = 0;
+
+        loop {
+            let result = self
+                .state
+                .connection_pool
+                .add_server(self.name.clone(), self.transport.clone())
+                .await;
+
+            if !matches!(result, Err(ProxyError::Timeout)) {
+                return result;
+            }
+
+            match self.config.on_initialization_timeout {
+                InitializationTimeoutAction::MarkFailed => return result,
+                InitializationTimeoutAction::Retry { attempts } => {
+                    if retries >= attempts {
+                        return result;
+                    }
+                    retries += 1;
+                    tracing::warn!(
+                        "Initialization of {} timed out, retrying ({}/{})",
+                        self.name,
+                        retries,
+                        attempts
+                    );
+                }
+                InitializationTimeoutAction::KeepQueuing { max_queue_age_ms } => {
+                    if started_at.elapsed() >= Duration::from_millis(max_queue_age_ms) {
+                        return result;
+                    }
+                    tracing::warn!(
+                        "Initialization of {} timed out, retrying while requests stay queued",
+                        self.name
+                    );
+                }
+            }
+        }
+    }
+
+    /// Forward requests queued while the server was initializing now that it is Ready
+    fn drain_queued_requests(&self) {
+        let handler = crate::proxy::RequestHandler::new(self.state.clone());
+        let name = self.name.clone();
+
+        tokio::spawn(async move {
+            let drained = handler.drain_queued_requests(&name).await;
+            if drained > 0 {
+                tracing::info!("Forwarded {} queued request(s) to server {}", drained, name);
+            }
+        });
+    }
+
+    /// Resolve requests queued while the server was initializing with an error
+    async fn fail_queued_requests(&self, error: &ProxyError) {
+        let queued = self
+            .state
+            .request_router
+            .clear_queued_requests(&self.name)
+            .await;
+
+        for request in queued {
+            if let Some(tx) = request.response_tx.lock().await.take() {
+                let _ = tx.send(Err(format!(
+                    "Server {} failed to initialize: {}",
+                    self.name, error
+                )));
+            }
+        }
+    }
+
+    /// Expose the pool's connection state (version, adapter) through the server info
+    fn attach_connection_state(&self) {
+        if let Some(connection_state) = self.state.connection_states.get(&self.name) {
+            if let Some(mut info) = self.state.servers.get_mut(&self.name) {
+                info.connection_state = Some(connection_state.clone());
             }
         }
     }
@@ -77,8 +222,11 @@ impl ManagedServer {
             .set_server_state(&self.name, ServerState::Stopping)
             .await?;
 
-        // Remove from connection pool
-        self.state.connection_pool.remove(&self.name);
+        // Remove from the connection pool, stopping the process
+        if let Err(e) = self.state.connection_pool.close(&self.name).await {
+            tracing::warn!("Error closing connection for {}: {}", self.name, e);
+        }
+        self.state.request_router.unregister_server(&self.name);
 
         // Update state
         self.state
@@ -125,6 +273,14 @@ impl ManagedServer {
             self.state
                 .set_server_state(&self.name, ServerState::Failed)
                 .await?;
+            self.state.raise_alert(
+                &self.name,
+                AlertKind::RestartLimitExceeded,
+                format!(
+                    "Server {} exceeded its maximum of {} restarts and was left down",
+                    self.name, self.config.max_restarts
+                ),
+            );
             return Err(ServerError::Crashed(format!(
                 "Exceeded maximum restart attempts: {}",
                 self.config.max_restarts
@@ -138,6 +294,11 @@ impl ManagedServer {
         tracing::info!("Waiting {:?} before restarting server {}", delay, self.name);
         sleep(delay).await;
 
+        if let Err(e) = quarantine::record_restart(&self.state, &self.name).await {
+            self.stop().await?;
+            return Err(e);
+        }
+
         // Attempt restart
         match self.restart().await {
             Ok(_) => {
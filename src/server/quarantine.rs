@@ -0,0 +1,216 @@
+//! Automatic quarantine of servers that restart too often, and disabling
+//! servers by hand
+
+use crate::error::{Result, ServerError};
+use crate::state::{AlertKind, AppState, DisabledServer, ServerState};
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Record a restart of `name` after it failed. Restarts asked for by an
+/// operator, a rolling restart or a schedule are not recorded.
+///
+/// If the server has now restarted more than `quarantine.maxRestarts` times
+/// within the window it is quarantined, and an error is returned so the
+/// caller does not bring it back up.
+pub async fn record_restart(state: &Arc<AppState>, name: &str) -> Result<()> {
+    let Some(info) = state.servers.get(name).map(|entry| entry.value().clone()) else {
+        return Ok(());
+    };
+    let config = state.config.read().await.quarantine.clone();
+
+    *info.restart_count.write().await += 1;
+
+    let now = Instant::now();
+    let recent = {
+        let mut times = info.restart_times.write().await;
+        times.push_back(now);
+        while times
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > config.window())
+        {
+            times.pop_front();
+        }
+        times.len() as u32
+    };
+
+    if !config.enabled || recent <= config.max_restarts {
+        return Ok(());
+    }
+
+    let reason = format!(
+        "quarantined after restarting {} times in {} minutes",
+        recent, config.window_minutes
+    );
+    quarantine(state, name, &reason).await?;
+    Err(ServerError::Disabled(reason).into())
+}
+
+/// Record the respawns of servers whose connection failed, stopping those
+/// that get quarantined. Runs until shutdown.
+pub async fn watch_respawns(
+    state: Arc<AppState>,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    let mut respawns = state.connection_pool.subscribe_respawns();
+    loop {
+        let name = tokio::select! {
+            respawn = respawns.recv() => match respawn {
+                Ok(name) => name,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            _ = shutdown_rx.recv() => break,
+        };
+        if record_restart(&state, &name).await.is_ok() {
+            continue;
+        }
+        if let Err(e) = state.connection_pool.close(&name).await {
+            tracing::warn!("Error closing connection for {}: {}", name, e);
+        }
+        state.request_router.unregister_server(&name);
+        let _ = state.set_server_state(&name, ServerState::Stopped).await;
+    }
+}
+
+/// Disable a server, persist that, and raise an alert
+pub async fn quarantine(state: &Arc<AppState>, name: &str, reason: &str) -> Result<()> {
+    tracing::error!("Server {} {}", name, reason);
+    mark_disabled(state, name, reason, true).await?;
+
+    state.raise_alert(
+        name,
+        AlertKind::Quarantined,
+        format!("Server {name} {reason}"),
+    );
+
+    Ok(())
+}
+
+/// Disable a server at an operator's request and persist that, so it is not
+/// started again until re-enabled. Stopping it is up to the caller.
+pub async fn disable(state: &Arc<AppState>, name: &str, reason: &str) -> Result<()> {
+    tracing::info!("Server {} disabled: {}", name, reason);
+    mark_disabled(state, name, reason, false).await
+}
+
+async fn mark_disabled(
+    state: &Arc<AppState>,
+    name: &str,
+    reason: &str,
+    quarantined: bool,
+) -> Result<()> {
+    let was_enabled = state
+        .config
+        .read()
+        .await
+        .servers
+        .get(name)
+        .is_some_and(|server_config| server_config.enabled);
+    state
+        .disabled_servers
+        .disable(
+            name,
+            DisabledServer {
+                reason: reason.to_string(),
+                quarantined,
+                was_enabled,
+                disabled_at: Utc::now(),
+            },
+        )
+        .await?;
+
+    if let Some(server_config) = state.config.write().await.servers.get_mut(name) {
+        server_config.enabled = false;
+    }
+    Ok(())
+}
+
+/// Lift a server's quarantine and forget its restart history. Returns
+/// whether the server was disabled.
+pub async fn release(state: &Arc<AppState>, name: &str) -> Result<bool> {
+    let released = state.disabled_servers.enable(name).await?;
+
+    // Servers disabled in the config stay disabled
+    if released.as_ref().is_some_and(|entry| entry.was_enabled) {
+        if let Some(server_config) = state.config.write().await.servers.get_mut(name) {
+            server_config.enabled = true;
+        }
+    }
+    if let Some(info) = state.servers.get(name).map(|entry| entry.value().clone()) {
+        info.restart_times.write().await.clear();
+    }
+
+    if released.is_some() {
+        tracing::info!("Server {} released from quarantine", name);
+    }
+    Ok(released.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config_with;
+    use crate::state::ServerInfo;
+
+    #[tokio::test]
+    async fn test_flapping_server_is_quarantined() {
+        let config = test_config_with(
+            serde_json::json!({
+                "flaky": {
+                    "command": "flaky",
+                    "args": [],
+                    "transport": { "type": "stdio" }
+                }
+            }),
+            serde_json::json!({ "quarantine": { "maxRestarts": 2, "windowMinutes": 10 } }),
+        );
+        let (state, _shutdown_rx) = AppState::new(config);
+        state
+            .register_server("flaky".to_string(), ServerInfo::new("flaky".to_string()))
+            .await;
+        let mut alerts = state.alert_tx.subscribe();
+
+        assert!(record_restart(&state, "flaky").await.is_ok());
+        assert!(record_restart(&state, "flaky").await.is_ok());
+        assert!(record_restart(&state, "flaky").await.is_err());
+
+        let entry = state.disabled_servers.get("flaky").await.unwrap();
+        assert!(entry.quarantined);
+        assert!(!state.config.read().await.servers["flaky"].enabled);
+        assert_eq!(alerts.try_recv().unwrap().kind, AlertKind::Quarantined);
+
+        assert!(release(&state, "flaky").await.unwrap());
+        assert!(!state.disabled_servers.is_disabled("flaky").await);
+        assert!(state.config.read().await.servers["flaky"].enabled);
+        // The restart window starts over after release
+        assert!(record_restart(&state, "flaky").await.is_ok());
+
+        disable(&state, "flaky", "disabled from the web UI")
+            .await
+            .unwrap();
+        let entry = state.disabled_servers.get("flaky").await.unwrap();
+        assert!(!entry.quarantined);
+        assert!(!state.config.read().await.servers["flaky"].enabled);
+        assert!(alerts.try_recv().is_err());
+        assert!(release(&state, "flaky").await.unwrap());
+
+        // A server disabled in the config is not turned on by a release
+        state
+            .config
+            .write()
+            .await
+            .servers
+            .get_mut("flaky")
+            .unwrap()
+            .enabled = false;
+        assert!(!release(&state, "flaky").await.unwrap());
+        assert!(!state.config.read().await.servers["flaky"].enabled);
+        disable(&state, "flaky", "disabled from the web UI")
+            .await
+            .unwrap();
+        assert!(release(&state, "flaky").await.unwrap());
+        assert!(!state.config.read().await.servers["flaky"].enabled);
+    }
+}
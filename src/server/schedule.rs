@@ -0,0 +1,274 @@
+//! Scheduled restarts and availability windows
+//!
+//! Servers may set `restartCron` to be restarted at fixed times and
+//! `availableCron` to run only during the minutes it matches. Both are
+//! five-field cron expressions (minute, hour, day of month, month, day of
+//! week) in the proxy's local time, checked once a minute.
+
+use super::ServerManager;
+use crate::config::ServerConfig;
+use crate::state::{AppState, ServerState};
+use chrono::{Datelike, Local, NaiveDateTime, Timelike};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Whether the day fields were `*`; when both are restricted a day
+    /// matching either one matches, as in cron
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> std::result::Result<Self, String> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            expression => expression,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(format!(
+                "expected 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            ));
+        };
+
+        let mut days_of_week = parse_field(day_of_week, 0, 7)?;
+        // Both 0 and 7 are Sunday
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days_of_month: parse_field(day_of_month, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            days_of_week,
+            any_day_of_month: day_of_month.starts_with('*'),
+            any_day_of_week: day_of_week.starts_with('*'),
+        })
+    }
+
+    /// Whether the minute containing `at` matches
+    pub fn matches(&self, at: &NaiveDateTime) -> bool {
+        let bit = |mask: u64, value: u32| mask & (1 << value) != 0;
+        let day_of_month = bit(self.days_of_month, at.day());
+        let day_of_week = bit(self.days_of_week, at.weekday().num_days_from_sunday());
+        let day = match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        };
+
+        day && bit(self.minutes, at.minute())
+            && bit(self.hours, at.hour())
+            && bit(self.months, at.month())
+    }
+}
+
+/// Parse one field (`*`, `5`, `1-5`, `*/15`, `9-17/2` or a comma-separated
+/// list of those) into a bitmask of the values it matches
+fn parse_field(field: &str, min: u32, max: u32) -> std::result::Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step in '{part}'"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let value = |text: &str| {
+            text.parse::<u32>()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(|| format!("'{text}' is not a number from {min} to {max}"))
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/15` means from 5 to the end in steps of 15
+                None if step > 1 => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if start > end {
+            return Err(format!("range '{range}' is backwards"));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+/// Whether a server should be running at `at` according to its
+/// `availableCron`. Servers without one are always available.
+pub fn is_available(config: &ServerConfig, at: &NaiveDateTime) -> bool {
+    config
+        .available_cron
+        .as_deref()
+        .and_then(|expression| CronSchedule::parse(expression).ok())
+        .is_none_or(|window| window.matches(at))
+}
+
+/// Restart servers and open and close their availability windows at the
+/// start of every minute until shutdown
+pub async fn run_scheduler(
+    state: Arc<AppState>,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    // Whether each server was inside its window at the previous check;
+    // servers are only started or stopped when that changes
+    let mut available: HashMap<String, bool> = HashMap::new();
+
+    loop {
+        let now = Local::now();
+        let into_minute = Duration::from_secs(now.second().into())
+            + Duration::from_nanos(now.nanosecond().min(999_999_999).into());
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(60).saturating_sub(into_minute)) => {}
+            _ = shutdown_rx.recv() => break,
+        }
+
+        let minute = Local::now().naive_local();
+        let servers: Vec<(String, ServerConfig)> = state
+            .config
+            .read()
+            .await
+            .servers
+            .iter()
+            .filter(|(_, config)| {
+                config.enabled && (config.restart_cron.is_some() || config.available_cron.is_some())
+            })
+            .map(|(name, config)| (name.clone(), config.clone()))
+            .collect();
+        available.retain(|name, _| servers.iter().any(|(server, _)| server == name));
+
+        for (name, config) in servers {
+            let now_available = is_available(&config, &minute);
+            let was_available = available.insert(name.clone(), now_available);
+            let restart_due = config
+                .restart_cron
+                .as_deref()
+                .and_then(|expression| CronSchedule::parse(expression).ok())
+                .is_some_and(|schedule| schedule.matches(&minute));
+
+            let action = match was_available {
+                Some(false) if now_available => "start",
+                Some(true) if !now_available => "stop",
+                _ if restart_due && now_available => "restart",
+                _ => continue,
+            };
+            // A slow server must not hold up the others
+            tokio::spawn(apply(state.clone(), name, action));
+        }
+    }
+}
+
+async fn apply(state: Arc<AppState>, name: String, action: &'static str) {
+    let running = state.get_server_state(&name).await == Some(ServerState::Running);
+    let manager = ServerManager::new(state.clone(), state.shutdown_tx.subscribe());
+
+    let result = match action {
+        "start" if running => return,
+        "start" => {
+            if state.disabled_servers.get(&name).await.is_some() {
+                tracing::info!(
+                    "Not starting disabled server {} for its availability window",
+                    name
+                );
+                return;
+            }
+            tracing::info!("Starting server {}: its availability window opened", name);
+            manager.start_server(&name).await
+        }
+        "stop" => {
+            tracing::info!("Stopping server {}: its availability window closed", name);
+            manager.stop_server(&name).await
+        }
+        // Scheduled restarts do not count towards quarantine
+        _ if running => {
+            tracing::info!("Restarting server {} on schedule", name);
+            match manager.stop_server(&name).await {
+                Ok(()) => manager.start_server(&name).await,
+                Err(e) => Err(e),
+            }
+        }
+        _ => return,
+    };
+
+    if let Err(e) = result {
+        tracing::error!("Scheduled {} of server {} failed: {}", action, name, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2025-06-02 is a Monday
+        NaiveDate::from_ymd_opt(2025, 6, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 30)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cron_matching() {
+        let nightly = CronSchedule::parse("0 4 * * *").unwrap();
+        assert!(nightly.matches(&at(2, 4, 0)));
+        assert!(!nightly.matches(&at(2, 4, 1)));
+
+        let work_hours = CronSchedule::parse("* 9-17 * * 1-5").unwrap();
+        assert!(work_hours.matches(&at(2, 9, 0)));
+        assert!(work_hours.matches(&at(6, 17, 59)));
+        assert!(!work_hours.matches(&at(2, 18, 0)));
+        // Saturday
+        assert!(!work_hours.matches(&at(7, 12, 0)));
+
+        let quarter_hours = CronSchedule::parse("*/15,7 * * * 7").unwrap();
+        assert!(quarter_hours.matches(&at(8, 0, 45)));
+        assert!(quarter_hours.matches(&at(8, 0, 7)));
+        assert!(!quarter_hours.matches(&at(8, 0, 50)));
+        assert!(!quarter_hours.matches(&at(2, 0, 45)));
+
+        // Restricted day of month and weekday: either matches
+        let first_or_monday = CronSchedule::parse("0 0 1 * 1").unwrap();
+        assert!(first_or_monday.matches(&at(1, 0, 0)));
+        assert!(first_or_monday.matches(&at(9, 0, 0)));
+        assert!(!first_or_monday.matches(&at(10, 0, 0)));
+
+        for invalid in [
+            "0 4 * *",
+            "60 * * * *",
+            "* * * * 8",
+            "5-1 * * * *",
+            "*/0 * * * *",
+        ] {
+            assert!(CronSchedule::parse(invalid).is_err(), "{invalid}");
+        }
+        assert_eq!(
+            CronSchedule::parse("@daily").unwrap(),
+            CronSchedule::parse("0 0 * * *").unwrap()
+        );
+    }
+}
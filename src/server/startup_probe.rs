@@ -0,0 +1,167 @@
+//! Startup probes: a tool call a server must answer successfully after its
+//! handshake before it is marked Running, catching servers that initialize
+//! but cannot actually do their job
+
+use crate::config::StartupProbeConfig;
+use crate::error::{ProxyError, Result, ServerError};
+use crate::state::translation::Direction;
+use crate::state::AppState;
+use serde_json::{json, Value};
+use tokio::time::{sleep, timeout, Duration};
+
+/// Call the probe's tool until it succeeds or its attempts run out
+pub(crate) async fn run(state: &AppState, server: &str, probe: &StartupProbeConfig) -> Result<()> {
+    let mut last_error = String::new();
+    for attempt in 1..=probe.attempts {
+        if attempt > 1 {
+            sleep(Duration::from_millis(probe.retry_interval_ms)).await;
+        }
+        let outcome = match timeout(
+            Duration::from_millis(probe.timeout_ms),
+            call(state, server, probe),
+        )
+        .await
+        {
+            Ok(Ok(reply)) => check(&reply, probe.expect_text.as_deref()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err(format!("no reply within {}ms", probe.timeout_ms)),
+        };
+        match outcome {
+            Ok(()) => {
+                tracing::info!("Server {} passed its startup probe", server);
+                return Ok(());
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Startup probe of {} failed (attempt {}/{}): {}",
+                    server,
+                    attempt,
+                    probe.attempts,
+                    e
+                );
+                last_error = e;
+            }
+        }
+    }
+
+    Err(ServerError::StartFailed(format!(
+        "startup probe calling {} failed: {}",
+        probe.tool, last_error
+    ))
+    .into())
+}
+
+async fn call(state: &AppState, server: &str, probe: &StartupProbeConfig) -> Result<Value> {
+    let adapter = match state.connection_states.get(server) {
+        Some(connection_state) => connection_state.clone().get_adapter().await,
+        None => None,
+    }
+    .filter(|adapter| adapter.source_version() != adapter.target_version());
+    let translation_error = |e| ProxyError::InvalidRequest(format!("Translation error: {e}"));
+
+    let mut request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": { "name": probe.tool, "arguments": probe.arguments }
+    });
+    if let Some(adapter) = &adapter {
+        let original = request.clone();
+        request = adapter
+            .translate_request(request)
+            .await
+            .map_err(translation_error)?;
+        state.translation_reports.record(
+            server,
+            "tools/call",
+            Direction::Request,
+            &original,
+            &request,
+        );
+    }
+
+    let conn = state.connection_pool.get(server).await?;
+    conn.send(bytes::Bytes::from(format!("{request}\n")))
+        .await?;
+    let mut reply: Value = serde_json::from_slice(&conn.recv().await?)?;
+    if let Some(adapter) = &adapter {
+        let original = reply.clone();
+        reply = adapter
+            .translate_response(reply)
+            .await
+            .map_err(translation_error)?;
+        state.translation_reports.record(
+            server,
+            "tools/call",
+            Direction::Response,
+            &original,
+            &reply,
+        );
+    }
+    Ok(reply)
+}
+
+/// Whether the reply counts as a pass: a result that is not a tool error and
+/// contains `expect_text`, when given
+fn check(reply: &Value, expect_text: Option<&str>) -> std::result::Result<(), String> {
+    if let Some(error) = reply.get("error").filter(|error| !error.is_null()) {
+        return Err(error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("error reply")
+            .to_string());
+    }
+    let result = reply.get("result").ok_or("reply has no result")?;
+    let text = result
+        .get("content")
+        .and_then(Value::as_array)
+        .map(|content| {
+            content
+                .iter()
+                .filter_map(|item| item.get("text").and_then(Value::as_str))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default();
+
+    if result.get("isError").and_then(Value::as_bool) == Some(true) {
+        return Err(format!("tool reported an error: {text}"));
+    }
+    match expect_text {
+        Some(expected) if !text.contains(expected) => {
+            Err(format!("result does not contain {expected:?}"))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_reply() {
+        let reply = |result: Value| json!({ "jsonrpc": "2.0", "id": 1, "result": result });
+        let ok = reply(json!({ "content": [{ "type": "text", "text": "1 row" }] }));
+
+        assert!(check(&ok, None).is_ok());
+        assert!(check(&ok, Some("row")).is_ok());
+        assert!(check(&ok, Some("2 rows")).is_err());
+        assert_eq!(
+            check(
+                &reply(json!({ "content": [{ "type": "text", "text": "no database" }], "isError": true })),
+                None
+            )
+            .unwrap_err(),
+            "tool reported an error: no database"
+        );
+        assert_eq!(
+            check(
+                &json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32602, "message": "Unknown tool" } }),
+                None
+            )
+            .unwrap_err(),
+            "Unknown tool"
+        );
+    }
+}
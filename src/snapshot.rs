@@ -0,0 +1,236 @@
+//! Capability snapshots for `check --snapshot`
+//!
+//! A snapshot holds what each pinged server offers: its tools, resources
+//! and prompts with their full definitions, schemas included. Comparing a
+//! fresh one with a saved one shows how upgrading a backend changes what
+//! clients see, before the upgrade is deployed.
+
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilitySnapshot {
+    pub taken_at: DateTime<Utc>,
+    pub servers: BTreeMap<String, ServerCapabilities>,
+}
+
+/// Definitions by tool name, resource URI and prompt name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerCapabilities {
+    #[serde(default)]
+    pub tools: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub resources: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub prompts: BTreeMap<String, Value>,
+}
+
+impl ServerCapabilities {
+    /// Build from the results of `tools/list`, `resources/list` and
+    /// `prompts/list`; a missing result counts as an empty list
+    pub fn from_lists(tools: &Value, resources: &Value, prompts: &Value) -> Self {
+        Self {
+            tools: by_key(tools, "tools", "name"),
+            resources: by_key(resources, "resources", "uri"),
+            prompts: by_key(prompts, "prompts", "name"),
+        }
+    }
+}
+
+fn by_key(result: &Value, list: &str, key: &str) -> BTreeMap<String, Value> {
+    result[list]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| Some((item[key].as_str()?.to_string(), item.clone())))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Kind {
+    Tool,
+    Resource,
+    Prompt,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Change {
+    Added,
+    Removed,
+    /// One `path: before -> after` line per difference
+    Changed(Vec<String>),
+}
+
+/// One difference between two snapshots
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Drift {
+    pub server: String,
+    pub kind: Kind,
+    pub name: String,
+    pub change: Change,
+}
+
+impl Drift {
+    /// Whether clients relying on the old snapshot may break: something
+    /// they used is gone or works differently
+    pub fn is_breaking(&self) -> bool {
+        !matches!(self.change, Change::Added)
+    }
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            Kind::Tool => "tool",
+            Kind::Resource => "resource",
+            Kind::Prompt => "prompt",
+        };
+        let what = format!("{} {kind} '{}'", self.server, self.name);
+        match &self.change {
+            Change::Added => write!(f, "{what} was added"),
+            Change::Removed => write!(f, "{what} was removed"),
+            Change::Changed(differences) => {
+                write!(f, "{what} changed: {}", differences.join("; "))
+            }
+        }
+    }
+}
+
+impl CapabilitySnapshot {
+    pub fn new(servers: BTreeMap<String, ServerCapabilities>) -> Self {
+        Self {
+            taken_at: Utc::now(),
+            servers,
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// How `current` differs from this snapshot, for the servers in
+    /// `current`. Servers it doesn't cover, such as ones left out with
+    /// `--tag`, are not compared.
+    pub fn drift(&self, current: &Self) -> Vec<Drift> {
+        let empty = ServerCapabilities::default();
+        let mut drift = Vec::new();
+        for (server, now) in &current.servers {
+            let before = self.servers.get(server).unwrap_or(&empty);
+            for (kind, before, now) in [
+                (Kind::Tool, &before.tools, &now.tools),
+                (Kind::Resource, &before.resources, &now.resources),
+                (Kind::Prompt, &before.prompts, &now.prompts),
+            ] {
+                compare(server, kind, before, now, &mut drift);
+            }
+        }
+        drift
+    }
+
+    /// This snapshot with the servers in `current` replaced by their
+    /// entries there
+    pub fn updated_with(&self, current: &Self) -> Self {
+        let mut servers = self.servers.clone();
+        servers.extend(current.servers.clone());
+        Self {
+            taken_at: current.taken_at,
+            servers,
+        }
+    }
+}
+
+fn compare(
+    server: &str,
+    kind: Kind,
+    before: &BTreeMap<String, Value>,
+    now: &BTreeMap<String, Value>,
+    drift: &mut Vec<Drift>,
+) {
+    let mut push = |name: &str, change| {
+        drift.push(Drift {
+            server: server.to_string(),
+            kind,
+            name: name.to_string(),
+            change,
+        })
+    };
+    for (name, old) in before {
+        match now.get(name) {
+            None => push(name, Change::Removed),
+            Some(new) => {
+                let differences = crate::recording::diff(old, new);
+                if !differences.is_empty() {
+                    push(name, Change::Changed(differences));
+                }
+            }
+        }
+    }
+    for name in now.keys().filter(|name| !before.contains_key(*name)) {
+        push(name, Change::Added);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn snapshot(tools: Value) -> CapabilitySnapshot {
+        let capabilities =
+            ServerCapabilities::from_lists(&json!({ "tools": tools }), &json!({}), &Value::Null);
+        CapabilitySnapshot::new(BTreeMap::from([("db".to_string(), capabilities)]))
+    }
+
+    #[test]
+    fn test_drift_between_snapshots() {
+        let saved = snapshot(json!([
+            { "name": "query", "inputSchema": { "type": "object", "required": ["sql"] } },
+            { "name": "vacuum", "inputSchema": { "type": "object" } }
+        ]));
+        let current = snapshot(json!([
+            { "name": "query", "inputSchema": { "type": "object", "required": ["sql", "db"] } },
+            { "name": "explain", "inputSchema": { "type": "object" } }
+        ]));
+
+        let drift = saved.drift(&current);
+        let lines: Vec<String> = drift.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "db tool 'query' changed: $.inputSchema.required[1]: missing -> \"db\"",
+                "db tool 'vacuum' was removed",
+                "db tool 'explain' was added",
+            ]
+        );
+        assert_eq!(drift.iter().filter(|d| d.is_breaking()).count(), 2);
+        assert!(saved.drift(&saved).is_empty());
+
+        // Servers not checked this time keep their saved entries
+        let other = CapabilitySnapshot::new(BTreeMap::from([(
+            "files".to_string(),
+            ServerCapabilities::default(),
+        )]));
+        assert!(other
+            .drift(&current)
+            .iter()
+            .all(|d| d.change == Change::Added));
+        let merged = other.updated_with(&current);
+        assert_eq!(merged.servers.len(), 2);
+        assert_eq!(merged.servers["db"], current.servers["db"]);
+    }
+}
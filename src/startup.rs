@@ -0,0 +1,196 @@
+//! Starting a proxy: restoring what a previous run saved, then the servers
+//! and every background task. The HTTP and stdio modes and the embedded
+//! [`crate::Proxy`] all start this way, so a task added here runs in each.
+
+use crate::config::Config;
+use crate::context::storage::{CacheConfig, HybridStorage, StorageBackend};
+use crate::proxy::cache_warmer::CacheWarmer;
+use crate::proxy::{ProxyServer, RequestHandler};
+use crate::server::ServerManager;
+use crate::state::{AppState, DisabledServers};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+/// What to start besides the servers and background tasks
+#[derive(Default)]
+pub struct StartOptions {
+    /// Serve clients over HTTP, and the web UI when configured
+    pub serve_http: bool,
+    /// Store context tracing data here instead of where `contextTracing`
+    /// says. Enables context tracing.
+    pub storage: Option<Arc<dyn StorageBackend>>,
+    /// Signalled once every configured server has finished starting,
+    /// whether it came up or failed
+    pub started_tx: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+/// Start the proxy on `state`, serving requests with `handler`, which the
+/// cache warmer keeps warm. Returns once everything is started; servers
+/// finish connecting in the background. The returned tasks finish after
+/// `state` shuts down.
+pub async fn start(
+    state: &Arc<AppState>,
+    handler: Arc<RequestHandler>,
+    options: StartOptions,
+) -> Vec<JoinHandle<()>> {
+    let config = state.config.read().await.clone();
+
+    // Restore servers disabled or quarantined in a previous run
+    if let Some(path) = DisabledServers::default_path().filter(|_| !config.ephemeral) {
+        if let Err(e) = state.disabled_servers.load(&path).await {
+            tracing::error!(
+                "Failed to load disabled servers from {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
+    // Share state with other instances behind the same load balancer
+    if let Some(shared_config) = &config.shared_state {
+        if let Err(e) = state.initialize_shared_state(shared_config).await {
+            tracing::error!(
+                "Failed to connect to shared state, running standalone: {}",
+                e
+            );
+        } else {
+            tracing::info!("Sharing state through Redis");
+        }
+    }
+
+    if let Some(plugin_manager) = &state.plugin_manager {
+        match plugin_manager.discover_plugins().await {
+            Ok(count) => tracing::info!("Discovered {} plugins", count),
+            Err(e) => tracing::error!("Failed to discover plugins: {}", e),
+        }
+    }
+
+    let mut tasks = Vec::new();
+    // Pick up servers other instances disable or enable
+    if let Some(shared_config) = &config.shared_state {
+        tasks.push(tokio::spawn(state.disabled_servers.clone().run_refresher(
+            shared_config.disabled_refresh_interval(),
+            state.shutdown_tx.subscribe(),
+        )));
+    }
+    if let Some(storage) = context_storage(&config, options.storage).await {
+        if let Err(e) = state.initialize_context_tracker(storage.clone()).await {
+            tracing::error!("Failed to initialize context tracker: {}", e);
+        } else {
+            tracing::info!("Context tracing initialized");
+            tasks.push(tokio::spawn(crate::context::maintenance::run_retention(
+                storage.clone(),
+                config.context_tracing.retention_days,
+                state.shutdown_tx.subscribe(),
+            )));
+            tasks.push(tokio::spawn(crate::context::maintenance::run(
+                storage,
+                config.context_tracing.maintenance.clone(),
+                state.shutdown_tx.subscribe(),
+            )));
+        }
+    }
+
+    // Evaluate alert rules and send alerts to chat webhooks
+    tasks.push(tokio::spawn(crate::server::alerts::run_notifiers(
+        state.clone(),
+        state.alert_tx.subscribe(),
+        state.shutdown_tx.subscribe(),
+    )));
+    tasks.push(tokio::spawn(crate::server::alerts::run_alert_rules(
+        state.clone(),
+        state.shutdown_tx.subscribe(),
+    )));
+
+    // Scheduled restarts and availability windows
+    tasks.push(tokio::spawn(crate::server::schedule::run_scheduler(
+        state.clone(),
+        state.shutdown_tx.subscribe(),
+    )));
+
+    // Rolling traffic history for the dashboard's charts
+    tasks.push(tokio::spawn(crate::state::metrics_history::run_sampler(
+        state.clone(),
+        state.shutdown_tx.subscribe(),
+    )));
+
+    // Carry metrics on across restarts
+    tasks.push(tokio::spawn(crate::state::metrics::run_persister(
+        state.clone(),
+        state.shutdown_tx.subscribe(),
+    )));
+
+    let mut server_manager = ServerManager::new(state.clone(), state.shutdown_tx.subscribe());
+    if let Some(started_tx) = options.started_tx {
+        server_manager = server_manager.notify_started(started_tx);
+    }
+    tasks.push(tokio::spawn(async move {
+        if let Err(e) = server_manager.run().await {
+            tracing::error!("Server manager error: {}", e);
+        }
+    }));
+
+    // Replace backend connections that died while idle
+    if let Some(interval) = config.connection_reap_interval() {
+        tasks.push(tokio::spawn(
+            state
+                .connection_pool
+                .clone()
+                .run_reaper(interval, state.shutdown_tx.subscribe()),
+        ));
+    }
+
+    // Keep tool and resource lists ready for clients
+    let warmer = CacheWarmer::new(state.clone(), handler.clone(), config.cache_warmer.clone());
+    tasks.push(tokio::spawn(warmer.run()));
+
+    if options.serve_http {
+        let proxy_server = ProxyServer::with_handler(state.clone(), handler);
+        tasks.push(tokio::spawn(async move {
+            if let Err(e) = proxy_server.run().await {
+                tracing::error!("Proxy server error: {}", e);
+            }
+        }));
+        if config.web_ui.enabled {
+            let web_state = state.clone();
+            tasks.push(tokio::spawn(async move {
+                if let Err(e) = crate::web::start_server(web_state).await {
+                    tracing::error!("Web UI server error: {}", e);
+                }
+            }));
+        }
+    }
+
+    tasks
+}
+
+/// The storage given, or the one `contextTracing` configures when enabled
+async fn context_storage(
+    config: &Config,
+    storage: Option<Arc<dyn StorageBackend>>,
+) -> Option<Arc<dyn StorageBackend>> {
+    if storage.is_some() || !config.context_tracing.enabled {
+        return storage;
+    }
+
+    tracing::info!("Initializing context tracing framework");
+    let tracing_config = &config.context_tracing;
+    let cache = Some(CacheConfig {
+        max_entries: tracing_config.cache_size,
+        ttl_seconds: tracing_config.cache_ttl_seconds,
+        eviction: tracing_config.eviction_strategy,
+        read_connections: tracing_config.read_connections,
+    });
+    let storage = if config.ephemeral {
+        HybridStorage::in_memory(cache).await
+    } else {
+        HybridStorage::new(tracing_config.sqlite_path.clone(), cache).await
+    };
+    match storage {
+        Ok(storage) => Some(Arc::new(storage)),
+        Err(e) => {
+            tracing::error!("Failed to create context storage: {}", e);
+            None
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use crate::config::AdaptiveConcurrencyConfig;
+use std::time::Duration;
+
+/// Weight of each call in the recent latency
+const RECENT_WEIGHT: f64 = 0.2;
+/// Weight of each call in the long-run latency, which the recent one is
+/// compared with
+const LONG_RUN_WEIGHT: f64 = 0.01;
+
+/// A concurrency limit adjusted by additive increase, multiplicative
+/// decrease (AIMD). Calls that succeed without the recent latency rising
+/// past `latency_tolerance` times the long-run latency add `1 / limit`, so
+/// a full round of good calls raises the limit by one. A failed or slow
+/// call multiplies it by `backoff`, at most once per round, since the calls
+/// of a round were all sent before the last decrease took effect.
+#[derive(Debug, Clone)]
+pub struct AdaptiveLimit {
+    min: f64,
+    max: f64,
+    latency_tolerance: f64,
+    backoff: f64,
+    limit: f64,
+    /// Moving averages of call latency, in seconds
+    recent: Option<f64>,
+    long_run: Option<f64>,
+    /// Calls finished since the limit last went down
+    since_decrease: usize,
+}
+
+impl AdaptiveLimit {
+    /// A limit between `config.min_limit` and `max`
+    pub fn new(config: &AdaptiveConcurrencyConfig, max: usize) -> Self {
+        let max = max.max(1) as f64;
+        let min = (config.min_limit.max(1) as f64).min(max);
+        Self {
+            min,
+            max,
+            latency_tolerance: config.latency_tolerance,
+            backoff: config.backoff,
+            limit: (config.initial_limit as f64).clamp(min, max),
+            recent: None,
+            long_run: None,
+            since_decrease: 0,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit as usize
+    }
+
+    /// Adjust the limit to a finished call, returning the new limit
+    pub fn record(&mut self, latency: Duration, success: bool) -> usize {
+        self.since_decrease += 1;
+        let overloaded = if success {
+            let latency = latency.as_secs_f64();
+            let average = |value: Option<f64>, weight: f64| {
+                Some(value.map_or(latency, |value| value + (latency - value) * weight))
+            };
+            self.recent = average(self.recent, RECENT_WEIGHT);
+            self.long_run = average(self.long_run, LONG_RUN_WEIGHT);
+            self.recent
+                .zip(self.long_run)
+                .is_some_and(|(recent, long_run)| recent > long_run * self.latency_tolerance)
+        } else {
+            true
+        };
+
+        if !overloaded {
+            self.limit = (self.limit + 1.0 / self.limit).min(self.max);
+        } else if self.since_decrease >= self.limit() {
+            self.limit = (self.limit * self.backoff).max(self.min);
+            self.since_decrease = 0;
+        }
+        self.limit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_follows_latency_and_errors() {
+        let mut limit = AdaptiveLimit::new(&AdaptiveConcurrencyConfig::default(), 8);
+        assert_eq!(limit.limit(), 4);
+
+        // Steady, fast calls raise it by about one per round, up to the maximum
+        let fast = Duration::from_millis(10);
+        for _ in 0..5 {
+            limit.record(fast, true);
+        }
+        assert_eq!(limit.limit(), 5);
+        for _ in 0..100 {
+            limit.record(fast, true);
+        }
+        assert_eq!(limit.limit(), 8);
+
+        // Latency climbing well past the norm cuts it, once per round
+        for _ in 0..6 {
+            limit.record(Duration::from_millis(200), true);
+        }
+        assert_eq!(limit.limit(), 6);
+
+        // So do failures, down to the minimum
+        for _ in 0..100 {
+            limit.record(fast, false);
+        }
+        assert_eq!(limit.limit(), 1);
+    }
+}
@@ -0,0 +1,301 @@
+//! Alert rules evaluated against per-server request statistics
+
+use super::{AlertKind, MetricsHistory};
+use crate::config::{AlertMetric, AlertRuleConfig};
+use crate::transport::pool::PoolStats;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleState {
+    Ok,
+    /// Above the threshold, but not yet for long enough
+    Pending,
+    Firing,
+}
+
+/// Where a rule stands for one server, as reported by `/api/alerts`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleStatus {
+    pub rule: String,
+    pub server: String,
+    pub metric: AlertMetric,
+    pub above: f64,
+    /// `None` when there was nothing to measure, e.g. no requests
+    pub value: Option<f64>,
+    pub state: RuleState,
+    /// When the value went above the threshold, while pending or firing
+    pub since: Option<DateTime<Utc>>,
+}
+
+/// A rule that started or stopped firing for a server
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleTransition {
+    pub server: String,
+    pub kind: AlertKind,
+    pub message: String,
+}
+
+struct Tracked {
+    status: RuleStatus,
+    /// Requests and errors at the previous evaluation, for error rates
+    counts: (u64, u64),
+}
+
+/// State of every (rule, server) pair
+#[derive(Default)]
+pub struct AlertRules {
+    tracked: Mutex<BTreeMap<(String, String), Tracked>>,
+}
+
+impl AlertRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn statuses(&self) -> Vec<RuleStatus> {
+        self.tracked
+            .lock()
+            .unwrap()
+            .values()
+            .map(|tracked| tracked.status.clone())
+            .collect()
+    }
+
+    /// Evaluate `rules` against the pool's current statistics and return
+    /// the rules that started or stopped firing. Error rates cover the
+    /// requests made since the previous evaluation, so the first evaluation
+    /// of an error rate rule has no value, unless the rule has a window,
+    /// which is read from `history`.
+    pub fn evaluate(
+        &self,
+        rules: &[AlertRuleConfig],
+        stats: &[PoolStats],
+        history: &MetricsHistory,
+        now: DateTime<Utc>,
+    ) -> Vec<RuleTransition> {
+        let mut tracked = self.tracked.lock().unwrap();
+        // Pairs not evaluated again, such as removed rules, are dropped
+        let mut previous = std::mem::take(&mut *tracked);
+        let mut transitions = Vec::new();
+
+        for rule in rules {
+            let watched = stats
+                .iter()
+                .filter(|s| rule.server.as_ref().is_none_or(|name| *name == s.server));
+            for server in watched {
+                let key = (rule.name.clone(), server.server.clone());
+                let counts = (server.requests, server.failures + server.error_replies);
+                let prior = previous.remove(&key);
+                let latency = server.latency.as_ref();
+                let value = match (rule.metric, rule.window_seconds) {
+                    (AlertMetric::ErrorRate, Some(window)) => history.error_rate(
+                        &server.server,
+                        rule.tool.as_deref(),
+                        now - std::time::Duration::from_secs(window),
+                    ),
+                    (AlertMetric::ErrorRate, None) => {
+                        prior.as_ref().and_then(|p| error_rate(p.counts, counts))
+                    }
+                    (AlertMetric::P50LatencyMs, _) => latency.map(|l| l.p50_ms),
+                    (AlertMetric::P95LatencyMs, _) => latency.map(|l| l.p95_ms),
+                    (AlertMetric::P99LatencyMs, _) => latency.map(|l| l.p99_ms),
+                    (AlertMetric::InFlight, _) => Some(server.in_flight as f64),
+                };
+
+                let mut status = prior.map_or_else(
+                    || RuleStatus {
+                        rule: rule.name.clone(),
+                        server: server.server.clone(),
+                        metric: rule.metric,
+                        above: rule.above,
+                        value: None,
+                        state: RuleState::Ok,
+                        since: None,
+                    },
+                    |prior| prior.status,
+                );
+                status.metric = rule.metric;
+                status.above = rule.above;
+                status.value = value;
+
+                match value.filter(|value| *value > rule.above) {
+                    None => {
+                        if status.state == RuleState::Firing {
+                            transitions.push(RuleTransition {
+                                server: server.server.clone(),
+                                kind: AlertKind::RuleResolved,
+                                message: format!(
+                                    "{}: {} of server {} is no longer above {}",
+                                    rule.name,
+                                    metric_name(rule.metric),
+                                    server.server,
+                                    rule.above
+                                ),
+                            });
+                        }
+                        status.state = RuleState::Ok;
+                        status.since = None;
+                    }
+                    Some(value) => {
+                        let since = *status.since.get_or_insert(now);
+                        let breached_for = (now - since).num_seconds().max(0) as u64;
+                        if status.state != RuleState::Firing && breached_for >= rule.for_seconds {
+                            status.state = RuleState::Firing;
+                            transitions.push(RuleTransition {
+                                server: server.server.clone(),
+                                kind: AlertKind::RuleFiring,
+                                message: format!(
+                                    "{}: {} of server {} is {value:.1}, above {} for {}s",
+                                    rule.name,
+                                    metric_name(rule.metric),
+                                    server.server,
+                                    rule.above,
+                                    breached_for
+                                ),
+                            });
+                        } else if status.state == RuleState::Ok {
+                            status.state = RuleState::Pending;
+                        }
+                    }
+                }
+
+                tracked.insert(key, Tracked { status, counts });
+            }
+        }
+
+        transitions
+    }
+}
+
+/// Percentage of the requests between two readings that failed. `None`
+/// without requests in between, or when the counters were reset.
+fn error_rate(before: (u64, u64), after: (u64, u64)) -> Option<f64> {
+    let requests = after.0.checked_sub(before.0)?;
+    let errors = after.1.checked_sub(before.1)?;
+    if requests == 0 {
+        return None;
+    }
+    // Failed connects count as errors without a request
+    Some((errors as f64 / requests as f64 * 100.0).min(100.0))
+}
+
+fn metric_name(metric: AlertMetric) -> &'static str {
+    match metric {
+        AlertMetric::ErrorRate => "error rate",
+        AlertMetric::P50LatencyMs => "p50 latency (ms)",
+        AlertMetric::P95LatencyMs => "p95 latency (ms)",
+        AlertMetric::P99LatencyMs => "p99 latency (ms)",
+        AlertMetric::InFlight => "requests in flight",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn stats(server: &str, requests: u64, errors: u64) -> PoolStats {
+        PoolStats {
+            server: server.to_string(),
+            connections: 1,
+            in_flight: 0,
+            idle_secs: None,
+            age_secs: None,
+            requests,
+            failures: 0,
+            error_replies: errors,
+            reaped: 0,
+            warm_spares: 0,
+            failovers: 0,
+            cancelled: 0,
+            responses: requests,
+            total_latency_ms: 0.0,
+            latency: None,
+        }
+    }
+
+    #[test]
+    fn test_error_rate_rule_fires_after_duration_and_resolves() {
+        let rules = vec![AlertRuleConfig {
+            name: "github-errors".to_string(),
+            metric: AlertMetric::ErrorRate,
+            server: Some("github".to_string()),
+            above: 5.0,
+            for_seconds: 300,
+            window_seconds: None,
+            tool: None,
+        }];
+        let history = MetricsHistory::new();
+        let engine = AlertRules::new();
+        let start = Utc::now();
+        let at = |minutes| start + Duration::minutes(minutes);
+
+        // First reading only sets the baseline
+        let first = engine.evaluate(
+            &rules,
+            &[stats("github", 100, 0), stats("git", 1, 1)],
+            &history,
+            at(0),
+        );
+        assert!(first.is_empty());
+        assert_eq!(engine.statuses().len(), 1);
+        assert_eq!(engine.statuses()[0].value, None);
+
+        // 10% errors: pending until it has lasted five minutes
+        assert!(engine
+            .evaluate(&rules, &[stats("github", 200, 10)], &history, at(1))
+            .is_empty());
+        assert_eq!(engine.statuses()[0].state, RuleState::Pending);
+        assert!(engine
+            .evaluate(&rules, &[stats("github", 300, 20)], &history, at(4))
+            .is_empty());
+        let fired = engine.evaluate(&rules, &[stats("github", 400, 30)], &history, at(6));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].kind, AlertKind::RuleFiring);
+        assert_eq!(engine.statuses()[0].state, RuleState::Firing);
+
+        // Still firing: no repeat
+        assert!(engine
+            .evaluate(&rules, &[stats("github", 500, 40)], &history, at(7))
+            .is_empty());
+
+        let resolved = engine.evaluate(&rules, &[stats("github", 600, 41)], &history, at(8));
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].kind, AlertKind::RuleResolved);
+        assert_eq!(engine.statuses()[0].state, RuleState::Ok);
+        assert_eq!(engine.statuses()[0].value, Some(1.0));
+    }
+
+    #[test]
+    fn test_windowed_rule_reads_tool_history() {
+        let rules = vec![AlertRuleConfig {
+            name: "search-errors".to_string(),
+            metric: AlertMetric::ErrorRate,
+            server: None,
+            above: 20.0,
+            for_seconds: 0,
+            window_seconds: Some(600),
+            tool: Some("github__search".to_string()),
+        }];
+        let history = MetricsHistory::new();
+        let now = Utc::now();
+        history.record_tool_call("github", "github__search", false, Default::default());
+        history.record_tool_call("github", "github__search", true, Default::default());
+        history.record_tool_call("github", "github__issue", true, Default::default());
+        history.sample(
+            &[stats("github", 3, 1)],
+            now,
+            std::time::Duration::from_secs(3600),
+        );
+
+        // No baseline reading needed
+        let fired = AlertRules::new().evaluate(&rules, &[stats("github", 3, 1)], &history, now);
+        assert_eq!(fired.len(), 1);
+        assert!(fired[0].message.contains("50.0"), "{}", fired[0].message);
+    }
+}
@@ -0,0 +1,168 @@
+//! Hit, miss and eviction counts of the proxy's caches, and clearing them
+//!
+//! The aggregated tool list is cached by each request handler; those share
+//! the counters here and are cleared by moving the catalog generation on.
+//! The context tracing caches live in their storage backend, which keeps
+//! its own counters.
+
+use super::AppState;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug, Default)]
+pub struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl CacheCounters {
+    pub fn record_hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// An entry was dropped because it expired or went stale
+    pub fn record_eviction(&self) {
+        self.record_evictions(1);
+    }
+
+    pub fn record_evictions(&self, count: usize) {
+        self.evictions.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self, entries: Option<usize>) -> CacheStats {
+        CacheStats::new(
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+            self.evictions.load(Ordering::Relaxed),
+            entries,
+        )
+    }
+}
+
+/// One cache's counters, as reported by `/api/cache/stats`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Fraction of lookups that hit; `None` before the first lookup
+    pub hit_rate: Option<f64>,
+    /// Entries cached now, when the cache can tell
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entries: Option<usize>,
+}
+
+impl CacheStats {
+    pub fn new(hits: u64, misses: u64, evictions: u64, entries: Option<usize>) -> Self {
+        let lookups = hits + misses;
+        Self {
+            hits,
+            misses,
+            evictions,
+            hit_rate: (lookups > 0).then(|| hits as f64 / lookups as f64),
+            entries,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheReport {
+    /// The aggregated `tools/list` response
+    pub tools: CacheStats,
+    /// Context tracing storage; `None` when tracing is off
+    pub context: Option<CacheStats>,
+}
+
+/// Which caches to clear
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheScope {
+    Tools,
+    Context,
+    All,
+}
+
+impl std::str::FromStr for CacheScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tools" => Ok(CacheScope::Tools),
+            "context" => Ok(CacheScope::Context),
+            "all" => Ok(CacheScope::All),
+            _ => Err(format!(
+                "unknown cache scope '{s}'; expected tools, context or all"
+            )),
+        }
+    }
+}
+
+/// What clearing removed
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClearReport {
+    /// Whether cached tool lists were invalidated
+    pub tools: bool,
+    /// Context cache entries removed; `None` when not cleared or tracing is
+    /// off
+    pub context: Option<usize>,
+}
+
+pub async fn report(state: &AppState) -> CacheReport {
+    let tracker = state.context_tracker.read().await.clone();
+    let context = match tracker {
+        Some(tracker) => tracker.storage().cache_stats().await,
+        None => None,
+    };
+    CacheReport {
+        tools: state.tools_cache.stats(None),
+        context,
+    }
+}
+
+/// Clear the caches in `scope`. Counters are kept, so hit rates stay
+/// comparable before and after.
+pub async fn clear(state: &AppState, scope: CacheScope) -> ClearReport {
+    let mut cleared = ClearReport::default();
+    if matches!(scope, CacheScope::Tools | CacheScope::All) {
+        state.invalidate_catalog().await;
+        cleared.tools = true;
+    }
+    if matches!(scope, CacheScope::Context | CacheScope::All) {
+        let tracker = state.context_tracker.read().await.clone();
+        if let Some(tracker) = tracker {
+            cleared.context = Some(tracker.storage().clear_cache().await);
+        }
+    }
+    tracing::info!("Cleared caches ({:?}): {:?}", scope, cleared);
+    cleared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_and_scope() {
+        let counters = CacheCounters::default();
+        assert_eq!(counters.stats(None).hit_rate, None);
+        counters.record_hit();
+        counters.record_hit();
+        counters.record_hit();
+        counters.record_miss();
+        counters.record_eviction();
+        let stats = counters.stats(Some(1));
+        assert_eq!(stats.hit_rate, Some(0.75));
+        assert_eq!(stats.evictions, 1);
+
+        assert_eq!("all".parse::<CacheScope>(), Ok(CacheScope::All));
+        assert!("everything".parse::<CacheScope>().is_err());
+    }
+}
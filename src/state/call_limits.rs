@@ -0,0 +1,365 @@
+use super::adaptive_limit::AdaptiveLimit;
+use crate::config::{AdaptiveConcurrencyConfig, RequestPriority};
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// Call slots bounding the tool calls each server handles at once
+#[derive(Default)]
+pub struct CallLimits {
+    servers: DashMap<String, Arc<CallSlots>>,
+}
+
+impl CallLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The slots for `server`: `limit` of them, or with `adaptive` as many
+    /// as the server keeps up with, up to `limit`. When the configuration
+    /// changes new slots replace the old ones; calls holding or waiting for
+    /// the old ones finish unaffected.
+    pub fn slots(
+        &self,
+        server: &str,
+        limit: usize,
+        adaptive: Option<&AdaptiveConcurrencyConfig>,
+    ) -> Arc<CallSlots> {
+        let mut entry = self
+            .servers
+            .entry(server.to_string())
+            .or_insert_with(|| Arc::new(CallSlots::new(limit, adaptive)));
+        if entry.max != limit || entry.adaptive.as_ref() != adaptive {
+            *entry = Arc::new(CallSlots::new(limit, adaptive));
+        }
+        entry.clone()
+    }
+
+    /// How many calls `server` may handle at once right now, if limited
+    pub fn limit(&self, server: &str) -> Option<usize> {
+        let slots = self.servers.get(server)?;
+        let limit = slots.state.lock().unwrap().limit;
+        Some(limit)
+    }
+}
+
+/// Why no slot was given
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotError {
+    /// Every slot is in use and the call may not wait
+    Busy,
+    /// The queue is full, or a waiting batch call made room for an
+    /// interactive one
+    Shed,
+}
+
+/// A fixed number of slots that waiting calls get by priority: every
+/// interactive call before any batch call, first come first served within
+/// each
+pub struct CallSlots {
+    /// Configured limit, and how it adapts; what `state.limit` follows
+    max: usize,
+    adaptive: Option<AdaptiveConcurrencyConfig>,
+    state: Mutex<SlotState>,
+}
+
+struct SlotState {
+    limit: usize,
+    adaptive: Option<AdaptiveLimit>,
+    in_use: usize,
+    next_waiter: u64,
+    interactive: VecDeque<Waiter>,
+    batch: VecDeque<Waiter>,
+}
+
+struct Waiter {
+    id: u64,
+    granted: oneshot::Sender<Result<(), SlotError>>,
+}
+
+impl SlotState {
+    fn queue(&mut self, priority: RequestPriority) -> &mut VecDeque<Waiter> {
+        match priority {
+            RequestPriority::Interactive => &mut self.interactive,
+            RequestPriority::Batch => &mut self.batch,
+        }
+    }
+
+    fn queued(&self) -> usize {
+        self.interactive.len() + self.batch.len()
+    }
+
+    /// Give free slots to waiters, interactive ones first
+    fn grant(&mut self) {
+        while self.in_use < self.limit {
+            let Some(waiter) = self
+                .interactive
+                .pop_front()
+                .or_else(|| self.batch.pop_front())
+            else {
+                return;
+            };
+            // Waiters leave the queue before they stop listening
+            if waiter.granted.send(Ok(())).is_ok() {
+                self.in_use += 1;
+            }
+        }
+    }
+}
+
+impl CallSlots {
+    fn new(max: usize, adaptive: Option<&AdaptiveConcurrencyConfig>) -> Self {
+        let adaptive_limit = adaptive.map(|config| AdaptiveLimit::new(config, max));
+        Self {
+            max,
+            adaptive: adaptive.cloned(),
+            state: Mutex::new(SlotState {
+                limit: adaptive_limit.as_ref().map_or(max, AdaptiveLimit::limit),
+                adaptive: adaptive_limit,
+                in_use: 0,
+                next_waiter: 0,
+                interactive: VecDeque::new(),
+                batch: VecDeque::new(),
+            }),
+        }
+    }
+
+    fn slot(self: &Arc<Self>) -> CallSlot {
+        CallSlot {
+            slots: self.clone(),
+            started: Instant::now(),
+            succeeded: false,
+        }
+    }
+
+    /// A free slot, without waiting
+    pub fn try_acquire(self: &Arc<Self>) -> Result<CallSlot, SlotError> {
+        let mut state = self.state.lock().unwrap();
+        if state.in_use < state.limit {
+            state.in_use += 1;
+            Ok(self.slot())
+        } else {
+            Err(SlotError::Busy)
+        }
+    }
+
+    /// A slot, waiting behind the calls of the same or a higher priority.
+    /// With `max_queued` calls already waiting, a batch call is shed: the
+    /// newest waiting batch call when an interactive one arrives, otherwise
+    /// the arriving one.
+    pub async fn acquire(
+        self: &Arc<Self>,
+        priority: RequestPriority,
+        max_queued: Option<usize>,
+    ) -> Result<CallSlot, SlotError> {
+        let (id, granted) = {
+            let mut state = self.state.lock().unwrap();
+            if state.in_use < state.limit {
+                state.in_use += 1;
+                return Ok(self.slot());
+            }
+            if max_queued.is_some_and(|max| state.queued() >= max) {
+                match (priority, state.batch.pop_back()) {
+                    (RequestPriority::Interactive, Some(shed)) => {
+                        let _ = shed.granted.send(Err(SlotError::Shed));
+                    }
+                    _ => return Err(SlotError::Shed),
+                }
+            }
+            let (tx, rx) = oneshot::channel();
+            state.next_waiter += 1;
+            let id = state.next_waiter;
+            state.queue(priority).push_back(Waiter { id, granted: tx });
+            (id, rx)
+        };
+
+        let mut waiting = Waiting {
+            slots: self,
+            priority,
+            id,
+            granted,
+        };
+        match (&mut waiting.granted).await {
+            Ok(Ok(())) => Ok(self.slot()),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(SlotError::Busy),
+        }
+    }
+
+    /// Free a slot, adapting the limit to how the call held in it went
+    fn release(&self, call: Option<(Duration, bool)>) {
+        let mut state = self.state.lock().unwrap();
+        state.in_use -= 1;
+        if let Some((latency, success)) = call {
+            if let Some(adaptive) = &mut state.adaptive {
+                let limit = adaptive.record(latency, success);
+                if limit != state.limit {
+                    tracing::debug!("Concurrent call limit {} -> {}", state.limit, limit);
+                    state.limit = limit;
+                }
+            }
+        }
+        state.grant();
+    }
+}
+
+/// A slot in use; dropping it frees the slot. A call that returns it
+/// without [`CallSlot::succeeded`] counts as failed.
+pub struct CallSlot {
+    slots: Arc<CallSlots>,
+    started: Instant,
+    succeeded: bool,
+}
+
+impl CallSlot {
+    /// Free the slot after the call got its reply
+    pub fn succeeded(mut self) {
+        self.succeeded = true;
+    }
+}
+
+impl Drop for CallSlot {
+    fn drop(&mut self) {
+        self.slots
+            .release(Some((self.started.elapsed(), self.succeeded)));
+    }
+}
+
+/// A call waiting for a slot. Should it stop waiting, for example on a
+/// timeout, it leaves the queue, and passes on a slot it was given but
+/// never took.
+struct Waiting<'a> {
+    slots: &'a Arc<CallSlots>,
+    priority: RequestPriority,
+    id: u64,
+    granted: oneshot::Receiver<Result<(), SlotError>>,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        let given = {
+            let mut state = self.slots.state.lock().unwrap();
+            let queue = state.queue(self.priority);
+            match queue.iter().position(|waiter| waiter.id == self.id) {
+                Some(index) => {
+                    queue.remove(index);
+                    false
+                }
+                None => matches!(self.granted.try_recv(), Ok(Ok(()))),
+            }
+        };
+        if given {
+            self.slots.release(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_interactive_calls_go_first_and_batch_is_shed() {
+        let slots = CallLimits::new().slots("browser", 1, None);
+        let running = slots.try_acquire().unwrap();
+        assert_eq!(slots.try_acquire().err(), Some(SlotError::Busy));
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let wait = |priority, name: &'static str| {
+            let (slots, order) = (slots.clone(), order.clone());
+            tokio::spawn(async move {
+                let result = slots.acquire(priority, Some(2)).await;
+                if result.is_ok() {
+                    order.lock().unwrap().push(name);
+                }
+                result.map(|_| ())
+            })
+        };
+        let settle = || tokio::time::sleep(Duration::from_millis(20));
+
+        let batch = wait(RequestPriority::Batch, "batch");
+        settle().await;
+        let first = wait(RequestPriority::Interactive, "first");
+        settle().await;
+        // The queue is full: the waiting batch call makes room
+        let second = wait(RequestPriority::Interactive, "second");
+        settle().await;
+        assert_eq!(batch.await.unwrap(), Err(SlotError::Shed));
+        // With only interactive calls waiting, a new batch call is turned away
+        assert_eq!(
+            slots.acquire(RequestPriority::Batch, Some(2)).await.err(),
+            Some(SlotError::Shed)
+        );
+
+        drop(running);
+        first.await.unwrap().unwrap();
+        second.await.unwrap().unwrap();
+        assert_eq!(*order.lock().unwrap(), ["first", "second"]);
+        assert!(slots.try_acquire().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_waiting_batch_calls_yield_to_interactive_ones() {
+        let slots = CallLimits::new().slots("browser", 1, None);
+        let running = slots.try_acquire().unwrap();
+        let batch = {
+            let slots = slots.clone();
+            tokio::spawn(async move {
+                slots
+                    .acquire(RequestPriority::Batch, None)
+                    .await
+                    .map(|_| ())
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let interactive = {
+            let slots = slots.clone();
+            tokio::spawn(async move { slots.acquire(RequestPriority::Interactive, None).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        drop(running);
+        let slot = interactive.await.unwrap().unwrap();
+        assert!(!batch.is_finished());
+        drop(slot);
+        batch.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_wait_leaves_the_queue() {
+        let slots = CallLimits::new().slots("browser", 1, None);
+        let running = slots.try_acquire().unwrap();
+        let timed_out = tokio::time::timeout(
+            Duration::from_millis(10),
+            slots.acquire(RequestPriority::Interactive, Some(1)),
+        )
+        .await;
+        assert!(timed_out.is_err());
+
+        // The queue has room again, and the slot is free once given back
+        drop(running);
+        assert!(slots.try_acquire().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_slots_shrink_on_failures() {
+        let limits = CallLimits::new();
+        let adaptive = AdaptiveConcurrencyConfig {
+            initial_limit: 2,
+            ..Default::default()
+        };
+        let slots = limits.slots("browser", 4, Some(&adaptive));
+        let calls = [slots.try_acquire().unwrap(), slots.try_acquire().unwrap()];
+        assert_eq!(slots.try_acquire().err(), Some(SlotError::Busy));
+
+        // Neither call got a reply
+        drop(calls);
+        assert_eq!(limits.limit("browser"), Some(1));
+        let call = slots.try_acquire().unwrap();
+        assert!(slots.try_acquire().is_err());
+        call.succeeded();
+    }
+}
@@ -0,0 +1,283 @@
+//! Failed tool calls, kept in a bounded store and optionally in SQLite, so
+//! failures that happen while nobody is watching can be investigated and
+//! replayed later
+
+use crate::config::DeadLetterConfig;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// A tool call that failed, with what is needed to understand and repeat it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetter {
+    pub id: u64,
+    pub timestamp: DateTime<Utc>,
+    /// The tool as the client named it
+    pub tool: String,
+    pub arguments: Value,
+    /// The server the call was routed to, when it got that far
+    pub server: Option<String>,
+    /// The JSON-RPC error reported to the client
+    pub error: Value,
+    pub duration_ms: u64,
+    /// The server's condition when the call failed
+    pub server_state: Option<ServerSnapshot>,
+    pub correlation_id: String,
+    pub client: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerSnapshot {
+    pub state: String,
+    pub restart_count: u32,
+    pub in_maintenance: bool,
+    /// Whether the last health check passed, if one ran
+    pub healthy: Option<bool>,
+    pub in_flight: usize,
+}
+
+/// The most recent failed calls, oldest first
+pub struct DeadLetterQueue {
+    max_entries: usize,
+    entries: Mutex<VecDeque<DeadLetter>>,
+    next_id: Mutex<u64>,
+    store: Mutex<Option<DeadLetterStore>>,
+}
+
+impl DeadLetterQueue {
+    /// A queue for the configuration, loading persisted entries; `None` when
+    /// dead-letter capture is disabled
+    pub fn new(config: &DeadLetterConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        let queue = Self {
+            max_entries: config.max_entries,
+            entries: Mutex::new(VecDeque::new()),
+            next_id: Mutex::new(1),
+            store: Mutex::new(None),
+        };
+        if config.persist {
+            match DeadLetterStore::open(&config.sqlite_path) {
+                Ok(store) => {
+                    match store.load(config.max_entries) {
+                        Ok(entries) => queue.restore(entries),
+                        Err(e) => tracing::warn!("Failed to load dead letters: {}", e),
+                    }
+                    *queue.store.lock().unwrap() = Some(store);
+                }
+                Err(e) => tracing::warn!(
+                    "Keeping dead letters in memory only; cannot open {}: {}",
+                    config.sqlite_path.display(),
+                    e
+                ),
+            }
+        }
+        Some(queue)
+    }
+
+    fn restore(&self, entries: Vec<DeadLetter>) {
+        let mut next_id = self.next_id.lock().unwrap();
+        *next_id = entries.iter().map(|e| e.id + 1).max().unwrap_or(1);
+        self.entries.lock().unwrap().extend(entries);
+    }
+
+    /// Keep a failed call, assigning its id, and drop the oldest beyond the
+    /// limit. Returns the id.
+    pub fn record(&self, mut entry: DeadLetter) -> u64 {
+        entry.id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            let id = *next_id;
+            *next_id += 1;
+            id
+        };
+        let id = entry.id;
+
+        if let Some(store) = &mut *self.store.lock().unwrap() {
+            if let Err(e) = store.save(&entry, self.max_entries) {
+                tracing::warn!("Failed to save dead letter: {}", e);
+            }
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.push_back(entry);
+        while entries.len() > self.max_entries {
+            entries.pop_front();
+        }
+        id
+    }
+
+    /// Entries newest first, optionally for one server
+    pub fn list(&self, server: Option<&str>, limit: usize) -> Vec<DeadLetter> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|e| server.is_none_or(|server| e.server.as_deref() == Some(server)))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub fn get(&self, id: u64) -> Option<DeadLetter> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|e| e.id == id)
+            .cloned()
+    }
+
+    pub fn remove(&self, id: u64) -> Option<DeadLetter> {
+        let mut entries = self.entries.lock().unwrap();
+        let index = entries.iter().position(|e| e.id == id)?;
+        if let Some(store) = &*self.store.lock().unwrap() {
+            if let Err(e) = store.delete(Some(id)) {
+                tracing::warn!("Failed to delete dead letter: {}", e);
+            }
+        }
+        entries.remove(index)
+    }
+
+    /// Drop every entry, returning how many there were
+    pub fn clear(&self) -> usize {
+        if let Some(store) = &*self.store.lock().unwrap() {
+            if let Err(e) = store.delete(None) {
+                tracing::warn!("Failed to clear dead letters: {}", e);
+            }
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let count = entries.len();
+        entries.clear();
+        count
+    }
+}
+
+/// Where entries are kept across restarts, as JSON documents
+struct DeadLetterStore(rusqlite::Connection);
+
+impl DeadLetterStore {
+    fn open(path: &Path) -> rusqlite::Result<Self> {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        Self::init(rusqlite::Connection::open(path)?)
+    }
+
+    fn init(conn: rusqlite::Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS dead_letters (
+                id INTEGER PRIMARY KEY,
+                entry TEXT NOT NULL
+            );",
+        )?;
+        Ok(Self(conn))
+    }
+
+    /// The newest `limit` entries, oldest first
+    fn load(&self, limit: usize) -> rusqlite::Result<Vec<DeadLetter>> {
+        let mut statement = self
+            .0
+            .prepare("SELECT entry FROM dead_letters ORDER BY id DESC LIMIT ?1")?;
+        let rows = statement.query_map([limit as i64], |row| row.get::<_, String>(0))?;
+        let mut entries = Vec::new();
+        for row in rows {
+            match serde_json::from_str(&row?) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => tracing::warn!("Skipping unreadable dead letter: {}", e),
+            }
+        }
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Store an entry and delete all but the newest `keep`
+    fn save(&mut self, entry: &DeadLetter, keep: usize) -> rusqlite::Result<()> {
+        let json = serde_json::to_string(entry)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let transaction = self.0.transaction()?;
+        transaction.execute(
+            "INSERT OR REPLACE INTO dead_letters (id, entry) VALUES (?1, ?2)",
+            rusqlite::params![entry.id as i64, json],
+        )?;
+        transaction.execute(
+            "DELETE FROM dead_letters WHERE id <= ?1",
+            [entry.id as i64 - keep as i64],
+        )?;
+        transaction.commit()
+    }
+
+    /// Delete one entry, or all of them
+    fn delete(&self, id: Option<u64>) -> rusqlite::Result<()> {
+        match id {
+            Some(id) => self
+                .0
+                .execute("DELETE FROM dead_letters WHERE id = ?1", [id as i64])?,
+            None => self.0.execute("DELETE FROM dead_letters", [])?,
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(tool: &str, server: &str) -> DeadLetter {
+        DeadLetter {
+            id: 0,
+            timestamp: Utc::now(),
+            tool: tool.to_string(),
+            arguments: json!({ "q": "bug" }),
+            server: Some(server.to_string()),
+            error: json!({ "code": -32003, "message": "Timeout error" }),
+            duration_ms: 30_000,
+            server_state: None,
+            correlation_id: "c-1".to_string(),
+            client: None,
+        }
+    }
+
+    #[test]
+    fn test_queue_is_bounded_and_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = DeadLetterConfig {
+            enabled: true,
+            max_entries: 2,
+            persist: true,
+            sqlite_path: dir.path().join("dead-letter.db"),
+        };
+
+        let queue = DeadLetterQueue::new(&config).unwrap();
+        queue.record(entry("search", "github"));
+        let second = queue.record(entry("query", "postgres"));
+        let third = queue.record(entry("search", "github"));
+
+        // The oldest entry made room, and the newest is listed first
+        let ids: Vec<u64> = queue.list(None, 10).iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![third, second]);
+        assert_eq!(queue.list(Some("postgres"), 10)[0].tool, "query");
+
+        assert!(queue.remove(second).is_some());
+        assert!(queue.get(second).is_none());
+
+        // Entries, and the id sequence, survive a restart
+        let reopened = DeadLetterQueue::new(&config).unwrap();
+        assert_eq!(reopened.list(None, 10), queue.list(None, 10));
+        assert_eq!(reopened.record(entry("search", "github")), third + 1);
+
+        assert_eq!(reopened.clear(), 2);
+        assert!(DeadLetterQueue::new(&config)
+            .unwrap()
+            .list(None, 10)
+            .is_empty());
+
+        assert!(DeadLetterQueue::new(&DeadLetterConfig::default()).is_none());
+    }
+}
@@ -0,0 +1,195 @@
+use super::SharedState;
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Why and when a server was disabled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisabledServer {
+    pub reason: String,
+    /// Disabled automatically because the server kept restarting
+    #[serde(default)]
+    pub quarantined: bool,
+    /// The server was enabled in the config until it was disabled, so
+    /// enabling it again turns it back on
+    #[serde(default = "default_was_enabled")]
+    pub was_enabled: bool,
+    pub disabled_at: DateTime<Utc>,
+}
+
+fn default_was_enabled() -> bool {
+    true
+}
+
+/// Servers that must not be started, optionally persisted to a JSON file so
+/// the set survives proxy restarts, and to shared state so other proxy
+/// instances see it. Reads use the local copy, which `run_refresher` keeps
+/// up to date with the shared set.
+#[derive(Default)]
+pub struct DisabledServers {
+    path: RwLock<Option<PathBuf>>,
+    servers: RwLock<HashMap<String, DisabledServer>>,
+    shared: RwLock<Option<Arc<SharedState>>>,
+}
+
+impl DisabledServers {
+    /// Create an in-memory set; call `load` to attach a file
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `~/.mcp-proxy/disabled-servers.json`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".mcp-proxy").join("disabled-servers.json"))
+    }
+
+    /// Load entries from `path` and persist later changes there. A missing
+    /// file is treated as empty.
+    pub async fn load(&self, path: &Path) -> Result<()> {
+        let servers = match tokio::fs::read_to_string(path).await {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        *self.servers.write().await = servers;
+        *self.path.write().await = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Share the set with other instances. Entries only known locally are
+    /// published; from then on the shared set is authoritative.
+    pub async fn attach_shared(&self, shared: Arc<SharedState>) -> Result<()> {
+        let remote = shared.disabled_servers().await?;
+        for (name, entry) in self.servers.read().await.iter() {
+            if !remote.contains_key(name) {
+                shared.set_disabled(name, entry).await?;
+            }
+        }
+
+        *self.shared.write().await = Some(shared);
+        self.refresh().await;
+        Ok(())
+    }
+
+    pub async fn get(&self, name: &str) -> Option<DisabledServer> {
+        self.servers.read().await.get(name).cloned()
+    }
+
+    pub async fn is_disabled(&self, name: &str) -> bool {
+        self.servers.read().await.contains_key(name)
+    }
+
+    pub async fn list(&self) -> HashMap<String, DisabledServer> {
+        self.servers.read().await.clone()
+    }
+
+    pub async fn disable(&self, name: &str, entry: DisabledServer) -> Result<()> {
+        if let Some(shared) = self.shared.read().await.as_ref() {
+            shared.set_disabled(name, &entry).await?;
+        }
+        self.servers.write().await.insert(name.to_string(), entry);
+        self.save().await
+    }
+
+    /// Remove a server from the set, returning its entry if it was disabled
+    pub async fn enable(&self, name: &str) -> Result<Option<DisabledServer>> {
+        self.refresh().await;
+        if let Some(shared) = self.shared.read().await.as_ref() {
+            shared.clear_disabled(name).await?;
+        }
+        let removed = self.servers.write().await.remove(name);
+        if removed.is_some() {
+            self.save().await?;
+        }
+        Ok(removed)
+    }
+
+    /// Pick up servers other instances disabled or enabled every
+    /// `interval` until shutdown. Does nothing without shared state.
+    pub async fn run_refresher(
+        self: Arc<Self>,
+        interval: std::time::Duration,
+        mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+    ) {
+        if self.shared.read().await.is_none() {
+            return;
+        }
+
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => self.refresh().await,
+                _ = shutdown_rx.recv() => break,
+            }
+        }
+    }
+
+    /// Pick up changes made by other instances, keeping the local copy if
+    /// shared state is unreachable
+    async fn refresh(&self) {
+        let Some(shared) = self.shared.read().await.clone() else {
+            return;
+        };
+
+        match shared.disabled_servers().await {
+            Ok(servers) => *self.servers.write().await = servers,
+            Err(e) => tracing::warn!("Using local disabled servers: {}", e),
+        }
+    }
+
+    async fn save(&self) -> Result<()> {
+        let Some(path) = self.path.read().await.clone() else {
+            return Ok(());
+        };
+
+        let content = serde_json::to_string_pretty(&*self.servers.read().await)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, content).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_disabled_servers_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("disabled-servers.json");
+
+        let disabled = DisabledServers::new();
+        disabled.load(&path).await.unwrap();
+        disabled
+            .disable(
+                "flaky",
+                DisabledServer {
+                    reason: "restarted 6 times in 10 minutes".to_string(),
+                    quarantined: true,
+                    was_enabled: true,
+                    disabled_at: Utc::now(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let reloaded = DisabledServers::new();
+        reloaded.load(&path).await.unwrap();
+        assert!(reloaded.get("flaky").await.unwrap().quarantined);
+
+        assert!(reloaded.enable("flaky").await.unwrap().is_some());
+        assert!(reloaded.enable("flaky").await.unwrap().is_none());
+
+        let reloaded = DisabledServers::new();
+        reloaded.load(&path).await.unwrap();
+        assert!(!reloaded.is_disabled("flaky").await);
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Tracks in-flight proxy requests so shutdown can stop taking new ones and
+/// wait for the rest to finish
+pub struct DrainState {
+    draining: watch::Sender<bool>,
+    in_flight: watch::Sender<usize>,
+    /// Set when shutdown was asked for, possibly before draining begins
+    terminating: AtomicBool,
+}
+
+impl Default for DrainState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DrainState {
+    pub fn new() -> Self {
+        Self {
+            draining: watch::channel(false).0,
+            in_flight: watch::channel(0).0,
+            terminating: AtomicBool::new(false),
+        }
+    }
+
+    /// Register a request for as long as the guard lives, or `None` once
+    /// draining has begun
+    pub fn track(&self) -> Option<InFlightGuard<'_>> {
+        // Count first so a drain starting concurrently cannot miss us
+        self.in_flight.send_modify(|count| *count += 1);
+        let guard = InFlightGuard { drain: self };
+
+        if self.is_draining() {
+            return None;
+        }
+        Some(guard)
+    }
+
+    /// Stop accepting new requests
+    pub fn start(&self) {
+        self.draining.send_replace(true);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        *self.draining.borrow()
+    }
+
+    /// Record that shutdown was asked for. Requests are still taken until
+    /// [`Self::start`].
+    pub fn mark_terminating(&self) {
+        self.terminating.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether shutdown was asked for or draining has begun
+    pub fn is_terminating(&self) -> bool {
+        self.terminating.load(Ordering::SeqCst) || self.is_draining()
+    }
+
+    pub fn in_flight(&self) -> usize {
+        *self.in_flight.borrow()
+    }
+
+    /// Resolves once draining has begun
+    pub async fn started(&self) {
+        let mut draining = self.draining.subscribe();
+        let _ = draining.wait_for(|draining| *draining).await;
+    }
+
+    /// Wait up to `timeout` for in-flight requests to finish, returning how
+    /// many are still running
+    pub async fn wait_idle(&self, timeout: Duration) -> usize {
+        let mut in_flight = self.in_flight.subscribe();
+        let _ = tokio::time::timeout(timeout, in_flight.wait_for(|count| *count == 0)).await;
+        self.in_flight()
+    }
+}
+
+/// Marks a request as in flight until dropped
+pub struct InFlightGuard<'a> {
+    drain: &'a DrainState,
+}
+
+impl InFlightGuard<'_> {
+    /// Resolves once the request has run `timeout` past the start of draining
+    pub async fn deadline(&self, timeout: Duration) {
+        self.drain.started().await;
+        tokio::time::sleep(timeout).await;
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.drain.in_flight.send_modify(|count| *count -= 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_in_flight_requests() {
+        let drain = std::sync::Arc::new(DrainState::new());
+        let guard = drain.track().unwrap();
+        assert_eq!(drain.in_flight(), 1);
+
+        drain.start();
+        assert!(drain.track().is_none());
+        assert_eq!(drain.in_flight(), 1);
+        assert_eq!(drain.wait_idle(Duration::from_millis(10)).await, 1);
+
+        let waiter = {
+            let drain = drain.clone();
+            tokio::spawn(async move { drain.wait_idle(Duration::from_secs(5)).await })
+        };
+        drop(guard);
+        assert_eq!(waiter.await.unwrap(), 0);
+    }
+}
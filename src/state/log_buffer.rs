@@ -0,0 +1,140 @@
+use super::LogEntry;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Default number of log lines kept in memory per server
+pub const DEFAULT_LOG_BUFFER_LINES: usize = 1000;
+
+/// Fixed-size in-memory history of a server's recent log entries, so clients
+/// that connect after a line was broadcast can still see it
+pub struct LogBuffer {
+    entries: Mutex<VecDeque<LogEntry>>,
+    capacity: usize,
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOG_BUFFER_LINES)
+    }
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(
+                capacity.min(DEFAULT_LOG_BUFFER_LINES),
+            )),
+            capacity,
+        }
+    }
+
+    /// Append an entry, evicting the oldest once the buffer is full
+    pub fn push(&self, entry: LogEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Return up to `lines` of the most recent entries at or above `min_level`,
+    /// oldest first
+    pub fn query(&self, lines: usize, min_level: Option<&str>) -> Vec<LogEntry> {
+        let entries = self.entries.lock().unwrap();
+        let mut matched: Vec<LogEntry> = entries
+            .iter()
+            .rev()
+            .filter(|entry| min_level.is_none_or(|level| level_matches(&entry.level, level)))
+            .take(lines)
+            .cloned()
+            .collect();
+        matched.reverse();
+        matched
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+/// Severity rank for a log level name, if it is a known level
+fn level_rank(level: &str) -> Option<u8> {
+    match level.to_ascii_lowercase().as_str() {
+        "trace" => Some(0),
+        "debug" => Some(1),
+        "info" => Some(2),
+        "warn" | "warning" => Some(3),
+        "error" => Some(4),
+        _ => None,
+    }
+}
+
+/// Check whether an entry's level is at or above the requested minimum level.
+/// Unknown level names only match themselves.
+pub fn level_matches(entry_level: &str, min_level: &str) -> bool {
+    match (level_rank(entry_level), level_rank(min_level)) {
+        (Some(entry), Some(min)) => entry >= min,
+        _ => entry_level.eq_ignore_ascii_case(min_level),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn entry(level: &str, message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: Utc::now(),
+            level: level.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_evicts_oldest() {
+        let buffer = LogBuffer::new(3);
+        for i in 0..5 {
+            buffer.push(entry("info", &format!("line {i}")));
+        }
+
+        let messages: Vec<_> = buffer
+            .query(10, None)
+            .into_iter()
+            .map(|e| e.message)
+            .collect();
+        assert_eq!(messages, vec!["line 2", "line 3", "line 4"]);
+    }
+
+    #[test]
+    fn test_query_filters_by_minimum_level() {
+        let buffer = LogBuffer::new(10);
+        buffer.push(entry("debug", "noise"));
+        buffer.push(entry("error", "first failure"));
+        buffer.push(entry("warn", "slow"));
+        buffer.push(entry("error", "second failure"));
+
+        let errors = buffer.query(10, Some("error"));
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].message, "first failure");
+
+        let warnings = buffer.query(10, Some("warn"));
+        assert_eq!(warnings.len(), 3);
+
+        // Only the most recent matches are returned
+        let last = buffer.query(1, Some("error"));
+        assert_eq!(last[0].message, "second failure");
+    }
+}
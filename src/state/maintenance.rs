@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Why and since when a server is out of rotation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceInfo {
+    pub reason: String,
+    pub since: DateTime<Utc>,
+}
+
+/// Servers taken out of rotation while they keep running.
+///
+/// Servers in maintenance are left out of aggregated lists and reject new
+/// calls, but their connections and in-flight requests are untouched. The
+/// generation changes on every transition so cached aggregates can tell
+/// they are stale.
+#[derive(Default)]
+pub struct MaintenanceRegistry {
+    servers: DashMap<String, MaintenanceInfo>,
+    generation: AtomicU64,
+}
+
+impl MaintenanceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Put a server into maintenance, returning false if it already was
+    pub fn enter(&self, name: &str, reason: Option<String>) -> bool {
+        if self.servers.contains_key(name) {
+            return false;
+        }
+
+        self.servers.insert(
+            name.to_string(),
+            MaintenanceInfo {
+                reason: reason.unwrap_or_else(|| "Server is in maintenance mode".to_string()),
+                since: Utc::now(),
+            },
+        );
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        true
+    }
+
+    /// Return a server to rotation, returning its maintenance info if it had any
+    pub fn exit(&self, name: &str) -> Option<MaintenanceInfo> {
+        let removed = self.servers.remove(name).map(|(_, info)| info);
+        if removed.is_some() {
+            self.generation.fetch_add(1, Ordering::SeqCst);
+        }
+        removed
+    }
+
+    pub fn get(&self, name: &str) -> Option<MaintenanceInfo> {
+        self.servers.get(name).map(|info| info.clone())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.servers.contains_key(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.servers.is_empty()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+}
@@ -1,5 +1,14 @@
-use prometheus::{Histogram, IntCounter, IntGauge, Registry};
-use std::sync::Arc;
+//! The proxy's Prometheus metrics, and snapshots of them kept in SQLite so
+//! counters carry on from where they were after a restart
+
+use chrono::{DateTime, Utc};
+use prometheus::core::Collector;
+use prometheus::proto::{MetricFamily, MetricType};
+use prometheus::{Histogram, IntCounter, IntCounterVec, IntGauge, Registry};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 pub struct Metrics {
@@ -28,6 +37,43 @@ pub struct Metrics {
     pub plugin_errors_total: IntCounter,
     pub plugin_timeouts_total: IntCounter,
     pub plugin_execution_duration: Histogram,
+
+    // Tool result size metrics, by server and tool
+    pub tool_results_total: IntCounterVec,
+    pub tool_result_tokens_total: IntCounterVec,
+
+    // Restarts seen through restored snapshots, and when this process
+    // started, so dashboards can mark restarts
+    pub restarts_total: IntCounter,
+    pub start_time: IntGauge,
+
+    /// Histogram totals from a restored snapshot, added to the live ones
+    /// when gathered since a histogram cannot be set
+    restored: Mutex<HashMap<String, HistogramTotals>>,
+}
+
+/// The counters and histograms at one moment
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub taken_at: DateTime<Utc>,
+    pub counters: Vec<CounterValue>,
+    pub histograms: Vec<HistogramTotals>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CounterValue {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistogramTotals {
+    pub name: String,
+    pub count: u64,
+    pub sum: f64,
+    /// Upper bound and cumulative count of each bucket
+    pub buckets: Vec<(f64, u64)>,
 }
 
 impl Default for Metrics {
@@ -110,6 +156,35 @@ impl Metrics {
         ))
         .expect("metric creation failed");
 
+        let tool_results_total = IntCounterVec::new(
+            prometheus::Opts::new(
+                "mcp_proxy_tool_results_total",
+                "Total number of tool results returned by backend servers",
+            ),
+            &["server", "tool"],
+        )
+        .expect("metric creation failed");
+        let tool_result_tokens_total = IntCounterVec::new(
+            prometheus::Opts::new(
+                "mcp_proxy_tool_result_tokens_total",
+                "Estimated tokens in tool results returned by backend servers",
+            ),
+            &["server", "tool"],
+        )
+        .expect("metric creation failed");
+
+        let restarts_total = IntCounter::new(
+            "mcp_proxy_restarts_total",
+            "Number of times the proxy restarted with persisted metrics",
+        )
+        .expect("metric creation failed");
+        let start_time = IntGauge::new(
+            "mcp_proxy_start_time_seconds",
+            "Unix time the proxy started",
+        )
+        .expect("metric creation failed");
+        start_time.set(Utc::now().timestamp());
+
         // Register all metrics
         registry.register(Box::new(total_servers.clone())).unwrap();
         registry
@@ -147,6 +222,14 @@ impl Metrics {
         registry
             .register(Box::new(plugin_execution_duration.clone()))
             .unwrap();
+        registry
+            .register(Box::new(tool_results_total.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(tool_result_tokens_total.clone()))
+            .unwrap();
+        registry.register(Box::new(restarts_total.clone())).unwrap();
+        registry.register(Box::new(start_time.clone())).unwrap();
 
         Self {
             registry,
@@ -164,6 +247,11 @@ impl Metrics {
             plugin_errors_total,
             plugin_timeouts_total,
             plugin_execution_duration,
+            tool_results_total,
+            tool_result_tokens_total,
+            restarts_total,
+            start_time,
+            restored: Mutex::new(HashMap::new()),
         }
     }
 
@@ -239,8 +327,239 @@ impl Metrics {
         self.plugin_timeouts_total.inc();
     }
 
-    pub fn gather_metrics(&self) -> Vec<prometheus::proto::MetricFamily> {
-        self.registry.gather()
+    /// Count a tool result and its estimated size in tokens
+    pub fn record_tool_result(&self, server_name: &str, tool: &str, tokens: u64) {
+        self.tool_results_total
+            .with_label_values(&[server_name, tool])
+            .inc();
+        self.tool_result_tokens_total
+            .with_label_values(&[server_name, tool])
+            .inc_by(tokens);
+    }
+
+    pub fn gather_metrics(&self) -> Vec<MetricFamily> {
+        let mut families = self.registry.gather();
+        let restored = self.restored.lock().unwrap();
+        if restored.is_empty() {
+            return families;
+        }
+        for family in &mut families {
+            let Some(before) = restored.get(family.get_name()) else {
+                continue;
+            };
+            let Some(histogram) = family.mut_metric().first_mut() else {
+                continue;
+            };
+            let histogram = histogram.mut_histogram();
+            let bounds_match = histogram.get_bucket().len() == before.buckets.len()
+                && histogram
+                    .get_bucket()
+                    .iter()
+                    .zip(&before.buckets)
+                    .all(|(bucket, (bound, _))| bucket.get_upper_bound() == *bound);
+            if !bounds_match {
+                continue;
+            }
+            histogram.set_sample_count(histogram.get_sample_count() + before.count);
+            histogram.set_sample_sum(histogram.get_sample_sum() + before.sum);
+            for (bucket, (_, count)) in histogram.mut_bucket().iter_mut().zip(&before.buckets) {
+                bucket.set_cumulative_count(bucket.get_cumulative_count() + count);
+            }
+        }
+        families
+    }
+
+    /// The counters and unlabelled histograms, restored values included
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let mut snapshot = MetricsSnapshot {
+            taken_at: Utc::now(),
+            ..Default::default()
+        };
+        for family in self.gather_metrics() {
+            let name = family.get_name().to_string();
+            match family.get_field_type() {
+                MetricType::COUNTER => {
+                    snapshot
+                        .counters
+                        .extend(family.get_metric().iter().map(|metric| {
+                            CounterValue {
+                                name: name.clone(),
+                                labels: metric
+                                    .get_label()
+                                    .iter()
+                                    .map(|pair| {
+                                        (pair.get_name().to_string(), pair.get_value().to_string())
+                                    })
+                                    .collect(),
+                                value: metric.get_counter().get_value() as u64,
+                            }
+                        }))
+                }
+                MetricType::HISTOGRAM => {
+                    if let [metric] = family.get_metric() {
+                        let histogram = metric.get_histogram();
+                        snapshot.histograms.push(HistogramTotals {
+                            name,
+                            count: histogram.get_sample_count(),
+                            sum: histogram.get_sample_sum(),
+                            buckets: histogram
+                                .get_bucket()
+                                .iter()
+                                .map(|b| (b.get_upper_bound(), b.get_cumulative_count()))
+                                .collect(),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        snapshot
+    }
+
+    /// Carry on from a snapshot taken by a previous run, counting the
+    /// restart. Counters are added to rather than set, so anything counted
+    /// before the restore is kept.
+    pub fn restore(&self, snapshot: &MetricsSnapshot) {
+        let counters = [
+            &self.total_requests,
+            &self.failed_requests,
+            &self.connection_errors,
+            &self.health_checks_total,
+            &self.health_checks_failed,
+            &self.plugin_executions_total,
+            &self.plugin_errors_total,
+            &self.plugin_timeouts_total,
+            &self.restarts_total,
+        ];
+        let counter_vecs = [&self.tool_results_total, &self.tool_result_tokens_total];
+        let named = |collector: &dyn Collector, name: &str| {
+            collector.desc().first().is_some_and(|d| d.fq_name == name)
+        };
+
+        for saved in &snapshot.counters {
+            if let Some(counter) = counters.iter().find(|c| named(**c, &saved.name)) {
+                counter.inc_by(saved.value);
+            } else if let Some(vec) = counter_vecs.iter().find(|v| named(**v, &saved.name)) {
+                let labels: HashMap<&str, &str> = saved
+                    .labels
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect();
+                if let Ok(counter) = vec.get_metric_with(&labels) {
+                    counter.inc_by(saved.value);
+                }
+            }
+        }
+        self.restarts_total.inc();
+
+        let mut restored = self.restored.lock().unwrap();
+        for histogram in &snapshot.histograms {
+            restored.insert(histogram.name.clone(), histogram.clone());
+        }
+    }
+}
+
+/// Where the latest snapshot is kept across restarts
+pub(crate) struct MetricsStore(rusqlite::Connection);
+
+impl MetricsStore {
+    pub(crate) fn open(path: &Path) -> rusqlite::Result<Self> {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        Self::init(rusqlite::Connection::open(path)?)
+    }
+
+    fn init(conn: rusqlite::Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS metrics_snapshot (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                taken_at INTEGER NOT NULL,
+                data TEXT NOT NULL
+            );",
+        )?;
+        Ok(Self(conn))
+    }
+
+    pub(crate) fn load(&self) -> rusqlite::Result<Option<MetricsSnapshot>> {
+        let data: Option<String> = self
+            .0
+            .query_row(
+                "SELECT data FROM metrics_snapshot WHERE id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .map(Some)
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                e => Err(e),
+            })?;
+        data.map(|data| {
+            serde_json::from_str(&data).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })
+        })
+        .transpose()
+    }
+
+    pub(crate) fn save(&self, snapshot: &MetricsSnapshot) -> rusqlite::Result<()> {
+        let data = serde_json::to_string(snapshot)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        self.0.execute(
+            "INSERT OR REPLACE INTO metrics_snapshot (id, taken_at, data) VALUES (1, ?1, ?2)",
+            rusqlite::params![snapshot.taken_at.timestamp(), data],
+        )?;
+        Ok(())
+    }
+}
+
+/// Restore the metrics saved by the previous run, then save them every
+/// `metricsPersistence.intervalSeconds` and once more at shutdown
+pub async fn run_persister(
+    state: Arc<super::AppState>,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    let config = state.config.read().await.metrics_persistence.clone();
+    if !config.enabled {
+        return;
+    }
+    let store = match MetricsStore::open(&config.sqlite_path) {
+        Ok(store) => store,
+        Err(e) => {
+            tracing::warn!(
+                "Metrics will start from zero after a restart; cannot open {}: {}",
+                config.sqlite_path.display(),
+                e
+            );
+            return;
+        }
+    };
+    match store.load() {
+        Ok(Some(snapshot)) => {
+            state.metrics.restore(&snapshot);
+            tracing::info!("Restored metrics saved at {}", snapshot.taken_at);
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to load saved metrics: {}", e),
+    }
+
+    let interval = config.interval();
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    loop {
+        let shutting_down = tokio::select! {
+            _ = ticker.tick() => false,
+            _ = shutdown_rx.recv() => true,
+        };
+        if let Err(e) = store.save(&state.metrics.snapshot()) {
+            tracing::warn!("Failed to save metrics: {}", e);
+        }
+        if shutting_down {
+            break;
+        }
     }
 }
 
@@ -269,3 +588,51 @@ impl RequestTimer {
         self.metrics.record_failed_request();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_carry_on_after_restore() {
+        let before = Metrics::new();
+        before.total_requests.inc_by(7);
+        before.record_tool_result("github", "search", 120);
+        before.record_request_duration(std::time::Duration::from_millis(20));
+        before.record_request_duration(std::time::Duration::from_millis(300));
+
+        let store = MetricsStore::init(rusqlite::Connection::open_in_memory().unwrap()).unwrap();
+        assert_eq!(store.load().unwrap(), None);
+        store.save(&before.snapshot()).unwrap();
+        let snapshot = store.load().unwrap().unwrap();
+
+        let after = Metrics::new();
+        after.total_requests.inc();
+        after.record_request_duration(std::time::Duration::from_millis(20));
+        after.restore(&snapshot);
+
+        assert_eq!(after.total_requests.get(), 8);
+        assert_eq!(
+            after
+                .tool_result_tokens_total
+                .with_label_values(&["github", "search"])
+                .get(),
+            120
+        );
+        assert_eq!(after.restarts_total.get(), 1);
+        let duration = after
+            .gather_metrics()
+            .into_iter()
+            .find(|f| f.get_name() == "mcp_proxy_request_duration_seconds")
+            .unwrap();
+        let histogram = duration.get_metric()[0].get_histogram();
+        assert_eq!(histogram.get_sample_count(), 3);
+        assert!((histogram.get_sample_sum() - 0.34).abs() < 1e-9);
+
+        // A second restart counts on from the first
+        let again = Metrics::new();
+        again.restore(&after.snapshot());
+        assert_eq!(again.restarts_total.get(), 2);
+        assert_eq!(again.total_requests.get(), 8);
+    }
+}
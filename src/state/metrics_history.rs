@@ -0,0 +1,460 @@
+//! Per-server and per-tool request counts, errors and latency over a
+//! rolling window, sampled from the connection pool's counters and the tool
+//! calls recorded since the last sample, and optionally kept in SQLite
+//! across restarts
+
+use super::AppState;
+use crate::transport::pool::PoolStats;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Traffic to one server during one sampling interval
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSample {
+    /// End of the interval
+    pub timestamp: DateTime<Utc>,
+    pub server: String,
+    /// The tool, as clients call it, for a tool's series; `None` for the
+    /// server's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    pub requests: u64,
+    /// Failed requests and JSON-RPC error replies
+    pub errors: u64,
+    /// Mean round trip of the replies received; `None` without replies
+    pub avg_latency_ms: Option<f64>,
+}
+
+/// A server's pool counters at the last sample
+#[derive(Debug, Clone, Copy, Default)]
+struct Totals {
+    requests: u64,
+    errors: u64,
+    responses: u64,
+    latency_ms: f64,
+}
+
+impl Totals {
+    fn of(stats: &PoolStats) -> Self {
+        Self {
+            requests: stats.requests,
+            errors: stats.failures + stats.error_replies,
+            responses: stats.responses,
+            latency_ms: stats.total_latency_ms,
+        }
+    }
+}
+
+/// Samples within the retention window, oldest first
+#[derive(Default)]
+pub struct MetricsHistory {
+    samples: Mutex<VecDeque<MetricsSample>>,
+    previous: Mutex<HashMap<String, Totals>>,
+    /// Tool calls since the last sample, by server and tool
+    tool_calls: Mutex<HashMap<(String, String), Totals>>,
+}
+
+/// Which series a query wants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Series<'a> {
+    /// Each server's
+    Servers,
+    /// One tool's
+    Tool(&'a str),
+    /// Every tool's
+    Tools,
+}
+
+impl Series<'_> {
+    fn matches(self, sample: &MetricsSample) -> bool {
+        match self {
+            Series::Servers => sample.tool.is_none(),
+            Series::Tool(tool) => sample.tool.as_deref() == Some(tool),
+            Series::Tools => sample.tool.is_some(),
+        }
+    }
+}
+
+impl MetricsHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a finished tool call towards the tool's next sample
+    pub fn record_tool_call(&self, server: &str, tool: &str, ok: bool, latency: Duration) {
+        let mut calls = self.tool_calls.lock().unwrap();
+        let totals = calls
+            .entry((server.to_string(), tool.to_string()))
+            .or_default();
+        totals.requests += 1;
+        totals.responses += 1;
+        totals.errors += u64::from(!ok);
+        totals.latency_ms += latency.as_secs_f64() * 1000.0;
+    }
+
+    /// Record the traffic since the last call, from the pool's current
+    /// counters and the tool calls recorded since, and drop samples older
+    /// than `retention`
+    pub fn sample(
+        &self,
+        stats: &[PoolStats],
+        now: DateTime<Utc>,
+        retention: Duration,
+    ) -> Vec<MetricsSample> {
+        let mut previous = self.previous.lock().unwrap();
+        let mut taken: Vec<MetricsSample> = stats
+            .iter()
+            .map(|stats| {
+                let current = Totals::of(stats);
+                // Counters start over when a server is removed and re-added
+                let before = previous
+                    .get(&stats.server)
+                    .copied()
+                    .filter(|before| before.requests <= current.requests)
+                    .unwrap_or_default();
+                previous.insert(stats.server.clone(), current);
+
+                let responses = current.responses.saturating_sub(before.responses);
+                MetricsSample {
+                    timestamp: now,
+                    server: stats.server.clone(),
+                    tool: None,
+                    requests: current.requests - before.requests,
+                    errors: current.errors.saturating_sub(before.errors),
+                    avg_latency_ms: (responses > 0)
+                        .then(|| (current.latency_ms - before.latency_ms) / responses as f64),
+                }
+            })
+            .collect();
+        drop(previous);
+
+        let mut tool_calls: Vec<_> = std::mem::take(&mut *self.tool_calls.lock().unwrap())
+            .into_iter()
+            .collect();
+        tool_calls.sort_by(|a, b| a.0.cmp(&b.0));
+        taken.extend(
+            tool_calls
+                .into_iter()
+                .map(|((server, tool), totals)| MetricsSample {
+                    timestamp: now,
+                    server,
+                    tool: Some(tool),
+                    requests: totals.requests,
+                    errors: totals.errors,
+                    avg_latency_ms: Some(totals.latency_ms / totals.responses as f64),
+                }),
+        );
+
+        self.extend(taken.iter().cloned(), now, retention);
+        taken
+    }
+
+    /// Add samples, such as ones loaded from disk, and drop those older than
+    /// `retention`
+    fn extend(
+        &self,
+        new: impl IntoIterator<Item = MetricsSample>,
+        now: DateTime<Utc>,
+        retention: Duration,
+    ) {
+        let cutoff = now - retention;
+        let mut samples = self.samples.lock().unwrap();
+        samples.extend(new);
+        while samples.front().is_some_and(|s| s.timestamp < cutoff) {
+            samples.pop_front();
+        }
+    }
+
+    /// Samples of `series` since `since`, for one server or all of them
+    pub fn query(
+        &self,
+        server: Option<&str>,
+        series: Series,
+        since: DateTime<Utc>,
+    ) -> Vec<MetricsSample> {
+        self.samples
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| s.timestamp >= since)
+            .filter(|s| server.is_none_or(|server| s.server == server))
+            .filter(|s| series.matches(s))
+            .cloned()
+            .collect()
+    }
+
+    /// Percentage of a server's or tool's requests since `since` that
+    /// failed; `None` without requests
+    pub fn error_rate(
+        &self,
+        server: &str,
+        tool: Option<&str>,
+        since: DateTime<Utc>,
+    ) -> Option<f64> {
+        let series = tool.map_or(Series::Servers, Series::Tool);
+        let (requests, errors) = self
+            .query(Some(server), series, since)
+            .iter()
+            .fold((0, 0), |(requests, errors), s| {
+                (requests + s.requests, errors + s.errors)
+            });
+        (requests > 0).then(|| (errors as f64 / requests as f64 * 100.0).min(100.0))
+    }
+}
+
+/// Where samples are kept across restarts
+struct HistoryStore(rusqlite::Connection);
+
+impl HistoryStore {
+    fn open(path: &Path) -> rusqlite::Result<Self> {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        Self::init(rusqlite::Connection::open(path)?)
+    }
+
+    fn init(conn: rusqlite::Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS metrics_history (
+                timestamp INTEGER NOT NULL,
+                server TEXT NOT NULL,
+                requests INTEGER NOT NULL,
+                errors INTEGER NOT NULL,
+                avg_latency_ms REAL
+            );
+            CREATE INDEX IF NOT EXISTS idx_metrics_history_timestamp
+                ON metrics_history(timestamp);",
+        )?;
+        // Tool series came later; fails when the column is already there
+        conn.execute("ALTER TABLE metrics_history ADD COLUMN tool TEXT", [])
+            .ok();
+        Ok(Self(conn))
+    }
+
+    fn load(&self, since: DateTime<Utc>) -> rusqlite::Result<Vec<MetricsSample>> {
+        let mut statement = self.0.prepare(
+            "SELECT timestamp, server, tool, requests, errors, avg_latency_ms
+             FROM metrics_history WHERE timestamp >= ?1 ORDER BY timestamp",
+        )?;
+        let rows = statement.query_map([since.timestamp()], |row| {
+            Ok(MetricsSample {
+                timestamp: DateTime::from_timestamp(row.get(0)?, 0).unwrap_or_default(),
+                server: row.get(1)?,
+                tool: row.get(2)?,
+                requests: row.get::<_, i64>(3)? as u64,
+                errors: row.get::<_, i64>(4)? as u64,
+                avg_latency_ms: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Store new samples and delete those before `cutoff`
+    fn save(&mut self, samples: &[MetricsSample], cutoff: DateTime<Utc>) -> rusqlite::Result<()> {
+        let transaction = self.0.transaction()?;
+        {
+            let mut insert = transaction.prepare(
+                "INSERT INTO metrics_history
+                 (timestamp, server, tool, requests, errors, avg_latency_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for sample in samples {
+                insert.execute(rusqlite::params![
+                    sample.timestamp.timestamp(),
+                    sample.server,
+                    sample.tool,
+                    sample.requests as i64,
+                    sample.errors as i64,
+                    sample.avg_latency_ms
+                ])?;
+            }
+        }
+        transaction.execute(
+            "DELETE FROM metrics_history WHERE timestamp < ?1",
+            [cutoff.timestamp()],
+        )?;
+        transaction.commit()
+    }
+}
+
+/// Sample the pool every `metricsHistory.intervalSeconds` until shutdown
+pub async fn run_sampler(
+    state: Arc<AppState>,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    let config = state.config.read().await.metrics_history.clone();
+    if !config.enabled {
+        return;
+    }
+    let retention = config.retention();
+
+    let mut store = None;
+    if config.persist {
+        match HistoryStore::open(&config.sqlite_path) {
+            Ok(opened) => {
+                match opened.load(Utc::now() - retention) {
+                    Ok(samples) => state.metrics_history.extend(samples, Utc::now(), retention),
+                    Err(e) => tracing::warn!("Failed to load metrics history: {}", e),
+                }
+                store = Some(opened);
+            }
+            Err(e) => tracing::warn!(
+                "Keeping metrics history in memory only; cannot open {}: {}",
+                config.sqlite_path.display(),
+                e
+            ),
+        }
+    }
+
+    let interval = config.interval();
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown_rx.recv() => break,
+        }
+        let now = Utc::now();
+        let samples = state
+            .metrics_history
+            .sample(&state.connection_pool.stats(), now, retention);
+        if let Some(store) = &mut store {
+            if let Err(e) = store.save(&samples, now - retention) {
+                tracing::warn!("Failed to save metrics history: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(server: &str, requests: u64, errors: u64, responses: u64, latency: f64) -> PoolStats {
+        PoolStats {
+            server: server.to_string(),
+            connections: 1,
+            in_flight: 0,
+            idle_secs: None,
+            age_secs: None,
+            requests,
+            failures: 0,
+            error_replies: errors,
+            reaped: 0,
+            warm_spares: 0,
+            failovers: 0,
+            cancelled: 0,
+            responses,
+            total_latency_ms: latency,
+            latency: None,
+        }
+    }
+
+    #[test]
+    fn test_samples_are_deltas_within_retention() {
+        let history = MetricsHistory::new();
+        let retention = Duration::from_secs(3600);
+        let start = Utc::now();
+
+        history.sample(&[stats("github", 10, 1, 10, 500.0)], start, retention);
+        let taken = history.sample(
+            &[stats("github", 16, 3, 14, 700.0)],
+            start + Duration::from_secs(60),
+            retention,
+        );
+        assert_eq!(taken[0].requests, 6);
+        assert_eq!(taken[0].errors, 2);
+        assert_eq!(taken[0].avg_latency_ms, Some(50.0));
+
+        // A server whose counters started over
+        let taken = history.sample(
+            &[stats("github", 2, 0, 0, 0.0)],
+            start + Duration::from_secs(120),
+            retention,
+        );
+        assert_eq!(taken[0].requests, 2);
+        assert_eq!(taken[0].avg_latency_ms, None);
+
+        assert_eq!(
+            history.query(Some("github"), Series::Servers, start).len(),
+            3
+        );
+        assert!(history
+            .query(Some("search"), Series::Servers, start)
+            .is_empty());
+
+        // The first sample falls out of the window
+        history.sample(
+            &[stats("github", 2, 0, 0, 0.0)],
+            start + Duration::from_secs(3601),
+            retention,
+        );
+        let kept = history.query(None, Series::Servers, start);
+        assert_eq!(kept.len(), 3);
+        assert_eq!(kept[0].requests, 6);
+    }
+
+    #[test]
+    fn test_tool_series_and_windowed_error_rate() {
+        let history = MetricsHistory::new();
+        let retention = Duration::from_secs(3600);
+        let start = Utc::now();
+
+        history.record_tool_call("github", "github__search", true, Duration::from_millis(30));
+        history.record_tool_call("github", "github__search", false, Duration::from_millis(10));
+        history.record_tool_call("github", "github__issue", true, Duration::from_millis(5));
+        history.sample(&[stats("github", 3, 1, 3, 45.0)], start, retention);
+        // Nothing called since
+        history.sample(
+            &[stats("github", 3, 1, 3, 45.0)],
+            start + Duration::from_secs(60),
+            retention,
+        );
+
+        let search = history.query(None, Series::Tool("github__search"), start);
+        assert_eq!(search.len(), 1);
+        assert_eq!(search[0].requests, 2);
+        assert_eq!(search[0].errors, 1);
+        assert_eq!(search[0].avg_latency_ms, Some(20.0));
+        assert_eq!(history.query(None, Series::Tools, start).len(), 2);
+        assert_eq!(history.query(None, Series::Servers, start).len(), 2);
+
+        assert_eq!(
+            history.error_rate("github", Some("github__search"), start),
+            Some(50.0)
+        );
+        // The window starts after the calls
+        let later = start + Duration::from_secs(30);
+        assert_eq!(history.error_rate("github", None, later), None);
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let mut store =
+            HistoryStore::init(rusqlite::Connection::open_in_memory().unwrap()).unwrap();
+        let now = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap();
+        let sample = |minutes_ago: i64| MetricsSample {
+            timestamp: now - chrono::Duration::minutes(minutes_ago),
+            server: "github".to_string(),
+            tool: (minutes_ago == 0).then(|| "github__search".to_string()),
+            requests: 5,
+            errors: 1,
+            avg_latency_ms: Some(12.5),
+        };
+
+        store
+            .save(&[sample(90), sample(30)], now - chrono::Duration::hours(2))
+            .unwrap();
+        store
+            .save(&[sample(0)], now - chrono::Duration::hours(1))
+            .unwrap();
+        assert_eq!(
+            store.load(now - chrono::Duration::hours(24)).unwrap(),
+            [sample(30), sample(0)]
+        );
+    }
+}
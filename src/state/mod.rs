@@ -3,15 +3,43 @@ use crate::context::tracker::ContextTracker;
 use crate::error::Result;
 use crate::logging::ServerLogger;
 use crate::protocol::ServerConnectionState;
+use crate::recording::SessionRecorder;
 use crate::transport::pool::ConnectionPool;
 use chrono::{DateTime, Utc};
 use dashmap::DashMap;
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::RwLock;
 
+pub mod adaptive_limit;
+pub mod alert_rules;
+pub mod cache_stats;
+pub mod call_limits;
+pub mod dead_letter;
+pub mod disabled;
+pub mod drain;
+pub mod log_buffer;
+pub mod maintenance;
 pub mod metrics;
-
+pub mod metrics_history;
+pub mod shared;
+pub mod translation;
+pub mod usage;
+
+pub use alert_rules::{AlertRules, RuleState, RuleStatus};
+pub use cache_stats::CacheCounters;
+pub use call_limits::CallLimits;
+pub use dead_letter::{DeadLetter, DeadLetterQueue};
+pub use disabled::{DisabledServer, DisabledServers};
+pub use drain::{DrainState, InFlightGuard};
+pub use log_buffer::{LogBuffer, DEFAULT_LOG_BUFFER_LINES};
+pub use maintenance::{MaintenanceInfo, MaintenanceRegistry};
 pub use metrics::Metrics;
+pub use metrics_history::{MetricsHistory, MetricsSample};
+pub use shared::SharedState;
+pub use translation::TranslationReports;
+pub use usage::{QuotaPeriod, Usage, UsageTracker};
 
 // #[cfg(test)]
 // mod server_state_tests; // TODO: Add test module
@@ -44,6 +72,46 @@ pub struct AppState {
     pub server_versions: Arc<DashMap<String, ServerVersion>>,
     /// Protocol connection states per server (for initialization tracking)
     pub connection_states: Arc<DashMap<String, Arc<ServerConnectionState>>>,
+    /// Request router shared by the proxy front-ends (holds per-server request queues)
+    pub request_router: Arc<crate::proxy::RequestRouter>,
+    /// Broadcasts (server name, new state) on every server state transition
+    pub server_state_tx: tokio::sync::broadcast::Sender<(String, ServerState)>,
+    /// Servers that are disabled or quarantined and must not be started
+    pub disabled_servers: Arc<DisabledServers>,
+    /// Broadcasts events operators should be told about
+    pub alert_tx: tokio::sync::broadcast::Sender<AlertEvent>,
+    /// Where configured alert rules stand
+    pub alert_rules: Arc<AlertRules>,
+    /// Servers kept running but taken out of rotation
+    pub maintenance: Arc<MaintenanceRegistry>,
+    /// In-flight proxy requests, tracked so shutdown can wait for them
+    pub drain: Arc<DrainState>,
+    /// State shared with other proxy instances, when configured
+    pub shared_state: Arc<RwLock<Option<Arc<SharedState>>>>,
+    /// Tool calls made by each client, for quotas
+    pub usage: Arc<UsageTracker>,
+    /// Servers' limits on concurrent tool calls
+    pub call_limits: Arc<CallLimits>,
+    /// Recording of the session, when started with `--record`
+    recorder: std::sync::OnceLock<Arc<SessionRecorder>>,
+    /// Catalog answering the list requests, when started with `catalog serve`
+    static_catalog: std::sync::OnceLock<Arc<crate::catalog::Catalog>>,
+    /// Broadcasts notifications for every connected client
+    pub client_notification_tx: tokio::sync::broadcast::Sender<serde_json::Value>,
+    /// Broadcasts a summary of each proxied request, for the dashboard
+    pub traffic_tx: tokio::sync::broadcast::Sender<crate::proxy::traffic::TrafficEvent>,
+    /// Per-server traffic over the last hours, for the dashboard's charts
+    pub metrics_history: Arc<MetricsHistory>,
+    /// Lookups of the request handlers' cached tool lists
+    pub tools_cache: Arc<CacheCounters>,
+    /// Failed tool calls, when dead-letter capture is enabled
+    pub dead_letters: Option<Arc<DeadLetterQueue>>,
+    /// Fields protocol translation has dropped, per server
+    pub translation_reports: Arc<TranslationReports>,
+    /// Blobs served through signed links, when blob downloads are enabled
+    pub blob_downloads: Option<Arc<crate::proxy::blobs::BlobDownloads>>,
+    /// Times a server came up or went away, changing the aggregated lists
+    catalog_changes: std::sync::atomic::AtomicU64,
 }
 
 #[derive(Clone)]
@@ -52,10 +120,14 @@ pub struct ServerInfo {
     pub state: Arc<RwLock<ServerState>>,
     pub process_handle: Option<Arc<tokio::task::JoinHandle<()>>>,
     pub restart_count: Arc<RwLock<u32>>,
+    /// When recent restarts happened, for flap detection
+    pub restart_times: Arc<RwLock<VecDeque<Instant>>>,
     pub last_health_check: Arc<RwLock<Option<HealthCheckStatus>>>,
     pub last_access_time: Arc<RwLock<Option<DateTime<Utc>>>>,
     pub log_subscribers: Arc<DashMap<String, tokio::sync::mpsc::UnboundedSender<LogEntry>>>,
     pub logger: Option<Arc<ServerLogger>>,
+    /// Recent log history replayed to clients that connect late
+    pub log_buffer: Arc<LogBuffer>,
 
     // T021: Protocol connection state for initialization tracking
     pub connection_state: Option<Arc<ServerConnectionState>>,
@@ -68,6 +140,43 @@ pub struct LogEntry {
     pub message: String,
 }
 
+/// Something the proxy did on its own that operators should know about
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct AlertEvent {
+    pub server: String,
+    pub kind: AlertKind,
+    pub message: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// The server restarted too often and was disabled
+    Quarantined,
+    /// The server failed more times than `maxRestarts` allows and was left
+    /// down
+    RestartLimitExceeded,
+    /// Every recent execution of a plugin failed
+    PluginFailing,
+    /// An alert rule's metric stayed above its threshold
+    RuleFiring,
+    /// A firing alert rule's metric is back under its threshold
+    RuleResolved,
+}
+
+impl AlertKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertKind::Quarantined => "quarantined",
+            AlertKind::RestartLimitExceeded => "restart_limit_exceeded",
+            AlertKind::PluginFailing => "plugin_failing",
+            AlertKind::RuleFiring => "rule_firing",
+            AlertKind::RuleResolved => "rule_resolved",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct HealthCheckStatus {
     pub timestamp: DateTime<Utc>,
@@ -77,27 +186,71 @@ pub struct HealthCheckStatus {
 }
 
 impl AppState {
-    pub fn new(config: Config) -> (Arc<Self>, tokio::sync::broadcast::Receiver<()>) {
+    pub fn new(mut config: Config) -> (Arc<Self>, tokio::sync::broadcast::Receiver<()>) {
+        if config.ephemeral {
+            config.make_ephemeral();
+        }
         let (shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel(16);
+        let (server_state_tx, _) = tokio::sync::broadcast::channel(64);
+        let (alert_tx, _) = tokio::sync::broadcast::channel(64);
+        let (client_notification_tx, _) = tokio::sync::broadcast::channel(64);
+        let (traffic_tx, _) = tokio::sync::broadcast::channel(256);
 
         // Initialize plugin manager if plugins are configured
         let plugin_manager = config.plugins.as_ref().map(|plugin_config| {
-            Arc::new(crate::plugin::PluginManager::new(plugin_config.clone()))
+            let mut manager = crate::plugin::PluginManager::new(plugin_config.clone());
+            manager.set_alerts(alert_tx.clone());
+            Arc::new(manager)
         });
 
-        // Create server_versions registry to share with connection pool
+        // Create server_versions and connection_states registries to share with connection pool
         let server_versions = Arc::new(DashMap::new());
+        let connection_states = Arc::new(DashMap::new());
+
+        #[cfg(feature = "draft-protocol")]
+        crate::protocol::ProtocolVersion::enable_draft(config.proxy.draft_protocol);
+
+        let dead_letters = DeadLetterQueue::new(&config.dead_letter).map(Arc::new);
+        let blob_downloads =
+            crate::proxy::blobs::BlobDownloads::new(&config.blobs.downloads, &config.proxy)
+                .map(Arc::new);
+
+        let request_router = Arc::new(crate::proxy::RequestRouter::new());
+        request_router.set_routing(config.routing.clone());
 
         let state = Arc::new(Self {
             config: Arc::new(RwLock::new(config)),
             servers: Arc::new(DashMap::new()),
             metrics: Arc::new(Metrics::new()),
-            connection_pool: Arc::new(ConnectionPool::new(server_versions.clone())),
+            connection_pool: Arc::new(ConnectionPool::new(
+                server_versions.clone(),
+                connection_states.clone(),
+            )),
             shutdown_tx,
             context_tracker: Arc::new(RwLock::new(None)),
             plugin_manager,
             server_versions,
-            connection_states: Arc::new(DashMap::new()),
+            connection_states,
+            request_router,
+            server_state_tx,
+            disabled_servers: Arc::new(DisabledServers::new()),
+            alert_tx,
+            alert_rules: Arc::new(AlertRules::new()),
+            maintenance: Arc::new(MaintenanceRegistry::new()),
+            drain: Arc::new(DrainState::new()),
+            shared_state: Arc::new(RwLock::new(None)),
+            usage: Arc::new(UsageTracker::new()),
+            call_limits: Arc::new(CallLimits::new()),
+            recorder: std::sync::OnceLock::new(),
+            static_catalog: std::sync::OnceLock::new(),
+            client_notification_tx,
+            traffic_tx,
+            metrics_history: Arc::new(MetricsHistory::new()),
+            tools_cache: Arc::new(CacheCounters::default()),
+            dead_letters,
+            translation_reports: Arc::new(TranslationReports::new()),
+            blob_downloads,
+            catalog_changes: std::sync::atomic::AtomicU64::new(0),
         });
 
         (state, shutdown_rx)
@@ -117,19 +270,44 @@ impl AppState {
         &self,
         storage: Arc<dyn crate::context::storage::StorageBackend>,
     ) -> Result<()> {
-        let tracker = Arc::new(ContextTracker::new(storage));
+        let config = self.config.read().await;
+        let storage = match &config.context_tracing.anonymization_key {
+            Some(key) if config.context_tracing.anonymize => Arc::new(
+                crate::context::anonymize::AnonymizingStorage::new(storage, key),
+            ),
+            _ => storage,
+        };
+        let mut tracker = ContextTracker::new(storage);
+        if let Some(embeddings) = &config.context_tracing.embeddings {
+            match crate::context::embedding::provider(embeddings) {
+                Ok(provider) => tracker = tracker.with_embeddings(provider),
+                Err(e) => tracing::error!("Failed to set up context embeddings: {}", e),
+            }
+        }
+        drop(config);
+        let tracker = Arc::new(tracker);
         let mut context_tracker = self.context_tracker.write().await;
         *context_tracker = Some(tracker);
         Ok(())
     }
 
-    pub async fn update_config(&self, new_config: Config) -> Result<()> {
+    pub async fn update_config(&self, mut new_config: Config) -> Result<()> {
         // Validate new config
         crate::config::validate(&new_config)?;
 
+        // A proxy started without disk access can't be given it
+        if new_config.ephemeral || self.config.read().await.ephemeral {
+            new_config.make_ephemeral();
+        }
+
         // Update config
+        self.request_router.set_routing(new_config.routing.clone());
         let mut config = self.config.write().await;
         *config = new_config;
+        drop(config);
+
+        // Filters and routing shape the aggregated lists
+        self.invalidate_catalog().await;
 
         // TODO: Notify all components of config change
 
@@ -159,7 +337,9 @@ impl AppState {
     pub async fn set_server_state(&self, name: &str, new_state: ServerState) -> Result<()> {
         if let Some(info) = self.servers.get(name) {
             let mut state = info.state.write().await;
+            let was_running = *state == ServerState::Running;
             *state = new_state;
+            drop(state);
 
             // Update metrics
             match new_state {
@@ -168,21 +348,112 @@ impl AppState {
                 _ => {}
             }
 
+            // No subscribers is fine; nobody is interested in transitions yet
+            let _ = self.server_state_tx.send((name.to_string(), new_state));
+
+            if was_running != (new_state == ServerState::Running) {
+                self.catalog_changed().await;
+            }
+
             Ok(())
         } else {
             Err(crate::error::ProxyError::ServerNotFound(name.to_string()))
         }
     }
 
+    /// Record the messages exchanged with clients and servers from now on
+    pub fn record_session(&self, recorder: Arc<SessionRecorder>) {
+        self.connection_pool.record_to(recorder.clone());
+        let _ = self.recorder.set(recorder);
+    }
+
+    /// The session recording, if there is one
+    pub fn recorder(&self) -> Option<&Arc<SessionRecorder>> {
+        self.recorder.get()
+    }
+
+    /// Answer the list requests from `catalog` instead of the servers
+    pub fn serve_catalog(&self, catalog: Arc<crate::catalog::Catalog>) {
+        let _ = self.static_catalog.set(catalog);
+    }
+
+    /// The catalog being served, if there is one
+    pub fn static_catalog(&self) -> Option<&Arc<crate::catalog::Catalog>> {
+        self.static_catalog.get()
+    }
+
+    /// Tell operators about something the proxy did on its own
+    pub fn raise_alert(&self, server: &str, kind: AlertKind, message: String) {
+        // No subscribers just means nobody is listening for alerts
+        let _ = self.alert_tx.send(AlertEvent {
+            server: server.to_string(),
+            kind,
+            message,
+            timestamp: Utc::now(),
+        });
+    }
+
+    /// Stop accepting proxy requests and close the listeners, give
+    /// in-flight requests until the drain timeout to finish, then flush
+    /// server logs and context storage. Servers keep running until
+    /// [`Self::shutdown`].
+    pub async fn drain_requests(&self) {
+        let timeout = self.config.read().await.drain_timeout();
+
+        self.drain.start();
+        tracing::info!(
+            "Draining {} in-flight requests (timeout {:?})",
+            self.drain.in_flight(),
+            timeout
+        );
+
+        let remaining = self.drain.wait_idle(timeout).await;
+        if remaining > 0 {
+            tracing::warn!("{} requests still running after drain timeout", remaining);
+        }
+
+        self.flush().await;
+    }
+
+    /// Flush buffered server logs and context tracing data to disk
+    pub async fn flush(&self) {
+        let loggers: Vec<_> = self
+            .servers
+            .iter()
+            .filter_map(|entry| entry.value().logger.clone())
+            .collect();
+        for logger in loggers {
+            if let Err(e) = logger.flush().await {
+                tracing::error!("Failed to flush server log: {}", e);
+            }
+        }
+
+        let tracker = self.context_tracker.read().await.clone();
+        if let Some(tracker) = tracker {
+            if let Err(e) = tracker.flush().await {
+                tracing::error!("Failed to flush context storage: {}", e);
+            }
+        }
+    }
+
+    /// Connect to shared state and share the disabled servers through it
+    pub async fn initialize_shared_state(
+        &self,
+        config: &crate::config::SharedStateConfig,
+    ) -> Result<()> {
+        let shared = Arc::new(SharedState::connect(config).await?);
+        self.disabled_servers.attach_shared(shared.clone()).await?;
+        *self.shared_state.write().await = Some(shared);
+        Ok(())
+    }
+
     pub async fn shutdown(&self) {
         tracing::info!("Initiating application shutdown");
 
-        // Send shutdown signal to all components
+        // Send shutdown signal to all components; the server manager closes
+        // connections in dependency order
         let _ = self.shutdown_tx.send(());
 
-        // Close all connections
-        let _ = self.connection_pool.close_all().await;
-
         // Stop all servers
         for entry in self.servers.iter() {
             let mut state = entry.value().state.write().await;
@@ -190,6 +461,42 @@ impl AppState {
         }
     }
 
+    /// Changes whenever the servers behind the aggregated tool, resource and
+    /// prompt lists change, so cached lists can be checked against it
+    pub fn catalog_generation(&self) -> u64 {
+        self.maintenance.generation()
+            + self
+                .catalog_changes
+                .load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// A server came up or went away: drop shared lists and tell clients
+    /// to fetch theirs again
+    async fn catalog_changed(&self) {
+        self.invalidate_catalog().await;
+        for method in crate::proxy::LIST_CHANGED_NOTIFICATIONS {
+            let _ = self
+                .client_notification_tx
+                .send(serde_json::json!({ "jsonrpc": "2.0", "method": method }));
+        }
+    }
+
+    /// Drop every cached tool and resource list, here and in shared state,
+    /// so the next request aggregates them again
+    pub async fn invalidate_catalog(&self) {
+        self.catalog_changes
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+
+        if let Some(shared) = self.shared_state.read().await.clone() {
+            if let Err(e) = shared
+                .clear_catalogs(&["tools/list", "resources/list"])
+                .await
+            {
+                tracing::warn!("Failed to clear shared catalogs: {}", e);
+            }
+        }
+    }
+
     pub fn is_shutting_down(&self) -> bool {
         self.shutdown_tx.receiver_count() == 0
     }
@@ -208,10 +515,12 @@ impl ServerInfo {
             state: Arc::new(RwLock::new(ServerState::Stopped)),
             process_handle: None,
             restart_count: Arc::new(RwLock::new(0)),
+            restart_times: Arc::new(RwLock::new(VecDeque::new())),
             last_health_check: Arc::new(RwLock::new(None)),
             last_access_time: Arc::new(RwLock::new(None)),
             log_subscribers: Arc::new(DashMap::new()),
             logger: None,
+            log_buffer: Arc::new(LogBuffer::default()),
             connection_state: None, // T021: Will be set during connection
         }
     }
@@ -220,7 +529,13 @@ impl ServerInfo {
         self.logger = Some(logger);
     }
 
+    pub fn set_log_buffer_capacity(&mut self, capacity: usize) {
+        self.log_buffer = Arc::new(LogBuffer::new(capacity));
+    }
+
     pub fn broadcast_log(&self, log_entry: LogEntry) {
+        self.log_buffer.push(log_entry.clone());
+
         // Send to all subscribers
         let subscriber_count = self.log_subscribers.len();
         tracing::debug!(
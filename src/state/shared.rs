@@ -0,0 +1,177 @@
+//! Redis-backed state shared between proxy instances.
+//!
+//! With several proxies behind a load balancer, each keeps its local state
+//! as before and additionally reads and writes the pieces that must agree
+//! across instances here: the aggregated catalogs, the disabled servers and
+//! client usage counters.
+
+use super::DisabledServer;
+use crate::config::SharedStateConfig;
+use crate::error::Result;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub struct SharedState {
+    conn: ConnectionManager,
+    key_prefix: String,
+    catalog_ttl: Duration,
+}
+
+impl SharedState {
+    /// Connect to Redis. The connection reconnects on its own if Redis goes
+    /// away later.
+    pub async fn connect(config: &SharedStateConfig) -> Result<Self> {
+        let client = redis::Client::open(config.redis_url.as_str())?;
+        let conn = ConnectionManager::new(client).await?;
+
+        Ok(Self {
+            conn,
+            key_prefix: config.key_prefix.clone(),
+            catalog_ttl: config.catalog_ttl(),
+        })
+    }
+
+    fn key(&self, name: &str) -> String {
+        format!("{}:{}", self.key_prefix, name)
+    }
+
+    /// A catalog (e.g. the tools/list result) aggregated by any instance
+    pub async fn catalog(&self, method: &str) -> Result<Option<Value>> {
+        let raw: Option<String> = self
+            .conn
+            .clone()
+            .get(self.key(&format!("catalog:{method}")))
+            .await?;
+        Ok(raw.map(|raw| serde_json::from_str(&raw)).transpose()?)
+    }
+
+    pub async fn set_catalog(&self, method: &str, value: &Value) -> Result<()> {
+        let _: () = self
+            .conn
+            .clone()
+            .set_ex(
+                self.key(&format!("catalog:{method}")),
+                value.to_string(),
+                self.catalog_ttl.as_secs(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn clear_catalogs(&self, methods: &[&str]) -> Result<()> {
+        let keys: Vec<String> = methods
+            .iter()
+            .map(|method| self.key(&format!("catalog:{method}")))
+            .collect();
+        let _: () = self.conn.clone().del(keys).await?;
+        Ok(())
+    }
+
+    pub async fn disabled_servers(&self) -> Result<HashMap<String, DisabledServer>> {
+        let raw: HashMap<String, String> = self.conn.clone().hgetall(self.key("disabled")).await?;
+        raw.into_iter()
+            .map(|(name, entry)| Ok((name, serde_json::from_str(&entry)?)))
+            .collect()
+    }
+
+    pub async fn set_disabled(&self, name: &str, entry: &DisabledServer) -> Result<()> {
+        let _: () = self
+            .conn
+            .clone()
+            .hset(self.key("disabled"), name, serde_json::to_string(entry)?)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn clear_disabled(&self, name: &str) -> Result<()> {
+        let _: () = self.conn.clone().hdel(self.key("disabled"), name).await?;
+        Ok(())
+    }
+
+    /// Add `amount` to `counter`, returning the total across all instances.
+    /// The counter expires `ttl` after it was created, so a counter keyed by
+    /// time window cleans itself up.
+    pub async fn increment(&self, counter: &str, amount: u64, ttl: Duration) -> Result<u64> {
+        let key = self.key(&format!("counter:{counter}"));
+        let mut conn = self.conn.clone();
+
+        // NX keeps the expiry set when the counter was created
+        let (total,): (u64,) = redis::pipe()
+            .atomic()
+            .incr(&key, amount)
+            .cmd("PEXPIRE")
+            .arg(&key)
+            .arg(ttl.as_millis() as u64)
+            .arg("NX")
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+        Ok(total)
+    }
+
+    /// Take `amount` back off `counter`
+    pub async fn decrement(&self, counter: &str, amount: u64) -> Result<()> {
+        let _: () = self
+            .conn
+            .clone()
+            .decr(self.key(&format!("counter:{counter}")), amount)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn counter(&self, counter: &str) -> Result<u64> {
+        let total: Option<u64> = self
+            .conn
+            .clone()
+            .get(self.key(&format!("counter:{counter}")))
+            .await?;
+        Ok(total.unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[tokio::test]
+    #[ignore = "needs a Redis server; set MCP_PROXY_TEST_REDIS_URL and run with --ignored"]
+    async fn test_shared_state_round_trip() {
+        let redis_url = std::env::var("MCP_PROXY_TEST_REDIS_URL")
+            .expect("MCP_PROXY_TEST_REDIS_URL must point at a Redis server");
+        let shared = SharedState::connect(&SharedStateConfig {
+            redis_url,
+            key_prefix: format!("mcp-proxy-test-{}", uuid::Uuid::new_v4()),
+            catalog_ttl_seconds: 60,
+            disabled_refresh_seconds: 5,
+        })
+        .await
+        .unwrap();
+
+        let tools = serde_json::json!({ "tools": [{ "name": "search" }] });
+        shared.set_catalog("tools/list", &tools).await.unwrap();
+        assert_eq!(shared.catalog("tools/list").await.unwrap(), Some(tools));
+        shared.clear_catalogs(&["tools/list"]).await.unwrap();
+        assert_eq!(shared.catalog("tools/list").await.unwrap(), None);
+
+        let entry = DisabledServer {
+            reason: "restarted 6 times in 10 minutes".to_string(),
+            quarantined: true,
+            was_enabled: true,
+            disabled_at: Utc::now(),
+        };
+        shared.set_disabled("flaky", &entry).await.unwrap();
+        assert_eq!(shared.disabled_servers().await.unwrap()["flaky"], entry);
+        shared.clear_disabled("flaky").await.unwrap();
+        assert!(shared.disabled_servers().await.unwrap().is_empty());
+
+        let ttl = Duration::from_secs(60);
+        assert_eq!(shared.increment("client", 1, ttl).await.unwrap(), 1);
+        assert_eq!(shared.increment("client", 5, ttl).await.unwrap(), 6);
+        shared.decrement("client", 2).await.unwrap();
+        assert_eq!(shared.counter("client").await.unwrap(), 4);
+    }
+}
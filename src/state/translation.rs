@@ -0,0 +1,224 @@
+//! What protocol translation dropped from each server's messages, so users
+//! can see what an older backend costs them and whether to upgrade it
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Lossy messages kept per server
+const RECENT_LOSSES: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    Request,
+    Response,
+}
+
+/// One message that lost fields in translation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Loss {
+    pub timestamp: DateTime<Utc>,
+    pub method: String,
+    pub direction: Direction,
+    /// Paths of the dropped fields, with `[]` for any array item
+    pub fields: Vec<String>,
+}
+
+/// How often a field has been dropped from a method's messages
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DroppedField {
+    pub method: String,
+    pub direction: Direction,
+    pub field: String,
+    pub count: u64,
+    pub last_seen: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslationReport {
+    pub messages_translated: u64,
+    pub lossy_messages: u64,
+    /// Most often dropped first
+    pub dropped_fields: Vec<DroppedField>,
+    /// Newest first
+    pub recent: Vec<Loss>,
+}
+
+#[derive(Default)]
+struct ServerReport {
+    messages_translated: u64,
+    lossy_messages: u64,
+    dropped: HashMap<(String, Direction, String), (u64, DateTime<Utc>)>,
+    recent: VecDeque<Loss>,
+}
+
+#[derive(Default)]
+pub struct TranslationReports {
+    servers: DashMap<String, Mutex<ServerReport>>,
+}
+
+impl TranslationReports {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a translated message, noting the fields `before` had that the
+    /// translation left out
+    pub fn record(
+        &self,
+        server: &str,
+        method: &str,
+        direction: Direction,
+        before: &Value,
+        after: &Value,
+    ) {
+        let fields = dropped_fields(before, after);
+        let entry = self.servers.entry(server.to_string()).or_default();
+        let mut report = entry.lock().unwrap();
+        report.messages_translated += 1;
+        if fields.is_empty() {
+            return;
+        }
+
+        let now = Utc::now();
+        report.lossy_messages += 1;
+        for field in &fields {
+            let seen = report
+                .dropped
+                .entry((method.to_string(), direction, field.clone()))
+                .or_insert((0, now));
+            *seen = (seen.0 + 1, now);
+        }
+        report.recent.push_front(Loss {
+            timestamp: now,
+            method: method.to_string(),
+            direction,
+            fields,
+        });
+        report.recent.truncate(RECENT_LOSSES);
+    }
+
+    /// The server's report; empty when nothing has been translated
+    pub fn report(&self, server: &str) -> TranslationReport {
+        let Some(entry) = self.servers.get(server) else {
+            return TranslationReport::default();
+        };
+        let report = entry.lock().unwrap();
+        let mut dropped_fields: Vec<DroppedField> = report
+            .dropped
+            .iter()
+            .map(
+                |((method, direction, field), &(count, last_seen))| DroppedField {
+                    method: method.clone(),
+                    direction: *direction,
+                    field: field.clone(),
+                    count,
+                    last_seen,
+                },
+            )
+            .collect();
+        dropped_fields.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| (&a.method, &a.field).cmp(&(&b.method, &b.field)))
+        });
+        TranslationReport {
+            messages_translated: report.messages_translated,
+            lossy_messages: report.lossy_messages,
+            dropped_fields,
+            recent: report.recent.iter().cloned().collect(),
+        }
+    }
+
+    /// Forget a server's history, e.g. after it is upgraded
+    pub fn clear(&self, server: &str) {
+        self.servers.remove(server);
+    }
+}
+
+/// Paths of the non-null fields in `before` that are missing from `after`.
+/// Array items are compared by position.
+pub fn dropped_fields(before: &Value, after: &Value) -> Vec<String> {
+    let mut dropped = BTreeSet::new();
+    collect(before, after, "", &mut dropped);
+    dropped.into_iter().collect()
+}
+
+fn collect(before: &Value, after: &Value, path: &str, dropped: &mut BTreeSet<String>) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            for (key, value) in before {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match after.get(key) {
+                    Some(translated) => collect(value, translated, &path, dropped),
+                    None if !value.is_null() => {
+                        dropped.insert(path);
+                    }
+                    None => {}
+                }
+            }
+        }
+        (Value::Array(before), Value::Array(after)) => {
+            let path = format!("{path}[]");
+            for (value, translated) in before.iter().zip(after) {
+                collect(value, translated, &path, dropped);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_dropped_fields_are_reported() {
+        let before = json!({
+            "result": {
+                "tools": [
+                    { "name": "a", "title": "A", "outputSchema": { "type": "object" } },
+                    { "name": "b", "title": null }
+                ],
+                "structuredContent": { "rows": 1 }
+            }
+        });
+        let after = json!({ "result": { "tools": [{ "name": "a" }, { "name": "b" }] } });
+        assert_eq!(
+            dropped_fields(&before, &after),
+            vec![
+                "result.structuredContent",
+                "result.tools[].outputSchema",
+                "result.tools[].title"
+            ]
+        );
+
+        let reports = TranslationReports::new();
+        reports.record("old", "tools/list", Direction::Response, &before, &after);
+        reports.record("old", "tools/list", Direction::Response, &before, &after);
+        reports.record("old", "ping", Direction::Request, &json!({}), &json!({}));
+
+        let report = reports.report("old");
+        assert_eq!(report.messages_translated, 3);
+        assert_eq!(report.lossy_messages, 2);
+        assert_eq!(report.dropped_fields.len(), 3);
+        assert_eq!(report.dropped_fields[0].count, 2);
+        assert_eq!(report.recent.len(), 2);
+        assert_eq!(report.recent[0].method, "tools/list");
+
+        reports.clear("old");
+        assert_eq!(reports.report("old").messages_translated, 0);
+    }
+}
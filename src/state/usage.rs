@@ -0,0 +1,343 @@
+//! Per-client usage accounting for quotas
+
+use super::SharedState;
+use crate::config::ClientQuotaConfig;
+use crate::error::{ProxyError, Result};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Backend tool calls and their token-equivalent cost within one period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Usage {
+    pub calls: u64,
+    pub tokens: u64,
+}
+
+/// Calendar periods quotas are counted in, in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaPeriod {
+    Day,
+    Month,
+}
+
+impl QuotaPeriod {
+    /// Identifies the period containing `now`, e.g. `2025-01-31` or `2025-01`
+    pub fn key(self, now: DateTime<Utc>) -> String {
+        match self {
+            Self::Day => now.format("%Y-%m-%d").to_string(),
+            Self::Month => now.format("%Y-%m").to_string(),
+        }
+    }
+
+    /// How long shared counters are kept, comfortably past the period's end
+    fn retention(self) -> Duration {
+        match self {
+            Self::Day => Duration::from_secs(2 * 24 * 3600),
+            Self::Month => Duration::from_secs(32 * 24 * 3600),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Day => "daily",
+            Self::Month => "monthly",
+        }
+    }
+}
+
+/// Rough token count of a JSON payload, at about four bytes per token
+pub fn estimate_tokens(value: &Value) -> u64 {
+    tokens_for_len(value.to_string().len())
+}
+
+/// Rough token count of a payload `len` bytes long
+pub fn tokens_for_len(len: usize) -> u64 {
+    (len as u64).div_ceil(4)
+}
+
+/// Add a token estimate to a result's `_meta`, for budget-aware clients
+pub fn annotate_tokens(result: &mut Value, tokens: u64) {
+    let Some(result) = result.as_object_mut() else {
+        return;
+    };
+    let meta = result
+        .entry("_meta")
+        .or_insert_with(|| Value::Object(Default::default()));
+    if let Some(meta) = meta.as_object_mut() {
+        meta.insert("tokenEstimate".to_string(), tokens.into());
+    }
+}
+
+/// Usage per client and period. Counts live in shared state when the
+/// proxy runs with other instances, and in memory otherwise.
+#[derive(Default)]
+pub struct UsageTracker {
+    usage: DashMap<(String, String), Usage>,
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn usage(
+        &self,
+        shared: Option<&SharedState>,
+        client: &str,
+        period: QuotaPeriod,
+        now: DateTime<Utc>,
+    ) -> Result<Usage> {
+        let key = period.key(now);
+
+        if let Some(shared) = shared {
+            return Ok(Usage {
+                calls: shared.counter(&counter_name(client, &key, "calls")).await?,
+                tokens: shared
+                    .counter(&counter_name(client, &key, "tokens"))
+                    .await?,
+            });
+        }
+
+        Ok(self
+            .usage
+            .get(&(client.to_string(), key))
+            .map(|usage| *usage)
+            .unwrap_or_default())
+    }
+
+    /// Count a call against every period before it is made, so concurrent
+    /// calls cannot all slip under the quota. A call that does not fit is
+    /// taken back and rejected. Without a quota the call is only counted.
+    pub async fn reserve(
+        &self,
+        shared: Option<&SharedState>,
+        client: &str,
+        quota: Option<&ClientQuotaConfig>,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let quota = quota.cloned().unwrap_or_default();
+        let limits = [
+            (QuotaPeriod::Day, quota.daily_calls, quota.daily_tokens),
+            (
+                QuotaPeriod::Month,
+                quota.monthly_calls,
+                quota.monthly_tokens,
+            ),
+        ];
+
+        if shared.is_none() {
+            self.forget_finished_periods(client, now);
+        }
+
+        let mut counted = Vec::new();
+        let mut exceeded = None;
+        for (period, calls_limit, tokens_limit) in limits {
+            let usage = self.add(shared, client, period, now, 1, 0).await?;
+            counted.push(period);
+
+            exceeded = if let Some(limit) = calls_limit.filter(|l| usage.calls > *l) {
+                Some(format!(
+                    "{} quota of {} tool calls used up",
+                    period.name(),
+                    limit
+                ))
+            } else {
+                tokens_limit
+                    .filter(|l| usage.tokens >= *l)
+                    .map(|limit| format!("{} quota of {} tokens used up", period.name(), limit))
+            };
+            if exceeded.is_some() {
+                break;
+            }
+        }
+
+        let Some(reason) = exceeded else {
+            return Ok(());
+        };
+        for period in counted {
+            self.take_back(shared, client, period, now).await?;
+        }
+        Err(ProxyError::QuotaExceeded {
+            client: client.to_string(),
+            reason,
+        })
+    }
+
+    /// Add the token cost of a call reserved with [`Self::reserve`]
+    pub async fn record_tokens(
+        &self,
+        shared: Option<&SharedState>,
+        client: &str,
+        tokens: u64,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        for period in [QuotaPeriod::Day, QuotaPeriod::Month] {
+            self.add(shared, client, period, now, 0, tokens).await?;
+        }
+        Ok(())
+    }
+
+    /// Add to a period's counts, returning its new totals
+    async fn add(
+        &self,
+        shared: Option<&SharedState>,
+        client: &str,
+        period: QuotaPeriod,
+        now: DateTime<Utc>,
+        calls: u64,
+        tokens: u64,
+    ) -> Result<Usage> {
+        let key = period.key(now);
+
+        if let Some(shared) = shared {
+            let retention = period.retention();
+            return Ok(Usage {
+                calls: shared
+                    .increment(&counter_name(client, &key, "calls"), calls, retention)
+                    .await?,
+                tokens: shared
+                    .increment(&counter_name(client, &key, "tokens"), tokens, retention)
+                    .await?,
+            });
+        }
+
+        let mut usage = self.usage.entry((client.to_string(), key)).or_default();
+        usage.calls += calls;
+        usage.tokens += tokens;
+        Ok(*usage)
+    }
+
+    async fn take_back(
+        &self,
+        shared: Option<&SharedState>,
+        client: &str,
+        period: QuotaPeriod,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let key = period.key(now);
+
+        if let Some(shared) = shared {
+            return shared
+                .decrement(&counter_name(client, &key, "calls"), 1)
+                .await;
+        }
+
+        if let Some(mut usage) = self.usage.get_mut(&(client.to_string(), key)) {
+            usage.calls = usage.calls.saturating_sub(1);
+        }
+        Ok(())
+    }
+
+    fn forget_finished_periods(&self, client: &str, now: DateTime<Utc>) {
+        let current: Vec<String> = [QuotaPeriod::Day, QuotaPeriod::Month]
+            .iter()
+            .map(|period| period.key(now))
+            .collect();
+        self.usage
+            .retain(|(name, key), _| name != client || current.contains(key));
+    }
+}
+
+fn counter_name(client: &str, period_key: &str, kind: &str) -> String {
+    format!("usage:{client}:{period_key}:{kind}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn test_quota_resets_with_period() {
+        let tracker = UsageTracker::new();
+        let quota = ClientQuotaConfig {
+            daily_calls: Some(2),
+            monthly_tokens: Some(100),
+            ..Default::default()
+        };
+        let quota = Some(&quota);
+        let day_one = Utc.with_ymd_and_hms(2025, 1, 30, 12, 0, 0).unwrap();
+
+        for _ in 0..2 {
+            tracker.reserve(None, "ci", quota, day_one).await.unwrap();
+            tracker
+                .record_tokens(None, "ci", 10, day_one)
+                .await
+                .unwrap();
+        }
+        assert!(tracker.reserve(None, "ci", quota, day_one).await.is_err());
+        // Other clients have their own quota
+        assert!(tracker.reserve(None, "bob", quota, day_one).await.is_ok());
+
+        let day_two = Utc.with_ymd_and_hms(2025, 1, 31, 0, 0, 1).unwrap();
+        assert!(tracker.reserve(None, "ci", quota, day_two).await.is_ok());
+        tracker
+            .record_tokens(None, "ci", 80, day_two)
+            .await
+            .unwrap();
+        let monthly = tracker
+            .usage(None, "ci", QuotaPeriod::Month, day_two)
+            .await
+            .unwrap();
+        // The rejected call is not counted
+        assert_eq!(
+            monthly,
+            Usage {
+                calls: 3,
+                tokens: 100
+            }
+        );
+        assert!(tracker.reserve(None, "ci", quota, day_two).await.is_err());
+
+        let next_month = Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 1).unwrap();
+        assert!(tracker.reserve(None, "ci", quota, next_month).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_calls_cannot_exceed_quota() {
+        let tracker = std::sync::Arc::new(UsageTracker::new());
+        let quota = ClientQuotaConfig {
+            daily_calls: Some(3),
+            ..Default::default()
+        };
+        let now = Utc::now();
+
+        let calls: Vec<_> = (0..10)
+            .map(|_| {
+                let tracker = tracker.clone();
+                let quota = quota.clone();
+                tokio::spawn(async move { tracker.reserve(None, "ci", Some(&quota), now).await })
+            })
+            .collect();
+        let mut allowed = 0;
+        for call in calls {
+            if call.await.unwrap().is_ok() {
+                allowed += 1;
+            }
+        }
+
+        assert_eq!(allowed, 3);
+        let daily = tracker
+            .usage(None, "ci", QuotaPeriod::Day, now)
+            .await
+            .unwrap();
+        assert_eq!(daily.calls, 3);
+    }
+
+    #[test]
+    fn test_annotate_tokens() {
+        let mut result = serde_json::json!({ "content": [], "_meta": { "source": "cache" } });
+        annotate_tokens(&mut result, 42);
+        assert_eq!(
+            result["_meta"],
+            serde_json::json!({ "source": "cache", "tokenEstimate": 42 })
+        );
+
+        let mut result = serde_json::json!({ "content": [] });
+        annotate_tokens(&mut result, estimate_tokens(&serde_json::json!("12345678")));
+        assert_eq!(result["_meta"]["tokenEstimate"], 3);
+    }
+}
@@ -0,0 +1,139 @@
+//! Service manager notifications for running as a systemd `Type=notify`
+//! service: readiness, status text and watchdog keep-alives.
+//!
+//! Every call is a no-op when `NOTIFY_SOCKET` is unset, so the proxy behaves
+//! the same outside systemd.
+
+use std::time::Duration;
+
+/// Send a state such as `READY=1` or `STATUS=...` to the service manager.
+/// Returns whether it was sent.
+pub fn notify(state: &str) -> bool {
+    let Some(socket) = std::env::var_os("NOTIFY_SOCKET") else {
+        return false;
+    };
+    match send(&socket.to_string_lossy(), state) {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!("Failed to notify systemd: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(unix)]
+fn send(socket: &str, state: &str) -> std::io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let datagram = UnixDatagram::unbound()?;
+    // A leading '@' names a socket in the abstract namespace
+    if let Some(name) = socket.strip_prefix('@') {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            #[cfg(target_os = "android")]
+            use std::os::android::net::SocketAddrExt;
+            #[cfg(target_os = "linux")]
+            use std::os::linux::net::SocketAddrExt;
+
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            datagram.send_to_addr(state.as_bytes(), &addr)?;
+            return Ok(());
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            let _ = name;
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "abstract sockets are not supported on this platform",
+            ));
+        }
+    }
+    datagram.send_to(state.as_bytes(), socket)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send(_socket: &str, _state: &str) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "systemd notification needs Unix sockets",
+    ))
+}
+
+/// How often to send `WATCHDOG=1`, when the unit sets `WatchdogSec=`
+pub fn watchdog_interval() -> Option<Duration> {
+    keepalive_interval(
+        std::env::var("WATCHDOG_USEC").ok().as_deref(),
+        std::env::var("WATCHDOG_PID").ok().as_deref(),
+        std::process::id(),
+    )
+}
+
+/// Half the watchdog timeout, as systemd recommends, so one late tick does
+/// not get the service killed
+fn keepalive_interval(
+    watchdog_usec: Option<&str>,
+    watchdog_pid: Option<&str>,
+    pid: u32,
+) -> Option<Duration> {
+    // Without WATCHDOG_PID the watchdog is meant for the main process
+    if let Some(watchdog_pid) = watchdog_pid {
+        if watchdog_pid.parse::<u32>().ok()? != pid {
+            return None;
+        }
+    }
+    let usec: u64 = watchdog_usec?.parse().ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec / 2))
+}
+
+/// Keep the systemd watchdog fed until shutdown. Ticks come from the runtime,
+/// so a proxy whose runtime stops making progress is restarted.
+pub async fn run_watchdog(
+    interval: Duration,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    tracing::info!("Sending systemd watchdog keep-alives every {:?}", interval);
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                notify("WATCHDOG=1");
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keepalive_interval() {
+        assert_eq!(
+            keepalive_interval(Some("30000000"), None, 42),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(
+            keepalive_interval(Some("30000000"), Some("42"), 42),
+            Some(Duration::from_secs(15))
+        );
+        // Meant for another process
+        assert_eq!(keepalive_interval(Some("30000000"), Some("7"), 42), None);
+        assert_eq!(keepalive_interval(Some("0"), None, 42), None);
+        assert_eq!(keepalive_interval(None, None, 42), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_send_reaches_notify_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notify");
+        let listener = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+
+        send(path.to_str().unwrap(), "READY=1\nSTATUS=2 servers running").unwrap();
+        let mut buf = [0u8; 64];
+        let len = listener.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"READY=1\nSTATUS=2 servers running");
+    }
+}
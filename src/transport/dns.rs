@@ -0,0 +1,76 @@
+//! Name resolution for network transports on dual-stack hosts
+//!
+//! Connections race the two address families (RFC 8305): the family of the
+//! first resolved address is dialed, and the other is dialed too if that
+//! hasn't connected within a short delay. Ordering the resolved addresses
+//! therefore chooses which family goes first without giving up the other.
+
+use crate::config::IpPreference;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::SocketAddr;
+
+/// Resolves with the system resolver, then puts the preferred family first
+pub struct PreferringResolver {
+    preference: IpPreference,
+}
+
+impl PreferringResolver {
+    /// A resolver for the preference, or `None` when the system order is
+    /// kept as it is
+    pub fn new(preference: IpPreference) -> Option<Self> {
+        match preference {
+            IpPreference::Auto => None,
+            preference => Some(Self { preference }),
+        }
+    }
+}
+
+impl Resolve for PreferringResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let preference = self.preference;
+        Box::pin(async move {
+            // The port is filled in from the URL
+            let addrs = tokio::net::lookup_host((name.as_str(), 0)).await?;
+            let addrs: Addrs = Box::new(order(addrs.collect(), preference).into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Addresses of the preferred family first, otherwise in resolver order
+pub fn order(mut addrs: Vec<SocketAddr>, preference: IpPreference) -> Vec<SocketAddr> {
+    match preference {
+        IpPreference::Auto => {}
+        IpPreference::PreferIpv4 => addrs.sort_by_key(|addr| !addr.is_ipv4()),
+        IpPreference::PreferIpv6 => addrs.sort_by_key(|addr| !addr.is_ipv6()),
+    }
+    addrs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preferred_family_first() {
+        let addrs: Vec<SocketAddr> = [
+            "[2001:db8::1]:0",
+            "192.0.2.1:0",
+            "[2001:db8::2]:0",
+            "192.0.2.2:0",
+        ]
+        .iter()
+        .map(|addr| addr.parse().unwrap())
+        .collect();
+
+        let ipv4 = order(addrs.clone(), IpPreference::PreferIpv4);
+        assert_eq!(ipv4[..2], [addrs[1], addrs[3]]);
+        assert_eq!(ipv4[2..], [addrs[0], addrs[2]]);
+
+        let ipv6 = order(addrs.clone(), IpPreference::PreferIpv6);
+        assert_eq!(ipv6, vec![addrs[0], addrs[2], addrs[1], addrs[3]]);
+
+        assert_eq!(order(addrs.clone(), IpPreference::Auto), addrs);
+        assert!(PreferringResolver::new(IpPreference::Auto).is_none());
+    }
+}
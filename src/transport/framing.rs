@@ -0,0 +1,261 @@
+//! Reading newline-delimited messages from a server's output with a cap on
+//! their size
+//!
+//! A line is buffered only up to the cap. Past it, the rest of the line is
+//! streamed through a small JSON scanner and thrown away, so a server
+//! writing gigabytes without a newline costs the proxy no more memory than
+//! the cap, and the scanner still tells what kind of message was dropped.
+
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// Longest `id` kept from an oversized message
+const MAX_ID_BYTES: usize = 256;
+
+/// A line read by [`MessageReader`]
+#[derive(Debug, PartialEq)]
+pub enum Frame {
+    /// A whole line, newline included when there was one
+    Line(Vec<u8>),
+    /// A line over the cap, which was discarded
+    Oversized(Oversized),
+}
+
+/// What is known about a discarded line
+#[derive(Debug, Default, PartialEq)]
+pub struct Oversized {
+    /// Length of the line, newline included
+    pub size: u64,
+    /// Whether it started like a JSON-RPC message rather than stray output
+    pub json: bool,
+    /// Its top-level `id`, when it had a small one
+    pub id: Option<Value>,
+    /// Whether it had a top-level `method`, i.e. was a request or
+    /// notification rather than a response
+    pub method: bool,
+}
+
+/// Reads lines of at most `limit` bytes. Cancel-safe: a line cut short by
+/// a cancelled [`next`](Self::next) is continued by the following call.
+pub struct MessageReader<R> {
+    reader: R,
+    limit: usize,
+    line: Vec<u8>,
+    /// Bytes of the current line read so far, discarded ones included
+    read: u64,
+    /// Set once the current line outgrew `limit`
+    overflow: Option<Scanner>,
+}
+
+impl<R: AsyncBufRead + Unpin> MessageReader<R> {
+    pub fn new(reader: R, limit: usize) -> Self {
+        Self {
+            reader,
+            limit,
+            line: Vec::new(),
+            read: 0,
+            overflow: None,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// The next line, or `None` at the end of the stream. A last line
+    /// without a newline is still returned.
+    pub async fn next(&mut self) -> std::io::Result<Option<Frame>> {
+        loop {
+            let available = self.reader.fill_buf().await?;
+            if available.is_empty() {
+                return Ok((self.read > 0).then(|| self.finish()));
+            }
+            let end = available.iter().position(|&b| b == b'\n');
+            let chunk = &available[..end.map_or(available.len(), |i| i + 1)];
+            let n = chunk.len();
+            self.read += n as u64;
+            match &mut self.overflow {
+                Some(scanner) => scanner.feed(chunk),
+                None if self.line.len() + n <= self.limit => self.line.extend_from_slice(chunk),
+                None => {
+                    let mut scanner = Scanner::default();
+                    scanner.feed(&std::mem::take(&mut self.line));
+                    scanner.feed(chunk);
+                    self.overflow = Some(scanner);
+                }
+            }
+            self.reader.consume(n);
+            if end.is_some() {
+                return Ok(Some(self.finish()));
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Frame {
+        let size = std::mem::take(&mut self.read);
+        match self.overflow.take() {
+            Some(scanner) => Frame::Oversized(scanner.into_oversized(size)),
+            None => Frame::Line(std::mem::take(&mut self.line)),
+        }
+    }
+}
+
+/// Follows the structure of a JSON object byte by byte, noting its
+/// top-level `id` and `method` keys without keeping anything else
+#[derive(Debug, Default)]
+struct Scanner {
+    started: bool,
+    json: bool,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    /// At the top level of an object, the next string is a key
+    expecting_key: bool,
+    in_key: bool,
+    /// The key being read or last read, up to a few bytes
+    key: Vec<u8>,
+    /// The `id` value is being read
+    in_id: bool,
+    id: Vec<u8>,
+    id_complete: bool,
+    method: bool,
+}
+
+impl Scanner {
+    fn feed(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.byte(b);
+        }
+    }
+
+    fn byte(&mut self, b: u8) {
+        if !self.started {
+            if b.is_ascii_whitespace() {
+                return;
+            }
+            self.started = true;
+            self.json = matches!(b, b'{' | b'[');
+        }
+
+        let top_level = self.depth == 1;
+        if self.in_id {
+            if !self.in_string && top_level && matches!(b, b',' | b'}') {
+                self.in_id = false;
+                self.id_complete = self.id.len() <= MAX_ID_BYTES;
+            } else if self.id.len() <= MAX_ID_BYTES {
+                self.id.push(b);
+            }
+        }
+
+        if self.in_string {
+            if self.escaped {
+                self.escaped = false;
+            } else if b == b'\\' {
+                self.escaped = true;
+            } else if b == b'"' {
+                self.in_string = false;
+                if std::mem::take(&mut self.in_key) && self.key == b"method" {
+                    self.method = true;
+                }
+            }
+            if self.in_key && self.key.len() <= 6 {
+                self.key.push(b);
+            }
+            return;
+        }
+
+        match b {
+            b'"' => {
+                self.in_string = true;
+                if top_level && std::mem::take(&mut self.expecting_key) {
+                    self.in_key = true;
+                    self.key.clear();
+                }
+            }
+            b'{' | b'[' => {
+                self.depth += 1;
+                self.expecting_key = self.depth == 1 && b == b'{';
+            }
+            b'}' | b']' => self.depth = self.depth.saturating_sub(1),
+            b',' if top_level => self.expecting_key = true,
+            b':' if top_level && self.key == b"id" => {
+                self.in_id = true;
+                self.id.clear();
+            }
+            _ => {}
+        }
+    }
+
+    fn into_oversized(self, size: u64) -> Oversized {
+        let id = self
+            .id_complete
+            .then(|| serde_json::from_slice(self.id.trim_ascii()).ok())
+            .flatten();
+        Oversized {
+            size,
+            json: self.json,
+            id,
+            method: self.method,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn frames(input: &[u8], limit: usize) -> Vec<Frame> {
+        // A small buffer makes lines span several reads
+        let reader = tokio::io::BufReader::with_capacity(7, input);
+        let mut reader = MessageReader::new(reader, limit);
+        let mut frames = Vec::new();
+        while let Some(frame) = reader.next().await.unwrap() {
+            frames.push(frame);
+        }
+        frames
+    }
+
+    #[tokio::test]
+    async fn test_oversized_lines_are_discarded() {
+        let big = "x".repeat(100);
+        let input = format!(
+            "{{\"jsonrpc\":\"2.0\",\"result\":{{\"text\":\"{big}\",\"id\":[1]}},\"id\":\"a\\\"b\"}}\n\
+             {{\"id\":1}}\n\
+             {{\"method\":\"notifications/message\",\"params\":{{\"data\":\"{big}\"}}}}\n\
+             {big}\n\
+             {{\"id\":2}}"
+        );
+
+        let frames = frames(input.as_bytes(), 64).await;
+        assert_eq!(frames.len(), 5);
+        assert_eq!(
+            frames[0],
+            Frame::Oversized(Oversized {
+                size: 160,
+                json: true,
+                id: Some(json!("a\"b")),
+                method: false,
+            })
+        );
+        assert_eq!(frames[1], Frame::Line(b"{\"id\":1}\n".to_vec()));
+        assert!(matches!(
+            &frames[2],
+            Frame::Oversized(Oversized {
+                method: true,
+                id: None,
+                ..
+            })
+        ));
+        assert!(matches!(
+            &frames[3],
+            Frame::Oversized(Oversized {
+                json: false,
+                size: 101,
+                ..
+            })
+        ));
+        // The last line has no newline
+        assert_eq!(frames[4], Frame::Line(b"{\"id\":2}".to_vec()));
+    }
+}
@@ -0,0 +1,194 @@
+//! Request headers for network transports, including values printed by a
+//! command and refreshed once they get old
+
+use crate::config::HeaderCommand;
+use crate::error::{Result, TransportError};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+/// How long a header command may run
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct HeaderSource {
+    fixed: HashMap<String, String>,
+    commands: HashMap<String, HeaderCommand>,
+    /// Command output by header name, with when it was produced. Held while
+    /// a command runs so concurrent requests wait for one refresh.
+    cached: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl HeaderSource {
+    pub fn new(fixed: HashMap<String, String>, commands: HashMap<String, HeaderCommand>) -> Self {
+        Self {
+            fixed,
+            commands,
+            cached: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The headers with fixed values
+    pub fn fixed(&self) -> &HashMap<String, String> {
+        &self.fixed
+    }
+
+    /// Whether any header comes from a command
+    pub fn refreshable(&self) -> bool {
+        !self.commands.is_empty()
+    }
+
+    /// The headers to send, running the commands whose values have expired
+    pub async fn resolve(&self) -> Result<Vec<(String, String)>> {
+        let mut headers: Vec<_> = self
+            .fixed
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        if self.commands.is_empty() {
+            return Ok(headers);
+        }
+
+        let mut cached = self.cached.lock().await;
+        for (name, command) in &self.commands {
+            let ttl = Duration::from_secs(command.ttl_seconds);
+            let fresh = cached
+                .get(name)
+                .filter(|(_, produced)| produced.elapsed() < ttl)
+                .map(|(value, _)| value.clone());
+            let value = match fresh {
+                Some(value) => value,
+                None => {
+                    let value = format!("{}{}", command.prefix, run(name, command).await?);
+                    cached.insert(name.clone(), (value.clone(), Instant::now()));
+                    value
+                }
+            };
+            headers.push((name.clone(), value));
+        }
+        Ok(headers)
+    }
+
+    /// Forget command output, so the next request runs the commands again.
+    /// For when the server rejects a token before its TTL is up.
+    pub async fn invalidate(&self) {
+        self.cached.lock().await.clear();
+    }
+}
+
+async fn run(header: &str, command: &HeaderCommand) -> Result<String> {
+    let failed =
+        |reason: String| TransportError::ConnectionFailed(format!("header {header}: {reason}"));
+
+    tracing::debug!("Running '{}' for header {}", command.command, header);
+    let output = tokio::time::timeout(
+        COMMAND_TIMEOUT,
+        Command::new(&command.command)
+            .args(&command.args)
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| failed(format!("'{}' timed out", command.command)))?
+    .map_err(|e| failed(format!("cannot run '{}': {e}", command.command)))?;
+
+    if !output.status.success() {
+        return Err(failed(format!(
+            "'{}' exited with {}: {}",
+            command.command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+        .into());
+    }
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if value.is_empty() {
+        return Err(failed(format!("'{}' printed nothing", command.command)).into());
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter(path: &std::path::Path, ttl_seconds: u64) -> HeaderCommand {
+        HeaderCommand {
+            command: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                format!("echo x >> {0}; wc -l < {0}", path.display()),
+            ],
+            prefix: "Bearer token-".to_string(),
+            ttl_seconds,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_headers_refresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = HeaderSource::new(
+            HashMap::from([("X-Team".to_string(), "infra".to_string())]),
+            HashMap::from([(
+                "Authorization".to_string(),
+                counter(&dir.path().join("runs"), 3600),
+            )]),
+        );
+        let authorization = |headers: Vec<(String, String)>| {
+            headers
+                .into_iter()
+                .find(|(name, _)| name == "Authorization")
+                .unwrap()
+                .1
+        };
+
+        let headers = source.resolve().await.unwrap();
+        assert!(headers.contains(&("X-Team".to_string(), "infra".to_string())));
+        assert_eq!(authorization(headers), "Bearer token-1");
+        // Still fresh
+        assert_eq!(
+            authorization(source.resolve().await.unwrap()),
+            "Bearer token-1"
+        );
+        source.invalidate().await;
+        assert_eq!(
+            authorization(source.resolve().await.unwrap()),
+            "Bearer token-2"
+        );
+
+        let expiring = HeaderSource::new(
+            HashMap::new(),
+            HashMap::from([(
+                "Authorization".to_string(),
+                counter(&dir.path().join("expiring"), 0),
+            )]),
+        );
+        assert_eq!(
+            authorization(expiring.resolve().await.unwrap()),
+            "Bearer token-1"
+        );
+        assert_eq!(
+            authorization(expiring.resolve().await.unwrap()),
+            "Bearer token-2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failing_command_is_reported() {
+        let source = HeaderSource::new(
+            HashMap::new(),
+            HashMap::from([(
+                "Authorization".to_string(),
+                HeaderCommand {
+                    command: "sh".to_string(),
+                    args: vec!["-c".to_string(), "echo expired >&2; exit 1".to_string()],
+                    prefix: String::new(),
+                    ttl_seconds: 60,
+                },
+            )]),
+        );
+        let error = source.resolve().await.unwrap_err().to_string();
+        assert!(error.contains("header Authorization"), "{error}");
+        assert!(error.contains("expired"), "{error}");
+    }
+}
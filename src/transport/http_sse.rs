@@ -1,4 +1,6 @@
+use super::headers::HeaderSource;
 use super::{Connection, Transport, TransportType};
+use crate::config::{HeaderCommand, IpPreference};
 use crate::error::{Result, TransportError};
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -8,20 +10,62 @@ use std::sync::Arc;
 
 pub struct HttpSseTransport {
     url: String,
-    #[allow(dead_code)]
-    headers: HashMap<String, String>,
+    headers: Arc<HeaderSource>,
     #[allow(dead_code)]
     timeout_ms: u64,
+    proxy_url: Option<String>,
+    ip_preference: IpPreference,
 }
 
 impl HttpSseTransport {
     pub fn new(url: String, headers: HashMap<String, String>, timeout_ms: u64) -> Self {
         Self {
             url,
-            headers,
+            headers: Arc::new(HeaderSource::new(headers, HashMap::new())),
             timeout_ms,
+            proxy_url: None,
+            ip_preference: IpPreference::Auto,
         }
     }
+
+    /// Also send headers whose values are printed by commands, rerunning
+    /// them as their values expire
+    pub fn with_header_commands(mut self, commands: HashMap<String, HeaderCommand>) -> Self {
+        let fixed = self.headers.fixed().clone();
+        self.headers = Arc::new(HeaderSource::new(fixed, commands));
+        self
+    }
+
+    /// Send requests through an HTTP or SOCKS5 proxy. Without one, the
+    /// `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY` and `NO_PROXY` environment
+    /// variables apply.
+    pub fn with_proxy(mut self, proxy_url: Option<String>) -> Self {
+        self.proxy_url = proxy_url;
+        self
+    }
+
+    /// Dial this address family first on dual-stack hosts
+    pub fn with_ip_preference(mut self, preference: IpPreference) -> Self {
+        self.ip_preference = preference;
+        self
+    }
+
+    fn client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(resolver) = super::dns::PreferringResolver::new(self.ip_preference) {
+            builder = builder.dns_resolver(Arc::new(resolver));
+        }
+        if let Some(proxy_url) = &self.proxy_url {
+            // Hosts in NO_PROXY bypass a configured proxy too
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .map_err(|e| TransportError::ConnectionFailed(format!("Invalid proxy URL: {e}")))?
+                .no_proxy(reqwest::NoProxy::from_env());
+            builder = builder.proxy(proxy);
+        }
+        builder
+            .build()
+            .map_err(|e| TransportError::ConnectionFailed(e.to_string()).into())
+    }
 }
 
 #[async_trait]
@@ -29,9 +73,13 @@ impl Transport for HttpSseTransport {
     async fn connect(&self) -> Result<Arc<dyn Connection>> {
         // TODO: Implement SSE connection
         // This is a placeholder implementation
+        // A header command that fails should fail the connection, not the
+        // first request
+        self.headers.resolve().await?;
         Ok(Arc::new(HttpSseConnection {
             url: self.url.clone(),
-            client: Arc::new(reqwest::Client::new()),
+            client: Arc::new(self.client()?),
+            headers: self.headers.clone(),
             closed: Arc::new(AtomicBool::new(false)),
         }))
     }
@@ -44,9 +92,24 @@ impl Transport for HttpSseTransport {
 pub struct HttpSseConnection {
     url: String,
     client: Arc<reqwest::Client>,
+    headers: Arc<HeaderSource>,
     closed: Arc<AtomicBool>,
 }
 
+impl HttpSseConnection {
+    async fn post(&self, data: Bytes) -> Result<reqwest::Response> {
+        let mut request = self.client.post(format!("{}/message", self.url));
+        for (name, value) in self.headers.resolve().await? {
+            request = request.header(name, value);
+        }
+        request
+            .body(data)
+            .send()
+            .await
+            .map_err(|e| TransportError::SendFailed(e.to_string()).into())
+    }
+}
+
 #[async_trait]
 impl Connection for HttpSseConnection {
     async fn send(&self, data: Bytes) -> Result<()> {
@@ -54,14 +117,13 @@ impl Connection for HttpSseConnection {
             return Err(TransportError::Closed.into());
         }
 
-        // TODO: Implement actual SSE send
-        let _response = self
-            .client
-            .post(format!("{}/message", self.url))
-            .body(data)
-            .send()
-            .await
-            .map_err(|e| TransportError::SendFailed(e.to_string()))?;
+        let response = self.post(data.clone()).await?;
+        // A token can be revoked or expire before its TTL is up
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED && self.headers.refreshable() {
+            tracing::info!("{} rejected the request, refreshing headers", self.url);
+            self.headers.invalidate().await;
+            self.post(data).await?;
+        }
 
         Ok(())
     }
@@ -84,3 +146,157 @@ impl Connection for HttpSseConnection {
         self.closed.load(Ordering::SeqCst)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_sends_through_proxy() {
+        let proxy = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_url = format!("http://{}", proxy.local_addr().unwrap());
+        let request_line = tokio::spawn(async move {
+            let (mut socket, _) = proxy.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            let request = String::from_utf8(request).unwrap();
+            request.lines().next().unwrap().to_string()
+        });
+
+        let transport =
+            HttpSseTransport::new("http://mcp.example.test".to_string(), HashMap::new(), 1000)
+                .with_proxy(Some(proxy_url));
+        let connection = transport.connect().await.unwrap();
+        connection.send(Bytes::from_static(b"{}")).await.unwrap();
+
+        assert_eq!(
+            request_line.await.unwrap(),
+            "POST http://mcp.example.test/message HTTP/1.1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sends_through_socks5_proxy() {
+        let proxy = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_url = format!("socks5h://{}", proxy.local_addr().unwrap());
+        let tunnelled = tokio::spawn(async move {
+            let (mut socket, _) = proxy.accept().await.unwrap();
+            // Greeting: version, method count, methods; accept "no auth"
+            let mut greeting = [0u8; 2];
+            socket.read_exact(&mut greeting).await.unwrap();
+            let mut methods = vec![0u8; greeting[1] as usize];
+            socket.read_exact(&mut methods).await.unwrap();
+            socket.write_all(&[5, 0]).await.unwrap();
+
+            // CONNECT to a host name, which the proxy resolves
+            let mut head = [0u8; 5];
+            socket.read_exact(&mut head).await.unwrap();
+            assert_eq!(&head[..4], &[5, 1, 0, 3]);
+            let mut host = vec![0u8; head[4] as usize + 2];
+            socket.read_exact(&mut host).await.unwrap();
+            let port = u16::from_be_bytes([host[host.len() - 2], host[host.len() - 1]]);
+            host.truncate(host.len() - 2);
+            socket
+                .write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            let request = String::from_utf8(request).unwrap();
+            (
+                format!("{}:{port}", String::from_utf8(host).unwrap()),
+                request.lines().next().unwrap().to_string(),
+            )
+        });
+
+        let transport =
+            HttpSseTransport::new("http://mcp.example.test".to_string(), HashMap::new(), 1000)
+                .with_proxy(Some(proxy_url));
+        let connection = transport.connect().await.unwrap();
+        connection.send(Bytes::from_static(b"{}")).await.unwrap();
+
+        assert_eq!(
+            tunnelled.await.unwrap(),
+            (
+                "mcp.example.test:80".to_string(),
+                "POST /message HTTP/1.1".to_string()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejected_token_is_refreshed() {
+        let server = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", server.local_addr().unwrap());
+        let tokens = tokio::spawn(async move {
+            let mut tokens = Vec::new();
+            for status in ["401 Unauthorized", "200 OK"] {
+                let (mut socket, _) = server.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                // Headers and the two-byte body
+                while !request.ends_with(b"\r\n\r\n{}") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                socket
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 {status}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                        )
+                        .as_bytes(),
+                    )
+                    .await
+                    .unwrap();
+                let request = String::from_utf8(request).unwrap();
+                tokens.extend(
+                    request
+                        .lines()
+                        .find_map(|line| line.strip_prefix("authorization: "))
+                        .map(str::to_string),
+                );
+            }
+            tokens
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let runs = dir.path().join("runs");
+        let transport =
+            HttpSseTransport::new(url, HashMap::new(), 1000).with_header_commands(HashMap::from([
+                (
+                    "Authorization".to_string(),
+                    HeaderCommand {
+                        command: "sh".to_string(),
+                        args: vec![
+                            "-c".to_string(),
+                            format!("echo x >> {0}; wc -l < {0}", runs.display()),
+                        ],
+                        prefix: "Bearer ".to_string(),
+                        ttl_seconds: 3600,
+                    },
+                ),
+            ]));
+        let connection = transport.connect().await.unwrap();
+        connection.send(Bytes::from_static(b"{}")).await.unwrap();
+
+        assert_eq!(tokens.await.unwrap(), ["Bearer 1", "Bearer 2"]);
+    }
+}
@@ -3,9 +3,16 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use std::sync::Arc;
 
+pub mod dns;
+pub mod framing;
+pub mod headers;
 pub mod http_sse;
 pub mod pool;
+pub mod process;
+pub mod runtime;
+pub mod sandbox;
 pub mod stdio;
+pub mod timeline;
 pub mod websocket;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -27,24 +34,48 @@ pub trait Connection: Send + Sync + 'static {
     async fn recv(&self) -> Result<Bytes>;
     async fn close(&self) -> Result<()>;
     fn is_closed(&self) -> bool;
+
+    /// Check that the connection can still carry requests, without sending
+    /// one. Must not disturb a request in progress.
+    async fn probe(&self) -> bool {
+        !self.is_closed()
+    }
+
+    /// When the server first wrote anything, for transports that can tell
+    fn first_output_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        None
+    }
 }
 
+/// Create the transport for a server. `proxy_url` is the outbound proxy for
+/// network transports, if any.
 pub fn create_transport(
     config: &crate::config::TransportConfig,
     server_config: &crate::config::ServerConfig,
     server_info: Option<Arc<crate::state::ServerInfo>>,
+    proxy_url: Option<&str>,
 ) -> Result<Arc<dyn Transport>> {
     match config {
         crate::config::TransportConfig::Stdio => {
-            let mut transport = stdio::StdioTransport::new();
-            transport =
-                transport.with_command(server_config.command.clone(), server_config.args.clone());
+            let (command, args) = runtime::launch_command(server_config);
+            let mut transport = stdio::StdioTransport::new().with_command(command, args);
             if !server_config.env.is_empty() {
                 transport = transport.with_env(server_config.env.clone());
             }
+            transport = transport.with_env_passthrough(server_config.env_passthrough.clone());
             if let Some(ref working_dir) = server_config.working_directory {
                 transport = transport.with_working_dir(working_dir.clone());
             }
+            let classifier = crate::logging::LogClassifier::new(&server_config.log_parsers)
+                .map_err(crate::error::ConfigError::Validation)?;
+            transport = transport.with_log_classifier(Arc::new(classifier));
+            transport = transport.with_strict_stdout(server_config.strict_stdout);
+            transport = transport.with_max_message_bytes(server_config.max_message_bytes);
+            let stop_policy = process::StopPolicy::from_config(server_config)
+                .map_err(crate::error::ConfigError::Validation)?;
+            transport = transport.with_stop_policy(stop_policy);
+            transport = transport
+                .with_confinement(server_config.run_as.clone(), server_config.sandbox.clone());
             if let Some(server_info) = server_info {
                 tracing::debug!(
                     "Adding server_info to transport for server: {}",
@@ -59,12 +90,14 @@ pub fn create_transport(
         crate::config::TransportConfig::HttpSse {
             url,
             headers,
+            headers_from_command,
             timeout_ms,
-        } => Ok(Arc::new(http_sse::HttpSseTransport::new(
-            url.clone(),
-            headers.clone(),
-            *timeout_ms,
-        ))),
+        } => Ok(Arc::new(
+            http_sse::HttpSseTransport::new(url.clone(), headers.clone(), *timeout_ms)
+                .with_header_commands(headers_from_command.clone())
+                .with_proxy(proxy_url.map(str::to_string))
+                .with_ip_preference(server_config.dns.ip_preference),
+        )),
         crate::config::TransportConfig::WebSocket {
             url,
             protocols,
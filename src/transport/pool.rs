@@ -1,24 +1,561 @@
+use super::timeline::StartupTimeline;
 use super::{Connection, Transport};
-use crate::error::{PoolError, Result};
+use crate::error::{PoolError, ProxyError, Result, TransportError};
+use crate::protocol::{create_adapter, ProtocolVersion, ServerConnectionState};
+use crate::recording::{Direction, SessionRecorder};
 use crate::state::ServerVersion;
+use async_trait::async_trait;
+use bytes::Bytes;
 use dashmap::DashMap;
-use std::sync::Arc;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Protocol version the proxy speaks towards its own clients
+pub const PROXY_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::V20250326;
+
+/// How long to wait for an initialize response when none is configured
+pub const DEFAULT_INITIALIZATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Connection statistics for one server, as reported by `/api/metrics`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStats {
+    pub server: String,
+    /// Open connections to the server (the pool keeps at most one)
+    pub connections: usize,
+    /// Requests currently holding the connection
+    pub in_flight: usize,
+    /// Seconds since the connection was last handed out or released
+    pub idle_secs: Option<u64>,
+    /// Seconds since the connection was established
+    pub age_secs: Option<u64>,
+    /// Requests sent to the server
+    pub requests: u64,
+    /// Failed connects, handshakes, sends and receives
+    pub failures: u64,
+    /// Replies that were JSON-RPC errors
+    pub error_replies: u64,
+    /// Connections replaced by the reaper
+    pub reaped: u64,
+    /// Initialized connections waiting to replace a failed one
+    pub warm_spares: usize,
+    /// Failed connections replaced by a warm spare
+    pub failovers: u64,
+    /// Requests abandoned before their reply arrived, e.g. because the
+    /// client went away
+    pub cancelled: u64,
+    /// Replies received from the server
+    pub responses: u64,
+    /// Summed round trips of those replies, for averaging over an interval
+    pub total_latency_ms: f64,
+    /// Round trips of recent requests; `None` before the first response
+    pub latency: Option<LatencySummary>,
+}
+
+/// Percentiles over a server's most recent request round trips
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencySummary {
+    pub samples: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencySummary {
+    pub fn of(samples: &VecDeque<Duration>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = samples.iter().copied().collect();
+        sorted.sort();
+        // Nearest-rank percentile
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1].as_secs_f64() * 1000.0
+        };
+        Some(Self {
+            samples: sorted.len(),
+            p50_ms: percentile(50.0),
+            p95_ms: percentile(95.0),
+            p99_ms: percentile(99.0),
+            max_ms: percentile(100.0),
+        })
+    }
+}
+
+/// Whether a backend message is a JSON-RPC error reply. Only messages that
+/// mention an error are parsed.
+fn is_error_reply(message: &[u8]) -> bool {
+    #[derive(serde::Deserialize)]
+    struct ReplyHead {
+        #[serde(default)]
+        error: Option<serde::de::IgnoredAny>,
+    }
+
+    message.windows(7).any(|window| window == b"\"error\"")
+        && serde_json::from_slice::<ReplyHead>(message).is_ok_and(|head| head.error.is_some())
+}
+
+/// How long to wait before starting a warm spare again after one failed
+const SPARE_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Where the steps of one connection's startup are noted
+type Timeline = Arc<std::sync::Mutex<StartupTimeline>>;
+
+/// A connection opened and initialized ahead of need, with the handshake
+/// state and timeline it got on its own
+struct Spare {
+    connection: Arc<dyn Connection>,
+    state: ServerConnectionState,
+    timeline: Timeline,
+}
+
+/// The spares kept for one server
+struct WarmSpares {
+    count: usize,
+    ready: std::sync::Mutex<VecDeque<Spare>>,
+    /// Woken when a spare is taken or the server is removed
+    changed: tokio::sync::Notify,
+}
+
+impl WarmSpares {
+    fn len(&self) -> usize {
+        self.ready.lock().unwrap().len()
+    }
+
+    async fn close(&self) {
+        let spares: Vec<Spare> = self.ready.lock().unwrap().drain(..).collect();
+        for spare in spares {
+            let _ = spare.connection.close().await;
+        }
+    }
+}
+
+fn mark(timeline: &Timeline, update: impl FnOnce(&mut StartupTimeline)) {
+    update(&mut timeline.lock().unwrap());
+}
+
+/// Round trips kept per server for latency percentiles
+const LATENCY_SAMPLES: usize = 256;
+
+/// Abandoned requests per connection whose late replies are watched for.
+/// A server that honours the cancellation never replies, so the oldest
+/// are forgotten.
+const ABANDONED_IDS: usize = 64;
+
+/// Use of one connection
+struct ConnectionStats {
+    created_at: Instant,
+    last_used: std::sync::Mutex<Instant>,
+    in_flight: AtomicUsize,
+    /// A send or receive failed in a way that leaves the connection unusable
+    broken: AtomicBool,
+    /// The id the next request sent over the connection is given
+    next_request_id: AtomicU64,
+    /// Ids of abandoned requests whose replies may still arrive, oldest first
+    abandoned: std::sync::Mutex<VecDeque<u64>>,
+}
+
+impl ConnectionStats {
+    fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            created_at: now,
+            last_used: std::sync::Mutex::new(now),
+            in_flight: AtomicUsize::new(0),
+            broken: AtomicBool::new(false),
+            next_request_id: AtomicU64::new(1),
+            abandoned: std::sync::Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn touch(&self) {
+        *self.last_used.lock().unwrap() = Instant::now();
+    }
+
+    fn is_broken(&self) -> bool {
+        self.broken.load(Ordering::SeqCst)
+    }
+
+    fn abandon(&self, id: u64) {
+        let mut abandoned = self.abandoned.lock().unwrap();
+        if abandoned.len() == ABANDONED_IDS {
+            abandoned.pop_front();
+        }
+        abandoned.push_back(id);
+    }
+
+    /// Whether `id` belongs to an abandoned request, which it then no
+    /// longer does since a request gets a single reply
+    fn take_abandoned(&self, id: u64) -> bool {
+        let mut abandoned = self.abandoned.lock().unwrap();
+        match abandoned.iter().position(|abandoned| *abandoned == id) {
+            Some(index) => {
+                abandoned.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// A JSON-RPC message's members, with their values as they were written
+type Members = std::collections::BTreeMap<String, Box<serde_json::value::RawValue>>;
+
+/// `members` written out as a message, keeping a trailing newline `like` has
+fn write_members(members: &Members, like: &[u8]) -> Option<Bytes> {
+    let mut message = serde_json::to_vec(members).ok()?;
+    if like.ends_with(b"\n") {
+        message.push(b'\n');
+    }
+    Some(Bytes::from(message))
+}
+
+/// Counters for a server that outlive its connections
+#[derive(Default)]
+struct ServerCounters {
+    requests: AtomicU64,
+    failures: AtomicU64,
+    error_replies: AtomicU64,
+    reaped: AtomicU64,
+    failovers: AtomicU64,
+    cancelled: AtomicU64,
+    responses: AtomicU64,
+    latency_total_us: AtomicU64,
+    latencies: std::sync::Mutex<VecDeque<Duration>>,
+}
+
+impl ServerCounters {
+    fn record_latency(&self, latency: Duration) {
+        self.responses.fetch_add(1, Ordering::Relaxed);
+        self.latency_total_us
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+        let mut latencies = self.latencies.lock().unwrap();
+        if latencies.len() == LATENCY_SAMPLES {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+}
+
+struct PooledConnection {
+    connection: Arc<dyn Connection>,
+    stats: Arc<ConnectionStats>,
+}
+
+impl PooledConnection {
+    fn new(connection: Arc<dyn Connection>) -> Self {
+        Self {
+            connection,
+            stats: Arc::new(ConnectionStats::new()),
+        }
+    }
+
+    fn is_usable(&self) -> bool {
+        !self.connection.is_closed() && !self.stats.is_broken()
+    }
+
+    /// Hand the connection out to a request
+    fn lease(
+        &self,
+        counters: Arc<ServerCounters>,
+        recording: Option<Recording>,
+    ) -> Arc<dyn Connection> {
+        self.stats.in_flight.fetch_add(1, Ordering::SeqCst);
+        self.stats.touch();
+        Arc::new(Lease {
+            connection: self.connection.clone(),
+            stats: self.stats.clone(),
+            counters,
+            pending: std::sync::Mutex::new(None),
+            recording,
+        })
+    }
+}
+
+/// Where a lease records the messages it carries
+struct Recording {
+    recorder: Arc<SessionRecorder>,
+    server: String,
+    exchange: u64,
+}
+
+impl Recording {
+    fn record(&self, direction: Direction, message: &[u8]) {
+        self.recorder
+            .backend(direction, &self.server, self.exchange, message);
+    }
+}
+
+/// A message sent over a lease that no reply has followed yet
+struct Pending {
+    sent_at: Instant,
+    /// Set when the message was a request rather than a notification
+    request: Option<SentRequest>,
+}
+
+/// A request as the lease sent it
+struct SentRequest {
+    /// The id the server saw
+    id: u64,
+    /// The id the caller gave it, restored in the reply
+    caller_id: Box<serde_json::value::RawValue>,
+    method: String,
+}
+
+/// A connection handed out by the pool. It counts as in flight until it is
+/// dropped, marks the pooled connection broken when its transport fails, and
+/// times each send until the reply that follows it.
+///
+/// Requests are sent with an id unique to the connection, and the reply
+/// gets back the id the caller used. A lease dropped while its request
+/// awaits a reply belongs to a request that was given up on: the server is
+/// sent `notifications/cancelled`, and a reply that still arrives for it is
+/// recognised by its id and dropped, so the connection stays in use.
+struct Lease {
+    connection: Arc<dyn Connection>,
+    stats: Arc<ConnectionStats>,
+    counters: Arc<ServerCounters>,
+    pending: std::sync::Mutex<Option<Pending>>,
+    recording: Option<Recording>,
+}
+
+impl Lease {
+    fn track<T>(&self, result: Result<T>) -> Result<T> {
+        if let Err(e) = &result {
+            self.counters.failures.fetch_add(1, Ordering::Relaxed);
+            if matches!(
+                e,
+                ProxyError::Transport(
+                    TransportError::SendFailed(_)
+                        | TransportError::ReceiveFailed(_)
+                        | TransportError::Closed
+                )
+            ) {
+                self.stats.broken.store(true, Ordering::SeqCst);
+            }
+        }
+        result
+    }
+
+    /// Give a request the connection's next id. Notifications and messages
+    /// that can't be parsed are sent as they are.
+    fn number(&self, message: Bytes) -> (Bytes, Option<SentRequest>) {
+        let Ok(mut members) = serde_json::from_slice::<Members>(&message) else {
+            return (message, None);
+        };
+        let Some(method) = members
+            .get("method")
+            .and_then(|method| serde_json::from_str::<String>(method.get()).ok())
+        else {
+            return (message, None);
+        };
+        if !members.contains_key("id") {
+            return (message, None);
+        }
+
+        let id = self.stats.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let Ok(numbered) = serde_json::value::to_raw_value(&id) else {
+            return (message, None);
+        };
+        let caller_id = members.insert("id".to_string(), numbered).unwrap();
+        match write_members(&members, &message) {
+            Some(numbered) => (
+                numbered,
+                Some(SentRequest {
+                    id,
+                    caller_id,
+                    method,
+                }),
+            ),
+            None => (message, None),
+        }
+    }
+
+    /// Tell the server to stop working on an abandoned request, and watch
+    /// for its reply
+    fn cancel(&self, pending: Pending) {
+        // Notifications get no reply, so there is nothing to cancel
+        let Some(request) = pending.request else {
+            return;
+        };
+
+        tracing::debug!(
+            "Cancelling {} abandoned after {:?}",
+            request.method,
+            pending.sent_at.elapsed()
+        );
+        self.counters.cancelled.fetch_add(1, Ordering::Relaxed);
+        self.stats.abandon(request.id);
+
+        let notification = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": { "requestId": request.id, "reason": "Request abandoned by the proxy" }
+        });
+        let notification = Bytes::from(format!("{notification}\n"));
+        if let Some(recording) = &self.recording {
+            recording.record(Direction::BackendRequest, &notification);
+        }
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let connection = self.connection.clone();
+            runtime.spawn(async move {
+                if let Err(e) = connection.send(notification).await {
+                    tracing::debug!("Failed to send cancellation: {}", e);
+                }
+            });
+        }
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        if let Some(pending) = self.pending.get_mut().unwrap().take() {
+            self.cancel(pending);
+        }
+        self.stats.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.stats.touch();
+    }
+}
+
+#[async_trait]
+impl Connection for Lease {
+    async fn send(&self, data: Bytes) -> Result<()> {
+        let sent_at = Instant::now();
+        self.counters.requests.fetch_add(1, Ordering::Relaxed);
+        let (data, request) = self.number(data);
+        if let Some(recording) = &self.recording {
+            recording.record(Direction::BackendRequest, &data);
+        }
+        self.track(self.connection.send(data).await)?;
+        *self.pending.lock().unwrap() = Some(Pending { sent_at, request });
+        Ok(())
+    }
+
+    async fn recv(&self) -> Result<Bytes> {
+        loop {
+            let received = self.connection.recv().await;
+            if received.is_err() {
+                // A failed connection is replaced anyway; nothing to cancel
+                self.pending.lock().unwrap().take();
+            }
+            let message = self.track(received)?;
+            if let Some(recording) = &self.recording {
+                recording.record(Direction::BackendResponse, &message);
+            }
+
+            // Only replies carry an id without a method
+            let mut reply = serde_json::from_slice::<Members>(&message)
+                .ok()
+                .filter(|members| !members.contains_key("method"));
+            let reply_id = reply
+                .as_ref()
+                .and_then(|members| members.get("id"))
+                .and_then(|id| id.get().parse::<u64>().ok());
+            if reply_id.is_some_and(|id| self.stats.take_abandoned(id)) {
+                tracing::debug!("Dropped the late reply to an abandoned request");
+                continue;
+            }
+
+            let Some(pending) = self.pending.lock().unwrap().take() else {
+                return Ok(message);
+            };
+            self.counters.record_latency(pending.sent_at.elapsed());
+            if is_error_reply(&message) {
+                self.counters.error_replies.fetch_add(1, Ordering::Relaxed);
+            }
+            if let (Some(request), Some(members)) = (pending.request, &mut reply) {
+                if reply_id == Some(request.id) {
+                    members.insert("id".to_string(), request.caller_id);
+                    if let Some(restored) = write_members(members, &message) {
+                        return Ok(restored);
+                    }
+                }
+            }
+            return Ok(message);
+        }
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.connection.close().await
+    }
+
+    fn is_closed(&self) -> bool {
+        self.connection.is_closed() || self.stats.is_broken()
+    }
+
+    async fn probe(&self) -> bool {
+        !self.stats.is_broken() && self.connection.probe().await
+    }
+
+    fn first_output_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.connection.first_output_at()
+    }
+}
 
 pub struct ConnectionPool {
-    connections: DashMap<String, Arc<dyn Connection>>,
+    connections: DashMap<String, PooledConnection>,
     transports: DashMap<String, Arc<dyn Transport>>,
+    counters: DashMap<String, Arc<ServerCounters>>,
     server_versions: Arc<DashMap<String, ServerVersion>>,
+    connection_states: Arc<DashMap<String, Arc<ServerConnectionState>>>,
+    pinned_versions: DashMap<String, ProtocolVersion>,
+    initialization_timeouts: DashMap<String, Duration>,
+    max_ages: DashMap<String, Duration>,
+    timelines: DashMap<String, Timeline>,
+    warm_spares: DashMap<String, Arc<WarmSpares>>,
+    recorder: OnceLock<Arc<SessionRecorder>>,
+    respawn_tx: tokio::sync::broadcast::Sender<String>,
 }
 
 impl ConnectionPool {
-    pub fn new(server_versions: Arc<DashMap<String, ServerVersion>>) -> Self {
+    pub fn new(
+        server_versions: Arc<DashMap<String, ServerVersion>>,
+        connection_states: Arc<DashMap<String, Arc<ServerConnectionState>>>,
+    ) -> Self {
         Self {
             connections: DashMap::new(),
             transports: DashMap::new(),
+            counters: DashMap::new(),
             server_versions,
+            connection_states,
+            pinned_versions: DashMap::new(),
+            initialization_timeouts: DashMap::new(),
+            max_ages: DashMap::new(),
+            timelines: DashMap::new(),
+            warm_spares: DashMap::new(),
+            recorder: OnceLock::new(),
+            respawn_tx: tokio::sync::broadcast::channel(64).0,
         }
     }
 
+    /// Names of servers whose process exited or whose transport was lost,
+    /// as their connection is replaced. Deliberate reconnects and
+    /// connections past their age are not included.
+    pub fn subscribe_respawns(&self) -> tokio::sync::broadcast::Receiver<String> {
+        self.respawn_tx.subscribe()
+    }
+
+    /// Record the messages sent and received over every connection handed
+    /// out from now on. Initialize handshakes are not recorded.
+    pub fn record_to(&self, recorder: Arc<SessionRecorder>) {
+        let _ = self.recorder.set(recorder);
+    }
+
+    fn recording(&self, server_name: &str) -> Option<Recording> {
+        let recorder = self.recorder.get()?;
+        Some(Recording {
+            recorder: recorder.clone(),
+            server: server_name.to_string(),
+            exchange: recorder.next_exchange(),
+        })
+    }
+
     pub async fn add_server(
         &self,
         server_name: String,
@@ -30,30 +567,113 @@ impl ConnectionPool {
 
         // Create initial connection
         tracing::debug!("Creating connection for server: {}", server_name);
-        let connection = transport.connect().await?;
+        let connection = self.connect(&server_name, &transport).await?;
+        self.connections
+            .insert(server_name, PooledConnection::new(connection));
 
-        // Perform MCP initialization handshake
-        self.initialize_connection(&server_name, &connection)
-            .await?;
+        Ok(())
+    }
 
-        self.connections.insert(server_name, connection);
+    /// Open and initialize a new connection, counting failures
+    async fn connect(
+        &self,
+        server_name: &str,
+        transport: &Arc<dyn Transport>,
+    ) -> Result<Arc<dyn Connection>> {
+        let timeline: Timeline = Arc::new(std::sync::Mutex::new(StartupTimeline::starting()));
+        self.timelines
+            .insert(server_name.to_string(), timeline.clone());
+        let connection_state = self.connection_state(server_name);
+        let result = self
+            .open(server_name, transport, &connection_state, &timeline)
+            .await;
+        if result.is_err() {
+            self.counters(server_name)
+                .failures
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
 
-        Ok(())
+    /// Open a connection and run the initialize handshake, noting its
+    /// progress in `connection_state` and `timeline`
+    async fn open(
+        &self,
+        server_name: &str,
+        transport: &Arc<dyn Transport>,
+        connection_state: &ServerConnectionState,
+        timeline: &Timeline,
+    ) -> Result<Arc<dyn Connection>> {
+        let result = match transport.connect().await {
+            Ok(connection) => {
+                // Perform MCP initialization handshake
+                let initialized = self
+                    .initialize_connection(server_name, &connection, connection_state, timeline)
+                    .await;
+                mark(timeline, |t| t.first_byte_at = connection.first_output_at());
+                match initialized {
+                    Ok(()) => Ok(connection),
+                    Err(e) => {
+                        let _ = connection.close().await;
+                        Err(e)
+                    }
+                }
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &result {
+            mark(timeline, |t| t.error = Some(e.to_string()));
+        }
+        result
+    }
+
+    /// The server's connection state, shared so holders of the Arc see
+    /// reconnects
+    fn connection_state(&self, server_name: &str) -> Arc<ServerConnectionState> {
+        self.connection_states
+            .entry(server_name.to_string())
+            .or_insert_with(|| Arc::new(ServerConnectionState::new(server_name.to_string())))
+            .clone()
+    }
+
+    /// When each step of the server's latest connection happened
+    pub fn startup_timeline(&self, server_name: &str) -> Option<StartupTimeline> {
+        self.timelines
+            .get(server_name)
+            .map(|t| t.lock().unwrap().clone())
+    }
+
+    fn counters(&self, server_name: &str) -> Arc<ServerCounters> {
+        self.counters
+            .entry(server_name.to_string())
+            .or_default()
+            .clone()
     }
 
     async fn initialize_connection(
         &self,
         server_name: &str,
         conn: &Arc<dyn Connection>,
+        connection_state: &ServerConnectionState,
+        timeline: &Timeline,
     ) -> Result<()> {
         use crate::protocol::{JsonRpcId, JsonRpcMessage, JsonRpcRequest, JsonRpcV2Message};
 
+        let pinned_version = self.pinned_protocol_version(server_name);
+        let requested_version = pinned_version.unwrap_or(PROXY_PROTOCOL_VERSION);
+
+        connection_state.reset().await;
+        connection_state
+            .start_initialization("1".to_string())
+            .await
+            .map_err(|e| crate::error::TransportError::ConnectionFailed(e.to_string()))?;
+
         // Step 1: Send initialize request
         let initialize_request = JsonRpcMessage::V2(JsonRpcV2Message::Request(JsonRpcRequest {
             id: JsonRpcId::Number(1),
             method: "initialize".to_string(),
             params: Some(serde_json::json!({
-                "protocolVersion": "2025-03-26",
+                "protocolVersion": requested_version.as_str(),
                 "capabilities": {},
                 "clientInfo": {
                     "name": "mcp-rust-proxy",
@@ -66,76 +686,125 @@ impl ConnectionPool {
         let request_bytes = bytes::Bytes::from(format!("{request_json}\n"));
 
         tracing::debug!("Sending initialize request to {}", server_name);
-        conn.send(request_bytes).await?;
+        if let Err(e) = conn.send(request_bytes).await {
+            connection_state.mark_failed(e.to_string()).await;
+            return Err(e);
+        }
+        mark(timeline, |t| {
+            t.initialize_sent_at = Some(chrono::Utc::now())
+        });
 
         // Wait for initialize response
-        let response_bytes = conn.recv().await?;
+        let timeout = self.initialization_timeout(server_name);
+        let response_bytes = match tokio::time::timeout(timeout, conn.recv()).await {
+            Ok(Ok(bytes)) => bytes,
+            Ok(Err(e)) => {
+                connection_state.mark_failed(e.to_string()).await;
+                return Err(e);
+            }
+            Err(_) => {
+                connection_state
+                    .mark_initialization_timed_out(timeout)
+                    .await;
+                return Err(crate::error::ProxyError::Timeout);
+            }
+        };
+        mark(timeline, |t| {
+            t.initialize_received_at = Some(chrono::Utc::now())
+        });
         let response_str = std::str::from_utf8(&response_bytes)
             .map_err(|_e| crate::error::TransportError::InvalidFormat)?;
         let response: JsonRpcMessage = serde_json::from_str(response_str.trim())?;
 
         // Verify we got a successful response and store version info
-        match response {
+        let negotiated_version = match response {
             JsonRpcMessage::V2(JsonRpcV2Message::Response(resp)) => {
                 if resp.error.is_some() {
-                    return Err(crate::error::TransportError::ConnectionFailed(format!(
-                        "Initialize failed for {}: {:?}",
-                        server_name, resp.error
-                    ))
-                    .into());
+                    let message =
+                        format!("Initialize failed for {}: {:?}", server_name, resp.error);
+                    connection_state.mark_failed(message.clone()).await;
+                    return Err(crate::error::TransportError::ConnectionFailed(message).into());
                 }
 
-                // Store protocol version and capabilities
-                if let Some(result) = &resp.result {
-                    let protocol_version = result
-                        .get("protocolVersion")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("unknown")
-                        .to_string();
-
-                    let capabilities = result
-                        .get("capabilities")
-                        .cloned()
-                        .unwrap_or(serde_json::json!({}));
-
-                    self.server_versions.insert(
-                        server_name.to_string(),
-                        ServerVersion {
-                            protocol_version: protocol_version.clone(),
-                            capabilities,
-                            detected_at: chrono::Utc::now(),
-                        },
-                    );
+                tracing::debug!(
+                    "Received initialize response from {}: {:?}",
+                    server_name,
+                    resp.result
+                );
 
-                    tracing::info!(
-                        "Server {} initialized with protocol version {}",
-                        server_name,
-                        protocol_version
-                    );
+                let result = resp.result.unwrap_or(serde_json::json!({}));
+                let reported_version = result
+                    .get("protocolVersion")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("unknown")
+                    .to_string();
+
+                let capabilities = result
+                    .get("capabilities")
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
 
-                    // Warn if version mismatch
-                    if protocol_version != "2025-03-26" {
-                        tracing::warn!(
-                            "Protocol version mismatch: Server {} uses {}, proxy uses 2025-03-26",
-                            server_name,
-                            protocol_version
-                        );
+                // A pinned version always wins over whatever the server reports
+                let (negotiated_version, stored_version) = match pinned_version {
+                    Some(pinned) => {
+                        if reported_version != pinned.as_str() {
+                            tracing::info!(
+                                "Server {} reported protocol version {}, using pinned version {}",
+                                server_name,
+                                reported_version,
+                                pinned.as_str()
+                            );
+                        }
+                        (pinned, pinned.as_str().to_string())
                     }
-                }
+                    None => {
+                        let (detected, _supported) =
+                            ProtocolVersion::from_string(&reported_version);
+                        (detected, reported_version)
+                    }
+                };
 
-                tracing::debug!(
-                    "Received initialize response from {}: {:?}",
+                self.server_versions.insert(
+                    server_name.to_string(),
+                    ServerVersion {
+                        protocol_version: stored_version.clone(),
+                        capabilities,
+                        detected_at: chrono::Utc::now(),
+                    },
+                );
+
+                tracing::info!(
+                    "Server {} initialized with protocol version {}",
                     server_name,
-                    resp.result
+                    stored_version
                 );
+
+                // Warn if version mismatch
+                if pinned_version.is_none() && stored_version != PROXY_PROTOCOL_VERSION.as_str() {
+                    tracing::warn!(
+                        "Protocol version mismatch: Server {} uses {}, proxy uses {}",
+                        server_name,
+                        stored_version,
+                        PROXY_PROTOCOL_VERSION.as_str()
+                    );
+                }
+
+                negotiated_version
             }
             _ => {
-                return Err(crate::error::TransportError::ConnectionFailed(format!(
-                    "Invalid initialize response from {server_name}"
-                ))
-                .into());
+                let message = format!("Invalid initialize response from {server_name}");
+                connection_state.mark_failed(message.clone()).await;
+                return Err(crate::error::TransportError::ConnectionFailed(message).into());
             }
-        }
+        };
+
+        connection_state
+            .received_initialize_response(negotiated_version)
+            .await
+            .map_err(|e| crate::error::TransportError::ConnectionFailed(e.to_string()))?;
+        connection_state
+            .set_adapter(create_adapter(PROXY_PROTOCOL_VERSION, negotiated_version))
+            .await;
 
         // Step 2: Send initialized notification
         let initialized_notification = JsonRpcMessage::V2(JsonRpcV2Message::Notification(
@@ -149,48 +818,688 @@ impl ConnectionPool {
         let notification_bytes = bytes::Bytes::from(format!("{notification_json}\n"));
 
         tracing::debug!("Sending initialized notification to {}", server_name);
-        conn.send(notification_bytes).await?;
+        if let Err(e) = conn.send(notification_bytes).await {
+            connection_state.mark_failed(e.to_string()).await;
+            return Err(e);
+        }
+
+        connection_state
+            .complete_initialization()
+            .await
+            .map_err(|e| crate::error::TransportError::ConnectionFailed(e.to_string()))?;
+
+        mark(timeline, |t| t.ready_at = Some(chrono::Utc::now()));
 
         tracing::info!("Successfully initialized MCP connection to {}", server_name);
         Ok(())
     }
 
+    /// Pin (or unpin) the protocol version used for a server's handshake
+    pub fn pin_protocol_version(&self, server_name: &str, version: Option<ProtocolVersion>) {
+        match version {
+            Some(version) => {
+                self.pinned_versions
+                    .insert(server_name.to_string(), version);
+            }
+            None => {
+                self.pinned_versions.remove(server_name);
+            }
+        }
+    }
+
+    /// Get the pinned protocol version for a server, if any
+    pub fn pinned_protocol_version(&self, server_name: &str) -> Option<ProtocolVersion> {
+        self.pinned_versions.get(server_name).map(|v| *v)
+    }
+
+    /// Set how long a server's initialize handshake may take
+    pub fn set_initialization_timeout(&self, server_name: &str, timeout: Duration) {
+        self.initialization_timeouts
+            .insert(server_name.to_string(), timeout);
+    }
+
+    /// Get the initialize handshake timeout for a server
+    pub fn initialization_timeout(&self, server_name: &str) -> Duration {
+        self.initialization_timeouts
+            .get(server_name)
+            .map(|t| *t)
+            .unwrap_or(DEFAULT_INITIALIZATION_TIMEOUT)
+    }
+
+    /// Set how old a server's connection may get before it is replaced, so
+    /// that its address is resolved again; `None` keeps it while it works
+    pub fn set_max_connection_age(&self, server_name: &str, max_age: Option<Duration>) {
+        match max_age {
+            Some(max_age) => {
+                self.max_ages.insert(server_name.to_string(), max_age);
+            }
+            None => {
+                self.max_ages.remove(server_name);
+            }
+        }
+    }
+
+    fn is_expired(&self, server_name: &str, stats: &ConnectionStats) -> bool {
+        self.max_ages
+            .get(server_name)
+            .is_some_and(|max_age| stats.created_at.elapsed() >= *max_age)
+    }
+
     pub async fn get(&self, server_name: &str) -> Result<Arc<dyn Connection>> {
         // Check if we have an existing connection
-        if let Some(conn) = self.connections.get(server_name) {
-            if !conn.is_closed() {
-                return Ok(conn.clone());
+        if let Some(pooled) = self.connections.get(server_name) {
+            if pooled.is_usable() && !self.is_expired(server_name, &pooled.stats) {
+                return Ok(pooled.lease(self.counters(server_name), self.recording(server_name)));
             }
-            // Connection is closed, remove it
-            drop(conn);
+            // Connection is closed, broken or past its age, remove it.
+            // Requests still using it keep it until they finish.
+            let idle = pooled.stats.in_flight.load(Ordering::SeqCst) == 0;
+            let usable = pooled.is_usable();
+            let connection = pooled.connection.clone();
+            drop(pooled);
             self.connections.remove(server_name);
+            if !usable && !connection.probe().await {
+                let _ = self.respawn_tx.send(server_name.to_string());
+            }
+            if idle {
+                let _ = connection.close().await;
+            }
         }
 
-        // Try to reconnect
-        if let Some(transport) = self.transports.get(server_name) {
-            let connection = transport.connect().await?;
+        // Swap in a warm spare, or reconnect
+        let transport = self
+            .transports
+            .get(server_name)
+            .map(|transport| transport.clone())
+            .ok_or_else(|| PoolError::ServerNotFound(server_name.to_string()))?;
+        let connection = match self.take_spare(server_name).await {
+            Some(connection) => connection,
+            None => self.connect(server_name, &transport).await?,
+        };
+        // The server may have been stopped while connecting
+        if !self.transports.contains_key(server_name) {
+            let _ = connection.close().await;
+            return Err(PoolError::ServerNotFound(server_name.to_string()).into());
+        }
+        let pooled = PooledConnection::new(connection);
+        let lease = pooled.lease(self.counters(server_name), self.recording(server_name));
+        self.connections.insert(server_name.to_string(), pooled);
+        Ok(lease)
+    }
 
-            // Initialize the reconnected connection
-            self.initialize_connection(server_name, &connection).await?;
+    /// Connection statistics for every server in the pool
+    pub fn stats(&self) -> Vec<PoolStats> {
+        let mut stats: Vec<PoolStats> = self
+            .transports
+            .iter()
+            .map(|entry| {
+                let server = entry.key().clone();
+                let counters = self.counters(&server);
+                let pooled = self.connections.get(&server);
+                let stats = pooled.as_ref().map(|pooled| pooled.stats.clone());
+                let latency = LatencySummary::of(&counters.latencies.lock().unwrap());
+                PoolStats {
+                    connections: usize::from(pooled.is_some()),
+                    in_flight: stats
+                        .as_ref()
+                        .map_or(0, |s| s.in_flight.load(Ordering::SeqCst)),
+                    idle_secs: stats
+                        .as_ref()
+                        .map(|s| s.last_used.lock().unwrap().elapsed().as_secs()),
+                    age_secs: stats.as_ref().map(|s| s.created_at.elapsed().as_secs()),
+                    requests: counters.requests.load(Ordering::Relaxed),
+                    failures: counters.failures.load(Ordering::Relaxed),
+                    error_replies: counters.error_replies.load(Ordering::Relaxed),
+                    reaped: counters.reaped.load(Ordering::Relaxed),
+                    warm_spares: self
+                        .warm_spares
+                        .get(&server)
+                        .map_or(0, |spares| spares.len()),
+                    failovers: counters.failovers.load(Ordering::Relaxed),
+                    cancelled: counters.cancelled.load(Ordering::Relaxed),
+                    responses: counters.responses.load(Ordering::Relaxed),
+                    total_latency_ms: counters.latency_total_us.load(Ordering::Relaxed) as f64
+                        / 1000.0,
+                    latency,
+                    server,
+                }
+            })
+            .collect();
+        stats.sort_by(|a, b| a.server.cmp(&b.server));
+        stats
+    }
 
-            self.connections
-                .insert(server_name.to_string(), connection.clone());
-            Ok(connection)
-        } else {
-            Err(PoolError::ServerNotFound(server_name.to_string()).into())
+    /// Keep `count` initialized connections to the server ready, to be
+    /// swapped in within milliseconds when its connection fails instead of
+    /// starting the server again. Runs until the server is removed from the
+    /// pool or given another count.
+    pub async fn keep_warm_spares(self: Arc<Self>, server_name: String, count: usize) {
+        let spares = Arc::new(WarmSpares {
+            count,
+            ready: std::sync::Mutex::new(VecDeque::new()),
+            changed: tokio::sync::Notify::new(),
+        });
+        if let Some(previous) = self.warm_spares.insert(server_name.clone(), spares.clone()) {
+            previous.changed.notify_one();
+        }
+        let current = |pool: &Self| {
+            pool.warm_spares
+                .get(&server_name)
+                .is_some_and(|kept| Arc::ptr_eq(&kept, &spares))
+        };
+
+        while current(&self) {
+            if spares.len() >= spares.count {
+                spares.changed.notified().await;
+                continue;
+            }
+            let Some(transport) = self.transports.get(&server_name).map(|t| t.clone()) else {
+                break;
+            };
+            let state = ServerConnectionState::new(server_name.clone());
+            let timeline: Timeline = Arc::new(std::sync::Mutex::new(StartupTimeline::starting()));
+            match self.open(&server_name, &transport, &state, &timeline).await {
+                Ok(connection) => {
+                    tracing::debug!("Warm spare for {} is ready", server_name);
+                    spares.ready.lock().unwrap().push_back(Spare {
+                        connection,
+                        state,
+                        timeline,
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to start a warm spare for {}: {}", server_name, e);
+                    tokio::select! {
+                        _ = tokio::time::sleep(SPARE_RETRY_DELAY) => {}
+                        _ = spares.changed.notified() => {}
+                    }
+                }
+            }
+        }
+        spares.close().await;
+    }
+
+    /// A working warm spare for the server, taking over the server's
+    /// connection state; spares that died while waiting are closed
+    async fn take_spare(&self, server_name: &str) -> Option<Arc<dyn Connection>> {
+        let spares = self.warm_spares.get(server_name)?.clone();
+        loop {
+            let spare = spares.ready.lock().unwrap().pop_front()?;
+            spares.changed.notify_one();
+            if !spare.connection.probe().await {
+                let _ = spare.connection.close().await;
+                continue;
+            }
+            self.connection_state(server_name).adopt(&spare.state).await;
+            self.timelines
+                .insert(server_name.to_string(), spare.timeline);
+            self.counters(server_name)
+                .failovers
+                .fetch_add(1, Ordering::Relaxed);
+            tracing::info!("Swapped in a warm spare for {}", server_name);
+            return Some(spare.connection);
+        }
+    }
+
+    /// Replace idle connections whose process has exited or whose transport
+    /// failed, so that the next request gets a working one. Connections in
+    /// use are left to the requests holding them. Returns the servers whose
+    /// connections were replaced.
+    pub async fn reap_stale(&self) -> Vec<String> {
+        let idle: Vec<(String, Arc<dyn Connection>, Arc<ConnectionStats>)> = self
+            .connections
+            .iter()
+            .filter(|entry| entry.stats.in_flight.load(Ordering::SeqCst) == 0)
+            .map(|entry| {
+                (
+                    entry.key().clone(),
+                    entry.connection.clone(),
+                    entry.stats.clone(),
+                )
+            })
+            .collect();
+
+        let mut reaped = Vec::new();
+        for (server_name, connection, stats) in idle {
+            let alive = connection.probe().await;
+            if alive && !stats.is_broken() && !self.is_expired(&server_name, &stats) {
+                continue;
+            }
+            // Leave it if a request replaced it meanwhile
+            let removed = self
+                .connections
+                .remove_if(&server_name, |_, pooled| Arc::ptr_eq(&pooled.stats, &stats));
+            if removed.is_none() {
+                continue;
+            }
+            let _ = connection.close().await;
+            self.counters(&server_name)
+                .reaped
+                .fetch_add(1, Ordering::Relaxed);
+            if !alive {
+                let _ = self.respawn_tx.send(server_name.clone());
+            }
+
+            tracing::warn!("Replacing stale connection to {}", server_name);
+            match self.get(&server_name).await {
+                Ok(_) => reaped.push(server_name),
+                Err(e) => tracing::warn!("Failed to reconnect to {}: {}", server_name, e),
+            }
+        }
+        reaped
+    }
+
+    /// Replace a server's connection with a new one, so that its address is
+    /// resolved again. Requests using the old connection see it closed.
+    pub async fn reconnect(&self, server_name: &str) -> Result<()> {
+        if let Some((_, pooled)) = self.connections.remove(server_name) {
+            let _ = pooled.connection.close().await;
+        }
+        self.get(server_name).await.map(|_| ())
+    }
+
+    /// Reap stale connections every `interval` until shutdown
+    pub async fn run_reaper(
+        self: Arc<Self>,
+        interval: Duration,
+        mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+    ) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    self.reap_stale().await;
+                }
+                _ = shutdown_rx.recv() => break,
+            }
         }
     }
 
     pub fn remove(&self, server_name: &str) {
         self.connections.remove(server_name);
+        if let Some((_, spares)) = self.warm_spares.remove(server_name) {
+            spares.changed.notify_one();
+        }
         self.transports.remove(server_name);
+        self.counters.remove(server_name);
+    }
+
+    /// Remove a server and close its connection
+    pub async fn close(&self, server_name: &str) -> Result<()> {
+        self.transports.remove(server_name);
+        self.counters.remove(server_name);
+        if let Some((_, spares)) = self.warm_spares.remove(server_name) {
+            spares.close().await;
+            spares.changed.notify_one();
+        }
+        if let Some((_, pooled)) = self.connections.remove(server_name) {
+            pooled.connection.close().await?;
+        }
+        Ok(())
     }
 
     pub async fn close_all(&self) -> Result<()> {
-        for conn in self.connections.iter() {
-            let _ = conn.value().close().await;
+        for pooled in self.connections.iter() {
+            let _ = pooled.connection.close().await;
         }
         self.connections.clear();
+        let spares: Vec<_> = self.warm_spares.iter().map(|s| s.clone()).collect();
+        self.warm_spares.clear();
+        for spares in spares {
+            spares.close().await;
+            spares.changed.notify_one();
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::TransportType;
+
+    /// Answers the initialize handshake until its process "exits", or with
+    /// the replies queued in `replies`
+    struct MockConnection {
+        alive: AtomicBool,
+        sent: std::sync::Mutex<Vec<Bytes>>,
+        replies: std::sync::Mutex<VecDeque<Bytes>>,
+    }
+
+    impl MockConnection {
+        /// The id of the last request sent
+        fn last_request_id(&self) -> u64 {
+            let sent = self.sent.lock().unwrap();
+            let request: serde_json::Value = serde_json::from_slice(sent.last().unwrap()).unwrap();
+            request["id"].as_u64().unwrap()
+        }
+
+        fn reply(&self, id: u64, text: &str) {
+            let reply =
+                serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": { "text": text } });
+            self.replies
+                .lock()
+                .unwrap()
+                .push_back(Bytes::from(reply.to_string()));
+        }
+    }
+
+    #[async_trait]
+    impl Connection for MockConnection {
+        async fn send(&self, data: Bytes) -> Result<()> {
+            if self.alive.load(Ordering::SeqCst) {
+                self.sent.lock().unwrap().push(data);
+                Ok(())
+            } else {
+                Err(TransportError::SendFailed("broken pipe".to_string()).into())
+            }
+        }
+
+        async fn recv(&self) -> Result<Bytes> {
+            if let Some(reply) = self.replies.lock().unwrap().pop_front() {
+                return Ok(reply);
+            }
+            Ok(Bytes::from_static(
+                br#"{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2025-03-26","capabilities":{}}}"#,
+            ))
+        }
+
+        async fn close(&self) -> Result<()> {
+            self.alive.store(false, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn is_closed(&self) -> bool {
+            false
+        }
+
+        async fn probe(&self) -> bool {
+            self.alive.load(Ordering::SeqCst)
+        }
+    }
+
+    #[derive(Default)]
+    struct MockTransport {
+        connections: std::sync::Mutex<Vec<Arc<MockConnection>>>,
+    }
+
+    impl MockTransport {
+        fn kill_latest(&self) {
+            let connections = self.connections.lock().unwrap();
+            connections
+                .last()
+                .unwrap()
+                .alive
+                .store(false, Ordering::SeqCst);
+        }
+
+        fn connects(&self) -> usize {
+            self.connections.lock().unwrap().len()
+        }
+    }
+
+    #[async_trait]
+    impl Transport for MockTransport {
+        async fn connect(&self) -> Result<Arc<dyn Connection>> {
+            let connection = Arc::new(MockConnection {
+                alive: AtomicBool::new(true),
+                sent: Default::default(),
+                replies: Default::default(),
+            });
+            self.connections.lock().unwrap().push(connection.clone());
+            Ok(connection)
+        }
+
+        fn transport_type(&self) -> TransportType {
+            TransportType::Stdio
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stats_and_reaping() {
+        let pool = ConnectionPool::new(Arc::default(), Arc::default());
+        let transport = Arc::new(MockTransport::default());
+        pool.add_server("files".to_string(), transport.clone())
+            .await
+            .unwrap();
+
+        // Every handshake step of the connection is timed
+        let timeline = pool.startup_timeline("files").unwrap();
+        let durations = timeline.durations();
+        assert!(durations.initialize_received_ms.is_some());
+        assert!(durations.ready_ms >= durations.initialize_sent_ms);
+        assert_eq!((timeline.first_byte_at, timeline.error), (None, None));
+
+        let lease = pool.get("files").await.unwrap();
+        assert_eq!(pool.stats()[0].in_flight, 1);
+        drop(lease);
+        let stats = &pool.stats()[0];
+        assert_eq!(
+            (stats.connections, stats.in_flight, stats.failures),
+            (1, 0, 0)
+        );
+
+        // Live connections are kept
+        assert!(pool.reap_stale().await.is_empty());
+        assert_eq!(transport.connects(), 1);
+
+        // A process that exited while idle is replaced before the next request
+        transport.kill_latest();
+        assert_eq!(pool.reap_stale().await, vec!["files".to_string()]);
+        assert_eq!(transport.connects(), 2);
+        assert_eq!(pool.stats()[0].reaped, 1);
+
+        // A failed send marks the connection broken, so it is not handed out again
+        transport.kill_latest();
+        let lease = pool.get("files").await.unwrap();
+        assert!(lease.send(Bytes::from_static(b"{}\n")).await.is_err());
+        drop(lease);
+        assert_eq!(pool.stats()[0].failures, 1);
+        pool.get("files").await.unwrap();
+        assert_eq!(transport.connects(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_respawns_are_reported() {
+        let pool = ConnectionPool::new(Arc::default(), Arc::default());
+        let mut respawns = pool.subscribe_respawns();
+        let transport = Arc::new(MockTransport::default());
+        pool.add_server("files".to_string(), transport.clone())
+            .await
+            .unwrap();
+
+        // A process that exited is reported whether the reaper or a request
+        // replaces it
+        transport.kill_latest();
+        pool.reap_stale().await;
+        assert_eq!(respawns.try_recv().unwrap(), "files");
+        transport.kill_latest();
+        let lease = pool.get("files").await.unwrap();
+        assert!(lease.send(Bytes::from_static(b"{}\n")).await.is_err());
+        drop(lease);
+        pool.get("files").await.unwrap();
+        assert_eq!(respawns.try_recv().unwrap(), "files");
+
+        // Deliberate reconnects and aged out connections are not
+        pool.reconnect("files").await.unwrap();
+        pool.set_max_connection_age("files", Some(Duration::ZERO));
+        pool.get("files").await.unwrap();
+        pool.reap_stale().await;
+        assert_eq!(transport.connects(), 6);
+        assert!(respawns.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_request_is_cancelled() {
+        let pool = ConnectionPool::new(Arc::default(), Arc::default());
+        let transport = Arc::new(MockTransport::default());
+        pool.add_server("files".to_string(), transport.clone())
+            .await
+            .unwrap();
+
+        // Notifications and answered requests leave the connection in use
+        let lease = pool.get("files").await.unwrap();
+        lease
+            .send(Bytes::from_static(
+                b"{\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"}\n",
+            ))
+            .await
+            .unwrap();
+        drop(lease);
+        let lease = pool.get("files").await.unwrap();
+        lease
+            .send(Bytes::from_static(
+                b"{\"jsonrpc\":\"2.0\",\"id\":4,\"method\":\"ping\"}\n",
+            ))
+            .await
+            .unwrap();
+        lease.recv().await.unwrap();
+        drop(lease);
+        assert_eq!(pool.stats()[0].cancelled, 0);
+
+        // A request given up on before its reply is cancelled under the id
+        // the server saw
+        let connection = transport.connections.lock().unwrap()[0].clone();
+        let lease = pool.get("files").await.unwrap();
+        lease
+            .send(Bytes::from_static(
+                b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"tools/call\"}\n",
+            ))
+            .await
+            .unwrap();
+        let abandoned = connection.last_request_id();
+        drop(lease);
+        tokio::task::yield_now().await;
+
+        let cancellation: serde_json::Value =
+            serde_json::from_slice(connection.sent.lock().unwrap().last().unwrap()).unwrap();
+        assert_eq!(cancellation["method"], "notifications/cancelled");
+        assert_eq!(cancellation["params"]["requestId"], abandoned);
+        assert_eq!(pool.stats()[0].cancelled, 1);
+
+        // The connection is kept, and the late reply is dropped rather than
+        // reaching the next request, which gets its own id back
+        let lease = pool.get("files").await.unwrap();
+        lease
+            .send(Bytes::from_static(
+                b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"tools/call\"}\n",
+            ))
+            .await
+            .unwrap();
+        let next = connection.last_request_id();
+        assert_ne!(next, abandoned);
+        connection.reply(abandoned, "late");
+        connection.reply(next, "current");
+        let reply: serde_json::Value =
+            serde_json::from_slice(&lease.recv().await.unwrap()).unwrap();
+        assert_eq!(reply["id"], 1);
+        assert_eq!(reply["result"]["text"], "current");
+        drop(lease);
+        assert_eq!(transport.connects(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_connection_age_and_reconnect() {
+        let pool = ConnectionPool::new(Arc::default(), Arc::default());
+        let transport = Arc::new(MockTransport::default());
+        pool.add_server("api".to_string(), transport.clone())
+            .await
+            .unwrap();
+
+        // Without a maximum age a working connection is kept
+        pool.get("api").await.unwrap();
+        assert_eq!(transport.connects(), 1);
+
+        // A forced reconnect replaces it even though it still works
+        pool.reconnect("api").await.unwrap();
+        assert_eq!(transport.connects(), 2);
+
+        // Past its age it is replaced on the next request, and by the reaper
+        pool.set_max_connection_age("api", Some(Duration::ZERO));
+        pool.get("api").await.unwrap();
+        assert_eq!(transport.connects(), 3);
+        assert_eq!(pool.reap_stale().await, vec!["api".to_string()]);
+        assert_eq!(transport.connects(), 4);
+
+        pool.set_max_connection_age("api", None);
+        pool.get("api").await.unwrap();
+        assert_eq!(transport.connects(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_warm_spare_replaces_failed_connection() {
+        let pool = Arc::new(ConnectionPool::new(Arc::default(), Arc::default()));
+        let transport = Arc::new(MockTransport::default());
+        pool.add_server("browser".to_string(), transport.clone())
+            .await
+            .unwrap();
+        tokio::spawn(pool.clone().keep_warm_spares("browser".to_string(), 1));
+        let spares_ready = |count: usize| {
+            let pool = pool.clone();
+            async move {
+                for _ in 0..100 {
+                    if pool.stats()[0].warm_spares == count {
+                        return;
+                    }
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                panic!("expected {count} warm spare(s)");
+            }
+        };
+        spares_ready(1).await;
+        assert_eq!(transport.connects(), 2);
+
+        // The failed connection is replaced by the spare, not a new process
+        let primary = transport.connections.lock().unwrap()[0].clone();
+        primary.alive.store(false, Ordering::SeqCst);
+        let lease = pool.get("browser").await.unwrap();
+        assert!(lease.send(Bytes::from_static(b"{}\n")).await.is_err());
+        drop(lease);
+        let lease = pool.get("browser").await.unwrap();
+        lease.send(Bytes::from_static(b"{}\n")).await.unwrap();
+        drop(lease);
+        assert_eq!(pool.stats()[0].failovers, 1);
+        assert!(pool.startup_timeline("browser").unwrap().ready_at.is_some());
+
+        // Another spare is started in its place
+        spares_ready(1).await;
+        assert_eq!(transport.connects(), 3);
+
+        // Removing the server stops its spares
+        pool.close("browser").await.unwrap();
+        let spare = transport.connections.lock().unwrap()[2].clone();
+        assert!(!spare.alive.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        assert_eq!(LatencySummary::of(&VecDeque::new()), None);
+
+        let samples: VecDeque<Duration> = (1..=100).rev().map(Duration::from_millis).collect();
+        let summary = LatencySummary::of(&samples).unwrap();
+        assert_eq!(summary.samples, 100);
+        assert_eq!(
+            (
+                summary.p50_ms,
+                summary.p95_ms,
+                summary.p99_ms,
+                summary.max_ms
+            ),
+            (50.0, 95.0, 99.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn test_is_error_reply() {
+        assert!(is_error_reply(
+            br#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"nope"}}"#
+        ));
+        assert!(!is_error_reply(
+            br#"{"jsonrpc":"2.0","id":1,"result":{"content":[{"type":"text","text":"\"error\""}]}}"#
+        ));
+        assert!(!is_error_reply(
+            br#"{"jsonrpc":"2.0","id":1,"error":null,"result":{}}"#
+        ));
+    }
+}
@@ -0,0 +1,382 @@
+//! Platform-specific child process management for stdio servers
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::process::{Child, Command};
+
+/// Extensions tried when PATHEXT is not set
+pub(crate) const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+/// Resolve a server command to the program that should be spawned.
+///
+/// On Windows, process creation only appends `.exe` when searching PATH, so
+/// npm-style shims such as `npx.cmd` are never found. Bare command names are
+/// resolved against the server's PATH using PATHEXT; on other platforms the
+/// command is returned unchanged.
+pub fn resolve_command(command: &str, env: &HashMap<String, String>) -> OsString {
+    #[cfg(windows)]
+    {
+        let path = env
+            .get("PATH")
+            .map(OsString::from)
+            .or_else(|| std::env::var_os("PATH"));
+        let pathext = env
+            .get("PATHEXT")
+            .cloned()
+            .or_else(|| std::env::var("PATHEXT").ok())
+            .unwrap_or_else(|| DEFAULT_PATHEXT.to_string());
+        let extensions: Vec<&str> = pathext.split(';').filter(|e| !e.is_empty()).collect();
+
+        if let Some(found) = path.and_then(|path| search_path(command, &path, &extensions)) {
+            tracing::debug!("Resolved command {} to {}", command, found.display());
+            return found.into_os_string();
+        }
+    }
+
+    #[cfg(not(windows))]
+    let _ = env;
+
+    OsString::from(command)
+}
+
+/// Search each directory in `path` for `command`, trying every extension when
+/// the command has none. Commands containing a directory are left alone.
+pub(crate) fn search_path(command: &str, path: &OsStr, extensions: &[&str]) -> Option<PathBuf> {
+    let command_path = Path::new(command);
+    if command_path.is_absolute() || command_path.components().count() > 1 {
+        return None;
+    }
+
+    for dir in std::env::split_paths(path) {
+        if command_path.extension().is_some() {
+            let candidate = dir.join(command);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+            continue;
+        }
+
+        for ext in extensions {
+            let candidate = dir.join(format!("{command}{}", ext.to_ascii_lowercase()));
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+    }
+
+    None
+}
+
+/// How a server process is asked to stop before it is killed
+#[derive(Debug, Clone, Copy)]
+pub struct StopPolicy {
+    /// Sent to the server's process group once its stdin is closed
+    #[cfg(unix)]
+    pub signal: nix::sys::signal::Signal,
+    /// How long the server may take to exit before it is killed
+    pub grace: Duration,
+}
+
+impl Default for StopPolicy {
+    fn default() -> Self {
+        Self {
+            #[cfg(unix)]
+            signal: nix::sys::signal::Signal::SIGTERM,
+            grace: Duration::from_secs(5),
+        }
+    }
+}
+
+impl StopPolicy {
+    /// The policy of a server's `stopSignal` and `stopTimeoutMs`. Signals
+    /// are names like `SIGTERM` or `INT`; they are ignored on Windows.
+    pub fn from_config(config: &crate::config::ServerConfig) -> Result<Self, String> {
+        #[cfg(unix)]
+        let signal = {
+            let name = config.stop_signal.to_ascii_uppercase();
+            let name = if name.starts_with("SIG") {
+                name
+            } else {
+                format!("SIG{name}")
+            };
+            name.parse()
+                .map_err(|_| format!("unknown stop signal '{}'", config.stop_signal))?
+        };
+
+        Ok(Self {
+            #[cfg(unix)]
+            signal,
+            grace: Duration::from_millis(config.stop_timeout_ms),
+        })
+    }
+}
+
+/// Apply platform process settings before spawning a server.
+///
+/// The child is killed if its handle is dropped. On Unix it leads a new
+/// process group, so that it and whatever it spawns can be signalled
+/// together. On Windows it is started without a console window, which
+/// Claude Desktop would otherwise flash open.
+pub fn configure_command(cmd: &mut Command) {
+    cmd.kill_on_drop(true);
+
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::System::Threading::CREATE_NO_WINDOW;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+}
+
+/// Proxy environment variables a server inherits when `envPassthrough` is unset
+pub const DEFAULT_ENV_PASSTHROUGH: &[&str] = &["PATH", "HOME"];
+
+/// Variables Windows programs (and `cmd.exe` shims) cannot run without
+const WINDOWS_REQUIRED_ENV: &[&str] = &[
+    "SYSTEMROOT",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "TEMP",
+    "TMP",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMDATA",
+];
+
+/// Whether a variable name matches a passthrough entry: an exact name, a
+/// prefix ending in `*`, or `*` alone. Names ignore case on Windows.
+fn env_matches(pattern: &str, name: &str) -> bool {
+    let eq = |a: &str, b: &str| {
+        if cfg!(windows) {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    };
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name
+            .get(..prefix.len())
+            .is_some_and(|start| eq(start, prefix)),
+        None => eq(pattern, name),
+    }
+}
+
+/// The variables of `vars` that a server may inherit
+fn inherited_env(
+    passthrough: &[String],
+    vars: impl IntoIterator<Item = (OsString, OsString)>,
+) -> Vec<(OsString, OsString)> {
+    vars.into_iter()
+        .filter(|(name, _)| {
+            let Some(name) = name.to_str() else {
+                return false;
+            };
+            passthrough.iter().any(|p| env_matches(p, name))
+                || (cfg!(windows) && WINDOWS_REQUIRED_ENV.iter().any(|p| env_matches(p, name)))
+        })
+        .collect()
+}
+
+/// Give the server only the proxy variables listed in `passthrough`, plus
+/// its own `env` entries, which win over inherited values. Everything else,
+/// such as the proxy's credentials, stays out of the child.
+pub fn apply_env(cmd: &mut Command, passthrough: &[String], env: &HashMap<String, String>) {
+    cmd.env_clear();
+    cmd.envs(inherited_env(passthrough, std::env::vars_os()));
+    cmd.envs(env);
+}
+
+/// Ties a child's whole process tree to the proxy.
+///
+/// On Windows the child is placed in a job object that kills every process in
+/// it when the last handle closes, so shims like `npx` cannot leave their
+/// `node` grandchildren behind, even if the proxy itself crashes. On Unix the
+/// child's process group is killed when the guard is dropped, which catches
+/// grandchildren that outlive the server.
+pub struct ProcessGuard {
+    #[cfg(windows)]
+    job: Option<job::JobObject>,
+    #[cfg(unix)]
+    group: Option<nix::unistd::Pid>,
+}
+
+impl ProcessGuard {
+    pub fn attach(child: &Child) -> Self {
+        #[cfg(windows)]
+        {
+            let job =
+                child
+                    .raw_handle()
+                    .and_then(|handle| match job::JobObject::for_process(handle) {
+                        Ok(job) => Some(job),
+                        Err(e) => {
+                            tracing::warn!(
+                                "Failed to assign server process to a job object: {}",
+                                e
+                            );
+                            None
+                        }
+                    });
+            Self { job }
+        }
+
+        #[cfg(unix)]
+        {
+            // The child leads its own group (see `configure_command`)
+            Self {
+                group: child.id().map(|id| nix::unistd::Pid::from_raw(id as i32)),
+            }
+        }
+    }
+
+    /// Send `signal` to every process in the tree
+    #[cfg(unix)]
+    pub fn signal(&self, signal: nix::sys::signal::Signal) {
+        if let Some(group) = self.group {
+            // ESRCH just means the group is already gone
+            let _ = nix::sys::signal::killpg(group, signal);
+        }
+    }
+
+    /// Forcefully stop every process in the tree
+    pub fn terminate(&self) {
+        #[cfg(windows)]
+        if let Some(job) = &self.job {
+            job.terminate();
+        }
+
+        #[cfg(unix)]
+        self.signal(nix::sys::signal::Signal::SIGKILL);
+    }
+}
+
+#[cfg(unix)]
+impl Drop for ProcessGuard {
+    fn drop(&mut self) {
+        self.terminate();
+    }
+}
+
+#[cfg(windows)]
+mod job {
+    use std::ffi::c_void;
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    pub struct JobObject(HANDLE);
+
+    // SAFETY: a job handle is an opaque kernel handle that may be used from any thread
+    unsafe impl Send for JobObject {}
+    unsafe impl Sync for JobObject {}
+
+    impl JobObject {
+        pub fn for_process(process: std::os::windows::io::RawHandle) -> std::io::Result<Self> {
+            // SAFETY: plain Win32 calls on handles we own; failures are checked
+            unsafe {
+                let handle = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+                if handle.is_null() {
+                    return Err(std::io::Error::last_os_error());
+                }
+                let job = Self(handle);
+
+                let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+                info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+                if SetInformationJobObject(
+                    job.0,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const c_void,
+                    std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                ) == 0
+                {
+                    return Err(std::io::Error::last_os_error());
+                }
+
+                if AssignProcessToJobObject(job.0, process as HANDLE) == 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+
+                Ok(job)
+            }
+        }
+
+        pub fn terminate(&self) {
+            // SAFETY: the handle stays valid until drop
+            unsafe {
+                TerminateJobObject(self.0, 1);
+            }
+        }
+    }
+
+    impl Drop for JobObject {
+        fn drop(&mut self) {
+            // SAFETY: closing our own handle; KILL_ON_JOB_CLOSE ends the process tree
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inherited_env_filters_by_passthrough() {
+        let vars = [
+            ("PATH", "/usr/bin"),
+            ("HOME", "/home/mcp"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+            ("LC_ALL", "C"),
+            ("LC_TIME", "C"),
+        ]
+        .map(|(name, value)| (OsString::from(name), OsString::from(value)));
+        let names = |passthrough: &[&str]| {
+            let passthrough: Vec<String> = passthrough.iter().map(|p| p.to_string()).collect();
+            inherited_env(&passthrough, vars.clone())
+                .into_iter()
+                .map(|(name, _)| name.into_string().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(DEFAULT_ENV_PASSTHROUGH), ["PATH", "HOME"]);
+        assert_eq!(names(&["LC_*"]), ["LC_ALL", "LC_TIME"]);
+        assert_eq!(names(&["*"]).len(), vars.len());
+        assert!(names(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_search_path_finds_shim_extensions() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("npx.cmd"), "").unwrap();
+        std::fs::write(dir.path().join("uvx.exe"), "").unwrap();
+        let path = std::env::join_paths([dir.path()]).unwrap();
+        let extensions = [".EXE", ".CMD"];
+
+        assert_eq!(
+            search_path("npx", &path, &extensions),
+            Some(dir.path().join("npx.cmd"))
+        );
+        assert_eq!(
+            search_path("uvx", &path, &extensions),
+            Some(dir.path().join("uvx.exe"))
+        );
+        assert_eq!(
+            search_path("npx.cmd", &path, &extensions),
+            Some(dir.path().join("npx.cmd"))
+        );
+        assert_eq!(search_path("missing", &path, &extensions), None);
+        // Commands with a directory component are never searched
+        assert_eq!(search_path("./npx", &path, &extensions), None);
+    }
+}
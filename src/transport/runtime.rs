@@ -0,0 +1,203 @@
+//! Servers run as packages through a runtime's launcher (`npx -y`, `uvx`,
+//! `bunx`) rather than as installed executables
+
+use super::process::{search_path, DEFAULT_PATHEXT};
+use crate::config::{Runtime, ServerConfig};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use tokio::process::Command;
+use tokio::time::Duration;
+
+/// How long fetching one package may take at startup
+const PREINSTALL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// The launcher program and the arguments it takes before the package
+fn launcher(runtime: Runtime) -> (&'static str, &'static [&'static str]) {
+    match runtime {
+        Runtime::Node => ("npx", &["-y"]),
+        Runtime::Python => ("uvx", &[]),
+        Runtime::Bun => ("bunx", &[]),
+    }
+}
+
+/// The package with its pinned version, as the launchers take it
+fn package_spec(config: &ServerConfig) -> String {
+    match &config.package_version {
+        Some(version) => format!("{}@{}", config.command, version),
+        None => config.command.clone(),
+    }
+}
+
+/// The program and arguments a stdio server is spawned with
+pub fn launch_command(config: &ServerConfig) -> (String, Vec<String>) {
+    let Some(runtime) = config.runtime else {
+        return (config.command.clone(), config.args.clone());
+    };
+    let (program, launcher_args) = launcher(runtime);
+    let mut args: Vec<String> = launcher_args.iter().map(|arg| arg.to_string()).collect();
+    args.push(package_spec(config));
+    args.extend(config.args.iter().cloned());
+    (program.to_string(), args)
+}
+
+/// Check that the launcher of a `runtime` server can be found on the PATH
+/// the server gets
+pub fn verify(config: &ServerConfig) -> Result<(), String> {
+    let Some(runtime) = config.runtime else {
+        return Ok(());
+    };
+    let (program, _) = launcher(runtime);
+    if find_program(program, &config.env) {
+        Ok(())
+    } else {
+        let install = match runtime {
+            Runtime::Node => "Node.js",
+            Runtime::Python => "uv",
+            Runtime::Bun => "Bun",
+        };
+        Err(format!(
+            "{program} was not found on PATH; install {install} to run this server"
+        ))
+    }
+}
+
+fn find_program(program: &str, env: &HashMap<String, String>) -> bool {
+    let Some(path) = env
+        .get("PATH")
+        .map(OsString::from)
+        .or_else(|| std::env::var_os("PATH"))
+    else {
+        return false;
+    };
+    let pathext = if cfg!(windows) {
+        std::env::var("PATHEXT").unwrap_or_else(|_| DEFAULT_PATHEXT.to_string())
+    } else {
+        String::new()
+    };
+    let extensions: Vec<&str> = pathext.split(';').collect();
+    search_path(program, &path, &extensions).is_some()
+}
+
+/// The command putting a server's package where its launcher looks first
+fn preinstall_command(runtime: Runtime, config: &ServerConfig) -> (&'static str, Vec<String>) {
+    match runtime {
+        Runtime::Node => (
+            "npm",
+            vec!["cache".into(), "add".into(), package_spec(config)],
+        ),
+        Runtime::Python => {
+            let spec = match &config.package_version {
+                Some(version) => format!("{}=={}", config.command, version),
+                None => config.command.clone(),
+            };
+            ("uv", vec!["tool".into(), "install".into(), spec])
+        }
+        Runtime::Bun => (
+            "bun",
+            vec!["add".into(), "--global".into(), package_spec(config)],
+        ),
+    }
+}
+
+/// Fetch the packages of servers with `preinstall` set, all at once. Failures
+/// are logged: the launcher fetches the package itself when it starts.
+pub async fn preinstall_packages(servers: &HashMap<String, ServerConfig>) {
+    let installs = servers
+        .iter()
+        .filter(|(_, config)| config.preinstall)
+        .filter_map(|(name, config)| Some((name, config, config.runtime?)))
+        .map(|(name, config, runtime)| async move {
+            let (program, args) = preinstall_command(runtime, config);
+            tracing::info!("Preinstalling {} for server {}", package_spec(config), name);
+            let mut command = Command::new(program);
+            command.args(&args).envs(&config.env).kill_on_drop(true);
+            match tokio::time::timeout(PREINSTALL_TIMEOUT, command.output()).await {
+                Ok(Ok(output)) if output.status.success() => {}
+                Ok(Ok(output)) => tracing::warn!(
+                    "Preinstalling {} for server {} failed: {}",
+                    package_spec(config),
+                    name,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+                Ok(Err(e)) => tracing::warn!(
+                    "Cannot run {} to preinstall server {}: {}",
+                    program,
+                    name,
+                    e
+                ),
+                Err(_) => tracing::warn!(
+                    "Preinstalling {} for server {} timed out",
+                    package_spec(config),
+                    name
+                ),
+            }
+        });
+    futures::future::join_all(installs).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(runtime: Option<&str>, version: Option<&str>) -> ServerConfig {
+        serde_json::from_value(serde_json::json!({
+            "command": "@modelcontextprotocol/server-github",
+            "args": ["--read-only"],
+            "runtime": runtime,
+            "packageVersion": version,
+            "transport": { "type": "stdio" }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_launch_command_expands_runtime() {
+        assert_eq!(
+            launch_command(&server(Some("node"), Some("2025.4.8"))),
+            (
+                "npx".to_string(),
+                vec![
+                    "-y".to_string(),
+                    "@modelcontextprotocol/server-github@2025.4.8".to_string(),
+                    "--read-only".to_string()
+                ]
+            )
+        );
+        assert_eq!(
+            launch_command(&server(Some("bun"), None)).1[0],
+            "@modelcontextprotocol/server-github"
+        );
+        let (program, args) = launch_command(&server(None, None));
+        assert_eq!(program, "@modelcontextprotocol/server-github");
+        assert_eq!(args, ["--read-only"]);
+        assert_eq!(
+            preinstall_command(Runtime::Python, &server(Some("python"), Some("1.0"))).1,
+            [
+                "tool",
+                "install",
+                "@modelcontextprotocol/server-github==1.0"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verify_looks_for_launcher_on_server_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = server(Some("python"), None);
+        config.env.insert(
+            "PATH".to_string(),
+            dir.path().to_string_lossy().into_owned(),
+        );
+        let error = verify(&config).unwrap_err();
+        assert!(error.contains("uvx was not found"), "{error}");
+
+        let uvx = dir.path().join("uvx");
+        std::fs::write(&uvx, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&uvx, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(verify(&config).is_ok());
+        assert!(verify(&server(None, None)).is_ok());
+    }
+}
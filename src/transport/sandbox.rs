@@ -0,0 +1,374 @@
+//! Running stdio servers as another user and inside a Linux sandbox.
+//!
+//! Everything that can allocate or block (account lookup, opening rule
+//! paths, building the Landlock ruleset) happens in the proxy before the
+//! fork. Between fork and exec the child only makes async-signal-safe
+//! system calls on that prepared data.
+
+use crate::config::{RunAsConfig, SandboxConfig};
+use std::path::Path;
+use tokio::process::Command;
+
+pub use imp::Confinement;
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use super::*;
+    use std::ffi::CString;
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::PathBuf;
+
+    /// The account switch and restrictions applied to one server process
+    pub struct Confinement {
+        account: Option<Account>,
+        /// Paths to hide behind tmpfs, or `None` to share the proxy's mount
+        /// namespace
+        mounts: Option<Vec<CString>>,
+        no_new_privileges: bool,
+        ruleset: Option<OwnedFd>,
+    }
+
+    struct Account {
+        uid: libc::uid_t,
+        gid: libc::gid_t,
+        groups: Vec<libc::gid_t>,
+        home: PathBuf,
+    }
+
+    impl Confinement {
+        /// Look up the account and build the sandbox; `None` when neither is
+        /// configured
+        pub fn prepare(
+            run_as: Option<&RunAsConfig>,
+            sandbox: Option<&SandboxConfig>,
+        ) -> Result<Option<Self>, String> {
+            if run_as.is_none() && sandbox.is_none() {
+                return Ok(None);
+            }
+
+            let account = run_as.map(Account::lookup).transpose()?;
+            let mut confinement = Self {
+                account,
+                mounts: None,
+                no_new_privileges: false,
+                ruleset: None,
+            };
+            if let Some(sandbox) = sandbox {
+                if sandbox.mount_namespace || !sandbox.tmpfs_paths.is_empty() {
+                    let mounts = sandbox
+                        .tmpfs_paths
+                        .iter()
+                        .map(|path| c_path(path))
+                        .collect::<Result<_, _>>()?;
+                    confinement.mounts = Some(mounts);
+                }
+                confinement.no_new_privileges = sandbox.no_new_privileges;
+                if !sandbox.read_only_paths.is_empty() || !sandbox.read_write_paths.is_empty() {
+                    confinement.ruleset = Some(landlock::ruleset(
+                        &sandbox.read_only_paths,
+                        &sandbox.read_write_paths,
+                    )?);
+                }
+            }
+            Ok(Some(confinement))
+        }
+
+        /// Home directory of the account the server runs as
+        pub fn home(&self) -> Option<&Path> {
+            self.account.as_ref().map(|account| account.home.as_path())
+        }
+
+        /// Apply the confinement in the child just before it execs
+        pub fn install(self, cmd: &mut Command) {
+            // SAFETY: `enter` only makes async-signal-safe system calls on
+            // data prepared before the fork
+            unsafe {
+                cmd.pre_exec(move || self.enter());
+            }
+        }
+
+        fn enter(&self) -> io::Result<()> {
+            if let Some(mounts) = &self.mounts {
+                // SAFETY: plain system calls on NUL-terminated strings
+                unsafe {
+                    check(libc::unshare(libc::CLONE_NEWNS))?;
+                    // Keep mounts made from here on out of the host
+                    check(libc::mount(
+                        std::ptr::null(),
+                        c"/".as_ptr(),
+                        std::ptr::null(),
+                        libc::MS_REC | libc::MS_PRIVATE,
+                        std::ptr::null(),
+                    ))?;
+                    for path in mounts {
+                        check(libc::mount(
+                            c"tmpfs".as_ptr(),
+                            path.as_ptr(),
+                            c"tmpfs".as_ptr(),
+                            libc::MS_NOSUID | libc::MS_NODEV,
+                            std::ptr::null(),
+                        ))?;
+                    }
+                }
+            }
+
+            if let Some(account) = &self.account {
+                // Groups go first: after setuid they can no longer be changed
+                // SAFETY: `groups` outlives the call
+                unsafe {
+                    check(libc::setgroups(
+                        account.groups.len(),
+                        account.groups.as_ptr(),
+                    ))?;
+                    check(libc::setgid(account.gid))?;
+                    check(libc::setuid(account.uid))?;
+                }
+            }
+
+            // Landlock requires no_new_privs once root privileges are gone
+            if self.no_new_privileges || self.ruleset.is_some() {
+                // SAFETY: no pointers involved
+                unsafe { check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0))? };
+            }
+
+            if let Some(ruleset) = &self.ruleset {
+                // SAFETY: `ruleset` is an open Landlock ruleset
+                let ret = unsafe {
+                    libc::syscall(libc::SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0u32)
+                };
+                check(ret as libc::c_int)?;
+            }
+            Ok(())
+        }
+    }
+
+    impl Account {
+        fn lookup(run_as: &RunAsConfig) -> Result<Self, String> {
+            use nix::unistd::{getgrouplist, Group, User};
+
+            let user = User::from_name(&run_as.user)
+                .map_err(|e| format!("cannot look up user '{}': {e}", run_as.user))?
+                .ok_or_else(|| format!("unknown user '{}'", run_as.user))?;
+            let gid = match &run_as.group {
+                Some(name) => {
+                    Group::from_name(name)
+                        .map_err(|e| format!("cannot look up group '{name}': {e}"))?
+                        .ok_or_else(|| format!("unknown group '{name}'"))?
+                        .gid
+                }
+                None => user.gid,
+            };
+            let name = CString::new(user.name.as_str())
+                .map_err(|_| format!("invalid user name '{}'", user.name))?;
+            let groups = getgrouplist(&name, gid)
+                .map_err(|e| format!("cannot list groups of '{}': {e}", user.name))?;
+
+            Ok(Self {
+                uid: user.uid.as_raw(),
+                gid: gid.as_raw(),
+                groups: groups.into_iter().map(|group| group.as_raw()).collect(),
+                home: user.dir,
+            })
+        }
+    }
+
+    fn c_path(path: &Path) -> Result<CString, String> {
+        CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| format!("invalid path {}", path.display()))
+    }
+
+    fn check(ret: libc::c_int) -> io::Result<()> {
+        if ret == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Just enough of the Landlock ABI (version 1) to grant access beneath
+    /// a set of paths
+    mod landlock {
+        use super::*;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        const RULE_PATH_BENEATH: libc::c_int = 1;
+
+        const ACCESS_EXECUTE: u64 = 1 << 0;
+        const ACCESS_WRITE_FILE: u64 = 1 << 1;
+        const ACCESS_READ_FILE: u64 = 1 << 2;
+        const ACCESS_READ_DIR: u64 = 1 << 3;
+        /// Every filesystem right in ABI version 1
+        const ACCESS_ALL: u64 = (1 << 13) - 1;
+        const ACCESS_READ_ONLY: u64 = ACCESS_EXECUTE | ACCESS_READ_FILE | ACCESS_READ_DIR;
+        /// The rights that apply to a file rather than a directory
+        const ACCESS_FILE: u64 = ACCESS_EXECUTE | ACCESS_WRITE_FILE | ACCESS_READ_FILE;
+
+        #[repr(C)]
+        struct RulesetAttr {
+            handled_access_fs: u64,
+        }
+
+        #[repr(C, packed)]
+        struct PathBeneathAttr {
+            allowed_access: u64,
+            parent_fd: i32,
+        }
+
+        /// A ruleset denying all filesystem access except read and execute
+        /// beneath `read_only` and everything beneath `read_write`
+        pub fn ruleset(read_only: &[PathBuf], read_write: &[PathBuf]) -> Result<OwnedFd, String> {
+            let attr = RulesetAttr {
+                handled_access_fs: ACCESS_ALL,
+            };
+            // SAFETY: `attr` is a valid ruleset attribute of the given size
+            let fd = unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_create_ruleset,
+                    &attr as *const RulesetAttr,
+                    std::mem::size_of::<RulesetAttr>(),
+                    0u32,
+                )
+            };
+            if fd < 0 {
+                return Err(format!(
+                    "Landlock is unavailable: {}",
+                    io::Error::last_os_error()
+                ));
+            }
+            // SAFETY: the kernel just returned this descriptor (close-on-exec)
+            let ruleset = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+            for (paths, access) in [(read_only, ACCESS_READ_ONLY), (read_write, ACCESS_ALL)] {
+                for path in paths {
+                    allow(&ruleset, path, access)
+                        .map_err(|e| format!("cannot allow {}: {e}", path.display()))?;
+                }
+            }
+            Ok(ruleset)
+        }
+
+        fn allow(ruleset: &OwnedFd, path: &Path, access: u64) -> io::Result<()> {
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_PATH)
+                .open(path)?;
+            let access = if file.metadata()?.is_dir() {
+                access
+            } else {
+                access & ACCESS_FILE
+            };
+            let attr = PathBeneathAttr {
+                allowed_access: access,
+                parent_fd: file.as_raw_fd(),
+            };
+            // SAFETY: `attr` is a valid path-beneath rule and `file` is open
+            let ret = unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_add_rule,
+                    ruleset.as_raw_fd(),
+                    RULE_PATH_BENEATH,
+                    &attr as *const PathBeneathAttr,
+                    0u32,
+                )
+            };
+            check(ret as libc::c_int)
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use super::*;
+
+    /// Never constructed: confinement is Linux-only
+    pub struct Confinement(std::convert::Infallible);
+
+    impl Confinement {
+        pub fn prepare(
+            run_as: Option<&RunAsConfig>,
+            sandbox: Option<&SandboxConfig>,
+        ) -> Result<Option<Self>, String> {
+            if run_as.is_some() || sandbox.is_some() {
+                Err("runAs and sandbox are only supported on Linux".to_string())
+            } else {
+                Ok(None)
+            }
+        }
+
+        pub fn home(&self) -> Option<&Path> {
+            match self.0 {}
+        }
+
+        pub fn install(self, _cmd: &mut Command) {
+            match self.0 {}
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"echo written > "$1/out"; cat /etc/passwd > /dev/null 2>&1 && echo leaked || echo denied"#;
+
+    #[tokio::test]
+    async fn test_landlock_limits_filesystem() {
+        let dir = tempfile::tempdir().unwrap();
+        let read_only: Vec<_> = ["/usr", "/bin", "/lib", "/lib64"]
+            .into_iter()
+            .filter(|path| Path::new(path).exists())
+            .collect();
+        let sandbox: SandboxConfig = serde_json::from_value(serde_json::json!({
+            "readOnlyPaths": read_only,
+            "readWritePaths": [dir.path()]
+        }))
+        .unwrap();
+        let confinement = match Confinement::prepare(None, Some(&sandbox)) {
+            Ok(confinement) => confinement.unwrap(),
+            Err(e) if e.starts_with("Landlock is unavailable") => return,
+            Err(e) => panic!("{e}"),
+        };
+
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", SCRIPT, "sh"]).arg(dir.path());
+        confinement.install(&mut cmd);
+        let output = cmd.output().await.unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "denied\n");
+        assert!(dir.path().join("out").exists());
+    }
+
+    #[tokio::test]
+    async fn test_runs_as_user_in_mount_namespace() {
+        // Switching users and mounting both need root
+        if !nix::unistd::geteuid().is_root() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("secret"), "").unwrap();
+        let run_as = RunAsConfig {
+            user: "nobody".to_string(),
+            group: None,
+        };
+        let sandbox: SandboxConfig = serde_json::from_value(serde_json::json!({
+            "tmpfsPaths": [dir.path()]
+        }))
+        .unwrap();
+        let confinement = Confinement::prepare(Some(&run_as), Some(&sandbox))
+            .unwrap()
+            .unwrap();
+
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", r#"id -u; ls -A "$1""#, "sh"])
+            .arg(dir.path());
+        confinement.install(&mut cmd);
+        let output = cmd.output().await.unwrap();
+
+        // The tmpfs hides the directory's contents from the server only
+        let uid = nix::unistd::User::from_name("nobody").unwrap().unwrap().uid;
+        assert_eq!(String::from_utf8_lossy(&output.stdout), format!("{uid}\n"));
+        assert!(dir.path().join("secret").exists());
+    }
+}
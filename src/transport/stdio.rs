@@ -1,12 +1,20 @@
+use super::framing::{Frame, MessageReader};
+use super::process::{
+    apply_env, configure_command, resolve_command, ProcessGuard, StopPolicy,
+    DEFAULT_ENV_PASSTHROUGH,
+};
+use super::sandbox::Confinement;
 use super::{Connection, Transport, TransportType};
-use crate::error::{Result, TransportError};
+use crate::config::{RunAsConfig, SandboxConfig};
+use crate::error::{ProxyError, Result, TransportError};
+use crate::logging::LogClassifier;
 use crate::state::{LogEntry, ServerInfo};
 use async_trait::async_trait;
-use bytes::{Bytes, BytesMut};
-use chrono::Utc;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use std::sync::{Arc, OnceLock};
+use tokio::io::{AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout, Command};
 use tokio::sync::Mutex;
 
@@ -14,8 +22,15 @@ pub struct StdioTransport {
     command: String,
     args: Vec<String>,
     env: std::collections::HashMap<String, String>,
+    env_passthrough: Vec<String>,
     working_dir: Option<std::path::PathBuf>,
     server_info: Option<Arc<ServerInfo>>,
+    log_classifier: Option<Arc<LogClassifier>>,
+    strict_stdout: bool,
+    max_message_bytes: usize,
+    stop_policy: StopPolicy,
+    run_as: Option<RunAsConfig>,
+    sandbox: Option<SandboxConfig>,
 }
 
 impl Default for StdioTransport {
@@ -30,8 +45,18 @@ impl StdioTransport {
             command: String::new(),
             args: Vec::new(),
             env: std::collections::HashMap::new(),
+            env_passthrough: DEFAULT_ENV_PASSTHROUGH
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
             working_dir: None,
             server_info: None,
+            log_classifier: None,
+            strict_stdout: false,
+            max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+            stop_policy: StopPolicy::default(),
+            run_as: None,
+            sandbox: None,
         }
     }
 
@@ -46,6 +71,12 @@ impl StdioTransport {
         self
     }
 
+    /// Proxy environment variables the server inherits; see [`apply_env`]
+    pub fn with_env_passthrough(mut self, names: Vec<String>) -> Self {
+        self.env_passthrough = names;
+        self
+    }
+
     pub fn with_working_dir(mut self, dir: std::path::PathBuf) -> Self {
         self.working_dir = Some(dir);
         self
@@ -55,20 +86,66 @@ impl StdioTransport {
         self.server_info = Some(server_info);
         self
     }
+
+    pub fn with_log_classifier(mut self, classifier: Arc<LogClassifier>) -> Self {
+        self.log_classifier = Some(classifier);
+        self
+    }
+
+    /// Fail reads on stdout lines that are not JSON-RPC messages. By
+    /// default such lines (banners, debug output) are logged and skipped.
+    pub fn with_strict_stdout(mut self, strict: bool) -> Self {
+        self.strict_stdout = strict;
+        self
+    }
+
+    /// Longest line read from the server's stdout or stderr; longer ones
+    /// are discarded
+    pub fn with_max_message_bytes(mut self, limit: usize) -> Self {
+        self.max_message_bytes = limit;
+        self
+    }
+
+    /// Run the server as another user and/or inside a sandbox (Linux only)
+    pub fn with_confinement(
+        mut self,
+        run_as: Option<RunAsConfig>,
+        sandbox: Option<SandboxConfig>,
+    ) -> Self {
+        self.run_as = run_as;
+        self.sandbox = sandbox;
+        self
+    }
+
+    pub fn with_stop_policy(mut self, policy: StopPolicy) -> Self {
+        self.stop_policy = policy;
+        self
+    }
 }
 
 #[async_trait]
 impl Transport for StdioTransport {
     async fn connect(&self) -> Result<Arc<dyn Connection>> {
-        let mut cmd = Command::new(&self.command);
+        let mut cmd = Command::new(resolve_command(&self.command, &self.env));
+        configure_command(&mut cmd);
         cmd.args(&self.args)
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped());
 
-        // Set environment variables
-        for (key, value) in &self.env {
-            cmd.env(key, value);
+        apply_env(&mut cmd, &self.env_passthrough, &self.env);
+
+        let confinement = Confinement::prepare(self.run_as.as_ref(), self.sandbox.as_ref())
+            .map_err(|e| {
+                TransportError::ConnectionFailed(format!("Failed to confine server: {e}"))
+            })?;
+        if let Some(confinement) = confinement {
+            if let Some(home) = confinement.home() {
+                if !self.env.contains_key("HOME") {
+                    cmd.env("HOME", home);
+                }
+            }
+            confinement.install(&mut cmd);
         }
 
         // Set working directory if specified
@@ -79,6 +156,7 @@ impl Transport for StdioTransport {
         let mut child = cmd.spawn().map_err(|e| {
             TransportError::ConnectionFailed(format!("Failed to spawn process: {e}"))
         })?;
+        let process_guard = ProcessGuard::attach(&child);
 
         let stdin = child
             .stdin
@@ -93,15 +171,27 @@ impl Transport for StdioTransport {
             .take()
             .ok_or_else(|| TransportError::ConnectionFailed("Failed to get stderr".into()))?;
 
+        let first_output = Arc::new(OnceLock::new());
+
         // Start stderr reader if we have server info
         if let Some(ref server_info) = self.server_info {
             tracing::debug!("Starting stderr reader for server: {}", server_info.name);
             let server_info_clone = Arc::clone(server_info);
+            let classifier = self.log_classifier.clone();
+            let first_output = first_output.clone();
+            let limit = self.max_message_bytes;
             tokio::spawn(async move {
                 tracing::debug!("Stderr reader task started");
-                let reader = BufReader::new(stderr);
-                let mut lines = reader.lines();
-                while let Ok(Some(line)) = lines.next_line().await {
+                let mut lines = MessageReader::new(BufReader::new(stderr), limit);
+                while let Ok(Some(frame)) = lines.next().await {
+                    first_output.get_or_init(Utc::now);
+                    let line = match frame {
+                        Frame::Line(line) => String::from_utf8_lossy(&line).trim_end().to_string(),
+                        Frame::Oversized(oversized) => format!(
+                            "[{}-byte line over the {limit}-byte limit omitted]",
+                            oversized.size
+                        ),
+                    };
                     tracing::debug!("Captured stderr from process: {}", line);
 
                     // Write to log file if logger is available
@@ -111,12 +201,11 @@ impl Transport for StdioTransport {
                         }
                     }
 
-                    let log_entry = LogEntry {
-                        timestamp: Utc::now(),
-                        level: "error".to_string(),
-                        message: line.clone(),
-                    };
-                    server_info_clone.broadcast_log(log_entry);
+                    server_info_clone.broadcast_log(classify_line(
+                        classifier.as_deref(),
+                        &line,
+                        "error",
+                    ));
                 }
                 tracing::debug!("Stderr reader task ended");
             });
@@ -126,10 +215,18 @@ impl Transport for StdioTransport {
 
         Ok(Arc::new(StdioConnection {
             child: Arc::new(Mutex::new(child)),
-            stdin: Arc::new(Mutex::new(stdin)),
-            stdout: Arc::new(Mutex::new(stdout)),
+            process_guard,
+            stdin: Arc::new(Mutex::new(Some(stdin))),
+            stdout: Arc::new(Mutex::new(MessageReader::new(
+                BufReader::new(stdout),
+                self.max_message_bytes,
+            ))),
             closed: Arc::new(AtomicBool::new(false)),
+            first_output,
             server_info: self.server_info.clone(),
+            log_classifier: self.log_classifier.clone(),
+            strict_stdout: self.strict_stdout,
+            stop_policy: self.stop_policy,
         }))
     }
 
@@ -140,10 +237,40 @@ impl Transport for StdioTransport {
 
 pub struct StdioConnection {
     child: Arc<Mutex<Child>>,
-    stdin: Arc<Mutex<ChildStdin>>,
-    stdout: Arc<Mutex<ChildStdout>>,
+    process_guard: ProcessGuard,
+    stdin: Arc<Mutex<Option<ChildStdin>>>,
+    stdout: Arc<Mutex<MessageReader<BufReader<ChildStdout>>>>,
     closed: Arc<AtomicBool>,
+    /// When output from the process, on stdout or stderr, was first seen
+    first_output: Arc<OnceLock<DateTime<Utc>>>,
     server_info: Option<Arc<ServerInfo>>,
+    log_classifier: Option<Arc<LogClassifier>>,
+    strict_stdout: bool,
+    stop_policy: StopPolicy,
+}
+
+/// Longest line read from a server's stdout or stderr unless configured
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Whether a stdout line is a JSON-RPC message (an object, or an array for
+/// a batch) rather than stray output
+fn is_message(line: &[u8]) -> bool {
+    let line = line.trim_ascii();
+    matches!(line.first(), Some(b'{' | b'['))
+        && serde_json::from_slice::<serde::de::IgnoredAny>(line).is_ok()
+}
+
+/// Turn a captured output line into a log entry, using the server's parsers
+/// when configured and `default_level` otherwise
+fn classify_line(classifier: Option<&LogClassifier>, line: &str, default_level: &str) -> LogEntry {
+    match classifier {
+        Some(classifier) => classifier.entry(line, default_level, Utc::now()),
+        None => LogEntry {
+            timestamp: Utc::now(),
+            level: default_level.to_string(),
+            message: line.to_string(),
+        },
+    }
 }
 
 #[async_trait]
@@ -153,7 +280,8 @@ impl Connection for StdioConnection {
             return Err(TransportError::Closed.into());
         }
 
-        let mut stdin = self.stdin.lock().await;
+        let mut stdin_guard = self.stdin.lock().await;
+        let stdin = stdin_guard.as_mut().ok_or(TransportError::Closed)?;
 
         tracing::trace!(
             "Sending to stdio: {}",
@@ -178,54 +306,90 @@ impl Connection for StdioConnection {
         }
 
         let mut stdout = self.stdout.lock().await;
-        let mut buffer = BytesMut::with_capacity(8192);
 
-        // Read until we get a complete message (assuming newline-delimited JSON)
+        // Messages are newline-delimited JSON; anything else on stdout is
+        // logged and skipped unless the server is strict
         loop {
-            let n = stdout
-                .read_buf(&mut buffer)
+            let frame = stdout
+                .next()
                 .await
                 .map_err(|e| TransportError::ReceiveFailed(e.to_string()))?;
-
-            if n == 0 {
+            let Some(frame) = frame else {
                 self.closed.store(true, Ordering::SeqCst);
                 return Err(TransportError::Closed.into());
-            }
-
-            // Check if we have a complete message
-            if let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
-                let message = buffer.split_to(pos + 1);
-                let msg_bytes = message.freeze();
-                let msg_str = std::str::from_utf8(&msg_bytes).unwrap_or("<binary>");
-                tracing::trace!("Received from stdio: {}", msg_str);
-
-                // Write to stdout log file if logger is available
-                if let Some(ref server_info) = self.server_info {
-                    if let Some(ref logger) = server_info.logger {
-                        if let Err(e) = logger.write_stdout(msg_str.trim_end()).await {
-                            tracing::error!("Failed to write stdout to log file: {}", e);
+            };
+            self.first_output.get_or_init(Utc::now);
+            let line = match frame {
+                Frame::Line(line) => line,
+                Frame::Oversized(oversized) => {
+                    let limit = stdout.limit();
+                    let server = self
+                        .server_info
+                        .as_ref()
+                        .map(|info| info.name.clone())
+                        .unwrap_or_default();
+                    let note = format!(
+                        "Discarded a {}-byte line from stdout, over the {limit}-byte limit",
+                        oversized.size
+                    );
+                    tracing::warn!(server = %server, id = ?oversized.id, "{}", note);
+                    if let Some(ref server_info) = self.server_info {
+                        server_info.broadcast_log(LogEntry {
+                            timestamp: Utc::now(),
+                            level: "warn".to_string(),
+                            message: note,
+                        });
+                    }
+                    if !oversized.json {
+                        if self.strict_stdout {
+                            return Err(TransportError::InvalidFormat.into());
                         }
+                        continue;
                     }
+                    // Requests and notifications from the server answer
+                    // nothing; a response fails the request waiting for it
+                    if oversized.method {
+                        continue;
+                    }
+                    return Err(ProxyError::ResponseTooLarge {
+                        server,
+                        size: oversized.size as usize,
+                        limit,
+                    });
+                }
+            };
 
-                    // Also broadcast non-JSON messages as logs
-                    if !msg_str.trim_start().starts_with('{') {
-                        let log_entry = LogEntry {
-                            timestamp: Utc::now(),
-                            level: "info".to_string(),
-                            message: msg_str.trim().to_string(),
-                        };
-                        server_info.broadcast_log(log_entry);
+            let msg_str = String::from_utf8_lossy(&line);
+            tracing::trace!("Received from stdio: {}", msg_str);
+            if msg_str.trim().is_empty() {
+                continue;
+            }
+
+            // Write to stdout log file if logger is available
+            if let Some(ref server_info) = self.server_info {
+                if let Some(ref logger) = server_info.logger {
+                    if let Err(e) = logger.write_stdout(msg_str.trim_end()).await {
+                        tracing::error!("Failed to write stdout to log file: {}", e);
                     }
                 }
+            }
 
-                return Ok(msg_bytes);
+            if is_message(&line) {
+                return Ok(Bytes::from(line));
             }
 
-            // Buffer is getting too large
-            if buffer.len() > 1024 * 1024 {
-                // 1MB limit
+            // Also broadcast non-JSON output as logs
+            if let Some(ref server_info) = self.server_info {
+                server_info.broadcast_log(classify_line(
+                    self.log_classifier.as_deref(),
+                    msg_str.trim(),
+                    "info",
+                ));
+            }
+            if self.strict_stdout {
                 return Err(TransportError::InvalidFormat.into());
             }
+            tracing::debug!("Skipped non-JSON stdout line: {}", msg_str.trim());
         }
     }
 
@@ -234,45 +398,163 @@ impl Connection for StdioConnection {
 
         let mut child = self.child.lock().await;
 
-        // Try graceful shutdown first with SIGTERM on Unix
-        #[cfg(unix)]
-        {
-            use nix::sys::signal::{self, Signal};
-            use nix::unistd::Pid;
+        // Ask the server to exit by closing its stdin, as the MCP spec
+        // describes, and on Unix by signalling it as well
+        self.stdin.lock().await.take();
+        if child.id().is_some() {
+            #[cfg(unix)]
+            self.process_guard.signal(self.stop_policy.signal);
 
-            if let Some(id) = child.id() {
-                let pid = Pid::from_raw(id as i32);
-                let _ = signal::kill(pid, Signal::SIGTERM);
-
-                // Give process time to exit gracefully
-                tokio::select! {
-                    _ = child.wait() => {
-                        tracing::debug!("Process exited gracefully");
-                    }
-                    _ = tokio::time::sleep(tokio::time::Duration::from_secs(5)) => {
-                        tracing::warn!("Process did not exit gracefully, forcing kill");
-                        if let Err(e) = child.kill().await {
-                            tracing::error!("Failed to kill child process: {}", e);
-                        }
+            tokio::select! {
+                _ = child.wait() => {
+                    tracing::debug!("Process exited gracefully");
+                }
+                _ = tokio::time::sleep(self.stop_policy.grace) => {
+                    tracing::warn!("Process did not exit gracefully, forcing kill");
+                    self.process_guard.terminate();
+                    if let Err(e) = child.kill().await {
+                        tracing::warn!("Failed to kill child process: {}", e);
                     }
                 }
             }
         }
 
-        #[cfg(not(unix))]
-        {
-            if let Err(e) = child.kill().await {
-                tracing::warn!("Failed to kill child process: {}", e);
-            }
-        }
-
         // Wait for the process to actually exit
         let _ = child.wait().await;
 
+        // Children it spawned (e.g. `node` under `npx`) may still be running
+        self.process_guard.terminate();
+
         Ok(())
     }
 
     fn is_closed(&self) -> bool {
         self.closed.load(Ordering::SeqCst)
     }
+
+    fn first_output_at(&self) -> Option<DateTime<Utc>> {
+        self.first_output.get().copied()
+    }
+
+    async fn probe(&self) -> bool {
+        if self.is_closed() {
+            return false;
+        }
+        // The child is only locked while closing
+        let Ok(mut child) = self.child.try_lock() else {
+            return false;
+        };
+        match child.try_wait() {
+            Ok(None) => true,
+            Ok(Some(status)) => {
+                tracing::warn!("Server process exited ({})", status);
+                self.closed.store(true, Ordering::SeqCst);
+                false
+            }
+            Err(e) => {
+                tracing::warn!("Failed to check server process: {}", e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = r#"echo 'Starting server v1.2'; echo; echo '{not json'; echo '{"jsonrpc":"2.0","id":1,"result":{}}'"#;
+
+    fn transport() -> StdioTransport {
+        StdioTransport::new()
+            .with_command("sh".to_string(), vec!["-c".to_string(), OUTPUT.to_string()])
+    }
+
+    #[tokio::test]
+    async fn test_skips_non_json_stdout() {
+        let connection = transport().connect().await.unwrap();
+        let message = connection.recv().await.unwrap();
+        assert_eq!(
+            &message[..],
+            b"{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n"
+        );
+        assert!(connection.recv().await.is_err());
+
+        let connection = transport()
+            .with_strict_stdout(true)
+            .connect()
+            .await
+            .unwrap();
+        assert!(matches!(
+            connection.recv().await,
+            Err(crate::error::ProxyError::Transport(
+                TransportError::InvalidFormat
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_response_fails_without_closing() {
+        let script = r#"printf '{"jsonrpc":"2.0","id":1,"result":{"text":"'; head -c 200000 /dev/zero | tr '\0' x; echo '"}}'; echo '{"jsonrpc":"2.0","id":2,"result":{}}'"#;
+        let connection = StdioTransport::new()
+            .with_command("sh".to_string(), vec!["-c".to_string(), script.to_string()])
+            .with_max_message_bytes(64 * 1024)
+            .connect()
+            .await
+            .unwrap();
+
+        match connection.recv().await {
+            Err(ProxyError::ResponseTooLarge { size, limit, .. }) => {
+                assert!(size > 200_000);
+                assert_eq!(limit, 64 * 1024);
+            }
+            other => panic!("expected ResponseTooLarge, got {other:?}"),
+        }
+        let message = connection.recv().await.unwrap();
+        assert_eq!(
+            &message[..],
+            b"{\"jsonrpc\":\"2.0\",\"id\":2,\"result\":{}}\n"
+        );
+    }
+
+    // Killed processes stay zombies until reaped, which is up to init here
+    #[cfg(target_os = "linux")]
+    fn is_running(pid: u64) -> bool {
+        std::fs::read_to_string(format!("/proc/{pid}/stat")).is_ok_and(|stat| {
+            let state = stat.rsplit(')').next().unwrap_or("");
+            !state.trim_start().starts_with('Z')
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_close_kills_process_group() {
+        // Ignores SIGTERM and leaves a child behind, like an `npx` shim can
+        let script = r#"trap '' TERM; sleep 30 & echo "{\"pid\":$!}"; wait"#;
+        let connection = StdioTransport::new()
+            .with_command("sh".to_string(), vec!["-c".to_string(), script.to_string()])
+            .with_stop_policy(StopPolicy {
+                signal: nix::sys::signal::Signal::SIGTERM,
+                grace: std::time::Duration::from_millis(200),
+            })
+            .connect()
+            .await
+            .unwrap();
+        let message: serde_json::Value =
+            serde_json::from_slice(&connection.recv().await.unwrap()).unwrap();
+        let pid = message["pid"].as_u64().unwrap();
+        assert!(is_running(pid));
+
+        let started = std::time::Instant::now();
+        connection.close().await.unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+        for _ in 0..50 {
+            if !is_running(pid) {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("child {pid} outlived its server");
+    }
 }
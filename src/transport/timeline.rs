@@ -0,0 +1,110 @@
+//! When each step of bringing up a server's connection happened, so a
+//! server that is slow to start shows where the time goes
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// The steps of a server's most recent connection. Steps not reached are
+/// `None`; `first_byte_at` is only known for stdio servers.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupTimeline {
+    /// Spawning the process, or opening the network connection, began
+    pub spawned_at: Option<DateTime<Utc>>,
+    /// Output from the process, on stdout or stderr, was first seen
+    pub first_byte_at: Option<DateTime<Utc>>,
+    pub initialize_sent_at: Option<DateTime<Utc>>,
+    pub initialize_received_at: Option<DateTime<Utc>>,
+    /// The handshake completed and requests could be sent
+    pub ready_at: Option<DateTime<Utc>>,
+    /// Why the connection never became ready
+    pub error: Option<String>,
+}
+
+/// Milliseconds from spawning to each step
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupDurations {
+    pub first_byte_ms: Option<i64>,
+    pub initialize_sent_ms: Option<i64>,
+    pub initialize_received_ms: Option<i64>,
+    pub ready_ms: Option<i64>,
+}
+
+impl StartupTimeline {
+    /// A timeline for a connection being opened now
+    pub fn starting() -> Self {
+        Self {
+            spawned_at: Some(Utc::now()),
+            ..Self::default()
+        }
+    }
+
+    pub fn durations(&self) -> StartupDurations {
+        let since_spawn =
+            |at: Option<DateTime<Utc>>| Some((at? - self.spawned_at?).num_milliseconds());
+        StartupDurations {
+            first_byte_ms: since_spawn(self.first_byte_at),
+            initialize_sent_ms: since_spawn(self.initialize_sent_at),
+            initialize_received_ms: since_spawn(self.initialize_received_at),
+            ready_ms: since_spawn(self.ready_at),
+        }
+    }
+
+    /// One line for logs and the `check` command, e.g.
+    /// `first byte 12ms, initialize sent 13ms, response 240ms, ready 241ms`
+    pub fn summary(&self) -> String {
+        let durations = self.durations();
+        let steps = [
+            ("first byte", durations.first_byte_ms),
+            ("initialize sent", durations.initialize_sent_ms),
+            ("response", durations.initialize_received_ms),
+            ("ready", durations.ready_ms),
+        ];
+        let mut parts: Vec<String> = steps
+            .iter()
+            .filter_map(|(step, ms)| Some(format!("{step} {}ms", (*ms)?)))
+            .collect();
+        if let Some(error) = &self.error {
+            parts.push(format!("failed: {error}"));
+        }
+        if parts.is_empty() {
+            "not started".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_durations_are_measured_from_spawn() {
+        let spawned = Utc::now();
+        let timeline = StartupTimeline {
+            spawned_at: Some(spawned),
+            first_byte_at: None,
+            initialize_sent_at: Some(spawned + Duration::milliseconds(5)),
+            initialize_received_at: Some(spawned + Duration::milliseconds(1500)),
+            ready_at: None,
+            error: Some("Timeout".to_string()),
+        };
+        assert_eq!(
+            timeline.durations(),
+            StartupDurations {
+                first_byte_ms: None,
+                initialize_sent_ms: Some(5),
+                initialize_received_ms: Some(1500),
+                ready_ms: None,
+            }
+        );
+        assert_eq!(
+            timeline.summary(),
+            "initialize sent 5ms, response 1500ms, failed: Timeout"
+        );
+        assert_eq!(StartupTimeline::default().summary(), "not started");
+    }
+}
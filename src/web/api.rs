@@ -1,153 +1,106 @@
 use crate::server::ServerManager;
 use crate::state::AppState;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
 use chrono::DateTime;
 use futures::stream;
 use futures::stream::{Stream, StreamExt};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::fs::File;
 use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader};
 use tokio::time::{interval, Duration};
-use warp::{Filter, Rejection, Reply};
-
-pub fn routes(
-    state: Arc<AppState>,
-) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
-    // Server endpoints
-    let servers = servers_routes(state.clone());
-
-    // Log endpoints
-    let logs = logs_routes(state.clone());
-
-    // Metrics endpoint
-    let metrics = metrics_route(state.clone());
-
-    // Config endpoint
-    let config = config_routes(state.clone());
-
-    // Context tracing endpoints
-    let trace = trace_routes(state);
-
-    warp::path("api").and(servers.or(logs).or(metrics).or(config).or(trace))
-}
-
-fn servers_routes(
-    state: Arc<AppState>,
-) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
-    let list = warp::path!("servers")
-        .and(warp::get())
-        .and(with_state(state.clone()))
-        .and_then(list_servers);
-
-    let action = warp::path!("servers" / String / String)
-        .and(warp::post())
-        .and(with_state(state.clone()))
-        .and_then(server_action);
-
-    let status = warp::path!("servers" / String)
-        .and(warp::get())
-        .and(with_state(state))
-        .and_then(server_status);
-
-    list.or(action).or(status)
-}
-
-fn logs_routes(
-    state: Arc<AppState>,
-) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
-    let logs_tail = warp::path!("logs" / String)
-        .and(warp::get())
-        .and(warp::query::<std::collections::HashMap<String, String>>())
-        .and(with_state(state.clone()))
-        .and_then(get_server_logs);
-
-    let logs_stream = warp::path!("logs" / String / "stream")
-        .and(warp::get())
-        .and(warp::query::<std::collections::HashMap<String, String>>())
-        .and(with_state(state))
-        .and_then(stream_server_logs);
-
-    logs_tail.or(logs_stream)
-}
-
-fn metrics_route(
-    state: Arc<AppState>,
-) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
-    warp::path!("metrics")
-        .and(warp::get())
-        .and(with_state(state))
-        .and_then(get_metrics)
-}
-
-fn config_routes(
-    state: Arc<AppState>,
-) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
-    let get = warp::path!("config")
-        .and(warp::get())
-        .and(with_state(state.clone()))
-        .and_then(get_config);
-
-    let update = warp::path!("config")
-        .and(warp::put())
-        .and(warp::body::json())
-        .and(with_state(state))
-        .and_then(update_config);
-
-    get.or(update)
-}
-
-fn trace_routes(
-    state: Arc<AppState>,
-) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
-    let get_trace_route = warp::path!("trace" / String)
-        .and(warp::get())
-        .and(warp::query::<std::collections::HashMap<String, String>>())
-        .and(with_state(state.clone()))
-        .and_then(get_trace);
-
-    let by_context_route = warp::path!("query" / "by-context" / String)
-        .and(warp::get())
-        .and(warp::query::<std::collections::HashMap<String, String>>())
-        .and(with_state(state.clone()))
-        .and_then(get_context_impact);
-
-    let by_response_route = warp::path!("query" / "by-response" / String / "contexts")
-        .and(warp::get())
-        .and(warp::query::<std::collections::HashMap<String, String>>())
-        .and(with_state(state.clone()))
-        .and_then(get_response_contexts);
-
-    let evolution_route = warp::path!("query" / "evolution" / String)
-        .and(warp::get())
-        .and(with_state(state.clone()))
-        .and_then(get_evolution_history);
-
-    let feedback_route = warp::path!("feedback")
-        .and(warp::post())
-        .and(warp::body::json())
-        .and(with_state(state))
-        .and_then(submit_feedback);
-
-    get_trace_route
-        .or(by_context_route)
-        .or(by_response_route)
-        .or(evolution_route)
-        .or(feedback_route)
-}
-
-fn with_state(
-    state: Arc<AppState>,
-) -> impl Filter<Extract = (Arc<AppState>,), Error = std::convert::Infallible> + Clone {
-    warp::any().map(move || state.clone())
-}
-
-async fn list_servers(state: Arc<AppState>) -> Result<impl Reply, Rejection> {
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        // Server endpoints
+        .route("/servers", get(list_servers))
+        .route("/servers/restart-all", post(restart_all_servers))
+        .route("/servers/:name", get(server_status))
+        .route("/servers/:name/:action", post(server_action))
+        .route("/tags/:tag/logs", get(get_tag_logs))
+        .route("/tags/:tag/:action", post(tag_action))
+        // Log endpoints
+        .route("/logs/:server", get(get_server_logs))
+        .route("/logs/:server/stream", get(stream_server_logs))
+        .route("/servers/:name/logs", get(get_server_log_history))
+        .route(
+            "/servers/:name/translation-report",
+            get(translation_report).delete(clear_translation_report),
+        )
+        .route("/servers/:name/timeline", get(startup_timeline))
+        // Metrics endpoints
+        .route("/metrics", get(get_metrics))
+        .route("/metrics/history", get(get_metrics_history))
+        .route("/alerts", get(get_alerts))
+        .route("/conflicts", get(get_conflicts))
+        .route("/catalog/docs", get(catalog_docs))
+        // Cache endpoints
+        .route("/cache/stats", get(cache_stats))
+        .route("/cache/clear", post(clear_cache))
+        // Config endpoint
+        .route("/config", get(get_config).put(update_config))
+        // Context tracing endpoints
+        .route("/trace/storage-stats", get(trace_storage_stats))
+        .route("/trace/:response_id", get(get_trace))
+        .route("/lineage/:response_id", get(get_lineage))
+        .route("/query/by-context/:context_id", get(get_context_impact))
+        .route(
+            "/query/by-response/:response_id/contexts",
+            get(get_response_contexts),
+        )
+        .route("/query/evolution/:context_id", get(get_evolution_history))
+        .route("/query/evolution/:context_id/diff", get(get_version_diff))
+        .route("/query/similar", get(similar_contexts))
+        .route("/query/similar/:context_id", get(similar_to_context))
+        .route("/feedback", post(submit_feedback))
+        // Client usage endpoints
+        .route("/clients/:client/usage", get(client_usage))
+        // Plugin endpoints
+        .route("/plugins", get(list_plugins))
+        .route("/plugins/reload", post(reload_plugins))
+        .route("/plugins/:name/:action", post(plugin_action))
+        // Dead-letter endpoints
+        .route(
+            "/deadletter",
+            get(list_dead_letters).delete(clear_dead_letters),
+        )
+        .route("/deadletter/:id", delete(remove_dead_letter))
+        .route("/deadletter/:id/retry", post(retry_dead_letter))
+        // Tool playground endpoints
+        .route("/playground/tools", get(playground_tools))
+        .route("/playground/call", post(playground_call))
+}
+
+async fn list_servers(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
     let mut servers = Vec::new();
 
     for entry in state.servers.iter() {
         let name = entry.key().clone();
         let info = entry.value();
 
+        let tags = state
+            .config
+            .read()
+            .await
+            .servers
+            .get(&name)
+            .map(|s| s.tags.clone())
+            .unwrap_or_default();
+        if let Some(tag) = query.get("tag") {
+            if !tags.contains(tag) {
+                continue;
+            }
+        }
+
         let server_state = info.state.read().await;
         let state_str = match *server_state {
             crate::state::ServerState::Starting => "starting",
@@ -179,14 +132,8 @@ async fn list_servers(state: Arc<AppState>) -> Result<impl Reply, Rejection> {
         drop(config);
 
         // T051: Get protocol version from connection state
-        let protocol_version = if let Some(connection_state) = &info.connection_state {
-            connection_state
-                .protocol_version()
-                .await
-                .map(|v| v.as_str().to_string())
-        } else {
-            None
-        };
+        let (protocol_version, protocol_version_pinned) =
+            protocol_version_info(&state, &name, info).await;
 
         servers.push(serde_json::json!({
             "name": name,
@@ -195,54 +142,295 @@ async fn list_servers(state: Arc<AppState>) -> Result<impl Reply, Rejection> {
             "health_check_enabled": health_check_enabled,
             "last_health_check": health_check_data,
             "last_access_time": last_access,
-            "protocolVersion": protocol_version
+            "protocolVersion": protocol_version,
+            "protocolVersionPinned": protocol_version_pinned,
+            "tags": tags,
+            "disabled": state.disabled_servers.get(&name).await,
+            "maintenance": state.maintenance.get(&name),
+            "concurrency_limit": state.call_limits.limit(&name)
         }));
     }
 
-    Ok(warp::reply::json(&serde_json::json!({
+    Ok(Json(serde_json::json!({
         "servers": servers
-    })))
+    }))
+    .into_response())
+}
+
+async fn tag_action(
+    State(state): State<Arc<AppState>>,
+    Path((tag, action)): Path<(String, String)>,
+) -> Result<Response, StatusCode> {
+    let names = state.config.read().await.servers_with_tag(&tag);
+    if names.is_empty() {
+        return Ok((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": format!("No servers tagged: {}", tag)
+            })),
+        )
+            .into_response());
+    }
+
+    let manager = ServerManager::new(state.clone(), state.shutdown_tx.subscribe());
+    let mut results = serde_json::Map::new();
+
+    for name in &names {
+        let result = match action.as_str() {
+            "enable" | "disable" => {
+                let mut config = state.config.write().await;
+                if let Some(server_config) = config.servers.get_mut(name) {
+                    server_config.enabled = action == "enable";
+                }
+                Ok(())
+            }
+            "start" => manager.start_server(name).await,
+            "stop" => manager.stop_server(name).await,
+            "restart" => manager.restart_server(name).await,
+            _ => {
+                return Ok((
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "error": format!("Unknown action: {}", action)
+                    })),
+                )
+                    .into_response())
+            }
+        };
+
+        let outcome = match result {
+            Ok(_) => serde_json::json!({ "status": "success" }),
+            Err(e) => serde_json::json!({ "status": "error", "error": e.to_string() }),
+        };
+        results.insert(name.clone(), outcome);
+    }
+
+    let all_ok = results.values().all(|r| r["status"] == "success");
+    let status = if all_ok {
+        StatusCode::OK
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    Ok((
+        status,
+        Json(serde_json::json!({
+            "tag": tag,
+            "action": action,
+            "servers": results
+        })),
+    )
+        .into_response())
+}
+
+async fn get_tag_logs(
+    State(state): State<Arc<AppState>>,
+    Path(tag): Path<String>,
+    Query(query_params): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
+    let names = state.config.read().await.servers_with_tag(&tag);
+    if names.is_empty() {
+        return Ok((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": format!("No servers tagged: {}", tag)
+            })),
+        )
+            .into_response());
+    }
+
+    let lines = query_params
+        .get("lines")
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(100);
+    let filter_type = query_params.get("type");
+
+    // Ephemeral proxies write no log files
+    if state.config.read().await.ephemeral {
+        let mut merged = Vec::new();
+        for name in &names {
+            if let Some(info) = state.servers.get(name).map(|entry| entry.value().clone()) {
+                merged.extend(
+                    info.log_buffer
+                        .query(lines, None)
+                        .into_iter()
+                        .map(|entry| (entry.timestamp, buffered_line(&entry), name.clone())),
+                );
+            }
+        }
+        merged.sort_by_key(|(timestamp, ..)| *timestamp);
+        let start = merged.len().saturating_sub(lines);
+        let entries: Vec<_> = merged[start..]
+            .iter()
+            .map(|(_, line, server)| serde_json::json!({ "server": server, "line": line }))
+            .collect();
+
+        return Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "tag": tag,
+                "servers": names,
+                "lines": entries,
+                "source": "memory",
+                "filter": "all"
+            })),
+        )
+            .into_response());
+    }
+
+    let Some(home) = dirs::home_dir() else {
+        return Ok((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": "Could not determine home directory"
+            })),
+        )
+            .into_response());
+    };
+
+    // Merge each server's tail into one timeline; lines start with a sortable timestamp
+    let mut merged = Vec::new();
+    for name in &names {
+        let log_file_path = home
+            .join(".mcp-proxy")
+            .join("logs")
+            .join(name)
+            .join("server.log");
+        if !log_file_path.exists() {
+            continue;
+        }
+
+        match read_last_lines(&log_file_path, lines, filter_type).await {
+            Ok(log_lines) => {
+                merged.extend(log_lines.into_iter().map(|line| (line, name.clone())));
+            }
+            Err(e) => {
+                tracing::error!("Error reading log file {:?}: {}", log_file_path, e);
+            }
+        }
+    }
+    merged.sort();
+    let start = merged.len().saturating_sub(lines);
+
+    let entries: Vec<_> = merged[start..]
+        .iter()
+        .map(|(line, server)| serde_json::json!({ "server": server, "line": line }))
+        .collect();
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "tag": tag,
+            "servers": names,
+            "lines": entries,
+            "filter": filter_type.map_or("all", |v| v.as_str())
+        })),
+    )
+        .into_response())
 }
 
 async fn server_action(
-    name: String,
-    action: String,
-    state: Arc<AppState>,
-) -> Result<impl Reply, Rejection> {
+    State(state): State<Arc<AppState>>,
+    Path((name, action)): Path<(String, String)>,
+) -> Result<Response, StatusCode> {
     let manager = ServerManager::new(state.clone(), state.shutdown_tx.subscribe());
 
     let result = match action.as_str() {
         "start" => manager.start_server(&name).await,
         "stop" => manager.stop_server(&name).await,
         "restart" => manager.restart_server(&name).await,
+        "maintenance" | "resume" | "toggle-disable" if !state.servers.contains_key(&name) => {
+            Err(crate::error::ProxyError::ServerNotFound(name.clone()))
+        }
+        "maintenance" => {
+            state.maintenance.enter(&name, None);
+            Ok(())
+        }
+        "resume" => {
+            state.maintenance.exit(&name);
+            Ok(())
+        }
+        "unquarantine" => match crate::server::quarantine::release(&state, &name).await {
+            Ok(_) => manager.start_server(&name).await,
+            Err(e) => Err(e),
+        },
+        // Disabled servers stay stopped across proxy restarts until enabled
+        "toggle-disable" if state.disabled_servers.is_disabled(&name).await => {
+            match crate::server::quarantine::release(&state, &name).await {
+                Ok(_) => manager.start_server(&name).await,
+                Err(e) => Err(e),
+            }
+        }
+        "toggle-disable" => {
+            match crate::server::quarantine::disable(&state, &name, "disabled from the web UI")
+                .await
+            {
+                Ok(()) => manager.stop_server(&name).await,
+                Err(e) => Err(e),
+            }
+        }
         _ => {
-            return Ok(warp::reply::with_status(
-                warp::reply::json(&serde_json::json!({
+            return Ok((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
                     "error": format!("Unknown action: {}", action)
                 })),
-                warp::http::StatusCode::BAD_REQUEST,
-            ))
+            )
+                .into_response())
         }
     };
 
     match result {
-        Ok(_) => Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({
+        Ok(_) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({
                 "status": "success",
                 "message": format!("Server {} action {} completed", name, action)
             })),
-            warp::http::StatusCode::OK,
-        )),
-        Err(e) => Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({
+        )
+            .into_response()),
+        Err(e) => Ok((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
                 "error": e.to_string()
             })),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-        )),
+        )
+            .into_response()),
+    }
+}
+
+async fn restart_all_servers(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
+    let strategy = query
+        .get("strategy")
+        .map(String::as_str)
+        .unwrap_or("rolling");
+    if strategy != "rolling" {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!("Unknown restart strategy: {}", strategy)
+            })),
+        )
+            .into_response());
     }
+
+    let manager = ServerManager::new(state.clone(), state.shutdown_tx.subscribe());
+    let report = manager.rolling_restart().await;
+
+    let status = if report.failed.is_some() {
+        StatusCode::INTERNAL_SERVER_ERROR
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(report)).into_response())
 }
 
-async fn server_status(name: String, state: Arc<AppState>) -> Result<impl Reply, Rejection> {
+async fn server_status(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Response, StatusCode> {
     if let Some(info) = state.servers.get(&name) {
         let server_state = info.state.read().await;
         let restart_count = info.restart_count.read().await;
@@ -266,38 +454,128 @@ async fn server_status(name: String, state: Arc<AppState>) -> Result<impl Reply,
         drop(config);
 
         // T051: Get protocol version from connection state
-        let protocol_version = if let Some(connection_state) = &info.connection_state {
-            connection_state
-                .protocol_version()
-                .await
-                .map(|v| v.as_str().to_string())
-        } else {
-            None
-        };
+        let (protocol_version, protocol_version_pinned) =
+            protocol_version_info(&state, &name, &info).await;
 
-        Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({
+        Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({
                 "name": name,
                 "state": format!("{:?}", *server_state),
                 "restart_count": *restart_count,
                 "health_check_enabled": health_check_enabled,
                 "last_health_check": health_check_data,
                 "last_access_time": last_access,
-                "protocolVersion": protocol_version
+                "protocolVersion": protocol_version,
+                "protocolVersionPinned": protocol_version_pinned,
+                "disabled": state.disabled_servers.get(&name).await,
+                "maintenance": state.maintenance.get(&name)
             })),
-            warp::http::StatusCode::OK,
-        ))
+        )
+            .into_response())
     } else {
-        Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({
+        Ok((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
                 "error": format!("Server not found: {}", name)
             })),
-            warp::http::StatusCode::NOT_FOUND,
-        ))
+        )
+            .into_response())
     }
 }
 
-async fn get_metrics(state: Arc<AppState>) -> Result<impl Reply, Rejection> {
+/// Negotiated (or pinned) protocol version for a server, and whether it is pinned
+async fn protocol_version_info(
+    state: &AppState,
+    name: &str,
+    info: &crate::state::ServerInfo,
+) -> (Option<String>, bool) {
+    let pinned = state
+        .connection_pool
+        .pinned_protocol_version(name)
+        .is_some();
+
+    let negotiated = match &info.connection_state {
+        Some(connection_state) => connection_state
+            .protocol_version()
+            .await
+            .map(|v| v.as_str().to_string()),
+        None => None,
+    };
+
+    let version = negotiated.or_else(|| {
+        state
+            .server_versions
+            .get(name)
+            .map(|v| v.protocol_version.clone())
+    });
+
+    (version, pinned)
+}
+
+fn server_not_found(name: &str) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({ "error": format!("Server not found: {}", name) })),
+    )
+        .into_response()
+}
+
+/// What protocol translation has dropped from a server's messages, against
+/// the versions the proxy and the server speak
+async fn translation_report(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response {
+    let Some(info) = state.servers.get(&name).map(|info| info.clone()) else {
+        return server_not_found(&name);
+    };
+    let (protocol_version, protocol_version_pinned) =
+        protocol_version_info(&state, &name, &info).await;
+    let report = state.translation_reports.report(&name);
+    Json(serde_json::json!({
+        "server": name,
+        "proxyProtocolVersion": crate::transport::pool::PROXY_PROTOCOL_VERSION.as_str(),
+        "protocolVersion": protocol_version,
+        "protocolVersionPinned": protocol_version_pinned,
+        "report": report,
+    }))
+    .into_response()
+}
+
+async fn clear_translation_report(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response {
+    if !state.servers.contains_key(&name) {
+        return server_not_found(&name);
+    }
+    state.translation_reports.clear(&name);
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// When each step of the server's latest startup happened, and how long
+/// after spawning it came
+async fn startup_timeline(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response {
+    if !state.servers.contains_key(&name) {
+        return server_not_found(&name);
+    }
+    let timeline = state
+        .connection_pool
+        .startup_timeline(&name)
+        .unwrap_or_default();
+    Json(serde_json::json!({
+        "server": name,
+        "timeline": timeline,
+        "durations": timeline.durations(),
+    }))
+    .into_response()
+}
+
+async fn get_metrics(State(state): State<Arc<AppState>>) -> Result<Response, StatusCode> {
     let metrics = state.metrics.gather_metrics();
 
     // Convert to JSON format
@@ -312,7 +590,7 @@ async fn get_metrics(state: Arc<AppState>) -> Result<impl Reply, Rejection> {
                     serde_json::json!({
                         "labels": m.get_label().iter().map(|l| {
                             (l.get_name(), l.get_value())
-                        }).collect::<std::collections::HashMap<_, _>>(),
+                        }).collect::<HashMap<_, _>>(),
                         "value": match m.get_counter().get_value() as i64 {
                             v if v != 0 => v as f64,
                             _ => m.get_gauge().get_value(),
@@ -323,52 +601,566 @@ async fn get_metrics(state: Arc<AppState>) -> Result<impl Reply, Rejection> {
         })
         .collect();
 
-    Ok(warp::reply::json(&serde_json::json!({
-        "metrics": json_metrics
-    })))
+    Ok(Json(serde_json::json!({
+        "metrics": json_metrics,
+        "pool": state.connection_pool.stats()
+    }))
+    .into_response())
+}
+
+/// Samples from the rolling history for the last `window` (e.g. `24h`) or
+/// `hours` (all kept samples by default), optionally for one `server`. Each
+/// server's series is returned unless `tool` names a tool, or is `*` for
+/// every tool.
+async fn get_metrics_history(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let config = state.config.read().await.metrics_history.clone();
+    let bad_request = |error: &str| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": error })),
+        )
+            .into_response()
+    };
+    let window = match (query.get("window"), query.get("hours")) {
+        (Some(window), _) => match crate::bench::parse_duration(window) {
+            Ok(window) => window,
+            Err(e) => return bad_request(&e),
+        },
+        (None, Some(hours)) => match hours.parse::<u64>() {
+            Ok(hours) => Duration::from_secs(hours * 60 * 60),
+            Err(_) => return bad_request("hours must be a whole number"),
+        },
+        (None, None) => config.retention(),
+    };
+    let since = chrono::Utc::now() - window;
+    let series = match query.get("tool").map(String::as_str) {
+        None => crate::state::metrics_history::Series::Servers,
+        Some("*") => crate::state::metrics_history::Series::Tools,
+        Some(tool) => crate::state::metrics_history::Series::Tool(tool),
+    };
+
+    Json(serde_json::json!({
+        "enabled": config.enabled,
+        "intervalSeconds": config.interval_seconds,
+        "retentionHours": config.retention_hours,
+        "samples": state
+            .metrics_history
+            .query(query.get("server").map(String::as_str), series, since)
+    }))
+    .into_response()
+}
+
+/// Hits, misses and evictions of the tool list and context caches
+async fn cache_stats(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!(
+        crate::state::cache_stats::report(&state).await
+    ))
+}
+
+/// Clear the caches named by `scope` (`tools`, `context` or `all`, the
+/// default)
+async fn clear_cache(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let scope = match query.get("scope").map(|scope| scope.parse()) {
+        None => crate::state::cache_stats::CacheScope::All,
+        Some(Ok(scope)) => scope,
+        Some(Err(e)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e })),
+            )
+                .into_response()
+        }
+    };
+    Json(serde_json::json!({
+        "status": "success",
+        "cleared": crate::state::cache_stats::clear(&state, scope).await
+    }))
+    .into_response()
+}
+
+/// Where each alert rule stands, per watched server
+async fn get_alerts(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "rules": state.alert_rules.statuses()
+    }))
+}
+
+/// Tool names exposed by more than one server, as of the last aggregated
+/// tool list, and where unprefixed calls to each go
+async fn get_conflicts(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let resolution = state.config.read().await.routing.conflicts;
+    Json(serde_json::json!({
+        "resolution": resolution,
+        "conflicts": state.request_router.tool_conflicts()
+    }))
+}
+
+/// Discovered and assigned plugins, each with the servers and phases it is
+/// bound to and how its executions for them have gone
+async fn list_plugins(State(state): State<Arc<AppState>>) -> Response {
+    use crate::plugin::config::PluginAssignment;
+
+    let config = state.config.read().await;
+    let (Some(plugin_config), Some(manager)) = (&config.plugins, &state.plugin_manager) else {
+        return Json(serde_json::json!({ "configured": false, "plugins": [] })).into_response();
+    };
+
+    let loaded = manager.plugin_names();
+    let mut names: std::collections::BTreeSet<&str> = loaded.iter().map(String::as_str).collect();
+    let mut bindings: Vec<(&str, &str, &PluginAssignment)> = Vec::new();
+    for (server, assignments) in &plugin_config.servers {
+        for (phase, assignments) in [
+            ("request", &assignments.request),
+            ("response", &assignments.response),
+        ] {
+            for assignment in assignments {
+                names.insert(&assignment.name);
+                bindings.push((server, phase, assignment));
+            }
+        }
+    }
+    bindings.sort_by_key(|(server, phase, assignment)| (*server, *phase, assignment.order));
+
+    let plugins: Vec<serde_json::Value> = names
+        .into_iter()
+        .map(|name| {
+            let bindings: Vec<serde_json::Value> = bindings
+                .iter()
+                .filter(|(_, _, assignment)| assignment.name == name)
+                .map(|(server, phase, assignment)| {
+                    serde_json::json!({
+                        "server": server,
+                        "phase": phase,
+                        "order": assignment.order,
+                        "enabled": assignment.enabled,
+                        "timeoutMs": plugin_config.get_plugin_timeout(assignment),
+                        "stats": manager.execution_stats(name, server),
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "name": name,
+                "loaded": loaded.iter().any(|loaded| loaded == name),
+                "bindings": bindings,
+            })
+        })
+        .collect();
+
+    Json(serde_json::json!({
+        "configured": true,
+        "pluginDir": plugin_config.plugin_dir,
+        "plugins": plugins
+    }))
+    .into_response()
+}
+
+/// Enable or disable a plugin for `?server=`, in both phases or only
+/// `?phase=`
+async fn plugin_action(
+    State(state): State<Arc<AppState>>,
+    Path((name, action)): Path<(String, String)>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    if action != "enable" && action != "disable" {
+        return plugin_error(StatusCode::BAD_REQUEST, format!("Unknown action: {action}"));
+    }
+    let Some(server) = query.get("server") else {
+        return plugin_error(StatusCode::BAD_REQUEST, "Missing server".to_string());
+    };
+    let mut arguments = serde_json::json!({ "server_name": server, "plugin_name": name });
+    if let Some(phase) = query.get("phase") {
+        arguments["phase"] = serde_json::json!(phase);
+    }
+
+    match crate::proxy::plugin_tools::handle_plugin_tool(&action, arguments, state).await {
+        Ok(_) => Json(serde_json::json!({
+            "status": "success",
+            "message": format!("Plugin {name} {action}d for server {server}")
+        }))
+        .into_response(),
+        Err(e) => plugin_error(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+/// Rediscover plugins and restart their warm processes
+async fn reload_plugins(State(state): State<Arc<AppState>>) -> Response {
+    match crate::proxy::plugin_tools::handle_plugin_tool("reload", serde_json::Value::Null, state)
+        .await
+    {
+        Ok(result) => Json(serde_json::json!({
+            "status": "success",
+            "message": result["content"][0]["text"]
+        }))
+        .into_response(),
+        Err(e) => plugin_error(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+fn plugin_error(status: StatusCode, error: String) -> Response {
+    (status, Json(serde_json::json!({ "error": error }))).into_response()
+}
+
+/// Send a request through the proxy as if a client had, returning the
+/// JSON-RPC response and its correlation ID. The request shows up in logs and
+/// on the Traffic page like any other.
+async fn playground_request(
+    state: &Arc<AppState>,
+    method: &str,
+    params: serde_json::Value,
+) -> crate::error::Result<(serde_json::Value, String)> {
+    use crate::proxy::{RequestContext, RequestHandler};
+    use tracing::Instrument;
+
+    let body = serde_json::to_vec(&serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params
+    }))?;
+    let ctx = RequestContext::default();
+    let _in_flight = state
+        .drain
+        .track()
+        .ok_or(crate::error::ProxyError::ShuttingDown)?;
+    let handler = RequestHandler::new(state.clone());
+    let started = std::time::Instant::now();
+    // Failures are answered as the proxy endpoint answers them
+    let reply = match crate::proxy::dispatch(&handler, &state.request_router, &body, &ctx)
+        .instrument(ctx.span())
+        .await
+    {
+        Ok(reply) => reply,
+        Err(e) => bytes::Bytes::from(serde_json::to_vec(
+            &crate::proxy::MCPResponse::error(None, &e).with_context(&ctx),
+        )?),
+    };
+    crate::proxy::traffic::publish(
+        state,
+        &handler,
+        &state.request_router,
+        &ctx,
+        &body,
+        &reply,
+        started.elapsed(),
+    );
+    Ok((serde_json::from_slice(&reply)?, ctx.correlation_id))
+}
+
+/// Documentation of the aggregated catalog, as Markdown or, with
+/// `?format=html`, an HTML page
+async fn catalog_docs(
+    State(state): State<Arc<AppState>>,
+    Query(query_params): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
+    use crate::catalog::docs::{self, DocFormat};
+
+    let format = match query_params.get("format") {
+        Some(format) => DocFormat::parse(format).ok_or(StatusCode::BAD_REQUEST)?,
+        None => DocFormat::Markdown,
+    };
+    let handler = crate::proxy::RequestHandler::new(state.clone());
+    match crate::catalog::Catalog::collect(&handler, &state.request_router).await {
+        Ok(catalog) => Ok((
+            [(header::CONTENT_TYPE, format.content_type())],
+            docs::render(&catalog, format),
+        )
+            .into_response()),
+        Err(e) => {
+            tracing::error!("Failed to collect the catalog: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// The aggregated tool list, as clients of the proxy see it
+async fn playground_tools(State(state): State<Arc<AppState>>) -> Result<Response, StatusCode> {
+    let reply = match playground_request(&state, "tools/list", serde_json::json!({})).await {
+        Ok((reply, _)) => reply,
+        Err(e) => {
+            return Ok((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response())
+        }
+    };
+    if let Some(message) = reply.pointer("/error/message").and_then(|m| m.as_str()) {
+        return Ok((
+            StatusCode::BAD_GATEWAY,
+            Json(serde_json::json!({ "error": message })),
+        )
+            .into_response());
+    }
+    let tools = reply
+        .pointer("/result/tools")
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!([]));
+    Ok(Json(serde_json::json!({ "tools": tools })).into_response())
+}
+
+#[derive(serde::Deserialize)]
+struct PlaygroundCall {
+    name: String,
+    #[serde(default)]
+    arguments: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Call a tool with the arguments given. A call the tool or the proxy
+/// rejects still succeeds, carrying the JSON-RPC error.
+async fn playground_call(
+    State(state): State<Arc<AppState>>,
+    Json(call): Json<PlaygroundCall>,
+) -> Result<Response, StatusCode> {
+    let started = std::time::Instant::now();
+    let params = serde_json::json!({ "name": call.name, "arguments": call.arguments });
+    match playground_request(&state, "tools/call", params).await {
+        Ok((reply, correlation_id)) => Ok(Json(serde_json::json!({
+            "result": reply.get("result"),
+            "error": reply.get("error"),
+            "durationMs": started.elapsed().as_millis() as u64,
+            "correlationId": correlation_id
+        }))
+        .into_response()),
+        Err(e) => Ok((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response()),
+    }
+}
+
+fn dead_letters_disabled() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({ "error": "Dead-letter capture is disabled" })),
+    )
+        .into_response()
+}
+
+fn dead_letter_not_found(id: u64) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({ "error": format!("Dead letter not found: {}", id) })),
+    )
+        .into_response()
 }
 
-async fn get_config(state: Arc<AppState>) -> Result<impl Reply, Rejection> {
+/// Failed tool calls, newest first, optionally for one `server` and at most
+/// `limit` (default 100)
+async fn list_dead_letters(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    let Some(dead_letters) = &state.dead_letters else {
+        return Json(serde_json::json!({ "enabled": false, "entries": [] }));
+    };
+    let limit = query
+        .get("limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(100);
+    Json(serde_json::json!({
+        "enabled": true,
+        "entries": dead_letters.list(query.get("server").map(String::as_str), limit)
+    }))
+}
+
+async fn remove_dead_letter(State(state): State<Arc<AppState>>, Path(id): Path<u64>) -> Response {
+    let Some(dead_letters) = &state.dead_letters else {
+        return dead_letters_disabled();
+    };
+    match dead_letters.remove(id) {
+        Some(_) => Json(serde_json::json!({
+            "status": "success",
+            "message": format!("Dead letter {id} removed")
+        }))
+        .into_response(),
+        None => dead_letter_not_found(id),
+    }
+}
+
+async fn clear_dead_letters(State(state): State<Arc<AppState>>) -> Response {
+    let Some(dead_letters) = &state.dead_letters else {
+        return dead_letters_disabled();
+    };
+    let count = dead_letters.clear();
+    Json(serde_json::json!({
+        "status": "success",
+        "message": format!("{count} dead letters removed")
+    }))
+    .into_response()
+}
+
+/// Send a failed call again. The entry is removed; if the call fails again
+/// it is recorded as a new entry.
+async fn retry_dead_letter(State(state): State<Arc<AppState>>, Path(id): Path<u64>) -> Response {
+    let Some(dead_letters) = &state.dead_letters else {
+        return dead_letters_disabled();
+    };
+    let Some(entry) = dead_letters.remove(id) else {
+        return dead_letter_not_found(id);
+    };
+
+    let started = std::time::Instant::now();
+    let params = serde_json::json!({ "name": entry.tool, "arguments": entry.arguments });
+    match playground_request(&state, "tools/call", params).await {
+        Ok((reply, correlation_id)) => Json(serde_json::json!({
+            "result": reply.get("result"),
+            "error": reply.get("error"),
+            "durationMs": started.elapsed().as_millis() as u64,
+            "correlationId": correlation_id
+        }))
+        .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+async fn client_usage(
+    State(state): State<Arc<AppState>>,
+    Path(client): Path<String>,
+) -> Result<Response, StatusCode> {
+    use crate::state::QuotaPeriod;
+
+    let quota = match state.config.read().await.clients.get(&client) {
+        Some(config) => config.quota.clone(),
+        None => {
+            return Ok((
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({
+                    "error": format!("Client not found: {}", client)
+                })),
+            )
+                .into_response())
+        }
+    };
+    let shared = state.shared_state.read().await.clone();
+    let now = chrono::Utc::now();
+
+    let mut usage = Vec::new();
+    for period in [QuotaPeriod::Day, QuotaPeriod::Month] {
+        match state
+            .usage
+            .usage(shared.as_deref(), &client, period, now)
+            .await
+        {
+            Ok(period_usage) => usage.push(period_usage),
+            Err(e) => {
+                return Ok((
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(serde_json::json!({
+                        "error": e.to_string()
+                    })),
+                )
+                    .into_response())
+            }
+        }
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "client": client,
+            "day": QuotaPeriod::Day.key(now),
+            "month": QuotaPeriod::Month.key(now),
+            "daily": usage[0],
+            "monthly": usage[1],
+            "quota": quota
+        })),
+    )
+        .into_response())
+}
+
+async fn get_config(State(state): State<Arc<AppState>>) -> Result<Response, StatusCode> {
     let config = state.config.read().await;
-    Ok(warp::reply::json(&*config))
+    Ok(Json(&*config).into_response())
 }
 
 async fn update_config(
-    new_config: crate::config::Config,
-    state: Arc<AppState>,
-) -> Result<impl Reply, Rejection> {
+    State(state): State<Arc<AppState>>,
+    Json(new_config): Json<crate::config::Config>,
+) -> Result<Response, StatusCode> {
     match state.update_config(new_config).await {
-        Ok(_) => Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({
+        Ok(_) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({
                 "status": "success",
                 "message": "Configuration updated successfully"
             })),
-            warp::http::StatusCode::OK,
-        )),
-        Err(e) => Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({
+        )
+            .into_response()),
+        Err(e) => Ok((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
                 "error": e.to_string()
             })),
-            warp::http::StatusCode::BAD_REQUEST,
-        )),
+        )
+            .into_response()),
     }
 }
 
 async fn get_server_logs(
-    server_name: String,
-    query_params: std::collections::HashMap<String, String>,
-    _state: Arc<AppState>,
-) -> Result<impl Reply, Rejection> {
+    State(state): State<Arc<AppState>>,
+    Path(server_name): Path<String>,
+    Query(query_params): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
+    // Ephemeral proxies write no log files
+    if state.config.read().await.ephemeral {
+        let Some(info) = state
+            .servers
+            .get(&server_name)
+            .map(|entry| entry.value().clone())
+        else {
+            return Ok((
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({
+                    "error": format!("Server not found: {}", server_name)
+                })),
+            )
+                .into_response());
+        };
+        let lines = query_params
+            .get("lines")
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(100);
+        let log_lines: Vec<String> = info
+            .log_buffer
+            .query(lines, None)
+            .iter()
+            .map(buffered_line)
+            .collect();
+
+        return Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "server": server_name,
+                "lines": log_lines,
+                "source": "memory",
+                "filter": "all"
+            })),
+        )
+            .into_response());
+    }
+
     // Get log directory path
     let home = match dirs::home_dir() {
         Some(dir) => dir,
         None => {
-            return Ok(warp::reply::with_status(
-                warp::reply::json(&serde_json::json!({
+            return Ok((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
                     "error": "Could not determine home directory"
                 })),
-                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-            ));
+            )
+                .into_response());
         }
     };
 
@@ -380,12 +1172,13 @@ async fn get_server_logs(
 
     // Check if log file exists
     if !log_file_path.exists() {
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({
+        return Ok((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
                 "error": format!("Log file not found for server: {}", server_name)
             })),
-            warp::http::StatusCode::NOT_FOUND,
-        ));
+        )
+            .into_response());
     }
 
     // Parse query parameters
@@ -398,27 +1191,108 @@ async fn get_server_logs(
 
     // Read the last N lines from the file
     match read_last_lines(&log_file_path, lines, filter_type).await {
-        Ok(log_lines) => Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({
+        Ok(log_lines) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({
                 "server": server_name,
                 "lines": log_lines,
                 "file_path": log_file_path.to_string_lossy(),
                 "filter": filter_type.map_or("all", |v| v.as_str())
             })),
-            warp::http::StatusCode::OK,
-        )),
+        )
+            .into_response()),
         Err(e) => {
             tracing::error!("Error reading log file {:?}: {}", log_file_path, e);
-            Ok(warp::reply::with_status(
-                warp::reply::json(&serde_json::json!({
+            Ok((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
                     "error": format!("Failed to read log file: {}", e)
                 })),
-                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-            ))
+            )
+                .into_response())
         }
     }
 }
 
+async fn get_server_log_history(
+    State(state): State<Arc<AppState>>,
+    Path(server_name): Path<String>,
+    Query(query_params): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
+    let lines = query_params
+        .get("lines")
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(500);
+    let level = query_params.get("level").map(String::as_str);
+
+    let buffered = match state.servers.get(&server_name) {
+        Some(info) => info.log_buffer.query(lines, level),
+        None => {
+            return Ok((
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({
+                    "error": format!("Server not found: {}", server_name)
+                })),
+            )
+                .into_response())
+        }
+    };
+
+    let classifier = match state.config.read().await.servers.get(&server_name) {
+        Some(server) => crate::logging::LogClassifier::new(&server.log_parsers).ok(),
+        None => None,
+    };
+
+    // The in-memory buffer answers most queries; older history comes from the
+    // current and rotated log files
+    let ephemeral = state.config.read().await.ephemeral;
+    let (entries, source) = if buffered.len() >= lines || ephemeral {
+        (buffered, "memory")
+    } else {
+        let log_dir =
+            dirs::home_dir().map(|home| home.join(".mcp-proxy").join("logs").join(&server_name));
+        match log_dir {
+            Some(dir) if dir.exists() => {
+                match crate::logging::read_log_history(&dir, lines, level, classifier.as_ref())
+                    .await
+                {
+                    Ok(entries) => (entries, "file"),
+                    Err(e) => {
+                        tracing::error!("Error reading log history for {}: {}", server_name, e);
+                        (buffered, "memory")
+                    }
+                }
+            }
+            _ => (buffered, "memory"),
+        }
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "server": server_name,
+            "lines": entries,
+            "source": source,
+            "level": level.unwrap_or("all")
+        })),
+    )
+        .into_response())
+}
+
+/// A log entry kept in memory, written like a line of the server's log
+/// file but with its level where the file names the stream
+fn buffered_line(entry: &crate::state::LogEntry) -> String {
+    format!(
+        "[{}] [{}] {}",
+        entry
+            .timestamp
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S%.3f"),
+        entry.level.to_uppercase(),
+        entry.message
+    )
+}
+
 async fn read_last_lines(
     file_path: &PathBuf,
     num_lines: usize,
@@ -480,17 +1354,28 @@ async fn read_last_lines(
 }
 
 async fn stream_server_logs(
-    server_name: String,
-    query_params: std::collections::HashMap<String, String>,
-    _state: Arc<AppState>,
-) -> Result<impl Reply, Rejection> {
+    State(state): State<Arc<AppState>>,
+    Path(server_name): Path<String>,
+    Query(query_params): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
+    // Ephemeral proxies write no log files, so follow the server's output
+    // as it is captured
+    if state.config.read().await.ephemeral {
+        let info = state
+            .servers
+            .get(&server_name)
+            .map(|entry| entry.value().clone())
+            .ok_or(StatusCode::NOT_FOUND)?;
+        let entries = info.subscribe_logs(uuid::Uuid::new_v4().to_string());
+        let sse_stream =
+            tokio_stream::wrappers::UnboundedReceiverStream::new(entries).map(|entry| {
+                Ok::<_, std::convert::Infallible>(Event::default().data(buffered_line(&entry)))
+            });
+        return Ok(Sse::new(sse_stream).into_response());
+    }
+
     // Get log directory path
-    let home = match dirs::home_dir() {
-        Some(dir) => dir,
-        None => {
-            return Err(warp::reject::custom(LogStreamError::HomeDirectoryNotFound));
-        }
-    };
+    let home = dirs::home_dir().ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
 
     let log_file_path = home
         .join(".mcp-proxy")
@@ -500,9 +1385,7 @@ async fn stream_server_logs(
 
     // Check if log file exists
     if !log_file_path.exists() {
-        return Err(warp::reject::custom(LogStreamError::LogFileNotFound(
-            server_name,
-        )));
+        return Err(StatusCode::NOT_FOUND);
     }
 
     let filter_type = query_params.get("type").cloned(); // Optional filter: "stdout", "stderr", or none for both
@@ -510,23 +1393,13 @@ async fn stream_server_logs(
     // Create the log stream
     let log_stream = create_log_stream(log_file_path, filter_type);
 
-    // Convert to SSE format
+    // Convert to SSE format (sent with Cache-Control: no-cache)
     let sse_stream =
-        log_stream.map(|line| Ok::<_, warp::Error>(warp::sse::Event::default().data(line)));
-
-    let reply = warp::sse::reply(sse_stream);
-    Ok(warp::reply::with_header(reply, "Cache-Control", "no-cache"))
-}
+        log_stream.map(|line| Ok::<_, std::convert::Infallible>(Event::default().data(line)));
 
-#[derive(Debug)]
-enum LogStreamError {
-    HomeDirectoryNotFound,
-    #[allow(dead_code)]
-    LogFileNotFound(String),
+    Ok(Sse::new(sse_stream).into_response())
 }
 
-impl warp::reject::Reject for LogStreamError {}
-
 fn create_log_stream(
     log_file_path: PathBuf,
     filter_type: Option<String>,
@@ -598,11 +1471,29 @@ fn create_log_stream(
 
 // ========== Context Tracing Handlers ==========
 
+/// Size and fragmentation of the tracing database
+async fn trace_storage_stats(State(state): State<Arc<AppState>>) -> Result<Response, StatusCode> {
+    let tracker = state
+        .context_tracker
+        .read()
+        .await
+        .clone()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    match tracker.storage().storage_stats().await {
+        Ok(Some(stats)) => Ok(Json(stats).into_response()),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to read tracing storage stats: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 async fn get_trace(
-    response_id: String,
-    query_params: std::collections::HashMap<String, String>,
-    state: Arc<AppState>,
-) -> Result<impl Reply, Rejection> {
+    State(state): State<Arc<AppState>>,
+    Path(response_id): Path<String>,
+    Query(query_params): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
     use crate::context::query::{format_manifest, OutputFormat};
 
     // Get format parameter (default: json)
@@ -627,41 +1518,93 @@ async fn get_trace(
                                 OutputFormat::Tree | OutputFormat::Compact => "text/plain",
                             };
 
-                            Ok(warp::reply::with_header(
-                                formatted,
-                                "Content-Type",
-                                content_type,
-                            ))
+                            Ok(([(header::CONTENT_TYPE, content_type)], formatted).into_response())
                         }
                         Err(e) => {
                             tracing::error!("Failed to format manifest: {}", e);
-                            Err(warp::reject::not_found())
+                            Err(StatusCode::NOT_FOUND)
                         }
                     }
                 }
                 Ok(None) => {
                     // Response not found
-                    Err(warp::reject::not_found())
+                    Err(StatusCode::NOT_FOUND)
                 }
                 Err(e) => {
                     tracing::error!("Failed to query lineage: {}", e);
-                    Err(warp::reject::not_found())
+                    Err(StatusCode::NOT_FOUND)
                 }
             }
         }
         None => {
             // Context tracing not enabled
             tracing::warn!("Context tracing is not enabled");
-            Err(warp::reject::not_found())
+            Err(StatusCode::NOT_FOUND)
+        }
+    }
+}
+
+/// A response's lineage manifest for the dashboard's graph, with each context
+/// unit's current version and feedback score, and the feedback the response
+/// has received
+async fn get_lineage(
+    State(state): State<Arc<AppState>>,
+    Path(response_id): Path<String>,
+) -> Result<Response, StatusCode> {
+    let Some(tracker) = state.context_tracker.read().await.clone() else {
+        tracing::warn!("Context tracing is not enabled");
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let storage = tracker.storage();
+
+    let manifest = match storage.query_lineage(&response_id).await {
+        Ok(Some(manifest)) => manifest,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to query lineage: {}", e);
+            return Err(StatusCode::NOT_FOUND);
+        }
+    };
+
+    let mut units = serde_json::Map::new();
+    for node in &manifest.context_tree {
+        match storage.get_context_unit(&node.id).await {
+            Ok(Some(unit)) => {
+                units.insert(
+                    node.id.clone(),
+                    serde_json::json!({
+                        "version": unit.version,
+                        "previous_version_id": unit.previous_version_id,
+                        "aggregate_score": unit.aggregate_score,
+                        "feedback_count": unit.feedback_count
+                    }),
+                );
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to load context unit {}: {}", node.id, e),
         }
     }
+    let feedback = storage
+        .get_feedback(&response_id)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to load feedback for {}: {}", response_id, e);
+            Vec::new()
+        });
+
+    Ok(Json(serde_json::json!({
+        "manifest": manifest,
+        "units": units,
+        "feedback": feedback
+    }))
+    .into_response())
 }
 
 async fn get_context_impact(
-    context_unit_id: String,
-    query_params: std::collections::HashMap<String, String>,
-    state: Arc<AppState>,
-) -> Result<impl Reply, Rejection> {
+    State(state): State<Arc<AppState>>,
+    Path(context_unit_id): Path<String>,
+    Query(query_params): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
     use crate::context::query::{QueryFilters, QueryService};
 
     match &*state.context_tracker.read().await {
@@ -689,32 +1632,27 @@ async fn get_context_impact(
                 .await
             {
                 Ok(report) => {
-                    let json =
-                        serde_json::to_string(&report).map_err(|_| warp::reject::not_found())?;
-                    Ok(warp::reply::with_header(
-                        json,
-                        "Content-Type",
-                        "application/json",
-                    ))
+                    let json = serde_json::to_string(&report).map_err(|_| StatusCode::NOT_FOUND)?;
+                    Ok(([(header::CONTENT_TYPE, "application/json")], json).into_response())
                 }
                 Err(e) => {
                     tracing::error!("Failed to query context impact: {}", e);
-                    Err(warp::reject::not_found())
+                    Err(StatusCode::NOT_FOUND)
                 }
             }
         }
         None => {
             tracing::warn!("Context tracing is not enabled");
-            Err(warp::reject::not_found())
+            Err(StatusCode::NOT_FOUND)
         }
     }
 }
 
 async fn get_response_contexts(
-    response_id: String,
-    query_params: std::collections::HashMap<String, String>,
-    state: Arc<AppState>,
-) -> Result<impl Reply, Rejection> {
+    State(state): State<Arc<AppState>>,
+    Path(response_id): Path<String>,
+    Query(query_params): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
     use crate::context::query::QueryService;
     use crate::context::types::ContextType;
 
@@ -738,30 +1676,26 @@ async fn get_response_contexts(
             {
                 Ok(contexts) => {
                     let json =
-                        serde_json::to_string(&contexts).map_err(|_| warp::reject::not_found())?;
-                    Ok(warp::reply::with_header(
-                        json,
-                        "Content-Type",
-                        "application/json",
-                    ))
+                        serde_json::to_string(&contexts).map_err(|_| StatusCode::NOT_FOUND)?;
+                    Ok(([(header::CONTENT_TYPE, "application/json")], json).into_response())
                 }
                 Err(e) => {
                     tracing::error!("Failed to query response contexts: {}", e);
-                    Err(warp::reject::not_found())
+                    Err(StatusCode::NOT_FOUND)
                 }
             }
         }
         None => {
             tracing::warn!("Context tracing is not enabled");
-            Err(warp::reject::not_found())
+            Err(StatusCode::NOT_FOUND)
         }
     }
 }
 
 async fn get_evolution_history(
-    context_unit_id: String,
-    state: Arc<AppState>,
-) -> Result<impl Reply, Rejection> {
+    State(state): State<Arc<AppState>>,
+    Path(context_unit_id): Path<String>,
+) -> Result<Response, StatusCode> {
     use crate::context::evolution::EvolutionService;
 
     match &*state.context_tracker.read().await {
@@ -775,36 +1709,118 @@ async fn get_evolution_history(
             {
                 Ok(history) => {
                     let json =
-                        serde_json::to_string(&history).map_err(|_| warp::reject::not_found())?;
-                    Ok(warp::reply::with_header(
-                        json,
-                        "Content-Type",
-                        "application/json",
-                    ))
+                        serde_json::to_string(&history).map_err(|_| StatusCode::NOT_FOUND)?;
+                    Ok(([(header::CONTENT_TYPE, "application/json")], json).into_response())
                 }
                 Err(e) => {
                     tracing::error!("Failed to get evolution history: {}", e);
-                    Err(warp::reject::not_found())
+                    Err(StatusCode::NOT_FOUND)
                 }
             }
         }
         None => {
             tracing::warn!("Context tracing is not enabled");
-            Err(warp::reject::not_found())
+            Err(StatusCode::NOT_FOUND)
+        }
+    }
+}
+
+/// How a context unit changed since an earlier version, `from` (default:
+/// the one before)
+async fn get_version_diff(
+    State(state): State<Arc<AppState>>,
+    Path(context_unit_id): Path<String>,
+    Query(query_params): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
+    use crate::context::evolution::EvolutionService;
+
+    let from_version = match query_params.get("from") {
+        Some(from) => Some(from.parse().map_err(|_| StatusCode::BAD_REQUEST)?),
+        None => None,
+    };
+    let tracker = state
+        .context_tracker
+        .read()
+        .await
+        .clone()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    match EvolutionService::new(tracker.storage())
+        .diff_versions(&context_unit_id, from_version)
+        .await
+    {
+        Ok(diff) => Ok(Json(diff).into_response()),
+        Err(e) => {
+            tracing::warn!("Failed to diff versions: {}", e);
+            Err(StatusCode::NOT_FOUND)
+        }
+    }
+}
+
+/// Context units whose embeddings are closest to `text`
+async fn similar_contexts(
+    State(state): State<Arc<AppState>>,
+    Query(query_params): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
+    let text = query_params
+        .get("text")
+        .filter(|text| !text.trim().is_empty())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let limit = similar_limit(&query_params);
+    let tracker = state
+        .context_tracker
+        .read()
+        .await
+        .clone()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    similar_response(tracker.similar_to_text(text, limit).await)
+}
+
+/// Context units whose embeddings are closest to the given unit's
+async fn similar_to_context(
+    State(state): State<Arc<AppState>>,
+    Path(context_unit_id): Path<String>,
+    Query(query_params): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
+    let limit = similar_limit(&query_params);
+    let tracker = state
+        .context_tracker
+        .read()
+        .await
+        .clone()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    similar_response(tracker.similar_to_context(&context_unit_id, limit).await)
+}
+
+fn similar_limit(query_params: &HashMap<String, String>) -> usize {
+    query_params
+        .get("limit")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(10)
+        .clamp(1, 100)
+}
+
+fn similar_response(
+    result: Result<Vec<crate::context::embedding::SimilarContext>, String>,
+) -> Result<Response, StatusCode> {
+    match result {
+        Ok(results) => Ok(Json(serde_json::json!({ "results": results })).into_response()),
+        Err(e) => {
+            tracing::warn!("Similarity search failed: {}", e);
+            Err(StatusCode::NOT_FOUND)
         }
     }
 }
 
 async fn submit_feedback(
-    submission: crate::context::types::FeedbackSubmission,
-    state: Arc<AppState>,
-) -> Result<impl Reply, Rejection> {
+    State(state): State<Arc<AppState>>,
+    Json(submission): Json<crate::context::types::FeedbackSubmission>,
+) -> Result<Response, StatusCode> {
     match &*state.context_tracker.read().await {
         Some(tracker) => {
             // Validate score range
             if submission.score < -1.0 || submission.score > 1.0 {
                 tracing::warn!("Invalid feedback score: {}", submission.score);
-                return Err(warp::reject::not_found()); // TODO: Return 400 Bad Request
+                return Err(StatusCode::BAD_REQUEST);
             }
 
             // Record feedback and propagate to contexts
@@ -826,23 +1842,460 @@ async fn submit_feedback(
                         "propagation": propagation_status
                     });
 
-                    let json = serde_json::to_string(&response_body)
-                        .map_err(|_| warp::reject::not_found())?;
+                    let json =
+                        serde_json::to_string(&response_body).map_err(|_| StatusCode::NOT_FOUND)?;
 
-                    Ok(warp::reply::with_status(
-                        warp::reply::with_header(json, "Content-Type", "application/json"),
-                        warp::http::StatusCode::CREATED,
-                    ))
+                    Ok((
+                        StatusCode::CREATED,
+                        [(header::CONTENT_TYPE, "application/json")],
+                        json,
+                    )
+                        .into_response())
                 }
                 Err(e) => {
                     tracing::error!("Failed to record feedback: {}", e);
-                    Err(warp::reject::not_found())
+                    Err(StatusCode::NOT_FOUND)
                 }
             }
         }
         None => {
             tracing::warn!("Context tracing is not enabled");
-            Err(warp::reject::not_found())
+            Err(StatusCode::NOT_FOUND)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_config, test_config_with};
+
+    #[tokio::test]
+    async fn test_playground_call() {
+        let config = test_config(serde_json::json!({}));
+        let (state, _) = AppState::new(config);
+
+        let response = playground_call(
+            State(state.clone()),
+            Json(PlaygroundCall {
+                name: "mcp__proxy__status__overview".to_string(),
+                arguments: Default::default(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(body["result"]["content"].is_array(), "{body}");
+        assert!(body["error"].is_null());
+
+        let response = playground_call(
+            State(state),
+            Json(PlaygroundCall {
+                name: "mcp__proxy__missing__tool".to_string(),
+                arguments: Default::default(),
+            }),
+        )
+        .await
+        .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(body["error"]["message"].is_string(), "{body}");
+    }
+
+    #[tokio::test]
+    async fn test_lineage_includes_scores_and_feedback() {
+        use crate::context::storage::{HybridStorage, StorageBackend};
+        use crate::context::types::{ContextType, ContextUnit};
+
+        let config = test_config(serde_json::json!({}));
+        let (state, _) = AppState::new(config);
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageBackend> = Arc::new(
+            HybridStorage::new(dir.path().join("context.db"), None)
+                .await
+                .unwrap(),
+        );
+        state.initialize_context_tracker(storage).await.unwrap();
+
+        let tracker = state.context_tracker.read().await.clone().unwrap();
+        let response_id = tracker
+            .start_response("agent".to_string(), "model".to_string())
+            .await
+            .unwrap();
+        let unit = ContextUnit {
+            id: "ctx_readme".to_string(),
+            r#type: ContextType::External,
+            source: "README.md".to_string(),
+            timestamp: chrono::Utc::now(),
+            embedding_id: None,
+            summary: Some("Project overview".to_string()),
+            version: 1,
+            previous_version_id: None,
+            aggregate_score: 0.0,
+            feedback_count: 0,
+        };
+        tracker
+            .add_context(response_id.clone(), unit, Some(0.8))
+            .await
+            .unwrap();
+        tracker
+            .finalize_response(response_id.clone(), None)
+            .await
+            .unwrap();
+        tracker
+            .record_feedback(&response_id, 1.0, Some("Accurate".to_string()), None)
+            .await
+            .unwrap();
+
+        let response = get_lineage(State(state.clone()), Path(response_id.clone()))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["manifest"]["response_id"], response_id);
+        assert_eq!(body["units"]["ctx_readme"]["version"], 1);
+        assert!(
+            body["units"]["ctx_readme"]["aggregate_score"]
+                .as_f64()
+                .unwrap()
+                > 0.0
+        );
+        assert_eq!(body["feedback"][0]["feedback_text"], "Accurate");
+
+        assert_eq!(
+            get_lineage(State(state.clone()), Path("resp_missing".to_string()))
+                .await
+                .unwrap_err(),
+            StatusCode::NOT_FOUND
+        );
+
+        let out_of_range = crate::context::types::FeedbackSubmission {
+            response_id,
+            score: 1.5,
+            feedback_text: None,
+            user_id: None,
+        };
+        assert_eq!(
+            submit_feedback(State(state), Json(out_of_range))
+                .await
+                .unwrap_err(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn test_similar_contexts() {
+        use crate::context::storage::{HybridStorage, StorageBackend};
+        use crate::context::types::{ContextType, ContextUnit};
+
+        let config = test_config_with(
+            serde_json::json!({}),
+            serde_json::json!({ "contextTracing": { "embeddings": { "provider": "hash" } } }),
+        );
+        let (state, _) = AppState::new(config);
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn StorageBackend> = Arc::new(
+            HybridStorage::new(dir.path().join("context.db"), None)
+                .await
+                .unwrap(),
+        );
+        state
+            .initialize_context_tracker(storage.clone())
+            .await
+            .unwrap();
+
+        let tracker = state.context_tracker.read().await.clone().unwrap();
+        let response_id = tracker
+            .start_response("agent".to_string(), "model".to_string())
+            .await
+            .unwrap();
+        for (id, summary) in [
+            ("ctx_tokio", "Tokio async runtime for Rust"),
+            ("ctx_axum", "Axum web framework built on the Tokio runtime"),
+            ("ctx_bread", "Baking sourdough bread at home"),
+        ] {
+            let unit = ContextUnit {
+                id: id.to_string(),
+                r#type: ContextType::External,
+                source: "docs".to_string(),
+                timestamp: chrono::Utc::now(),
+                embedding_id: None,
+                summary: Some(summary.to_string()),
+                version: 1,
+                previous_version_id: None,
+                aggregate_score: 0.0,
+                feedback_count: 0,
+            };
+            tracker
+                .add_context(response_id.clone(), unit, None)
+                .await
+                .unwrap();
+        }
+        // Units are embedded in the background
+        for _ in 0..100 {
+            if storage
+                .get_embedding("ctx_bread", "hash-256")
+                .await
+                .unwrap()
+                .is_some()
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
         }
+
+        let response = similar_to_context(
+            State(state.clone()),
+            Path("ctx_tokio".to_string()),
+            Query(HashMap::from([("limit".to_string(), "1".to_string())])),
+        )
+        .await
+        .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let results = body["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["context_unit"]["id"], "ctx_axum");
+        assert_eq!(
+            results[0]["context_unit"]["embedding_id"],
+            "hash-256:ctx_axum"
+        );
+
+        let response = similar_contexts(
+            State(state.clone()),
+            Query(HashMap::from([(
+                "text".to_string(),
+                "sourdough".to_string(),
+            )])),
+        )
+        .await
+        .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["results"][0]["context_unit"]["id"], "ctx_bread");
+        assert_eq!(body["results"].as_array().unwrap().len(), 3);
+
+        assert_eq!(
+            similar_contexts(State(state), Query(HashMap::new()))
+                .await
+                .unwrap_err(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ephemeral_logs_served_from_memory() {
+        let config = test_config_with(
+            serde_json::json!({}),
+            serde_json::json!({ "ephemeral": true }),
+        );
+        let (state, _) = AppState::new(config);
+        let info = crate::state::ServerInfo::new("echo".to_string());
+        info.log_buffer.push(crate::state::LogEntry {
+            timestamp: chrono::Utc::now(),
+            level: "error".to_string(),
+            message: "listening failed".to_string(),
+        });
+        state.register_server("echo".to_string(), info).await;
+
+        let response = get_server_logs(
+            State(state.clone()),
+            Path("echo".to_string()),
+            Query(HashMap::new()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["source"], "memory");
+        let line = body["lines"][0].as_str().unwrap();
+        assert!(line.ends_with("[ERROR] listening failed"), "{line}");
+
+        let response = get_server_logs(
+            State(state),
+            Path("missing".to_string()),
+            Query(HashMap::new()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_client_usage() {
+        let config = test_config_with(
+            serde_json::json!({}),
+            serde_json::json!({ "clients": { "ci": { "apiKey": "secret", "quota": { "dailyCalls": 100 } } } }),
+        );
+        let (state, _) = AppState::new(config);
+        let now = chrono::Utc::now();
+        state.usage.reserve(None, "ci", None, now).await.unwrap();
+        state
+            .usage
+            .record_tokens(None, "ci", 42, now)
+            .await
+            .unwrap();
+
+        let response = client_usage(State(state.clone()), Path("ci".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body["daily"],
+            serde_json::json!({ "calls": 1, "tokens": 42 })
+        );
+        assert_eq!(body["monthly"]["calls"], 1);
+        assert_eq!(body["quota"]["dailyCalls"], 100);
+
+        let response = client_usage(State(state), Path("bob".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_get_conflicts() {
+        let config = test_config_with(
+            serde_json::json!({
+                "github": { "command": "github", "args": [], "transport": { "type": "stdio" } },
+                "gitlab": { "command": "gitlab", "args": [], "transport": { "type": "stdio" } }
+            }),
+            serde_json::json!({ "routing": { "conflicts": "priority", "priority": ["gitlab"] } }),
+        );
+        let (state, _) = AppState::new(config);
+        let router = &state.request_router;
+        router.register_server_tools("github", ["search".to_string(), "issues".to_string()]);
+        router.register_server_tools("gitlab", ["search".to_string()]);
+
+        let Json(body) = get_conflicts(State(state.clone())).await;
+        assert_eq!(body["resolution"], "priority");
+        assert_eq!(
+            body["conflicts"],
+            serde_json::json!([{
+                "tool": "search",
+                "servers": ["github", "gitlab"],
+                "resolvedTo": "gitlab"
+            }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_capture_and_retry() {
+        let config = test_config_with(
+            serde_json::json!({}),
+            serde_json::json!({ "deadLetter": { "enabled": true } }),
+        );
+        let (state, _) = AppState::new(config);
+
+        let params = serde_json::json!({ "name": "search", "arguments": { "q": "bug" } });
+        let (reply, correlation_id) = playground_request(&state, "tools/call", params)
+            .await
+            .unwrap();
+        assert_eq!(reply["error"]["data"]["category"], "not_found");
+
+        let Json(body) = list_dead_letters(State(state.clone()), Query(HashMap::new())).await;
+        let entry = &body["entries"][0];
+        assert_eq!(entry["tool"], "search");
+        assert_eq!(entry["arguments"]["q"], "bug");
+        assert_eq!(entry["correlationId"], correlation_id.as_str());
+        let id = entry["id"].as_u64().unwrap();
+
+        // A retry that fails again replaces the entry with a new one
+        let response = retry_dead_letter(State(state.clone()), Path(id)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let entries = state.dead_letters.as_ref().unwrap().list(None, 10);
+        assert_eq!(entries.len(), 1);
+        assert_ne!(entries[0].id, id);
+
+        let response = retry_dead_letter(State(state.clone()), Path(id)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_plugins_list_and_toggle() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("redact.js"), "").unwrap();
+        std::fs::write(dir.path().join("unused.js"), "").unwrap();
+        let config = test_config_with(
+            serde_json::json!({}),
+            serde_json::json!({
+                "plugins": {
+                    "pluginDir": dir.path(),
+                    "servers": {
+                        "github": {
+                            "request": [{ "name": "redact", "order": 1 }],
+                            "response": [{ "name": "redact", "order": 2 }, { "name": "missing", "order": 1 }]
+                        }
+                    }
+                }
+            }),
+        );
+        let (state, _) = AppState::new(config);
+        state
+            .plugin_manager
+            .as_ref()
+            .unwrap()
+            .discover_plugins()
+            .await
+            .unwrap();
+
+        let response = plugin_action(
+            State(state.clone()),
+            Path(("redact".to_string(), "disable".to_string())),
+            Query(HashMap::from([
+                ("server".to_string(), "github".to_string()),
+                ("phase".to_string(), "response".to_string()),
+            ])),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = list_plugins(State(state.clone())).await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let names: Vec<&str> = body["plugins"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["missing", "redact", "unused"]);
+        assert_eq!(body["plugins"][0]["loaded"], false);
+        let redact = &body["plugins"][1]["bindings"];
+        assert_eq!(redact[0]["phase"], "request");
+        assert_eq!(redact[0]["enabled"], true);
+        assert_eq!(redact[1]["phase"], "response");
+        assert_eq!(redact[1]["enabled"], false);
+        assert!(redact[0]["stats"].is_null());
+        assert_eq!(body["plugins"][2]["bindings"], serde_json::json!([]));
+
+        let response = plugin_action(
+            State(state),
+            Path(("redact".to_string(), "disable".to_string())),
+            Query(HashMap::from([("server".to_string(), "jira".to_string())])),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 }
@@ -1,7 +1,16 @@
+use crate::config::CorsConfig;
 use crate::error::Result;
+use crate::proxy::middleware;
 use crate::state::AppState;
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderName, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
 use std::sync::Arc;
-use warp::Filter;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::services::ServeDir;
 
 pub mod api;
 pub mod ws;
@@ -16,12 +25,24 @@ pub async fn start_server(state: Arc<AppState>) -> Result<()> {
     let config = state.config.read().await;
     let addr = format!("{}:{}", config.web_ui.host, config.web_ui.port);
     let api_key = config.web_ui.api_key.clone();
+    let cors = config.web_ui.cors.clone();
+    let reuse_port = config.proxy.reuse_port;
+    let compression = config.proxy.compression;
+    let allowlist = config
+        .web_ui_allowlist()
+        .map_err(|e| crate::error::ProxyError::Config(crate::error::ConfigError::Validation(e)))?;
     drop(config);
 
     tracing::info!("Starting web UI server on {}", addr);
 
+    let api_key_set = api_key.is_some();
+
     // Create routes
-    let routes = create_routes(state.clone(), api_key);
+    let app = middleware::apply(
+        create_routes(state.clone(), api_key, &cors),
+        allowlist.clone(),
+        compression,
+    );
 
     // Parse address
     let addr: std::net::SocketAddr = addr.parse().map_err(|e| {
@@ -30,30 +51,30 @@ pub async fn start_server(state: Arc<AppState>) -> Result<()> {
         )))
     })?;
 
-    // Start server with graceful shutdown
-    let (_, server) = warp::serve(routes).bind_with_graceful_shutdown(addr, async move {
-        let _ = state.shutdown_tx.subscribe().recv().await;
-    });
+    let listener = crate::proxy::listener::bind("webui", 1, addr, reuse_port)?;
+    middleware::warn_if_exposed("web UI", addr, api_key_set, &allowlist);
 
-    server.await;
+    // Stop accepting connections once the proxy starts draining; open
+    // WebSocket sessions close when the servers shut down
+    let drain = state.drain.clone();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move { drain.started().await })
+    .await?;
 
     tracing::info!("Web UI server stopped");
     Ok(())
 }
 
-fn create_routes(
-    state: Arc<AppState>,
-    api_key: Option<String>,
-) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
-    // API routes
-    let api_routes = api::routes(state.clone());
-
-    // WebSocket route
-    let ws_route = ws::route(state.clone());
+fn create_routes(state: Arc<AppState>, api_key: Option<String>, cors: &CorsConfig) -> Router {
+    // API and WebSocket routes
+    let api_routes = api::routes().route("/ws", get(ws::handler));
 
     // Health check
-    let health = warp::path("health").and(warp::get()).map(|| {
-        warp::reply::json(&serde_json::json!({
+    let health = get(|| async {
+        Json(serde_json::json!({
             "status": "healthy",
             "service": "mcp-proxy-web-ui"
         }))
@@ -71,70 +92,79 @@ fn create_routes(
 
     // Static files - always use directory approach
     let static_dir = if use_yew_ui { "yew-dist" } else { "web-ui" };
-    let static_files = warp::fs::dir(static_dir);
 
     // Combine all routes
-    let routes = api_routes.or(ws_route).or(health).or(static_files);
-
-    // Apply CORS to all routes
-    let routes_with_cors = routes.with(
-        warp::cors()
-            .allow_any_origin()
-            .allow_methods(vec!["GET", "POST", "PUT", "DELETE"])
-            .allow_headers(vec!["Content-Type", "Authorization"]),
-    );
+    let mut routes = Router::new()
+        .nest("/api", api_routes)
+        .route("/health", health)
+        .fallback_service(ServeDir::new(static_dir))
+        .with_state(state);
 
     // Add API key authentication if configured
     if let Some(key) = api_key {
-        routes_with_cors.and(api_key_auth(key)).boxed()
-    } else {
-        routes_with_cors.boxed()
+        routes = routes.layer(axum::middleware::from_fn_with_state(
+            Arc::<str>::from(key),
+            api_key_auth,
+        ));
     }
-}
 
-fn api_key_auth(api_key: String) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
-    warp::header::optional::<String>("authorization")
-        .and_then(move |auth_header: Option<String>| {
-            let api_key = api_key.clone();
-            async move {
-                if let Some(header) = auth_header {
-                    if header == format!("Bearer {api_key}") {
-                        Ok(())
-                    } else {
-                        Err(warp::reject::custom(AuthError::invalid_api_key()))
-                    }
-                } else {
-                    Err(warp::reject::custom(AuthError::missing_api_key()))
-                }
-            }
-        })
-        .untuple_one()
-}
+    // Apply CORS to all routes. Without allowed origins no CORS headers are
+    // sent, so browsers keep other sites from using the API.
+    if !cors.allowed_origins.is_empty() {
+        routes = routes.layer(cors_layer(cors));
+    }
 
-#[derive(Debug)]
-struct AuthError {
-    #[allow(dead_code)]
-    kind: AuthErrorKind,
+    routes
 }
 
-#[derive(Debug)]
-enum AuthErrorKind {
-    MissingApiKey,
-    InvalidApiKey,
+fn cors_layer(config: &CorsConfig) -> CorsLayer {
+    // Validated when the configuration is loaded
+    let methods: Vec<Method> = config
+        .allowed_methods
+        .iter()
+        .filter_map(|m| Method::from_bytes(m.as_bytes()).ok())
+        .collect();
+    let headers: Vec<HeaderName> = config
+        .allowed_headers
+        .iter()
+        .filter_map(|h| HeaderName::from_bytes(h.as_bytes()).ok())
+        .collect();
+
+    let origins = if config.allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config
+                .allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(methods)
+        .allow_headers(headers)
 }
 
-impl AuthError {
-    fn missing_api_key() -> Self {
-        Self {
-            kind: AuthErrorKind::MissingApiKey,
-        }
-    }
+async fn api_key_auth(State(api_key): State<Arc<str>>, request: Request, next: Next) -> Response {
+    let key = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
 
-    fn invalid_api_key() -> Self {
-        Self {
-            kind: AuthErrorKind::InvalidApiKey,
+    let error = match key {
+        Some(key) if crate::proxy::auth::constant_time_eq(key.as_bytes(), api_key.as_bytes()) => {
+            return next.run(request).await
         }
-    }
+        Some(_) => "Invalid API key",
+        None => "Missing API key",
+    };
+
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(serde_json::json!({ "error": error })),
+    )
+        .into_response()
 }
-
-impl warp::reject::Reject for AuthError {}
@@ -1,11 +1,17 @@
 use crate::state::AppState;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
 use dashmap::DashMap;
 use futures::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::UnboundedReceiverStream;
-use warp::{Filter, Rejection};
+
+/// Subscription key of a client watching proxied requests
+const TRAFFIC_SUBSCRIPTION: &str = "traffic";
 
 #[derive(Debug, Deserialize)]
 struct WsMessage {
@@ -23,16 +29,11 @@ struct WsResponse {
     data: serde_json::Value,
 }
 
-pub fn route(
-    state: Arc<AppState>,
-) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
-    warp::path!("api" / "ws")
-        .and(warp::ws())
-        .and(warp::any().map(move || state.clone()))
-        .map(|ws: warp::ws::Ws, state| ws.on_upgrade(move |socket| client_connected(socket, state)))
+pub async fn handler(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> Response {
+    ws.on_upgrade(move |socket| client_connected(socket, state))
 }
 
-async fn client_connected(ws: warp::ws::WebSocket, state: Arc<AppState>) {
+async fn client_connected(ws: WebSocket, state: Arc<AppState>) {
     let (mut ws_tx, mut ws_rx) = ws.split();
     let (tx, rx) = mpsc::unbounded_channel();
     let mut rx = UnboundedReceiverStream::new(rx);
@@ -49,7 +50,7 @@ async fn client_connected(ws: warp::ws::WebSocket, state: Arc<AppState>) {
 
     // Send initial state
     let initial_data = collect_state_update(&state).await;
-    let msg = warp::ws::Message::text(
+    let msg = Message::Text(
         serde_json::json!({
             "type": "initial",
             "data": initial_data
@@ -70,6 +71,7 @@ async fn client_connected(ws: warp::ws::WebSocket, state: Arc<AppState>) {
 
     // Subscribe to state changes
     let mut shutdown_rx = state.shutdown_tx.subscribe();
+    let mut alert_rx = state.alert_tx.subscribe();
 
     // Start update loop - reduce frequency to avoid constant UI refreshes
     let update_interval = tokio::time::interval(tokio::time::Duration::from_secs(5));
@@ -83,7 +85,7 @@ async fn client_connected(ws: warp::ws::WebSocket, state: Arc<AppState>) {
 
                 // Only send if state has changed
                 if current_state != previous_state {
-                    let msg = warp::ws::Message::text(serde_json::json!({
+                    let msg = Message::Text(serde_json::json!({
                         "type": "update",
                         "data": current_state
                     }).to_string());
@@ -95,22 +97,29 @@ async fn client_connected(ws: warp::ws::WebSocket, state: Arc<AppState>) {
                     previous_state = current_state;
                 }
             }
+            Ok(alert) = alert_rx.recv() => {
+                let msg = Message::Text(serde_json::json!({
+                    "type": "alert",
+                    "data": alert
+                }).to_string());
+
+                if tx.send(msg).is_err() {
+                    break;
+                }
+            }
             _ = shutdown_rx.recv() => {
                 tracing::debug!("WebSocket connection closing due to shutdown");
                 break;
             }
             msg = ws_rx.next() => {
                 match msg {
-                    Some(Ok(msg)) => {
-                        if msg.is_close() {
-                            break;
-                        }
-                        if let Ok(text) = msg.to_str() {
-                            if let Ok(ws_msg) = serde_json::from_str::<WsMessage>(text) {
-                                handle_ws_message(ws_msg, &state, &tx, &subscriptions).await;
-                            }
+                    Some(Ok(Message::Close(_))) => break,
+                    Some(Ok(Message::Text(text))) => {
+                        if let Ok(ws_msg) = serde_json::from_str::<WsMessage>(&text) {
+                            handle_ws_message(ws_msg, &state, &tx, &subscriptions).await;
                         }
                     }
+                    Some(Ok(_)) => {}
                     Some(Err(e)) => {
                         tracing::error!("WebSocket error: {}", e);
                         break;
@@ -159,6 +168,11 @@ async fn collect_state_update(state: &Arc<AppState>) -> serde_json::Value {
         // Check if health checks are enabled for this server
         let config = state.config.read().await;
         let health_check_enabled = config.get_server_health_check(&name).is_some();
+        let tags = config
+            .servers
+            .get(&name)
+            .map(|s| s.tags.clone())
+            .unwrap_or_default();
         drop(config);
 
         servers.push(serde_json::json!({
@@ -167,7 +181,10 @@ async fn collect_state_update(state: &Arc<AppState>) -> serde_json::Value {
             "restart_count": restart_count,
             "health_check_enabled": health_check_enabled,
             "last_health_check": health_check_data,
-            "last_access_time": last_access
+            "last_access_time": last_access,
+            "tags": tags,
+            "disabled": state.disabled_servers.get(&name).await,
+            "maintenance": state.maintenance.get(&name)
         }));
     }
 
@@ -198,7 +215,7 @@ async fn collect_state_update(state: &Arc<AppState>) -> serde_json::Value {
 async fn handle_ws_message(
     msg: WsMessage,
     state: &Arc<AppState>,
-    tx: &mpsc::UnboundedSender<warp::ws::Message>,
+    tx: &mpsc::UnboundedSender<Message>,
     subscriptions: &Arc<DashMap<String, bool>>,
 ) {
     match msg.msg_type.as_str() {
@@ -233,10 +250,7 @@ async fn handle_ws_message(
                                 }
                             });
 
-                            if tx_clone
-                                .send(warp::ws::Message::text(log_msg.to_string()))
-                                .is_err()
-                            {
+                            if tx_clone.send(Message::Text(log_msg.to_string())).is_err() {
                                 break;
                             }
                         }
@@ -248,7 +262,7 @@ async fn handle_ws_message(
                 //     msg_type: "subscribed".to_string(),
                 //     data: serde_json::json!({ "server": server_name, "type": "logs" }),
                 // };
-                // let _ = tx.send(warp::ws::Message::text(serde_json::to_string(&response).unwrap()));
+                // let _ = tx.send(Message::Text(serde_json::to_string(&response).unwrap()));
             }
         }
         "unsubscribe_logs" => {
@@ -266,7 +280,53 @@ async fn handle_ws_message(
                 //     msg_type: "unsubscribed".to_string(),
                 //     data: serde_json::json!({ "server": server_name, "type": "logs" }),
                 // };
-                // let _ = tx.send(warp::ws::Message::text(serde_json::to_string(&response).unwrap()));
+                // let _ = tx.send(Message::Text(serde_json::to_string(&response).unwrap()));
+            }
+        }
+        "subscribe_traffic" => {
+            // The forwarding task outlives unsubscribing, so a client that
+            // subscribes again only turns it back on
+            if subscriptions
+                .insert(TRAFFIC_SUBSCRIPTION.to_string(), true)
+                .is_some()
+            {
+                return;
+            }
+            let mut traffic_rx = state.traffic_tx.subscribe();
+            let tx = tx.clone();
+            let subscriptions = subscriptions.clone();
+
+            tokio::spawn(async move {
+                loop {
+                    let event = match traffic_rx.recv().await {
+                        Ok(event) => event,
+                        // Requests missed while the client was slow are dropped
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    };
+                    if tx.is_closed() {
+                        break;
+                    }
+                    let subscribed = subscriptions
+                        .get(TRAFFIC_SUBSCRIPTION)
+                        .is_some_and(|subscribed| *subscribed);
+                    if !subscribed {
+                        continue;
+                    }
+
+                    let traffic_msg = serde_json::json!({
+                        "type": "traffic",
+                        "data": event,
+                    });
+                    if tx.send(Message::Text(traffic_msg.to_string())).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        "unsubscribe_traffic" => {
+            if let Some(mut subscribed) = subscriptions.get_mut(TRAFFIC_SUBSCRIPTION) {
+                *subscribed = false;
             }
         }
         _ => {
@@ -39,6 +39,7 @@ fn test_plugin_input_phase_detection() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -55,6 +56,7 @@ fn test_plugin_input_phase_detection() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -93,6 +95,7 @@ fn test_plugin_input_serialization_with_phases() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -108,6 +111,7 @@ fn test_plugin_input_serialization_with_phases() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -172,6 +176,7 @@ fn test_metadata_with_different_phases() {
         user_query: Some("test query".to_string()),
         tool_arguments: None,
         mcp_servers: None,
+        correlation_id: None,
     };
 
     let response_metadata = PluginMetadata {
@@ -182,6 +187,7 @@ fn test_metadata_with_different_phases() {
         user_query: None,
         tool_arguments: None,
         mcp_servers: None,
+        correlation_id: None,
     };
 
     // Verify metadata can be serialized
@@ -115,6 +115,7 @@ async fn test_three_plugin_chain_metadata_aggregation() {
             user_query: Some("Get file".to_string()),
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -334,6 +335,7 @@ rl.on('close', () => process.exit(0));
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -436,6 +438,7 @@ async fn test_metadata_preserved_through_chain() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
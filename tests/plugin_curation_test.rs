@@ -51,6 +51,7 @@ async fn test_curation_flow_with_echo_plugin() {
             user_query: Some("Explain React hooks".to_string()),
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -117,6 +118,7 @@ async fn test_plugin_handles_large_documents() {
             user_query: Some("Summarize documentation".to_string()),
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -170,6 +172,7 @@ async fn test_plugin_chain_simulation() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -234,6 +237,7 @@ async fn test_curation_metadata_preservation() {
             user_query: Some("Test query".to_string()),
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
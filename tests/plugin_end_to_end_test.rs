@@ -70,6 +70,7 @@ async fn test_complete_plugin_system_integration() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -109,6 +110,7 @@ async fn test_plugin_system_handles_all_error_types() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
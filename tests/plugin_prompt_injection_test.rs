@@ -36,6 +36,7 @@ async fn test_scanner_detects_ignore_instructions() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -97,6 +98,7 @@ async fn test_scanner_allows_clean_content() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -154,6 +156,7 @@ async fn test_scanner_detects_role_manipulation() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -200,6 +203,7 @@ async fn test_scanner_only_processes_response_phase() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -50,6 +50,7 @@ async fn test_security_blocks_sensitive_password() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -128,6 +129,7 @@ async fn test_security_blocks_api_key() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -175,6 +177,7 @@ async fn test_security_allows_safe_requests() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -242,6 +245,7 @@ async fn test_security_plugin_only_processes_request_phase() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -298,6 +302,7 @@ async fn test_security_blocks_bearer_token() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -345,6 +350,7 @@ async fn test_security_blocks_private_key() {
             user_query: None,
             tool_arguments: None,
             mcp_servers: None,
+            correlation_id: None,
         },
     };
 
@@ -54,6 +54,19 @@ fn test_round_trip_conversion() {
     }
 }
 
+#[test]
+fn test_parse_strict() {
+    assert_eq!(
+        ProtocolVersion::parse("2024-11-05"),
+        Some(ProtocolVersion::V20241105)
+    );
+    assert_eq!(
+        ProtocolVersion::parse("2025-06-18"),
+        Some(ProtocolVersion::V20250618)
+    );
+    assert_eq!(ProtocolVersion::parse("2026-01-01"), None);
+}
+
 #[test]
 fn test_supports_audio_content() {
     assert!(!ProtocolVersion::V20241105.supports_audio_content());
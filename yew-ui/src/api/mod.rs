@@ -1,7 +1,10 @@
 pub mod log_stream;
 pub mod websocket;
 
-use crate::types::{ApiResponse, MetricsResponse, ServersResponse};
+use crate::types::{
+    ApiResponse, EvolutionHistory, LineageResponse, MetricsHistoryResponse, MetricsResponse,
+    PlaygroundCallResponse, PluginsResponse, ServersResponse, Tool, ToolsResponse,
+};
 use gloo_net::http::Request;
 
 const API_BASE: &str = "/api";
@@ -22,6 +25,15 @@ pub async fn fetch_metrics() -> Result<MetricsResponse, gloo_net::Error> {
         .await
 }
 
+pub async fn fetch_metrics_history(hours: u64) -> Result<MetricsHistoryResponse, String> {
+    let response = Request::get(&format!("{}/metrics/history", API_BASE))
+        .query([("hours", hours.to_string())])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    json_or_error(response).await
+}
+
 pub async fn server_action(
     server_name: &str,
     action: &str,
@@ -33,3 +45,138 @@ pub async fn server_action(
         .json()
         .await
 }
+
+pub async fn tag_action(tag: &str, action: &str) -> Result<ApiResponse, gloo_net::Error> {
+    Request::post(&format!("{}/tags/{}/{}", API_BASE, tag, action))
+        .header("Content-Type", "application/json")
+        .send()
+        .await?
+        .json()
+        .await
+}
+
+/// The body of a response, or the error the API reported
+async fn json_or_error<T: serde::de::DeserializeOwned>(
+    response: gloo_net::http::Response,
+) -> Result<T, String> {
+    if response.ok() {
+        return response.json().await.map_err(|e| e.to_string());
+    }
+    let status = response.status();
+    match response.json::<ApiResponse>().await {
+        Ok(ApiResponse {
+            error: Some(error), ..
+        }) => Err(error),
+        _ => Err(format!("HTTP {}", status)),
+    }
+}
+
+pub async fn fetch_playground_tools() -> Result<Vec<Tool>, String> {
+    let response = Request::get(&format!("{}/playground/tools", API_BASE))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    json_or_error::<ToolsResponse>(response)
+        .await
+        .map(|response| response.tools)
+}
+
+pub async fn call_playground_tool(
+    name: &str,
+    arguments: serde_json::Map<String, serde_json::Value>,
+) -> Result<PlaygroundCallResponse, String> {
+    let response = Request::post(&format!("{}/playground/call", API_BASE))
+        .json(&serde_json::json!({ "name": name, "arguments": arguments }))
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    json_or_error(response).await
+}
+
+/// The response's lineage, or `None` when none was recorded or context
+/// tracing is disabled
+pub async fn fetch_lineage(response_id: &str) -> Result<Option<LineageResponse>, String> {
+    let response = Request::get(&format!(
+        "{}/lineage/{}",
+        API_BASE,
+        js_sys::encode_uri_component(response_id)
+    ))
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    if response.status() == 404 {
+        return Ok(None);
+    }
+    json_or_error(response).await.map(Some)
+}
+
+pub async fn fetch_context_evolution(context_id: &str) -> Result<EvolutionHistory, String> {
+    let response = Request::get(&format!(
+        "{}/query/evolution/{}",
+        API_BASE,
+        js_sys::encode_uri_component(context_id)
+    ))
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    json_or_error(response).await
+}
+
+pub async fn submit_feedback(
+    response_id: &str,
+    score: f32,
+    feedback_text: Option<String>,
+    user_id: Option<String>,
+) -> Result<(), String> {
+    let response = Request::post(&format!("{}/feedback", API_BASE))
+        .json(&serde_json::json!({
+            "response_id": response_id,
+            "score": score,
+            "feedback_text": feedback_text,
+            "user_id": user_id
+        }))
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    json_or_error::<serde_json::Value>(response)
+        .await
+        .map(|_| ())
+}
+
+pub async fn fetch_plugins() -> Result<PluginsResponse, String> {
+    let response = Request::get(&format!("{}/plugins", API_BASE))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    json_or_error(response).await
+}
+
+/// Enable or disable a plugin for a server in one phase
+pub async fn plugin_action(
+    plugin: &str,
+    action: &str,
+    server: &str,
+    phase: &str,
+) -> Result<ApiResponse, String> {
+    let response = Request::post(&format!(
+        "{}/plugins/{}/{}",
+        API_BASE,
+        js_sys::encode_uri_component(plugin),
+        action
+    ))
+    .query([("server", server), ("phase", phase)])
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    json_or_error(response).await
+}
+
+pub async fn reload_plugins() -> Result<ApiResponse, String> {
+    let response = Request::post(&format!("{}/plugins/reload", API_BASE))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    json_or_error(response).await
+}
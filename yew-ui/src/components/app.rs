@@ -1,11 +1,27 @@
 use crate::api::{self, log_stream::*, websocket::*};
-use crate::components::{Header, LogsModal, Metrics, Modal, ServersList};
+use crate::components::{
+    Header, Lineage, LogsModal, Metrics, MetricsHistory, Modal, Plugins, ServersList, Tools,
+    Traffic,
+};
 use crate::types::*;
 use gloo_timers::callback::Interval;
+use std::collections::VecDeque;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 
+/// Requests kept on the Traffic page; older ones are dropped
+const TRAFFIC_HISTORY: usize = 500;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Page {
+    Servers,
+    Traffic,
+    Tools,
+    Plugins,
+    Lineage,
+}
+
 pub enum Msg {
     // WebSocket messages
     WsConnect,
@@ -19,12 +35,17 @@ pub enum Msg {
     MetricsLoaded(Vec<Metric>),
 
     // UI actions
-    ShowActionModal(String, String), // server_name, action
+    ShowActionModal(String, String),    // server_name, action
+    ShowTagActionModal(String, String), // tag, action
     HideActionModal,
     ConfirmAction,
     ViewLogs(String),
     CloseLogs,
     ClearLogs,
+    DismissAlert(usize),
+    ShowPage(Page),
+    ToggleTrafficPause,
+    ClearTraffic,
 
     // Log streaming
     LogStreamMessage(String),
@@ -37,6 +58,8 @@ pub struct App {
     servers: Vec<Server>,
     metrics: Vec<Metric>,
     stats: Option<Stats>,
+    alerts: Vec<AlertData>,
+    page: Page,
     ws_service: WebSocketService,
     ws_status: WsStatus,
     _metrics_interval: Option<Interval>,
@@ -45,12 +68,17 @@ pub struct App {
     // Modal state
     show_action_modal: bool,
     pending_action: Option<(String, String)>,
+    pending_tag_action: Option<(String, String)>,
 
     // Logs modal state
     show_logs_modal: bool,
     current_log_server: Option<String>,
     logs: Vec<LogData>,
     log_stream_service: LogStreamService,
+
+    // Traffic page state
+    traffic: VecDeque<TrafficEvent>,
+    traffic_paused: bool,
 }
 
 impl Component for App {
@@ -75,16 +103,21 @@ impl Component for App {
             servers: vec![],
             metrics: vec![],
             stats: None,
+            alerts: vec![],
+            page: Page::Servers,
             ws_service: WebSocketService::new(),
             ws_status: WsStatus::Disconnected,
             _metrics_interval: Some(metrics_interval),
             _reconnect_interval: None,
             show_action_modal: false,
             pending_action: None,
+            pending_tag_action: None,
             show_logs_modal: false,
             current_log_server: None,
             logs: vec![],
             log_stream_service: LogStreamService::new(),
+            traffic: VecDeque::new(),
+            traffic_paused: false,
         }
     }
 
@@ -118,6 +151,10 @@ impl Component for App {
                 WsMessage::Initial { data } => {
                     self.servers = data.servers;
                     self.stats = data.stats;
+                    // A new connection starts without subscriptions
+                    if self.page == Page::Traffic {
+                        self.send_command(WsCommand::SubscribeTraffic);
+                    }
                     // Update connection status when we receive initial data
                     ctx.link().send_message(Msg::WsConnectionStatusChanged);
                     true
@@ -150,6 +187,20 @@ impl Component for App {
 
                     any_change
                 }
+                WsMessage::Alert { data } => {
+                    self.alerts.push(data);
+                    true
+                }
+                WsMessage::Traffic { data } => {
+                    if self.traffic_paused {
+                        return false;
+                    }
+                    if self.traffic.len() == TRAFFIC_HISTORY {
+                        self.traffic.pop_front();
+                    }
+                    self.traffic.push_back(data);
+                    self.page == Page::Traffic
+                }
                 WsMessage::Log { data } => {
                     if Some(&data.server) == self.current_log_server.as_ref() {
                         self.logs.push(data);
@@ -214,9 +265,16 @@ impl Component for App {
                 true
             }
 
+            Msg::ShowTagActionModal(tag, action) => {
+                self.pending_tag_action = Some((tag, action));
+                self.show_action_modal = true;
+                true
+            }
+
             Msg::HideActionModal => {
                 self.show_action_modal = false;
                 self.pending_action = None;
+                self.pending_tag_action = None;
                 true
             }
 
@@ -230,6 +288,15 @@ impl Component for App {
                         link.send_message(Msg::ActionCompleted(result));
                     });
                 }
+                if let Some((tag, action)) = self.pending_tag_action.take() {
+                    let link = ctx.link().clone();
+                    spawn_local(async move {
+                        let result = api::tag_action(&tag, &action)
+                            .await
+                            .map_err(|e| format!("{:?}", e));
+                        link.send_message(Msg::ActionCompleted(result));
+                    });
+                }
                 self.show_action_modal = false;
                 true
             }
@@ -286,6 +353,39 @@ impl Component for App {
                 true
             }
 
+            Msg::DismissAlert(index) => {
+                if index < self.alerts.len() {
+                    self.alerts.remove(index);
+                }
+                true
+            }
+
+            Msg::ShowPage(page) => {
+                if page == self.page {
+                    return false;
+                }
+                // Requests are only streamed while the page is open
+                match page {
+                    Page::Traffic => self.send_command(WsCommand::SubscribeTraffic),
+                    _ if self.page == Page::Traffic => {
+                        self.send_command(WsCommand::UnsubscribeTraffic)
+                    }
+                    _ => {}
+                }
+                self.page = page;
+                true
+            }
+
+            Msg::ToggleTrafficPause => {
+                self.traffic_paused = !self.traffic_paused;
+                true
+            }
+
+            Msg::ClearTraffic => {
+                self.traffic.clear();
+                true
+            }
+
             Msg::LogStreamMessage(message) => {
                 if let Some(ref server_name) = self.current_log_server {
                     if let Some(mut log_data) = parse_log_line(&message) {
@@ -307,6 +407,10 @@ impl Component for App {
             .link()
             .callback(|(server, action): (String, String)| Msg::ShowActionModal(server, action));
 
+        let on_tag_action = ctx
+            .link()
+            .callback(|(tag, action): (String, String)| Msg::ShowTagActionModal(tag, action));
+
         let on_view_logs = ctx.link().callback(Msg::ViewLogs);
 
         html! {
@@ -316,14 +420,54 @@ impl Component for App {
                     ws_status={self.ws_status.clone()}
                 />
 
-                <main>
-                    <ServersList
-                        servers={self.servers.clone()}
-                        on_action={on_action}
-                        on_view_logs={on_view_logs}
-                    />
+                {self.alerts.iter().enumerate().map(|(index, alert)| html! {
+                    <div class="alert-banner">
+                        <span>{&alert.message}</span>
+                        <button class="btn btn-secondary" onclick={ctx.link().callback(move |_| Msg::DismissAlert(index))}>
+                            {"Dismiss"}
+                        </button>
+                    </div>
+                }).collect::<Html>()}
+
+                <nav class="tabs">
+                    {[(Page::Servers, "Servers"), (Page::Traffic, "Traffic"), (Page::Tools, "Tools"), (Page::Plugins, "Plugins"), (Page::Lineage, "Lineage")].into_iter().map(|(page, label)| html! {
+                        <button
+                            class={classes!("tab", (page == self.page).then_some("active"))}
+                            onclick={ctx.link().callback(move |_| Msg::ShowPage(page))}
+                        >
+                            {label}
+                        </button>
+                    }).collect::<Html>()}
+                </nav>
 
-                    <Metrics metrics={self.metrics.clone()} />
+                <main>
+                    {match self.page {
+                        Page::Servers => html! {
+                            <>
+                                <ServersList
+                                    servers={self.servers.clone()}
+                                    on_action={on_action}
+                                    on_tag_action={on_tag_action}
+                                    on_view_logs={on_view_logs}
+                                />
+
+                                <Metrics metrics={self.metrics.clone()} />
+
+                                <MetricsHistory />
+                            </>
+                        },
+                        Page::Traffic => html! {
+                            <Traffic
+                                events={Vec::from(self.traffic.clone())}
+                                paused={self.traffic_paused}
+                                on_toggle_pause={ctx.link().callback(|_| Msg::ToggleTrafficPause)}
+                                on_clear={ctx.link().callback(|_| Msg::ClearTraffic)}
+                            />
+                        },
+                        Page::Tools => html! { <Tools /> },
+                        Page::Plugins => html! { <Plugins /> },
+                        Page::Lineage => html! { <Lineage /> },
+                    }}
                 </main>
 
                 // Action confirmation modal
@@ -334,6 +478,10 @@ impl Component for App {
                             html! {
                                 <p>{format!("Are you sure you want to {} the server \"{}\"?", action, server)}</p>
                             }
+                        } else if let Some((tag, action)) = &self.pending_tag_action {
+                            html! {
+                                <p>{format!("Are you sure you want to {} all servers tagged \"{}\"?", action, tag)}</p>
+                            }
                         } else {
                             html! {}
                         }}
@@ -360,3 +508,11 @@ impl Component for App {
         }
     }
 }
+
+impl App {
+    fn send_command(&self, command: WsCommand) {
+        if let Err(e) = self.ws_service.send(command) {
+            web_sys::console::error_1(&e);
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use crate::api;
+use crate::types::{ContextTreeNode, EvolutionHistory, LineageResponse};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+const GRAPH_WIDTH: f64 = 720.0;
+const ROW_HEIGHT: f64 = 60.0;
+const NODE_HEIGHT: f64 = 44.0;
+const RESPONSE_X: f64 = 10.0;
+const RESPONSE_WIDTH: f64 = 200.0;
+const CONTEXT_X: f64 = 460.0;
+const CONTEXT_WIDTH: f64 = 250.0;
+
+/// Red for poorly rated context, through amber, to green for well rated
+fn score_color(score: f32) -> String {
+    let hue = (score.clamp(-1.0, 1.0) + 1.0) * 60.0;
+    format!("hsl({:.0}, 65%, 45%)", hue)
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max - 1).collect();
+    cut.push('…');
+    cut
+}
+
+/// The response on the left with an edge to each context unit on the right,
+/// thicker for units that contributed more
+fn graph(lineage: &LineageResponse, selected: Option<&str>, on_select: &Callback<String>) -> Html {
+    let manifest = &lineage.manifest;
+    let nodes = &manifest.context_tree;
+    let height = (nodes.len().max(1) as f64) * ROW_HEIGHT;
+    let node_y = |index: usize| index as f64 * ROW_HEIGHT + (ROW_HEIGHT - NODE_HEIGHT) / 2.0;
+    let response_y = (height - NODE_HEIGHT) / 2.0;
+
+    let edges = manifest
+        .provenance_tree
+        .edges
+        .iter()
+        .filter_map(|edge| {
+            let index = nodes.iter().position(|node| node.id == edge.to)?;
+            let (x1, y1) = (RESPONSE_X + RESPONSE_WIDTH, response_y + NODE_HEIGHT / 2.0);
+            let (x2, y2) = (CONTEXT_X, node_y(index) + NODE_HEIGHT / 2.0);
+            let middle = (x1 + x2) / 2.0;
+            Some(html! {
+                <g class="lineage-edge">
+                    <path
+                        d={format!("M {x1} {y1} C {middle} {y1}, {middle} {y2}, {x2} {y2}")}
+                        stroke-width={format!("{:.1}", 1.0 + edge.weight * 6.0)}
+                    />
+                    <text x={format!("{}", x2 - 8.0)} y={format!("{}", y2 - 6.0)} text-anchor="end">
+                        {format!("{:.2}", edge.weight)}
+                    </text>
+                </g>
+            })
+        })
+        .collect::<Html>();
+
+    let context_nodes = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| {
+            let unit = lineage.units.get(&node.id);
+            let y = node_y(index);
+            let onclick = {
+                let on_select = on_select.clone();
+                let id = node.id.clone();
+                Callback::from(move |_| on_select.emit(id.clone()))
+            };
+            let class = classes!(
+                "lineage-node",
+                (selected == Some(node.id.as_str())).then_some("selected")
+            );
+            let detail = match unit {
+                Some(unit) => format!(
+                    "v{} · score {:.2} ({} ratings)",
+                    unit.version, unit.aggregate_score, unit.feedback_count
+                ),
+                None => "no longer stored".to_string(),
+            };
+            html! {
+                <g class={class} {onclick}>
+                    <rect
+                        x={CONTEXT_X.to_string()}
+                        y={y.to_string()}
+                        width={CONTEXT_WIDTH.to_string()}
+                        height={NODE_HEIGHT.to_string()}
+                        rx="6"
+                        style={format!("stroke: {}", score_color(unit.map_or(0.0, |u| u.aggregate_score)))}
+                    />
+                    <text x={(CONTEXT_X + 10.0).to_string()} y={(y + 18.0).to_string()} class="lineage-node-title">
+                        {truncate(&format!("{} · {}", node.context_type, node.source), 34)}
+                    </text>
+                    <text x={(CONTEXT_X + 10.0).to_string()} y={(y + 35.0).to_string()} class="lineage-node-detail">
+                        {detail}
+                    </text>
+                </g>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <svg class="lineage-graph" viewBox={format!("0 0 {} {}", GRAPH_WIDTH, height)}>
+            {edges}
+            <g class="lineage-node response">
+                <rect
+                    x={RESPONSE_X.to_string()}
+                    y={response_y.to_string()}
+                    width={RESPONSE_WIDTH.to_string()}
+                    height={NODE_HEIGHT.to_string()}
+                    rx="6"
+                />
+                <text x={(RESPONSE_X + 10.0).to_string()} y={(response_y + 18.0).to_string()} class="lineage-node-title">
+                    {truncate(&manifest.response_id, 26)}
+                </text>
+                <text x={(RESPONSE_X + 10.0).to_string()} y={(response_y + 35.0).to_string()} class="lineage-node-detail">
+                    {truncate(&format!("{} · {}", manifest.agent, manifest.model), 28)}
+                </text>
+            </g>
+            {context_nodes}
+        </svg>
+    }
+}
+
+fn node_details(
+    node: &ContextTreeNode,
+    lineage: &LineageResponse,
+    evolution: &Option<Result<EvolutionHistory, String>>,
+) -> Html {
+    let unit = lineage.units.get(&node.id);
+    let versions = match evolution {
+        None => html! { <div class="lineage-empty">{"Loading versions..."}</div> },
+        Some(Err(error)) => {
+            html! { <div class="lineage-error">{format!("Failed to load versions: {}", error)}</div> }
+        }
+        Some(Ok(evolution)) => html! {
+            <ul class="lineage-versions">
+                {evolution.history.iter().rev().map(|version| html! {
+                    <li class={classes!((version.id == node.id).then_some("current"))}>
+                        <strong>{format!("v{}", version.version)}</strong>
+                        {format!(" {} ", version.timestamp.format("%Y-%m-%d %H:%M"))}
+                        {version.summary.clone().unwrap_or_default()}
+                    </li>
+                }).collect::<Html>()}
+            </ul>
+        },
+    };
+
+    html! {
+        <div class="lineage-details">
+            <h3>{&node.source}</h3>
+            <dl>
+                <dt>{"ID"}</dt><dd>{&node.id}</dd>
+                <dt>{"Type"}</dt><dd>{&node.context_type}</dd>
+                <dt>{"Weight"}</dt><dd>{format!("{:.2}", node.weight)}</dd>
+                <dt>{"Score"}</dt>
+                <dd>
+                    {unit.map(|u| format!("{:.2} from {} ratings", u.aggregate_score, u.feedback_count))
+                        .unwrap_or_else(|| "-".to_string())}
+                </dd>
+                <dt>{"Version"}</dt><dd>{unit.map(|u| u.version.to_string()).unwrap_or_else(|| "-".to_string())}</dd>
+                {node.summary.as_ref().map(|summary| html! {
+                    <><dt>{"Summary"}</dt><dd>{summary}</dd></>
+                }).unwrap_or_default()}
+            </dl>
+            <h4>{"Versions"}</h4>
+            {versions}
+        </div>
+    }
+}
+
+#[function_component(Lineage)]
+pub fn lineage() -> Html {
+    let response_id = use_state(String::new);
+    let lineage = use_state(|| None::<Result<Option<LineageResponse>, String>>);
+    let selected = use_state(|| None::<String>);
+    let evolution = use_state(|| None::<Result<EvolutionHistory, String>>);
+    let score = use_state(|| 0.0f32);
+    let feedback_text = use_state(String::new);
+    let user_id = use_state(String::new);
+    let feedback_status = use_state(|| None::<Result<String, String>>);
+    let submitting = use_state(|| false);
+
+    let load = {
+        let lineage = lineage.clone();
+        let selected = selected.clone();
+        Callback::from(move |id: String| {
+            let lineage = lineage.clone();
+            selected.set(None);
+            spawn_local(async move {
+                lineage.set(Some(api::fetch_lineage(&id).await));
+            });
+        })
+    };
+
+    let on_id_input = {
+        let response_id = response_id.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            response_id.set(input.value());
+        })
+    };
+
+    let on_submit_id = {
+        let response_id = response_id.clone();
+        let load = load.clone();
+        let feedback_status = feedback_status.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let id = response_id.trim().to_string();
+            if !id.is_empty() {
+                feedback_status.set(None);
+                load.emit(id);
+            }
+        })
+    };
+
+    let on_select = {
+        let selected = selected.clone();
+        let evolution = evolution.clone();
+        Callback::from(move |id: String| {
+            let evolution = evolution.clone();
+            selected.set(Some(id.clone()));
+            evolution.set(None);
+            spawn_local(async move {
+                evolution.set(Some(api::fetch_context_evolution(&id).await));
+            });
+        })
+    };
+
+    let body = match &*lineage {
+        None => html! {
+            <div class="lineage-empty">
+                {"Enter a response ID, as returned by the context tracing tools, to see the context it was built from"}
+            </div>
+        },
+        Some(Err(error)) => {
+            html! { <div class="lineage-error">{format!("Failed to load lineage: {}", error)}</div> }
+        }
+        Some(Ok(None)) => html! {
+            <div class="lineage-empty">
+                {"No lineage was recorded for this response, or context tracing is disabled"}
+            </div>
+        },
+        Some(Ok(Some(lineage_response))) => {
+            let manifest = &lineage_response.manifest;
+            let selected_node = selected
+                .as_ref()
+                .and_then(|id| manifest.context_tree.iter().find(|node| &node.id == id));
+
+            let on_score = {
+                let score = score.clone();
+                Callback::from(move |e: InputEvent| {
+                    let input = e.target_unchecked_into::<HtmlInputElement>();
+                    score.set(input.value().parse().unwrap_or(0.0));
+                })
+            };
+            let on_feedback_text = {
+                let feedback_text = feedback_text.clone();
+                Callback::from(move |e: InputEvent| {
+                    let input = e.target_unchecked_into::<HtmlTextAreaElement>();
+                    feedback_text.set(input.value());
+                })
+            };
+            let on_user_id = {
+                let user_id = user_id.clone();
+                Callback::from(move |e: InputEvent| {
+                    let input = e.target_unchecked_into::<HtmlInputElement>();
+                    user_id.set(input.value());
+                })
+            };
+            let on_submit_feedback = {
+                let id = manifest.response_id.clone();
+                let score = score.clone();
+                let feedback_text = feedback_text.clone();
+                let user_id = user_id.clone();
+                let feedback_status = feedback_status.clone();
+                let submitting = submitting.clone();
+                let load = load.clone();
+                Callback::from(move |e: SubmitEvent| {
+                    e.prevent_default();
+                    let id = id.clone();
+                    let score = *score;
+                    let text = Some(feedback_text.trim().to_string()).filter(|t| !t.is_empty());
+                    let user = Some(user_id.trim().to_string()).filter(|u| !u.is_empty());
+                    let feedback_status = feedback_status.clone();
+                    let submitting = submitting.clone();
+                    let feedback_text = feedback_text.clone();
+                    let load = load.clone();
+                    submitting.set(true);
+                    spawn_local(async move {
+                        match api::submit_feedback(&id, score, text, user).await {
+                            Ok(()) => {
+                                feedback_status
+                                    .set(Some(Ok("Feedback recorded; scores updated".to_string())));
+                                feedback_text.set(String::new());
+                                // Show the context units' new scores
+                                load.emit(id);
+                            }
+                            Err(error) => feedback_status.set(Some(Err(error))),
+                        }
+                        submitting.set(false);
+                    });
+                })
+            };
+
+            html! {
+                <>
+                    <div class="lineage-summary">
+                        <span>{format!("Agent: {}", manifest.agent)}</span>
+                        <span>{format!("Model: {}", manifest.model)}</span>
+                        <span>{manifest.timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string()}</span>
+                        {manifest.token_count.map(|tokens| html! {
+                            <span>{format!("{} tokens", tokens)}</span>
+                        }).unwrap_or_default()}
+                    </div>
+                    <div class="lineage-layout">
+                        {graph(lineage_response, selected.as_deref(), &on_select)}
+                        {match selected_node {
+                            Some(node) => node_details(node, lineage_response, &evolution),
+                            None => html! {
+                                <div class="lineage-details lineage-empty">
+                                    {"Select a context unit to see its score and versions"}
+                                </div>
+                            },
+                        }}
+                    </div>
+                    <div class="lineage-feedback">
+                        <h3>{"Feedback"}</h3>
+                        {if lineage_response.feedback.is_empty() {
+                            html! { <div class="lineage-empty">{"No feedback yet"}</div> }
+                        } else {
+                            html! {
+                                <ul class="lineage-feedback-list">
+                                    {lineage_response.feedback.iter().map(|record| html! {
+                                        <li key={record.id.clone()}>
+                                            <strong style={format!("color: {}", score_color(record.score))}>
+                                                {format!("{:+.1}", record.score)}
+                                            </strong>
+                                            {format!(
+                                                " {} {}",
+                                                record.timestamp.format("%Y-%m-%d %H:%M"),
+                                                record.user_id.clone().unwrap_or_default()
+                                            )}
+                                            {record.feedback_text.as_ref().map(|text| html! {
+                                                <div>{text}</div>
+                                            }).unwrap_or_default()}
+                                        </li>
+                                    }).collect::<Html>()}
+                                </ul>
+                            }
+                        }}
+                        <form class="lineage-feedback-form" onsubmit={on_submit_feedback}>
+                            <label class="tool-field">
+                                <span class="tool-field-name">{format!("Score: {:+.1}", *score)}</span>
+                                <input
+                                    type="range"
+                                    min="-1"
+                                    max="1"
+                                    step="0.1"
+                                    value={score.to_string()}
+                                    oninput={on_score}
+                                />
+                            </label>
+                            <label class="tool-field">
+                                <span class="tool-field-name">{"Comment"}</span>
+                                <textarea rows="3" value={(*feedback_text).clone()} oninput={on_feedback_text} />
+                            </label>
+                            <label class="tool-field">
+                                <span class="tool-field-name">{"User"}</span>
+                                <input type="text" value={(*user_id).clone()} oninput={on_user_id} />
+                            </label>
+                            <button type="submit" class="btn btn-primary" disabled={*submitting}>
+                                {if *submitting { "Sending..." } else { "Send feedback" }}
+                            </button>
+                            {match &*feedback_status {
+                                None => html! {},
+                                Some(Ok(message)) => html! { <span class="lineage-feedback-ok">{message}</span> },
+                                Some(Err(error)) => html! { <span class="lineage-error">{error}</span> },
+                            }}
+                        </form>
+                    </div>
+                </>
+            }
+        }
+    };
+
+    html! {
+        <section class="lineage-section">
+            <h2>{"Context Lineage"}</h2>
+            <form class="lineage-controls" onsubmit={on_submit_id}>
+                <input
+                    type="search"
+                    placeholder="Response ID, e.g. resp_..."
+                    value={(*response_id).clone()}
+                    oninput={on_id_input}
+                />
+                <button type="submit" class="btn btn-primary">{"Load"}</button>
+            </form>
+            {body}
+        </section>
+    }
+}
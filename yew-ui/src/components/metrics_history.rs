@@ -0,0 +1,208 @@
+use crate::api;
+use crate::types::{MetricsHistoryResponse, MetricsSample};
+use chrono::{DateTime, Duration, Utc};
+use gloo_timers::callback::Interval;
+use std::collections::{BTreeMap, BTreeSet};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+
+const CHART_WIDTH: f64 = 600.0;
+const CHART_HEIGHT: f64 = 120.0;
+const RANGES: [(u64, &str); 3] = [(1, "Last hour"), (6, "Last 6 hours"), (24, "Last 24 hours")];
+
+/// One interval's traffic, for one server or summed over all of them
+#[derive(Default)]
+struct Point {
+    requests: u64,
+    errors: u64,
+    /// Latency summed over requests, for averaging across servers
+    weighted_latency: f64,
+    latency_weight: u64,
+}
+
+fn points(samples: &[MetricsSample], server: &str) -> BTreeMap<DateTime<Utc>, Point> {
+    let mut points = BTreeMap::<DateTime<Utc>, Point>::new();
+    for sample in samples
+        .iter()
+        .filter(|sample| server.is_empty() || sample.server == server)
+    {
+        let point = points.entry(sample.timestamp).or_default();
+        point.requests += sample.requests;
+        point.errors += sample.errors;
+        if let Some(latency) = sample.avg_latency_ms {
+            let weight = sample.requests.max(1);
+            point.weighted_latency += latency * weight as f64;
+            point.latency_weight += weight;
+        }
+    }
+    points
+}
+
+/// A line over the time range, scaled to the largest value
+fn chart(
+    title: &str,
+    unit: &str,
+    values: &[(DateTime<Utc>, f64)],
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Html {
+    let max = values.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+    let span = (now - since).num_seconds().max(1) as f64;
+    let line = values
+        .iter()
+        .map(|(at, value)| {
+            let x = (*at - since).num_seconds() as f64 / span * CHART_WIDTH;
+            let y = if max > 0.0 {
+                CHART_HEIGHT - value / max * CHART_HEIGHT
+            } else {
+                CHART_HEIGHT
+            };
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let latest = values
+        .last()
+        .map(|(_, v)| format!("{:.1} {}", v, unit))
+        .unwrap_or_else(|| "-".to_string());
+
+    html! {
+        <div class="history-chart">
+            <div class="history-chart-header">
+                <span class="history-chart-title">{title}</span>
+                <span class="history-chart-latest">{latest}</span>
+            </div>
+            <svg
+                viewBox={format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT)}
+                preserveAspectRatio="none"
+            >
+                <polyline points={line} />
+            </svg>
+            <div class="history-chart-scale">
+                <span>{since.with_timezone(&chrono::Local).format("%H:%M").to_string()}</span>
+                <span>{format!("max {:.1} {}", max, unit)}</span>
+                <span>{now.with_timezone(&chrono::Local).format("%H:%M").to_string()}</span>
+            </div>
+        </div>
+    }
+}
+
+#[function_component(MetricsHistory)]
+pub fn metrics_history() -> Html {
+    let hours = use_state(|| 1u64);
+    let server = use_state(String::new);
+    let history = use_state(|| None::<Result<MetricsHistoryResponse, String>>);
+
+    // Samples arrive once per interval, so refetching every minute is enough
+    {
+        let history = history.clone();
+        use_effect_with(*hours, move |hours| {
+            let hours = *hours;
+            let load = move || {
+                let history = history.clone();
+                spawn_local(async move {
+                    history.set(Some(api::fetch_metrics_history(hours).await));
+                });
+            };
+            load();
+            let interval = Interval::new(60_000, load);
+            move || drop(interval)
+        });
+    }
+
+    let on_hours_change = {
+        let hours = hours.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target_unchecked_into::<HtmlSelectElement>();
+            hours.set(select.value().parse().unwrap_or(1));
+        })
+    };
+
+    let on_server_change = {
+        let server = server.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target_unchecked_into::<HtmlSelectElement>();
+            server.set(select.value());
+        })
+    };
+
+    let body = match &*history {
+        None => html! { <div class="history-empty">{"Loading history..."}</div> },
+        Some(Err(error)) => {
+            html! { <div class="history-empty">{format!("Failed to load history: {}", error)}</div> }
+        }
+        Some(Ok(response)) if !response.enabled => {
+            html! { <div class="history-empty">{"Metrics history is disabled in the configuration"}</div> }
+        }
+        Some(Ok(response)) if response.samples.is_empty() => {
+            html! { <div class="history-empty">{"No samples yet; one is taken every interval"}</div> }
+        }
+        Some(Ok(response)) => {
+            let now = Utc::now();
+            let since = now - Duration::hours(*hours as i64);
+            let minutes = (response.interval_seconds.max(1) as f64) / 60.0;
+            let points = points(&response.samples, &server);
+
+            let request_rate: Vec<_> = points
+                .iter()
+                .map(|(at, p)| (*at, p.requests as f64 / minutes))
+                .collect();
+            let error_rate: Vec<_> = points
+                .iter()
+                .map(|(at, p)| {
+                    let rate = if p.requests > 0 {
+                        p.errors as f64 / p.requests as f64 * 100.0
+                    } else {
+                        0.0
+                    };
+                    (*at, rate)
+                })
+                .collect();
+            let latency: Vec<_> = points
+                .iter()
+                .filter(|(_, p)| p.latency_weight > 0)
+                .map(|(at, p)| (*at, p.weighted_latency / p.latency_weight as f64))
+                .collect();
+
+            html! {
+                <div class="history-charts">
+                    {chart("Request rate", "req/min", &request_rate, since, now)}
+                    {chart("Error rate", "%", &error_rate, since, now)}
+                    {chart("Average latency", "ms", &latency, since, now)}
+                </div>
+            }
+        }
+    };
+
+    let servers: BTreeSet<&str> = match &*history {
+        Some(Ok(response)) => response
+            .samples
+            .iter()
+            .map(|sample| sample.server.as_str())
+            .collect(),
+        _ => BTreeSet::new(),
+    };
+
+    html! {
+        <section class="history-section">
+            <h2>{"Metrics History"}</h2>
+            <div class="history-controls">
+                <select onchange={on_server_change}>
+                    <option value="" selected={server.is_empty()}>{"All servers"}</option>
+                    {servers.iter().map(|name| html! {
+                        <option value={name.to_string()} selected={*name == server.as_str()}>
+                            {name}
+                        </option>
+                    }).collect::<Html>()}
+                </select>
+                <select onchange={on_hours_change}>
+                    {RANGES.iter().map(|(range, label)| html! {
+                        <option value={range.to_string()} selected={*range == *hours}>{label}</option>
+                    }).collect::<Html>()}
+                </select>
+            </div>
+            {body}
+        </section>
+    }
+}
@@ -1,15 +1,25 @@
 pub mod app;
 pub mod header;
+pub mod lineage;
 pub mod logs_modal;
 pub mod metrics;
+pub mod metrics_history;
 pub mod modal;
+pub mod plugins;
 pub mod server_card;
 pub mod servers_list;
+pub mod tools;
+pub mod traffic;
 
 pub use app::App;
 pub use header::Header;
+pub use lineage::Lineage;
 pub use logs_modal::LogsModal;
 pub use metrics::Metrics;
+pub use metrics_history::MetricsHistory;
 pub use modal::Modal;
+pub use plugins::Plugins;
 pub use server_card::ServerCard;
 pub use servers_list::ServersList;
+pub use tools::Tools;
+pub use traffic::Traffic;
@@ -0,0 +1,188 @@
+use crate::api;
+use crate::types::{PluginBinding, PluginsResponse};
+use gloo_timers::callback::Interval;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+
+fn stats_cells(binding: &PluginBinding) -> Html {
+    let Some(stats) = &binding.stats else {
+        return html! { <td colspan="5" class="plugins-muted">{"Not run yet"}</td> };
+    };
+    let failure_class = if stats.failures > 0 {
+        "plugins-failures"
+    } else {
+        ""
+    };
+    html! {
+        <>
+            <td>{stats.executions}</td>
+            <td class={failure_class}>
+                {format!("{} ({} timeouts)", stats.failures, stats.timeouts)}
+            </td>
+            <td>{format!("{:.1} ms", stats.avg_duration_ms)}</td>
+            <td>
+                {stats.last_execution
+                    .map(|at| at.with_timezone(&chrono::Local).format("%H:%M:%S").to_string())
+                    .unwrap_or_default()}
+            </td>
+            <td class="plugins-error" title={stats.last_error.clone().unwrap_or_default()}>
+                {stats.last_error.clone().unwrap_or_default()}
+            </td>
+        </>
+    }
+}
+
+#[function_component(Plugins)]
+pub fn plugins() -> Html {
+    let plugins = use_state(|| None::<Result<PluginsResponse, String>>);
+    let message = use_state(|| None::<Result<String, String>>);
+
+    let load = {
+        let plugins = plugins.clone();
+        Callback::from(move |_: ()| {
+            let plugins = plugins.clone();
+            spawn_local(async move {
+                plugins.set(Some(api::fetch_plugins().await));
+            });
+        })
+    };
+
+    // Execution stats change with traffic, so keep them fresh
+    {
+        let load = load.clone();
+        use_effect_with((), move |_| {
+            load.emit(());
+            let interval = Interval::new(10_000, move || load.emit(()));
+            move || drop(interval)
+        });
+    }
+
+    let report = {
+        let message = message.clone();
+        let load = load.clone();
+        Callback::from(move |result: Result<crate::types::ApiResponse, String>| {
+            message.set(Some(
+                result.map(|response| response.message.unwrap_or_default()),
+            ));
+            load.emit(());
+        })
+    };
+
+    let on_reload = {
+        let report = report.clone();
+        Callback::from(move |_| {
+            let report = report.clone();
+            spawn_local(async move {
+                report.emit(api::reload_plugins().await);
+            });
+        })
+    };
+
+    let body = match &*plugins {
+        None => html! { <div class="plugins-muted">{"Loading plugins..."}</div> },
+        Some(Err(error)) => {
+            html! { <div class="plugins-error">{format!("Failed to load plugins: {}", error)}</div> }
+        }
+        Some(Ok(response)) if !response.configured => html! {
+            <div class="plugins-muted">{"Plugins are not configured; add a plugins section to the configuration"}</div>
+        },
+        Some(Ok(response)) => html! {
+            <>
+                {response.plugin_dir.as_ref().map(|dir| html! {
+                    <div class="plugins-muted">{format!("Plugin directory: {}", dir)}</div>
+                }).unwrap_or_default()}
+                {response.plugins.iter().map(|plugin| {
+                    let bindings = if plugin.bindings.is_empty() {
+                        html! { <div class="plugins-muted">{"Not assigned to any server"}</div> }
+                    } else {
+                        html! {
+                            <table class="plugins-table">
+                                <thead>
+                                    <tr>
+                                        <th>{"Server"}</th>
+                                        <th>{"Phase"}</th>
+                                        <th>{"Order"}</th>
+                                        <th>{"Timeout"}</th>
+                                        <th>{"Runs"}</th>
+                                        <th>{"Failures"}</th>
+                                        <th>{"Avg"}</th>
+                                        <th>{"Last run"}</th>
+                                        <th>{"Last error"}</th>
+                                        <th></th>
+                                    </tr>
+                                </thead>
+                                <tbody>
+                                    {plugin.bindings.iter().map(|binding| {
+                                        let action = if binding.enabled { "disable" } else { "enable" };
+                                        let on_toggle = {
+                                            let report = report.clone();
+                                            let plugin = plugin.name.clone();
+                                            let server = binding.server.clone();
+                                            let phase = binding.phase.clone();
+                                            Callback::from(move |_| {
+                                                let report = report.clone();
+                                                let plugin = plugin.clone();
+                                                let server = server.clone();
+                                                let phase = phase.clone();
+                                                spawn_local(async move {
+                                                    report.emit(
+                                                        api::plugin_action(&plugin, action, &server, &phase).await,
+                                                    );
+                                                });
+                                            })
+                                        };
+                                        html! {
+                                            <tr class={classes!((!binding.enabled).then_some("plugins-disabled"))}>
+                                                <td>{&binding.server}</td>
+                                                <td>{&binding.phase}</td>
+                                                <td>{binding.order}</td>
+                                                <td>{format!("{} ms", binding.timeout_ms)}</td>
+                                                {stats_cells(binding)}
+                                                <td>
+                                                    <button
+                                                        class={classes!("btn", if binding.enabled { "btn-stop" } else { "btn-start" })}
+                                                        onclick={on_toggle}
+                                                    >
+                                                        {if binding.enabled { "Disable" } else { "Enable" }}
+                                                    </button>
+                                                </td>
+                                            </tr>
+                                        }
+                                    }).collect::<Html>()}
+                                </tbody>
+                            </table>
+                        }
+                    };
+                    html! {
+                        <div key={plugin.name.clone()} class="plugin-card">
+                            <h3>
+                                {&plugin.name}
+                                {if plugin.loaded {
+                                    html! {}
+                                } else {
+                                    html! { <span class="plugins-missing">{"not found in plugin directory"}</span> }
+                                }}
+                            </h3>
+                            {bindings}
+                        </div>
+                    }
+                }).collect::<Html>()}
+            </>
+        },
+    };
+
+    html! {
+        <section class="plugins-section">
+            <h2>{"Plugins"}</h2>
+            <div class="plugins-controls">
+                <button class="btn btn-secondary" onclick={on_reload}>{"Reload plugins"}</button>
+                {match &*message {
+                    None => html! {},
+                    Some(Ok(text)) => html! { <span class="plugins-muted">{text}</span> },
+                    Some(Err(error)) => html! { <span class="plugins-error">{error}</span> },
+                }}
+            </div>
+            {body}
+        </section>
+    }
+}
@@ -21,7 +21,7 @@ pub fn server_card(props: &ServerCardProps) -> Html {
     };
 
     let is_running = matches!(server.state, ServerState::Running);
-    let is_stopped = matches!(server.state, ServerState::Stopped);
+    let is_stopped = matches!(server.state, ServerState::Stopped) && server.disabled.is_none();
 
     // Format health check status
     let health_status = if server.health_check_enabled {
@@ -100,6 +100,46 @@ pub fn server_card(props: &ServerCardProps) -> Html {
         Callback::from(move |_| callback.emit((name.clone(), "restart".to_string())))
     };
 
+    let quarantine = match &server.disabled {
+        Some(disabled) => {
+            let name = server.name.clone();
+            let callback = props.on_action.clone();
+            let on_reenable = Callback::from(move |_| {
+                callback.emit((name.clone(), "toggle-disable".to_string()))
+            });
+            let label = if disabled.quarantined {
+                "Quarantined"
+            } else {
+                "Disabled"
+            };
+
+            html! {
+                <div class="server-quarantine">
+                    <span>{format!("{}: {}", label, disabled.reason)}</span>
+                    <button class="btn btn-start" onclick={on_reenable}>{"Re-enable"}</button>
+                </div>
+            }
+        }
+        None => html! {},
+    };
+
+    let on_maintenance = {
+        let name = server.name.clone();
+        let callback = props.on_action.clone();
+        let action = if server.maintenance.is_some() {
+            "resume"
+        } else {
+            "maintenance"
+        };
+        Callback::from(move |_| callback.emit((name.clone(), action.to_string())))
+    };
+
+    let on_disable = {
+        let name = server.name.clone();
+        let callback = props.on_action.clone();
+        Callback::from(move |_| callback.emit((name.clone(), "toggle-disable".to_string())))
+    };
+
     let on_logs = {
         let name = server.name.clone();
         let callback = props.on_view_logs.clone();
@@ -114,6 +154,19 @@ pub fn server_card(props: &ServerCardProps) -> Html {
                     {format!("{:?}", server.state).to_uppercase()}
                 </span>
             </div>
+            if !server.tags.is_empty() {
+                <div class="server-tags">
+                    {server.tags.iter().map(|tag| html! {
+                        <span class="server-tag">{tag}</span>
+                    }).collect::<Html>()}
+                </div>
+            }
+            {quarantine}
+            if let Some(maintenance) = &server.maintenance {
+                <div class="server-maintenance">
+                    {format!("Maintenance ({}): {}", format_time_ago(&maintenance.since), maintenance.reason)}
+                </div>
+            }
             <div class="server-info">
                 <div>{format!("Restarts: {}", server.restart_count)}</div>
                 {health_status}
@@ -129,6 +182,14 @@ pub fn server_card(props: &ServerCardProps) -> Html {
                 <button class="btn btn-restart" disabled={!is_running} onclick={on_restart}>
                     {"Restart"}
                 </button>
+                <button class="btn btn-maintenance" disabled={!is_running} onclick={on_maintenance}>
+                    {if server.maintenance.is_some() { "Resume" } else { "Maintenance" }}
+                </button>
+                if server.disabled.is_none() {
+                    <button class="btn btn-stop" onclick={on_disable}>
+                        {"Disable"}
+                    </button>
+                }
                 <button class="btn btn-logs" onclick={on_logs}>
                     {"Logs"}
                 </button>
@@ -1,21 +1,76 @@
 use crate::components::ServerCard;
 use crate::types::Server;
+use std::collections::BTreeSet;
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
 pub struct ServersListProps {
     pub servers: Vec<Server>,
     pub on_action: Callback<(String, String)>,
+    pub on_tag_action: Callback<(String, String)>, // (tag, action)
     pub on_view_logs: Callback<String>,
 }
 
 #[function_component(ServersList)]
 pub fn servers_list(props: &ServersListProps) -> Html {
+    let selected_tag = use_state(|| None::<String>);
+
+    let tags: BTreeSet<&String> = props
+        .servers
+        .iter()
+        .flat_map(|server| server.tags.iter())
+        .collect();
+
+    let tag_filter = if tags.is_empty() {
+        html! {}
+    } else {
+        let tag_button = |tag: Option<String>| {
+            let label = tag.clone().unwrap_or_else(|| "All".to_string());
+            let class = if *selected_tag == tag {
+                "btn btn-tag active"
+            } else {
+                "btn btn-tag"
+            };
+            let selected_tag = selected_tag.clone();
+            let onclick = Callback::from(move |_| selected_tag.set(tag.clone()));
+            html! { <button {class} {onclick}>{label}</button> }
+        };
+
+        let group_actions = match &*selected_tag {
+            Some(tag) => ["start", "stop", "restart"]
+                .iter()
+                .map(|action| {
+                    let tag = tag.clone();
+                    let callback = props.on_tag_action.clone();
+                    let onclick =
+                        Callback::from(move |_| callback.emit((tag.clone(), action.to_string())));
+                    html! {
+                        <button class={format!("btn btn-{}", action)} {onclick}>
+                            {format!("{} all", capitalize(action))}
+                        </button>
+                    }
+                })
+                .collect::<Html>(),
+            None => html! {},
+        };
+
+        html! {
+            <div class="tag-filter">
+                {tag_button(None)}
+                {tags.iter().map(|tag| tag_button(Some((*tag).clone()))).collect::<Html>()}
+                <span class="tag-actions">{group_actions}</span>
+            </div>
+        }
+    };
+
     html! {
         <section class="servers-section">
             <h2>{"Servers"}</h2>
+            {tag_filter}
             <div class="servers-list">
-                {props.servers.iter().map(|server| {
+                {props.servers.iter().filter(|server| {
+                    selected_tag.as_ref().is_none_or(|tag| server.tags.contains(tag))
+                }).map(|server| {
                     html! {
                         <ServerCard
                             key={server.name.clone()}
@@ -29,3 +84,11 @@ pub fn servers_list(props: &ServersListProps) -> Html {
         </section>
     }
 }
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,361 @@
+use crate::api;
+use crate::types::{PlaygroundCallResponse, Tool};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+/// One argument field generated from a tool's input schema
+struct Field {
+    name: String,
+    kind: FieldKind,
+    required: bool,
+    description: Option<String>,
+}
+
+#[derive(Clone, PartialEq)]
+enum FieldKind {
+    Text,
+    Number {
+        integer: bool,
+    },
+    Boolean,
+    Choice(Vec<String>),
+    /// Objects, arrays and anything else, entered as JSON
+    Json,
+}
+
+fn fields(schema: &Value) -> Vec<Field> {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+
+    properties
+        .iter()
+        .map(|(name, property)| {
+            let choices = property
+                .get("enum")
+                .and_then(Value::as_array)
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                });
+            let kind = match (property.get("type").and_then(Value::as_str), choices) {
+                (Some("string"), Some(choices)) if !choices.is_empty() => {
+                    FieldKind::Choice(choices)
+                }
+                (Some("string"), _) => FieldKind::Text,
+                (Some("integer"), _) => FieldKind::Number { integer: true },
+                (Some("number"), _) => FieldKind::Number { integer: false },
+                (Some("boolean"), _) => FieldKind::Boolean,
+                _ => FieldKind::Json,
+            };
+            Field {
+                name: name.clone(),
+                kind,
+                required: required.contains(&name.as_str()),
+                description: property
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            }
+        })
+        .collect()
+}
+
+/// Turn the form's values into call arguments. Empty optional fields are
+/// left out.
+fn arguments(
+    fields: &[Field],
+    values: &HashMap<String, String>,
+) -> Result<Map<String, Value>, String> {
+    let mut arguments = Map::new();
+    for field in fields {
+        let value = values.get(&field.name).map(String::as_str).unwrap_or("");
+        if field.kind == FieldKind::Boolean {
+            if !value.is_empty() || field.required {
+                arguments.insert(field.name.clone(), Value::Bool(value == "true"));
+            }
+            continue;
+        }
+        if value.trim().is_empty() {
+            if field.required {
+                return Err(format!("{} is required", field.name));
+            }
+            continue;
+        }
+        let value = match &field.kind {
+            FieldKind::Text | FieldKind::Choice(_) => Value::String(value.to_string()),
+            FieldKind::Number { integer: true } => value
+                .trim()
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|_| format!("{} must be a whole number", field.name))?,
+            FieldKind::Number { integer: false } => value
+                .trim()
+                .parse::<f64>()
+                .map(Value::from)
+                .map_err(|_| format!("{} must be a number", field.name))?,
+            FieldKind::Json | FieldKind::Boolean => serde_json::from_str(value)
+                .map_err(|e| format!("{} is not valid JSON: {}", field.name, e))?,
+        };
+        arguments.insert(field.name.clone(), value);
+    }
+    Ok(arguments)
+}
+
+#[function_component(Tools)]
+pub fn tools() -> Html {
+    let tools = use_state(|| None::<Result<Vec<Tool>, String>>);
+    let search = use_state(String::new);
+    let selected = use_state(|| None::<String>);
+    let values = use_state(HashMap::<String, String>::new);
+    let invoking = use_state(|| false);
+    let outcome = use_state(|| None::<Result<PlaygroundCallResponse, String>>);
+
+    let load = {
+        let tools = tools.clone();
+        Callback::from(move |_: ()| {
+            let tools = tools.clone();
+            spawn_local(async move {
+                tools.set(Some(api::fetch_playground_tools().await));
+            });
+        })
+    };
+
+    {
+        let load = load.clone();
+        use_effect_with((), move |_| load.emit(()));
+    }
+
+    let on_search = {
+        let search = search.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            search.set(input.value());
+        })
+    };
+
+    let on_reload = {
+        let load = load.clone();
+        Callback::from(move |_| load.emit(()))
+    };
+
+    let tool_list = match &*tools {
+        None => html! { <div class="tools-empty">{"Loading tools..."}</div> },
+        Some(Err(error)) => {
+            html! { <div class="tools-error">{format!("Failed to load tools: {}", error)}</div> }
+        }
+        Some(Ok(list)) => {
+            let search_text = search.to_lowercase();
+            list.iter()
+                .filter(|tool| {
+                    search_text.is_empty()
+                        || tool.name.to_lowercase().contains(&search_text)
+                        || tool
+                            .description
+                            .as_ref()
+                            .is_some_and(|d| d.to_lowercase().contains(&search_text))
+                })
+                .map(|tool| {
+                    let name = tool.name.clone();
+                    let on_select = {
+                        let selected = selected.clone();
+                        let values = values.clone();
+                        let outcome = outcome.clone();
+                        Callback::from(move |_| {
+                            selected.set(Some(name.clone()));
+                            values.set(HashMap::new());
+                            outcome.set(None);
+                        })
+                    };
+                    let class = classes!(
+                        "tool-item",
+                        (selected.as_deref() == Some(tool.name.as_str())).then_some("active")
+                    );
+                    html! {
+                        <div class={class} onclick={on_select}>
+                            <div class="tool-name">{&tool.name}</div>
+                        </div>
+                    }
+                })
+                .collect::<Html>()
+        }
+    };
+
+    let current = match (&*tools, selected.as_ref()) {
+        (Some(Ok(list)), Some(name)) => list.iter().find(|tool| &tool.name == name).cloned(),
+        _ => None,
+    };
+
+    let detail = match current {
+        None => {
+            html! { <div class="tools-empty">{"Select a tool to see its schema and call it"}</div> }
+        }
+        Some(tool) => {
+            let tool_fields = fields(&tool.input_schema);
+
+            let inputs = tool_fields
+                .iter()
+                .map(|field| {
+                    let value = values.get(&field.name).cloned().unwrap_or_default();
+                    let name = field.name.clone();
+                    let set_value = {
+                        let values = values.clone();
+                        move |value: String| {
+                            let mut updated = (*values).clone();
+                            updated.insert(name.clone(), value);
+                            values.set(updated);
+                        }
+                    };
+                    let input = match &field.kind {
+                        FieldKind::Boolean => {
+                            let onchange = Callback::from(move |e: Event| {
+                                let input = e.target_unchecked_into::<HtmlInputElement>();
+                                set_value(input.checked().to_string());
+                            });
+                            html! { <input type="checkbox" checked={value == "true"} {onchange} /> }
+                        }
+                        FieldKind::Choice(choices) => {
+                            let onchange = Callback::from(move |e: Event| {
+                                let select = e.target_unchecked_into::<HtmlSelectElement>();
+                                set_value(select.value());
+                            });
+                            html! {
+                                <select {onchange}>
+                                    <option value="" selected={value.is_empty()}>{"-"}</option>
+                                    {choices.iter().map(|choice| html! {
+                                        <option value={choice.clone()} selected={*choice == value}>{choice}</option>
+                                    }).collect::<Html>()}
+                                </select>
+                            }
+                        }
+                        FieldKind::Json => {
+                            let oninput = Callback::from(move |e: InputEvent| {
+                                let input = e.target_unchecked_into::<HtmlTextAreaElement>();
+                                set_value(input.value());
+                            });
+                            html! { <textarea rows="4" placeholder="JSON" value={value} {oninput} /> }
+                        }
+                        FieldKind::Text | FieldKind::Number { .. } => {
+                            let input_type = if matches!(field.kind, FieldKind::Number { .. }) {
+                                "number"
+                            } else {
+                                "text"
+                            };
+                            let oninput = Callback::from(move |e: InputEvent| {
+                                let input = e.target_unchecked_into::<HtmlInputElement>();
+                                set_value(input.value());
+                            });
+                            html! { <input type={input_type} value={value} {oninput} /> }
+                        }
+                    };
+                    html! {
+                        <label class="tool-field">
+                            <span class="tool-field-name">
+                                {&field.name}
+                                {if field.required { html! { <span class="tool-field-required">{" *"}</span> } } else { html! {} }}
+                            </span>
+                            {input}
+                            {field.description.as_ref().map(|d| html! { <span class="tool-field-description">{d}</span> }).unwrap_or_default()}
+                        </label>
+                    }
+                })
+                .collect::<Html>();
+
+            let on_invoke = {
+                let name = tool.name.clone();
+                let values = values.clone();
+                let invoking = invoking.clone();
+                let outcome = outcome.clone();
+                Callback::from(move |_| {
+                    let arguments = match arguments(&tool_fields, &values) {
+                        Ok(arguments) => arguments,
+                        Err(error) => {
+                            outcome.set(Some(Err(error)));
+                            return;
+                        }
+                    };
+                    let name = name.clone();
+                    let invoking = invoking.clone();
+                    let outcome = outcome.clone();
+                    invoking.set(true);
+                    spawn_local(async move {
+                        outcome.set(Some(api::call_playground_tool(&name, arguments).await));
+                        invoking.set(false);
+                    });
+                })
+            };
+
+            let result = match &*outcome {
+                None => html! {},
+                Some(Err(error)) => html! { <div class="tools-error">{error}</div> },
+                Some(Ok(response)) => {
+                    let (title, class, body) = match &response.error {
+                        Some(error) if !error.is_null() => ("Error", "tool-result error", error),
+                        _ => (
+                            "Result",
+                            "tool-result",
+                            response.result.as_ref().unwrap_or(&Value::Null),
+                        ),
+                    };
+                    html! {
+                        <div class={class}>
+                            <h4>{format!("{} ({} ms)", title, response.duration_ms)}</h4>
+                            <pre>{serde_json::to_string_pretty(body).unwrap_or_default()}</pre>
+                            <div class="traffic-correlation">
+                                {format!("Correlation ID: {}", response.correlation_id)}
+                            </div>
+                        </div>
+                    }
+                }
+            };
+
+            html! {
+                <div class="tool-detail">
+                    <h3>{&tool.name}</h3>
+                    {tool.description.as_ref().map(|d| html! { <p class="tool-description">{d}</p> }).unwrap_or_default()}
+                    <details class="tool-schema">
+                        <summary>{"Input schema"}</summary>
+                        <pre>{serde_json::to_string_pretty(&tool.input_schema).unwrap_or_default()}</pre>
+                    </details>
+                    <div class="tool-form">{inputs}</div>
+                    <button class="btn btn-primary" disabled={*invoking} onclick={on_invoke}>
+                        {if *invoking { "Calling..." } else { "Call tool" }}
+                    </button>
+                    {result}
+                </div>
+            }
+        }
+    };
+
+    html! {
+        <section class="tools-section">
+            <h2>{"Tools"}</h2>
+            <div class="tools-layout">
+                <div class="tools-list">
+                    <div class="tools-controls">
+                        <input
+                            type="search"
+                            placeholder="Filter tools"
+                            value={(*search).clone()}
+                            oninput={on_search}
+                        />
+                        <button class="btn btn-secondary" onclick={on_reload}>{"Reload"}</button>
+                    </div>
+                    {tool_list}
+                </div>
+                {detail}
+            </div>
+        </section>
+    }
+}
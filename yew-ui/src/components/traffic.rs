@@ -0,0 +1,205 @@
+use crate::types::TrafficEvent;
+use std::collections::BTreeSet;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct TrafficProps {
+    pub events: Vec<TrafficEvent>,
+    pub paused: bool,
+    pub on_toggle_pause: Callback<()>,
+    pub on_clear: Callback<()>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum StatusFilter {
+    All,
+    Ok,
+    Error,
+}
+
+#[function_component(Traffic)]
+pub fn traffic(props: &TrafficProps) -> Html {
+    let search = use_state(String::new);
+    let server_filter = use_state(String::new);
+    let status_filter = use_state(|| StatusFilter::All);
+    let expanded = use_state(|| None::<String>);
+
+    let on_search = {
+        let search = search.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            search.set(input.value());
+        })
+    };
+
+    let on_server_change = {
+        let server_filter = server_filter.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target_unchecked_into::<HtmlSelectElement>();
+            server_filter.set(select.value());
+        })
+    };
+
+    let on_status_change = {
+        let status_filter = status_filter.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target_unchecked_into::<HtmlSelectElement>();
+            status_filter.set(match select.value().as_str() {
+                "ok" => StatusFilter::Ok,
+                "error" => StatusFilter::Error,
+                _ => StatusFilter::All,
+            });
+        })
+    };
+
+    let servers: BTreeSet<&str> = props
+        .events
+        .iter()
+        .filter_map(|event| event.server.as_deref())
+        .collect();
+
+    let search_text = search.to_lowercase();
+    let visible: Vec<&TrafficEvent> = props
+        .events
+        .iter()
+        .rev()
+        .filter(|event| {
+            server_filter.is_empty() || event.server.as_deref() == Some(server_filter.as_str())
+        })
+        .filter(|event| match *status_filter {
+            StatusFilter::All => true,
+            StatusFilter::Ok => event.ok,
+            StatusFilter::Error => !event.ok,
+        })
+        .filter(|event| {
+            search_text.is_empty()
+                || event.method.to_lowercase().contains(&search_text)
+                || event
+                    .tool
+                    .as_ref()
+                    .is_some_and(|tool| tool.to_lowercase().contains(&search_text))
+                || event.correlation_id.contains(&search_text)
+        })
+        .collect();
+
+    let on_toggle_pause = {
+        let callback = props.on_toggle_pause.clone();
+        Callback::from(move |_| callback.emit(()))
+    };
+
+    let on_clear = {
+        let callback = props.on_clear.clone();
+        Callback::from(move |_| callback.emit(()))
+    };
+
+    html! {
+        <section class="traffic-section">
+            <h2>{"Traffic"}</h2>
+            <div class="traffic-controls">
+                <input
+                    type="search"
+                    placeholder="Filter by method, tool or correlation ID"
+                    value={(*search).clone()}
+                    oninput={on_search}
+                />
+                <select onchange={on_server_change}>
+                    <option value="" selected={server_filter.is_empty()}>{"All servers"}</option>
+                    {servers.iter().map(|server| html! {
+                        <option value={server.to_string()} selected={*server == server_filter.as_str()}>
+                            {server}
+                        </option>
+                    }).collect::<Html>()}
+                </select>
+                <select onchange={on_status_change}>
+                    <option value="all">{"All statuses"}</option>
+                    <option value="ok">{"OK"}</option>
+                    <option value="error">{"Errors"}</option>
+                </select>
+                <span class="traffic-count">
+                    {format!("{} of {} requests", visible.len(), props.events.len())}
+                </span>
+                <button class="btn btn-secondary" onclick={on_toggle_pause}>
+                    {if props.paused { "Resume" } else { "Pause" }}
+                </button>
+                <button class="btn btn-secondary" onclick={on_clear}>{"Clear"}</button>
+            </div>
+            <table class="traffic-table">
+                <thead>
+                    <tr>
+                        <th>{"Time"}</th>
+                        <th>{"Method"}</th>
+                        <th>{"Tool"}</th>
+                        <th>{"Server"}</th>
+                        <th>{"Client"}</th>
+                        <th>{"Duration"}</th>
+                        <th>{"Status"}</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {visible.into_iter().map(|event| {
+                        let id = event.correlation_id.clone();
+                        let is_expanded = expanded.as_deref() == Some(id.as_str());
+                        let on_row_click = {
+                            let expanded = expanded.clone();
+                            let id = id.clone();
+                            Callback::from(move |_| {
+                                if expanded.as_deref() == Some(id.as_str()) {
+                                    expanded.set(None);
+                                } else {
+                                    expanded.set(Some(id.clone()));
+                                }
+                            })
+                        };
+                        let status_class = if event.ok { "traffic-status ok" } else { "traffic-status error" };
+
+                        html! {
+                            <>
+                                <tr class="traffic-row" onclick={on_row_click}>
+                                    <td>{event.timestamp.format("%H:%M:%S%.3f").to_string()}</td>
+                                    <td>{&event.method}</td>
+                                    <td>{event.tool.clone().unwrap_or_default()}</td>
+                                    <td>{event.server.clone().unwrap_or_default()}</td>
+                                    <td>{event.client.clone().unwrap_or_default()}</td>
+                                    <td>{format!("{} ms", event.duration_ms)}</td>
+                                    <td>
+                                        <span class={status_class}>
+                                            {event.error.clone().unwrap_or_else(|| if event.ok { "OK".to_string() } else { "Error".to_string() })}
+                                        </span>
+                                    </td>
+                                </tr>
+                                {if is_expanded {
+                                    html! {
+                                        <tr class="traffic-details">
+                                            <td colspan="7">
+                                                <div class="traffic-payloads">
+                                                    <div>
+                                                        <h4>{"Request"}</h4>
+                                                        <pre>{pretty(&event.request)}</pre>
+                                                    </div>
+                                                    <div>
+                                                        <h4>{if event.ok { "Result" } else { "Error" }}</h4>
+                                                        <pre>{pretty(&event.response)}</pre>
+                                                    </div>
+                                                </div>
+                                                <div class="traffic-correlation">
+                                                    {format!("Correlation ID: {}", event.correlation_id)}
+                                                </div>
+                                            </td>
+                                        </tr>
+                                    }
+                                } else {
+                                    html! {}
+                                }}
+                            </>
+                        }
+                    }).collect::<Html>()}
+                </tbody>
+            </table>
+        </section>
+    }
+}
+
+fn pretty(value: &serde_json::Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -26,6 +27,28 @@ pub struct Server {
     pub health_check_enabled: bool,
     pub last_health_check: Option<HealthCheck>,
     pub last_access_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub disabled: Option<DisabledInfo>,
+    #[serde(default)]
+    pub maintenance: Option<MaintenanceInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisabledInfo {
+    pub reason: String,
+    #[serde(default)]
+    pub quarantined: bool,
+    pub disabled_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceInfo {
+    pub reason: String,
+    pub since: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -50,6 +73,25 @@ pub struct MetricsResponse {
     pub metrics: Vec<Metric>,
 }
 
+/// Traffic to one server during one sampling interval
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSample {
+    pub timestamp: DateTime<Utc>,
+    pub server: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub avg_latency_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsHistoryResponse {
+    pub enabled: bool,
+    pub interval_seconds: u64,
+    pub samples: Vec<MetricsSample>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ServersResponse {
     pub servers: Vec<Server>,
@@ -62,6 +104,33 @@ pub enum WsMessage {
     Initial { data: WsData },
     Update { data: WsData },
     Log { data: LogData },
+    Alert { data: AlertData },
+    Traffic { data: TrafficEvent },
+}
+
+/// A proxied request as shown on the Traffic page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficEvent {
+    pub correlation_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub client: Option<String>,
+    pub method: String,
+    pub tool: Option<String>,
+    pub server: Option<String>,
+    pub duration_ms: u64,
+    pub ok: bool,
+    pub error: Option<String>,
+    pub request: serde_json::Value,
+    pub response: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertData {
+    pub server: String,
+    pub kind: String,
+    pub message: String,
+    pub timestamp: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -83,6 +152,148 @@ pub struct LogData {
 pub enum WsCommand {
     SubscribeLogs { server: String },
     UnsubscribeLogs { server: String },
+    SubscribeTraffic,
+    UnsubscribeTraffic,
+}
+
+/// A tool from the aggregated tool list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tool {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub input_schema: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolsResponse {
+    pub tools: Vec<Tool>,
+}
+
+/// The outcome of a call from the tool playground
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaygroundCallResponse {
+    pub result: Option<serde_json::Value>,
+    pub error: Option<serde_json::Value>,
+    pub duration_ms: u64,
+    pub correlation_id: String,
+}
+
+/// A context unit in a response's lineage, as the context tracing API
+/// reports it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextTreeNode {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub context_type: String,
+    pub source: String,
+    pub weight: f32,
+    pub summary: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProvenanceEdge {
+    pub from: String,
+    pub to: String,
+    pub weight: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProvenanceTree {
+    pub root: String,
+    pub edges: Vec<ProvenanceEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineageManifest {
+    pub response_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub agent: String,
+    pub model: String,
+    pub token_count: Option<i32>,
+    pub context_tree: Vec<ContextTreeNode>,
+    pub provenance_tree: ProvenanceTree,
+}
+
+/// A context unit's current version and feedback
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextUnitScore {
+    pub version: i32,
+    pub previous_version_id: Option<String>,
+    pub aggregate_score: f32,
+    pub feedback_count: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedbackRecord {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub score: f32,
+    pub feedback_text: Option<String>,
+    pub user_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineageResponse {
+    pub manifest: LineageManifest,
+    pub units: HashMap<String, ContextUnitScore>,
+    pub feedback: Vec<FeedbackRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextVersion {
+    pub id: String,
+    pub version: i32,
+    pub timestamp: DateTime<Utc>,
+    pub summary: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvolutionHistory {
+    pub current_version: ContextVersion,
+    pub history: Vec<ContextVersion>,
+}
+
+/// How a plugin's executions for one server have gone since startup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginExecutionStats {
+    pub executions: u64,
+    pub failures: u64,
+    pub timeouts: u64,
+    pub avg_duration_ms: f64,
+    pub last_execution: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// A plugin's assignment to one server and phase
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginBinding {
+    pub server: String,
+    pub phase: String,
+    pub order: u32,
+    pub enabled: bool,
+    pub timeout_ms: u64,
+    pub stats: Option<PluginExecutionStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub loaded: bool,
+    pub bindings: Vec<PluginBinding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginsResponse {
+    pub configured: bool,
+    pub plugin_dir: Option<String>,
+    pub plugins: Vec<PluginInfo>,
 }
 
 // API responses